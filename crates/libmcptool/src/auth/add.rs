@@ -1,16 +1,21 @@
-use std::{
-    net::TcpListener,
-    time::{Duration, Instant, SystemTime},
-};
+use std::{net::TcpListener, time::Duration};
 
 use rustyline::DefaultEditor;
-use tmcp::auth::{OAuth2CallbackServer, OAuth2Client, OAuth2Config, OAuth2Token};
+use tmcp::auth::OAuth2CallbackServer;
 use tokio::{signal, time::timeout};
 
 use crate::{
-    Error, Result, auth::validate_auth_name, ctx::Ctx, output::Output, storage::StoredAuth,
+    Error, Result,
+    auth::{
+        oauth::{self, AuthorizationFlow, ServerMetadata},
+        validate_auth_name,
+    },
+    ctx::Ctx,
+    output::Output,
+    storage::StoredAuth,
 };
 
+#[derive(Debug, Default)]
 pub struct AddCommandArgs {
     pub name: String,
     pub server_url: Option<String>,
@@ -22,6 +27,9 @@ pub struct AddCommandArgs {
     pub resource: Option<String>,
     pub scopes: Option<String>,
     pub show_redirect_url: bool,
+    /// Where the server's protected resource metadata is, if a
+    /// `WWW-Authenticate` challenge said.
+    pub resource_metadata: Option<String>,
 }
 
 pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
@@ -54,35 +62,6 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
         }
     };
 
-    let auth_url = match args.auth_url {
-        Some(url) => url,
-        None => rl.readline("Authorization URL: ")?,
-    };
-
-    let token_url = match args.token_url {
-        Some(url) => url,
-        None => rl.readline("Token URL: ")?,
-    };
-
-    let client_id = match args.client_id {
-        Some(id) => id,
-        None => rl.readline("Client ID: ")?,
-    };
-
-    // Client secret is optional
-    let client_secret = match args.client_secret {
-        Some(secret) => Some(secret),
-        None => {
-            let client_secret_input =
-                rl.readline("Client Secret (optional, press Enter to skip): ")?;
-            if client_secret_input.trim().is_empty() {
-                None
-            } else {
-                Some(client_secret_input)
-            }
-        }
-    };
-
     // Redirect URL configuration
     let (redirect_url, use_local_server) = match args.redirect_url {
         Some(url) => {
@@ -120,34 +99,80 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
         return Ok(());
     }
 
-    // Resource (audience) - use flag or default
-    let resource = args.resource.unwrap_or_default();
-
     // Scopes - use flag or default to empty
     let scopes: Vec<String> = match args.scopes {
         Some(s) => s.split(',').map(|s| s.trim().to_string()).collect(),
         None => vec![],
     };
 
+    // Discover the authorization server unless it was fully specified
+    let metadata =
+        if args.auth_url.is_none() || args.token_url.is_none() || args.client_id.is_none() {
+            discover_metadata(&ctx.output, &server_url, args.resource_metadata.as_deref()).await?
+        } else {
+            None
+        };
+
+    // Resource (audience) - use the flag, or the one the server's metadata names
+    let resource = args
+        .resource
+        .or_else(|| metadata.as_ref().and_then(|m| m.resource.clone()));
+
+    let auth_url = match args.auth_url {
+        Some(url) => url,
+        None => match &metadata {
+            Some(metadata) => metadata.authorization_endpoint.clone(),
+            None => rl.readline("Authorization URL: ")?,
+        },
+    };
+
+    let token_url = match args.token_url {
+        Some(url) => url,
+        None => match &metadata {
+            Some(metadata) => metadata.token_endpoint.clone(),
+            None => rl.readline("Token URL: ")?,
+        },
+    };
+
+    let registration_endpoint = metadata.and_then(|m| m.registration_endpoint);
+    let (client_id, client_secret) = match (args.client_id, registration_endpoint) {
+        (Some(id), _) => (id, prompt_client_secret(&mut rl, args.client_secret)?),
+        (None, Some(endpoint)) => {
+            ctx.output
+                .text(format!("Registering client at {endpoint}"))?;
+            let registration = oauth::register_client(&endpoint, &redirect_url, &scopes).await?;
+            ctx.output
+                .trace_success(format!("Registered client ID: {}", registration.client_id))?;
+            (registration.client_id, registration.client_secret)
+        }
+        (None, None) => {
+            let id = rl.readline("Client ID: ")?;
+            (id, prompt_client_secret(&mut rl, args.client_secret)?)
+        }
+    };
+
     ctx.output.text("")?;
     ctx.output.text("Initiating OAuth flow...")?;
 
-    // Create OAuth configuration
-    let oauth_config = OAuth2Config {
-        client_id: client_id.clone(),
-        client_secret: client_secret.clone(),
-        auth_url: auth_url.clone(),
-        token_url: token_url.clone(),
-        redirect_url: redirect_url.clone(),
-        resource: resource.clone(),
-        scopes: scopes.clone(),
+    let mut stored_auth = StoredAuth {
+        name: name.clone(),
+        server_url,
+        client_id,
+        client_secret,
+        access_token: None,
+        refresh_token: None,
+        expires_at: None,
+        auth_url,
+        token_url,
+        redirect_url: Some(redirect_url.clone()),
+        scopes,
+        resource,
     };
 
-    // Create OAuth client
-    let mut oauth_client = OAuth2Client::new(oauth_config)?;
-
-    // Get authorization URL
-    let (auth_url_with_params, csrf_token) = oauth_client.get_authorization_url();
+    // Start an authorization code flow protected by PKCE
+    let flow = AuthorizationFlow::new(&stored_auth)?;
+    let auth_url_with_params = flow.authorize_url();
+    let expected_state = flow.state().to_string();
 
     ctx.output.text("")?;
     ctx.output.h1("Authorization required")?;
@@ -181,7 +206,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
     ctx.output.text("")?;
 
     // Handle different callback modes
-    let token_result = if let Some(callback_port) = use_local_server {
+    let code_result = if let Some(callback_port) = use_local_server {
         // Use local callback server
         let callback_server = OAuth2CallbackServer::new(callback_port);
 
@@ -196,7 +221,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
 
         // Use tokio::select to handle both callback and cancellation
         tokio::select! {
-            result = wait_for_callback(&mut oauth_client, callback_server, csrf_token.secret().to_string()) => {
+            result = wait_for_callback(callback_server, &expected_state) => {
                 Ok(result)
             }
             _ = signal::ctrl_c() => {
@@ -204,7 +229,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
                 ctx.output.trace_warn("Cancelled! Switching to manual mode...")?;
                 timeout(
                     Duration::from_secs(300),
-                    wait_for_manual_callback(&mut oauth_client, csrf_token.secret().to_string(), &ctx.output),
+                    wait_for_manual_callback(&expected_state, &ctx.output),
                 ).await
             }
         }
@@ -218,109 +243,116 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
 
         timeout(
             Duration::from_secs(300), // 5 minute timeout
-            wait_for_manual_callback(
-                &mut oauth_client,
-                csrf_token.secret().to_string(),
-                &ctx.output,
-            ),
+            wait_for_manual_callback(&expected_state, &ctx.output),
         )
         .await
     };
 
-    let token = match token_result {
-        Ok(Ok(token)) => token,
-        Ok(Err(e)) => {
-            let error_msg = format!("{e}");
-            if error_msg.contains("redirect_uri") || error_msg.contains("redirect URL") {
-                ctx.output.text("")?;
-                ctx.output
-                    .trace_error("OAuth Error: Redirect URL not registered")?;
-                ctx.output
-                    .text("The redirect URL is not associated with your OAuth application.")?;
-                ctx.output.text("")?;
-                ctx.output.text("To fix this:")?;
-                ctx.output
-                    .text("1. Go to your OAuth application settings")?;
-                ctx.output
-                    .text(format!("2. Add this redirect URL: {redirect_url}"))?;
-                ctx.output.text("3. Run this command again")?;
-                ctx.output.text("")?;
-                return Err(Error::Other(format!(
-                    "OAuth configuration error: {error_msg}"
-                )));
-            } else if error_msg.contains("incorrect_client_credentials")
-                || error_msg.contains("client_id and/or client_secret")
-            {
-                ctx.output.text("")?;
-                ctx.output
-                    .trace_error("OAuth Error: Invalid client credentials")?;
-                ctx.output
-                    .text("The client_id and/or client_secret are incorrect.")?;
-                ctx.output.text("")?;
-                ctx.output.text("To fix this:")?;
-                ctx.output
-                    .text("1. Verify your OAuth application settings")?;
-                ctx.output
-                    .text("2. Make sure the client_id and client_secret match exactly")?;
-                ctx.output
-                    .text("3. For GitHub: client_secret is required for OAuth Apps")?;
-                ctx.output
-                    .text("4. Check for trailing spaces or incorrect copy/paste")?;
-                ctx.output.text("")?;
-                return Err(Error::Other(format!(
-                    "OAuth authentication error: {error_msg}"
-                )));
-            }
-            return Err(Error::Other(format!("OAuth error: {error_msg}")));
-        }
+    let exchange_result = match code_result {
+        Ok(Ok(code)) => flow.exchange_code(code, &mut stored_auth).await,
+        Ok(Err(e)) => Err(e),
         Err(_) => {
             return Err(Error::Other(
                 "OAuth authorization timed out after 5 minutes".to_string(),
             ));
         }
     };
+    if let Err(e) = exchange_result {
+        return Err(report_oauth_error(&ctx.output, &e, &redirect_url)?);
+    }
 
     ctx.output.trace_success("Authorization successful!")?;
 
-    // Convert token expiration from Instant to SystemTime
-    let expires_at = token.expires_at.map(|instant| {
-        let duration_since_now = instant.duration_since(Instant::now());
-        SystemTime::now() + duration_since_now
-    });
-
     // Store the authentication
-    let stored_auth = StoredAuth {
-        name: name.clone(),
-        server_url,
-        client_id,
-        client_secret,
-        access_token: Some(token.access_token),
-        refresh_token: token.refresh_token,
-        expires_at,
-        auth_url,
-        token_url,
-        redirect_url: Some(redirect_url),
-        scopes,
-    };
-
     storage.store_auth(&stored_auth)?;
 
     ctx.output.text("")?;
     ctx.output
         .trace_success(format!("Authentication entry '{name}' saved successfully!"))?;
-    ctx.output.text(format!(
-        "You can now use: mcptool connect --auth {name} <target>"
-    ))?;
+    ctx.output
+        .text(format!("You can now use: mcptool connect auth://{name}"))?;
 
     Ok(())
 }
 
-/// Waits for the OAuth callback to be received via local server.
+/// Looks up the authorization server metadata for a server, returning `None`
+/// if discovery fails so the user can be prompted instead.
+async fn discover_metadata(
+    output: &Output,
+    server_url: &str,
+    resource_metadata: Option<&str>,
+) -> Result<Option<ServerMetadata>> {
+    output.text("Discovering OAuth endpoints...")?;
+    match oauth::discover(server_url, resource_metadata).await {
+        Ok(metadata) => {
+            output.trace_success(format!(
+                "Found authorization server ({})",
+                metadata.authorization_endpoint
+            ))?;
+            Ok(Some(metadata))
+        }
+        Err(e) => {
+            output.trace_warn(format!("OAuth discovery failed: {e}"))?;
+            Ok(None)
+        }
+    }
+}
+
+/// Uses the given client secret or prompts for an optional one.
+fn prompt_client_secret(rl: &mut DefaultEditor, secret: Option<String>) -> Result<Option<String>> {
+    if secret.is_some() {
+        return Ok(secret);
+    }
+    let client_secret_input = rl.readline("Client Secret (optional, press Enter to skip): ")?;
+    if client_secret_input.trim().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(client_secret_input))
+    }
+}
+
+/// Prints troubleshooting hints for common OAuth failures and returns the error to report.
+fn report_oauth_error(output: &Output, e: &Error, redirect_url: &str) -> Result<Error> {
+    let error_msg = format!("{e}");
+    if error_msg.contains("redirect_uri") || error_msg.contains("redirect URL") {
+        output.text("")?;
+        output.trace_error("OAuth Error: Redirect URL not registered")?;
+        output.text("The redirect URL is not associated with your OAuth application.")?;
+        output.text("")?;
+        output.text("To fix this:")?;
+        output.text("1. Go to your OAuth application settings")?;
+        output.text(format!("2. Add this redirect URL: {redirect_url}"))?;
+        output.text("3. Run this command again")?;
+        output.text("")?;
+        return Ok(Error::Other(format!(
+            "OAuth configuration error: {error_msg}"
+        )));
+    } else if error_msg.contains("incorrect_client_credentials")
+        || error_msg.contains("client_id and/or client_secret")
+    {
+        output.text("")?;
+        output.trace_error("OAuth Error: Invalid client credentials")?;
+        output.text("The client_id and/or client_secret are incorrect.")?;
+        output.text("")?;
+        output.text("To fix this:")?;
+        output.text("1. Verify your OAuth application settings")?;
+        output.text("2. Make sure the client_id and client_secret match exactly")?;
+        output.text("3. For GitHub: client_secret is required for OAuth Apps")?;
+        output.text("4. Check for trailing spaces or incorrect copy/paste")?;
+        output.text("")?;
+        return Ok(Error::Other(format!(
+            "OAuth authentication error: {error_msg}"
+        )));
+    }
+    Ok(Error::Other(format!("OAuth error: {error_msg}")))
+}
+
+/// Waits for the OAuth callback to be received via local server, returning
+/// the authorization code.
 async fn wait_for_callback(
-    oauth_client: &mut OAuth2Client,
     callback_server: OAuth2CallbackServer,
-    expected_state: String,
-) -> Result<OAuth2Token> {
+    expected_state: &str,
+) -> Result<String> {
     // Wait for the OAuth callback
     let (code, state) = callback_server.wait_for_callback().await?;
 
@@ -331,18 +363,12 @@ async fn wait_for_callback(
         ));
     }
 
-    // Exchange the authorization code for an access token
-    let token = oauth_client.exchange_code(code, state).await?;
-
-    Ok(token)
+    Ok(code)
 }
 
-/// Waits for the OAuth callback URL to be manually entered by the user.
-async fn wait_for_manual_callback(
-    oauth_client: &mut OAuth2Client,
-    expected_state: String,
-    output: &Output,
-) -> Result<OAuth2Token> {
+/// Waits for the OAuth callback URL to be manually entered by the user,
+/// returning the authorization code.
+async fn wait_for_manual_callback(expected_state: &str, output: &Output) -> Result<String> {
     let mut rl = DefaultEditor::new()?;
 
     output.text("")?;
@@ -390,13 +416,7 @@ async fn wait_for_manual_callback(
         ));
     }
 
-    // Exchange the authorization code for an access token
-    let token = oauth_client
-        .exchange_code(code, state)
-        .await
-        .map_err(|e| Error::Other(format!("Token exchange failed: {e}")))?;
-
-    Ok(token)
+    Ok(code)
 }
 
 /// Finds an available port on localhost.
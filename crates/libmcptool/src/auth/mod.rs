@@ -4,6 +4,8 @@
 mod add;
 /// OAuth credential listing functionality.
 mod list;
+/// OAuth discovery, registration, authorization and refresh.
+pub mod oauth;
 /// OAuth credential removal functionality.
 mod remove;
 /// OAuth token renewal functionality.
@@ -0,0 +1,399 @@
+//! OAuth 2.0 protocol helpers: authorization server discovery, dynamic client
+//! registration, the authorization code flow with PKCE, and token refresh.
+
+use std::{error, time::SystemTime};
+
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointNotSet, EndpointSet,
+    PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, RequestTokenError, Scope,
+    TokenResponse, TokenUrl,
+    basic::{BasicClient, BasicErrorResponse, BasicTokenResponse},
+};
+use reqwest::{StatusCode, header::WWW_AUTHENTICATE};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use url::Url;
+
+use crate::{Error, Result, storage::StoredAuth};
+
+/// OAuth client with authorization and token endpoints configured.
+type ConfiguredClient =
+    BasicClient<EndpointSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>;
+
+/// Authorization server metadata (RFC 8414), reduced to the fields we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerMetadata {
+    /// URL of the authorization endpoint.
+    pub authorization_endpoint: String,
+    /// URL of the token endpoint.
+    pub token_endpoint: String,
+    /// URL of the dynamic client registration endpoint, if supported.
+    #[serde(default)]
+    pub registration_endpoint: Option<String>,
+    /// Scopes the server advertises.
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+    /// The resource identifier from the MCP server's protected resource
+    /// metadata, to request tokens for.
+    #[serde(skip)]
+    pub resource: Option<String>,
+}
+
+/// Protected resource metadata (RFC 9728), reduced to the fields we use.
+#[derive(Debug, Deserialize)]
+struct ResourceMetadata {
+    /// The resource's identifier.
+    #[serde(default)]
+    resource: Option<String>,
+    /// Authorization servers that issue tokens for this resource.
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+}
+
+/// Client credentials issued by dynamic client registration (RFC 7591).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientRegistration {
+    /// The issued client identifier.
+    pub client_id: String,
+    /// The issued client secret, for confidential clients.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+}
+
+/// Sends an MCP server an unauthenticated request through `http`, to see
+/// whether it wants OAuth. If it answers 401 with a `WWW-Authenticate`
+/// challenge, returns the URL of its protected resource metadata: the
+/// challenge's `resource_metadata` parameter (RFC 9728), or the well-known
+/// location. Returns None if the server doesn't ask for OAuth.
+pub async fn challenge(http: &reqwest::Client, server_url: &str) -> Result<Option<String>> {
+    let server = Url::parse(server_url)
+        .map_err(|e| Error::Format(format!("Invalid server URL '{server_url}': {e}")))?;
+    let response = http
+        .post(server.clone())
+        .header("Accept", "application/json, text/event-stream")
+        .json(&json!({ "jsonrpc": "2.0", "id": 0, "method": "ping" }))
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to probe {server_url} for OAuth: {e}")))?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+    let Some(header) = response
+        .headers()
+        .get(WWW_AUTHENTICATE)
+        .and_then(|header| header.to_str().ok())
+    else {
+        return Ok(None);
+    };
+    Ok(Some(resource_metadata_param(header).unwrap_or_else(|| {
+        well_known_url(&server, "oauth-protected-resource")
+    })))
+}
+
+/// Extracts the `resource_metadata` parameter from a `WWW-Authenticate`
+/// header value.
+fn resource_metadata_param(header: &str) -> Option<String> {
+    let (_, rest) = header.split_once("resource_metadata=")?;
+    let value = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => rest.split([',', ' ']).next()?,
+    };
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Discovers the authorization server for an MCP server.
+///
+/// The server's protected resource metadata, read from `resource_metadata`
+/// or the well-known location, names the authorization server and the
+/// resource to request tokens for; if it is unavailable, the MCP server's own
+/// origin is assumed to be the issuer.
+pub async fn discover(server_url: &str, resource_metadata: Option<&str>) -> Result<ServerMetadata> {
+    let http = reqwest::Client::new();
+    let server = Url::parse(server_url)
+        .map_err(|e| Error::Format(format!("Invalid server URL '{server_url}': {e}")))?;
+
+    let resource_url = resource_metadata.map_or_else(
+        || well_known_url(&server, "oauth-protected-resource"),
+        str::to_string,
+    );
+    let (issuer, resource) = match fetch_json::<ResourceMetadata>(&http, &resource_url).await {
+        Ok(metadata) => match metadata.authorization_servers.first() {
+            Some(issuer) => (
+                Url::parse(issuer).map_err(|e| {
+                    Error::Other(format!("Invalid authorization server URL '{issuer}': {e}"))
+                })?,
+                metadata.resource,
+            ),
+            None => (server, metadata.resource),
+        },
+        Err(_) => (server, None),
+    };
+
+    for suffix in ["oauth-authorization-server", "openid-configuration"] {
+        if let Ok(metadata) =
+            fetch_json::<ServerMetadata>(&http, &well_known_url(&issuer, suffix)).await
+        {
+            return Ok(ServerMetadata {
+                resource,
+                ..metadata
+            });
+        }
+    }
+    Err(Error::Other(format!(
+        "No authorization server metadata found for {issuer}"
+    )))
+}
+
+/// Registers mcptool as a public client with the authorization server.
+pub async fn register_client(
+    registration_endpoint: &str,
+    redirect_url: &str,
+    scopes: &[String],
+) -> Result<ClientRegistration> {
+    let mut request = json!({
+        "client_name": "mcptool",
+        "redirect_uris": [redirect_url],
+        "grant_types": ["authorization_code", "refresh_token"],
+        "response_types": ["code"],
+        "token_endpoint_auth_method": "none",
+    });
+    if !scopes.is_empty() {
+        request["scope"] = json!(scopes.join(" "));
+    }
+
+    let response = reqwest::Client::new()
+        .post(registration_endpoint)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Client registration request failed: {e}")))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Client registration failed with {status}: {body}"
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| Error::Other(format!("Invalid client registration response: {e}")))
+}
+
+/// An in-progress authorization code flow protected by PKCE.
+pub struct AuthorizationFlow {
+    /// The configured OAuth client.
+    client: ConfiguredClient,
+    /// URL the user visits to authorize mcptool.
+    authorize_url: Url,
+    /// State parameter used for CSRF protection.
+    csrf_token: CsrfToken,
+    /// PKCE verifier sent with the code exchange.
+    pkce_verifier: PkceCodeVerifier,
+}
+
+impl AuthorizationFlow {
+    /// Starts a flow for the given entry, requesting its resource audience if
+    /// it has one.
+    pub fn new(auth: &StoredAuth) -> Result<Self> {
+        let client = oauth_client(auth)?;
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let mut request = client
+            .authorize_url(CsrfToken::new_random)
+            .add_scopes(auth.scopes.iter().cloned().map(Scope::new))
+            .set_pkce_challenge(pkce_challenge);
+        if let Some(resource) = auth.resource.as_deref().filter(|r| !r.is_empty()) {
+            request = request.add_extra_param("resource", resource);
+        }
+        let (authorize_url, csrf_token) = request.url();
+
+        Ok(Self {
+            client,
+            authorize_url,
+            csrf_token,
+            pkce_verifier,
+        })
+    }
+
+    /// The URL the user should open to authorize the application.
+    pub fn authorize_url(&self) -> &Url {
+        &self.authorize_url
+    }
+
+    /// The state value the authorization callback must echo back.
+    pub fn state(&self) -> &str {
+        self.csrf_token.secret()
+    }
+
+    /// Exchanges an authorization code for tokens and records them in `auth`.
+    pub async fn exchange_code(self, code: String, auth: &mut StoredAuth) -> Result<()> {
+        let token = self
+            .client
+            .exchange_code(AuthorizationCode::new(code))
+            .set_pkce_verifier(self.pkce_verifier)
+            .request_async(&reqwest::Client::new())
+            .await
+            .map_err(token_error)?;
+        apply_token(auth, &token);
+        Ok(())
+    }
+}
+
+/// Exchanges the entry's refresh token for a new access token.
+pub async fn refresh(auth: &mut StoredAuth) -> Result<()> {
+    let refresh_token = auth.refresh_token.clone().ok_or(Error::Other(
+        "No refresh token available for this authentication entry".to_string(),
+    ))?;
+    let client = oauth_client(auth)?;
+    let refresh_token = RefreshToken::new(refresh_token);
+    let mut request = client.exchange_refresh_token(&refresh_token);
+    if let Some(resource) = auth.resource.as_deref().filter(|r| !r.is_empty()) {
+        request = request.add_extra_param("resource", resource);
+    }
+    let token = request
+        .request_async(&reqwest::Client::new())
+        .await
+        .map_err(token_error)?;
+    apply_token(auth, &token);
+    Ok(())
+}
+
+/// Returns true if the entry's access token has expired.
+pub fn is_expired(auth: &StoredAuth) -> bool {
+    auth.expires_at
+        .is_some_and(|expires_at| expires_at <= SystemTime::now())
+}
+
+/// Builds an OAuth client from a stored entry.
+fn oauth_client(auth: &StoredAuth) -> Result<ConfiguredClient> {
+    let mut client = BasicClient::new(ClientId::new(auth.client_id.clone()))
+        .set_auth_uri(
+            AuthUrl::new(auth.auth_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid auth URL: {e}")))?,
+        )
+        .set_token_uri(
+            TokenUrl::new(auth.token_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid token URL: {e}")))?,
+        );
+
+    if let Some(client_secret) = auth.client_secret.as_ref() {
+        client = client.set_client_secret(ClientSecret::new(client_secret.clone()));
+    }
+    if let Some(redirect_url) = auth.redirect_url.as_ref() {
+        client = client.set_redirect_uri(
+            RedirectUrl::new(redirect_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid redirect URL: {e}")))?,
+        );
+    }
+    Ok(client)
+}
+
+/// Records the tokens from a token response in a stored entry.
+fn apply_token(auth: &mut StoredAuth, token: &BasicTokenResponse) {
+    auth.access_token = Some(token.access_token().secret().clone());
+    // Servers may rotate refresh tokens; keep the old one if no new one was issued
+    if let Some(refresh_token) = token.refresh_token() {
+        auth.refresh_token = Some(refresh_token.secret().clone());
+    }
+    auth.expires_at = token
+        .expires_in()
+        .map(|duration| SystemTime::now() + duration);
+}
+
+/// Converts a token endpoint error into our error type.
+fn token_error<RE: error::Error + 'static>(e: RequestTokenError<RE, BasicErrorResponse>) -> Error {
+    Error::Other(match e {
+        RequestTokenError::ServerResponse(response) => {
+            format!("Server error: {:?}", response.error())
+        }
+        RequestTokenError::Request(e) => format!("Request error: {e}"),
+        RequestTokenError::Parse(e, _) => format!("Parse error: {e}"),
+        RequestTokenError::Other(e) => format!("Other error: {e}"),
+    })
+}
+
+/// Fetches and decodes a JSON document.
+async fn fetch_json<T: DeserializeOwned>(http: &reqwest::Client, url: &str) -> Result<T> {
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Other(format!("Failed to fetch {url}: {e}")))?;
+    response
+        .json()
+        .await
+        .map_err(|e| Error::Other(format!("Invalid metadata at {url}: {e}")))
+}
+
+/// Builds a well-known metadata URL, inserting the well-known segment between
+/// the origin and any path as described in RFC 8414.
+fn well_known_url(base: &Url, suffix: &str) -> String {
+    let origin = base.origin().ascii_serialization();
+    let path = base.path().trim_end_matches('/');
+    format!("{origin}/.well-known/{suffix}{path}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_metadata_param() {
+        assert_eq!(
+            resource_metadata_param(
+                r#"Bearer error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource""#
+            )
+            .as_deref(),
+            Some("https://mcp.example.com/.well-known/oauth-protected-resource")
+        );
+        assert_eq!(
+            resource_metadata_param("Bearer resource_metadata=https://a.example/rm, scope=x")
+                .as_deref(),
+            Some("https://a.example/rm")
+        );
+        assert_eq!(resource_metadata_param(r#"Bearer realm="mcp""#), None);
+    }
+
+    #[test]
+    fn test_well_known_url() {
+        let cases = [
+            (
+                "https://auth.example.com",
+                "https://auth.example.com/.well-known/oauth-authorization-server",
+            ),
+            (
+                "https://auth.example.com/",
+                "https://auth.example.com/.well-known/oauth-authorization-server",
+            ),
+            (
+                "https://example.com:8443/tenant/one",
+                "https://example.com:8443/.well-known/oauth-authorization-server/tenant/one",
+            ),
+        ];
+        for (base, expected) in cases {
+            let base = Url::parse(base).unwrap();
+            assert_eq!(
+                well_known_url(&base, "oauth-authorization-server"),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_server_metadata_optional_fields() {
+        let metadata: ServerMetadata = serde_json::from_str(
+            r#"{
+                "issuer": "https://auth.example.com",
+                "authorization_endpoint": "https://auth.example.com/authorize",
+                "token_endpoint": "https://auth.example.com/token"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.token_endpoint, "https://auth.example.com/token");
+        assert!(metadata.registration_endpoint.is_none());
+        assert!(metadata.scopes_supported.is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+//! Running many tool calls from a JSONL file.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tmcp::{Arguments, Client, ClientHandler};
+use tokio::{sync::mpsc, task::JoinSet};
+
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    ctx::Ctx,
+    mcp,
+    retry::CallRetry,
+    target::Target,
+};
+
+/// One line of a batch file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchCall {
+    /// The tool to call.
+    pub tool: String,
+    /// The arguments to call it with.
+    #[serde(default)]
+    pub arguments: Option<Map<String, Value>>,
+}
+
+/// Parses a batch file, returning each call with its line number. Blank
+/// lines are skipped, and any line that isn't a call fails the whole batch
+/// before anything is run.
+pub fn parse_batch(contents: &str, name: &str) -> Result<Vec<(usize, BatchCall)>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map(|call| (i + 1, call))
+                .map_err(|e| Error::Format(format!("{name}:{}: {e}", i + 1)))
+        })
+        .collect()
+}
+
+/// Calls waiting to be run, with their position in the batch and line number.
+type Queue = Arc<Mutex<VecDeque<(usize, usize, BatchCall)>>>;
+
+/// Connects to a target and calls the tools in a JSONL batch file, printing a
+/// result object for each call as a line of JSON. With a `concurrency` above
+/// one, that many connections are opened and calls run in parallel across
+/// them, but results are still printed in input order. Failed calls are
+/// retried as `retry` says. Fails if any call failed.
+pub async fn callbatch_command(
+    ctx: &Ctx,
+    target: Target,
+    path: &Path,
+    concurrency: usize,
+    retry: CallRetry,
+) -> Result<()> {
+    if concurrency == 0 {
+        return Err(Error::Format(
+            "--concurrency must be at least 1".to_string(),
+        ));
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+    let calls = parse_batch(&contents, &path.display().to_string())?;
+    let total = calls.len();
+
+    // Each connection handles one request at a time, so parallel calls need a
+    // pool of them
+    let queue: Queue = Arc::new(Mutex::new(
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, (line, call))| (index, line, call))
+            .collect(),
+    ));
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut workers = JoinSet::new();
+    for _ in 0..concurrency.min(total) {
+        let (client, _) = client::get_client(ctx, &target).await?;
+        workers.spawn(worker(
+            client,
+            ctx.clone(),
+            queue.clone(),
+            retry.clone(),
+            sender.clone(),
+        ));
+    }
+    drop(sender);
+
+    // Results arrive in completion order, and are held back until all the
+    // calls before them are printed
+    let mut pending = BTreeMap::new();
+    let mut printed = 0;
+    let mut failed = 0;
+    while let Some((index, record)) = receiver.recv().await {
+        pending.insert(index, record);
+        while let Some(record) = pending.remove(&printed) {
+            if record["ok"] != Value::Bool(true) {
+                failed += 1;
+            }
+            ctx.output.json_line(&record)?;
+            printed += 1;
+        }
+    }
+    while let Some(joined) = workers.join_next().await {
+        joined.map_err(|e| Error::Internal(format!("Batch worker failed: {e}")))?;
+    }
+    if printed < total {
+        return Err(Error::Internal(format!(
+            "Only {printed} of {total} call(s) completed"
+        )));
+    }
+
+    let passed = total - failed;
+    ctx.output
+        .text(format!("{passed} passed, {failed} failed"))?;
+    if failed > 0 {
+        return Err(Error::Other(format!("{failed} of {total} call(s) failed")));
+    }
+    Ok(())
+}
+
+/// Runs calls from the queue over one connection until it is empty, sending
+/// each result record back with the call's position in the batch.
+async fn worker<C: ClientHandler + 'static>(
+    mut client: Connected<C>,
+    ctx: Ctx,
+    queue: Queue,
+    retry: CallRetry,
+    results: mpsc::UnboundedSender<(usize, Value)>,
+) {
+    while let Some((index, line, call)) = next_call(&queue) {
+        let record = run_call(&mut client, &ctx, line, &call, &retry).await;
+        if results.send((index, record)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Takes the next call from the queue.
+fn next_call(queue: &Queue) -> Option<(usize, usize, BatchCall)> {
+    queue
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .pop_front()
+}
+
+/// Calls one tool, returning its result record: the line number, the tool,
+/// whether it succeeded, and the result or error. A result flagged as an
+/// error counts as a failure.
+async fn run_call<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    line: usize,
+    call: &BatchCall,
+    retry: &CallRetry,
+) -> Value {
+    let arguments = call
+        .arguments
+        .clone()
+        .map(|map| Arguments::from(map.into_iter().collect::<HashMap<_, _>>()));
+    match mcp::call_tool_with_retries(client, ctx, &call.tool, arguments, retry).await {
+        Ok(result) => json!({
+            "line": line,
+            "tool": call.tool,
+            "ok": result.is_error != Some(true),
+            "result": result,
+        }),
+        Err(e) => json!({
+            "line": line,
+            "tool": call.tool,
+            "ok": false,
+            "error": e.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch() {
+        let contents = "\
+{\"tool\": \"echo\", \"arguments\": {\"text\": \"hi\"}}
+
+{\"tool\": \"ping\"}
+";
+        let calls = parse_batch(contents, "calls.jsonl").unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, 1);
+        assert_eq!(calls[0].1.tool, "echo");
+        assert_eq!(
+            calls[0].1.arguments.as_ref().unwrap()["text"],
+            Value::String("hi".to_string())
+        );
+        assert_eq!(calls[1].0, 3);
+        assert!(calls[1].1.arguments.is_none());
+    }
+
+    #[test]
+    fn test_parse_batch_errors() {
+        let err = parse_batch("{\"tool\": \"echo\"}\n{\"tol\": \"x\"}\n", "calls.jsonl")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("calls.jsonl:2:"));
+        assert!(parse_batch("not json\n", "calls.jsonl").is_err());
+        assert!(parse_batch("{\"tool\": \"echo\", \"arguments\": [1]}\n", "calls.jsonl").is_err());
+    }
+}
@@ -0,0 +1,433 @@
+//! Load benchmarks: one request sent over and over from several connections
+//! at once for a fixed time, reporting throughput, latency percentiles and
+//! the error rate.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+use hdrhistogram::{
+    Histogram,
+    serialization::{Serializer, V2DeflateSerializer},
+};
+use serde_json::{Value, json};
+use tmcp::{Arguments, Client, ServerAPI};
+use tokio::{task::JoinSet, time};
+
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    ctx::Ctx,
+    output::Output,
+    stats::{millis, percentile},
+    target::Target,
+};
+
+/// The significant figures the latency histogram keeps, which sets its
+/// relative precision.
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+/// A method a benchmark can send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BenchMethod {
+    /// `ping`.
+    #[value(name = "ping")]
+    Ping,
+    /// `tools/list`.
+    #[value(name = "tools/list")]
+    ListTools,
+    /// `prompts/list`.
+    #[value(name = "prompts/list")]
+    ListPrompts,
+    /// `resources/list`.
+    #[value(name = "resources/list")]
+    ListResources,
+    /// `resources/templates/list`.
+    #[value(name = "resources/templates/list")]
+    ListResourceTemplates,
+    /// `tools/call`, which needs a tool.
+    #[value(name = "tools/call")]
+    CallTool,
+    /// `resources/read`, which needs a URI.
+    #[value(name = "resources/read")]
+    ReadResource,
+}
+
+/// The request a benchmark sends.
+#[derive(Debug, Clone)]
+pub enum BenchRequest {
+    /// `ping`.
+    Ping,
+    /// `tools/list`, first page only.
+    ListTools,
+    /// `prompts/list`, first page only.
+    ListPrompts,
+    /// `resources/list`, first page only.
+    ListResources,
+    /// `resources/templates/list`, first page only.
+    ListResourceTemplates,
+    /// `tools/call` with fixed arguments.
+    CallTool {
+        /// The tool to call.
+        name: String,
+        /// The arguments to call it with.
+        arguments: Option<Arguments>,
+    },
+    /// `resources/read`.
+    ReadResource {
+        /// The resource to read.
+        uri: String,
+    },
+}
+
+impl BenchRequest {
+    /// The request for a method. `tools/call` needs a tool, and
+    /// `resources/read` a URI.
+    pub fn new(
+        method: BenchMethod,
+        tool: Option<String>,
+        arguments: Option<Arguments>,
+        uri: Option<String>,
+    ) -> Result<Self> {
+        Ok(match method {
+            BenchMethod::Ping => Self::Ping,
+            BenchMethod::ListTools => Self::ListTools,
+            BenchMethod::ListPrompts => Self::ListPrompts,
+            BenchMethod::ListResources => Self::ListResources,
+            BenchMethod::ListResourceTemplates => Self::ListResourceTemplates,
+            BenchMethod::CallTool => Self::CallTool {
+                name: tool.ok_or_else(|| {
+                    Error::Format("Benchmarking tools/call needs --tool".to_string())
+                })?,
+                arguments,
+            },
+            BenchMethod::ReadResource => Self::ReadResource {
+                uri: uri.ok_or_else(|| {
+                    Error::Format("Benchmarking resources/read needs --uri".to_string())
+                })?,
+            },
+        })
+    }
+
+    /// The method the request calls.
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Ping => "ping",
+            Self::ListTools => "tools/list",
+            Self::ListPrompts => "prompts/list",
+            Self::ListResources => "resources/list",
+            Self::ListResourceTemplates => "resources/templates/list",
+            Self::CallTool { .. } => "tools/call",
+            Self::ReadResource { .. } => "resources/read",
+        }
+    }
+
+    /// Sends the request once. A tool result flagged as an error counts as
+    /// a failure.
+    async fn send(&self, client: &mut Client<()>, ctx: &Ctx) -> Result<()> {
+        match self {
+            Self::Ping => ctx.request(client.ping()).await.map(|_| ()),
+            Self::ListTools => ctx.request(client.list_tools(None)).await.map(|_| ()),
+            Self::ListPrompts => ctx.request(client.list_prompts(None)).await.map(|_| ()),
+            Self::ListResources => ctx.request(client.list_resources(None)).await.map(|_| ()),
+            Self::ListResourceTemplates => ctx
+                .request(client.list_resource_templates(None))
+                .await
+                .map(|_| ()),
+            Self::CallTool { name, arguments } => {
+                let result = ctx
+                    .request(client.call_tool(name, arguments.clone()))
+                    .await?;
+                if result.is_error == Some(true) {
+                    return Err(Error::Other("Tool returned an error result".to_string()));
+                }
+                Ok(())
+            }
+            Self::ReadResource { uri } => ctx.request(client.resources_read(uri)).await.map(|_| ()),
+        }
+    }
+}
+
+/// How a benchmark is run.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// The request to send.
+    pub request: BenchRequest,
+    /// The number of connections sending requests at once.
+    pub concurrency: usize,
+    /// How long to send requests for.
+    pub duration: Duration,
+    /// A file to write the latency histogram to, as JSON.
+    pub histogram: Option<PathBuf>,
+}
+
+/// What one connection saw over a benchmark.
+#[derive(Debug, Default)]
+struct Samples {
+    /// The latency of each successful request.
+    latencies: Vec<Duration>,
+    /// The number of failed requests.
+    errors: u64,
+    /// The message of each kind of error, with how often it happened.
+    messages: BTreeMap<String, u64>,
+}
+
+impl Samples {
+    /// Adds another connection's samples to these.
+    fn merge(&mut self, other: Self) {
+        self.latencies.extend(other.latencies);
+        self.errors += other.errors;
+        for (message, count) in other.messages {
+            *self.messages.entry(message).or_default() += count;
+        }
+    }
+}
+
+/// Sends requests over one connection until the deadline.
+async fn worker(
+    mut client: Connected<()>,
+    ctx: Ctx,
+    request: Arc<BenchRequest>,
+    deadline: Instant,
+    completed: Arc<AtomicU64>,
+) -> Samples {
+    let mut samples = Samples::default();
+    while Instant::now() < deadline {
+        let started = Instant::now();
+        match request.send(&mut client, &ctx).await {
+            Ok(()) => samples.latencies.push(started.elapsed()),
+            Err(e) => {
+                samples.errors += 1;
+                *samples.messages.entry(e.to_string()).or_default() += 1;
+            }
+        }
+        completed.fetch_add(1, Ordering::Relaxed);
+    }
+    samples
+}
+
+/// A duration in whole microseconds.
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// An HDR histogram of latencies, in microseconds, as JSON: the latency at
+/// percentiles that close in on the slowest request by halving the distance
+/// each time, the count at each recorded value, and the histogram itself in
+/// HdrHistogram's compressed encoding, base64'd, for HdrHistogram's own tools
+/// to load.
+fn histogram(latencies: &[Duration]) -> Result<Value> {
+    let failed = |e: &dyn fmt::Display| Error::Internal(format!("Latency histogram failed: {e}"));
+    let mut histogram = Histogram::<u64>::new(SIGNIFICANT_FIGURES).map_err(|e| failed(&e))?;
+    for latency in latencies {
+        histogram.record(micros(*latency)).map_err(|e| failed(&e))?;
+    }
+    let mut encoded = Vec::new();
+    V2DeflateSerializer::new()
+        .serialize(&histogram, &mut encoded)
+        .map_err(|e| failed(&e))?;
+    Ok(json!({
+        "unit": "microseconds",
+        "count": histogram.len(),
+        "percentiles": histogram
+            .iter_quantiles(1)
+            .map(|step| json!({
+                "percentile": step.quantile_iterated_to() * 100.0,
+                "value": step.value_iterated_to(),
+            }))
+            .collect::<Vec<_>>(),
+        "values": histogram
+            .iter_recorded()
+            .map(|step| json!({
+                "value": step.value_iterated_to(),
+                "count": step.count_at_value(),
+            }))
+            .collect::<Vec<_>>(),
+        "encoded": STANDARD.encode(encoded),
+    }))
+}
+
+/// Sends a request from `concurrency` connections at once for the given
+/// duration, then reports throughput, latency percentiles and errors.
+/// Requests still in flight at the deadline are waited for and counted.
+pub async fn bench_command(ctx: &Ctx, target: Target, options: BenchOptions) -> Result<()> {
+    if options.concurrency == 0 {
+        return Err(Error::Format(
+            "--concurrency must be at least 1".to_string(),
+        ));
+    }
+    let output = &ctx.output;
+    let request = Arc::new(options.request);
+
+    // Each connection handles one request at a time, so concurrent requests
+    // need a connection each, all opened before the clock starts
+    let mut clients = Vec::new();
+    for _ in 0..options.concurrency {
+        let (client, _) = client::get_client(ctx, &target).await?;
+        clients.push(client);
+    }
+
+    let started = Instant::now();
+    let deadline = started + options.duration;
+    let completed = Arc::new(AtomicU64::new(0));
+    let mut workers = JoinSet::new();
+    for client in clients {
+        workers.spawn(worker(
+            client,
+            ctx.clone(),
+            request.clone(),
+            deadline,
+            completed.clone(),
+        ));
+    }
+    let mut ticker = time::interval(Duration::from_secs(1));
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        output.progress(format!(
+            "{}s/{}s, {} requests",
+            started.elapsed().as_secs(),
+            options.duration.as_secs(),
+            completed.load(Ordering::Relaxed)
+        ))?;
+    }
+    let mut samples = Samples::default();
+    while let Some(joined) = workers.join_next().await {
+        samples.merge(joined.map_err(|e| Error::Internal(format!("Bench worker failed: {e}")))?);
+    }
+    let elapsed = started.elapsed();
+    output.end_progress()?;
+
+    samples.latencies.sort();
+    if let Some(path) = &options.histogram {
+        write_histogram(path, &samples.latencies)?;
+    }
+    report(
+        output,
+        &target,
+        request.method(),
+        options.concurrency,
+        elapsed,
+        &samples,
+    )
+}
+
+/// Writes the latency histogram to a file.
+fn write_histogram(path: &Path, sorted: &[Duration]) -> Result<()> {
+    let contents = serde_json::to_string_pretty(&histogram(sorted)?)? + "\n";
+    fs::write(path, contents)
+        .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))
+}
+
+/// Prints the results of a benchmark.
+fn report(
+    output: &Output,
+    target: &Target,
+    method: &str,
+    concurrency: usize,
+    elapsed: Duration,
+    samples: &Samples,
+) -> Result<()> {
+    let sorted = &samples.latencies;
+    let requests = sorted.len() as u64 + samples.errors;
+    let throughput = requests as f64 / elapsed.as_secs_f64();
+    let error_rate = if requests == 0 {
+        0.0
+    } else {
+        samples.errors as f64 / requests as f64
+    };
+    let latencies: Vec<(&str, Duration)> = if sorted.is_empty() {
+        Vec::new()
+    } else {
+        let total: Duration = sorted.iter().sum();
+        vec![
+            ("min", sorted[0]),
+            ("p50", percentile(sorted, 50.0)),
+            ("p90", percentile(sorted, 90.0)),
+            ("p99", percentile(sorted, 99.0)),
+            ("p99.9", percentile(sorted, 99.9)),
+            ("max", sorted[sorted.len() - 1]),
+            (
+                "mean",
+                total / u32::try_from(sorted.len()).unwrap_or(u32::MAX),
+            ),
+        ]
+    };
+
+    if output.structured() {
+        let latency: serde_json::Map<String, Value> = latencies
+            .iter()
+            .map(|(name, value)| (name.replace('.', ""), json!(millis(*value))))
+            .collect();
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "method": method,
+            "concurrency": concurrency,
+            "seconds": elapsed.as_secs_f64(),
+            "requests": requests,
+            "errors": samples.errors,
+            "errorRate": error_rate,
+            "throughput": throughput,
+            "latencyMs": latency,
+            "errorMessages": samples.messages,
+        }))?;
+        return Ok(());
+    }
+
+    output.h1(format!("Benchmark of {method} on {target}"))?;
+    let out = output.indent();
+    out.kv("Concurrency", concurrency.to_string())?;
+    out.kv("Duration", format!("{:.2}s", elapsed.as_secs_f64()))?;
+    out.kv("Requests", requests.to_string())?;
+    out.kv("Throughput", format!("{throughput:.1} req/s"))?;
+    out.kv(
+        "Errors",
+        format!("{} ({:.2}%)", samples.errors, error_rate * 100.0),
+    )?;
+    if !latencies.is_empty() {
+        output.h2("Latency")?;
+        for (name, value) in &latencies {
+            out.kv(*name, format!("{:.2}ms", millis(*value)))?;
+        }
+    }
+    if !samples.messages.is_empty() {
+        output.h2("Errors")?;
+        for (message, count) in &samples.messages {
+            out.kv(count.to_string(), message)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let sorted: Vec<Duration> = (1..=1000).map(Duration::from_micros).collect();
+        let written = histogram(&sorted).unwrap();
+        assert_eq!(written["count"], 1000);
+        let values = written["values"].as_array().unwrap();
+        let total: u64 = values.iter().map(|v| v["count"].as_u64().unwrap()).sum();
+        assert_eq!(total, 1000);
+
+        let percentiles = written["percentiles"].as_array().unwrap();
+        assert_eq!(percentiles[0]["value"], 1);
+        assert_eq!(percentiles[1]["percentile"], 50.0);
+        assert_eq!(percentiles[1]["value"], 500);
+        assert_eq!(percentiles.last().unwrap()["value"], 1000);
+        assert!(written["encoded"].as_str().is_some_and(|s| !s.is_empty()));
+
+        let empty = histogram(&[]).unwrap();
+        assert!(empty["percentiles"].as_array().unwrap().is_empty());
+    }
+}
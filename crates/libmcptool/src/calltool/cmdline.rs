@@ -1,56 +1,306 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Read},
+};
 
-use tmcp::Arguments;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::{Map, Number, Value};
+use tmcp::{Arguments, schema::Tool};
 
-use crate::{Result, output::Output};
+use crate::{Error, Result, output::Output};
 
 /// Parses command line arguments in the form `key=value`.
+///
+/// Keys can be dotted paths such as `user.name` to build nested objects, and
+/// can end in `[]` to append to an array, as in `tags[]=a tags[]=b`. Values
+/// are converted to the type the tool's input schema expects, or guessed if
+/// the schema doesn't say. A value of `@path` is read from a file and `@-`
+/// from stdin, base64 encoded if the schema gives the parameter a
+/// `contentEncoding` of `base64`. Use `@@` for a value that starts with a
+/// literal `@`.
+///
+/// `key:=value` passes the value as raw JSON, so numbers, booleans, arrays
+/// and objects can be given exactly, and `key:=@path` reads the JSON from a
+/// file.
 pub fn parse_command_line_arguments(
     args: Vec<String>,
+    tool: &Tool,
     output: &Output,
+) -> Result<Option<Arguments>> {
+    parse_command_line_arguments_with_stdin(args, tool, output, &mut io::stdin().lock())
+}
+
+/// Internal implementation that accepts a stdin reader for testing.
+fn parse_command_line_arguments_with_stdin<R: Read>(
+    args: Vec<String>,
+    tool: &Tool,
+    output: &Output,
+    stdin: &mut R,
 ) -> Result<Option<Arguments>> {
     if args.is_empty() {
         return Ok(None);
     }
 
-    let mut arg_map = HashMap::new();
+    let mut arguments = Map::new();
+    let mut stdin = Some(stdin);
     for arg in args {
         let parts: Vec<&str> = arg.splitn(2, '=').collect();
         if parts.len() != 2 {
-            return Err(crate::Error::Other(format!(
+            return Err(Error::Other(format!(
                 "Invalid argument format: '{}'. Expected 'key=value'",
                 arg
             )));
         }
-        let key = parts[0].to_string();
+        let (key, raw) = match parts[0].strip_suffix(':') {
+            Some(key) => (key, true),
+            None => (parts[0], false),
+        };
+        let path = KeyPath::parse(key, tool)?;
+        let schema = path.schema(tool);
         let value = parts[1].to_string();
 
-        // Try to parse as different types
-        let json_value = if value == "true" || value == "false" {
-            serde_json::Value::Bool(value.parse().unwrap())
-        } else if let Ok(num) = value.parse::<i64>() {
-            // Check if the string representation would be different after parsing
-            // This catches cases like "00123" which should remain as strings
-            if num.to_string() == value {
-                serde_json::Value::Number(serde_json::Number::from(num))
-            } else {
-                serde_json::Value::String(value)
-            }
-        } else if let Ok(num) = value.parse::<f64>() {
-            serde_json::Value::Number(serde_json::Number::from_f64(num).unwrap())
+        let json_value = if raw {
+            parse_raw(path.key, &value, &mut stdin)?
+        } else if let Some(literal) = value.strip_prefix("@@") {
+            Value::String(format!("@{literal}"))
+        } else if let Some(source) = value.strip_prefix('@') {
+            read_value(path.key, source, schema, &mut stdin)?
         } else {
-            serde_json::Value::String(value)
+            coerce(value, schema)
         };
 
-        arg_map.insert(key, json_value);
+        path.insert(&mut arguments, json_value)?;
     }
 
+    let arg_map: HashMap<String, Value> = arguments.into_iter().collect();
     output.trace_info(format!("Parsed arguments: {:?}", arg_map))?;
     Ok(Some(Arguments::from(arg_map)))
 }
 
+/// Where a `key=value` argument goes: a property, a dotted path into nested
+/// objects, or either of those ending in `[]` to append to an array.
+#[derive(Debug, PartialEq, Eq)]
+struct KeyPath<'a> {
+    /// The key as given.
+    key: &'a str,
+    /// Property names, outermost first.
+    segments: Vec<&'a str>,
+    /// Whether the value is appended to an array.
+    append: bool,
+}
+
+impl<'a> KeyPath<'a> {
+    /// Parses a key. A key that names one of the tool's parameters is used as
+    /// is, even if it contains dots.
+    fn parse(key: &'a str, tool: &Tool) -> Result<Self> {
+        let is_parameter = tool
+            .input_schema
+            .properties
+            .as_ref()
+            .is_some_and(|properties| properties.contains_key(key));
+        if is_parameter {
+            return Ok(Self {
+                key,
+                segments: vec![key],
+                append: false,
+            });
+        }
+        let (path, append) = match key.strip_suffix("[]") {
+            Some(path) => (path, true),
+            None => (key, false),
+        };
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments
+            .iter()
+            .any(|segment| segment.is_empty() || segment.contains("[]"))
+        {
+            return Err(Error::Other(format!(
+                "Invalid argument key '{key}'. Expected names separated by '.', optionally ending in '[]'"
+            )));
+        }
+        Ok(Self {
+            key,
+            segments,
+            append,
+        })
+    }
+
+    /// The schema of the value, looked up in the tool's input schema.
+    fn schema<'t>(&self, tool: &'t Tool) -> Option<&'t Value> {
+        let (first, rest) = self.segments.split_first()?;
+        let mut schema = tool.input_schema.properties.as_ref()?.get(*first)?;
+        for segment in rest {
+            schema = schema.get("properties")?.get(*segment)?;
+        }
+        if self.append {
+            schema = schema.get("items")?;
+        }
+        Some(schema)
+    }
+
+    /// Inserts a value at the path, creating objects and arrays as needed.
+    /// A later value for the same key replaces an earlier one.
+    fn insert(&self, arguments: &mut Map<String, Value>, value: Value) -> Result<()> {
+        let Some((last, parents)) = self.segments.split_last() else {
+            return Ok(());
+        };
+        let mut object = arguments;
+        for segment in parents {
+            object = object
+                .entry(*segment)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| self.conflict())?;
+        }
+        if self.append {
+            object
+                .entry(*last)
+                .or_insert_with(|| Value::Array(Vec::new()))
+                .as_array_mut()
+                .ok_or_else(|| self.conflict())?
+                .push(value);
+        } else {
+            object.insert(last.to_string(), value);
+        }
+        Ok(())
+    }
+
+    /// The error for a key that needs an object or array where an earlier
+    /// argument put a different value.
+    fn conflict(&self) -> Error {
+        Error::Other(format!(
+            "Argument '{self}' conflicts with an earlier argument"
+        ))
+    }
+}
+
+impl fmt::Display for KeyPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Converts a value to the type its schema expects. Values that don't parse
+/// as that type are kept as strings, so validation can report them. Without
+/// a single expected type, the type is guessed from the value.
+fn coerce(value: String, schema: Option<&Value>) -> Value {
+    let expected = schema
+        .and_then(|schema| schema.get("type"))
+        .and_then(Value::as_str);
+    match expected {
+        Some("string") => Value::String(value),
+        Some("integer") => match value.parse::<i64>() {
+            Ok(num) => Value::Number(Number::from(num)),
+            Err(_) => Value::String(value),
+        },
+        Some("number") => match value.parse::<f64>().ok().and_then(Number::from_f64) {
+            Some(num) => Value::Number(num),
+            None => Value::String(value),
+        },
+        Some("boolean") => match value.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(value),
+        },
+        _ => infer(value),
+    }
+}
+
+/// Guesses the type of a value: booleans, integers and floats are converted,
+/// anything else is a string.
+fn infer(value: String) -> Value {
+    if value == "true" || value == "false" {
+        Value::Bool(value == "true")
+    } else if let Ok(num) = value.parse::<i64>() {
+        // Check if the string representation would be different after parsing
+        // This catches cases like "00123" which should remain as strings
+        if num.to_string() == value {
+            Value::Number(Number::from(num))
+        } else {
+            Value::String(value)
+        }
+    } else if let Some(num) = value.parse::<f64>().ok().and_then(Number::from_f64) {
+        Value::Number(num)
+    } else {
+        Value::String(value)
+    }
+}
+
+/// Converts an `--arg-json key=json` option to the equivalent `key:=json`
+/// argument.
+pub fn json_argument(arg: String) -> String {
+    match arg.split_once('=') {
+        Some((key, value)) => format!("{key}:={value}"),
+        None => arg,
+    }
+}
+
+/// Parses a raw JSON argument value, reading it from a file or stdin if it
+/// starts with `@`.
+fn parse_raw<R: Read>(key: &str, value: &str, stdin: &mut Option<&mut R>) -> Result<Value> {
+    let bytes = match value.strip_prefix('@') {
+        Some(source) => read_bytes(key, source, stdin)?.0,
+        None => value.as_bytes().to_vec(),
+    };
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::Other(format!("Invalid JSON for argument '{key}': {e}")))
+}
+
+/// Reads an argument value from a file, or from stdin if `source` is `-`.
+/// Stdin can only be read once.
+fn read_value<R: Read>(
+    key: &str,
+    source: &str,
+    schema: Option<&Value>,
+    stdin: &mut Option<&mut R>,
+) -> Result<Value> {
+    let (bytes, name) = read_bytes(key, source, stdin)?;
+    let encoding = schema
+        .and_then(|schema| schema.get("contentEncoding"))
+        .and_then(Value::as_str);
+    if encoding == Some("base64") {
+        return Ok(Value::String(STANDARD.encode(bytes)));
+    }
+    String::from_utf8(bytes).map(Value::String).map_err(|_| {
+        Error::Other(format!(
+            "{name} is not UTF-8 text. Binary data can only be sent for parameters with a \
+                 contentEncoding of base64, and '{key}' has none"
+        ))
+    })
+}
+
+/// Reads the contents of a file, or stdin if `source` is `-`, returning them
+/// with a name for the source to use in messages.
+fn read_bytes<R: Read>(
+    key: &str,
+    source: &str,
+    stdin: &mut Option<&mut R>,
+) -> Result<(Vec<u8>, String)> {
+    if source == "-" {
+        let reader = stdin.take().ok_or_else(|| {
+            Error::Other("Only one argument can be read from stdin with @-".to_string())
+        })?;
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Other(format!("Failed to read stdin for argument '{key}': {e}")))?;
+        Ok((bytes, "stdin".to_string()))
+    } else {
+        let bytes = fs::read(source).map_err(|e| {
+            Error::Other(format!("Failed to read {source} for argument '{key}': {e}"))
+        })?;
+        Ok((bytes, source.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+    use tmcp::schema::ToolSchema;
+
     use super::*;
     use crate::output::Output;
 
@@ -58,10 +308,48 @@ mod tests {
         Output::new(false, 80)
     }
 
+    fn create_test_tool() -> Tool {
+        let properties = json!({
+            "body": {"type": "string"},
+            "data": {"type": "string", "contentEncoding": "base64"},
+            "label": {"type": "string"},
+            "file.name": {"type": "string"},
+            "user": {
+                "type": "object",
+                "properties": {"name": {"type": "string"}, "age": {"type": "integer"}}
+            },
+            "tags": {"type": "array", "items": {"type": "string"}},
+        });
+        Tool {
+            name: "test_tool".to_string(),
+            title: None,
+            description: None,
+            input_schema: ToolSchema {
+                schema_type: "object".to_string(),
+                properties: serde_json::from_value(properties).unwrap(),
+                required: None,
+            },
+            output_schema: None,
+            annotations: None,
+            _meta: None,
+        }
+    }
+
+    fn parse_with_stdin(args: Vec<String>, stdin: &[u8]) -> Result<serde_json::Value> {
+        let output = create_test_output();
+        let result = parse_command_line_arguments_with_stdin(
+            args,
+            &create_test_tool(),
+            &output,
+            &mut Cursor::new(stdin),
+        )?;
+        Ok(serde_json::to_value(result).unwrap())
+    }
+
     #[test]
     fn test_empty_args() {
         let output = create_test_output();
-        let result = parse_command_line_arguments(vec![], &output).unwrap();
+        let result = parse_command_line_arguments(vec![], &create_test_tool(), &output).unwrap();
         assert!(result.is_none());
     }
 
@@ -69,7 +357,7 @@ mod tests {
     fn test_string_argument() {
         let output = create_test_output();
         let args = vec!["name=test".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -88,7 +376,7 @@ mod tests {
     fn test_boolean_arguments() {
         let output = create_test_output();
         let args = vec!["flag1=true".to_string(), "flag2=false".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -105,7 +393,7 @@ mod tests {
     fn test_integer_argument() {
         let output = create_test_output();
         let args = vec!["count=42".to_string(), "negative=-10".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -128,7 +416,7 @@ mod tests {
     fn test_float_argument() {
         let output = create_test_output();
         let args = vec!["rate=8.14".to_string(), "zero=0.0".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -156,7 +444,7 @@ mod tests {
             "count=100".to_string(),
             "rate=2.5".to_string(),
         ];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -184,7 +472,7 @@ mod tests {
     fn test_string_with_equals() {
         let output = create_test_output();
         let args = vec!["url=https://example.com/path?param=value".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -203,7 +491,7 @@ mod tests {
     fn test_empty_value() {
         let output = create_test_output();
         let args = vec!["empty=".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -223,7 +511,7 @@ mod tests {
         let output = create_test_output();
         // Numbers that should be parsed as strings due to formatting
         let args = vec!["version=1.0.0".to_string(), "id=00123".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -246,7 +534,7 @@ mod tests {
     fn test_invalid_format_no_equals() {
         let output = create_test_output();
         let args = vec!["invalid_arg".to_string()];
-        let result = parse_command_line_arguments(args, &output);
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output);
 
         assert!(result.is_err());
         assert!(
@@ -261,7 +549,7 @@ mod tests {
     fn test_invalid_format_only_key() {
         let output = create_test_output();
         let args = vec!["key=".to_string(), "another_key".to_string()];
-        let result = parse_command_line_arguments(args, &output);
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output);
 
         assert!(result.is_err());
         assert!(
@@ -276,7 +564,7 @@ mod tests {
     fn test_key_override() {
         let output = create_test_output();
         let args = vec!["key=first".to_string(), "key=second".to_string()];
-        let result = parse_command_line_arguments(args, &output)
+        let result = parse_command_line_arguments(args, &create_test_tool(), &output)
             .unwrap()
             .unwrap();
 
@@ -291,4 +579,144 @@ mod tests {
             &serde_json::Value::String("second".to_string())
         );
     }
+
+    #[test]
+    fn test_file_argument() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        fs::write(&path, [0xff, 0x00, 0x01]).unwrap();
+        let text_path = dir.path().join("notes.txt");
+        fs::write(&text_path, "line one\nline two\n").unwrap();
+
+        let args = vec![
+            format!("body=@{}", text_path.display()),
+            format!("data=@{}", path.display()),
+        ];
+        let map = parse_with_stdin(args, b"").unwrap();
+        assert_eq!(map["body"], "line one\nline two\n");
+        assert_eq!(map["data"], STANDARD.encode([0xff, 0x00, 0x01]));
+
+        // Binary data needs a base64 parameter
+        let args = vec![format!("body=@{}", path.display())];
+        let err = parse_with_stdin(args, b"").unwrap_err();
+        assert!(err.to_string().contains("not UTF-8"));
+    }
+
+    #[test]
+    fn test_stdin_argument() {
+        let map = parse_with_stdin(vec!["body=@-".to_string()], b"from stdin").unwrap();
+        assert_eq!(map["body"], "from stdin");
+
+        let map = parse_with_stdin(vec!["data=@-".to_string()], b"\x00\x01").unwrap();
+        assert_eq!(map["data"], "AAE=");
+
+        let args = vec!["body=@-".to_string(), "data=@-".to_string()];
+        assert!(parse_with_stdin(args, b"x").is_err());
+    }
+
+    #[test]
+    fn test_file_argument_errors() {
+        let err = parse_with_stdin(vec!["body=@/nonexistent/notes.txt".to_string()], b"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/nonexistent/notes.txt"));
+        assert!(err.contains("'body'"));
+
+        let map = parse_with_stdin(vec!["user=@@alice".to_string()], b"").unwrap();
+        assert_eq!(map["user"], "@alice");
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        let args = [
+            "user.name=alice",
+            "user.age=30",
+            "tags[]=a",
+            "tags[]=007",
+            "extra.list[]=1",
+            "extra.list[]=x",
+            "file.name=notes.txt",
+        ];
+        let map = parse_with_stdin(args.map(String::from).to_vec(), b"").unwrap();
+        assert_eq!(
+            map,
+            json!({
+                "user": {"name": "alice", "age": 30},
+                "tags": ["a", "007"],
+                "extra": {"list": [1, "x"]},
+                "file.name": "notes.txt",
+            })
+        );
+    }
+
+    #[test]
+    fn test_dotted_key_errors() {
+        for args in [
+            vec!["user=1", "user.name=alice"],
+            vec!["tags[]=a", "tags.first=b"],
+        ] {
+            let args = args.into_iter().map(String::from).collect();
+            let err = parse_with_stdin(args, b"").unwrap_err().to_string();
+            assert!(err.contains("conflicts with an earlier argument"));
+        }
+        for key in ["a..b", "a[].b", ""] {
+            let err = parse_with_stdin(vec![format!("{key}=1")], b"")
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("Invalid argument key"));
+        }
+    }
+
+    #[test]
+    fn test_schema_coercion() {
+        let args = ["label=123", "body=true", "user.age=abc", "tags[]=1.5"];
+        let map = parse_with_stdin(args.map(String::from).to_vec(), b"").unwrap();
+        assert_eq!(
+            map,
+            json!({
+                "label": "123",
+                "body": "true",
+                "user": {"age": "abc"},
+                "tags": ["1.5"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_raw_json_arguments() {
+        let args = [
+            "user:={\"name\": \"alice\", \"age\": 30}",
+            "tags:=[\"a\", \"b\"]",
+            "label:=null",
+            "count:=7.5",
+            "extra.flag:=true",
+            "body=42",
+        ];
+        let map = parse_with_stdin(args.map(String::from).to_vec(), b"").unwrap();
+        assert_eq!(
+            map,
+            json!({
+                "user": {"name": "alice", "age": 30},
+                "tags": ["a", "b"],
+                "label": null,
+                "count": 7.5,
+                "extra": {"flag": true},
+                "body": "42",
+            })
+        );
+
+        let map = parse_with_stdin(vec!["user:=@-".to_string()], b"{\"name\": \"bob\"}").unwrap();
+        assert_eq!(map["user"], json!({"name": "bob"}));
+
+        let err = parse_with_stdin(vec!["tags:=[1,".to_string()], b"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid JSON for argument 'tags'"));
+    }
+
+    #[test]
+    fn test_json_argument() {
+        assert_eq!(json_argument("a={\"b\": 1}".to_string()), "a:={\"b\": 1}");
+        assert_eq!(json_argument("novalue".to_string()), "novalue");
+    }
 }
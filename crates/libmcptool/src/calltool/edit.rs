@@ -0,0 +1,268 @@
+use std::{collections::HashMap, env, fs, path::PathBuf, process};
+
+use serde_json::Value;
+use tempfile::NamedTempFile;
+use tmcp::{Arguments, schema::Tool};
+
+use crate::{Error, Result, output::Output};
+
+/// Opens a JSON5 skeleton of the tool's arguments in the user's editor, and
+/// parses the saved file as the arguments.
+pub fn edit_arguments(tool: &Tool, output: &Output) -> Result<Option<Arguments>> {
+    let file = tempfile::Builder::new()
+        .prefix("mcptool-")
+        .suffix(".json5")
+        .tempfile()
+        .map_err(|e| Error::Other(format!("Failed to create a file to edit: {e}")))?;
+    let path = file.path().to_path_buf();
+    fs::write(&path, skeleton(tool))
+        .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let words = shell_words::split(&editor)
+        .map_err(|e| Error::Other(format!("Invalid editor command '{editor}': {e}")))?;
+    let (program, args) = words
+        .split_first()
+        .ok_or_else(|| Error::Other("The editor command is empty".to_string()))?;
+    output.text(format!("Editing arguments with {editor}..."))?;
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(&path)
+        .status()
+        .map_err(|e| Error::Other(format!("Failed to run editor '{editor}': {e}")))?;
+    if !status.success() {
+        return Err(Error::Other(format!(
+            "Editor '{editor}' exited with {status}, call cancelled. Arguments were left in {}",
+            keep(file)?.display()
+        )));
+    }
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+    let arguments = match parse_edited(&contents) {
+        Ok(arguments) => arguments,
+        Err(e) => {
+            return Err(Error::Other(format!(
+                "{e}. Arguments were left in {}",
+                keep(file)?.display()
+            )));
+        }
+    };
+    output.trace_info(format!("Edited arguments: {:?}", arguments))?;
+    Ok(Some(arguments))
+}
+
+/// Keeps the edited file once the call has given up on it, so the edits
+/// aren't lost, and returns its path.
+fn keep(file: NamedTempFile) -> Result<PathBuf> {
+    file.into_temp_path()
+        .keep()
+        .map_err(|e| Error::Other(format!("Failed to keep the edited arguments: {e}")))
+}
+
+/// A JSON5 document with a placeholder for each parameter, and its
+/// description as a comment. Optional parameters are commented out.
+fn skeleton(tool: &Tool) -> String {
+    let mut text = format!(
+        "// Arguments for {}. Uncomment optional parameters to send them.\n\
+         // Save and quit to call the tool, or delete everything to cancel.\n",
+        tool.name
+    );
+    let required = tool.input_schema.required.clone().unwrap_or_default();
+    let properties = tool.input_schema.properties.as_ref();
+    text.push_str(&object(properties.into_iter().flatten(), &required, 0));
+    text.push('\n');
+    text
+}
+
+/// The skeleton of an object, indented by `depth` levels.
+fn object<'a>(
+    properties: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    required: &[String],
+    depth: usize,
+) -> String {
+    let mut properties: Vec<_> = properties.into_iter().collect();
+    if properties.is_empty() {
+        return "{}".to_string();
+    }
+    properties.sort_by_key(|(name, _)| *name);
+
+    let pad = "  ".repeat(depth + 1);
+    let mut text = "{\n".to_string();
+    for (name, schema) in properties {
+        if let Some(description) = schema.get("description").and_then(Value::as_str) {
+            for line in description.lines() {
+                text.push_str(&format!("{pad}// {line}\n"));
+            }
+        }
+        if let Some(choices) = schema.get("enum").and_then(Value::as_array) {
+            let choices: Vec<String> = choices.iter().map(Value::to_string).collect();
+            text.push_str(&format!("{pad}// One of: {}\n", choices.join(", ")));
+        }
+        let entry = format!("\"{name}\": {},", placeholder(schema, depth + 1));
+        let comment = if required.contains(name) { "" } else { "// " };
+        // Nested lines are already indented, so only the first needs the padding
+        for (i, line) in entry.lines().enumerate() {
+            let (indent, line) = if i == 0 {
+                (pad.as_str(), line)
+            } else {
+                line.split_at(line.len() - line.trim_start().len())
+            };
+            text.push_str(&format!("{indent}{comment}{line}\n"));
+        }
+    }
+    text.push_str(&"  ".repeat(depth));
+    text.push('}');
+    text
+}
+
+/// A placeholder value for a parameter: its default, its first enum value, or
+/// an empty value of its type.
+fn placeholder(schema: &Value, depth: usize) -> String {
+    if let Some(value) = schema
+        .get("default")
+        .or_else(|| schema.get("enum").and_then(Value::as_array)?.first())
+    {
+        return value.to_string();
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => "\"\"".to_string(),
+        Some("integer" | "number") => "0".to_string(),
+        Some("boolean") => "false".to_string(),
+        Some("array") => "[]".to_string(),
+        Some("object") => {
+            let required: Vec<String> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|required| {
+                    required
+                        .iter()
+                        .filter_map(|name| name.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let properties = schema.get("properties").and_then(Value::as_object);
+            object(properties.into_iter().flatten(), &required, depth)
+        }
+        _ => "null".to_string(),
+    }
+}
+
+/// Parses the edited document, which must be a JSON5 object. A document
+/// holding nothing but comments cancels the call.
+fn parse_edited(contents: &str) -> Result<Arguments> {
+    let blank = contents
+        .lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with("//"));
+    if blank {
+        return Err(Error::Other(
+            "No arguments were saved, call cancelled".to_string(),
+        ));
+    }
+    match json5::from_str::<Value>(contents) {
+        Ok(Value::Object(map)) => {
+            let map: HashMap<String, Value> = map.into_iter().collect();
+            Ok(Arguments::from(map))
+        }
+        Ok(_) => Err(Error::Other(
+            "Edited arguments must be an object".to_string(),
+        )),
+        Err(e) => Err(Error::Other(format!(
+            "Invalid JSON5 in edited arguments: {e}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tmcp::schema::ToolSchema;
+
+    use super::*;
+
+    fn test_tool() -> Tool {
+        let properties: HashMap<String, Value> = [
+            (
+                "query".to_string(),
+                json!({"type": "string", "description": "What to search for"}),
+            ),
+            (
+                "limit".to_string(),
+                json!({"type": "integer", "default": 10}),
+            ),
+            (
+                "mode".to_string(),
+                json!({"type": "string", "enum": ["fast", "exact"]}),
+            ),
+            (
+                "filter".to_string(),
+                json!({
+                    "type": "object",
+                    "properties": {"tag": {"type": "string"}, "after": {"type": "string"}},
+                    "required": ["tag"],
+                }),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        Tool {
+            name: "search".to_string(),
+            title: None,
+            description: None,
+            input_schema: ToolSchema {
+                schema_type: "object".to_string(),
+                properties: Some(properties),
+                required: Some(vec!["query".to_string(), "filter".to_string()]),
+            },
+            output_schema: None,
+            annotations: None,
+            _meta: None,
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Value> {
+        Ok(serde_json::to_value(parse_edited(contents)?).unwrap())
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let text = skeleton(&test_tool());
+        assert!(text.contains("  // What to search for\n  \"query\": \"\",\n"));
+        assert!(text.contains("  // \"limit\": 10,\n"));
+        assert!(text.contains("  // One of: \"fast\", \"exact\"\n  // \"mode\": \"fast\",\n"));
+        assert!(text.contains("    \"tag\": \"\",\n"));
+        assert!(text.contains("    // \"after\": \"\",\n"));
+        assert_eq!(
+            parse(&text).unwrap(),
+            json!({"query": "", "filter": {"tag": ""}})
+        );
+
+        let uncommented = text.replace("// \"limit\"", "\"limit\"");
+        assert_eq!(parse(&uncommented).unwrap()["limit"], json!(10));
+    }
+
+    #[test]
+    fn test_parse_edited() {
+        assert_eq!(
+            parse("{query: 'rust', limit: 5, // trailing\n}").unwrap(),
+            json!({"query": "rust", "limit": 5})
+        );
+        let err = parse("// nothing\n\n").unwrap_err().to_string();
+        assert!(err.contains("call cancelled"));
+        assert!(
+            parse("[1, 2]")
+                .unwrap_err()
+                .to_string()
+                .contains("must be an object")
+        );
+        assert!(
+            parse("{query: }")
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid JSON5")
+        );
+    }
+}
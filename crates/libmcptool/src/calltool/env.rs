@@ -0,0 +1,219 @@
+//! Expansion of `${VAR}` environment variable references in arguments.
+
+use std::{collections::HashMap, env};
+
+use serde_json::Value;
+use tmcp::Arguments;
+
+use crate::{Error, Result};
+
+/// Replaces each `${NAME}` in `text` with the value `lookup` gives for NAME,
+/// adding names that have no value to `missing`. `$${` is a literal `${`,
+/// and anything else, such as a `$` not followed by `{NAME}`, is left alone.
+fn expand(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    missing: &mut Vec<String>,
+) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            expanded.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let reference = after
+            .strip_prefix('{')
+            .and_then(|body| body.split_once('}'))
+            .filter(|(name, _)| is_name(name));
+        match reference {
+            Some((name, remainder)) => {
+                match lookup(name) {
+                    Some(value) => expanded.push_str(&value),
+                    None => {
+                        if !missing.iter().any(|m| m == name) {
+                            missing.push(name.to_string());
+                        }
+                    }
+                }
+                rest = remainder;
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Whether a string is a valid environment variable name.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Expands every string inside a JSON value, leaving keys alone.
+fn expand_value(
+    value: Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+    missing: &mut Vec<String>,
+) -> Value {
+    match value {
+        Value::String(text) => Value::String(expand(&text, lookup, missing)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| expand_value(item, lookup, missing))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, item)| (key, expand_value(item, lookup, missing)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// The error for references to variables that aren't set, naming all of them
+/// so they can be fixed in one go.
+fn unset_error(missing: &[String]) -> Error {
+    Error::Format(format!(
+        "Environment variables not set: {}",
+        missing.join(", ")
+    ))
+}
+
+/// Expands `${VAR}` references in `key=value` arguments before they are
+/// parsed, so expanded values are coerced to their parameter's type.
+pub fn expand_args(args: Vec<String>) -> Result<Vec<String>> {
+    expand_args_with(args, &|name| env::var(name).ok())
+}
+
+/// Expands arguments with a custom variable lookup.
+fn expand_args_with(
+    args: Vec<String>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    let expanded = args
+        .into_iter()
+        .map(|arg| expand(&arg, lookup, &mut missing))
+        .collect();
+    if missing.is_empty() {
+        Ok(expanded)
+    } else {
+        Err(unset_error(&missing))
+    }
+}
+
+/// Expands `${VAR}` references in the string values of parsed arguments, as
+/// read from a JSON or YAML document.
+pub fn expand_arguments(arguments: Option<Arguments>) -> Result<Option<Arguments>> {
+    expand_arguments_with(arguments, &|name| env::var(name).ok())
+}
+
+/// Expands parsed arguments with a custom variable lookup.
+fn expand_arguments_with(
+    arguments: Option<Arguments>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<Option<Arguments>> {
+    let Some(arguments) = arguments else {
+        return Ok(None);
+    };
+    let mut missing = Vec::new();
+    let value = expand_value(serde_json::to_value(arguments)?, lookup, &mut missing);
+    if !missing.is_empty() {
+        return Err(unset_error(&missing));
+    }
+    let map: HashMap<String, Value> = serde_json::from_value(value)?;
+    Ok(Some(Arguments::from(map)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A lookup with a fixed set of variables.
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "API_TOKEN" => Some("s3cret".to_string()),
+            "COUNT" => Some("3".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut missing = Vec::new();
+        assert_eq!(
+            expand("token=${API_TOKEN}", &lookup, &mut missing),
+            "token=s3cret"
+        );
+        assert_eq!(
+            expand(
+                "cost=$5 ${COUNT}x ${not-a-name} $${API_TOKEN}",
+                &lookup,
+                &mut missing
+            ),
+            "cost=$5 3x ${not-a-name} ${API_TOKEN}"
+        );
+        assert!(missing.is_empty());
+
+        expand("${A}${B}${A}", &lookup, &mut missing);
+        assert_eq!(missing, ["A", "B"]);
+    }
+
+    #[test]
+    fn test_expand_args() {
+        let args = vec![
+            "token=${API_TOKEN}".to_string(),
+            "count=${COUNT}".to_string(),
+        ];
+        assert_eq!(
+            expand_args_with(args, &lookup).unwrap(),
+            ["token=s3cret", "count=3"]
+        );
+
+        let err = expand_args_with(
+            vec![
+                "a=${MISSING_ONE}".to_string(),
+                "b=${MISSING_TWO}".to_string(),
+            ],
+            &lookup,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("MISSING_ONE, MISSING_TWO"));
+    }
+
+    #[test]
+    fn test_expand_arguments() {
+        let arguments = Arguments::from(HashMap::from([
+            (
+                "headers".to_string(),
+                json!({"auth": "Bearer ${API_TOKEN}", "retries": 2}),
+            ),
+            ("tags".to_string(), json!(["${COUNT}"])),
+        ]));
+        let expanded = expand_arguments_with(Some(arguments), &lookup)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(expanded).unwrap(),
+            json!({"headers": {"auth": "Bearer s3cret", "retries": 2}, "tags": ["3"]})
+        );
+        assert!(expand_arguments_with(None, &lookup).unwrap().is_none());
+    }
+}
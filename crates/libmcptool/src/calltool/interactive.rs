@@ -1,17 +1,93 @@
 use std::{
     collections::HashMap,
     io::{self, BufRead, Write},
+    result,
 };
 
-use tmcp::{Arguments, schema::Tool};
+use serde_json::{Map, Number, Value, json};
+use tmcp::{
+    Arguments,
+    schema::{Prompt, Tool},
+};
 
 use crate::{Result, output::Output};
 
+/// Values the server suggests for parameters, by parameter name, offered
+/// when the user types `?` at a prompt.
+pub type Suggestions = HashMap<String, Vec<String>>;
+
 /// Parses tool arguments interactively by prompting the user.
 pub fn parse_interactive_arguments(tool: &Tool, output: &Output) -> Result<Option<Arguments>> {
     parse_interactive_arguments_with_io(tool, output, &mut io::stdin().lock(), &mut io::stdout())
 }
 
+/// Asks whether to go ahead with calling a tool that is marked destructive.
+pub fn confirm_destructive(tool: &Tool) -> Result<bool> {
+    confirm_destructive_with_io(tool, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+/// Asks whether to call a destructive tool, using the given reader and
+/// writer.
+pub fn confirm_destructive_with_io<R: BufRead, W: Write>(
+    tool: &Tool,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<bool> {
+    confirm(
+        reader,
+        writer,
+        &format!(
+            "Tool '{}' may make destructive changes. Call it?",
+            tool.name
+        ),
+    )
+}
+
+/// Parses prompt arguments interactively, offering the server's completions
+/// as suggestions.
+pub fn parse_prompt_arguments(
+    prompt: &Prompt,
+    suggestions: &Suggestions,
+    output: &Output,
+) -> Result<Option<Arguments>> {
+    parse_prompt_arguments_with_io(
+        prompt,
+        suggestions,
+        output,
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+    )
+}
+
+/// Internal implementation that accepts custom readers/writers for testing.
+fn parse_prompt_arguments_with_io<R: BufRead, W: Write>(
+    prompt: &Prompt,
+    suggestions: &Suggestions,
+    output: &Output,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Option<Arguments>> {
+    output.text("Interactive mode: Enter prompt arguments")?;
+    // Prompt arguments are always strings, so describe them as a schema
+    let mut properties = HashMap::new();
+    let mut required = Vec::new();
+    for argument in prompt.arguments.iter().flatten() {
+        let mut schema = json!({"type": "string"});
+        if let Some(description) = &argument.description {
+            schema["description"] = Value::String(description.clone());
+        }
+        properties.insert(argument.name.clone(), schema);
+        if argument.required == Some(true) {
+            required.push(argument.name.clone());
+        }
+    }
+    if properties.is_empty() {
+        output.text("No arguments required for this prompt")?;
+        return Ok(None);
+    }
+    collect(&properties, &required, suggestions, output, reader, writer)
+}
+
 /// Internal implementation that accepts custom readers/writers for testing.
 fn parse_interactive_arguments_with_io<R: BufRead, W: Write>(
     tool: &Tool,
@@ -30,118 +106,422 @@ fn parse_interactive_arguments_with_io<R: BufRead, W: Write>(
         return Ok(None);
     }
 
-    let properties = properties.unwrap();
-    let mut arg_map = HashMap::new();
+    // The protocol only offers completions for prompts and resource templates,
+    // so there are no server suggestions for tool parameters
+    collect(
+        properties.unwrap(),
+        required,
+        &Suggestions::new(),
+        output,
+        reader,
+        writer,
+    )
+}
+
+/// Prompts for every parameter, then lets the user review the arguments.
+fn collect<R: BufRead, W: Write>(
+    properties: &HashMap<String, Value>,
+    required: &[String],
+    suggestions: &Suggestions,
+    output: &Output,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Option<Arguments>> {
+    let mut arg_map = prompt_properties(None, properties, required, suggestions, reader, writer)?;
+    if arg_map.is_empty() {
+        return Ok(None);
+    }
+    review(
+        &mut arg_map,
+        properties,
+        required,
+        suggestions,
+        reader,
+        writer,
+    )?;
 
+    output.trace_info(format!("Interactive arguments: {:?}", arg_map))?;
+    let arg_map: HashMap<String, Value> = arg_map.into_iter().collect();
+    Ok(Some(Arguments::from(arg_map)))
+}
+
+/// Prompts for each of an object's properties in turn. Nested properties are
+/// named by their dotted path from `prefix`.
+fn prompt_properties<'a, R: BufRead, W: Write>(
+    prefix: Option<&str>,
+    properties: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    required: &[String],
+    suggestions: &Suggestions,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Map<String, Value>> {
     // Sort parameters by name for deterministic order in tests
-    let mut sorted_params: Vec<_> = properties.iter().collect();
+    let mut sorted_params: Vec<_> = properties.into_iter().collect();
     sorted_params.sort_by_key(|(name, _)| *name);
 
+    let mut values = Map::new();
     for (param_name, param_schema) in sorted_params {
         let is_required = required.contains(param_name);
-        let param_type = param_schema
-            .get("type")
-            .and_then(|t| t.as_str())
-            .unwrap_or("string");
-        let description = param_schema
-            .get("description")
-            .and_then(|d| d.as_str())
-            .unwrap_or("");
-
-        let prompt = if is_required {
-            format!("{} ({})*: {}", param_name, param_type, description)
-        } else {
-            format!(
-                "{} ({}) [optional]: {}",
-                param_name, param_type, description
-            )
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{param_name}"),
+            None => param_name.clone(),
         };
+        if let Some(value) = prompt_value(
+            &path,
+            param_schema,
+            is_required,
+            suggestions,
+            reader,
+            writer,
+        )? {
+            values.insert(param_name.clone(), value);
+        }
+    }
+    Ok(values)
+}
 
-        loop {
-            writeln!(writer, "{}", prompt)
-                .map_err(|e| crate::Error::Other(format!("Failed to write prompt: {}", e)))?;
-            write!(writer, "> ")
-                .map_err(|e| crate::Error::Other(format!("Failed to write prompt: {}", e)))?;
-            writer
-                .flush()
-                .map_err(|e| crate::Error::Other(format!("Failed to flush stdout: {}", e)))?;
+/// Shows the collected arguments and asks whether to send them, letting the
+/// user re-enter any top-level parameter first.
+fn review<R: BufRead, W: Write>(
+    arguments: &mut Map<String, Value>,
+    properties: &HashMap<String, Value>,
+    required: &[String],
+    suggestions: &Suggestions,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<()> {
+    let mut names: Vec<_> = properties.keys().map(String::as_str).collect();
+    names.sort();
+    loop {
+        let json = serde_json::to_string_pretty(&*arguments)?;
+        write_line(writer, &format!("Arguments:\n{json}"))?;
+        let answer = ask(reader, writer, "Send these arguments? [Y/n/e to edit] > ")?;
+        match answer.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("" | "y" | "yes") => return Ok(()),
+            Some("n" | "no") => return Err(crate::Error::Other("Tool call cancelled".to_string())),
+            Some("e" | "edit") => {
+                let prompt = format!("Parameter to edit ({}) > ", names.join(", "));
+                let Some(name) = ask(reader, writer, &prompt)? else {
+                    return Ok(());
+                };
+                let Some(schema) = properties.get(name.as_str()) else {
+                    write_line(writer, &format!("Unknown parameter '{name}'."))?;
+                    continue;
+                };
+                let is_required = required.contains(&name);
+                match prompt_value(&name, schema, is_required, suggestions, reader, writer)? {
+                    Some(value) => arguments.insert(name, value),
+                    None => arguments.remove(&name),
+                };
+            }
+            _ => write_line(writer, "Answer y, n or e.")?,
+        }
+    }
+}
+
+/// Prompts for a parameter until a valid value is entered. Enums are offered
+/// as a numbered menu, booleans as a y/n question, and an empty answer takes
+/// the schema's default. Objects are entered property by property, and arrays
+/// item by item. If the server suggested values, `?` lists them, and `?text`
+/// the ones starting with `text`, to pick by number. Returns `None` if an
+/// optional parameter is skipped.
+fn prompt_value<R: BufRead, W: Write>(
+    name: &str,
+    schema: &Value,
+    is_required: bool,
+    suggestions: &Suggestions,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Option<Value>> {
+    let param_type = schema
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("string");
+    match param_type {
+        "object" if schema.get("properties").is_some() => {
+            return prompt_object(name, schema, is_required, suggestions, reader, writer);
+        }
+        "array" => return prompt_array(name, schema, is_required, suggestions, reader, writer),
+        _ => {}
+    }
+    let choices = schema.get("enum").and_then(Value::as_array);
+    let default = schema.get("default");
+    let suggested = suggestions.get(name).filter(|_| choices.is_none());
 
-            let mut input = String::new();
-            reader
-                .read_line(&mut input)
-                .map_err(|e| crate::Error::Other(format!("Failed to read input: {}", e)))?;
+    let mut prompt = heading(name, param_type, schema, is_required);
+    if let Some(choices) = choices {
+        for (i, choice) in choices.iter().enumerate() {
+            prompt.push_str(&format!("\n  {}) {}", i + 1, display(choice)));
+        }
+    }
+    let hint = match (param_type, default.and_then(Value::as_bool)) {
+        ("boolean", Some(true)) => "[Y/n] ".to_string(),
+        ("boolean", Some(false)) => "[y/N] ".to_string(),
+        ("boolean", None) => "[y/n] ".to_string(),
+        _ => default
+            .map(|default| format!("[default: {}] ", display(default)))
+            .unwrap_or_default(),
+    };
+    if suggested.is_some() {
+        prompt.push_str(" (? for suggestions)");
+    }
 
-            let input = input.trim();
+    let mut listed: Vec<&String> = Vec::new();
+    loop {
+        write_line(writer, &prompt)?;
+        let input = ask(reader, writer, &format!("{hint}> "))?;
+        let ended = input.is_none();
+        let input = input.unwrap_or_default();
 
+        if input.is_empty() {
+            if let Some(default) = default {
+                return Ok(Some(default.clone()));
+            }
             // Skip optional empty parameters
-            if input.is_empty() && !is_required {
-                break;
+            if !is_required {
+                return Ok(None);
+            }
+            if ended {
+                return Err(crate::Error::Other(format!(
+                    "Input ended before a value for {name} was entered"
+                )));
             }
+            write_line(writer, "This parameter is required. Please enter a value.")?;
+            continue;
+        }
 
-            // Require input for required parameters
-            if input.is_empty() && is_required {
-                writeln!(writer, "This parameter is required. Please enter a value.")
-                    .map_err(|e| crate::Error::Other(format!("Failed to write error: {}", e)))?;
-                continue;
+        if let Some(suggested) = suggested
+            && let Some(prefix) = input.strip_prefix('?')
+        {
+            listed = suggested
+                .iter()
+                .filter(|value| value.starts_with(prefix))
+                .collect();
+            if listed.is_empty() {
+                write_line(writer, "No suggestions match.")?;
+            }
+            for (i, value) in listed.iter().enumerate() {
+                write_line(writer, &format!("  {}) {value}", i + 1))?;
             }
+            continue;
+        }
+        let picked = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|i| listed.get(i));
+        let input = picked.map_or(input.as_str(), |value| value.as_str());
 
-            // Parse input based on expected type
-            let json_value = match param_type {
-                "boolean" => match input.to_lowercase().as_str() {
-                    "true" | "t" | "yes" | "y" | "1" => serde_json::Value::Bool(true),
-                    "false" | "f" | "no" | "n" | "0" => serde_json::Value::Bool(false),
-                    _ => {
-                        writeln!(
-                            writer,
-                            "Invalid boolean value. Use true/false, yes/no, or 1/0."
-                        )
-                        .map_err(|e| {
-                            crate::Error::Other(format!("Failed to write error: {}", e))
-                        })?;
-                        continue;
-                    }
-                },
-                "integer" => match input.parse::<i64>() {
-                    Ok(num) => serde_json::Value::Number(serde_json::Number::from(num)),
-                    Err(_) => {
-                        writeln!(writer, "Invalid integer value.").map_err(|e| {
-                            crate::Error::Other(format!("Failed to write error: {}", e))
-                        })?;
-                        continue;
-                    }
-                },
-                "number" => match input.parse::<f64>() {
-                    Ok(num) => {
-                        serde_json::Value::Number(serde_json::Number::from_f64(num).unwrap())
-                    }
-                    Err(_) => {
-                        writeln!(writer, "Invalid number value.").map_err(|e| {
-                            crate::Error::Other(format!("Failed to write error: {}", e))
-                        })?;
-                        continue;
-                    }
-                },
-                _ => serde_json::Value::String(input.to_string()),
-            };
+        let parsed = match choices {
+            Some(choices) => choose(choices, input),
+            None => parse_scalar(param_type, input),
+        };
+        match parsed {
+            Ok(value) => return Ok(Some(value)),
+            Err(message) => write_line(writer, &message)?,
+        }
+    }
+}
+
+/// Prompts for the properties of an object parameter. An optional object is
+/// only entered if the user asks to.
+fn prompt_object<R: BufRead, W: Write>(
+    name: &str,
+    schema: &Value,
+    is_required: bool,
+    suggestions: &Suggestions,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Option<Value>> {
+    write_line(writer, &heading(name, "object", schema, is_required))?;
+    if !is_required && !confirm(reader, writer, &format!("Enter {name}?"))? {
+        return Ok(None);
+    }
+    let required: Vec<String> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| {
+            required
+                .iter()
+                .filter_map(|name| name.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let values = prompt_properties(
+        Some(name),
+        properties.into_iter().flatten(),
+        &required,
+        suggestions,
+        reader,
+        writer,
+    )?;
+    Ok(Some(Value::Object(values)))
+}
+
+/// Prompts for the items of an array parameter until an empty item is
+/// entered, or for object items, until the user doesn't want another one.
+fn prompt_array<R: BufRead, W: Write>(
+    name: &str,
+    schema: &Value,
+    is_required: bool,
+    suggestions: &Suggestions,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<Option<Value>> {
+    write_line(writer, &heading(name, "array", schema, is_required))?;
+    let mut items = schema.get("items").cloned().unwrap_or_else(|| json!({}));
+    // An empty item ends the array, so it can't also mean the default
+    if let Some(items) = items.as_object_mut() {
+        items.remove("default");
+    }
+    let object_items = items.get("type").and_then(Value::as_str) == Some("object")
+        && items.get("properties").is_some();
+    if !object_items {
+        write_line(
+            writer,
+            "Enter one item at a time, and an empty line to finish.",
+        )?;
+    }
+
+    let mut values = Vec::new();
+    loop {
+        let item_name = format!("{name}[{}]", values.len());
+        let value = if object_items {
+            if !confirm(reader, writer, &format!("Add an item to {name}?"))? {
+                break;
+            }
+            prompt_object(&item_name, &items, true, suggestions, reader, writer)?
+        } else {
+            prompt_value(&item_name, &items, false, suggestions, reader, writer)?
+        };
+        match value {
+            Some(value) => values.push(value),
+            None => break,
+        }
+    }
 
-            arg_map.insert(param_name.clone(), json_value);
-            break;
+    if values.is_empty() {
+        if let Some(default) = schema.get("default") {
+            return Ok(Some(default.clone()));
+        }
+        if !is_required {
+            return Ok(None);
         }
     }
+    Ok(Some(Value::Array(values)))
+}
 
-    if arg_map.is_empty() {
-        Ok(None)
+/// The line introducing a parameter: its name, type, whether it is required,
+/// and its description.
+fn heading(name: &str, param_type: &str, schema: &Value, is_required: bool) -> String {
+    let description = schema
+        .get("description")
+        .and_then(|d| d.as_str())
+        .unwrap_or("");
+    if is_required {
+        format!("{} ({})*: {}", name, param_type, description)
     } else {
-        output.trace_info(format!("Interactive arguments: {:?}", arg_map))?;
-        Ok(Some(Arguments::from(arg_map)))
+        format!("{} ({}) [optional]: {}", name, param_type, description)
+    }
+}
+
+/// Asks a yes/no question, where an empty answer means no.
+fn confirm<R: BufRead, W: Write>(reader: &mut R, writer: &mut W, question: &str) -> Result<bool> {
+    loop {
+        let answer = ask(reader, writer, &format!("{question} [y/N] > "))?.unwrap_or_default();
+        if answer.is_empty() {
+            return Ok(false);
+        }
+        match parse_scalar("boolean", &answer) {
+            Ok(value) => return Ok(value == Value::Bool(true)),
+            Err(message) => write_line(writer, &message)?,
+        }
     }
 }
 
+/// Writes a prompt and reads a trimmed line of input. Returns `None` at the
+/// end of input.
+fn ask<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    prompt: &str,
+) -> Result<Option<String>> {
+    write!(writer, "{prompt}")
+        .map_err(|e| crate::Error::Other(format!("Failed to write prompt: {}", e)))?;
+    writer
+        .flush()
+        .map_err(|e| crate::Error::Other(format!("Failed to flush stdout: {}", e)))?;
+
+    let mut input = String::new();
+    let read = reader
+        .read_line(&mut input)
+        .map_err(|e| crate::Error::Other(format!("Failed to read input: {}", e)))?;
+    Ok((read > 0).then(|| input.trim().to_string()))
+}
+
+/// Picks an enum value, given either the value itself or its number in the
+/// menu.
+fn choose(choices: &[Value], input: &str) -> result::Result<Value, String> {
+    if let Some(choice) = choices.iter().find(|choice| display(choice) == input) {
+        return Ok(choice.clone());
+    }
+    input
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|i| choices.get(i))
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "Invalid choice. Enter a number from 1 to {} or one of the listed values.",
+                choices.len()
+            )
+        })
+}
+
+/// Parses input based on the expected type.
+fn parse_scalar(param_type: &str, input: &str) -> result::Result<Value, String> {
+    match param_type {
+        "boolean" => match input.to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+            "false" | "f" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+            _ => Err("Invalid boolean value. Use true/false, yes/no, or 1/0.".to_string()),
+        },
+        "integer" => input
+            .parse::<i64>()
+            .map(|num| Value::Number(Number::from(num)))
+            .map_err(|_| "Invalid integer value.".to_string()),
+        "number" => input
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| "Invalid number value.".to_string()),
+        _ => Ok(Value::String(input.to_string())),
+    }
+}
+
+/// Shows a value as the user would type it: strings without quotes, anything
+/// else as JSON.
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes a line of the prompt dialogue.
+fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<()> {
+    writeln!(writer, "{}", line)
+        .map_err(|e| crate::Error::Other(format!("Failed to write prompt: {}", e)))
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
-    use tmcp::schema::{Tool, ToolSchema};
+    use tmcp::schema::{PromptArgument, Tool, ToolSchema};
 
     use super::*;
     use crate::output::Output;
@@ -561,4 +941,239 @@ mod tests {
         let output_str = String::from_utf8(writer).unwrap();
         assert!(output_str.contains("This parameter is required"));
     }
+
+    fn parse_single(schema: serde_json::Value, required: bool, input: &str) -> (Value, String) {
+        let output = create_test_output();
+        let mut properties = serde_json::Map::new();
+        properties.insert("param".to_string(), schema);
+        let required = required.then(|| vec!["param".to_string()]);
+        let tool = create_test_tool(properties, required);
+        let mut reader = Cursor::new(input.to_string());
+        let mut writer = Vec::new();
+
+        let result = parse_interactive_arguments_with_io(&tool, &output, &mut reader, &mut writer)
+            .unwrap()
+            .map(|arguments| serde_json::to_value(arguments).unwrap()["param"].clone())
+            .unwrap_or(Value::Null);
+        (result, String::from_utf8(writer).unwrap())
+    }
+
+    #[test]
+    fn test_enum_menu() {
+        let schema = serde_json::json!({
+            "type": "string",
+            "enum": ["fast", "slow", "careful"],
+        });
+        let (value, prompts) = parse_single(schema.clone(), true, "2\n");
+        assert_eq!(value, serde_json::json!("slow"));
+        assert!(prompts.contains("  1) fast\n  2) slow\n  3) careful"));
+
+        let (value, _) = parse_single(schema.clone(), true, "careful\n");
+        assert_eq!(value, serde_json::json!("careful"));
+
+        let (value, prompts) = parse_single(schema, true, "4\nmedium\n1\n");
+        assert_eq!(value, serde_json::json!("fast"));
+        assert_eq!(prompts.matches("Invalid choice").count(), 2);
+
+        let (value, _) = parse_single(serde_json::json!({"enum": [10, 20]}), true, "20\n");
+        assert_eq!(value, serde_json::json!(20));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let schema = serde_json::json!({"type": "integer", "default": 5});
+        let (value, prompts) = parse_single(schema.clone(), true, "\n");
+        assert_eq!(value, serde_json::json!(5));
+        assert!(prompts.contains("[default: 5] > "));
+        assert!(!prompts.contains("required"));
+
+        let (value, _) = parse_single(schema, false, "7\n");
+        assert_eq!(value, serde_json::json!(7));
+
+        let schema = serde_json::json!({
+            "type": "string",
+            "enum": ["fast", "slow"],
+            "default": "slow",
+        });
+        let (value, prompts) = parse_single(schema, false, "\n");
+        assert_eq!(value, serde_json::json!("slow"));
+        assert!(prompts.contains("[default: slow] > "));
+    }
+
+    #[test]
+    fn test_boolean_prompt() {
+        let (value, prompts) = parse_single(
+            serde_json::json!({"type": "boolean", "default": true}),
+            true,
+            "\n",
+        );
+        assert_eq!(value, serde_json::json!(true));
+        assert!(prompts.contains("[Y/n] > "));
+
+        let (value, prompts) = parse_single(
+            serde_json::json!({"type": "boolean", "default": false}),
+            true,
+            "y\n",
+        );
+        assert_eq!(value, serde_json::json!(true));
+        assert!(prompts.contains("[y/N] > "));
+
+        let (_, prompts) = parse_single(serde_json::json!({"type": "boolean"}), true, "n\n");
+        assert!(prompts.contains("[y/n] > "));
+    }
+
+    #[test]
+    fn test_nested_object() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}},
+                },
+            },
+            "required": ["name"],
+        });
+        let (value, prompts) = parse_single(schema.clone(), true, "y\nParis\n30\nalice\n");
+        assert_eq!(
+            value,
+            serde_json::json!({"name": "alice", "age": 30, "address": {"city": "Paris"}})
+        );
+        assert!(prompts.contains("param.address.city (string) [optional]"));
+        assert!(prompts.contains("param.name (string)*"));
+
+        let (value, _) = parse_single(schema, false, "\n");
+        assert_eq!(value, Value::Null);
+    }
+
+    #[test]
+    fn test_arrays() {
+        let schema = serde_json::json!({"type": "array", "items": {"type": "integer"}});
+        let (value, prompts) = parse_single(schema.clone(), true, "1\nx\n2\n\n");
+        assert_eq!(value, serde_json::json!([1, 2]));
+        assert!(prompts.contains("param[1] (integer)"));
+        assert!(prompts.contains("Invalid integer value"));
+
+        let (value, _) = parse_single(schema.clone(), true, "\n");
+        assert_eq!(value, serde_json::json!([]));
+        let (value, _) = parse_single(schema, false, "\n");
+        assert_eq!(value, Value::Null);
+
+        let schema = serde_json::json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {"tag": {"type": "string"}},
+                "required": ["tag"],
+            },
+        });
+        let (value, _) = parse_single(schema, true, "y\na\nyes\nb\nn\n");
+        assert_eq!(value, serde_json::json!([{"tag": "a"}, {"tag": "b"}]));
+    }
+
+    #[test]
+    fn test_review() {
+        let schema = serde_json::json!({"type": "string"});
+        let (value, prompts) = parse_single(schema.clone(), true, "first\ne\nparam\nsecond\ny\n");
+        assert_eq!(value, serde_json::json!("second"));
+        assert!(prompts.contains("\"param\": \"first\""));
+        assert!(prompts.contains("Send these arguments?"));
+
+        let (value, prompts) = parse_single(schema.clone(), true, "first\ne\nbogus\ny\n");
+        assert_eq!(value, serde_json::json!("first"));
+        assert!(prompts.contains("Unknown parameter 'bogus'"));
+
+        let output = create_test_output();
+        let mut properties = serde_json::Map::new();
+        properties.insert("param".to_string(), schema);
+        let tool = create_test_tool(properties, None);
+        let mut reader = Cursor::new("value\nn\n");
+        let mut writer = Vec::new();
+        let err = parse_interactive_arguments_with_io(&tool, &output, &mut reader, &mut writer)
+            .unwrap_err();
+        assert!(err.to_string().contains("Tool call cancelled"));
+    }
+
+    #[test]
+    fn test_confirm_destructive() {
+        let tool = create_test_tool(serde_json::Map::new(), None);
+        for (input, expected) in [("y\n", true), ("n\n", false), ("\n", false), ("", false)] {
+            let mut reader = Cursor::new(input);
+            let mut writer = Vec::new();
+            assert_eq!(
+                confirm_destructive_with_io(&tool, &mut reader, &mut writer).unwrap(),
+                expected
+            );
+            assert!(
+                String::from_utf8(writer)
+                    .unwrap()
+                    .contains("destructive changes")
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_ends_early() {
+        let output = create_test_output();
+        let mut properties = serde_json::Map::new();
+        properties.insert("param".to_string(), serde_json::json!({"type": "string"}));
+        let tool = create_test_tool(properties, Some(vec!["param".to_string()]));
+        let mut reader = Cursor::new("");
+        let mut writer = Vec::new();
+        let err = parse_interactive_arguments_with_io(&tool, &output, &mut reader, &mut writer)
+            .unwrap_err();
+        assert!(err.to_string().contains("Input ended"));
+    }
+
+    #[test]
+    fn test_prompt_arguments_with_suggestions() {
+        let output = create_test_output();
+        let prompt = Prompt {
+            name: "review".to_string(),
+            title: None,
+            description: None,
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "language".to_string(),
+                    title: None,
+                    description: Some("Language of the code".to_string()),
+                    required: Some(true),
+                },
+                PromptArgument {
+                    name: "style".to_string(),
+                    title: None,
+                    description: None,
+                    required: None,
+                },
+            ]),
+            _meta: None,
+        };
+        let suggestions = Suggestions::from([(
+            "language".to_string(),
+            vec!["python".to_string(), "rust".to_string(), "ruby".to_string()],
+        )]);
+        let mut reader = Cursor::new("?r\n2\n\n");
+        let mut writer = Vec::new();
+
+        let result = parse_prompt_arguments_with_io(
+            &prompt,
+            &suggestions,
+            &output,
+            &mut reader,
+            &mut writer,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            serde_json::json!({"language": "ruby"})
+        );
+        let prompts = String::from_utf8(writer).unwrap();
+        assert!(prompts.contains("language (string)*: Language of the code (? for suggestions)"));
+        assert!(prompts.contains("  1) rust\n  2) ruby\n"));
+        assert!(!prompts.contains("python\n"));
+        assert!(prompts.contains("style (string) [optional]: \n"));
+    }
 }
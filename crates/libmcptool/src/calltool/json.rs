@@ -1,15 +1,43 @@
 use std::{
     collections::HashMap,
+    fs,
     io::{self, BufRead},
+    path::Path,
 };
 
 use tmcp::Arguments;
 
+use super::Source;
 use crate::{Result, output::Output};
 
-/// Parses JSON arguments from stdin.
-pub fn parse_json_arguments(output: &Output) -> Result<Option<Arguments>> {
-    parse_json_arguments_from_reader(io::stdin().lock(), output)
+/// Parses JSON arguments from stdin or a file.
+pub fn parse_json_arguments(source: &Source, output: &Output) -> Result<Option<Arguments>> {
+    match source {
+        Source::Stdin => parse_json_arguments_from_reader(io::stdin().lock(), output),
+        Source::File(path) => parse_json_file(path, output).map(Some),
+    }
+}
+
+/// Parses a file holding a JSON object of arguments. Errors name the file.
+fn parse_json_file(path: &Path, output: &Output) -> Result<Arguments> {
+    let display = path.display();
+    output.text(format!("Reading JSON arguments from {display}..."))?;
+    let contents = fs::read_to_string(path).map_err(|e| {
+        crate::Error::Other(format!("Failed to read JSON arguments from {display}: {e}"))
+    })?;
+    match serde_json::from_str::<serde_json::Value>(&contents) {
+        Ok(serde_json::Value::Object(map)) => {
+            output.trace_info(format!("Parsed JSON arguments: {:?}", map))?;
+            let map: HashMap<String, serde_json::Value> = map.into_iter().collect();
+            Ok(Arguments::from(map))
+        }
+        Ok(_) => Err(crate::Error::Other(format!(
+            "JSON arguments in {display} must be an object"
+        ))),
+        Err(e) => Err(crate::Error::Other(format!(
+            "Invalid JSON in {display}: {e}"
+        ))),
+    }
 }
 
 /// Internal implementation that accepts a reader for testing.
@@ -311,4 +339,30 @@ mod tests {
             &serde_json::Value::String("C:\\Users\\test".to_string())
         );
     }
+
+    #[test]
+    fn test_json_file() {
+        let output = create_test_output();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("args.json");
+
+        fs::write(&path, "{\n  \"text\": \"hello\",\n\n  \"count\": 2\n}\n").unwrap();
+        let result = parse_json_file(&path, &output).unwrap();
+        let value = serde_json::to_value(result).unwrap();
+        assert_eq!(value["text"], "hello");
+        assert_eq!(value["count"], 2);
+
+        fs::write(&path, "[1, 2]").unwrap();
+        let err = parse_json_file(&path, &output).unwrap_err().to_string();
+        assert!(err.contains("must be an object"));
+        assert!(err.contains(&path.display().to_string()));
+
+        fs::write(&path, "{\"text\": ").unwrap();
+        let err = parse_json_file(&path, &output).unwrap_err().to_string();
+        assert!(err.contains(&format!("Invalid JSON in {}", path.display())));
+
+        fs::remove_file(&path).unwrap();
+        let err = parse_json_file(&path, &output).unwrap_err().to_string();
+        assert!(err.contains(&path.display().to_string()));
+    }
 }
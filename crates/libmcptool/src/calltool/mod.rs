@@ -1,8 +1,442 @@
-//! Tool argument parsing from different sources (command line, interactive, JSON).
+//! Tool argument parsing from different sources (command line, interactive, editor, JSON,
+//! YAML).
+
+use std::{collections::HashMap, mem, path::PathBuf, time::Duration};
+
+use serde_json::{Map, Value};
+use tmcp::{
+    Arguments,
+    schema::{CallToolResult, Tool},
+};
+
+use crate::{Error, Result, expect::Expectations, output::Output, retry::CallRetry, schema};
 
 /// Command line argument parsing.
 pub mod cmdline;
+/// Argument entry in the user's editor.
+pub mod edit;
+/// Environment variable expansion in arguments.
+pub mod env;
 /// Interactive argument prompting.
 pub mod interactive;
-/// JSON argument parsing from stdin.
+/// JSON argument parsing from stdin or a file.
 pub mod json;
+/// YAML argument parsing from stdin or a file.
+pub mod yaml;
+
+/// Where an argument document is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Read from stdin.
+    Stdin,
+    /// Read from a file.
+    File(PathBuf),
+}
+
+impl Source {
+    /// Resolves a `--<format>-args [FILE]` option and its
+    /// `--<format>-file FILE` counterpart.
+    fn from_flags(args: Option<Option<PathBuf>>, file: Option<PathBuf>) -> Option<Self> {
+        match (args, file) {
+            (_, Some(path)) | (Some(Some(path)), None) => Some(Self::File(path)),
+            (Some(None), None) => Some(Self::Stdin),
+            (None, None) => None,
+        }
+    }
+}
+
+/// The format of an argument document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A JSON object.
+    Json,
+    /// A YAML mapping.
+    Yaml,
+}
+
+/// A document holding all of a tool's arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    /// How the document is written.
+    pub format: Format,
+    /// Where the document is read from.
+    pub source: Source,
+}
+
+impl Document {
+    /// Resolves the `--json-args`, `--json-file`, `--yaml-args` and
+    /// `--yaml-file` options, which are mutually exclusive.
+    pub fn from_flags(
+        json: Option<Option<PathBuf>>,
+        json_file: Option<PathBuf>,
+        yaml: Option<Option<PathBuf>>,
+        yaml_file: Option<PathBuf>,
+    ) -> Option<Self> {
+        let document = |format| move |source| Self { format, source };
+        Source::from_flags(json, json_file)
+            .map(document(Format::Json))
+            .or_else(|| Source::from_flags(yaml, yaml_file).map(document(Format::Yaml)))
+    }
+
+    /// Reads and parses the arguments.
+    pub fn parse(&self, output: &Output) -> Result<Option<Arguments>> {
+        match self.format {
+            Format::Json => json::parse_json_arguments(&self.source, output),
+            Format::Yaml => yaml::parse_yaml_arguments(&self.source, output),
+        }
+    }
+}
+
+/// How a tool's arguments are given, and how they are checked before the call.
+#[derive(Debug)]
+pub struct CallOptions {
+    /// Arguments in `key=value` form.
+    pub args: Vec<String>,
+    /// Prompt for each argument.
+    pub interactive: bool,
+    /// Write the arguments in the user's editor.
+    pub edit: bool,
+    /// A document holding the arguments.
+    pub document: Option<Document>,
+    /// Arguments already parsed from a JSON object, as entered in the REPL.
+    pub json_arguments: Option<Map<String, Value>>,
+    /// Expand `${VAR}` references to environment variables in `key=value`
+    /// arguments and argument documents.
+    pub expand_env: bool,
+    /// Check the arguments against the tool's input schema before calling it.
+    pub validate: bool,
+    /// Print the request instead of sending it.
+    pub dry_run: bool,
+    /// Fail, rather than warn, when the result doesn't match the tool's
+    /// output schema.
+    pub strict: bool,
+    /// A directory to write each content block of the result to.
+    pub save_content: Option<PathBuf>,
+    /// How many times to call the tool. Above one, latency statistics are
+    /// printed instead of the result.
+    pub repeat: u32,
+    /// How long to wait between repeated calls.
+    pub interval: Duration,
+    /// Assertions on the outcome of the call.
+    pub expect: Expectations,
+    /// Call tools marked destructive without asking first.
+    pub yes: bool,
+    /// How to retry the call if it fails.
+    pub retry: CallRetry,
+}
+
+impl CallOptions {
+    /// Checks that exactly one way of giving the arguments was chosen.
+    pub fn check_modes(&self) -> Result<()> {
+        let mode_count = [
+            !self.args.is_empty(),
+            self.interactive,
+            self.edit,
+            self.document.is_some(),
+            self.json_arguments.is_some(),
+        ]
+        .iter()
+        .filter(|&&x| x)
+        .count();
+        if mode_count == 0 {
+            return Err(Error::Other(
+                "Must specify one of: --interactive, --edit, --json-args, --json-file, --yaml-args, --yaml-file, or --arg key=value arguments"
+                    .to_string(),
+            ));
+        }
+        if mode_count > 1 {
+            return Err(Error::Other(
+                "Cannot combine --interactive, --edit, --json-args, --yaml-args, --arg and JSON object modes"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads the arguments from wherever these options say they come from,
+    /// expanding environment variables and checking them against the tool's
+    /// input schema as asked. The argument sources are taken out of the
+    /// options.
+    pub fn take_arguments(&mut self, tool: &Tool, output: &Output) -> Result<Option<Arguments>> {
+        let from_document = self.json_arguments.is_some() || self.document.is_some();
+        let args = mem::take(&mut self.args);
+        let args = if self.expand_env {
+            env::expand_args(args)?
+        } else {
+            args
+        };
+        let mut arguments = if let Some(map) = self.json_arguments.take() {
+            Some(Arguments::from(map.into_iter().collect::<HashMap<_, _>>()))
+        } else if let Some(document) = self.document.take() {
+            document.parse(output)?
+        } else if self.interactive {
+            interactive::parse_interactive_arguments(tool, output)?
+        } else if self.edit {
+            edit::edit_arguments(tool, output)?
+        } else {
+            cmdline::parse_command_line_arguments(args, tool, output)?
+        };
+        if self.expand_env && from_document {
+            arguments = env::expand_arguments(arguments)?;
+        }
+        if self.validate {
+            validate_arguments(tool, arguments.as_ref())?;
+        }
+        Ok(arguments)
+    }
+}
+
+/// The JSON-RPC request that calls a tool, as printed by `--dry-run`. The
+/// request id is left out, since the client assigns it when sending.
+pub fn call_request(tool_name: &str, arguments: Option<&Arguments>) -> Result<Value> {
+    let mut params = Map::new();
+    params.insert("name".to_string(), Value::String(tool_name.to_string()));
+    if let Some(arguments) = arguments {
+        params.insert("arguments".to_string(), serde_json::to_value(arguments)?);
+    }
+    let mut request = Map::new();
+    request.insert("jsonrpc".to_string(), Value::String("2.0".to_string()));
+    request.insert(
+        "method".to_string(),
+        Value::String("tools/call".to_string()),
+    );
+    request.insert("params".to_string(), Value::Object(params));
+    Ok(Value::Object(request))
+}
+
+/// Whether a tool's annotations say it may make destructive changes. The
+/// hint is ignored for tools that are also marked read-only.
+pub fn is_destructive(tool: &Tool) -> bool {
+    let Some(annotations) = tool
+        .annotations
+        .as_ref()
+        .and_then(|annotations| serde_json::to_value(annotations).ok())
+    else {
+        return false;
+    };
+    let hint = |name| annotations.get(name).and_then(Value::as_bool);
+    hint("destructiveHint") == Some(true) && hint("readOnlyHint") != Some(true)
+}
+
+/// Checks arguments against the tool's input schema, listing every field
+/// that doesn't match and why.
+pub fn validate_arguments(tool: &Tool, arguments: Option<&Arguments>) -> Result<()> {
+    let schema = serde_json::to_value(&tool.input_schema)?;
+    let value = match arguments {
+        Some(arguments) => serde_json::to_value(arguments)?,
+        None => Value::Object(Map::new()),
+    };
+    let violations = schema::validate(&schema, &value)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = violations
+        .iter()
+        .map(|violation| format!("  {violation}"))
+        .collect();
+    Err(Error::Format(format!(
+        "Arguments don't match the input schema of '{}':\n{}\nUse --no-validate to send them anyway",
+        tool.name,
+        details.join("\n")
+    )))
+}
+
+/// Checks a result's structured content against the tool's output schema,
+/// returning a description of each mismatch. Tools without an output schema,
+/// and error results, always pass.
+pub fn validate_result(tool: &Tool, result: &CallToolResult) -> Result<Vec<String>> {
+    let Some(output_schema) = &tool.output_schema else {
+        return Ok(Vec::new());
+    };
+    if result.is_error == Some(true) {
+        return Ok(Vec::new());
+    }
+    let Some(structured) = &result.structured_content else {
+        return Ok(vec![
+            "structuredContent is missing, but the tool declares an output schema".to_string(),
+        ]);
+    };
+    let schema = serde_json::to_value(output_schema)?;
+    let value = serde_json::to_value(structured)?;
+    Ok(schema::validate(&schema, &value)?
+        .iter()
+        .map(ToString::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tmcp::schema::ToolSchema;
+
+    use super::*;
+
+    #[test]
+    fn test_validate_arguments() {
+        let properties = serde_json::from_value(serde_json::json!({
+            "text": {"type": "string"},
+            "count": {"type": "integer"},
+        }))
+        .unwrap();
+        let tool = Tool {
+            name: "echo".to_string(),
+            title: None,
+            description: None,
+            input_schema: ToolSchema {
+                schema_type: "object".to_string(),
+                properties: Some(properties),
+                required: Some(vec!["text".to_string()]),
+            },
+            output_schema: None,
+            annotations: None,
+            _meta: None,
+        };
+        let arguments = |value: Value| {
+            Arguments::from(serde_json::from_value::<HashMap<String, Value>>(value).unwrap())
+        };
+
+        let valid = arguments(serde_json::json!({"text": "hi", "count": 2}));
+        assert!(validate_arguments(&tool, Some(&valid)).is_ok());
+
+        let invalid = arguments(serde_json::json!({"count": "two"}));
+        let err = validate_arguments(&tool, Some(&invalid))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'echo'"));
+        assert!(err.contains("text: is required"));
+        assert!(err.contains("count: expected integer, got string"));
+
+        assert!(validate_arguments(&tool, None).is_err());
+    }
+
+    #[test]
+    fn test_validate_result() {
+        let mut tool = Tool {
+            name: "weather".to_string(),
+            title: None,
+            description: None,
+            input_schema: ToolSchema {
+                schema_type: "object".to_string(),
+                properties: None,
+                required: None,
+            },
+            output_schema: None,
+            annotations: None,
+            _meta: None,
+        };
+        let mut result = CallToolResult::new();
+        assert!(validate_result(&tool, &result).unwrap().is_empty());
+
+        tool.output_schema = Some(ToolSchema {
+            schema_type: "object".to_string(),
+            properties: Some(
+                serde_json::from_value(serde_json::json!({
+                    "temperature": {"type": "number"},
+                }))
+                .unwrap(),
+            ),
+            required: Some(vec!["temperature".to_string()]),
+        });
+        let missing = validate_result(&tool, &result).unwrap();
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].contains("structuredContent is missing"));
+
+        result.structured_content = Some(serde_json::json!({"temperature": 21.5}));
+        assert!(validate_result(&tool, &result).unwrap().is_empty());
+
+        result.structured_content = Some(serde_json::json!({"temperature": "warm"}));
+        let violations = validate_result(&tool, &result).unwrap();
+        assert!(violations[0].contains("temperature: expected number, got string"));
+
+        result.is_error = Some(true);
+        assert!(validate_result(&tool, &result).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_destructive() {
+        let tool = |annotations: Value| Tool {
+            name: "delete".to_string(),
+            title: None,
+            description: None,
+            input_schema: ToolSchema {
+                schema_type: "object".to_string(),
+                properties: None,
+                required: None,
+            },
+            output_schema: None,
+            annotations: serde_json::from_value(annotations).unwrap(),
+            _meta: None,
+        };
+        assert!(is_destructive(&tool(
+            serde_json::json!({"destructiveHint": true})
+        )));
+        assert!(!is_destructive(&tool(
+            serde_json::json!({"destructiveHint": false})
+        )));
+        assert!(!is_destructive(&tool(serde_json::json!({}))));
+        assert!(!is_destructive(&tool(Value::Null)));
+        assert!(!is_destructive(&tool(
+            serde_json::json!({"destructiveHint": true, "readOnlyHint": true})
+        )));
+    }
+
+    #[test]
+    fn test_call_request() {
+        let arguments =
+            Arguments::from(HashMap::from([("count".to_string(), serde_json::json!(2))]));
+        assert_eq!(
+            call_request("echo", Some(&arguments)).unwrap(),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": {"name": "echo", "arguments": {"count": 2}}
+            })
+        );
+        assert_eq!(
+            call_request("ping", None).unwrap()["params"],
+            serde_json::json!({"name": "ping"})
+        );
+    }
+
+    #[test]
+    fn test_document_from_flags() {
+        let path = PathBuf::from("args.json");
+        assert_eq!(Document::from_flags(None, None, None, None), None);
+        assert_eq!(
+            Document::from_flags(Some(None), None, None, None),
+            Some(Document {
+                format: Format::Json,
+                source: Source::Stdin
+            })
+        );
+        assert_eq!(
+            Document::from_flags(Some(Some(path.clone())), None, None, None),
+            Some(Document {
+                format: Format::Json,
+                source: Source::File(path.clone())
+            })
+        );
+        assert_eq!(
+            Document::from_flags(None, Some(path.clone()), None, None),
+            Some(Document {
+                format: Format::Json,
+                source: Source::File(path.clone())
+            })
+        );
+        assert_eq!(
+            Document::from_flags(None, None, None, Some(path.clone())),
+            Some(Document {
+                format: Format::Yaml,
+                source: Source::File(path)
+            })
+        );
+        assert_eq!(
+            Document::from_flags(None, None, Some(None), None),
+            Some(Document {
+                format: Format::Yaml,
+                source: Source::Stdin
+            })
+        );
+    }
+}
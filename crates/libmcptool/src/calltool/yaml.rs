@@ -0,0 +1,111 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+};
+
+use tmcp::Arguments;
+
+use super::Source;
+use crate::{Result, output::Output};
+
+/// Parses YAML arguments from stdin or a file.
+pub fn parse_yaml_arguments(source: &Source, output: &Output) -> Result<Option<Arguments>> {
+    match source {
+        Source::Stdin => {
+            output.text("Reading YAML arguments from stdin...")?;
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(|e| crate::Error::Other(format!("Failed to read stdin: {}", e)))?;
+            parse_yaml(&contents, "stdin", output)
+        }
+        Source::File(path) => {
+            let display = path.display();
+            output.text(format!("Reading YAML arguments from {display}..."))?;
+            let contents = fs::read_to_string(path).map_err(|e| {
+                crate::Error::Other(format!("Failed to read YAML arguments from {display}: {e}"))
+            })?;
+            parse_yaml(&contents, &display.to_string(), output)
+        }
+    }
+}
+
+/// Parses a YAML mapping of arguments. Empty input means no arguments. `name`
+/// says where the YAML came from, for error messages.
+fn parse_yaml(contents: &str, name: &str, output: &Output) -> Result<Option<Arguments>> {
+    if contents.trim().is_empty() {
+        return Ok(None);
+    }
+    match serde_yaml::from_str::<serde_json::Value>(contents) {
+        Ok(serde_json::Value::Object(map)) => {
+            output.trace_info(format!("Parsed YAML arguments: {:?}", map))?;
+            let map: HashMap<String, serde_json::Value> = map.into_iter().collect();
+            Ok(Some(Arguments::from(map)))
+        }
+        Ok(_) => Err(crate::Error::Other(format!(
+            "YAML arguments in {name} must be a mapping"
+        ))),
+        Err(e) => Err(crate::Error::Other(format!("Invalid YAML in {name}: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::output::Output;
+
+    fn create_test_output() -> Output {
+        Output::new(false, 80)
+    }
+
+    fn parse(contents: &str) -> Result<Option<serde_json::Value>> {
+        let output = create_test_output();
+        Ok(parse_yaml(contents, "args.yaml", &output)?
+            .map(|arguments| serde_json::to_value(arguments).unwrap()))
+    }
+
+    #[test]
+    fn test_mapping() {
+        let yaml = "\
+text: |
+  first line
+  second line
+count: 3
+enabled: true
+options:
+  mode: fast
+  tags: [a, b]
+";
+        assert_eq!(
+            parse(yaml).unwrap().unwrap(),
+            json!({
+                "text": "first line\nsecond line\n",
+                "count": 3,
+                "enabled": true,
+                "options": {"mode": "fast", "tags": ["a", "b"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(parse("").unwrap().is_none());
+        assert!(parse("  \n").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_non_mapping_rejection() {
+        let err = parse("- a\n- b\n").unwrap_err().to_string();
+        assert!(err.contains("YAML arguments in args.yaml must be a mapping"));
+        assert!(parse("just a string").is_err());
+    }
+
+    #[test]
+    fn test_invalid_yaml() {
+        let err = parse("text: [unclosed\n").unwrap_err().to_string();
+        assert!(err.contains("Invalid YAML in args.yaml"));
+    }
+}
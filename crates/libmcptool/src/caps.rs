@@ -0,0 +1,391 @@
+//! A capability matrix: what a server declares against what works.
+//!
+//! The initialize result only says what a server claims. This probes each
+//! capability it could have, declared or not, and reports the two side by
+//! side, so a server that declares logging but refuses `logging/setLevel`,
+//! or serves prompts it never declared, stands out.
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tmcp::{
+    ServerAPI,
+    schema::{ArgumentInfo, LoggingLevel, PromptReference, Reference, ResourceReference},
+};
+
+use crate::{
+    Result,
+    client::{self, Connected},
+    ctx::Ctx,
+    mcp::{self, Listing},
+    output::{Output, table},
+    target::Target,
+};
+
+/// A capability, whether it's declared, and whether it worked when probed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capability {
+    /// The capability, such as "logging" or "resources.subscribe".
+    pub name: String,
+    /// Whether the server declares it, or `None` for behaviour that isn't
+    /// declared, such as pagination.
+    pub declared: Option<bool>,
+    /// Whether it worked, or `None` if it couldn't be probed.
+    pub works: Option<bool>,
+    /// What the probe found.
+    pub detail: String,
+}
+
+impl Capability {
+    /// Whether what's declared disagrees with what works.
+    pub fn mismatch(&self) -> bool {
+        matches!((self.declared, self.works), (Some(declared), Some(works)) if declared != works)
+    }
+}
+
+/// A yes/no cell in the matrix, with a dash for unknown.
+fn cell(value: Option<bool>) -> &'static str {
+    match value {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "-",
+    }
+}
+
+/// Probes a server's capabilities over one connection.
+struct Prober<'a> {
+    /// The context requests are made through.
+    ctx: &'a Ctx,
+    /// The connection to the server.
+    client: Connected<()>,
+    /// The capabilities the server declared.
+    capabilities: Value,
+    /// The matrix so far.
+    rows: Vec<Capability>,
+}
+
+impl Prober<'_> {
+    /// Records a row of the matrix.
+    fn record(
+        &mut self,
+        name: &str,
+        declared: Option<bool>,
+        works: Option<bool>,
+        detail: impl Into<String>,
+    ) {
+        self.rows.push(Capability {
+            name: name.to_string(),
+            declared,
+            works,
+            detail: detail.into(),
+        });
+    }
+
+    /// Whether the capability at a `/`-separated path is declared.
+    fn declares(&self, path: &str) -> bool {
+        self.capabilities
+            .pointer(&format!("/{path}"))
+            .is_some_and(|value| !value.is_null() && value != &json!(false))
+    }
+
+    /// Fetches the first page of each listing, recording whether it's
+    /// served, and walks the rest of those with more than one page. Returns
+    /// the items of each listing served.
+    async fn probe_listings(&mut self) -> Vec<(Listing, Vec<Value>)> {
+        let mut served = Vec::new();
+        let mut paged = Vec::new();
+        let mut broken = Vec::new();
+        for listing in Listing::ALL {
+            let declared = self.declares(listing.capability());
+            let method = listing.method();
+            let page = match mcp::list_page(&mut self.client, self.ctx, listing, None).await {
+                Ok(page) => page,
+                Err(e) => {
+                    self.record(method, Some(declared), Some(false), e.to_string());
+                    continue;
+                }
+            };
+            let items = if page.get("nextCursor").is_none_or(Value::is_null) {
+                page.get(listing.field())
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default()
+            } else {
+                match mcp::list_all(&mut self.client, self.ctx, listing).await {
+                    Ok((items, pages)) => {
+                        paged.push(format!("{method} ({pages} pages)"));
+                        items
+                    }
+                    Err(e) => {
+                        broken.push(format!("{method}: {e}"));
+                        Vec::new()
+                    }
+                }
+            };
+            self.record(
+                method,
+                Some(declared),
+                Some(true),
+                format!("{} item(s)", items.len()),
+            );
+            served.push((listing, items));
+        }
+        let (works, detail) = if !broken.is_empty() {
+            (Some(false), broken.join("; "))
+        } else if paged.is_empty() {
+            (None, "Every listing fit on one page".to_string())
+        } else {
+            (Some(true), format!("Walked {}", paged.join(", ")))
+        };
+        self.record("pagination", None, works, detail);
+        served
+    }
+
+    /// Records the list-changed capabilities. They can only be seen working
+    /// when the server's lists change, so they aren't probed.
+    fn probe_list_changed(&mut self) {
+        for capability in ["tools", "prompts", "resources"] {
+            let name = format!("{capability}.listChanged");
+            let declared = self.declares(&format!("{capability}/listChanged"));
+            self.record(
+                &name,
+                Some(declared),
+                None,
+                "Only seen when the server's list changes",
+            );
+        }
+    }
+
+    /// Sets the logging level.
+    async fn probe_logging(&mut self) {
+        let declared = self.declares("logging");
+        let result = self
+            .ctx
+            .request(self.client.set_level(LoggingLevel::Info))
+            .await;
+        let (works, detail) = match result {
+            Ok(()) => (true, "logging/setLevel succeeded".to_string()),
+            Err(e) => (false, format!("logging/setLevel failed: {e}")),
+        };
+        self.record("logging", Some(declared), Some(works), detail);
+    }
+
+    /// Asks for completions of the first prompt argument or resource
+    /// template variable there is.
+    async fn probe_completions(&mut self, served: &[(Listing, Vec<Value>)]) {
+        let declared = self.declares("completions");
+        let Some((reference, argument)) = completion_target(served) else {
+            self.record(
+                "completions",
+                Some(declared),
+                None,
+                "Nothing to complete: no prompt arguments or template variables",
+            );
+            return;
+        };
+        let describe = match &reference {
+            Reference::Prompt(prompt) => format!("prompt {}", prompt.name),
+            Reference::Resource(resource) => format!("template {}", resource.uri),
+        };
+        let info = ArgumentInfo {
+            name: argument.clone(),
+            value: String::new(),
+        };
+        let (works, detail) = match self
+            .ctx
+            .request(self.client.complete(reference, info))
+            .await
+        {
+            Ok(_) => (true, format!("Completed {argument} of {describe}")),
+            Err(e) => (
+                false,
+                format!("Completing {argument} of {describe} failed: {e}"),
+            ),
+        };
+        self.record("completions", Some(declared), Some(works), detail);
+    }
+
+    /// Subscribes to the first resource and unsubscribes again.
+    async fn probe_subscribe(&mut self, served: &[(Listing, Vec<Value>)]) {
+        let declared = self.declares("resources/subscribe");
+        let uri = served
+            .iter()
+            .filter(|(listing, _)| *listing == Listing::Resources)
+            .flat_map(|(_, items)| items)
+            .find_map(|resource| resource.get("uri")?.as_str());
+        let Some(uri) = uri else {
+            self.record(
+                "resources.subscribe",
+                Some(declared),
+                None,
+                "No resources to subscribe to",
+            );
+            return;
+        };
+        let result = match self.ctx.request(self.client.resources_subscribe(uri)).await {
+            Ok(_) => self
+                .ctx
+                .request(self.client.resources_unsubscribe(uri))
+                .await
+                .map_err(|e| format!("Unsubscribing from {uri} failed: {e}")),
+            Err(e) => Err(format!("Subscribing to {uri} failed: {e}")),
+        };
+        let (works, detail) = match result {
+            Ok(_) => (true, format!("Subscribed to and unsubscribed from {uri}")),
+            Err(detail) => (false, detail),
+        };
+        self.record("resources.subscribe", Some(declared), Some(works), detail);
+    }
+}
+
+/// Something to ask completions for: the first argument of the first prompt
+/// that has one, or else the first variable of the first resource template.
+fn completion_target(served: &[(Listing, Vec<Value>)]) -> Option<(Reference, String)> {
+    let items = |wanted: Listing| {
+        served
+            .iter()
+            .filter(move |(listing, _)| *listing == wanted)
+            .flat_map(|(_, items)| items)
+    };
+    let prompt = items(Listing::Prompts).find_map(|prompt| {
+        let name = prompt.get("name")?.as_str()?;
+        let argument = prompt
+            .get("arguments")?
+            .as_array()?
+            .first()?
+            .get("name")?
+            .as_str()?;
+        Some((
+            Reference::Prompt(PromptReference {
+                name: name.to_string(),
+                title: None,
+            }),
+            argument.to_string(),
+        ))
+    });
+    prompt.or_else(|| {
+        items(Listing::ResourceTemplates).find_map(|template| {
+            let uri = template.get("uriTemplate")?.as_str()?;
+            let start = uri.find('{')?;
+            let end = start + uri[start..].find('}')?;
+            let variable =
+                uri[start + 1..end].trim_start_matches(['+', '#', '?', '&', '/', '.', ';']);
+            let variable = variable.split([',', '*', ':']).next()?;
+            Some((
+                Reference::Resource(ResourceReference {
+                    uri: uri.to_string(),
+                }),
+                variable.to_string(),
+            ))
+        })
+    })
+}
+
+/// Connects to a target and probes every capability.
+pub async fn probe(ctx: &Ctx, target: &Target) -> Result<Vec<Capability>> {
+    let (client, init) = client::get_client(ctx, target).await?;
+    let init = serde_json::to_value(&init)?;
+    let mut prober = Prober {
+        ctx,
+        client,
+        capabilities: init.get("capabilities").cloned().unwrap_or_default(),
+        rows: Vec::new(),
+    };
+    let served = prober.probe_listings().await;
+    prober.probe_list_changed();
+    prober.probe_logging().await;
+    prober.probe_completions(&served).await;
+    prober.probe_subscribe(&served).await;
+    Ok(prober.rows)
+}
+
+/// Probes a server's capabilities and prints them as a matrix of declared
+/// against working.
+pub async fn caps_command(ctx: &Ctx, target: Target) -> Result<()> {
+    let rows = probe(ctx, &target).await?;
+    report(&ctx.output, &target, &rows)
+}
+
+/// Prints the matrix, and a warning for each capability whose declaration
+/// doesn't match what works.
+fn report(output: &Output, target: &Target, rows: &[Capability]) -> Result<()> {
+    if output.structured() {
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "capabilities": rows,
+        }))?;
+        return Ok(());
+    }
+    output.h1(format!("Capabilities of {target}"))?;
+    let header: Vec<String> = ["Capability", "Declared", "Works", "Detail"]
+        .map(str::to_string)
+        .to_vec();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            vec![
+                row.name.clone(),
+                cell(row.declared).to_string(),
+                cell(row.works).to_string(),
+                row.detail.clone(),
+            ]
+        })
+        .collect();
+    for line in table::render(&header, &cells, output.width()) {
+        output.text(line)?;
+    }
+    for row in rows.iter().filter(|row| row.mismatch()) {
+        if row.declared == Some(true) {
+            output.trace_warn(format!("{} is declared but doesn't work", row.name))?;
+        } else {
+            output.trace_warn(format!("{} works but isn't declared", row.name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_target() {
+        let prompts = (
+            Listing::Prompts,
+            vec![
+                json!({ "name": "plain" }),
+                json!({ "name": "greet", "arguments": [{ "name": "who" }] }),
+            ],
+        );
+        let templates = (
+            Listing::ResourceTemplates,
+            vec![json!({ "uriTemplate": "file:///{+path}/{name}" })],
+        );
+
+        let (reference, argument) = completion_target(&[templates.clone(), prompts]).unwrap();
+        assert!(matches!(reference, Reference::Prompt(prompt) if prompt.name == "greet"));
+        assert_eq!(argument, "who");
+
+        let (reference, argument) = completion_target(&[templates]).unwrap();
+        assert!(
+            matches!(reference, Reference::Resource(resource) if resource.uri == "file:///{+path}/{name}")
+        );
+        assert_eq!(argument, "path");
+
+        assert!(completion_target(&[]).is_none());
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let row = |declared, works| Capability {
+            name: "logging".to_string(),
+            declared,
+            works,
+            detail: String::new(),
+        };
+        assert!(row(Some(true), Some(false)).mismatch());
+        assert!(row(Some(false), Some(true)).mismatch());
+        assert!(!row(Some(true), Some(true)).mismatch());
+        assert!(!row(Some(true), None).mismatch());
+        assert!(!row(None, Some(false)).mismatch());
+    }
+}
@@ -0,0 +1,745 @@
+//! Protocol conformance checks.
+//!
+//! A suite of checks run against a live server: that initialize is
+//! answered properly, that the capabilities the server declares match what
+//! it serves, that listings paginate correctly, that bad requests are
+//! refused cleanly, that the server survives a cancelled request, and that
+//! the input and output schemas of its tools are well formed.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tmcp::{
+    ServerAPI,
+    schema::{LATEST_PROTOCOL_VERSION, LoggingLevel},
+};
+
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    ctx::Ctx,
+    frametrace::{Direction, Frame, ProtocolTrace},
+    mcp::{self, Listing},
+    negotiate::KNOWN_VERSIONS,
+    output::Output,
+    report::{CaseStatus, Report, TestCase},
+    target::Target,
+    trace,
+};
+
+/// The timeout for each request when none is given with `--timeout`, so a
+/// server that never answers fails a check instead of hanging the run.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A tool, prompt and resource name no server should have.
+const MISSING_NAME: &str = "__mcptool_check_missing__";
+
+/// A resource URI no server should have.
+const MISSING_URI: &str = "mcptool-check://missing";
+
+/// A cursor no server should have handed out.
+const INVALID_CURSOR: &str = "mcptool-check-invalid-cursor";
+
+/// The types JSON Schema knows.
+const SCHEMA_TYPES: &[&str] = &[
+    "string", "number", "integer", "boolean", "object", "array", "null",
+];
+
+/// The outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The server behaved as the protocol requires.
+    Pass,
+    /// The server broke the protocol.
+    Fail,
+    /// The server did something the protocol discourages.
+    Warn,
+    /// The check doesn't apply, usually because a capability isn't declared.
+    Skip,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "pass"),
+            Self::Fail => write!(f, "fail"),
+            Self::Warn => write!(f, "warn"),
+            Self::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+/// The result of one check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// The group the check belongs to, such as "pagination".
+    pub category: &'static str,
+    /// What was checked.
+    pub name: String,
+    /// The outcome.
+    pub status: Status,
+    /// What was found, most useful when the check didn't pass.
+    pub details: Vec<String>,
+    /// How long the check took.
+    #[serde(skip)]
+    pub duration: Duration,
+}
+
+impl CheckResult {
+    /// The check as a test case in a CI report. Warnings pass, with their
+    /// details marked as warnings.
+    fn to_case(&self) -> TestCase {
+        let (status, message) = match self.status {
+            Status::Pass | Status::Warn => (CaseStatus::Passed, None),
+            Status::Fail => (CaseStatus::Failed, self.details.first().cloned()),
+            Status::Skip => (CaseStatus::Skipped, self.details.first().cloned()),
+        };
+        let output = match self.status {
+            Status::Warn => self
+                .details
+                .iter()
+                .map(|detail| format!("Warning: {detail}"))
+                .collect(),
+            _ => self.details.clone(),
+        };
+        TestCase {
+            classname: self.category.to_string(),
+            name: self.name.clone(),
+            status,
+            message,
+            output,
+            time: self.duration,
+        }
+    }
+}
+
+/// Runs the checks over one connection, collecting their results.
+struct Checker<'a> {
+    /// The context requests are made through.
+    ctx: &'a Ctx,
+    /// The connection to the server.
+    client: Connected<()>,
+    /// The capabilities the server declared.
+    capabilities: Value,
+    /// The frames exchanged with the server, for targets whose frames can be
+    /// seen.
+    frames: Option<ProtocolTrace>,
+    /// The results so far.
+    results: Vec<CheckResult>,
+    /// When the current check started, which is when the last one was
+    /// recorded, as checks run one after another.
+    started: Instant,
+}
+
+impl Checker<'_> {
+    /// Records the result of a check.
+    fn record(
+        &mut self,
+        category: &'static str,
+        name: impl Into<String>,
+        status: Status,
+        details: Vec<String>,
+    ) {
+        self.results.push(CheckResult {
+            category,
+            name: name.into(),
+            status,
+            details,
+            duration: self.started.elapsed(),
+        });
+        self.started = Instant::now();
+    }
+
+    /// Whether the server declared a capability.
+    fn declares(&self, capability: &str) -> bool {
+        self.capabilities
+            .get(capability)
+            .is_some_and(|value| !value.is_null())
+    }
+
+    /// Fetches one page of a listing as JSON.
+    async fn page(&mut self, listing: Listing, cursor: Option<&str>) -> Result<Value> {
+        mcp::list_page(&mut self.client, self.ctx, listing, cursor).await
+    }
+
+    /// Walks every page of a listing, returning its items and the number of
+    /// pages.
+    async fn collect(&mut self, listing: Listing) -> Result<(Vec<Value>, usize)> {
+        mcp::list_all(&mut self.client, self.ctx, listing).await
+    }
+
+    /// Checks the initialize result.
+    fn check_initialize(&mut self, init: &Value) {
+        let version = init
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let (status, detail) = if version == LATEST_PROTOCOL_VERSION {
+            (Status::Pass, format!("Negotiated {version}"))
+        } else if KNOWN_VERSIONS.contains(&version) {
+            (
+                Status::Warn,
+                format!("Asked for {LATEST_PROTOCOL_VERSION}, but the server chose {version}"),
+            )
+        } else {
+            (
+                Status::Fail,
+                format!("The server chose an unknown protocol version '{version}'"),
+            )
+        };
+        self.record("initialize", "protocol version", status, vec![detail]);
+
+        let mut problems = Vec::new();
+        for field in ["name", "version"] {
+            match init.pointer(&format!("/serverInfo/{field}")) {
+                Some(Value::String(value)) if !value.is_empty() => {}
+                _ => problems.push(format!("serverInfo.{field} is missing or empty")),
+            }
+        }
+        let status = if problems.is_empty() {
+            Status::Pass
+        } else {
+            Status::Fail
+        };
+        self.record("initialize", "server info", status, problems);
+
+        let (status, details) = if init.get("capabilities").is_some_and(Value::is_object) {
+            (Status::Pass, Vec::new())
+        } else {
+            (
+                Status::Fail,
+                vec!["capabilities is not an object".to_string()],
+            )
+        };
+        self.record("initialize", "capabilities object", status, details);
+    }
+
+    /// Checks that each declared capability is served, and that listings
+    /// that aren't declared are refused.
+    async fn check_capabilities(&mut self) {
+        for listing in Listing::ALL {
+            let declared = self.declares(listing.capability());
+            let method = listing.method();
+            let (status, detail) = match (declared, self.page(listing, None).await) {
+                (true, Ok(_)) => (Status::Pass, "Declared and served".to_string()),
+                (true, Err(e)) => (Status::Fail, format!("Declared, but {method} failed: {e}")),
+                (false, Err(_)) => (Status::Pass, "Not declared, and refused".to_string()),
+                (false, Ok(_)) => (
+                    Status::Warn,
+                    format!("Not declared, but {method} is answered anyway"),
+                ),
+            };
+            self.record("capabilities", method, status, vec![detail]);
+        }
+
+        if !self.declares("logging") {
+            self.record(
+                "capabilities",
+                "logging/setLevel",
+                Status::Skip,
+                vec!["Logging is not declared".to_string()],
+            );
+            return;
+        }
+        let result = self
+            .ctx
+            .request(self.client.set_level(LoggingLevel::Info))
+            .await;
+        let (status, details) = match result {
+            Ok(()) => (Status::Pass, Vec::new()),
+            Err(e) => (
+                Status::Fail,
+                vec![format!("Declared, but logging/setLevel failed: {e}")],
+            ),
+        };
+        self.record("capabilities", "logging/setLevel", status, details);
+    }
+
+    /// Checks that each declared listing can be walked to its end without
+    /// repeating items, and that a made-up cursor is refused.
+    async fn check_pagination(&mut self) {
+        for listing in Listing::ALL {
+            let method = listing.method();
+            if !self.declares(listing.capability()) {
+                self.record(
+                    "pagination",
+                    method,
+                    Status::Skip,
+                    vec![format!("{} is not declared", listing.capability())],
+                );
+                continue;
+            }
+            let (status, details) = match self.collect(listing).await {
+                Ok((items, pages)) => {
+                    let duplicates = duplicates(&items, listing.key());
+                    let mut details = vec![format!("{} item(s) over {pages} page(s)", items.len())];
+                    let status = if duplicates.is_empty() {
+                        Status::Pass
+                    } else {
+                        details.push(format!("Listed more than once: {}", duplicates.join(", ")));
+                        Status::Fail
+                    };
+                    (status, details)
+                }
+                Err(e) => (Status::Fail, vec![e.to_string()]),
+            };
+            self.record("pagination", method, status, details);
+
+            let (status, detail) = match self.page(listing, Some(INVALID_CURSOR)).await {
+                Err(_) => (Status::Pass, "Refused".to_string()),
+                Ok(_) => (Status::Warn, "A made-up cursor was accepted".to_string()),
+            };
+            self.record(
+                "pagination",
+                format!("{method} invalid cursor"),
+                status,
+                vec![detail],
+            );
+        }
+    }
+
+    /// Checks that requests for things that don't exist fail cleanly, and
+    /// that the server keeps answering afterwards.
+    async fn check_errors(&mut self) {
+        if self.declares("tools") {
+            let result = self
+                .ctx
+                .request(self.client.call_tool(MISSING_NAME, None))
+                .await
+                .map(|result| result.is_error == Some(true));
+            self.record_refusal("tools/call unknown tool", result);
+        }
+        if self.declares("prompts") {
+            let result = self
+                .ctx
+                .request(self.client.get_prompt(MISSING_NAME, None))
+                .await
+                .map(|_| false);
+            self.record_refusal("prompts/get unknown prompt", result);
+        }
+        if self.declares("resources") {
+            let result = self
+                .ctx
+                .request(self.client.resources_read(MISSING_URI))
+                .await
+                .map(|_| false);
+            self.record_refusal("resources/read unknown resource", result);
+        }
+        self.check_ping("errors", "ping after errors").await;
+    }
+
+    /// Records whether a request that should fail did. `result` holds
+    /// whether a successful response was flagged as an error.
+    fn record_refusal(&mut self, name: &str, result: Result<bool>) {
+        let (status, detail) = match result {
+            Ok(true) => (Status::Pass, "Returned an error result".to_string()),
+            Ok(false) => (Status::Fail, "Succeeded".to_string()),
+            Err(e @ Error::Timeout(_)) => (Status::Fail, e.to_string()),
+            Err(e) => (Status::Pass, format!("Refused: {e}")),
+        };
+        self.record("errors", name, status, vec![detail]);
+    }
+
+    /// Checks that the server answers a ping.
+    async fn check_ping(&mut self, category: &'static str, name: &str) {
+        let (status, details) = match self.ctx.request(self.client.ping()).await {
+            Ok(_) => (Status::Pass, Vec::new()),
+            Err(e) => (Status::Fail, vec![format!("Ping failed: {e}")]),
+        };
+        self.record(category, name, status, details);
+    }
+
+    /// Checks that the server copes with a cancelled request. A ping is sent
+    /// and, once it's on the wire, a cancelled notification for its id; the
+    /// server should go on answering, and any late response to the cancelled
+    /// request mustn't be mistaken for another's. The request id is read from
+    /// the frames sent, so HTTP targets are skipped.
+    async fn check_cancellation(&mut self) {
+        let Some(frames) = self.frames.clone() else {
+            self.record(
+                "cancellation",
+                "cancelled request",
+                Status::Skip,
+                vec!["Request ids can't be seen over HTTP".to_string()],
+            );
+            return;
+        };
+        let (status, details) = match self.cancel_ping(&frames).await {
+            Ok(id) => (Status::Pass, vec![format!("Cancelled request {id}")]),
+            Err(e) => (Status::Fail, vec![e.to_string()]),
+        };
+        self.record("cancellation", "cancelled request", status, details);
+        if status == Status::Fail {
+            return;
+        }
+        self.check_ping("cancellation", "ping after a cancelled request")
+            .await;
+        if self.declares("tools") {
+            let (status, details) = match self.page(Listing::Tools, None).await {
+                Ok(page) if page.get("tools").is_some_and(Value::is_array) => {
+                    (Status::Pass, Vec::new())
+                }
+                Ok(page) => (
+                    Status::Fail,
+                    vec![format!("Got a response that isn't a tool listing: {page}")],
+                ),
+                Err(e) => (Status::Fail, vec![e.to_string()]),
+            };
+            self.record(
+                "cancellation",
+                "tools/list after a cancelled request",
+                status,
+                details,
+            );
+        }
+    }
+
+    /// Sends a ping and then a cancelled notification for it, returning the
+    /// id of the cancelled request.
+    async fn cancel_ping(&mut self, frames: &ProtocolTrace) -> Result<Value> {
+        let start = frames.frames().len();
+        let id = {
+            let ping = self.ctx.request(self.client.ping());
+            let sent = frames.wait_for(|frames| sent_request_id(&frames[start..], "ping"));
+            tokio::select! {
+                id = sent => Some(id),
+                // A ping that's been answered has been sent
+                result = ping => match result {
+                    Err(e @ Error::Timeout(_)) => return Err(e),
+                    _ => sent_request_id(&frames.frames()[start..], "ping"),
+                },
+            }
+        }
+        .ok_or_else(|| Error::Other("The ping was never sent".to_string()))?;
+
+        mcp::cancel_request(&mut self.client, &id, "Cancelled by mcptool check").await?;
+        Ok(id)
+    }
+
+    /// Checks the input and output schemas of every tool.
+    async fn check_schemas(&mut self) {
+        if !self.declares("tools") {
+            self.record(
+                "schemas",
+                "tool schemas",
+                Status::Skip,
+                vec!["tools is not declared".to_string()],
+            );
+            return;
+        }
+        let tools = match self.collect(Listing::Tools).await {
+            Ok((tools, _)) => tools,
+            Err(e) => {
+                self.record("schemas", "tool schemas", Status::Fail, vec![e.to_string()]);
+                return;
+            }
+        };
+        for tool in tools {
+            let name = tool
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let (status, details) = tool_problems(&tool);
+            self.record("schemas", format!("tool {name}"), status, details);
+        }
+    }
+}
+
+/// The values of `key` that appear more than once among items.
+fn duplicates(items: &[Value], key: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    for value in items.iter().filter_map(|item| item.get(key)?.as_str()) {
+        if !seen.insert(value) && !duplicates.iter().any(|d| d == value) {
+            duplicates.push(value.to_string());
+        }
+    }
+    duplicates
+}
+
+/// Problems with a tool definition. Broken schemas fail; names outside the
+/// characters the spec recommends only warn.
+fn tool_problems(tool: &Value) -> (Status, Vec<String>) {
+    let mut problems = Vec::new();
+    let name = tool.get("name").and_then(Value::as_str).unwrap_or_default();
+    if name.is_empty() {
+        problems.push("The tool has no name".to_string());
+    }
+    match tool.get("inputSchema") {
+        Some(schema) => schema_problems(schema, "inputSchema", true, &mut problems),
+        None => problems.push("inputSchema is missing".to_string()),
+    }
+    if let Some(schema) = tool.get("outputSchema") {
+        schema_problems(schema, "outputSchema", true, &mut problems);
+    }
+    if !problems.is_empty() {
+        return (Status::Fail, problems);
+    }
+    let valid_name = name.len() <= 128
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid_name {
+        (Status::Pass, Vec::new())
+    } else {
+        (
+            Status::Warn,
+            vec![format!(
+                "The name '{name}' isn't 1-128 letters, digits, '_', '-' or '.'"
+            )],
+        )
+    }
+}
+
+/// Adds the problems with a schema at `path` to `problems`. Tool schemas
+/// must be objects at the top level.
+fn schema_problems(schema: &Value, path: &str, top: bool, problems: &mut Vec<String>) {
+    let Some(object) = schema.as_object() else {
+        if !schema.is_boolean() || top {
+            problems.push(format!("{path} is not a schema object"));
+        }
+        return;
+    };
+    let types: Vec<&Value> = match object.get("type") {
+        Some(Value::Array(types)) => types.iter().collect(),
+        Some(single) => vec![single],
+        None => Vec::new(),
+    };
+    for kind in &types {
+        if !kind
+            .as_str()
+            .is_some_and(|kind| SCHEMA_TYPES.contains(&kind))
+        {
+            problems.push(format!("{path}.type has an unknown type {kind}"));
+        }
+    }
+    if top && object.get("type") != Some(&json!("object")) {
+        problems.push(format!("{path}.type must be \"object\""));
+    }
+
+    let properties = match object.get("properties") {
+        Some(Value::Object(properties)) => Some(properties),
+        Some(_) => {
+            problems.push(format!("{path}.properties is not an object"));
+            None
+        }
+        None => None,
+    };
+    for (name, property) in properties.into_iter().flatten() {
+        schema_problems(
+            property,
+            &format!("{path}.properties.{name}"),
+            false,
+            problems,
+        );
+    }
+    match object.get("required") {
+        Some(Value::Array(required)) => {
+            for name in required {
+                match name.as_str() {
+                    Some(name) if properties.is_some_and(|p| !p.contains_key(name)) => problems
+                        .push(format!(
+                            "{path}.required names '{name}', which isn't in its properties"
+                        )),
+                    Some(_) => {}
+                    None => problems.push(format!("{path}.required has a non-string {name}")),
+                }
+            }
+        }
+        Some(_) => problems.push(format!("{path}.required is not an array")),
+        None => {}
+    }
+    if let Some(items) = object.get("items") {
+        schema_problems(items, &format!("{path}.items"), false, problems);
+    }
+}
+
+/// The id of the last request for `method` among frames sent.
+fn sent_request_id(frames: &[Frame], method: &str) -> Option<Value> {
+    frames
+        .iter()
+        .rev()
+        .find(|frame| {
+            frame.direction == Direction::Sent
+                && frame.message.get("method").and_then(Value::as_str) == Some(method)
+        })
+        .and_then(|frame| frame.message.get("id").cloned())
+}
+
+/// Connects to a target and runs every check against it.
+pub async fn run_checks(ctx: &Ctx, target: &Target) -> Result<Vec<CheckResult>> {
+    // Frames are kept so the cancellation check can find a request's id
+    let frames =
+        trace::traceable(target).then(|| ProtocolTrace::keeping(ctx.trace_protocol.clone()));
+    let ctx = &ctx.clone().with_trace_protocol(frames.clone());
+    let (client, init) = client::get_client(ctx, target).await?;
+    let init = serde_json::to_value(&init)?;
+    let mut checker = Checker {
+        ctx,
+        client,
+        capabilities: init.get("capabilities").cloned().unwrap_or_default(),
+        frames,
+        results: Vec::new(),
+        started: Instant::now(),
+    };
+    checker.check_initialize(&init);
+    checker.check_capabilities().await;
+    checker.check_pagination().await;
+    checker.check_errors().await;
+    checker.check_cancellation().await;
+    checker.check_schemas().await;
+    Ok(checker.results)
+}
+
+/// Runs the conformance checks against a target and prints a report, also
+/// writing it to each of `reports` as JUnit XML or a JSON summary. Fails if
+/// any check failed.
+pub async fn check_command(ctx: &Ctx, target: Target, reports: &[PathBuf]) -> Result<()> {
+    let ctx = &ctx
+        .clone()
+        .with_timeout(Some(ctx.timeout.unwrap_or(DEFAULT_TIMEOUT)));
+    let results = run_checks(ctx, &target).await?;
+    let ci_report = Report {
+        suite: format!("mcptool check {target}"),
+        cases: results.iter().map(CheckResult::to_case).collect(),
+    };
+    for path in reports {
+        ci_report.write(path)?;
+    }
+    report(&ctx.output, &target, &results)
+}
+
+/// Prints the results of a run, grouped by category, and a count of each
+/// outcome. Fails if any check failed.
+fn report(output: &Output, target: &Target, results: &[CheckResult]) -> Result<()> {
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let failed = count(Status::Fail);
+    if output.structured() {
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "passed": count(Status::Pass),
+            "failed": failed,
+            "warnings": count(Status::Warn),
+            "skipped": count(Status::Skip),
+            "checks": results,
+        }))?;
+    } else {
+        output.h1(format!("Conformance checks for {target}"))?;
+        let mut category = "";
+        for result in results {
+            if result.category != category {
+                category = result.category;
+                output.h2(category)?;
+            }
+            let line = format!("{}: {}", result.status, result.name);
+            match result.status {
+                Status::Pass => output.trace_success(line)?,
+                Status::Fail => output.trace_error(line)?,
+                Status::Warn => output.trace_warn(line)?,
+                Status::Skip => output.note(line)?,
+            }
+            let out = output.indent();
+            for detail in &result.details {
+                out.text(detail)?;
+            }
+        }
+        output.text(format!(
+            "{} checks: {} passed, {failed} failed, {} warnings, {} skipped",
+            results.len(),
+            count(Status::Pass),
+            count(Status::Warn),
+            count(Status::Skip),
+        ))?;
+    }
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} of {} conformance check(s) failed",
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sent_request_id() {
+        let frame = |direction, message| Frame {
+            direction,
+            at: Instant::now(),
+            message,
+        };
+        let frames = [
+            frame(
+                Direction::Sent,
+                json!({"jsonrpc": "2.0", "id": 3, "method": "ping"}),
+            ),
+            frame(
+                Direction::Received,
+                json!({"jsonrpc": "2.0", "id": 9, "method": "ping"}),
+            ),
+            frame(
+                Direction::Sent,
+                json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}),
+            ),
+        ];
+        assert_eq!(sent_request_id(&frames, "ping"), Some(json!(3)));
+        assert_eq!(sent_request_id(&frames, "tools/list"), Some(json!(4)));
+        assert_eq!(sent_request_id(&frames, "prompts/list"), None);
+    }
+
+    #[test]
+    fn test_tool_problems() {
+        let good = json!({
+            "name": "echo",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "message": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": ["string", "null"]}}
+                },
+                "required": ["message"]
+            }
+        });
+        assert_eq!(tool_problems(&good), (Status::Pass, Vec::new()));
+
+        let bad = json!({
+            "name": "echo",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"message": {"type": "text"}},
+                "required": ["missing"]
+            },
+            "outputSchema": {"type": "string"}
+        });
+        let (status, problems) = tool_problems(&bad);
+        assert_eq!(status, Status::Fail);
+        assert_eq!(problems.len(), 3, "{problems:?}");
+
+        let odd_name = json!({"name": "echo tool", "inputSchema": {"type": "object"}});
+        assert_eq!(tool_problems(&odd_name).0, Status::Warn);
+        assert_eq!(tool_problems(&json!({"name": "echo"})).0, Status::Fail);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let items = vec![
+            json!({"name": "a"}),
+            json!({"name": "b"}),
+            json!({"name": "a"}),
+            json!({"name": "a"}),
+        ];
+        assert_eq!(duplicates(&items, "name"), ["a"]);
+        assert!(duplicates(&items, "uri").is_empty());
+    }
+}
@@ -1,81 +1,502 @@
 //! MCP client connection management.
 
 use std::{
+    collections::BTreeMap,
+    error::Error as StdError,
+    io::{self, IsTerminal},
+    iter,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    process::Stdio,
     sync::Arc,
     time::{Instant, SystemTime},
 };
 
+use reqwest::{
+    Proxy, StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
 use tmcp::{
     Client, ClientHandler,
     auth::{OAuth2Client, OAuth2Config, OAuth2Token},
     schema::InitializeResult,
 };
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+    task::{self, JoinHandle},
+    time,
+};
 
 use crate::{
     Error, Result,
+    auth::{self, AddCommandArgs, oauth},
     ctx::{Ctx, VERSION},
+    frametrace::{Traced, traced_split},
+    negotiate::{self, RequestRewrite},
+    output::Output,
+    retry::RetryPolicy,
+    ssh,
+    storage::SecretStore,
     target::Target,
+    tls::{self, TlsOptions},
+    tunnel,
     utils::TimedFuture,
 };
 
+/// A server process spawned for a client. Dropping it stops the task that
+/// supervises the process, which kills the process if it is still running.
+#[derive(Debug)]
+struct ServerProcess(JoinHandle<()>);
+
+impl ServerProcess {
+    /// Supervises a spawned server process.
+    fn supervise(child: Child, output: Output) -> Self {
+        Self(task::spawn(supervise_process(child, output)))
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A client connected to a target. For targets that spawn a server, the
+/// server process lives exactly as long as the client.
+pub struct Connected<C> {
+    /// The MCP client.
+    client: Client<C>,
+    /// The server process the client talks to, if mcptool spawned it. Never
+    /// read: it is held so the process is stopped when the client is dropped.
+    _server: Option<ServerProcess>,
+}
+
+impl<C> Deref for Connected<C> {
+    type Target = Client<C>;
+
+    fn deref(&self) -> &Client<C> {
+        &self.client
+    }
+}
+
+impl<C> DerefMut for Connected<C> {
+    fn deref_mut(&mut self) -> &mut Client<C> {
+        &mut self.client
+    }
+}
+
+/// Transport-level settings applied when connecting to a target.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectOptions {
+    /// TLS settings used for `tls://` targets.
+    pub tls: TlsOptions,
+    /// Proxy URL for outgoing connections (`socks5://`, `socks5h://` or `http://`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Extra headers sent with every request to HTTP targets.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Environment variables set for servers spawned by `cmd://` targets.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Whether spawned servers inherit mcptool's environment. Unset means yes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inherit_env: Option<bool>,
+    /// Working directory for servers spawned by `cmd://` targets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// The protocol version to ask for instead of the latest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<String>,
+    /// How often to retry failed connection attempts.
+    #[serde(skip)]
+    pub retry: RetryPolicy,
+    /// Persist these settings for the target after a successful connection.
+    #[serde(skip)]
+    pub save: bool,
+}
+
+impl ConnectOptions {
+    /// Fills unset options from settings stored for a target.
+    pub fn merged_with(self, stored: &Self) -> Self {
+        let mut headers = stored.headers.clone();
+        headers.extend(self.headers);
+        let mut env = stored.env.clone();
+        env.extend(self.env);
+        Self {
+            tls: self.tls.merged_with(&stored.tls),
+            proxy: self.proxy.or_else(|| stored.proxy.clone()),
+            headers,
+            env,
+            inherit_env: self.inherit_env.or(stored.inherit_env),
+            cwd: self.cwd.or_else(|| stored.cwd.clone()),
+            protocol_version: self
+                .protocol_version
+                .or_else(|| stored.protocol_version.clone()),
+            retry: self.retry,
+            save: self.save,
+        }
+    }
+
+    /// Whether any of the options that are stored with a target are set.
+    pub fn has_stored_settings(&self) -> bool {
+        *self
+            != Self {
+                retry: self.retry,
+                save: self.save,
+                ..Self::default()
+            }
+    }
+
+    /// The proxy for raw TCP connections to `host`: the configured proxy, or
+    /// one from `HTTPS_PROXY`/`ALL_PROXY` in the environment unless `NO_PROXY`
+    /// or a loopback host rules it out.
+    pub fn tcp_proxy(&self, host: &str) -> Option<String> {
+        self.proxy.clone().or_else(|| tunnel::proxy_from_env(host))
+    }
+
+    /// The command that runs a `cmd://` server, with the environment and
+    /// working directory these options give it.
+    pub fn server_command(&self, command: &str, args: &[String]) -> Command {
+        let mut cmd = Command::new(command);
+        if self.inherit_env == Some(false) {
+            cmd.env_clear();
+        }
+        cmd.args(args).envs(&self.env);
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        cmd
+    }
+}
+
+/// Parses a `KEY=VALUE` header specification.
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::Format(format!("Header must be KEY=VALUE: {input}")))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(Error::Format(format!("Empty header name: {input}")));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Parses a `KEY=VALUE` environment variable specification.
+pub fn parse_env_var(input: &str) -> Result<(String, String)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::Format(format!("Environment variable must be KEY=VALUE: {input}")))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(Error::Format(format!(
+            "Empty environment variable name: {input}"
+        )));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Parses the contents of a dotenv-style file: `KEY=VALUE` lines, with blank
+/// lines and `#` comments ignored, an optional `export ` prefix, and values
+/// optionally wrapped in matching quotes.
+pub fn parse_env_file(contents: &str) -> Result<BTreeMap<String, String>> {
+    let mut vars = BTreeMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = parse_env_var(line)?;
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|rest| rest.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        vars.insert(key, value.to_string());
+    }
+    Ok(vars)
+}
+
+/// Builds the HTTP client used for HTTP targets, carrying any configured
+/// headers. `{secret:NAME}` references in header values are resolved from the
+/// secret store.
+fn http_client(options: &ConnectOptions, secrets: &SecretStore) -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    for (key, value) in &options.headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| Error::Format(format!("Invalid header name '{key}': {e}")))?;
+        let value = HeaderValue::from_str(&secrets.expand(value)?)
+            .map_err(|e| Error::Format(format!("Invalid value for header '{key}': {e}")))?;
+        headers.insert(name, value);
+    }
+    // Without an explicit proxy, reqwest picks up HTTP(S)_PROXY from the environment
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(
+            Proxy::all(proxy)
+                .map_err(|e| Error::Format(format!("Invalid proxy URL '{proxy}': {e}")))?,
+        );
+    }
+    builder
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build HTTP client: {e}")))
+}
+
+/// Combines the command-line connection options with any settings stored for
+/// the target, which for a named target are those saved with it.
+pub fn resolve_options(ctx: &Ctx, target: &Target) -> Result<ConnectOptions> {
+    let storage = ctx.storage()?;
+    let stored = match target {
+        Target::Named { name, .. } => {
+            storage.get_named_target_settings(&storage.get_target(name)?)?
+        }
+        target => storage.get_target_settings(&target.to_string())?,
+    };
+    Ok(match stored {
+        Some(stored) => ctx.connect.clone().merged_with(&stored),
+        None => ctx.connect.clone(),
+    })
+}
+
+/// Persists connection settings for a target, with the named target if it
+/// has a name.
+fn save_options(ctx: &Ctx, target: &Target, options: &ConnectOptions) -> Result<()> {
+    let storage = ctx.storage()?;
+    match target {
+        Target::Named { name, .. } => storage.store_named_target_settings(name, options)?,
+        target => storage.store_target_settings(&target.to_string(), options)?,
+    }
+    ctx.output
+        .trace_success(format!("Saved connection settings for {target}"))?;
+    Ok(())
+}
+
 /// Creates an MCP client connected to the specified target.
-pub async fn get_client(ctx: &Ctx, target: &Target) -> Result<(Client<()>, InitializeResult)> {
+pub async fn get_client(ctx: &Ctx, target: &Target) -> Result<(Connected<()>, InitializeResult)> {
     get_client_with_connection(ctx, target, ()).await
 }
 
 /// Creates an MCP client with a custom connection handler.
-pub async fn get_client_with_connection<C: ClientHandler + Send + 'static>(
+///
+/// Failed connection attempts are retried according to the configured retry
+/// policy, with each attempt reported through the output.
+pub async fn get_client_with_connection<C: ClientHandler + Clone + Send + 'static>(
     ctx: &Ctx,
     target: &Target,
     conn: C,
-) -> Result<(Client<C>, InitializeResult)> {
-    match target {
-        Target::Auth { name } => {
-            let storage = ctx.storage()?;
-            let auth_entry = storage.get_auth(name)?;
-            ctx.output
-                .text(format!("Using auth {name} ({})", auth_entry.server_url))?;
-            let resolved_target = Target::parse(&auth_entry.server_url)?;
-            connect_with_auth(ctx, &resolved_target, name, conn)
-                .timed("Connected and initialized", &ctx.output)
-                .await
-        }
-        _ => {
-            // For other targets, connect directly without auth
-            ctx.output.text(format!("Connecting to {target}"))?;
-            connect_to_server(target, conn)
-                .timed("Connected and initialized", &ctx.output)
-                .await
+) -> Result<(Connected<C>, InitializeResult)> {
+    let policy = ctx.connect.retry;
+    let mut retry = 0;
+    loop {
+        match connect_once(ctx, target, conn.clone()).await {
+            Err(e) if retry < policy.retries && is_retryable(&e) => {
+                let delay = policy.delay_for(retry);
+                retry += 1;
+                ctx.output.trace_warn(format!("Connection failed: {e}"))?;
+                ctx.output.status(format!(
+                    "Retrying in {:.1}s (attempt {} of {})",
+                    delay.as_secs_f64(),
+                    retry + 1,
+                    policy.retries + 1
+                ))?;
+                time::sleep(delay).await;
+            }
+            result => return result,
         }
     }
 }
 
+/// Makes a single attempt at connecting to a target. HTTP targets that
+/// refuse the connection with an OAuth challenge are connected to again
+/// through an auth entry for the server.
+async fn connect_once<C: ClientHandler + Clone + Send + 'static>(
+    ctx: &Ctx,
+    target: &Target,
+    conn: C,
+) -> Result<(Connected<C>, InitializeResult)> {
+    if let Target::Auth { name } = target.unnamed() {
+        return connect_auth_entry(ctx, name, conn).await;
+    }
+    // For other targets, connect directly without auth
+    ctx.output.status(format!("Connecting to {target}"))?;
+    let result = ctx
+        .request(connect_to_server(ctx, target, conn.clone()))
+        .timed("Connected and initialized", &ctx.output)
+        .await;
+    match result {
+        Err(e @ Error::Unauthorized(_)) => match authorize(ctx, target).await? {
+            Some(name) => connect_auth_entry(ctx, &name, conn).await,
+            None => Err(e),
+        },
+        result => result,
+    }
+}
+
+/// The error for a failed HTTP connection. A connection refused with 401
+/// Unauthorized is told apart, since the server may want OAuth.
+fn http_failed(context: String, error: &tmcp::Error) -> Error {
+    let message = format!("{context}: {error}");
+    if http_status(error) == Some(StatusCode::UNAUTHORIZED) {
+        Error::Unauthorized(message)
+    } else {
+        Error::Other(message)
+    }
+}
+
+/// The HTTP status a request was answered with, if an error or one of its
+/// causes is a failed HTTP request.
+fn http_status(error: &(dyn StdError + 'static)) -> Option<StatusCode> {
+    iter::successors(Some(error), |e| (*e).source())
+        .find_map(|e| e.downcast_ref::<reqwest::Error>()?.status())
+}
+
+/// Connects to the server of an auth entry using its tokens.
+async fn connect_auth_entry<C: ClientHandler + Send + 'static>(
+    ctx: &Ctx,
+    name: &str,
+    conn: C,
+) -> Result<(Connected<C>, InitializeResult)> {
+    let auth_entry = ctx.storage()?.get_auth(name)?;
+    ctx.output
+        .status(format!("Using auth {name} ({})", auth_entry.server_url))?;
+    let resolved_target = Target::parse(&auth_entry.server_url)?;
+    ctx.request(connect_with_auth(ctx, &resolved_target, name, conn))
+        .timed("Connected and initialized", &ctx.output)
+        .await
+}
+
+/// Handles an HTTP target that may want OAuth. If an unauthenticated request
+/// is refused with a `WWW-Authenticate` challenge, the auth entry for the
+/// server is used, or one is created by running the authorization flow with
+/// the endpoints found through RFC 9728 discovery. The probe is sent with the
+/// target's own HTTP settings. Returns the entry's name, or None if the server
+/// didn't ask for OAuth or couldn't be probed.
+async fn authorize(ctx: &Ctx, target: &Target) -> Result<Option<String>> {
+    let options = resolve_options(ctx, target)?;
+    let target = target.unnamed();
+    let (Target::Http { host, .. } | Target::Https { host, .. }) = target else {
+        return Ok(None);
+    };
+    let url = target.to_string();
+    let http = http_client(&options, ctx.storage()?.secrets())?;
+    let Ok(Some(resource_metadata)) = ctx.request(oauth::challenge(&http, &url)).await else {
+        return Ok(None);
+    };
+    let storage = ctx.storage()?;
+    let auths = storage.get_all_auth()?;
+    if let Some(auth) = auths
+        .iter()
+        .find(|auth| Target::parse(&auth.server_url).is_ok_and(|t| t == *target))
+    {
+        ctx.output
+            .status(format!("{target} requires OAuth, using auth {}", auth.name))?;
+        return Ok(Some(auth.name.clone()));
+    }
+    if !io::stdin().is_terminal() {
+        return Err(Error::Config(format!(
+            "{target} requires OAuth. Authorize it with 'mcptool auth add <name> --server-url {url}'"
+        )));
+    }
+
+    let base: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let name = (1..)
+        .map(|n| {
+            if n == 1 {
+                base.clone()
+            } else {
+                format!("{base}_{n}")
+            }
+        })
+        .find(|name| !auths.iter().any(|auth| auth.name == *name))
+        .unwrap_or(base);
+    ctx.output.trace_warn(format!(
+        "{target} requires OAuth, authorizing as auth://{name}"
+    ))?;
+    auth::add_command(
+        ctx,
+        AddCommandArgs {
+            name: name.clone(),
+            server_url: Some(url),
+            resource_metadata: Some(resource_metadata),
+            ..AddCommandArgs::default()
+        },
+    )
+    .await?;
+    Ok(Some(name))
+}
+
+/// Returns true for errors that a later connection attempt might not hit, as
+/// opposed to configuration errors, reported as `Error::Config`, and input
+/// that didn't parse, reported as `Error::Format`, that will fail the same
+/// way every time.
+fn is_retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Io(_) | Error::Other(_) | Error::MpcClient(_) | Error::Timeout(_)
+    )
+}
+
+/// The error for a server process that couldn't be started. A missing or
+/// unrunnable command won't start on a later attempt either.
+fn spawn_failed(e: io::Error) -> Error {
+    let message = format!("Failed to spawn MCP server process: {e}");
+    match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => Error::Config(message),
+        _ => Error::Other(message),
+    }
+}
+
 /// Connects to a target using OAuth authentication.
 async fn connect_with_auth<C: ClientHandler + Send + 'static>(
     ctx: &Ctx,
     target: &Target,
     auth_name: &str,
     conn: C,
-) -> Result<(Client<C>, InitializeResult)> {
+) -> Result<(Connected<C>, InitializeResult)> {
+    if !ctx.connect.headers.is_empty() {
+        return Err(Error::Config(
+            "Custom headers cannot be combined with auth:// targets".to_string(),
+        ));
+    }
+    if ctx.connect.protocol_version.is_some() {
+        return Err(Error::Config(
+            "A protocol version can't be forced for auth:// targets".to_string(),
+        ));
+    }
+
     // Only HTTP/HTTPS targets support OAuth
     match target {
         Target::Http { .. } | Target::Https { .. } => {}
         _ => {
-            return Err(Error::Other(
+            return Err(Error::Config(
                 "OAuth authentication is only supported for HTTP/HTTPS targets".to_string(),
             ));
         }
     }
 
     let storage = ctx.storage()?;
-    let auth = storage.get_auth(auth_name)?;
-    if let Some(expires_at) = auth.expires_at
-        && expires_at <= SystemTime::now()
-    {
-        return Err(Error::Other(
-            "Access token has expired. Please re-authenticate with 'mcptool auth add/renew'"
-                .to_string(),
-        ));
+    let mut auth = storage.get_auth(auth_name)?;
+    if oauth::is_expired(&auth) {
+        if auth.refresh_token.is_none() {
+            return Err(Error::Config(
+                "Access token has expired. Please re-authenticate with 'mcptool auth add/renew'"
+                    .to_string(),
+            ));
+        }
+        ctx.output.status("Access token has expired, refreshing")?;
+        oauth::refresh(&mut auth).await?;
+        storage.store_auth(&auth)?;
     }
 
     // Create OAuth config
@@ -87,7 +508,7 @@ async fn connect_with_auth<C: ClientHandler + Send + 'static>(
         redirect_url: auth
             .redirect_url
             .unwrap_or_else(|| "http://localhost:0".to_string()),
-        resource: "".to_string(), // Empty resource, could be stored in auth if needed
+        resource: auth.resource.unwrap_or_default(),
         scopes: auth.scopes,
     };
 
@@ -111,6 +532,7 @@ async fn connect_with_auth<C: ClientHandler + Send + 'static>(
     }
 
     let oauth_client = Arc::new(oauth_client);
+    warn_untraced(ctx)?;
 
     let mut client = Client::new("mcptool", VERSION).with_handler(conn);
 
@@ -139,59 +561,373 @@ async fn connect_with_auth<C: ClientHandler + Send + 'static>(
         }
         _ => unreachable!(), // We checked this above
     };
+    negotiate::report(&ctx.output, None, &init_result)?;
 
-    Ok((client, init_result))
+    Ok((
+        Connected {
+            client,
+            _server: None,
+        },
+        init_result,
+    ))
 }
 
-use tokio::process::Command;
-
 /// Connects to an MCP server without authentication.
 pub async fn connect_to_server<C: ClientHandler + Send + 'static>(
+    ctx: &Ctx,
     target: &Target,
     conn: C,
-) -> Result<(Client<C>, InitializeResult)> {
+) -> Result<(Connected<C>, InitializeResult)> {
+    let options = resolve_options(ctx, target)?;
+    let (named, target) = (target, target.unnamed());
+    if options.tls.has_client_auth() && !matches!(target, Target::Tls { .. }) {
+        return Err(Error::Config(
+            "Client certificates are only supported for tls:// targets".to_string(),
+        ));
+    }
+    if !options.headers.is_empty() && !matches!(target, Target::Http { .. } | Target::Https { .. })
+    {
+        return Err(Error::Config(
+            "Custom headers are only supported for HTTP/HTTPS targets".to_string(),
+        ));
+    }
+    if (!options.env.is_empty() || options.inherit_env.is_some())
+        && !matches!(target, Target::Stdio { .. })
+    {
+        return Err(Error::Config(
+            "Environment variables are only supported for cmd:// targets".to_string(),
+        ));
+    }
+    if options.cwd.is_some() && !matches!(target, Target::Stdio { .. }) {
+        return Err(Error::Config(
+            "A working directory is only supported for cmd:// targets".to_string(),
+        ));
+    }
+    if options.protocol_version.is_some()
+        && matches!(target, Target::Http { .. } | Target::Https { .. })
+    {
+        return Err(Error::Config(
+            "A protocol version can only be forced for tcp, tls, cmd and ssh targets".to_string(),
+        ));
+    }
+
     let mut client = Client::new("mcptool", VERSION).with_handler(conn);
+    let mut server = None;
 
     let init_result = match target {
         Target::Tcp { host, port } => {
-            let addr = format!("{host}:{port}");
-            client.connect_tcp(&addr).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to TCP address {addr}: {e}"))
+            let proxy = options.tcp_proxy(host);
+            let stream = tunnel::connect(host, *port, proxy.as_deref()).await?;
+            let (reader, writer) = traced_split(stream, ctx.trace_protocol.clone());
+            let writer = RequestRewrite::new(writer, options.protocol_version.clone());
+            let via = proxy
+                .map(|proxy| format!(" via {proxy}"))
+                .unwrap_or_default();
+            client.connect_stream(reader, writer).await.map_err(|e| {
+                Error::Other(format!(
+                    "Failed to connect to TCP address {host}:{port}{via}: {e}"
+                ))
+            })?
+        }
+        Target::Tls { host, port } => {
+            let stream = tunnel::connect(host, *port, options.tcp_proxy(host).as_deref()).await?;
+            let stream = tls::connect(stream, host, &options.tls).await?;
+            let (reader, writer) = traced_split(stream, ctx.trace_protocol.clone());
+            let writer = RequestRewrite::new(writer, options.protocol_version.clone());
+            client.connect_stream(reader, writer).await.map_err(|e| {
+                Error::Other(format!(
+                    "Failed to connect to TLS address {host}:{port}: {e}"
+                ))
             })?
         }
         Target::Stdio { command, args } => {
-            let mut cmd = Command::new(command.clone());
-            cmd.args(args);
-
-            let _child = client
-                .connect_process(cmd)
-                .await
-                .map_err(|e| Error::Other(format!("Failed to spawn MCP server process: {e}")))?;
-
-            // The new API handles initialization automatically
-            client
-                .init()
-                .await
-                .map_err(|e| Error::Other(format!("Failed to initialize MCP client: {e}")))?
+            let cmd = options.server_command(command, args);
+            let (init_result, process) =
+                spawn_server(ctx, &mut client, cmd, options.protocol_version.as_deref()).await?;
+            server = Some(process);
+            init_result
+        }
+        Target::Ssh {
+            user,
+            host,
+            port,
+            command,
+        } => {
+            let cmd = ssh::command(user.as_deref(), host, *port, command);
+            let (init_result, process) =
+                spawn_server(ctx, &mut client, cmd, options.protocol_version.as_deref()).await?;
+            server = Some(process);
+            init_result
         }
         Target::Http { host, port } => {
+            warn_untraced(ctx)?;
             let url = format!("http://{host}:{port}");
-            client.connect_http(&url).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to HTTP endpoint {url}: {e}"))
-            })?
+            let http = http_client(&options, ctx.storage()?.secrets())?;
+            client
+                .connect_http_with_client(&url, http)
+                .await
+                .map_err(|e| http_failed(format!("Failed to connect to HTTP endpoint {url}"), &e))?
         }
         Target::Https { host, port } => {
+            warn_untraced(ctx)?;
             let url = format!("https://{host}:{port}");
-            client.connect_http(&url).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to HTTPS endpoint {url}: {e}"))
-            })?
+            let http = http_client(&options, ctx.storage()?.secrets())?;
+            client
+                .connect_http_with_client(&url, http)
+                .await
+                .map_err(|e| {
+                    http_failed(format!("Failed to connect to HTTPS endpoint {url}"), &e)
+                })?
         }
-        Target::Auth { .. } => {
-            return Err(Error::Other(
+        Target::Auth { .. } | Target::Named { .. } => {
+            return Err(Error::Internal(
                 "Auth targets should be resolved to actual targets before calling connect_to_server".to_string()
             ));
         }
     };
 
-    Ok((client, init_result))
+    negotiate::report(
+        &ctx.output,
+        options.protocol_version.as_deref(),
+        &init_result,
+    )?;
+
+    if options.save {
+        save_options(ctx, named, &options)?;
+    }
+
+    Ok((
+        Connected {
+            client,
+            _server: server,
+        },
+        init_result,
+    ))
+}
+
+/// Warns that frames can't be traced for HTTP targets, where they aren't
+/// carried on a stream mcptool can see.
+fn warn_untraced(ctx: &Ctx) -> Result<()> {
+    if ctx.trace_protocol.is_some() {
+        ctx.output
+            .trace_warn("Protocol tracing is not supported for HTTP targets")?;
+    }
+    Ok(())
+}
+
+/// Spawns a server process, connects to it over stdio, and initializes it,
+/// asking for `protocol_version` if one is given. The process is returned
+/// along with the result, and is killed when it is dropped.
+async fn spawn_server<C: ClientHandler + Send + 'static>(
+    ctx: &Ctx,
+    client: &mut Client<C>,
+    mut cmd: Command,
+    protocol_version: Option<&str>,
+) -> Result<(InitializeResult, ServerProcess)> {
+    cmd.stderr(Stdio::piped()).kill_on_drop(true);
+    if ctx.trace_protocol.is_some() || protocol_version.is_some() {
+        return spawn_wrapped_server(ctx, client, cmd, protocol_version).await;
+    }
+
+    let child = client.connect_process(cmd).await.map_err(spawn_failed)?;
+    let process = ServerProcess::supervise(child, ctx.output.clone());
+
+    // The new API handles initialization automatically
+    let init_result = client
+        .init()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to initialize MCP client: {e}")))?;
+    Ok((init_result, process))
+}
+
+/// Spawns a server process with its stdio wrapped, so frames can be traced
+/// and the initialize request can ask for another protocol version, then
+/// connects to it and initializes it.
+async fn spawn_wrapped_server<C: ClientHandler + Send + 'static>(
+    ctx: &Ctx,
+    client: &mut Client<C>,
+    mut cmd: Command,
+    protocol_version: Option<&str>,
+) -> Result<(InitializeResult, ServerProcess)> {
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped());
+    let mut child = cmd.spawn().map_err(spawn_failed)?;
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err(Error::Internal(
+            "Server process has no stdio pipes".to_string(),
+        ));
+    };
+    let process = ServerProcess::supervise(child, ctx.output.clone());
+
+    let init_result = client
+        .connect_stream(
+            Traced::reader(stdout, ctx.trace_protocol.clone()),
+            RequestRewrite::new(
+                Traced::writer(stdin, ctx.trace_protocol.clone()),
+                protocol_version.map(str::to_string),
+            ),
+        )
+        .await
+        .map_err(|e| Error::Other(format!("Failed to initialize MCP client: {e}")))?;
+    Ok((init_result, process))
+}
+
+/// Forwards a spawned server's stderr into the output and reports how the
+/// process exited. The child is killed if it is still running when this task
+/// is aborted, since it is dropped with the task.
+async fn supervise_process(mut child: Child, output: Output) {
+    if let Some(stderr) = child.stderr.take() {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            _ = output.status(format!("[server stderr] {line}"));
+        }
+    }
+    match child.wait().await {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            _ = output.trace_warn(format!("Server process exited with {status}"));
+        }
+        Err(e) => {
+            _ = output.trace_error(format!("Failed to wait for server process: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::Path};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_server_command() {
+        let options = ConnectOptions {
+            env: BTreeMap::from([("KEY".to_string(), "value".to_string())]),
+            inherit_env: Some(false),
+            cwd: Some(PathBuf::from("/srv")),
+            ..ConnectOptions::default()
+        };
+        let cmd = options.server_command("server", &["--flag".to_string()]);
+        let cmd = cmd.as_std();
+        assert_eq!(cmd.get_program(), "server");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["--flag"]);
+        assert_eq!(
+            cmd.get_envs().collect::<Vec<_>>(),
+            [(OsStr::new("KEY"), Some(OsStr::new("value")))]
+        );
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/srv")));
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization=Bearer abc=def").unwrap(),
+            ("Authorization".to_string(), "Bearer abc=def".to_string())
+        );
+        assert_eq!(
+            parse_header("X-Empty=").unwrap(),
+            ("X-Empty".to_string(), String::new())
+        );
+        assert!(parse_header("no-separator").is_err());
+        assert!(parse_header("=value").is_err());
+    }
+
+    #[test]
+    fn test_has_stored_settings() {
+        let mut options = ConnectOptions {
+            save: true,
+            ..ConnectOptions::default()
+        };
+        options.retry.retries = 3;
+        assert!(!options.has_stored_settings());
+
+        options.cwd = Some(PathBuf::from("/srv/mcp"));
+        assert!(options.has_stored_settings());
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let contents = r#"
+# Comment
+API_URL=https://example.com/?a=b
+export TOKEN="quoted value"
+SINGLE='single'
+EMPTY=
+"#;
+        let vars = parse_env_file(contents).unwrap();
+        assert_eq!(vars["API_URL"], "https://example.com/?a=b");
+        assert_eq!(vars["TOKEN"], "quoted value");
+        assert_eq!(vars["SINGLE"], "single");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars.len(), 4);
+
+        assert!(parse_env_file("NO_SEPARATOR").is_err());
+        assert!(parse_env_var("=value").is_err());
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(&Error::Other(
+            "connection refused".to_string()
+        )));
+        assert!(!is_retryable(&Error::Config(
+            "Custom headers are only supported for HTTP/HTTPS targets".to_string()
+        )));
+        assert!(!is_retryable(&spawn_failed(io::ErrorKind::NotFound.into())));
+        assert!(is_retryable(&spawn_failed(
+            io::ErrorKind::WouldBlock.into()
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_http_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        task::spawn(async move {
+            for status in ["401 Unauthorized", "503 Service Unavailable"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                _ = stream.read(&mut request).await.unwrap();
+                let response = format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        let http = reqwest::Client::new();
+        for expected in [StatusCode::UNAUTHORIZED, StatusCode::SERVICE_UNAVAILABLE] {
+            let error = http
+                .get(&url)
+                .send()
+                .await
+                .unwrap()
+                .error_for_status()
+                .unwrap_err();
+            assert_eq!(http_status(&error), Some(expected));
+        }
+        let unreachable = io::Error::new(io::ErrorKind::ConnectionRefused, "port 4010");
+        assert_eq!(http_status(&unreachable), None);
+    }
+
+    #[test]
+    fn test_merged_headers_override_stored() {
+        let mut stored = ConnectOptions::default();
+        stored
+            .headers
+            .insert("Authorization".to_string(), "old".to_string());
+        stored
+            .headers
+            .insert("X-Team".to_string(), "core".to_string());
+
+        let mut explicit = ConnectOptions::default();
+        explicit
+            .headers
+            .insert("Authorization".to_string(), "new".to_string());
+
+        let merged = explicit.merged_with(&stored);
+        assert_eq!(merged.headers["Authorization"], "new");
+        assert_eq!(merged.headers["X-Team"], "core");
+    }
 }
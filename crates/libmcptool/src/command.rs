@@ -1,7 +1,84 @@
+use std::{path::PathBuf, result, time::Duration};
+
 use clap::{Args, CommandFactory, Parser, Subcommand};
+use serde_json::{Map, Value};
 use tmcp::{Client, ClientHandler, schema::InitializeResult};
+use tokio::{signal, time};
+
+use crate::{
+    Error, Result,
+    calltool::{CallOptions, Document, cmdline},
+    client,
+    ctx::Ctx,
+    expect::{self, Expectations},
+    frametrace::{ProtocolTrace, unanswered_request},
+    mcp,
+    progress::ProgressConn,
+    retry::{CallRetry, RetryOn, RetryPolicy},
+    target::Target,
+    trace,
+};
+
+/// Options for retrying failed tool calls.
+#[derive(Args, Debug, Clone)]
+pub struct RetryArgs {
+    /// Retry a failed call up to N times, with exponential backoff from --retry-delay
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    /// The failures to retry
+    #[arg(long, value_delimiter = ',', default_value = "timeout,server-error")]
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl RetryArgs {
+    /// The retry settings, with the backoff delay taken from the connection
+    /// retry options.
+    pub fn into_call_retry(self, ctx: &Ctx) -> CallRetry {
+        CallRetry {
+            policy: RetryPolicy {
+                retries: self.retries,
+                delay: ctx.connect.retry.delay,
+            },
+            on: self.retry_on,
+        }
+    }
+}
+
+/// Assertions on a tool call's outcome, which fail the command when unmet.
+#[derive(Args, Debug, Clone)]
+pub struct ExpectArgs {
+    /// Fail unless the result's text contains STR (can be specified multiple times)
+    #[arg(long, value_name = "STR")]
+    pub expect_contains: Vec<String>,
+
+    /// Fail unless the result JSON has VALUE at PATH, e.g. structuredContent.count=3 (can be
+    /// specified multiple times)
+    #[arg(long, value_name = "PATH=VALUE", value_parser = parse_expect_json)]
+    pub expect_json: Vec<(String, Value)>,
+
+    /// Fail unless the call fails, with a protocol error or an error result
+    #[arg(long)]
+    pub expect_error: bool,
+}
+
+impl From<ExpectArgs> for Expectations {
+    fn from(args: ExpectArgs) -> Self {
+        Self {
+            contains: args.expect_contains,
+            json: args.expect_json,
+            error: args.expect_error,
+        }
+    }
+}
+
+/// Parses an `--expect-json` value for clap.
+fn parse_expect_json(input: &str) -> result::Result<(String, Value), String> {
+    expect::parse_json_expectation(input).map_err(|e| e.to_string())
+}
 
-use crate::{Result, client, ctx::Ctx, mcp, target::Target};
+/// How long a cancelled one-shot command waits for the server's response.
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
 
 #[derive(Args)]
 pub struct McpArgs {
@@ -13,7 +90,21 @@ pub struct McpArgs {
 #[command(no_binary_name = true)]
 pub enum McpCommand {
     /// Send a ping request to an MCP server
-    Ping,
+    Ping {
+        /// Send N pings and print round-trip and loss statistics
+        #[arg(
+            long,
+            short,
+            value_name = "N",
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        count: u32,
+
+        /// Time to wait between pings (e.g. 500ms, 2s)
+        #[arg(long, short, value_parser = humantime::parse_duration, default_value = "1s")]
+        interval: Duration,
+    },
 
     /// List all MCP tools from a server
     Listtools,
@@ -41,23 +132,120 @@ pub enum McpCommand {
         /// Name of the tool to call
         tool_name: String,
 
-        /// Arguments in key=value format (can be specified multiple times)
+        /// Arguments in key=value format (can be specified multiple times); key=@file and
+        /// key=@- read the value from a file or stdin, and key:=value takes raw JSON
         #[arg(long = "arg", short = 'a')]
         args: Vec<String>,
 
+        /// Arguments in key=json format, with the value as raw JSON (can be specified multiple
+        /// times)
+        #[arg(long, value_name = "KEY=JSON")]
+        arg_json: Vec<String>,
+
         /// Interactive mode: prompt for each tool parameter
         #[arg(long, short)]
         interactive: bool,
 
-        /// JSON mode: read arguments from stdin as JSON
+        /// Write the arguments in $EDITOR, starting from a skeleton of the tool's parameters
+        #[arg(long)]
+        edit: bool,
+
+        /// Read arguments as a JSON object from stdin, or from FILE if given. Named apart from the
+        /// global --json, which picks the output format
+        #[arg(long, short = 'j', num_args = 0..=1, value_name = "FILE")]
+        json_args: Option<Option<PathBuf>>,
+
+        /// Read arguments as a JSON object from a file
+        #[arg(long, value_name = "FILE", conflicts_with = "json_args")]
+        json_file: Option<PathBuf>,
+
+        /// Read arguments as a YAML mapping from stdin, or from FILE if given. Named apart from the
+        /// global --yaml, which picks the output format
+        #[arg(
+            long,
+            num_args = 0..=1,
+            value_name = "FILE",
+            conflicts_with_all = ["json_args", "json_file"]
+        )]
+        yaml_args: Option<Option<PathBuf>>,
+
+        /// Read arguments as a YAML mapping from a file
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["json_args", "json_file", "yaml_args"]
+        )]
+        yaml_file: Option<PathBuf>,
+
+        /// Expand ${VAR} references to environment variables in --arg values and JSON or YAML
+        /// arguments, failing if any variable is unset
+        #[arg(long)]
+        expand_env: bool,
+
+        /// Send the arguments without checking them against the tool's input schema
+        #[arg(long)]
+        no_validate: bool,
+
+        /// Print the JSON-RPC request that would be sent, without calling the tool
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Fail if the result doesn't match the tool's output schema, instead of warning
+        #[arg(long)]
+        strict: bool,
+
+        /// Write each content block of the result to a file in DIR, decoding binary data
+        #[arg(long, value_name = "DIR")]
+        save_content: Option<PathBuf>,
+
+        /// Call the tool N times and print latency statistics instead of the result
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u32).range(1..),
+            conflicts_with_all = [
+                "dry_run",
+                "save_content",
+                "strict",
+                "expect_contains",
+                "expect_json",
+                "expect_error"
+            ]
+        )]
+        repeat: u32,
+
+        /// Time to wait between repeated calls (e.g. 500ms, 2s)
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "0s", requires = "repeat")]
+        interval: Duration,
+
+        #[command(flatten)]
+        expect: ExpectArgs,
+
+        /// Call tools marked destructive without asking for confirmation
         #[arg(long, short)]
-        json: bool,
+        yes: bool,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+
+        /// Arguments given as a JSON object after the command in the REPL
+        #[arg(skip)]
+        arguments: Option<Map<String, Value>>,
     },
 
     /// Read a resource by URI
     Readresource {
         /// URI of the resource to read
         uri: String,
+
+        /// Show at most N bytes of binary contents in the hexdump
+        #[arg(long, value_name = "N", default_value_t = 256)]
+        hexdump_bytes: usize,
+
+        /// Write the decoded contents to stdout as they are, e.g. to pipe an image to a file
+        #[arg(long)]
+        raw: bool,
     },
 
     /// Get a prompt by name with optional arguments
@@ -68,6 +256,11 @@ pub enum McpCommand {
         /// Arguments in key=value format (can be specified multiple times)
         #[arg(long = "arg", short = 'a')]
         args: Vec<String>,
+
+        /// Interactive mode: prompt for each argument, with the server's completions as
+        /// suggestions
+        #[arg(long, short)]
+        interactive: bool,
     },
 
     /// Subscribe to resource update notifications
@@ -95,7 +288,7 @@ pub enum McpCommand {
 // For CLI use - target is required at this level
 #[derive(Parser)]
 pub struct CliMcpCommand {
-    /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "auth://name")
+    /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "tls://host:port", "http://host:port", "ssh://user@host/server", "auth://name") or a saved target name
     pub target: String,
 
     #[command(subcommand)]
@@ -110,70 +303,169 @@ pub struct ReplCommandWrapper {
     pub command: McpCommand,
 }
 
-// For REPL use - reuses existing client connection
+// For REPL use - reuses existing client connection. Returns the command's
+// result as JSON, for commands that produce one.
 pub async fn execute_mcp_command_with_client<C: ClientHandler + 'static>(
     command: McpCommand,
     client: &mut Client<C>,
     init_result: &InitializeResult,
     ctx: &Ctx,
-) -> Result<()> {
-    match command {
-        McpCommand::Ping => {
-            mcp::ping(client, &ctx.output).await?;
-        }
-        McpCommand::Listtools => {
-            mcp::listtools(client, &ctx.output).await?;
+) -> Result<Option<Value>> {
+    let result = match command {
+        McpCommand::Ping { count, interval } => {
+            mcp::ping(client, ctx, count, interval).await?;
+            None
         }
+        McpCommand::Listtools => Some(serde_json::to_value(mcp::listtools(client, ctx).await?)?),
         McpCommand::Init => {
             mcp::init(init_result, &ctx.output)?;
+            Some(serde_json::to_value(init_result)?)
         }
-        McpCommand::Listresources => {
-            mcp::listresources(client, &ctx.output).await?;
-        }
+        McpCommand::Listresources => Some(serde_json::to_value(
+            mcp::listresources(client, ctx).await?,
+        )?),
         McpCommand::Listprompts => {
-            mcp::listprompts(client, &ctx.output).await?;
-        }
-        McpCommand::Listresourcetemplates => {
-            mcp::listresourcetemplates(client, &ctx.output).await?;
+            Some(serde_json::to_value(mcp::listprompts(client, ctx).await?)?)
         }
+        McpCommand::Listresourcetemplates => Some(serde_json::to_value(
+            mcp::listresourcetemplates(client, ctx).await?,
+        )?),
         McpCommand::Setlevel { level } => {
-            mcp::set_level(client, &ctx.output, &level).await?;
+            mcp::set_level(client, ctx, &level).await?;
+            None
         }
         McpCommand::Calltool {
             tool_name,
-            args,
+            mut args,
+            arg_json,
             interactive,
-            json,
+            edit,
+            json_args,
+            json_file,
+            yaml_args,
+            yaml_file,
+            expand_env,
+            no_validate,
+            dry_run,
+            strict,
+            save_content,
+            repeat,
+            interval,
+            expect,
+            yes,
+            retry,
+            arguments,
         } => {
-            mcp::calltool(client, &ctx.output, &tool_name, args, interactive, json).await?;
-        }
-        McpCommand::Readresource { uri } => {
-            mcp::read_resource(client, &ctx.output, &uri).await?;
-        }
-        McpCommand::Getprompt { name, args } => {
-            mcp::get_prompt(client, &ctx.output, &name, args).await?;
+            args.extend(arg_json.into_iter().map(cmdline::json_argument));
+            let options = CallOptions {
+                args,
+                interactive,
+                edit,
+                document: Document::from_flags(json_args, json_file, yaml_args, yaml_file),
+                json_arguments: arguments,
+                expand_env,
+                validate: !no_validate,
+                dry_run,
+                strict,
+                save_content,
+                repeat,
+                interval,
+                expect: expect.into(),
+                yes,
+                retry: retry.into_call_retry(ctx),
+            };
+            mcp::calltool(client, ctx, &tool_name, options)
+                .await?
+                .map(serde_json::to_value)
+                .transpose()?
         }
+        McpCommand::Readresource {
+            uri,
+            hexdump_bytes,
+            raw,
+        } => Some(serde_json::to_value(
+            mcp::read_resource(client, ctx, &uri, hexdump_bytes, raw).await?,
+        )?),
+        McpCommand::Getprompt {
+            name,
+            args,
+            interactive,
+        } => Some(serde_json::to_value(
+            mcp::get_prompt(client, ctx, &name, args, interactive).await?,
+        )?),
         McpCommand::Subscriberesource { uri } => {
-            mcp::subscribe_resource(client, &ctx.output, &uri).await?;
+            mcp::subscribe_resource(client, ctx, &uri).await?;
+            None
         }
         McpCommand::Unsubscriberesource { uri } => {
-            mcp::unsubscribe_resource(client, &ctx.output, &uri).await?;
+            mcp::unsubscribe_resource(client, ctx, &uri).await?;
+            None
         }
         McpCommand::Complete {
             reference,
             argument,
-        } => {
-            mcp::complete(client, &ctx.output, &reference, &argument).await?;
+        } => Some(serde_json::to_value(
+            mcp::complete(client, ctx, &reference, &argument).await?,
+        )?),
+    };
+    Ok(result)
+}
+
+// For CLI use - creates new client connection for single command, drawing any progress
+// notifications the server sends while it runs. Ctrl-C tells the server the request is
+// cancelled, gives it a moment to answer, then fails with Error::Cancelled.
+pub async fn execute_mcp_command(command: McpCommand, target: &str, ctx: &Ctx) -> Result<()> {
+    let target = Target::resolve(target, &ctx.storage()?)?;
+    // Frames are kept so a request interrupted by Ctrl-C can be cancelled by id
+    let frames =
+        trace::traceable(&target).then(|| ProtocolTrace::keeping(ctx.trace_protocol.clone()));
+    let ctx = &ctx.clone().with_trace_protocol(frames.clone());
+    let conn = ProgressConn::new(ctx.output.clone());
+    let (mut client, init_result) = client::get_client_with_connection(ctx, &target, conn).await?;
+    tokio::select! {
+        result = execute_mcp_command_with_client(command, &mut client, &init_result, ctx) => {
+            result?;
+            return Ok(());
         }
+        _ = signal::ctrl_c() => {}
     }
-    Ok(())
+    ctx.output.end_progress()?;
+    match &frames {
+        Some(frames) => cancel_interrupted(&mut client, frames, ctx).await?,
+        None => ctx.output.trace_warn(
+            "Cancelled without telling the server, as requests to HTTP targets can't be traced",
+        )?,
+    }
+    Err(Error::Cancelled)
 }
 
-// For CLI use - creates new client connection for single command
-pub async fn execute_mcp_command(command: McpCommand, target: &str, ctx: &Ctx) -> Result<()> {
-    let target = Target::parse(target)?;
-    let (mut client, init_result) = client::get_client(ctx, &target).await?;
-    execute_mcp_command_with_client(command, &mut client, &init_result, ctx).await
+/// Tells the server the request interrupted by Ctrl-C is cancelled, then
+/// waits up to [`CANCEL_GRACE`] for it to answer. `frames` must be the kept
+/// frames of the client's connection.
+pub async fn cancel_interrupted<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    frames: &ProtocolTrace,
+    ctx: &Ctx,
+) -> Result<()> {
+    let Some(id) = unanswered_request(&frames.frames()) else {
+        return Ok(());
+    };
+    mcp::cancel_request(client, &id, "Interrupted by the user").await?;
+    ctx.output.trace_warn(format!(
+        "Cancelled request {id}, waiting up to {}s for the server to answer",
+        CANCEL_GRACE.as_secs()
+    ))?;
+    let answered =
+        frames.wait_for(|frames| frames.iter().find(|frame| frame.answers(&id)).cloned());
+    let outcome = match time::timeout(CANCEL_GRACE, answered).await {
+        Ok(frame) => match frame.message.get("error") {
+            Some(error) => format!("Request failed: {error}"),
+            None => "The server answered the cancelled request".to_string(),
+        },
+        Err(_) => "The server didn't answer the cancelled request".to_string(),
+    };
+    ctx.output.trace_warn(outcome)?;
+    Ok(())
 }
 
 /// Generate help text for the REPL using clap's built-in help generation
@@ -196,7 +488,29 @@ pub fn generate_repl_help() -> String {
 
     help.push_str("\nAdditional REPL commands:\n");
     help.push_str("  help                 - Show this help message\n");
+    help.push_str("  reconnect            - Reconnect to the server\n");
     help.push_str("  quit/exit            - Exit the REPL\n");
+    help.push_str("  connect <target> [--as NAME] - Open another session and switch to it\n");
+    help.push_str("  servers              - List open sessions\n");
+    help.push_str("  tools, resources     - List tools or resources with numbers\n");
+    help.push_str("  call <N> [args]      - Call the tool numbered N in the last listing\n");
+    help.push_str("  read <N>             - Read the resource numbered N in the last listing\n");
+    help.push_str("  use <name>           - Switch to another session\n");
+    help.push_str("  notifications [type] - List received notifications, optionally of one type\n");
+    help.push_str("  notifications clear  - Discard received notifications\n");
+    help.push_str("  alias [name = cmd]   - Define a command alias, or list aliases\n");
+    help.push_str("  unalias <name>       - Remove an alias\n");
+    help.push_str("  set [name = value]   - Set a variable, or list variables and settings\n");
+    help.push_str("  set <setting> <val>  - Change output: json on|off, color on|off, width N,\n");
+    help.push_str("                         verbosity quiet|normal\n");
+    help.push_str("  unset <name>         - Remove a variable\n");
+    help.push_str("  source [--keep-going] <file> - Run the commands in a script file\n");
+    help.push_str("  $name, ${name}       - Substitute a variable; $LAST is the previous result\n");
+    help.push_str("  <command> > file     - Write the command's output to a file (>> appends)\n");
+    help.push_str(
+        "  calltool <tool> {..} - Pass a JSON object as arguments; an unclosed object or\n",
+    );
+    help.push_str("                         a trailing :json continues on the following lines\n");
 
     help
 }
@@ -214,6 +528,7 @@ mod tests {
         assert!(help.contains("Additional REPL commands:"));
         assert!(help.contains("help"));
         assert!(help.contains("quit/exit"));
+        assert!(help.contains("reconnect"));
 
         // Check that some of the MCP commands are included
         assert!(help.contains("ping"));
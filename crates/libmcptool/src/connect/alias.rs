@@ -0,0 +1,137 @@
+//! Command aliases, such as `ls = listtools`, expanded before a REPL line is
+//! run.
+
+use std::collections::BTreeMap;
+
+use super::complete;
+use crate::{Error, Result, storage::TokenStorage};
+
+/// Aliases by name. Each alias replaces the first word of a line with its
+/// expansion.
+#[derive(Debug, Default)]
+pub struct Aliases {
+    /// Expansions by alias name.
+    aliases: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    /// Loads the aliases saved in the configuration directory.
+    pub fn load(storage: &TokenStorage) -> Result<Self> {
+        Ok(Self {
+            aliases: storage.get_aliases()?,
+        })
+    }
+
+    /// Defines an alias and saves the aliases.
+    pub fn set(&mut self, storage: &TokenStorage, name: &str, expansion: &str) -> Result<()> {
+        validate_alias_name(name)?;
+        if expansion.is_empty() {
+            return Err(Error::Format(format!("Alias '{name}' needs an expansion")));
+        }
+        self.aliases.insert(name.to_string(), expansion.to_string());
+        Ok(storage.store_aliases(&self.aliases)?)
+    }
+
+    /// Removes an alias and saves the aliases.
+    pub fn remove(&mut self, storage: &TokenStorage, name: &str) -> Result<()> {
+        if self.aliases.remove(name).is_none() {
+            return Err(Error::Other(format!("No alias named '{name}'")));
+        }
+        Ok(storage.store_aliases(&self.aliases)?)
+    }
+
+    /// All aliases, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.aliases.iter()
+    }
+
+    /// Replaces the first word of `line` with its expansion, if it is an
+    /// alias. Expansions are not expanded again, so aliases can't loop.
+    pub fn expand(&self, line: &str) -> String {
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match self.aliases.get(first) {
+            Some(expansion) if rest.is_empty() => expansion.clone(),
+            Some(expansion) => format!("{expansion} {rest}"),
+            None => line.to_string(),
+        }
+    }
+}
+
+/// Validates an alias name. Names are single words, and can't shadow a
+/// command.
+fn validate_alias_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(Error::Format(format!(
+            "Invalid alias name '{name}'. Aliases are single words"
+        )));
+    }
+    if complete::command_names()
+        .iter()
+        .any(|command| command == name)
+    {
+        return Err(Error::Format(format!(
+            "'{name}' is a command and can't be used as an alias"
+        )));
+    }
+    Ok(())
+}
+
+/// Parses the `name = expansion` part of an `alias` command.
+pub fn parse_definition(input: &str) -> Result<(&str, &str)> {
+    let (name, expansion) = input
+        .split_once('=')
+        .ok_or_else(|| Error::Format("Usage: alias <name> = <command>".to_string()))?;
+    Ok((name.trim(), expansion.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases() -> Aliases {
+        Aliases {
+            aliases: BTreeMap::from([
+                ("ls".to_string(), "listtools".to_string()),
+                ("rt".to_string(), "readresource".to_string()),
+                ("echo".to_string(), "calltool echo --arg".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let aliases = aliases();
+        assert_eq!(aliases.expand("ls"), "listtools");
+        assert_eq!(
+            aliases.expand("rt file:///a.txt > out.txt"),
+            "readresource file:///a.txt > out.txt"
+        );
+        assert_eq!(
+            aliases.expand("echo text=hi"),
+            "calltool echo --arg text=hi"
+        );
+        assert_eq!(aliases.expand("listtools"), "listtools");
+        assert_eq!(aliases.expand("calltool ls"), "calltool ls");
+    }
+
+    #[test]
+    fn test_validate_alias_name() {
+        assert!(validate_alias_name("ls").is_ok());
+        assert!(validate_alias_name("two words").is_err());
+        assert!(validate_alias_name("listtools").is_err());
+        assert!(validate_alias_name("quit").is_err());
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        assert_eq!(
+            parse_definition("ls = listtools").unwrap(),
+            ("ls", "listtools")
+        );
+        assert_eq!(
+            parse_definition("ct=calltool x --arg a=b").unwrap(),
+            ("ct", "calltool x --arg a=b")
+        );
+        assert!(parse_definition("ls").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+//! Tab completion for the REPL.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use clap::CommandFactory;
+use rustyline::{
+    Context, Helper, completion::Completer, highlight::Highlighter, hint::Hinter,
+    validate::Validator,
+};
+use tmcp::{
+    Client, ClientHandler, ServerAPI,
+    schema::{InitializeResult, ServerNotification},
+};
+
+use crate::{command::ReplCommandWrapper, ctx::Ctx};
+
+/// Commands handled by the REPL itself rather than sent to the server.
+const REPL_COMMANDS: [&str; 17] = [
+    "help",
+    "quit",
+    "exit",
+    "reconnect",
+    "set",
+    "unset",
+    "connect",
+    "servers",
+    "use",
+    "alias",
+    "unalias",
+    "notifications",
+    "source",
+    "tools",
+    "resources",
+    "call",
+    "read",
+];
+
+/// Commands whose first argument is a resource URI.
+const RESOURCE_COMMANDS: [&str; 4] = [
+    "readresource",
+    "subscriberesource",
+    "unsubscriberesource",
+    "read",
+];
+
+/// A server list cached for completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum List {
+    /// Tool names.
+    Tools,
+    /// Prompt names.
+    Prompts,
+    /// Resource URIs.
+    Resources,
+}
+
+impl List {
+    /// All lists.
+    const ALL: [Self; 3] = [Self::Tools, Self::Prompts, Self::Resources];
+
+    /// The list a list-changed notification is about, if it is one.
+    pub fn changed_by(notification: &ServerNotification) -> Option<Self> {
+        match notification {
+            ServerNotification::ToolListChanged => Some(Self::Tools),
+            ServerNotification::PromptListChanged => Some(Self::Prompts),
+            ServerNotification::ResourceListChanged => Some(Self::Resources),
+            _ => None,
+        }
+    }
+
+    /// Name of the list, for messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Tools => "Tools",
+            Self::Prompts => "Prompts",
+            Self::Resources => "Resources",
+        }
+    }
+
+    /// Whether the server advertises the capability the list belongs to.
+    fn is_supported(self, init_result: &InitializeResult) -> bool {
+        let capabilities = &init_result.capabilities;
+        match self {
+            Self::Tools => capabilities.tools.is_some(),
+            Self::Prompts => capabilities.prompts.is_some(),
+            Self::Resources => capabilities.resources.is_some(),
+        }
+    }
+
+    /// Loads the names in the list from the server.
+    async fn fetch<C: ClientHandler + 'static>(
+        self,
+        client: &mut Client<C>,
+        ctx: &Ctx,
+    ) -> Option<Vec<String>> {
+        match self {
+            Self::Tools => {
+                let result = ctx.request(client.list_tools(None)).await.ok()?;
+                Some(result.tools.into_iter().map(|tool| tool.name).collect())
+            }
+            Self::Prompts => {
+                let result = ctx.request(client.list_prompts(None)).await.ok()?;
+                Some(
+                    result
+                        .prompts
+                        .into_iter()
+                        .map(|prompt| prompt.name)
+                        .collect(),
+                )
+            }
+            Self::Resources => {
+                let result = ctx.request(client.list_resources(None)).await.ok()?;
+                Some(
+                    result
+                        .resources
+                        .into_iter()
+                        .map(|resource| resource.uri)
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Names offered for completion, refreshed from the server's lists.
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    /// REPL and MCP command names.
+    commands: Vec<String>,
+    /// Tool names.
+    tools: Vec<String>,
+    /// Prompt names.
+    prompts: Vec<String>,
+    /// Resource URIs.
+    resources: Vec<String>,
+}
+
+/// Names of all REPL and MCP commands, sorted.
+pub fn command_names() -> Vec<String> {
+    let mut commands: Vec<String> = ReplCommandWrapper::command()
+        .get_subcommands()
+        .map(|cmd| cmd.get_name().to_string())
+        .chain(REPL_COMMANDS.iter().map(|cmd| cmd.to_string()))
+        .collect();
+    commands.sort();
+    commands.dedup();
+    commands
+}
+
+impl Completions {
+    /// Completions holding only the command names.
+    pub fn new() -> Self {
+        Self {
+            commands: command_names(),
+            ..Self::default()
+        }
+    }
+
+    /// Fetches tool, prompt and resource names for the capabilities the server
+    /// advertises. Lists that fail to load are left empty, since completion is
+    /// only a convenience.
+    pub async fn fetch<C: ClientHandler + 'static>(
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        init_result: &InitializeResult,
+    ) -> Self {
+        let mut completions = Self::new();
+        for list in List::ALL {
+            if list.is_supported(init_result)
+                && let Some(names) = list.fetch(client, ctx).await
+            {
+                *completions.names_mut(list) = names;
+            }
+        }
+        completions
+    }
+
+    /// Reloads one list after the server said it changed, returning a
+    /// summary of the names added and removed. The list is left empty if it
+    /// fails to load.
+    pub async fn refresh<C: ClientHandler + 'static>(
+        &mut self,
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        list: List,
+    ) -> String {
+        let names = list.fetch(client, ctx).await.unwrap_or_default();
+        let summary = diff(self.names_mut(list), &names);
+        *self.names_mut(list) = names;
+        summary
+    }
+
+    /// Forgets the names in a list that is known to be out of date.
+    pub fn invalidate(&mut self, list: List) {
+        self.names_mut(list).clear();
+    }
+
+    /// The names in a list.
+    fn names_mut(&mut self, list: List) -> &mut Vec<String> {
+        match list {
+            List::Tools => &mut self.tools,
+            List::Prompts => &mut self.prompts,
+            List::Resources => &mut self.resources,
+        }
+    }
+
+    /// Completes the word ending at the end of `line`. Returns the position the
+    /// word starts at and the candidates that could replace it.
+    pub fn complete(&self, line: &str) -> (usize, Vec<String>) {
+        let word = line.split(char::is_whitespace).next_back().unwrap_or("");
+        let start = line.len() - word.len();
+        let preceding: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let candidates: &[String] = match preceding.as_slice() {
+            [] => &self.commands,
+            ["calltool" | "call"] => &self.tools,
+            ["getprompt"] => &self.prompts,
+            [command] if RESOURCE_COMMANDS.contains(command) => &self.resources,
+            _ => &[],
+        };
+        let matches = candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .cloned()
+            .collect();
+        (start, matches)
+    }
+}
+
+/// Describes the names added to and removed from a list, such as
+/// `+echo -add`.
+fn diff(old: &[String], new: &[String]) -> String {
+    let added = new
+        .iter()
+        .filter(|name| !old.contains(name))
+        .map(|name| format!("+{name}"));
+    let removed = old
+        .iter()
+        .filter(|name| !new.contains(name))
+        .map(|name| format!("-{name}"));
+    let changes: Vec<String> = added.chain(removed).collect();
+    if changes.is_empty() {
+        "no changes".to_string()
+    } else {
+        changes.join(" ")
+    }
+}
+
+/// Line editor helper that completes from shared [`Completions`].
+pub struct ReplHelper {
+    /// Completions, updated by the REPL as the server's lists change.
+    completions: Arc<Mutex<Completions>>,
+}
+
+impl ReplHelper {
+    pub fn new(completions: Arc<Mutex<Completions>>) -> Self {
+        Self { completions }
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let completions = self
+            .completions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(completions.complete(&line[..pos]))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completions() -> Completions {
+        Completions {
+            tools: vec!["echo".to_string(), "encode".to_string(), "add".to_string()],
+            prompts: vec!["greeting".to_string()],
+            resources: vec!["file:///a.txt".to_string(), "file:///b.txt".to_string()],
+            ..Completions::new()
+        }
+    }
+
+    #[test]
+    fn test_complete_command_names() {
+        let completions = completions();
+        let (start, matches) = completions.complete("list");
+        assert_eq!(start, 0);
+        assert!(matches.contains(&"listtools".to_string()));
+        assert!(matches.contains(&"listprompts".to_string()));
+        assert!(matches.iter().all(|m| m.starts_with("list")));
+
+        let (_, matches) = completions.complete("rec");
+        assert_eq!(matches, ["reconnect"]);
+    }
+
+    #[test]
+    fn test_complete_arguments() {
+        let completions = completions();
+        assert_eq!(
+            completions.complete("calltool e"),
+            (9, vec!["echo".to_string(), "encode".to_string()])
+        );
+        assert_eq!(
+            completions.complete("getprompt "),
+            (10, vec!["greeting".to_string()])
+        );
+        assert_eq!(
+            completions.complete("readresource file:///b").1,
+            ["file:///b.txt"]
+        );
+        assert_eq!(completions.complete("call a").1, ["add"]);
+        assert!(completions.complete("calltool echo --arg ").1.is_empty());
+        assert!(completions.complete("ping x").1.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            diff(&names(&["echo", "add"]), &names(&["echo", "encode"])),
+            "+encode -add"
+        );
+        assert_eq!(diff(&names(&["echo"]), &names(&["echo"])), "no changes");
+        assert_eq!(diff(&[], &names(&["echo"])), "+echo");
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut completions = completions();
+        completions.invalidate(List::Tools);
+        assert!(completions.complete("calltool e").1.is_empty());
+        assert_eq!(completions.complete("getprompt ").1, ["greeting"]);
+    }
+}
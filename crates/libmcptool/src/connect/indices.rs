@@ -0,0 +1,155 @@
+//! Numbered shortcuts for the tools and resources of the most recent listing,
+//! as used by `call N` and `read N`.
+
+use serde_json::Value;
+
+use crate::{Error, Result, output::Output};
+
+/// Listing results that are numbered: the array holding the items, and the
+/// field naming each item.
+const LISTINGS: [(&str, &str); 2] = [("tools", "name"), ("resources", "uri")];
+
+/// Tool names and resource URIs in the order they were last listed.
+#[derive(Debug, Default)]
+pub struct Indices {
+    /// Tool names from the most recent tool listing.
+    tools: Vec<String>,
+    /// Resource URIs from the most recent resource listing.
+    resources: Vec<String>,
+}
+
+impl Indices {
+    /// Remembers the tools or resources in a listing command's result. Other
+    /// results are ignored.
+    pub fn update(&mut self, result: &Value) {
+        if let Some(names) = names(result, "tools", "name") {
+            self.tools = names;
+        }
+        if let Some(uris) = names(result, "resources", "uri") {
+            self.resources = uris;
+        }
+    }
+
+    /// Rewrites `call N ...` to `calltool <tool> ...` and `read N` to
+    /// `readresource <uri>`, using the numbers from the most recent listing. A
+    /// name can be given instead of a number. Other lines are returned as is.
+    pub fn expand(&self, line: &str) -> Result<String> {
+        let (command, rest) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(command, rest)| (command, rest.trim_start()));
+        let (index, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let (expansion, names, item, listing) = match command {
+            "call" => ("calltool", &self.tools, "tool", "tools"),
+            "read" => ("readresource", &self.resources, "resource", "resources"),
+            _ => return Ok(line.to_string()),
+        };
+        if index.is_empty() {
+            return Err(Error::Format(format!("Usage: {command} <number|name>")));
+        }
+        let name = lookup(names, index, item, listing)?;
+        if args.is_empty() {
+            Ok(format!("{expansion} {name}"))
+        } else {
+            Ok(format!("{expansion} {name} {args}"))
+        }
+    }
+}
+
+/// The names of the items in a listing result, if it is one.
+fn names(result: &Value, key: &str, field: &str) -> Option<Vec<String>> {
+    let items = result.get(key)?.as_array()?;
+    Some(
+        items
+            .iter()
+            .filter_map(|item| item.get(field)?.as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// Resolves the argument of `call` or `read` to a name. Numbers count from 1.
+fn lookup<'a>(names: &'a [String], index: &'a str, item: &str, listing: &str) -> Result<&'a str> {
+    let Ok(number) = index.parse::<usize>() else {
+        return Ok(index);
+    };
+    number
+        .checked_sub(1)
+        .and_then(|i| names.get(i))
+        .map(String::as_str)
+        .ok_or_else(|| {
+            Error::Format(format!(
+                "No {item} {number} in the last listing. Type '{listing}' to list them"
+            ))
+        })
+}
+
+/// Prints the tools or resources in a listing result, numbered for `call N`
+/// and `read N`, with the first line of each description.
+pub fn display(output: &Output, result: &Value) -> Result<()> {
+    let Some((items, field)) = LISTINGS
+        .iter()
+        .find_map(|(key, field)| Some((result.get(key)?.as_array()?, field)))
+    else {
+        return Ok(());
+    };
+    if items.is_empty() {
+        output.text("Nothing listed.")?;
+        return Ok(());
+    }
+    let width = items.len().to_string().len();
+    for (i, item) in items.iter().enumerate() {
+        let name = item.get(field).and_then(Value::as_str).unwrap_or_default();
+        let description = item
+            .get("description")
+            .and_then(Value::as_str)
+            .and_then(|description| description.lines().next())
+            .map(|line| format!(" - {line}"))
+            .unwrap_or_default();
+        output.text(format!("{:>width$}  {name}{description}", i + 1))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn indices() -> Indices {
+        let mut indices = Indices::default();
+        indices.update(&json!({"tools": [{"name": "echo"}, {"name": "add"}]}));
+        indices.update(&json!({"resources": [{"uri": "file:///a.txt", "name": "a"}]}));
+        indices
+    }
+
+    #[test]
+    fn test_expand() {
+        let indices = indices();
+        assert_eq!(
+            indices.expand("call 2 --arg x=1").unwrap(),
+            "calltool add --arg x=1"
+        );
+        assert_eq!(indices.expand("call 1").unwrap(), "calltool echo");
+        assert_eq!(
+            indices.expand("call 1 {\"text\": \"hi\"}").unwrap(),
+            "calltool echo {\"text\": \"hi\"}"
+        );
+        assert_eq!(
+            indices.expand("read 1").unwrap(),
+            "readresource file:///a.txt"
+        );
+        assert_eq!(indices.expand("call echo").unwrap(), "calltool echo");
+        assert_eq!(indices.expand("listtools").unwrap(), "listtools");
+        assert!(indices.expand("call 3").is_err());
+        assert!(indices.expand("call 0").is_err());
+        assert!(indices.expand("read").is_err());
+    }
+
+    #[test]
+    fn test_update_ignores_other_results() {
+        let mut indices = indices();
+        indices.update(&json!({"content": [], "isError": false}));
+        indices.update(&json!({"capabilities": {"tools": {}}}));
+        assert_eq!(indices.expand("call 1").unwrap(), "calltool echo");
+    }
+}
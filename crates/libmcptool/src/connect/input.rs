@@ -0,0 +1,93 @@
+//! Line input for the REPL, read on a dedicated thread.
+
+use std::{sync::mpsc as std_mpsc, thread};
+
+use rustyline::{Editor, error::ReadlineError, history::DefaultHistory};
+use tokio::sync::mpsc;
+
+use super::{complete::ReplHelper, multiline};
+use crate::Result;
+
+/// Reads lines from the terminal on a background thread, so the REPL can keep
+/// handling notifications while waiting for input.
+///
+/// A single editor is kept for the whole session, so history works across
+/// lines.
+pub struct LineReader {
+    /// Prompts for lines to read, sent to the input thread.
+    prompts: std_mpsc::Sender<String>,
+    /// Lines read by the input thread.
+    lines: mpsc::UnboundedReceiver<rustyline::Result<String>>,
+    /// Whether a prompt has been sent whose line has not been received yet.
+    pending: bool,
+}
+
+impl LineReader {
+    /// Starts the input thread, completing input with `helper`.
+    pub fn spawn(helper: ReplHelper) -> Result<Self> {
+        let mut editor = Editor::<ReplHelper, DefaultHistory>::new()?;
+        editor.set_helper(Some(helper));
+        let (prompts, prompt_receiver) = std_mpsc::channel::<String>();
+        let (line_sender, lines) = mpsc::unbounded_channel();
+
+        thread::spawn(move || {
+            for prompt in prompt_receiver {
+                let result = editor.readline(&prompt);
+                if let Ok(line) = &result
+                    && !line.trim().is_empty()
+                {
+                    // History failures are not worth interrupting input for
+                    _ = editor.add_history_entry(line.as_str());
+                }
+                if line_sender.send(result).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            prompts,
+            lines,
+            pending: false,
+        })
+    }
+
+    /// Whether a prompt is showing, waiting for a line to be entered.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Reads a line, showing the prompt. This is cancel-safe: if the future is
+    /// dropped, the next call picks up the line that was being read.
+    pub async fn read_line(&mut self, prompt: &str) -> rustyline::Result<String> {
+        if !self.pending {
+            self.prompts
+                .send(prompt.to_string())
+                .map_err(|_| ReadlineError::Eof)?;
+            self.pending = true;
+        }
+        let line = self.lines.recv().await.unwrap_or(Err(ReadlineError::Eof));
+        self.pending = false;
+        line
+    }
+
+    /// Reads the rest of a multi-line JSON entry that starts with `line`, if it
+    /// needs more input. A trailing `:json` is removed. Returns `None` if the
+    /// entry is abandoned with Ctrl-C.
+    pub async fn read_continuation(&mut self, line: String) -> rustyline::Result<Option<String>> {
+        let command = multiline::strip_json_suffix(&line).map(str::to_string);
+        let json_mode = command.is_some();
+        let mut entry = command.unwrap_or(line);
+        while multiline::needs_more(&entry, json_mode) {
+            match self.read_line("...> ").await {
+                Ok(more) => {
+                    entry.push('\n');
+                    entry.push_str(&more);
+                }
+                Err(ReadlineError::Interrupted) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Some(entry))
+    }
+}
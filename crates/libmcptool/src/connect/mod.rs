@@ -0,0 +1,909 @@
+//! Interactive REPL for MCP server connections.
+
+mod alias;
+mod complete;
+mod indices;
+mod input;
+mod multiline;
+mod notifications;
+mod redirect;
+mod sessions;
+mod settings;
+mod transcript;
+mod vars;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use rustyline::error::ReadlineError;
+use serde_json::{Map, json};
+use tmcp::{
+    ClientCtx, ClientHandler, Result as McpResult, ServerAPI,
+    schema::{InitializeResult, ServerNotification},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader, stdin},
+    signal,
+    sync::mpsc,
+};
+#[cfg(unix)]
+use tokio::{
+    signal::unix::{self, SignalKind},
+    task::{self, JoinHandle},
+};
+
+use self::{
+    alias::Aliases,
+    complete::{Completions, List, ReplHelper},
+    indices::Indices,
+    input::LineReader,
+    notifications::NotificationLog,
+    sessions::{MAIN_SESSION, SessionNotification, Sessions},
+    settings::Setting,
+    transcript::{Event, Transcript},
+    vars::Variables,
+};
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    command::{
+        McpCommand, ReplCommandWrapper, cancel_interrupted, execute_mcp_command_with_client,
+        generate_repl_help,
+    },
+    ctx::Ctx,
+    frametrace::ProtocolTrace,
+    output::initresult,
+    report::{CaseStatus, Report, TestCase},
+    target::Target,
+    trace,
+};
+
+/// How deeply `source` commands can nest, to stop a script sourcing itself
+/// forever.
+const MAX_SOURCE_DEPTH: usize = 16;
+
+/// Client connection that forwards server notifications to a channel.
+#[derive(Clone)]
+struct NotificationClientConn {
+    /// Name of the session the connection belongs to.
+    session: String,
+    /// Sender for server notifications, tagged with the session name.
+    notification_sender: mpsc::UnboundedSender<SessionNotification>,
+}
+
+#[async_trait::async_trait]
+impl ClientHandler for NotificationClientConn {
+    async fn notification(
+        &self,
+        _context: &ClientCtx,
+        notification: ServerNotification,
+    ) -> McpResult<()> {
+        // Best-effort notification delivery - receiver may be gone during shutdown
+        let _send_result = self
+            .notification_sender
+            .send((self.session.clone(), notification));
+        Ok(())
+    }
+}
+
+/// A connection to the server, which can be re-established if it drops.
+struct Session {
+    /// The target the session was opened with.
+    target: Target,
+    /// Handler shared by every connection in the session, so notifications
+    /// keep arriving on the same channel after a reconnect.
+    conn: NotificationClientConn,
+    /// The connected client.
+    client: Connected<NotificationClientConn>,
+    /// Initialization result from the most recent connection.
+    init_result: InitializeResult,
+    /// Frames kept so an interrupted request can be cancelled by id, for
+    /// targets whose frames can be traced.
+    frames: Option<ProtocolTrace>,
+    /// Names offered for tab completion.
+    completions: Completions,
+    /// Tools and resources numbered by the most recent listing.
+    indices: Indices,
+}
+
+impl Session {
+    /// Connects to the target.
+    async fn connect(ctx: &Ctx, target: Target, conn: NotificationClientConn) -> Result<Self> {
+        let frames =
+            trace::traceable(&target).then(|| ProtocolTrace::keeping(ctx.trace_protocol.clone()));
+        let ctx = &ctx.clone().with_trace_protocol(frames.clone());
+        let (client, init_result) =
+            client::get_client_with_connection(ctx, &target, conn.clone()).await?;
+        Ok(Self {
+            target,
+            conn,
+            client,
+            init_result,
+            frames,
+            completions: Completions::new(),
+            indices: Indices::default(),
+        })
+    }
+
+    /// Reloads the tool, prompt and resource names used for completion.
+    async fn refresh_completions(&mut self, ctx: &Ctx) {
+        self.completions = Completions::fetch(&mut self.client, ctx, &self.init_result).await;
+    }
+
+    /// Checks whether the server still answers pings, giving it as long as
+    /// any other request.
+    async fn is_alive(&mut self, ctx: &Ctx) -> bool {
+        ctx.request(self.client.ping()).await.is_ok()
+    }
+
+    /// Connects to the original target again, replacing the current client.
+    /// The current client is kept if reconnecting fails.
+    async fn reconnect(&mut self, ctx: &Ctx) -> Result<()> {
+        let traced = ctx.clone().with_trace_protocol(self.frames.clone());
+        let (client, init_result) =
+            client::get_client_with_connection(&traced, &self.target, self.conn.clone()).await?;
+        self.client = client;
+        self.init_result = init_result;
+        self.refresh_completions(ctx).await;
+        ctx.output.trace_success(format!(
+            "Reconnected to: {} v{}",
+            self.init_result.server_info.name, self.init_result.server_info.version
+        ))?;
+        Ok(())
+    }
+
+    /// Reconnects, reporting the outcome without failing the REPL.
+    async fn reconnect_with_banner(&mut self, ctx: &Ctx) -> Result<()> {
+        ctx.output
+            .h2(format!("Connection lost - reconnecting to {}", self.target))?;
+        if let Err(e) = self.reconnect(ctx).await {
+            ctx.output.trace_error(format!("Reconnect failed: {e}"))?;
+            ctx.output
+                .text("Type 'reconnect' to try again, or 'quit' to exit.")?;
+        }
+        Ok(())
+    }
+}
+
+/// State kept across the lines of a REPL session.
+struct Repl {
+    /// Open sessions.
+    sessions: Sessions,
+    /// Variables set with `set`.
+    vars: Variables,
+    /// Command aliases.
+    aliases: Aliases,
+    /// Notifications received from the servers.
+    notifications: NotificationLog,
+    /// Transcript the session is recorded to, if any.
+    transcript: Option<Transcript>,
+    /// Receives notifications from every session.
+    notification_receiver: mpsc::UnboundedReceiver<SessionNotification>,
+    /// How many `source` commands are running.
+    source_depth: usize,
+    /// Whether lists are reloaded when the server says they changed, rather
+    /// than just forgotten.
+    refresh_lists: bool,
+    /// Changes to the servers' lists not yet shown.
+    list_changes: Vec<String>,
+    /// The outcome of each entry of a script run with `--report`, as test
+    /// cases. Entries of sourced scripts aren't counted separately.
+    cases: Option<Vec<TestCase>>,
+}
+
+impl Repl {
+    /// Records the outcome of a top-level script entry as a test case, if a
+    /// report is being made.
+    fn record_case(
+        &mut self,
+        source: &str,
+        number: usize,
+        entry: &str,
+        flow: &Flow,
+        started: Instant,
+    ) {
+        if self.source_depth != 1 {
+            return;
+        }
+        let Some(cases) = &mut self.cases else {
+            return;
+        };
+        let (status, message) = match flow {
+            Flow::Failed => (CaseStatus::Failed, Some("Command failed".to_string())),
+            Flow::Continue | Flow::Quit => (CaseStatus::Passed, None),
+        };
+        cases.push(TestCase {
+            classname: source.to_string(),
+            name: case_name(number, entry),
+            status,
+            message,
+            output: Vec::new(),
+            time: started.elapsed(),
+        });
+    }
+
+    /// Records a top-level script entry that wasn't run because an earlier
+    /// one failed.
+    fn record_skipped(&mut self, source: &str, number: usize, entry: &str) {
+        if self.source_depth != 1 {
+            return;
+        }
+        if let Some(cases) = &mut self.cases {
+            cases.push(TestCase {
+                classname: source.to_string(),
+                name: case_name(number, entry),
+                status: CaseStatus::Skipped,
+                message: Some("Not run after an earlier command failed".to_string()),
+                output: Vec::new(),
+                time: Duration::ZERO,
+            });
+        }
+    }
+
+    /// Connects to the target as the main session.
+    async fn start(
+        ctx: &Ctx,
+        target: Target,
+        transcript: Option<PathBuf>,
+        refresh_lists: bool,
+    ) -> Result<Self> {
+        let transcript = transcript
+            .map(|path| Transcript::create(&path))
+            .transpose()?;
+        ctx.output.status(format!("Connecting to {target}..."))?;
+
+        // Notifications from every session arrive on one channel
+        let (notification_sender, notification_receiver) = mpsc::unbounded_channel();
+        let mut sessions = Sessions::new(notification_sender);
+        let session = sessions.open(ctx, MAIN_SESSION, target).await?;
+        ctx.output.trace_success(format!(
+            "Connected to: {} v{}",
+            session.init_result.server_info.name, session.init_result.server_info.version
+        ))?;
+
+        Ok(Self {
+            sessions,
+            vars: Variables::default(),
+            aliases: Aliases::load(&ctx.storage()?)?,
+            notifications: NotificationLog::default(),
+            transcript,
+            notification_receiver,
+            source_depth: 0,
+            refresh_lists,
+            list_changes: Vec::new(),
+            cases: None,
+        })
+    }
+
+    /// Records an event in the transcript, if one is being written.
+    fn record(&mut self, event: Event<'_>) -> Result<()> {
+        match &mut self.transcript {
+            Some(transcript) => transcript.record(self.sessions.current_name(), event),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What the REPL should do after handling a line.
+enum Flow {
+    /// Read the next line.
+    Continue,
+    /// Leave the REPL.
+    Quit,
+    /// The command failed. Scripts stop here unless run with `--keep-going`.
+    Failed,
+}
+
+/// Connects to a target and runs the REPL, recording the session to
+/// `transcript` if given. When stdin is not a terminal, commands are read from
+/// it line by line instead, as from a script. With `refresh_lists` off, lists
+/// the server says changed are dropped from completion rather than reloaded.
+pub async fn connect_command(
+    ctx: &Ctx,
+    target: Target,
+    transcript: Option<PathBuf>,
+    refresh_lists: bool,
+) -> Result<()> {
+    // Output settings can be changed with `set` for the rest of the session
+    let ctx = &mut ctx.clone();
+    let mut repl = Repl::start(ctx, target, transcript, refresh_lists).await?;
+    if !atty::is(atty::Stream::Stdin) {
+        return run_stdin(ctx, &mut repl).await;
+    }
+    ctx.output
+        .text("Type 'help' for available commands, 'quit' to exit\n")?;
+
+    let mut reader = LineReader::spawn(ReplHelper::new(repl.sessions.completions()))?;
+    #[cfg(unix)]
+    let resizes = follow_resizes(ctx)?;
+
+    loop {
+        // Pick up resizes on platforms without a resize signal
+        ctx.output.refresh_width();
+        // Notifications are announced between commands rather than printed
+        // over the line being typed
+        if !reader.is_pending() {
+            announce(ctx, &mut repl)?;
+        }
+        let prompt = repl.sessions.prompt();
+        tokio::select! {
+            // Handle incoming notifications
+            notification = repl.notification_receiver.recv() => {
+                if let Some((name, notification)) = notification {
+                    handle_notification(ctx, &mut repl, &name, notification).await?;
+                }
+            }
+            // Handle user input without blocking notifications
+            readline = reader.read_line(&prompt) => {
+                let entry = match readline {
+                    Ok(line) => reader.read_continuation(line).await,
+                    Err(e) => Err(e),
+                };
+                match entry {
+                    Ok(Some(line)) => {
+                        let flow = handle_line(ctx, &mut repl, line.trim()).await?;
+                        repl.sessions.sync_completions();
+                        if let Flow::Quit = flow {
+                            break;
+                        }
+                    }
+                    // A multi-line entry was abandoned
+                    Ok(None) => {}
+                    Err(ReadlineError::Interrupted) => {
+                        ctx.output.text("CTRL-C")?;
+                        break;
+                    }
+                    Err(ReadlineError::Eof) => {
+                        ctx.output.text("CTRL-D")?;
+                        break;
+                    }
+                    Err(err) => {
+                        ctx.output.trace_error(format!("Error: {err:?}"))?;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    resizes.abort();
+    Ok(())
+}
+
+/// Re-reads the terminal width whenever the terminal is resized, so output
+/// from commands and notifications wraps to the new width.
+#[cfg(unix)]
+fn follow_resizes(ctx: &Ctx) -> Result<JoinHandle<()>> {
+    let mut resized = unix::signal(SignalKind::window_change())?;
+    let output = ctx.output.clone();
+    Ok(task::spawn(async move {
+        while resized.recv().await.is_some() {
+            output.refresh_width();
+        }
+    }))
+}
+
+/// Connects to a target and runs the REPL commands in a script file. Stops at
+/// the first command that fails unless `keep_going` is set; the command fails
+/// if any script command did.
+pub async fn script_command(
+    ctx: &Ctx,
+    target: Target,
+    path: &Path,
+    keep_going: bool,
+    reports: &[PathBuf],
+) -> Result<()> {
+    let ctx = &mut ctx.clone();
+    let suite = format!("mcptool script {} {target}", path.display());
+    let mut repl = Repl::start(ctx, target, None, true).await?;
+    if !reports.is_empty() {
+        repl.cases = Some(Vec::new());
+    }
+    let result = run_script(ctx, &mut repl, path, keep_going).await;
+    if let Some(cases) = repl.cases.take() {
+        let report = Report { suite, cases };
+        for path in reports {
+            report.write(path)?;
+        }
+    }
+    result
+}
+
+/// Runs the commands in a script file, one entry at a time. Blank lines and
+/// lines starting with `#` are skipped.
+async fn run_script(ctx: &mut Ctx, repl: &mut Repl, path: &Path, keep_going: bool) -> Result<()> {
+    if repl.source_depth >= MAX_SOURCE_DEPTH {
+        return Err(Error::Other(format!(
+            "Scripts are nested more than {MAX_SOURCE_DEPTH} deep"
+        )));
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+
+    repl.source_depth += 1;
+    let result = run_entries(ctx, repl, path, &contents, keep_going).await;
+    repl.source_depth -= 1;
+    result
+}
+
+/// Runs the entries of a script, see [`run_script`].
+async fn run_entries(
+    ctx: &mut Ctx,
+    repl: &mut Repl,
+    path: &Path,
+    contents: &str,
+    keep_going: bool,
+) -> Result<()> {
+    let source = path.display().to_string();
+    let entries = multiline::script_entries(contents);
+    let mut failed = 0;
+    for (index, (number, entry)) in entries.iter().enumerate() {
+        let started = Instant::now();
+        let flow = run_entry(ctx, repl, entry).await?;
+        repl.record_case(&source, *number, entry, &flow, started);
+        match flow {
+            Flow::Continue => {}
+            Flow::Quit => break,
+            Flow::Failed if keep_going => failed += 1,
+            Flow::Failed => {
+                for (number, entry) in &entries[index + 1..] {
+                    repl.record_skipped(&source, *number, entry);
+                }
+                return Err(entry_failed(&source, *number));
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} command(s) in {source} failed"
+        )));
+    }
+    Ok(())
+}
+
+/// Runs commands piped to stdin as they arrive, stopping at the first one that
+/// fails.
+async fn run_stdin(ctx: &mut Ctx, repl: &mut Repl) -> Result<()> {
+    let mut lines = BufReader::new(stdin()).lines();
+    let mut entries = multiline::Entries::default();
+    while let Some(line) = lines.next_line().await? {
+        let Some((number, entry)) = entries.push(&line) else {
+            continue;
+        };
+        match run_entry(ctx, repl, &entry).await? {
+            Flow::Continue => {}
+            Flow::Quit => return Ok(()),
+            Flow::Failed => return Err(entry_failed("stdin", number)),
+        }
+    }
+    // An unclosed JSON entry at the end of the input is run as is, and fails
+    if let Some((number, entry)) = entries.finish()
+        && let Flow::Failed = run_entry(ctx, repl, &entry).await?
+    {
+        return Err(entry_failed("stdin", number));
+    }
+    Ok(())
+}
+
+/// Echoes and runs one script entry, then handles the notifications that
+/// arrived meanwhile.
+async fn run_entry(ctx: &mut Ctx, repl: &mut Repl, entry: &str) -> Result<Flow> {
+    ctx.output.text(format!("> {entry}"))?;
+    let flow = handle_line(ctx, repl, entry).await?;
+    drain_notifications(ctx, repl).await?;
+    Ok(flow)
+}
+
+/// The name of a script entry's test case: its line number and first line.
+fn case_name(number: usize, entry: &str) -> String {
+    format!(
+        "{number}: {}",
+        entry.lines().next().unwrap_or_default().trim()
+    )
+}
+
+/// The error for a script entry that failed.
+fn entry_failed(source: &str, number: usize) -> Error {
+    Error::Other(format!("{source}:{number}: command failed, stopping"))
+}
+
+/// Handles the notifications that arrived while a script command ran.
+async fn drain_notifications(ctx: &Ctx, repl: &mut Repl) -> Result<()> {
+    while let Ok((name, notification)) = repl.notification_receiver.try_recv() {
+        handle_notification(ctx, repl, &name, notification).await?;
+    }
+    announce(ctx, repl)
+}
+
+/// Tells the user about notifications and list changes since they were last
+/// told.
+fn announce(ctx: &Ctx, repl: &mut Repl) -> Result<()> {
+    for change in repl.list_changes.drain(..) {
+        ctx.output.note(change)?;
+    }
+    match repl.notifications.take_unannounced() {
+        0 => {}
+        1 => ctx.output.note("[1 new notification]")?,
+        count => ctx.output.note(format!("[{count} new notifications]"))?,
+    }
+    Ok(())
+}
+
+/// Adds a notification from one of the sessions to the backlog. When the
+/// server says a list changed, reloads it and notes the names added and
+/// removed.
+async fn handle_notification(
+    ctx: &Ctx,
+    repl: &mut Repl,
+    name: &str,
+    notification: ServerNotification,
+) -> Result<()> {
+    if let Some(transcript) = &mut repl.transcript {
+        transcript.record(
+            name,
+            Event::Notification {
+                notification: &notification,
+            },
+        )?;
+    }
+    let list = List::changed_by(&notification);
+    repl.notifications.push(name, notification);
+    let show_session = repl.sessions.is_multiple();
+    if let Some(list) = list
+        && let Some(session) = repl.sessions.get_mut(name)
+    {
+        let change = if repl.refresh_lists {
+            session
+                .completions
+                .refresh(&mut session.client, ctx, list)
+                .await
+        } else {
+            session.completions.invalidate(list);
+            "not reloaded".to_string()
+        };
+        let prefix = if show_session {
+            format!("[{name}] ")
+        } else {
+            String::new()
+        };
+        repl.list_changes
+            .push(format!("{prefix}{} changed: {change}", list.label()));
+        repl.sessions.sync_completions();
+    }
+    Ok(())
+}
+
+/// Handles a single line of REPL input, sending its output to a file if it
+/// ends in a redirect.
+async fn handle_line(ctx: &mut Ctx, repl: &mut Repl, line: &str) -> Result<Flow> {
+    if !line.is_empty() {
+        repl.record(Event::Command { line })?;
+    }
+    let (line, redirect) = match redirect::parse(line) {
+        Ok(parsed) => parsed,
+        Err(e) => return report(ctx, Err(e)),
+    };
+    let output_ctx = match redirect.map(|redirect| redirect.open()).transpose() {
+        Ok(Some(file)) => ctx.clone().with_output(ctx.output.with_sink(file)),
+        Ok(None) => ctx.clone(),
+        Err(e) => return report(ctx, Err(e)),
+    };
+    run_line(ctx, &output_ctx, repl, line).await
+}
+
+/// Runs a REPL command. Results are written to `output_ctx`, while errors and
+/// connection status go to `ctx`.
+async fn run_line(ctx: &mut Ctx, output_ctx: &Ctx, repl: &mut Repl, line: &str) -> Result<Flow> {
+    let line = &repl.aliases.expand(line);
+    let line = &match repl.sessions.current_mut().indices.expand(line) {
+        Ok(line) => line,
+        Err(e) => return report(ctx, Err(e)),
+    };
+    let (command, rest) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(command, rest)| (command, rest.trim()));
+    let result = match command {
+        "" => Ok(()),
+        "quit" | "exit" => {
+            ctx.output.text("Goodbye!")?;
+            return Ok(Flow::Quit);
+        }
+        "help" => {
+            output_ctx.output.h1("Available commands")?;
+            output_ctx.output.text(generate_repl_help())?;
+            Ok(())
+        }
+        "init" => {
+            output_ctx.output.note(
+                "Showing initialization result from the current connection (not re-initializing)",
+            )?;
+            initresult::init_result(&output_ctx.output, &repl.sessions.current_mut().init_result)?;
+            Ok(())
+        }
+        "reconnect" => repl
+            .sessions
+            .current_mut()
+            .reconnect(ctx)
+            .await
+            .map_err(|e| Error::Other(format!("Reconnect failed: {e}"))),
+        "connect" => open_session(ctx, &mut repl.sessions, rest)
+            .await
+            .map_err(|e| Error::Other(format!("Connect failed: {e}"))),
+        "servers" => list_sessions(output_ctx, &repl.sessions),
+        "tools" | "resources" => {
+            list_numbered(output_ctx, repl.sessions.current_mut(), command).await
+        }
+        "use" => use_session(ctx, &mut repl.sessions, rest),
+        "set" => set_command(ctx, output_ctx, &mut repl.vars, rest),
+        "unset" => {
+            if repl.vars.unset(rest) {
+                Ok(())
+            } else {
+                Err(Error::Other(format!("Variable ${rest} is not set")))
+            }
+        }
+        "notifications" => notifications_command(output_ctx, repl, rest),
+        "alias" => define_alias(ctx, output_ctx, &mut repl.aliases, rest),
+        "unalias" => repl.aliases.remove(&ctx.storage()?, rest),
+        "source" => Box::pin(source_command(ctx, repl, rest)).await,
+        _ => return run_mcp_command(ctx, output_ctx, repl, line).await,
+    };
+    report(ctx, result)
+}
+
+/// Shows the error of a REPL command that failed.
+fn report(ctx: &Ctx, result: Result<()>) -> Result<Flow> {
+    match result {
+        Ok(()) => Ok(Flow::Continue),
+        Err(e) => {
+            ctx.output.trace_error(e.to_string())?;
+            Ok(Flow::Failed)
+        }
+    }
+}
+
+/// Switches to another session.
+fn use_session(ctx: &Ctx, sessions: &mut Sessions, name: &str) -> Result<()> {
+    sessions.switch(name)?;
+    let target = sessions.current_mut().target.to_string();
+    ctx.output
+        .trace_success(format!("Using {name} ({target})"))?;
+    Ok(())
+}
+
+/// Lists received notifications, optionally of one kind, or discards them
+/// with `clear`.
+fn notifications_command(ctx: &Ctx, repl: &mut Repl, input: &str) -> Result<()> {
+    if input == "clear" {
+        repl.notifications.clear();
+        return Ok(());
+    }
+    let show_session = repl.sessions.is_multiple();
+    let received = repl
+        .notifications
+        .list(Some(input).filter(|kind| !kind.is_empty()))?;
+    notifications::display(&ctx.output, &received, show_session)
+}
+
+/// Runs a script from the REPL's `source [--keep-going] <file>` command.
+async fn source_command(ctx: &mut Ctx, repl: &mut Repl, input: &str) -> Result<()> {
+    let (keep_going, path) = match input.strip_prefix("--keep-going") {
+        Some(path) => (true, path.trim()),
+        None => (false, input),
+    };
+    if path.is_empty() {
+        return Err(Error::Format(
+            "Usage: source [--keep-going] <file>".to_string(),
+        ));
+    }
+    run_script(ctx, repl, Path::new(path), keep_going).await
+}
+
+/// Lists the current session's tools or resources, numbered for `call N` and
+/// `read N`.
+async fn list_numbered(ctx: &Ctx, session: &mut Session, command: &str) -> Result<()> {
+    let result = if command == "tools" {
+        serde_json::to_value(ctx.request(session.client.list_tools(None)).await?)?
+    } else {
+        serde_json::to_value(ctx.request(session.client.list_resources(None)).await?)?
+    };
+    session.indices.update(&result);
+    if ctx.output.structured() {
+        ctx.output.json_value(&result)?;
+    } else {
+        indices::display(&ctx.output, &result)?;
+    }
+    Ok(())
+}
+
+/// Opens a session from the REPL's `connect <target> [--as NAME]` command and
+/// makes it current.
+async fn open_session(ctx: &Ctx, sessions: &mut Sessions, input: &str) -> Result<()> {
+    let (input, name) = sessions::parse_connect(input)?;
+    let name = name.map_or_else(|| sessions.default_name(input), str::to_string);
+    let target = Target::resolve(input, &ctx.storage()?)?;
+    ctx.output
+        .status(format!("Connecting to {target} as '{name}'..."))?;
+    let session = sessions.open(ctx, &name, target).await?;
+    ctx.output.trace_success(format!(
+        "Connected to: {} v{}",
+        session.init_result.server_info.name, session.init_result.server_info.version
+    ))?;
+    Ok(())
+}
+
+/// Lists the open sessions, marking the current one.
+fn list_sessions(ctx: &Ctx, sessions: &Sessions) -> Result<()> {
+    for (name, session) in sessions.iter() {
+        let marker = if name == sessions.current_name() {
+            "*"
+        } else {
+            " "
+        };
+        let info = &session.init_result.server_info;
+        ctx.output.kv(
+            format!("{marker} {name}"),
+            format!("{} ({} v{})", session.target, info.name, info.version),
+        )?;
+    }
+    Ok(())
+}
+
+/// Defines an alias from `name = command`, or lists all aliases if `input` is
+/// empty.
+fn define_alias(ctx: &Ctx, output_ctx: &Ctx, aliases: &mut Aliases, input: &str) -> Result<()> {
+    if input.is_empty() {
+        for (name, expansion) in aliases.iter() {
+            output_ctx.output.kv(name, expansion)?;
+        }
+        return Ok(());
+    }
+    let (name, expansion) = alias::parse_definition(input)?;
+    aliases.set(&ctx.storage()?, name, expansion)
+}
+
+/// Handles `set`: `set name = value` sets a variable, `set <setting> <value>`
+/// changes an output setting for the rest of the session, and `set` on its own
+/// lists both.
+fn set_command(ctx: &mut Ctx, output_ctx: &Ctx, vars: &mut Variables, input: &str) -> Result<()> {
+    if input.is_empty() {
+        return list_settings(output_ctx, vars);
+    }
+    if input.contains('=') {
+        let (name, value) = vars::parse_assignment(input)?;
+        let value = vars.expand(value)?;
+        return vars.set(name, value);
+    }
+    let setting = Setting::parse(input)?;
+    ctx.output = setting.apply(ctx.output.clone());
+    Ok(())
+}
+
+/// Lists the variables and output settings.
+fn list_settings(ctx: &Ctx, vars: &Variables) -> Result<()> {
+    let settings = settings::current(&ctx.output);
+    if ctx.output.structured() {
+        let vars: Map<_, _> = vars
+            .iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect();
+        let settings: Map<_, _> = settings
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), json!(value)))
+            .collect();
+        return ctx
+            .output
+            .json_value(&json!({ "variables": vars, "settings": settings }));
+    }
+    ctx.output.h2("Variables")?;
+    for (name, value) in vars.iter() {
+        ctx.output.kv(name, value)?;
+    }
+    ctx.output.h2("Settings")?;
+    for (name, value) in settings {
+        ctx.output.kv(name, value)?;
+    }
+    Ok(())
+}
+
+/// Runs an MCP command after substituting variables, and records its result as
+/// `$LAST`. Variables are substituted word by word, so values containing spaces
+/// stay a single argument. Ctrl-C cancels the command and returns to the prompt.
+async fn run_mcp_command(ctx: &Ctx, output_ctx: &Ctx, repl: &mut Repl, line: &str) -> Result<Flow> {
+    let vars = &mut repl.vars;
+    let (command, json) = match multiline::split_json(line) {
+        Some((command, json)) => (command, Some(json)),
+        None => (line, None),
+    };
+    let words = match command
+        .split_whitespace()
+        .map(|word| vars.expand(word))
+        .collect::<Result<Vec<_>>>()
+    {
+        Ok(words) => words,
+        Err(e) => return report(ctx, Err(e)),
+    };
+
+    let mut wrapper = match ReplCommandWrapper::try_parse_from(words) {
+        Ok(wrapper) => wrapper,
+        Err(e) => {
+            ctx.output.trace_error(format!("Invalid command: {e}"))?;
+            ctx.output.text("Type 'help' for available commands.")?;
+            return Ok(Flow::Failed);
+        }
+    };
+    if let Some(json) = json
+        && let Err(e) = set_json_arguments(&mut wrapper.command, vars, json)
+    {
+        return report(ctx, Err(e));
+    }
+
+    let session = repl.sessions.current_mut();
+    // Only the current command's frames are needed to find its request
+    if let Some(frames) = &session.frames {
+        frames.clear_frames();
+    }
+    let result = tokio::select! {
+        result = execute_mcp_command_with_client(
+            wrapper.command,
+            &mut session.client,
+            &session.init_result,
+            output_ctx,
+        ) => Some(result),
+        _ = signal::ctrl_c() => None,
+    };
+    // Dropping the request future doesn't tell the server, so on Ctrl-C the
+    // cancellation is sent explicitly, leaving the connection usable
+    let Some(result) = result else {
+        ctx.output.trace_warn("Request cancelled")?;
+        if let Some(frames) = &session.frames
+            && let Err(e) = cancel_interrupted(&mut session.client, frames, ctx).await
+        {
+            ctx.output.trace_error(e.to_string())?;
+        }
+        repl.record(Event::Error {
+            message: "Request cancelled".to_string(),
+        })?;
+        return Ok(Flow::Failed);
+    };
+    match result {
+        Ok(value) => {
+            repl.record(Event::Result {
+                result: value.as_ref(),
+            })?;
+            if let Some(value) = value {
+                repl.sessions.current_mut().indices.update(&value);
+                repl.vars.set_last(&value);
+            }
+            Ok(Flow::Continue)
+        }
+        Err(e) => {
+            repl.record(Event::Error {
+                message: e.to_string(),
+            })?;
+            ctx.output.trace_error(format!("Command failed: {e}"))?;
+            let session = repl.sessions.current_mut();
+            if !session.is_alive(ctx).await {
+                session.reconnect_with_banner(ctx).await?;
+            }
+            Ok(Flow::Failed)
+        }
+    }
+}
+
+/// Passes a JSON object entered after a `calltool` command as the tool's
+/// arguments. Variables in the JSON are substituted first.
+fn set_json_arguments(command: &mut McpCommand, vars: &Variables, json: &str) -> Result<()> {
+    let McpCommand::Calltool { arguments, .. } = command else {
+        return Err(Error::Format(
+            "JSON arguments can only be given to calltool".to_string(),
+        ));
+    };
+    *arguments = Some(multiline::parse_object(&vars.expand(json)?)?);
+    Ok(())
+}
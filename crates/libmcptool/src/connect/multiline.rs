@@ -0,0 +1,222 @@
+//! Multi-line JSON arguments in the REPL.
+//!
+//! A command whose JSON object is still open at the end of the line, or that
+//! ends in `:json`, continues on the following lines until the object is
+//! closed. Scripts follow the same rules.
+
+use serde_json::{Map, Value};
+
+use crate::{Error, Result};
+
+/// Suffix that starts a multi-line JSON entry.
+const JSON_SUFFIX: &str = ":json";
+
+/// Removes a trailing `:json` from a command line.
+pub fn strip_json_suffix(line: &str) -> Option<&str> {
+    let command = line.trim_end().strip_suffix(JSON_SUFFIX)?;
+    (command.is_empty() || command.ends_with(char::is_whitespace)).then(|| command.trim_end())
+}
+
+/// Splits a command line into the command and the JSON object that follows
+/// it. The object starts at the first word beginning with `{`.
+pub fn split_json(line: &str) -> Option<(&str, &str)> {
+    let mut previous = ' ';
+    for (pos, c) in line.char_indices() {
+        if c == '{' && previous.is_whitespace() {
+            return Some((line[..pos].trim_end(), &line[pos..]));
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Whether `json` has unclosed braces, brackets or strings, so more input is
+/// needed.
+pub fn is_incomplete(json: &str) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in json.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    in_string || depth > 0
+}
+
+/// Whether a line being entered needs continuation lines. `json_mode` is set
+/// for entries started with `:json`, which continue until an object has been
+/// entered and closed.
+pub fn needs_more(entry: &str, json_mode: bool) -> bool {
+    match split_json(entry) {
+        Some((_, json)) => is_incomplete(json),
+        None => json_mode,
+    }
+}
+
+/// Groups the lines of a script or piped input into entries, following the
+/// same continuation rules as the prompt. Blank lines and lines starting with
+/// `#` are skipped, except inside a multi-line JSON entry.
+#[derive(Debug, Default)]
+pub struct Entries {
+    /// Number of lines seen so far.
+    line_number: usize,
+    /// The entry being continued: its first line number, text, and whether it
+    /// was started with `:json`.
+    pending: Option<(usize, String, bool)>,
+}
+
+impl Entries {
+    /// Adds a line, returning the entry it completes, with the line number the
+    /// entry starts on.
+    pub fn push(&mut self, line: &str) -> Option<(usize, String)> {
+        self.line_number += 1;
+        let (number, entry, json_mode) = match self.pending.take() {
+            Some((number, mut entry, json_mode)) => {
+                entry.push('\n');
+                entry.push_str(line);
+                (number, entry, json_mode)
+            }
+            None => {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let command = strip_json_suffix(line);
+                let json_mode = command.is_some();
+                (
+                    self.line_number,
+                    command.unwrap_or(line).to_string(),
+                    json_mode,
+                )
+            }
+        };
+        if needs_more(&entry, json_mode) {
+            self.pending = Some((number, entry, json_mode));
+            return None;
+        }
+        Some((number, entry))
+    }
+
+    /// Returns an entry left unfinished at the end of the input.
+    pub fn finish(self) -> Option<(usize, String)> {
+        self.pending.map(|(number, entry, _)| (number, entry))
+    }
+}
+
+/// Splits a whole script into entries, see [`Entries`].
+pub fn script_entries(script: &str) -> Vec<(usize, String)> {
+    let mut entries = Entries::default();
+    let mut result: Vec<_> = script
+        .lines()
+        .filter_map(|line| entries.push(line))
+        .collect();
+    result.extend(entries.finish());
+    result
+}
+
+/// Parses JSON tool arguments, which must be an object.
+pub fn parse_object(json: &str) -> Result<Map<String, Value>> {
+    match serde_json::from_str(json) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(Error::Format(
+            "JSON arguments must be an object".to_string(),
+        )),
+        Err(e) => Err(Error::Format(format!("Invalid JSON arguments: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_strip_json_suffix() {
+        assert_eq!(
+            strip_json_suffix("calltool echo :json"),
+            Some("calltool echo")
+        );
+        assert_eq!(
+            strip_json_suffix("calltool echo :json  "),
+            Some("calltool echo")
+        );
+        assert_eq!(strip_json_suffix("calltool echo"), None);
+        assert_eq!(strip_json_suffix("calltool echo --arg uri=x:json"), None);
+    }
+
+    #[test]
+    fn test_split_json() {
+        assert_eq!(
+            split_json(r#"calltool echo {"text": "hi"}"#),
+            Some(("calltool echo", r#"{"text": "hi"}"#))
+        );
+        assert_eq!(
+            split_json("calltool echo\n{\n  \"a\": 1\n}"),
+            Some(("calltool echo", "{\n  \"a\": 1\n}"))
+        );
+        assert_eq!(split_json("calltool echo --arg text={x}"), None);
+        assert_eq!(split_json("listtools"), None);
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete("{"));
+        assert!(is_incomplete(r#"{"a": [1, 2"#));
+        assert!(is_incomplete(r#"{"a": "unterminated"#));
+        assert!(!is_incomplete(r#"{"a": "}"}"#));
+        assert!(!is_incomplete(r#"{"a": "\"}"}"#));
+        assert!(!is_incomplete(r#"{"a": {"b": [1]}}"#));
+    }
+
+    #[test]
+    fn test_needs_more() {
+        assert!(needs_more("calltool echo {", false));
+        assert!(!needs_more(r#"calltool echo {"text": "hi"}"#, false));
+        assert!(!needs_more("calltool echo --arg text=hi", false));
+        assert!(needs_more("calltool echo", true));
+        assert!(needs_more("calltool echo\n{\"text\":", true));
+        assert!(!needs_more("calltool echo\n{\"text\": 1}", true));
+    }
+
+    #[test]
+    fn test_script_entries() {
+        let script = "# smoke test\nping\n\ncalltool echo {\n  \"text\": \"hi\"\n}\n  listtools  \ncalltool echo :json\n{\"a\": 1}\n";
+        assert_eq!(
+            script_entries(script),
+            [
+                (2, "ping".to_string()),
+                (4, "calltool echo {\n  \"text\": \"hi\"\n}".to_string()),
+                (7, "listtools".to_string()),
+                (8, "calltool echo\n{\"a\": 1}".to_string()),
+            ]
+        );
+        assert_eq!(
+            script_entries("calltool echo {"),
+            [(1, "calltool echo {".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_object() {
+        assert_eq!(
+            parse_object(r#"{"n": {"x": [1]}}"#).unwrap(),
+            json!({"n": {"x": [1]}}).as_object().unwrap().clone()
+        );
+        assert!(parse_object("[1]").is_err());
+        assert!(parse_object("{").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Buffering server notifications in the REPL, so they don't interleave with
+//! what is being typed.
+
+use std::mem;
+
+use chrono::{DateTime, Local};
+use tmcp::schema::ServerNotification;
+
+use crate::{Error, Result, output::Output};
+
+/// Notification kinds, as used to filter the `notifications` command.
+pub const KINDS: [&str; 7] = [
+    "log",
+    "progress",
+    "cancelled",
+    "resource_updated",
+    "resource_list_changed",
+    "tool_list_changed",
+    "prompt_list_changed",
+];
+
+/// A notification and when it arrived.
+#[derive(Debug, Clone)]
+pub struct Received {
+    /// When the notification arrived.
+    pub time: DateTime<Local>,
+    /// The session it arrived on.
+    pub session: String,
+    /// The notification.
+    pub notification: ServerNotification,
+}
+
+/// Notifications received during the REPL session.
+#[derive(Debug, Default)]
+pub struct NotificationLog {
+    /// Every notification received, oldest first.
+    received: Vec<Received>,
+    /// How many notifications arrived since the user was last told about them.
+    unannounced: usize,
+}
+
+impl NotificationLog {
+    /// Adds a notification to the backlog.
+    pub fn push(&mut self, session: &str, notification: ServerNotification) {
+        self.received.push(Received {
+            time: Local::now(),
+            session: session.to_string(),
+            notification,
+        });
+        self.unannounced += 1;
+    }
+
+    /// Returns the number of notifications that arrived since the last call,
+    /// or since they were last listed.
+    pub fn take_unannounced(&mut self) -> usize {
+        mem::take(&mut self.unannounced)
+    }
+
+    /// Notifications of the given kind, or all of them, oldest first. Marks
+    /// every notification as announced.
+    pub fn list(&mut self, filter: Option<&str>) -> Result<Vec<&Received>> {
+        if let Some(filter) = filter
+            && !KINDS.contains(&filter)
+        {
+            return Err(Error::Format(format!(
+                "Unknown notification type '{filter}'. Types are: {}",
+                KINDS.join(", ")
+            )));
+        }
+        self.unannounced = 0;
+        Ok(self
+            .received
+            .iter()
+            .filter(|received| filter.is_none_or(|filter| kind(&received.notification) == filter))
+            .collect())
+    }
+
+    /// Discards the backlog.
+    pub fn clear(&mut self) {
+        self.received.clear();
+        self.unannounced = 0;
+    }
+}
+
+/// The kind of a notification, one of [`KINDS`].
+pub fn kind(notification: &ServerNotification) -> &'static str {
+    match notification {
+        ServerNotification::LoggingMessage { .. } => "log",
+        ServerNotification::Progress { .. } => "progress",
+        ServerNotification::Cancelled { .. } => "cancelled",
+        ServerNotification::ResourceUpdated { .. } => "resource_updated",
+        ServerNotification::ResourceListChanged => "resource_list_changed",
+        ServerNotification::ToolListChanged => "tool_list_changed",
+        ServerNotification::PromptListChanged => "prompt_list_changed",
+    }
+}
+
+/// A one-line description of a notification.
+pub fn describe(notification: &ServerNotification) -> String {
+    match notification {
+        ServerNotification::LoggingMessage {
+            level,
+            logger,
+            data,
+        } => {
+            let logger_str = logger.as_deref().unwrap_or("server");
+            format!("{level:?} [{logger_str}]: {data}")
+        }
+        ServerNotification::ResourceUpdated { uri } => format!("Resource updated: {uri}"),
+        ServerNotification::ResourceListChanged => "Resource list changed".to_string(),
+        ServerNotification::ToolListChanged => "Tool list changed".to_string(),
+        ServerNotification::PromptListChanged => "Prompt list changed".to_string(),
+        ServerNotification::Cancelled { request_id, reason } => {
+            let reason_str = reason.as_deref().unwrap_or("no reason given");
+            format!("Request cancelled: {request_id:?} ({reason_str})")
+        }
+        ServerNotification::Progress {
+            progress_token,
+            progress,
+            total,
+            message,
+        } => {
+            let total_str = total.map(|t| format!("/{t}")).unwrap_or_default();
+            let message_str = message.as_deref().unwrap_or("");
+            format!("Progress {progress_token:?}: {progress}{total_str} - {message_str}")
+        }
+    }
+}
+
+/// Prints notifications with their arrival time, and the session they
+/// arrived on if several sessions are open.
+pub fn display(output: &Output, received: &[&Received], show_session: bool) -> Result<()> {
+    if received.is_empty() {
+        output.text("No notifications")?;
+        return Ok(());
+    }
+    for received in received {
+        let session = if show_session {
+            format!(" [{}]", received.session)
+        } else {
+            String::new()
+        };
+        output.text(format!(
+            "{}{session} {}",
+            received.time.format("%H:%M:%S"),
+            describe(&received.notification)
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log() {
+        let mut log = NotificationLog::default();
+        log.push("main", ServerNotification::ToolListChanged);
+        log.push("main", ServerNotification::PromptListChanged);
+        log.push("other", ServerNotification::ToolListChanged);
+        assert_eq!(log.take_unannounced(), 3);
+        assert_eq!(log.take_unannounced(), 0);
+
+        log.push("main", ServerNotification::ResourceListChanged);
+        let tools = log.list(Some("tool_list_changed")).unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[1].session, "other");
+        assert_eq!(log.take_unannounced(), 0);
+        assert_eq!(log.list(None).unwrap().len(), 4);
+        assert!(log.list(Some("bogus")).is_err());
+
+        log.clear();
+        assert!(log.list(None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Redirecting the output of a REPL command to a file with `>` and `>>`.
+
+use std::{
+    fs::{File, OpenOptions},
+    path::PathBuf,
+};
+
+use crate::{Error, Result};
+
+/// A file a command's output is written to.
+#[derive(Debug, PartialEq)]
+pub struct Redirect {
+    /// File to write to.
+    pub path: PathBuf,
+    /// Append to the file (`>>`) instead of replacing it (`>`).
+    pub append: bool,
+}
+
+impl Redirect {
+    /// Opens the file, creating it if needed.
+    pub fn open(&self) -> Result<File> {
+        let mut options = OpenOptions::new();
+        options.create(true);
+        if self.append {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        options
+            .open(&self.path)
+            .map_err(|e| Error::Other(format!("Cannot write to {}: {e}", self.path.display())))
+    }
+}
+
+/// Splits a trailing `> file` or `>> file` off a command line.
+///
+/// The operator has to be preceded by whitespace and followed by a single
+/// file name, so `>` inside arguments such as `--arg expr=a>b` is left alone.
+pub fn parse(line: &str) -> Result<(&str, Option<Redirect>)> {
+    let Some(pos) = line.rfind('>') else {
+        return Ok((line, None));
+    };
+    let path = line[pos + 1..].trim();
+    if path.contains(char::is_whitespace) {
+        return Ok((line, None));
+    }
+
+    let append = line[..pos].ends_with('>');
+    let operator_start = if append { pos - 1 } else { pos };
+    let before = &line[..operator_start];
+    if !before.is_empty() && !before.ends_with(char::is_whitespace) {
+        return Ok((line, None));
+    }
+
+    let command = before.trim_end();
+    if command.is_empty() {
+        return Err(Error::Format("Missing command before redirect".to_string()));
+    }
+    if path.is_empty() {
+        return Err(Error::Format(
+            "Missing file name after redirect".to_string(),
+        ));
+    }
+    Ok((
+        command,
+        Some(Redirect {
+            path: PathBuf::from(path),
+            append,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Write, process};
+
+    use super::*;
+
+    fn redirect(path: &str, append: bool) -> Option<Redirect> {
+        Some(Redirect {
+            path: PathBuf::from(path),
+            append,
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("listtools").unwrap(), ("listtools", None));
+        assert_eq!(
+            parse("listtools > tools.json").unwrap(),
+            ("listtools", redirect("tools.json", false))
+        );
+        assert_eq!(
+            parse("readresource file:///x >> dump.txt").unwrap(),
+            ("readresource file:///x", redirect("dump.txt", true))
+        );
+        assert_eq!(
+            parse("listtools >tools.json").unwrap(),
+            ("listtools", redirect("tools.json", false))
+        );
+
+        // Not redirects
+        assert_eq!(
+            parse("calltool calc --arg expr=a>b").unwrap(),
+            ("calltool calc --arg expr=a>b", None)
+        );
+        assert_eq!(
+            parse("calltool echo --arg text=> hello world").unwrap(),
+            ("calltool echo --arg text=> hello world", None)
+        );
+
+        assert!(parse("listtools >").is_err());
+        assert!(parse("> out.txt").is_err());
+    }
+
+    #[test]
+    fn test_open_truncates_or_appends() {
+        let dir = env::temp_dir().join("mcptool_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("redirect_{}.txt", process::id()));
+
+        let write = |append: bool, text: &str| {
+            let mut file = Redirect {
+                path: path.clone(),
+                append,
+            }
+            .open()
+            .unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        };
+        write(false, "one\n");
+        write(true, "two\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+        write(false, "three\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "three\n");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
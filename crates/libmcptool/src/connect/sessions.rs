@@ -0,0 +1,202 @@
+//! Holding connections to several servers in one REPL.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tmcp::schema::ServerNotification;
+use tokio::sync::mpsc;
+
+use super::{NotificationClientConn, Session, complete::Completions};
+use crate::{Error, Result, ctx::Ctx, target::Target};
+
+/// Name of the session opened when the REPL starts.
+pub const MAIN_SESSION: &str = "main";
+
+/// A server notification, tagged with the session it arrived on.
+pub type SessionNotification = (String, ServerNotification);
+
+/// The REPL's open sessions, one of which is current. Commands go to the
+/// current session.
+pub struct Sessions {
+    /// Open sessions by name.
+    sessions: BTreeMap<String, Session>,
+    /// Name of the current session.
+    current: String,
+    /// Channel every session's notifications are sent to.
+    notification_sender: mpsc::UnboundedSender<SessionNotification>,
+    /// Completions for the current session, shared with the line editor.
+    completions: Arc<Mutex<Completions>>,
+}
+
+impl Sessions {
+    /// Creates an empty set of sessions. Call [`Self::open`] before using it.
+    pub fn new(notification_sender: mpsc::UnboundedSender<SessionNotification>) -> Self {
+        Self {
+            sessions: BTreeMap::new(),
+            current: String::new(),
+            notification_sender,
+            completions: Arc::new(Mutex::new(Completions::new())),
+        }
+    }
+
+    /// Connects to a target as a new session and makes it current.
+    pub async fn open(&mut self, ctx: &Ctx, name: &str, target: Target) -> Result<&mut Session> {
+        validate_session_name(name)?;
+        if self.sessions.contains_key(name) {
+            return Err(Error::Other(format!(
+                "A session named '{name}' is already open"
+            )));
+        }
+        let conn = NotificationClientConn {
+            session: name.to_string(),
+            notification_sender: self.notification_sender.clone(),
+        };
+        let mut session = Session::connect(ctx, target, conn).await?;
+        session.refresh_completions(ctx).await;
+        self.sessions.insert(name.to_string(), session);
+        self.switch(name)?;
+        Ok(self.current_mut())
+    }
+
+    /// Makes the named session current.
+    pub fn switch(&mut self, name: &str) -> Result<()> {
+        if !self.sessions.contains_key(name) {
+            return Err(Error::Other(format!(
+                "No session named '{name}'. Type 'servers' to list sessions"
+            )));
+        }
+        self.current = name.to_string();
+        self.sync_completions();
+        Ok(())
+    }
+
+    /// The current session.
+    pub fn current_mut(&mut self) -> &mut Session {
+        self.sessions
+            .get_mut(&self.current)
+            .unwrap_or_else(|| panic!("current session '{}' is not open", self.current))
+    }
+
+    /// A session by name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Session> {
+        self.sessions.get_mut(name)
+    }
+
+    /// Name of the current session.
+    pub fn current_name(&self) -> &str {
+        &self.current
+    }
+
+    /// Whether more than one session is open.
+    pub fn is_multiple(&self) -> bool {
+        self.sessions.len() > 1
+    }
+
+    /// All sessions, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Session)> {
+        self.sessions.iter()
+    }
+
+    /// A name for a session opened without `--as`: the target's input if it
+    /// is a valid name (such as a saved target), otherwise `s2`, `s3`, ...
+    pub fn default_name(&self, input: &str) -> String {
+        if validate_session_name(input).is_ok() && !self.sessions.contains_key(input) {
+            return input.to_string();
+        }
+        (self.sessions.len() + 1..)
+            .map(|n| format!("s{n}"))
+            .find(|name| !self.sessions.contains_key(name))
+            .unwrap_or_default()
+    }
+
+    /// The prompt to show, naming the current session once there are several.
+    pub fn prompt(&self) -> String {
+        if self.is_multiple() {
+            format!("mcp[{}]> ", self.current)
+        } else {
+            "mcp> ".to_string()
+        }
+    }
+
+    /// Completions shared with the line editor.
+    pub fn completions(&self) -> Arc<Mutex<Completions>> {
+        self.completions.clone()
+    }
+
+    /// Points completion at the current session's names. Called after the
+    /// current session changes or its names are reloaded.
+    pub fn sync_completions(&self) {
+        if let Some(session) = self.sessions.get(&self.current) {
+            *self
+                .completions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = session.completions.clone();
+        }
+    }
+}
+
+/// Validates a session name: letters, numbers, `_` and `-`.
+fn validate_session_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        return Err(Error::Format(format!(
+            "Invalid session name '{name}'. Names can only contain letters, numbers, '_' and '-'"
+        )));
+    }
+    Ok(())
+}
+
+/// Parses the arguments of the REPL's `connect` command:
+/// `<target> [--as NAME]`. The target may contain spaces, as stdio targets do.
+pub fn parse_connect(input: &str) -> Result<(&str, Option<&str>)> {
+    let (target, name) = match input.rsplit_once(" --as ") {
+        Some((target, name)) if !name.trim().contains(char::is_whitespace) => {
+            (target.trim(), Some(name.trim()))
+        }
+        _ => (input.trim(), None),
+    };
+    if target.is_empty() || target.starts_with("--as") {
+        return Err(Error::Format(
+            "Usage: connect <target> [--as NAME]".to_string(),
+        ));
+    }
+    Ok((target, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_connect() {
+        assert_eq!(
+            parse_connect("http://localhost:3000 --as local").unwrap(),
+            ("http://localhost:3000", Some("local"))
+        );
+        assert_eq!(
+            parse_connect("cmd://./server --verbose --as dev").unwrap(),
+            ("cmd://./server --verbose", Some("dev"))
+        );
+        assert_eq!(
+            parse_connect("cmd://./server --verbose").unwrap(),
+            ("cmd://./server --verbose", None)
+        );
+        assert_eq!(parse_connect("staging").unwrap(), ("staging", None));
+        assert!(parse_connect("").is_err());
+        assert!(parse_connect("--as x").is_err());
+    }
+
+    #[test]
+    fn test_default_name() {
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let sessions = Sessions::new(sender);
+        assert_eq!(sessions.default_name("staging"), "staging");
+        assert_eq!(sessions.default_name("http://localhost:3000"), "s1");
+        assert_eq!(sessions.prompt(), "mcp> ");
+    }
+}
@@ -0,0 +1,133 @@
+//! Output settings that can be changed from the REPL with `set <setting> <value>`.
+
+use crate::{
+    Error, Result,
+    output::{DataFormat, Output, OutputMode},
+};
+
+/// Names of the settings, for listing and error messages.
+pub const SETTINGS: [&str; 4] = ["json", "color", "width", "verbosity"];
+
+/// A change to the live output settings.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Setting {
+    /// Print results as JSON, or as text when off.
+    Json(bool),
+    /// Color terminal output.
+    Color(bool),
+    /// Wrap text to this many columns.
+    Width(usize),
+    /// Print only results, without progress and status messages.
+    Quiet(bool),
+}
+
+impl Setting {
+    /// Parses `<setting> <value>`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let (name, value) = input
+            .split_once(char::is_whitespace)
+            .map(|(name, value)| (name, value.trim()))
+            .ok_or_else(|| {
+                Error::Format(format!(
+                    "Usage: set <setting> <value>, where setting is one of: {}",
+                    SETTINGS.join(", ")
+                ))
+            })?;
+        match name {
+            "json" => Ok(Self::Json(parse_switch(value)?)),
+            "color" => Ok(Self::Color(parse_switch(value)?)),
+            "width" => value
+                .parse()
+                .ok()
+                .filter(|width| *width > 0)
+                .map(Self::Width)
+                .ok_or_else(|| Error::Format(format!("Invalid width '{value}'"))),
+            "verbosity" => match value {
+                "quiet" => Ok(Self::Quiet(true)),
+                "normal" => Ok(Self::Quiet(false)),
+                _ => Err(Error::Format(format!(
+                    "Invalid verbosity '{value}'. Use quiet or normal"
+                ))),
+            },
+            _ => Err(Error::Format(format!(
+                "Unknown setting '{name}'. Settings are: {}",
+                SETTINGS.join(", ")
+            ))),
+        }
+    }
+
+    /// Returns `output` with the setting applied.
+    pub fn apply(self, output: Output) -> Output {
+        match self {
+            Self::Json(json) => output.with_mode(if json {
+                OutputMode::Data(DataFormat::Json)
+            } else {
+                OutputMode::Text
+            }),
+            Self::Color(color) => output.with_color(color),
+            Self::Width(width) => output.with_width(width),
+            Self::Quiet(quiet) => output.with_quiet(quiet),
+        }
+    }
+}
+
+/// The current value of each setting, for listing.
+pub fn current(output: &Output) -> Vec<(&'static str, String)> {
+    let switch = |on: bool| if on { "on" } else { "off" }.to_string();
+    vec![
+        ("json", switch(output.structured())),
+        ("color", switch(output.color())),
+        ("width", output.width().to_string()),
+        (
+            "verbosity",
+            if output.quiet { "quiet" } else { "normal" }.to_string(),
+        ),
+    ]
+}
+
+/// Parses an on/off value.
+fn parse_switch(value: &str) -> Result<bool> {
+    match value {
+        "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        _ => Err(Error::Format(format!(
+            "Invalid value '{value}'. Use on or off"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Setting::parse("json on").unwrap(), Setting::Json(true));
+        assert_eq!(Setting::parse("color off").unwrap(), Setting::Color(false));
+        assert_eq!(Setting::parse("width 120").unwrap(), Setting::Width(120));
+        assert_eq!(
+            Setting::parse("verbosity quiet").unwrap(),
+            Setting::Quiet(true)
+        );
+        assert!(Setting::parse("json").is_err());
+        assert!(Setting::parse("json maybe").is_err());
+        assert!(Setting::parse("width 0").is_err());
+        assert!(Setting::parse("theme dark").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let output = Output::new(false, 80);
+        let output = Setting::Json(true).apply(output);
+        let output = Setting::Width(100).apply(output);
+        assert_eq!(
+            current(&output),
+            [
+                ("json", "on".to_string()),
+                ("color", "off".to_string()),
+                ("width", "100".to_string()),
+                ("verbosity", "normal".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Recording a REPL session as a JSONL transcript.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use tmcp::schema::ServerNotification;
+
+use crate::{Error, Result};
+
+/// Something that happened in the REPL.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A line entered at the prompt.
+    Command {
+        /// The line as entered.
+        line: &'a str,
+    },
+    /// The result of an MCP command. Commands without a result, such as
+    /// `ping`, record `null`.
+    Result {
+        /// The result as JSON.
+        result: Option<&'a Value>,
+    },
+    /// An MCP command that failed or was cancelled.
+    Error {
+        /// What went wrong.
+        message: String,
+    },
+    /// A notification from the server.
+    Notification {
+        /// The notification.
+        notification: &'a ServerNotification,
+    },
+}
+
+/// A transcript line: an event with the time and session it happened in.
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    /// When the event happened, in RFC 3339 format.
+    timestamp: String,
+    /// The session the event belongs to.
+    session: &'a str,
+    /// What happened.
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// Writes one JSON object per line for each command, result and notification.
+pub struct Transcript {
+    /// The transcript file.
+    writer: BufWriter<File>,
+}
+
+impl Transcript {
+    /// Creates the transcript file, replacing any existing file.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).map_err(|e| {
+            Error::Other(format!("Cannot create transcript {}: {e}", path.display()))
+        })?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Appends an event. Each line is flushed, so the transcript is complete
+    /// even if the REPL exits abruptly.
+    pub fn record(&mut self, session: &str, event: Event<'_>) -> Result<()> {
+        let entry = Entry {
+            timestamp: Utc::now().to_rfc3339(),
+            session,
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let dir = env::temp_dir().join("mcptool_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("transcript_{}.jsonl", process::id()));
+
+        let mut transcript = Transcript::create(&path).unwrap();
+        let result = json!({"tools": []});
+        transcript
+            .record("main", Event::Command { line: "listtools" })
+            .unwrap();
+        transcript
+            .record(
+                "main",
+                Event::Result {
+                    result: Some(&result),
+                },
+            )
+            .unwrap();
+        transcript
+            .record(
+                "main",
+                Event::Error {
+                    message: "Request cancelled".to_string(),
+                },
+            )
+            .unwrap();
+
+        let lines: Vec<Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "command");
+        assert_eq!(lines[0]["line"], "listtools");
+        assert_eq!(lines[0]["session"], "main");
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[1]["type"], "result");
+        assert_eq!(lines[1]["result"], result);
+        assert_eq!(lines[2]["message"], "Request cancelled");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+//! REPL variables and `$name` substitution.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Name of the variable holding the previous command's result.
+pub const LAST: &str = "LAST";
+
+/// Variables set in the REPL, substituted into command lines.
+#[derive(Debug, Default)]
+pub struct Variables {
+    /// Variable values by name.
+    values: BTreeMap<String, String>,
+}
+
+impl Variables {
+    /// Sets a variable. `LAST` is reserved for the previous result.
+    pub fn set(&mut self, name: &str, value: String) -> Result<()> {
+        validate_name(name)?;
+        if name == LAST {
+            return Err(Error::Format(format!(
+                "${LAST} is set automatically from the previous result"
+            )));
+        }
+        self.values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// Removes a variable, returning whether it was set.
+    pub fn unset(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// Records a command's result as `$LAST`.
+    pub fn set_last(&mut self, result: &Value) {
+        self.values.insert(LAST.to_string(), result_text(result));
+    }
+
+    /// All variables, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.values.iter()
+    }
+
+    /// Replaces `$name` and `${name}` references in `input` with variable
+    /// values. `$$` produces a literal `$`.
+    pub fn expand(&self, input: &str) -> Result<String> {
+        let mut expanded = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(pos) = rest.find('$') {
+            expanded.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            if let Some(after) = after.strip_prefix('$') {
+                expanded.push('$');
+                rest = after;
+                continue;
+            }
+
+            let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+                let end = braced.find('}').ok_or_else(|| {
+                    Error::Format("Unclosed ${ in variable reference".to_string())
+                })?;
+                (&braced[..end], &braced[end + 1..])
+            } else {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            };
+
+            if name.is_empty() {
+                // A lone `$` is kept as is
+                expanded.push('$');
+            } else {
+                let value = self
+                    .values
+                    .get(name)
+                    .ok_or_else(|| Error::Format(format!("Undefined variable ${name}")))?;
+                expanded.push_str(value);
+            }
+            rest = remainder;
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+}
+
+/// Validates a variable name: a letter or `_`, followed by letters, digits or `_`.
+fn validate_name(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(Error::Format(format!(
+            "Invalid variable name '{name}'. Names start with a letter or '_' and contain only letters, numbers and '_'"
+        )));
+    }
+    Ok(())
+}
+
+/// Parses the `name = value` part of a `set` command. The value is everything
+/// after the `=`, trimmed.
+pub fn parse_assignment(input: &str) -> Result<(&str, &str)> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::Format("Usage: set <name> = <value>".to_string()))?;
+    let name = name.trim();
+    validate_name(name)?;
+    Ok((name, value.trim()))
+}
+
+/// The text of a result, for use in later commands: the text content of tool
+/// results, prompts and resources, or compact JSON for anything else.
+pub fn result_text(result: &Value) -> String {
+    let texts: Vec<&str> = ["content", "contents"]
+        .iter()
+        .filter_map(|field| result.get(field).and_then(Value::as_array))
+        .flatten()
+        .filter_map(|item| item.get("text").and_then(Value::as_str))
+        .collect();
+    if texts.is_empty() {
+        result.to_string()
+    } else {
+        texts.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let mut vars = Variables::default();
+        vars.set("uri", "file:///long/path.txt".to_string())
+            .unwrap();
+        vars.set("name", "world".to_string()).unwrap();
+
+        assert_eq!(
+            vars.expand("readresource $uri").unwrap(),
+            "readresource file:///long/path.txt"
+        );
+        assert_eq!(
+            vars.expand("text=hello_${name}!").unwrap(),
+            "text=hello_world!"
+        );
+        assert_eq!(vars.expand("cost=$$5 $").unwrap(), "cost=$5 $");
+        assert_eq!(vars.expand("no variables").unwrap(), "no variables");
+        assert!(vars.expand("$missing").is_err());
+        assert!(vars.expand("${name").is_err());
+    }
+
+    #[test]
+    fn test_set_and_last() {
+        let mut vars = Variables::default();
+        assert!(vars.set("LAST", "x".to_string()).is_err());
+        assert!(vars.set("1abc", "x".to_string()).is_err());
+
+        vars.set_last(&json!({"content": [{"type": "text", "text": "42"}]}));
+        assert_eq!(vars.expand("$LAST").unwrap(), "42");
+
+        vars.set_last(&json!({"tools": []}));
+        assert_eq!(vars.expand("$LAST").unwrap(), r#"{"tools":[]}"#);
+
+        assert!(vars.unset("LAST"));
+        assert!(!vars.unset("LAST"));
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("uri = file:///x y").unwrap(),
+            ("uri", "file:///x y")
+        );
+        assert_eq!(parse_assignment("a=b=c").unwrap(), ("a", "b=c"));
+        assert!(parse_assignment("novalue").is_err());
+        assert!(parse_assignment("bad name = 1").is_err());
+    }
+
+    #[test]
+    fn test_result_text() {
+        assert_eq!(
+            result_text(
+                &json!({"contents": [{"uri": "a", "text": "one"}, {"uri": "b", "text": "two"}]})
+            ),
+            "one\ntwo"
+        );
+        assert_eq!(
+            result_text(&json!({"completion": {"values": []}})),
+            r#"{"completion":{"values":[]}}"#
+        );
+    }
+}
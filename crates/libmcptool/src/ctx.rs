@@ -1,8 +1,12 @@
-use std::path::PathBuf;
+use std::{future::Future, path::PathBuf, result, time::Duration};
+
+use tokio::time;
 
 use crate::{
-    Result,
-    output::{LogLevel, Output},
+    Error, Result,
+    client::ConnectOptions,
+    frametrace::ProtocolTrace,
+    output::{self, LogLevel, Output, OutputOptions, StatusStream},
     storage::TokenStorage,
 };
 
@@ -22,6 +26,12 @@ pub struct Ctx {
     pub config_path: PathBuf,
     /// Output instance for consistent formatting
     pub output: Output,
+    /// Transport settings used when connecting to targets
+    pub connect: ConnectOptions,
+    /// Maximum time to wait for any single request
+    pub timeout: Option<Duration>,
+    /// Where JSON-RPC frames exchanged with servers are traced, if anywhere
+    pub trace_protocol: Option<ProtocolTrace>,
 }
 
 impl Ctx {
@@ -29,24 +39,100 @@ impl Ctx {
     pub fn new(
         config_path: PathBuf,
         logs: Option<LogLevel>,
-        json: bool,
+        options: OutputOptions,
         quiet: bool,
         color: bool,
-        width: usize,
     ) -> Result<Self> {
-        let output = Output::new(color, width)
-            .with_json(json)
+        let output = Output::new(color, output::DEFAULT_WIDTH)
+            .with_terminal_width()
+            .with_options(options)
             .with_quiet(quiet)
             .with_logging(logs)?;
 
         Ok(Self {
             config_path,
             output,
+            connect: ConnectOptions::default(),
+            timeout: None,
+            trace_protocol: None,
         })
     }
 
+    /// Set the transport settings used when connecting to targets
+    pub fn with_connect_options(mut self, connect: ConnectOptions) -> Self {
+        self.connect = connect;
+        self
+    }
+
+    /// Choose where status, progress and trace lines are written
+    pub fn with_status_stream(mut self, stream: StatusStream) -> Self {
+        self.output = self.output.with_status_stream(stream);
+        self
+    }
+
+    /// Replace the output, e.g. to send a command's results to a file
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Set the maximum time to wait for any single request
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Trace the JSON-RPC frames exchanged with servers
+    pub fn with_trace_protocol(mut self, trace: Option<ProtocolTrace>) -> Self {
+        self.trace_protocol = trace;
+        self
+    }
+
+    /// Await a request, failing with Error::Timeout if it exceeds the configured timeout
+    pub async fn request<T, E, F>(&self, request: F) -> Result<T>
+    where
+        F: Future<Output = result::Result<T, E>>,
+        Error: From<E>,
+    {
+        match self.timeout {
+            Some(limit) => match time::timeout(limit, request).await {
+                Ok(result) => Ok(result?),
+                Err(_) => Err(Error::Timeout(limit)),
+            },
+            None => Ok(request.await?),
+        }
+    }
+
     /// Create a TokenStorage instance using the configured path
     pub fn storage(&self) -> Result<TokenStorage> {
         Ok(TokenStorage::new(self.config_path.clone())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, future};
+
+    use super::*;
+
+    fn test_ctx() -> Ctx {
+        Ctx::new(env::temp_dir(), None, OutputOptions::default(), true, false).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_times_out() {
+        let ctx = test_ctx().with_timeout(Some(Duration::from_millis(10)));
+        let result = ctx
+            .request(future::pending::<result::Result<(), Error>>())
+            .await;
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        assert_eq!(result.unwrap_err().exit_code(), 124);
+    }
+
+    #[tokio::test]
+    async fn test_request_without_timeout() {
+        let ctx = test_ctx();
+        let result = ctx.request(future::ready(Ok::<_, Error>(42))).await;
+        assert_eq!(result.unwrap(), 42);
+    }
+}
@@ -0,0 +1,310 @@
+//! Comparing what two servers offer, or one server at two points in time.
+//!
+//! Each side is a live target or a saved snapshot. Tools, prompts,
+//! resources and resource templates are matched by name or URI, and those
+//! on both sides are compared field by field, down into their schemas.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{Error, Result, ctx::Ctx, mcp::Listing, output::Output, snapshot::Snapshot};
+
+/// The fields of the initialize result compared between servers.
+const SERVER_FIELDS: &[&str] = &[
+    "protocolVersion",
+    "serverInfo",
+    "capabilities",
+    "instructions",
+];
+
+/// How an item differs between the two sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// Only on the second side.
+    Added,
+    /// Only on the first side.
+    Removed,
+    /// On both sides, but different.
+    Changed,
+}
+
+/// A difference at one path within an item.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The path to the field, such as `inputSchema.properties.text.type`.
+    pub path: String,
+    /// The value on the first side, if it has one.
+    pub before: Option<Value>,
+    /// The value on the second side, if it has one.
+    pub after: Option<Value>,
+}
+
+/// An item that differs between the two sides.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// What kind of item it is: "server", "tool", "prompt", "resource",
+    /// "resource template" or "resource contents".
+    pub section: &'static str,
+    /// The item's name or URI, or the initialize field for the server.
+    pub name: String,
+    /// How it differs.
+    pub kind: ChangeKind,
+    /// The fields that differ, for a changed item.
+    pub fields: Vec<FieldChange>,
+}
+
+/// Joins a field name onto a path.
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Adds the differences between two values to `fields`. Objects are
+/// compared key by key; anything else, arrays included, is compared whole.
+fn diff_values(path: &str, before: &Value, after: &Value, fields: &mut Vec<FieldChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let keys = before
+                .keys()
+                .chain(after.keys().filter(|key| !before.contains_key(*key)));
+            for key in keys {
+                let path = join(path, key);
+                match (before.get(key), after.get(key)) {
+                    (Some(before), Some(after)) => diff_values(&path, before, after, fields),
+                    (before, after) => fields.push(FieldChange {
+                        path,
+                        before: before.cloned(),
+                        after: after.cloned(),
+                    }),
+                }
+            }
+        }
+        _ if before != after => fields.push(FieldChange {
+            path: path.to_string(),
+            before: Some(before.clone()),
+            after: Some(after.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// Adds the differences between two sides' items of a listing to
+/// `changes`, in order of name.
+fn diff_items(listing: Listing, before: &[Value], after: &[Value], changes: &mut Vec<Change>) {
+    let keyed = |items: &[Value]| -> BTreeMap<String, Value> {
+        items
+            .iter()
+            .filter_map(|item| {
+                let key = item.get(listing.key())?.as_str()?;
+                Some((key.to_string(), item.clone()))
+            })
+            .collect()
+    };
+    let (before, after) = (keyed(before), keyed(after));
+    let names = before
+        .keys()
+        .chain(after.keys().filter(|name| !before.contains_key(*name)));
+    let mut names: Vec<&String> = names.collect();
+    names.sort();
+    for name in names {
+        let (kind, fields) = match (before.get(name), after.get(name)) {
+            (Some(before), Some(after)) => {
+                let mut fields = Vec::new();
+                diff_values("", before, after, &mut fields);
+                if fields.is_empty() {
+                    continue;
+                }
+                (ChangeKind::Changed, fields)
+            }
+            (Some(_), None) => (ChangeKind::Removed, Vec::new()),
+            _ => (ChangeKind::Added, Vec::new()),
+        };
+        changes.push(Change {
+            section: listing.noun(),
+            name: name.clone(),
+            kind,
+            fields,
+        });
+    }
+}
+
+/// Every difference between two snapshots: the server's initialize fields
+/// first, then each listing, then resource contents if both snapshots have
+/// them.
+pub fn compare(before: &Snapshot, after: &Snapshot) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for field in SERVER_FIELDS {
+        let mut fields = Vec::new();
+        diff_values(
+            field,
+            before.init.get(*field).unwrap_or(&Value::Null),
+            after.init.get(*field).unwrap_or(&Value::Null),
+            &mut fields,
+        );
+        if !fields.is_empty() {
+            changes.push(Change {
+                section: "server",
+                name: (*field).to_string(),
+                kind: ChangeKind::Changed,
+                fields,
+            });
+        }
+    }
+    for listing in Listing::ALL {
+        diff_items(
+            listing,
+            before.items(listing),
+            after.items(listing),
+            &mut changes,
+        );
+    }
+    if !before.contents.is_empty() && !after.contents.is_empty() {
+        for (uri, before_contents) in &before.contents {
+            if let Some(after_contents) = after.contents.get(uri) {
+                let mut fields = Vec::new();
+                diff_values("", before_contents, after_contents, &mut fields);
+                if !fields.is_empty() {
+                    changes.push(Change {
+                        section: "resource contents",
+                        name: uri.clone(),
+                        kind: ChangeKind::Changed,
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// Prints the changes between two sides as a colored diff.
+pub fn print_changes(output: &Output, changes: &[Change]) -> Result<()> {
+    for change in changes {
+        let marker = match change.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        output.diff(marker, format!("{} {}", change.section, change.name))?;
+        let out = output.indent();
+        for field in &change.fields {
+            if let Some(before) = &field.before {
+                out.diff('-', format!("{}: {before}", field.path))?;
+            }
+            if let Some(after) = &field.after {
+                out.diff('+', format!("{}: {after}", field.path))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compares two servers, each a target or a snapshot file, and prints what
+/// was added, removed and changed. With `exit_code`, fails if they differ.
+pub async fn diff_command(ctx: &Ctx, first: &str, second: &str, exit_code: bool) -> Result<()> {
+    let before = Snapshot::from_source(ctx, first).await?;
+    let after = Snapshot::from_source(ctx, second).await?;
+    let changes = compare(&before, &after);
+
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_value(&json!({
+            "first": before.target,
+            "second": after.target,
+            "identical": changes.is_empty(),
+            "changes": changes,
+        }))?;
+    } else {
+        output.h1(format!("{} → {}", before.target, after.target))?;
+        if changes.is_empty() {
+            output.text("No differences")?;
+        } else {
+            print_changes(output, &changes)?;
+            let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+            output.text(format!(
+                "{} added, {} removed, {} changed",
+                count(ChangeKind::Added),
+                count(ChangeKind::Removed),
+                count(ChangeKind::Changed)
+            ))?;
+        }
+    }
+    if exit_code && !changes.is_empty() {
+        return Err(Error::Other(format!(
+            "The servers differ in {} place(s)",
+            changes.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    /// A snapshot with the given version and tools.
+    fn snapshot(version: &str, tools: Vec<Value>) -> Snapshot {
+        Snapshot {
+            target: format!("server-{version}"),
+            captured_at: Utc::now(),
+            init: json!({
+                "protocolVersion": "2025-06-18",
+                "serverInfo": { "name": "server", "version": version },
+                "capabilities": { "tools": {} },
+            }),
+            tools,
+            prompts: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let echo = json!({
+            "name": "echo",
+            "inputSchema": { "type": "object", "properties": { "text": { "type": "string" } } },
+        });
+        let before = snapshot(
+            "1.0",
+            vec![echo.clone(), json!({ "name": "old", "inputSchema": {} })],
+        );
+        let mut new_echo = echo;
+        new_echo["inputSchema"]["properties"]["text"]["type"] = json!("integer");
+        new_echo["description"] = json!("Echoes");
+        let after = snapshot(
+            "1.1",
+            vec![new_echo, json!({ "name": "new", "inputSchema": {} })],
+        );
+
+        let changes = compare(&before, &after);
+        let summary: Vec<(&str, &str, ChangeKind)> = changes
+            .iter()
+            .map(|c| (c.section, c.name.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("server", "serverInfo", ChangeKind::Changed),
+                ("tool", "echo", ChangeKind::Changed),
+                ("tool", "new", ChangeKind::Added),
+                ("tool", "old", ChangeKind::Removed),
+            ]
+        );
+        assert_eq!(changes[0].fields[0].path, "serverInfo.version");
+        let paths: Vec<&str> = changes[1].fields.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["inputSchema.properties.text.type", "description"]);
+        assert_eq!(changes[1].fields[1].before, None);
+
+        assert!(compare(&before, &before).is_empty());
+    }
+}
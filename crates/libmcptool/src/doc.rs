@@ -0,0 +1,473 @@
+//! Markdown documentation generated from a live server.
+//!
+//! Every tool, prompt, resource and resource template gets a page of its
+//! own, and an index describes the server and links to each page. Tool
+//! pages render the input schema as a table of arguments, with an example
+//! call built from the schema's examples, defaults and types.
+
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use serde_json::{Map, Value, json};
+use tmcp::schema::InitializeResult;
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    mcp::Listing,
+    output::markdown::{self, annotations, json_block, paragraph, table},
+    snapshot::Snapshot,
+    target::Target,
+};
+
+/// How deep example values are built into nested objects and arrays.
+const MAX_EXAMPLE_DEPTH: usize = 4;
+
+/// A generated page.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// The page's path, relative to the output directory.
+    pub path: String,
+    /// The Markdown.
+    pub contents: String,
+}
+
+/// The directory a listing's pages go in.
+fn directory(listing: Listing) -> &'static str {
+    match listing {
+        Listing::Tools => "tools",
+        Listing::Prompts => "prompts",
+        Listing::Resources => "resources",
+        Listing::ResourceTemplates => "resource-templates",
+    }
+}
+
+/// The index heading for a listing.
+fn heading(listing: Listing) -> &'static str {
+    match listing {
+        Listing::Tools => "Tools",
+        Listing::Prompts => "Prompts",
+        Listing::Resources => "Resources",
+        Listing::ResourceTemplates => "Resource Templates",
+    }
+}
+
+/// A file name for an item, made of its name's letters, digits, dashes and
+/// underscores. Names that come out the same are numbered.
+fn slug(name: &str, used: &mut HashSet<String>) -> String {
+    let mut base: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    base = base.trim_matches('-').to_string();
+    if base.is_empty() {
+        base = "item".to_string();
+    }
+    let mut slug = base.clone();
+    for n in 2.. {
+        if used.insert(slug.clone()) {
+            break;
+        }
+        slug = format!("{base}-{n}");
+    }
+    slug
+}
+
+/// Quotes a word for the shell, if it needs quoting.
+fn quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@=+,".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// A string field of an item.
+fn field<'a>(item: &'a Value, name: &str) -> Option<&'a str> {
+    item.get(name).and_then(Value::as_str)
+}
+
+/// The first line of an item's description, for the index.
+fn summary(item: &Value) -> String {
+    field(item, "description")
+        .and_then(|description| description.lines().map(str::trim).find(|l| !l.is_empty()))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The type a schema describes, for the arguments table.
+fn type_label(schema: &Value) -> String {
+    let mut label = match schema.get("type") {
+        Some(Value::String(name)) => name.clone(),
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        _ => "any".to_string(),
+    };
+    if label == "array"
+        && let Some(items) = schema.get("items")
+    {
+        label = format!("array of {}", type_label(items));
+    }
+    label
+}
+
+/// An example value for a schema: the first of its examples, its default,
+/// its constant or first enum value, or a placeholder of its type.
+fn example(schema: &Value, depth: usize) -> Value {
+    let given = schema
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|examples| examples.first())
+        .or_else(|| schema.get("default"))
+        .or_else(|| schema.get("const"))
+        .or_else(|| schema.get("enum").and_then(Value::as_array)?.first());
+    if let Some(value) = given {
+        return value.clone();
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(name)) => name.as_str(),
+        Some(Value::Array(names)) => names.iter().find_map(Value::as_str).unwrap_or("string"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "string",
+    };
+    match kind {
+        "integer" | "number" => json!(0),
+        "boolean" => json!(false),
+        "null" => Value::Null,
+        "array" if depth < MAX_EXAMPLE_DEPTH => {
+            let items = schema.get("items").unwrap_or(&Value::Null);
+            json!([example(items, depth + 1)])
+        }
+        "array" => json!([]),
+        "object" => Value::Object(example_object(schema, depth)),
+        _ => json!("string"),
+    }
+}
+
+/// Example values for each property of an object schema.
+fn example_object(schema: &Value, depth: usize) -> Map<String, Value> {
+    if depth >= MAX_EXAMPLE_DEPTH {
+        return Map::new();
+    }
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| (name.clone(), example(property, depth + 1)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Appends a table of the properties of an object schema.
+fn arguments_table(doc: &mut String, schema: &Value) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        doc.push_str("No arguments.\n\n");
+        return;
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let rows: Vec<Vec<String>> = properties
+        .iter()
+        .map(|(name, property)| {
+            let mut description = field(property, "description")
+                .unwrap_or_default()
+                .to_string();
+            if let Some(choices) = property.get("enum").and_then(Value::as_array) {
+                let choices: Vec<String> = choices.iter().map(|c| format!("`{c}`")).collect();
+                description = format!("{description} One of {}.", choices.join(", "))
+                    .trim()
+                    .to_string();
+            }
+            vec![
+                format!("`{name}`"),
+                type_label(property),
+                if required.contains(&name.as_str()) {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_string(),
+                description,
+            ]
+        })
+        .collect();
+    table(doc, &["Name", "Type", "Required", "Description"], &rows);
+}
+
+/// The page for a tool.
+fn tool_page(tool: &Value, target: &str) -> Result<String> {
+    let name = field(tool, "name").unwrap_or_default();
+    let mut doc = format!("# {name}\n\n");
+    let (target, quoted) = (quote(target), quote(name));
+    if let Some(title) = field(tool, "title") {
+        doc.push_str(&format!("*{title}*\n\n"));
+    }
+    paragraph(&mut doc, field(tool, "description"));
+    if let Some(tool_annotations) = tool.get("annotations") {
+        annotations(&mut doc, "## Annotations", tool_annotations);
+    }
+
+    let input = tool.get("inputSchema").unwrap_or(&Value::Null);
+    doc.push_str("## Arguments\n\n");
+    arguments_table(&mut doc, input);
+
+    doc.push_str("## Example\n\n");
+    let arguments = example_object(input, 0);
+    json_block(&mut doc, &arguments)?;
+    doc.push_str(&format!(
+        "```bash\nmcptool mcp calltool {target} {quoted} --json-args <<'EOF'\n{}\nEOF\n```\n\n",
+        serde_json::to_string_pretty(&arguments)?
+    ));
+
+    doc.push_str("## Input Schema\n\n");
+    json_block(&mut doc, input)?;
+    if let Some(output) = tool.get("outputSchema") {
+        doc.push_str("## Output Schema\n\n");
+        json_block(&mut doc, output)?;
+    }
+    Ok(doc)
+}
+
+/// The page for a prompt.
+fn prompt_page(prompt: &Value, target: &str) -> String {
+    let name = field(prompt, "name").unwrap_or_default();
+    let mut doc = format!("# {name}\n\n");
+    paragraph(&mut doc, field(prompt, "description"));
+    let arguments = prompt
+        .get("arguments")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    doc.push_str("## Arguments\n\n");
+    if arguments.is_empty() {
+        doc.push_str("No arguments.\n\n");
+    } else {
+        let rows: Vec<Vec<String>> = arguments
+            .iter()
+            .map(|argument| {
+                let required = argument.get("required") == Some(&Value::Bool(true));
+                vec![
+                    format!("`{}`", field(argument, "name").unwrap_or_default()),
+                    if required { "yes" } else { "no" }.to_string(),
+                    field(argument, "description")
+                        .unwrap_or_default()
+                        .to_string(),
+                ]
+            })
+            .collect();
+        table(&mut doc, &["Name", "Required", "Description"], &rows);
+    }
+    let args: String = arguments
+        .iter()
+        .filter_map(|argument| field(argument, "name"))
+        .map(|name| format!(" --arg {}", quote(&format!("{name}=..."))))
+        .collect();
+    doc.push_str(&format!(
+        "## Example\n\n```bash\nmcptool mcp getprompt {} {}{args}\n```\n\n",
+        quote(target),
+        quote(name)
+    ));
+    doc
+}
+
+/// The page for a resource or resource template, with a table of its
+/// properties.
+fn resource_page(item: &Value, listing: Listing, target: &str) -> Result<String> {
+    let key = field(item, listing.key()).unwrap_or_default();
+    let mut doc = format!("# {}\n\n", field(item, "name").unwrap_or(key));
+    paragraph(&mut doc, field(item, "description"));
+    let properties = [
+        ("URI", "uri"),
+        ("URI Template", "uriTemplate"),
+        ("Title", "title"),
+        ("MIME Type", "mimeType"),
+    ];
+    let mut rows: Vec<Vec<String>> = properties
+        .iter()
+        .filter_map(|(label, name)| {
+            Some(vec![label.to_string(), format!("`{}`", field(item, name)?)])
+        })
+        .collect();
+    if let Some(size) = item.get("size").and_then(Value::as_u64) {
+        rows.push(vec!["Size".to_string(), format!("{size} bytes")]);
+    }
+    table(&mut doc, &["Property", "Value"], &rows);
+    if let Some(item_annotations) = item.get("annotations") {
+        doc.push_str("## Annotations\n\n");
+        json_block(&mut doc, item_annotations)?;
+    }
+    if listing == Listing::Resources {
+        doc.push_str(&format!(
+            "## Example\n\n```bash\nmcptool mcp readresource {} {}\n```\n\n",
+            quote(target),
+            quote(key)
+        ));
+    }
+    Ok(doc)
+}
+
+/// Every page for a server: the index first, then a page per item.
+pub fn pages(snapshot: &Snapshot) -> Result<Vec<Page>> {
+    let init: InitializeResult = serde_json::from_value(snapshot.init.clone())
+        .map_err(|e| Error::Format(format!("Invalid initialize result: {e}")))?;
+    let target = &snapshot.target;
+    let mut index = markdown::init(&init)?;
+    let mut pages = Vec::new();
+    for listing in Listing::ALL {
+        let items = snapshot.items(listing);
+        if items.is_empty() {
+            continue;
+        }
+        let mut used = HashSet::new();
+        let mut rows = Vec::new();
+        for item in items {
+            let name = field(item, listing.key()).unwrap_or_default();
+            let label = field(item, "name").unwrap_or(name);
+            let path = format!("{}/{}.md", directory(listing), slug(label, &mut used));
+            let contents = match listing {
+                Listing::Tools => tool_page(item, target)?,
+                Listing::Prompts => prompt_page(item, target),
+                Listing::Resources | Listing::ResourceTemplates => {
+                    resource_page(item, listing, target)?
+                }
+            };
+            rows.push(vec![format!("[{label}]({path})"), summary(item)]);
+            pages.push(Page { path, contents });
+        }
+        index.push_str(&format!("## {}\n\n", heading(listing)));
+        table(&mut index, &["Name", "Description"], &rows);
+    }
+    pages.insert(
+        0,
+        Page {
+            path: "index.md".to_string(),
+            contents: index,
+        },
+    );
+    Ok(pages)
+}
+
+/// Generates documentation for a server and writes it to `directory`.
+pub async fn doc_command(ctx: &Ctx, target: Target, directory: PathBuf) -> Result<()> {
+    let snapshot = Snapshot::capture(ctx, &target, false).await?;
+    let pages = pages(&snapshot)?;
+    for page in &pages {
+        let path = directory.join(&page.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::Other(format!("Failed to create {}: {e}", parent.display())))?;
+        }
+        fs::write(&path, &page.contents)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))?;
+    }
+
+    let output = &ctx.output;
+    if output.structured() {
+        let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        output.json_value(&json!({
+            "target": snapshot.target,
+            "directory": directory,
+            "pages": paths,
+        }))?;
+    } else {
+        output.trace_success(format!(
+            "Wrote {} pages for {target} to {}",
+            pages.len(),
+            directory.display()
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        let snapshot = Snapshot {
+            target: "cmd://server".to_string(),
+            captured_at: Utc::now(),
+            init: json!({
+                "protocolVersion": "2025-06-18",
+                "serverInfo": { "name": "server", "version": "1.0" },
+                "capabilities": { "tools": {}, "resources": {} },
+            }),
+            tools: vec![
+                json!({
+                    "name": "Search Docs",
+                    "description": "Searches the docs.\nSlowly.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "query": { "type": "string", "examples": ["tokio"] },
+                            "mode": { "type": "string", "enum": ["fast", "exact"] },
+                            "tags": { "type": "array", "items": { "type": "string" } },
+                        },
+                        "required": ["query"],
+                    },
+                    "annotations": { "readOnlyHint": true },
+                }),
+                json!({ "name": "search/docs", "inputSchema": { "type": "object" } }),
+            ],
+            prompts: Vec::new(),
+            resources: vec![json!({ "uri": "file:///a.txt", "name": "a", "size": 3 })],
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        };
+        let pages = pages(&snapshot).unwrap();
+        let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "index.md",
+                "tools/search-docs.md",
+                "tools/search-docs-2.md",
+                "resources/a.md"
+            ]
+        );
+
+        let index = &pages[0].contents;
+        assert!(index.starts_with("# server (1.0)\n"));
+        assert!(index.contains("| [Search Docs](tools/search-docs.md) | Searches the docs. |"));
+        assert!(!index.contains("## Prompts"));
+
+        let tool = &pages[1].contents;
+        assert!(tool.contains("| `readOnlyHint` | true |"));
+        assert!(tool.contains("| `query` | string | yes |  |"));
+        assert!(tool.contains("| `mode` | string | no | One of `\"fast\"`, `\"exact\"`. |"));
+        assert!(tool.contains("| `tags` | array of string | no |  |"));
+        assert!(tool.contains("\"query\": \"tokio\""));
+        assert!(tool.contains("\"mode\": \"fast\""));
+        assert!(pages[2].contents.contains("No arguments."));
+        assert!(pages[3].contents.contains("| Size | 3 bytes |"));
+        assert!(
+            pages[3]
+                .contents
+                .contains("mcptool mcp readresource cmd://server file:///a.txt")
+        );
+        assert_eq!(quote("it's here"), "'it'\\''s here'");
+    }
+}
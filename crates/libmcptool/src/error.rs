@@ -1,6 +1,6 @@
 //! Error types for mcptool.
 
-use std::{io, result};
+use std::{io, result, time::Duration};
 
 use rustyline::error::ReadlineError;
 use thiserror::Error;
@@ -29,6 +29,11 @@ pub enum Error {
     #[error("MCP error: {0}")]
     Other(String),
 
+    /// An HTTP server refused the connection with 401 Unauthorized, as one
+    /// that wants OAuth does.
+    #[error("MCP error: {0}")]
+    Unauthorized(String),
+
     /// Readline errors from rustyline.
     #[error("Readline error: {0}")]
     Readline(#[from] ReadlineError),
@@ -41,9 +46,49 @@ pub enum Error {
     #[error("Invalid format: {0}")]
     Format(String),
 
+    /// Options or a target that can't work as given, so trying again won't
+    /// help.
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
     /// Errors that should be rare, and are not expected to be handled by the user.
     #[error("MCP error: {0}")]
     Internal(String),
+
+    /// A tool call's outcome didn't meet the expectations given for it.
+    #[error("Expectation failed:\n{0}")]
+    Expectation(String),
+
+    /// The user interrupted a request with Ctrl-C.
+    #[error("Request cancelled")]
+    Cancelled,
+
+    /// A request did not complete within the configured timeout.
+    #[error("Request timed out after {}", humantime::format_duration(*.0))]
+    Timeout(Duration),
+
+    /// A health check reached the server, but an assertion about it failed.
+    #[error("Unhealthy: {0}")]
+    Unhealthy(String),
+
+    /// A health check couldn't connect to, initialize or ping the server.
+    #[error("Unreachable: {0}")]
+    Unreachable(String),
+}
+
+impl Error {
+    /// The process exit code to use when a command fails with this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            // Matches the convention of timeout(1)
+            Self::Timeout(_) => 124,
+            // The shell's code for a command interrupted by SIGINT
+            Self::Cancelled => 130,
+            // Health checks tell a server that's down from one that's degraded
+            Self::Unreachable(_) => 2,
+            _ => 1,
+        }
+    }
 }
 
 impl From<String> for Error {
@@ -0,0 +1,202 @@
+//! Assertions on the outcome of a tool call, for use in CI pipelines.
+
+use serde_json::Value;
+use tmcp::schema::{CallToolResult, Content, ResourceContents};
+
+use crate::{Error, Result};
+
+/// What a tool call is expected to produce. Expectations that aren't met
+/// make the command fail.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expectations {
+    /// Strings that must appear in the result's text.
+    pub contains: Vec<String>,
+    /// Values that must be found at paths in the result's JSON.
+    pub json: Vec<(String, Value)>,
+    /// The call must fail, either with a protocol error or an error result.
+    pub error: bool,
+}
+
+impl Expectations {
+    /// Whether there is nothing to check.
+    pub fn is_empty(&self) -> bool {
+        self.contains.is_empty() && self.json.is_empty() && !self.error
+    }
+
+    /// Checks a result, returning a description of each unmet expectation.
+    pub fn check_result(&self, result: &CallToolResult) -> Result<Vec<String>> {
+        let mut failures = Vec::new();
+        let is_error = result.is_error == Some(true);
+        if self.error && !is_error {
+            failures.push("expected the call to fail, but it succeeded".to_string());
+        } else if !self.error && is_error {
+            failures.push("the tool returned an error result".to_string());
+        }
+
+        let text = result_text(result)?;
+        self.check_contains(&text, &mut failures);
+
+        let value = serde_json::to_value(result)?;
+        for (path, expected) in &self.json {
+            match lookup(&value, path) {
+                Some(actual) if actual == expected => {}
+                Some(actual) => failures.push(format!("{path}: expected {expected}, got {actual}")),
+                None => failures.push(format!("{path}: not found in the result")),
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Checks a call that failed with an error, returning a description of
+    /// each unmet expectation. `--expect-contains` is matched against the
+    /// error message.
+    pub fn check_error(&self, error: &Error) -> Vec<String> {
+        let mut failures = Vec::new();
+        if !self.error {
+            failures.push(format!("the call failed: {error}"));
+        }
+        self.check_contains(&error.to_string(), &mut failures);
+        for (path, _) in &self.json {
+            failures.push(format!("{path}: the call failed, so there is no result"));
+        }
+        failures
+    }
+
+    /// Adds a failure for each expected string missing from `text`.
+    fn check_contains(&self, text: &str, failures: &mut Vec<String>) {
+        for needle in &self.contains {
+            if !text.contains(needle.as_str()) {
+                failures.push(format!("expected the result to contain '{needle}'"));
+            }
+        }
+    }
+}
+
+/// Parses an `--expect-json PATH=VALUE` argument. The value is read as JSON
+/// if it parses, and as a plain string otherwise.
+pub fn parse_json_expectation(arg: &str) -> Result<(String, Value)> {
+    let (path, value) = arg
+        .split_once('=')
+        .ok_or_else(|| Error::Format(format!("Expectation '{arg}' must be in PATH=VALUE form")))?;
+    if path.is_empty() {
+        return Err(Error::Format(format!("Expectation '{arg}' has no path")));
+    }
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((path.to_string(), value))
+}
+
+/// Finds the value at a path like `structuredContent.items[0].name`.
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = value;
+    for segment in path.split('.') {
+        let (key, indices) = match segment.find('[') {
+            Some(start) => segment.split_at(start),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indices.split('[').skip(1) {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+/// All the text in a result: text content, embedded text resources and the
+/// structured content as JSON.
+fn result_text(result: &CallToolResult) -> Result<String> {
+    let mut parts = Vec::new();
+    for content in &result.content {
+        match content {
+            Content::Text(text) => parts.push(text.text.clone()),
+            Content::Resource(resource) => {
+                if let ResourceContents::Text(text) = &resource.resource {
+                    parts.push(text.text.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(structured) = &result.structured_content {
+        parts.push(serde_json::to_string(structured)?);
+    }
+    Ok(parts.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_json_expectation() {
+        assert_eq!(
+            parse_json_expectation("structuredContent.count=3").unwrap(),
+            ("structuredContent.count".to_string(), json!(3))
+        );
+        assert_eq!(
+            parse_json_expectation("content[0].text=hello world").unwrap(),
+            ("content[0].text".to_string(), json!("hello world"))
+        );
+        assert!(parse_json_expectation("count").is_err());
+        assert!(parse_json_expectation("=3").is_err());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let value = json!({"items": [{"name": "a"}, {"name": "b", "tags": [[1, 2]]}]});
+        assert_eq!(lookup(&value, "items[1].name"), Some(&json!("b")));
+        assert_eq!(lookup(&value, "items[1].tags[0][1]"), Some(&json!(2)));
+        assert_eq!(lookup(&value, "items[2]"), None);
+        assert_eq!(lookup(&value, "missing"), None);
+    }
+
+    #[test]
+    fn test_check_result() {
+        let mut result = CallToolResult::new().with_text_content("Echo: hello");
+        result.structured_content = Some(json!({"count": 2}));
+
+        let expect = Expectations {
+            contains: vec!["hello".to_string(), "\"count\":2".to_string()],
+            json: vec![
+                ("structuredContent.count".to_string(), json!(2)),
+                ("content[0].text".to_string(), json!("Echo: hello")),
+            ],
+            error: false,
+        };
+        assert!(expect.check_result(&result).unwrap().is_empty());
+
+        let expect = Expectations {
+            contains: vec!["goodbye".to_string()],
+            json: vec![("structuredContent.count".to_string(), json!(3))],
+            error: true,
+        };
+        let failures = expect.check_result(&result).unwrap();
+        assert_eq!(failures.len(), 3);
+        assert!(failures[0].contains("expected the call to fail"));
+        assert!(failures[1].contains("'goodbye'"));
+        assert!(failures[2].contains("expected 3, got 2"));
+
+        result.is_error = Some(true);
+        let expect = Expectations {
+            error: true,
+            ..Expectations::default()
+        };
+        assert!(expect.check_result(&result).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_error() {
+        let error = Error::Other("Tool 'nope' not found".to_string());
+        let expect = Expectations {
+            contains: vec!["not found".to_string()],
+            error: true,
+            ..Expectations::default()
+        };
+        assert!(expect.check_error(&error).is_empty());
+        assert_eq!(Expectations::default().check_error(&error).len(), 1);
+    }
+}
@@ -0,0 +1,287 @@
+//! Exporting a server's tool schemas for other toolchains.
+//!
+//! Each tool's input and output schemas become named definitions, either in
+//! an OpenAPI 3.1 document with an operation per tool, or in a JSON Schema
+//! bundle. Both use JSON Schema 2020-12, the dialect MCP tool schemas are
+//! written in, so the schemas are carried over unchanged.
+
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use serde_json::{Map, Value, json};
+
+use crate::{Error, Result, ctx::Ctx, snapshot::Snapshot, target::Target};
+
+/// The dialect of the exported JSON Schema bundle.
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Formats tool schemas can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    /// An OpenAPI 3.1 document with a POST operation for each tool
+    Openapi,
+    /// A JSON Schema document with each tool's schemas under `$defs`
+    Jsonschema,
+}
+
+/// A tool's schemas, under the names they're exported as.
+#[derive(Debug, Clone)]
+struct ToolSchemas {
+    /// The tool's name.
+    name: String,
+    /// The name for the tool's definitions, such as `SearchDocs`.
+    type_name: String,
+    /// The tool as listed.
+    tool: Value,
+    /// The input schema.
+    input: Value,
+    /// The output schema, if the tool declares one.
+    output: Option<Value>,
+}
+
+/// A name for a tool's definitions: the words of its name, capitalized and
+/// joined. Names that come out the same are numbered.
+pub fn type_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut base: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) {
+        base = format!("Tool{base}");
+    }
+    let mut unique = base.clone();
+    for n in 2.. {
+        if used.insert(unique.clone()) {
+            break;
+        }
+        unique = format!("{base}{n}");
+    }
+    unique
+}
+
+/// A schema as a definition within a larger document, without its own
+/// `$schema` declaration.
+fn definition(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    if let Some(fields) = schema.as_object_mut() {
+        fields.remove("$schema");
+    }
+    schema
+}
+
+/// The schemas of every tool in a snapshot.
+fn tool_schemas(snapshot: &Snapshot) -> Vec<ToolSchemas> {
+    let mut used = HashSet::new();
+    snapshot
+        .tools
+        .iter()
+        .filter_map(|tool| {
+            let name = tool.get("name")?.as_str()?.to_string();
+            Some(ToolSchemas {
+                type_name: type_name(&name, &mut used),
+                input: definition(
+                    tool.get("inputSchema")
+                        .unwrap_or(&json!({"type": "object"})),
+                ),
+                output: tool.get("outputSchema").map(definition),
+                tool: tool.clone(),
+                name,
+            })
+        })
+        .collect()
+}
+
+/// The server's name and version from its initialize result.
+fn server_info(snapshot: &Snapshot) -> (String, String) {
+    let field = |name: &str| {
+        snapshot
+            .init
+            .pointer(&format!("/serverInfo/{name}"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    (field("name"), field("version"))
+}
+
+/// An OpenAPI 3.1 document with a POST operation for each tool, taking its
+/// arguments as the request body and returning its structured content.
+pub fn openapi(snapshot: &Snapshot) -> Value {
+    let (name, version) = server_info(snapshot);
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    for tool in tool_schemas(snapshot) {
+        let input_ref = format!("#/components/schemas/{}Input", tool.type_name);
+        schemas.insert(format!("{}Input", tool.type_name), tool.input);
+        let response_schema = match tool.output {
+            Some(output) => {
+                schemas.insert(format!("{}Output", tool.type_name), output);
+                json!({ "$ref": format!("#/components/schemas/{}Output", tool.type_name) })
+            }
+            None => json!({}),
+        };
+        let mut operation = json!({
+            "operationId": tool.type_name,
+            "requestBody": {
+                "required": true,
+                "content": { "application/json": { "schema": { "$ref": input_ref } } },
+            },
+            "responses": {
+                "200": {
+                    "description": "The tool's result",
+                    "content": { "application/json": { "schema": response_schema } },
+                },
+            },
+        });
+        for (key, field) in [
+            ("summary", "title"),
+            ("description", "description"),
+            ("x-mcp-annotations", "annotations"),
+        ] {
+            if let Some(value) = tool.tool.get(field) {
+                operation[key] = value.clone();
+            }
+        }
+        paths.insert(
+            format!("/tools/{}", tool.name),
+            json!({ "post": operation }),
+        );
+    }
+    let mut info = json!({ "title": name, "version": version });
+    if let Some(instructions) = snapshot.init.get("instructions").and_then(Value::as_str) {
+        info["description"] = json!(instructions);
+    }
+    json!({
+        "openapi": "3.1.0",
+        "info": info,
+        "jsonSchemaDialect": JSON_SCHEMA_DIALECT,
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+/// A JSON Schema document holding each tool's input and output schemas
+/// under `$defs`.
+pub fn json_schema(snapshot: &Snapshot) -> Value {
+    let (name, version) = server_info(snapshot);
+    let mut defs = Map::new();
+    for tool in tool_schemas(snapshot) {
+        let mut input = tool.input;
+        if let Some(fields) = input.as_object_mut() {
+            fields
+                .entry("title")
+                .or_insert_with(|| json!(format!("{} arguments", tool.name)));
+        }
+        defs.insert(format!("{}Input", tool.type_name), input);
+        if let Some(mut output) = tool.output {
+            if let Some(fields) = output.as_object_mut() {
+                fields
+                    .entry("title")
+                    .or_insert_with(|| json!(format!("{} result", tool.name)));
+            }
+            defs.insert(format!("{}Output", tool.type_name), output);
+        }
+    }
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": format!("{name} {version}").trim(),
+        "$defs": defs,
+    })
+}
+
+/// Exports a server's tool schemas, printing the document or writing it to
+/// `path`.
+pub async fn export_schema_command(
+    ctx: &Ctx,
+    target: Target,
+    format: SchemaFormat,
+    path: Option<PathBuf>,
+) -> Result<()> {
+    let snapshot = Snapshot::capture(ctx, &target, false).await?;
+    let document = match format {
+        SchemaFormat::Openapi => openapi(&snapshot),
+        SchemaFormat::Jsonschema => json_schema(&snapshot),
+    };
+    match path {
+        Some(path) => {
+            fs::write(&path, serde_json::to_string_pretty(&document)? + "\n")
+                .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))?;
+            ctx.output.trace_success(format!(
+                "Wrote {} tool schemas to {}",
+                snapshot.tools.len(),
+                path.display()
+            ))?;
+        }
+        // The document is the command's output, so it is printed as JSON regardless of --json
+        None => ctx.output.json_value(&document)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let schema = json!({
+            "$schema": JSON_SCHEMA_DIALECT,
+            "type": "object",
+            "properties": { "query": { "type": "string" } },
+        });
+        let snapshot = Snapshot {
+            target: "cmd://server".to_string(),
+            captured_at: Utc::now(),
+            init: json!({ "serverInfo": { "name": "docs", "version": "1.2" } }),
+            tools: vec![
+                json!({
+                    "name": "search_docs",
+                    "description": "Searches",
+                    "inputSchema": schema,
+                    "outputSchema": { "type": "object" },
+                }),
+                json!({ "name": "search-docs", "inputSchema": { "type": "object" } }),
+                json!({ "name": "2fa", "inputSchema": { "type": "object" } }),
+            ],
+            prompts: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        };
+
+        let document = openapi(&snapshot);
+        assert_eq!(document["info"]["title"], "docs");
+        let operation = &document["paths"]["/tools/search_docs"]["post"];
+        assert_eq!(operation["operationId"], "SearchDocs");
+        assert_eq!(operation["description"], "Searches");
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/SearchDocsOutput"
+        );
+        let schemas = &document["components"]["schemas"];
+        assert!(schemas["SearchDocsInput"].get("$schema").is_none());
+        assert!(schemas.get("SearchDocs2Input").is_some());
+        assert!(schemas.get("Tool2faInput").is_some());
+
+        let bundle = json_schema(&snapshot);
+        assert_eq!(bundle["title"], "docs 1.2");
+        assert_eq!(
+            bundle["$defs"]["SearchDocsInput"]["title"],
+            "search_docs arguments"
+        );
+        assert_eq!(
+            bundle["$defs"]["SearchDocsInput"]["properties"]["query"]["type"],
+            "string"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! jq filters for picking parts of a command's JSON output.
+//!
+//! Filters are compiled and run by jaq, with the jq standard library
+//! available, so anything from `.tools[].name` to `map(select(.n > 1))`
+//! works as it would in jq. As in jq, a filter produces a stream of values.
+
+use std::{fmt, iter};
+
+use jaq_core::{
+    Compiler, Ctx, Native, RcIter, compile,
+    load::{self, Arena, File, Loader, lex, parse},
+};
+use jaq_json::Val;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// A filter applied to JSON output before it is printed.
+#[derive(Clone)]
+pub struct Filter {
+    /// The filter as it was given, for messages.
+    text: String,
+    /// The compiled filter.
+    filter: jaq_core::Filter<Native<Val>>,
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Filter").field(&self.text).finish()
+    }
+}
+
+impl Filter {
+    /// Parses and compiles a filter expression.
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid =
+            |message: String| Error::Format(format!("Invalid filter '{text}': {message}"));
+        let arena = Arena::default();
+        let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+        let modules = loader
+            .load(
+                &arena,
+                File {
+                    code: text,
+                    path: (),
+                },
+            )
+            .map_err(|errors| invalid(describe_load_errors(text, &errors)))?;
+        let filter = Compiler::default()
+            .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+            .compile(modules)
+            .map_err(|errors| invalid(describe_compile_errors(&errors)))?;
+        Ok(Self {
+            text: text.to_string(),
+            filter,
+        })
+    }
+
+    /// Applies the filter to a value, returning every value it produces.
+    pub fn apply(&self, value: &Value) -> Result<Vec<Value>> {
+        let inputs = RcIter::new(iter::empty());
+        self.filter
+            .run((Ctx::new([], &inputs), Val::from(value.clone())))
+            .map(|output| {
+                output
+                    .map(Value::from)
+                    .map_err(|e| Error::Format(format!("Filter failed: {e}")))
+            })
+            .collect()
+    }
+}
+
+/// Describes the first error found while parsing a filter.
+fn describe_load_errors(text: &str, errors: &load::Errors<&str, ()>) -> String {
+    let Some((_, error)) = errors.first() else {
+        return "unknown error".to_string();
+    };
+    match error {
+        load::Error::Io(errors) => errors
+            .first()
+            .map(|(path, e)| format!("{path}: {e}"))
+            .unwrap_or_default(),
+        load::Error::Lex(errors) => errors
+            .first()
+            .map(|(expected, rest): &lex::Error<&str>| {
+                format!(
+                    "expected {} at position {}",
+                    expected.as_str(),
+                    text.len() - rest.len() + 1
+                )
+            })
+            .unwrap_or_default(),
+        load::Error::Parse(errors) => errors
+            .first()
+            .map(|(expected, found): &parse::Error<&str>| match *found {
+                "" => format!("expected {}, found end of filter", expected.as_str()),
+                found => format!("expected {}, found '{found}'", expected.as_str()),
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Describes the first undefined name found while compiling a filter.
+fn describe_compile_errors(errors: &compile::Errors<&str, ()>) -> String {
+    errors
+        .iter()
+        .flat_map(|(_, errors)| errors)
+        .next()
+        .map(|(name, undefined)| format!("undefined {} '{name}'", undefined.as_str()))
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Applies a filter to a value.
+    fn run(filter: &str, value: &Value) -> Vec<Value> {
+        Filter::parse(filter).unwrap().apply(value).unwrap()
+    }
+
+    #[test]
+    fn test_paths() {
+        let value =
+            json!({"tools": [{"name": "a", "n": 1}, {"name": "b", "n": 2}], "odd key": true});
+        assert_eq!(run(".", &value), vec![value.clone()]);
+        assert_eq!(run(".tools[0].name", &value), [json!("a")]);
+        assert_eq!(run(".tools[-1].n", &value), [json!(2)]);
+        assert_eq!(run(".tools[].name", &value), [json!("a"), json!("b")]);
+        assert_eq!(
+            run(".tools | .[] | .name", &value),
+            [json!("a"), json!("b")]
+        );
+        assert_eq!(run(".\"odd key\"", &value), [json!(true)]);
+        assert_eq!(run(".[\"odd key\"]", &value), [json!(true)]);
+        assert_eq!(run(".missing", &value), [Value::Null]);
+        assert_eq!(run(".missing.deeper?", &value), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn test_construction() {
+        let value = json!({"tools": [{"name": "a", "n": 1}, {"name": "b", "n": 2}]});
+        assert_eq!(run("[.tools[].name]", &value), [json!(["a", "b"])]);
+        assert_eq!(
+            run(".tools[] | {name, count: .n}", &value),
+            [
+                json!({"name": "a", "count": 1}),
+                json!({"name": "b", "count": 2})
+            ]
+        );
+        assert_eq!(
+            run(".tools[] | select(.n >= 2) | .name", &value),
+            [json!("b")]
+        );
+        assert_eq!(
+            run(".tools[] | select(.name != \"b\") | .n", &value),
+            [json!(1)]
+        );
+        assert_eq!(run(".tools | length", &value), [json!(2)]);
+        assert_eq!(run(".tools[0] | keys", &value), [json!(["n", "name"])]);
+        assert_eq!(run(".tools | map(.n) | add", &value), [json!(3)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Filter::parse(".tools[").is_err());
+        assert!(Filter::parse("frobnicate").is_err());
+        assert!(Filter::parse(".a )").is_err());
+        let err = Filter::parse(".name.first")
+            .unwrap()
+            .apply(&json!({"name": "x"}))
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot use \"x\""));
+    }
+}
@@ -0,0 +1,451 @@
+//! Tracing of the raw JSON-RPC frames exchanged with a server, for debugging
+//! servers that don't follow the spec.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    pin::{Pin, pin},
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf, ReadHalf, WriteHalf, split},
+    sync::Notify,
+};
+
+use crate::{Error, Result};
+
+/// Which way a frame travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From mcptool to the server.
+    Sent,
+    /// From the server to mcptool.
+    Received,
+}
+
+impl Direction {
+    /// The marker shown before frames going this way.
+    fn marker(self) -> &'static str {
+        match self {
+            Self::Sent => "-->",
+            Self::Received => "<--",
+        }
+    }
+}
+
+/// A frame kept by a trace, with when it passed through.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Which way it travelled.
+    pub direction: Direction,
+    /// When it passed through.
+    pub at: Instant,
+    /// The message it carried.
+    pub message: Value,
+}
+
+impl Frame {
+    /// Whether this frame is a response or error answering the request with
+    /// `id`.
+    pub fn answers(&self, id: &Value) -> bool {
+        self.direction == Direction::Received
+            && self.message.get("method").is_none()
+            && self.message.get("id") == Some(id)
+    }
+
+    /// The id of the request this frame sent, if it sent one.
+    fn sent_request(&self) -> Option<&Value> {
+        if self.direction != Direction::Sent || self.message.get("method").is_none() {
+            return None;
+        }
+        self.message.get("id").filter(|id| !id.is_null())
+    }
+}
+
+/// The id of the last request sent, if the server hasn't answered it yet.
+pub fn unanswered_request(frames: &[Frame]) -> Option<Value> {
+    let id = frames.iter().rev().find_map(Frame::sent_request)?;
+    (!frames.iter().any(|frame| frame.answers(id))).then(|| id.clone())
+}
+
+/// State shared by every traced stream of a session.
+struct TraceState {
+    /// Where trace lines are written.
+    sink: Box<dyn Write + Send>,
+    /// When tracing started, for timestamps.
+    start: Instant,
+    /// When each outstanding request was sent, by direction and id, so the
+    /// response can show how long it took.
+    pending: HashMap<(Direction, String), Instant>,
+    /// Frames kept for inspection, once keeping them has been asked for.
+    kept: Option<Vec<Frame>>,
+}
+
+/// A log of protocol frames, written to stderr or a file. Clones share the
+/// same log.
+#[derive(Clone)]
+pub struct ProtocolTrace {
+    /// The shared trace state.
+    state: Arc<Mutex<TraceState>>,
+    /// Signalled whenever a frame is kept.
+    kept: Arc<Notify>,
+}
+
+impl ProtocolTrace {
+    /// A trace written to the given writer.
+    pub fn new(sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TraceState {
+                sink,
+                start: Instant::now(),
+                pending: HashMap::new(),
+                kept: None,
+            })),
+            kept: Arc::new(Notify::new()),
+        }
+    }
+
+    /// A trace written to stderr.
+    pub fn stderr() -> Self {
+        Self::new(Box::new(io::stderr()))
+    }
+
+    /// A trace written to a file, replacing its contents.
+    pub fn file(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| Error::Other(format!("Failed to create {}: {e}", path.display())))?;
+        Ok(Self::new(Box::new(file)))
+    }
+
+    /// A trace that keeps frames: `trace` if one is being written, or one
+    /// written nowhere otherwise.
+    pub fn keeping(trace: Option<Self>) -> Self {
+        let trace = trace.unwrap_or_else(|| Self::new(Box::new(io::sink())));
+        trace.keep_frames();
+        trace
+    }
+
+    /// Starts keeping a copy of each valid frame, for [`Self::frames`].
+    pub fn keep_frames(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.kept.get_or_insert_with(Vec::new);
+    }
+
+    /// The frames kept since [`Self::keep_frames`] was called.
+    pub fn frames(&self) -> Vec<Frame> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.kept.clone().unwrap_or_default()
+    }
+
+    /// Drops the frames kept so far, while still keeping later ones.
+    pub fn clear_frames(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(kept) = &mut state.kept {
+            kept.clear();
+        }
+    }
+
+    /// Waits until `find` finds something among the kept frames, checking
+    /// again each time a frame is kept, and returns what it found.
+    pub async fn wait_for<T>(&self, find: impl Fn(&[Frame]) -> Option<T>) -> T {
+        loop {
+            let mut kept = pin!(self.kept.notified());
+            // Registered before looking, so a frame kept meanwhile isn't missed
+            kept.as_mut().enable();
+            if let Some(found) = find(&self.frames()) {
+                return found;
+            }
+            kept.await;
+        }
+    }
+
+    /// Records a frame. Tracing is best-effort, so failures to write the
+    /// trace are ignored rather than breaking the connection.
+    pub fn frame(&self, direction: Direction, frame: &[u8]) {
+        let text = String::from_utf8_lossy(frame);
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let message = serde_json::from_str::<Value>(text).ok();
+        let summary = match &message {
+            Some(message) => summarize(message, direction, now, &mut state.pending),
+            None => "invalid JSON".to_string(),
+        };
+        let elapsed = now.duration_since(state.start).as_secs_f64();
+        let line = format!("[{elapsed:>9.3}s] {} {summary}: {text}", direction.marker());
+        writeln!(state.sink, "{line}").ok();
+        state.sink.flush().ok();
+        if let (Some(kept), Some(message)) = (&mut state.kept, message) {
+            kept.push(Frame {
+                direction,
+                at: now,
+                message,
+            });
+            self.kept.notify_waiters();
+        }
+    }
+}
+
+/// The JSON form of a request id, used to pair responses with requests.
+fn id_key(id: &Value) -> String {
+    id.to_string()
+}
+
+/// Describes a message by kind, id and method, and for responses, how long
+/// after the request they arrived.
+fn summarize(
+    message: &Value,
+    direction: Direction,
+    now: Instant,
+    pending: &mut HashMap<(Direction, String), Instant>,
+) -> String {
+    let method = message.get("method").and_then(Value::as_str);
+    let id = message.get("id").filter(|id| !id.is_null());
+    match (method, id) {
+        (Some(method), Some(id)) => {
+            pending.insert((direction, id_key(id)), now);
+            format!("request #{id} {method}")
+        }
+        (Some(method), None) => format!("notification {method}"),
+        (None, Some(id)) => {
+            // The request went the other way
+            let request_direction = match direction {
+                Direction::Sent => Direction::Received,
+                Direction::Received => Direction::Sent,
+            };
+            let kind = if message.get("error").is_some() {
+                "error"
+            } else {
+                "response"
+            };
+            match pending.remove(&(request_direction, id_key(id))) {
+                Some(sent) => format!("{kind} #{id} after {}", millis(now.duration_since(sent))),
+                None => format!("{kind} #{id}"),
+            }
+        }
+        (None, None) if message.is_array() => "batch".to_string(),
+        (None, None) => "unknown message".to_string(),
+    }
+}
+
+/// A duration in milliseconds, for display.
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// A stream that records the newline-delimited frames passing through it.
+/// Without a trace it passes data through untouched.
+pub struct Traced<S> {
+    /// The wrapped stream.
+    inner: S,
+    /// Where frames are recorded.
+    trace: Option<ProtocolTrace>,
+    /// Which way data read from or written to this stream travels.
+    direction: Direction,
+    /// Data that hasn't yet made a complete frame.
+    buffer: Vec<u8>,
+}
+
+impl<S> Traced<S> {
+    /// Wraps the stream frames from the server are read from.
+    pub fn reader(inner: S, trace: Option<ProtocolTrace>) -> Self {
+        Self::new(inner, trace, Direction::Received)
+    }
+
+    /// Wraps the stream frames to the server are written to.
+    pub fn writer(inner: S, trace: Option<ProtocolTrace>) -> Self {
+        Self::new(inner, trace, Direction::Sent)
+    }
+
+    /// Wraps a stream carrying frames in one direction.
+    fn new(inner: S, trace: Option<ProtocolTrace>, direction: Direction) -> Self {
+        Self {
+            inner,
+            trace,
+            direction,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Adds data to the buffer, recording each frame it completes.
+    fn record(&mut self, data: &[u8]) {
+        let Some(trace) = &self.trace else {
+            return;
+        };
+        self.buffer.extend_from_slice(data);
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let frame: Vec<u8> = self.buffer.drain(..=end).collect();
+            trace.frame(self.direction, &frame);
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Traced<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.record(&buf.filled()[before..]);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Traced<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.record(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Splits a bidirectional stream into traced read and write halves.
+pub fn traced_split<S: AsyncRead + AsyncWrite>(
+    stream: S,
+    trace: Option<ProtocolTrace>,
+) -> (Traced<ReadHalf<S>>, Traced<WriteHalf<S>>) {
+    let (reader, writer) = split(stream);
+    (
+        Traced::reader(reader, trace.clone()),
+        Traced::writer(writer, trace),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A writer that appends to a shared buffer.
+    #[derive(Clone, Default)]
+    struct Buffer {
+        /// Everything written so far.
+        data: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.data.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let buffer = Buffer::default();
+        let trace = ProtocolTrace::new(Box::new(buffer.clone()));
+        trace.keep_frames();
+        let mut writer = Traced::writer(Vec::new(), Some(trace.clone()));
+        let mut reader = Traced::reader(Vec::new(), Some(trace.clone()));
+
+        // Frames can arrive in pieces
+        writer.record(br#"{"jsonrpc":"2.0","id":1,"#);
+        writer.record(b"\"method\":\"tools/list\"}\n");
+        reader.record(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n");
+        writer
+            .record(b"{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\nnot json\n");
+
+        let log = String::from_utf8(buffer.data.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("--> request #1 tools/list: {"));
+        assert!(lines[1].contains("<-- response #1 after "));
+        assert!(lines[2].contains("--> notification notifications/initialized"));
+        assert!(lines[3].contains("--> invalid JSON: not json"));
+
+        let frames = trace.frames();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].direction, Direction::Received);
+        assert_eq!(frames[1].message["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let trace = ProtocolTrace::new(Box::new(io::sink()));
+        trace.keep_frames();
+        let waiting = tokio::spawn({
+            let trace = trace.clone();
+            async move {
+                trace
+                    .wait_for(|frames| {
+                        frames
+                            .iter()
+                            .find_map(|frame| frame.message.get("id").cloned())
+                    })
+                    .await
+            }
+        });
+        trace.frame(
+            Direction::Sent,
+            br#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        );
+        trace.frame(
+            Direction::Sent,
+            br#"{"jsonrpc":"2.0","id":7,"method":"ping"}"#,
+        );
+        assert_eq!(waiting.await.unwrap(), 7);
+    }
+
+    #[test]
+    fn test_unanswered_request() {
+        let trace = ProtocolTrace::keeping(None);
+        trace.frame(
+            Direction::Sent,
+            br#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+        );
+        trace.frame(
+            Direction::Received,
+            br#"{"jsonrpc":"2.0","id":1,"result":{}}"#,
+        );
+        assert_eq!(unanswered_request(&trace.frames()), None);
+
+        trace.frame(
+            Direction::Sent,
+            br#"{"jsonrpc":"2.0","id":2,"method":"tools/call"}"#,
+        );
+        trace.frame(
+            Direction::Received,
+            br#"{"jsonrpc":"2.0","method":"notifications/progress"}"#,
+        );
+        trace.frame(
+            Direction::Sent,
+            br#"{"jsonrpc":"2.0","method":"notifications/cancelled"}"#,
+        );
+        assert_eq!(unanswered_request(&trace.frames()), Some(json!(2)));
+
+        trace.clear_frames();
+        assert_eq!(unanswered_request(&trace.frames()), None);
+    }
+}
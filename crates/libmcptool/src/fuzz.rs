@@ -0,0 +1,586 @@
+//! Schema-driven fuzzing of a tool.
+//!
+//! Arguments are generated from the tool's input schema in three kinds,
+//! taken in turn: valid values, values at the edges of what the schema
+//! allows, and deliberately invalid arguments. Each case is sent to the
+//! server, and anything other than a clean result is reported with the
+//! arguments that caused it.
+
+use std::{collections::HashMap, fmt};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use tmcp::{Arguments, ServerAPI, schema::CallToolResult};
+
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    ctx::Ctx,
+    output::Output,
+    target::Target,
+};
+
+/// How deep nested objects and arrays are generated before falling back to
+/// empty values.
+const MAX_DEPTH: usize = 4;
+
+/// Characters used for generated strings.
+const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _-";
+
+/// Strings that often trip up servers.
+const EDGE_STRINGS: &[&str] = &[
+    "",
+    " ",
+    "ñandú 文字 🎉",
+    "\"'\\<>%{}$`;|&",
+    "\0\n\r\t",
+    "../../../../etc/passwd",
+];
+
+/// Name of the property added to arguments that don't allow unknown ones.
+const UNKNOWN_PROPERTY: &str = "__fuzz_unknown";
+
+/// The kind of arguments a case is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseKind {
+    /// Arguments that match the schema.
+    Valid,
+    /// Arguments at the edges of what the schema allows.
+    Boundary,
+    /// Arguments that break the schema in one place.
+    Invalid,
+}
+
+impl CaseKind {
+    /// The kind of the case at a position in a run, cycling through all kinds.
+    fn for_iteration(iteration: usize) -> Self {
+        [Self::Valid, Self::Boundary, Self::Invalid][iteration % 3]
+    }
+}
+
+impl fmt::Display for CaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Valid => write!(f, "valid"),
+            Self::Boundary => write!(f, "boundary"),
+            Self::Invalid => write!(f, "invalid"),
+        }
+    }
+}
+
+/// What went wrong in a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    /// The server stopped answering, and had to be reconnected.
+    Crash,
+    /// The server answered with a JSON-RPC error.
+    ProtocolError,
+    /// The call ran past the timeout.
+    Timeout,
+    /// The tool returned a result flagged with `isError`.
+    ToolError,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crash => write!(f, "crash"),
+            Self::ProtocolError => write!(f, "protocol error"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::ToolError => write!(f, "tool error"),
+        }
+    }
+}
+
+/// A call that didn't return a clean result.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// The position of the case in the run, from 1.
+    pub iteration: usize,
+    /// The kind of arguments sent.
+    pub kind: CaseKind,
+    /// What went wrong.
+    pub outcome: Outcome,
+    /// The error, or the text of the error result.
+    pub message: String,
+    /// The arguments that caused it.
+    pub arguments: Map<String, Value>,
+}
+
+/// Generates tool arguments from a JSON Schema.
+pub struct Generator {
+    /// The source of randomness, seeded so runs can be repeated.
+    rng: StdRng,
+}
+
+impl Generator {
+    /// A generator whose output is fixed by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Generates the arguments for one case from a tool's input schema.
+    pub fn arguments(&mut self, schema: &Value, kind: CaseKind) -> Map<String, Value> {
+        let mut arguments = match kind {
+            CaseKind::Valid | CaseKind::Invalid => self.object(schema, 0, Self::valid),
+            CaseKind::Boundary => self.object(schema, 0, Self::boundary),
+        };
+        if kind == CaseKind::Invalid {
+            self.break_arguments(schema, &mut arguments);
+        }
+        arguments
+    }
+
+    /// Generates an object's properties with `value`, always including the
+    /// required ones and each optional one half of the time.
+    fn object(
+        &mut self,
+        schema: &Value,
+        depth: usize,
+        value: fn(&mut Self, &Value, usize) -> Value,
+    ) -> Map<String, Value> {
+        let mut object = Map::new();
+        if depth > MAX_DEPTH {
+            return object;
+        }
+        let required = required(schema);
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                if required.contains(&name.as_str()) || self.rng.random_bool(0.5) {
+                    object.insert(name.clone(), value(self, property, depth + 1));
+                }
+            }
+        }
+        object
+    }
+
+    /// A random value that matches a schema.
+    fn valid(&mut self, schema: &Value, depth: usize) -> Value {
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(choices) = schema.get("enum").and_then(Value::as_array)
+            && let Some(choice) = choices.choose(&mut self.rng)
+        {
+            return choice.clone();
+        }
+        match schema_type(schema) {
+            "string" => {
+                let min = bound(schema, "minLength").unwrap_or(0);
+                let max = bound(schema, "maxLength").unwrap_or(min + 16).max(min);
+                let len = self.rng.random_range(min..=max.min(min + 16));
+                Value::String(self.string(len))
+            }
+            "integer" => {
+                let (min, max) = integer_range(schema);
+                let low = min.max(-1000).min(max);
+                let high = max.min(1000).max(low);
+                json!(self.rng.random_range(low..=high))
+            }
+            "number" => {
+                let max = number(schema, "maximum");
+                let low = number(schema, "minimum")
+                    .unwrap_or(-1000.0)
+                    .min(max.unwrap_or(f64::MAX));
+                let high = max.unwrap_or(1000.0).max(low);
+                json!(self.rng.random_range(low..=high))
+            }
+            "boolean" => Value::Bool(self.rng.random_bool(0.5)),
+            "null" => Value::Null,
+            "array" => {
+                let min = bound(schema, "minItems").unwrap_or(0);
+                let max = bound(schema, "maxItems").unwrap_or(min + 3).max(min);
+                let len = if depth > MAX_DEPTH {
+                    min
+                } else {
+                    self.rng.random_range(min..=max.min(min + 3))
+                };
+                let items = schema.get("items").unwrap_or(&Value::Bool(true));
+                Value::Array((0..len).map(|_| self.valid(items, depth + 1)).collect())
+            }
+            _ => Value::Object(self.object(schema, depth, Self::valid)),
+        }
+    }
+
+    /// A value at the edge of what a schema allows, such as an empty or very
+    /// long string, or a number at the end of its range.
+    fn boundary(&mut self, schema: &Value, depth: usize) -> Value {
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(choices) = schema.get("enum").and_then(Value::as_array)
+            && !choices.is_empty()
+        {
+            let edge = if self.rng.random_bool(0.5) {
+                choices.last()
+            } else {
+                choices.first()
+            };
+            return edge.cloned().unwrap_or(Value::Null);
+        }
+        match schema_type(schema) {
+            "string" => match (bound(schema, "minLength"), bound(schema, "maxLength")) {
+                (Some(min), Some(max)) => {
+                    Value::String(self.string(if self.rng.random_bool(0.5) { min } else { max }))
+                }
+                (Some(min), None) => Value::String(self.string(min)),
+                (None, Some(max)) => Value::String(self.string(max)),
+                (None, None) => {
+                    if self.rng.random_bool(0.2) {
+                        Value::String(self.string(10_000))
+                    } else {
+                        let edge = EDGE_STRINGS.choose(&mut self.rng).copied().unwrap_or("");
+                        Value::String(edge.to_string())
+                    }
+                }
+            },
+            "integer" => {
+                let (min, max) = integer_range(schema);
+                let edge = [min, max, 0, -1].choose(&mut self.rng).copied();
+                json!(edge.unwrap_or(0).clamp(min, max))
+            }
+            "number" => {
+                let min = number(schema, "minimum").unwrap_or(-f64::MAX);
+                let max = number(schema, "maximum").unwrap_or(f64::MAX).max(min);
+                let edge = [min, max, 0.0, f64::MIN_POSITIVE]
+                    .choose(&mut self.rng)
+                    .copied();
+                json!(edge.unwrap_or(0.0).clamp(min, max))
+            }
+            "boolean" => Value::Bool(self.rng.random_bool(0.5)),
+            "null" => Value::Null,
+            "array" => {
+                let min = bound(schema, "minItems").unwrap_or(0);
+                let len = match bound(schema, "maxItems") {
+                    Some(max) if self.rng.random_bool(0.5) => max.min(100),
+                    _ => min,
+                };
+                let items = schema.get("items").unwrap_or(&Value::Bool(true));
+                Value::Array((0..len).map(|_| self.boundary(items, depth + 1)).collect())
+            }
+            _ => Value::Object(self.object(schema, depth, Self::boundary)),
+        }
+    }
+
+    /// Breaks valid arguments in one place: a required property is dropped,
+    /// a property gets a value of the wrong type or null, or an unknown
+    /// property is added.
+    fn break_arguments(&mut self, schema: &Value, arguments: &mut Map<String, Value>) {
+        let required = required(schema);
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let names: Vec<String> = arguments.keys().cloned().collect();
+        match self.rng.random_range(0..4) {
+            0 if !required.is_empty() => {
+                if let Some(name) = required.choose(&mut self.rng) {
+                    arguments.remove(*name);
+                }
+            }
+            1 | 2 if !names.is_empty() => {
+                if let Some(name) = names.choose(&mut self.rng) {
+                    let property = properties
+                        .and_then(|properties| properties.get(name))
+                        .unwrap_or(&Value::Bool(true));
+                    let value = if self.rng.random_bool(0.5) {
+                        wrong_type(property)
+                    } else {
+                        Value::Null
+                    };
+                    arguments.insert(name.clone(), value);
+                }
+            }
+            _ => {
+                arguments.insert(
+                    UNKNOWN_PROPERTY.to_string(),
+                    Value::String("unexpected".to_string()),
+                );
+            }
+        }
+    }
+
+    /// A random string of `len` characters.
+    fn string(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| char::from(CHARSET[self.rng.random_range(0..CHARSET.len())]))
+            .collect()
+    }
+}
+
+/// The required property names of an object schema.
+fn required(schema: &Value) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// The type a schema describes, taking the first of a list of types and
+/// guessing from other keywords when there is none.
+fn schema_type(schema: &Value) -> &str {
+    match schema.get("type") {
+        Some(Value::String(name)) => name.as_str(),
+        Some(Value::Array(names)) => names.iter().find_map(Value::as_str).unwrap_or("string"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "string",
+    }
+}
+
+/// A non-negative size bound, such as `maxLength`.
+fn bound(schema: &Value, keyword: &str) -> Option<usize> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_u64)
+        .and_then(|n| usize::try_from(n).ok())
+}
+
+/// A numeric bound, such as `minimum`.
+fn number(schema: &Value, keyword: &str) -> Option<f64> {
+    schema.get(keyword).and_then(Value::as_f64)
+}
+
+/// The inclusive range of an integer schema, taking exclusive bounds into
+/// account.
+fn integer_range(schema: &Value) -> (i64, i64) {
+    let bound = |inclusive: &str, exclusive: &str, step: i64| {
+        schema.get(inclusive).and_then(Value::as_i64).or_else(|| {
+            schema
+                .get(exclusive)
+                .and_then(Value::as_i64)
+                .map(|n| n.saturating_add(step))
+        })
+    };
+    let min = bound("minimum", "exclusiveMinimum", 1).unwrap_or(i64::MIN);
+    let max = bound("maximum", "exclusiveMaximum", -1).unwrap_or(i64::MAX);
+    (min, max.max(min))
+}
+
+/// A value of a different type from the one a schema asks for.
+fn wrong_type(schema: &Value) -> Value {
+    if schema.get("enum").is_some() {
+        return Value::String("__not_a_choice__".to_string());
+    }
+    match schema_type(schema) {
+        "string" => json!(42),
+        "integer" => json!(1.5),
+        "number" => json!("not a number"),
+        "boolean" => json!("yes"),
+        "array" => json!({"not": "an array"}),
+        "null" => json!(0),
+        _ => json!("not an object"),
+    }
+}
+
+/// The text of an error result, for reporting.
+fn error_text(result: &CallToolResult) -> String {
+    serde_json::to_value(&result.content)
+        .ok()
+        .and_then(|content| {
+            content
+                .as_array()?
+                .iter()
+                .find_map(|block| block.get("text")?.as_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| "Tool returned an error result".to_string())
+}
+
+/// Calls a tool with generated arguments, and reports every call that
+/// crashed the server, failed with a protocol error, timed out or returned
+/// an error result. A crashed server is reconnected before the run goes on.
+/// `seed` makes a run repeatable, and a random one is used if it is not
+/// given. Fails if the server crashed.
+pub async fn fuzz_command(
+    ctx: &Ctx,
+    target: Target,
+    tool_name: &str,
+    iterations: usize,
+    seed: Option<u64>,
+) -> Result<()> {
+    let output = &ctx.output;
+    let (mut client, _) = client::get_client(ctx, &target).await?;
+    let tools = ctx.request(client.list_tools(None)).await?;
+    let tool = tools
+        .tools
+        .iter()
+        .find(|t| t.name == tool_name)
+        .ok_or_else(|| Error::Other(format!("Tool '{tool_name}' not found")))?;
+    let schema = serde_json::to_value(&tool.input_schema)?;
+
+    let seed = seed.unwrap_or_else(|| rand::rng().random());
+    output.h1(format!("Fuzzing {tool_name}"))?;
+    output.kv("Seed", seed.to_string())?;
+    output.kv("Iterations", iterations.to_string())?;
+
+    let mut generator = Generator::new(seed);
+    let mut findings = Vec::new();
+    for index in 0..iterations {
+        let kind = CaseKind::for_iteration(index);
+        let arguments = generator.arguments(&schema, kind);
+        output.progress(format!(
+            "{}/{iterations} {kind} cases, {} finding(s)",
+            index + 1,
+            findings.len()
+        ))?;
+        let call = Arguments::from(arguments.clone().into_iter().collect::<HashMap<_, _>>());
+        let failure = match ctx.request(client.call_tool(tool_name, Some(call))).await {
+            Ok(result) if result.is_error == Some(true) => {
+                Some((Outcome::ToolError, error_text(&result)))
+            }
+            Ok(_) => None,
+            Err(e @ Error::Timeout(_)) => Some((Outcome::Timeout, e.to_string())),
+            Err(e) => Some(classify(ctx, &target, &mut client, e).await?),
+        };
+        if let Some((outcome, message)) = failure {
+            findings.push(Finding {
+                iteration: index + 1,
+                kind,
+                outcome,
+                message,
+                arguments,
+            });
+        }
+    }
+    output.end_progress()?;
+    report(output, seed, iterations, &findings)
+}
+
+/// Tells a protocol error from a crash by pinging the server after a failed
+/// call. A server that doesn't answer is reconnected.
+async fn classify(
+    ctx: &Ctx,
+    target: &Target,
+    client: &mut Connected<()>,
+    error: Error,
+) -> Result<(Outcome, String)> {
+    if ctx.request(client.ping()).await.is_ok() {
+        return Ok((Outcome::ProtocolError, error.to_string()));
+    }
+    ctx.output
+        .trace_warn(format!("Server stopped answering ({error}), reconnecting"))?;
+    let (reconnected, _) = client::get_client(ctx, target)
+        .await
+        .map_err(|e| Error::Other(format!("Server crashed and could not be reconnected: {e}")))?;
+    *client = reconnected;
+    Ok((Outcome::Crash, error.to_string()))
+}
+
+/// Prints the findings of a run and a count of each outcome. Fails if any
+/// call crashed the server.
+fn report(output: &Output, seed: u64, iterations: usize, findings: &[Finding]) -> Result<()> {
+    let count = |outcome| findings.iter().filter(|f| f.outcome == outcome).count();
+    let crashes = count(Outcome::Crash);
+    if output.structured() {
+        output.json_value(&json!({
+            "seed": seed,
+            "iterations": iterations,
+            "crashes": crashes,
+            "protocolErrors": count(Outcome::ProtocolError),
+            "timeouts": count(Outcome::Timeout),
+            "toolErrors": count(Outcome::ToolError),
+            "findings": findings,
+        }))?;
+    } else {
+        for finding in findings {
+            output.h2(format!(
+                "#{} {} ({} arguments)",
+                finding.iteration, finding.outcome, finding.kind
+            ))?;
+            let out = output.indent();
+            out.kv("Message", &finding.message)?;
+            out.kv("Arguments", serde_json::to_string(&finding.arguments)?)?;
+        }
+        output.text(format!(
+            "{iterations} calls: {} clean, {crashes} crashes, {} protocol errors, {} timeouts, {} tool errors",
+            iterations - findings.len(),
+            count(Outcome::ProtocolError),
+            count(Outcome::Timeout),
+            count(Outcome::ToolError),
+        ))?;
+        output.text(format!("Repeat this run with --seed {seed}"))?;
+    }
+    if crashes > 0 {
+        return Err(Error::Other(format!(
+            "{crashes} of {iterations} call(s) crashed the server"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema;
+
+    /// A schema exercising each type and the bounds the generator honours.
+    fn sample_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 2, "maxLength": 8},
+                "count": {"type": "integer", "minimum": 1, "maximum": 10},
+                "ratio": {"type": "number", "minimum": 0, "maximum": 1},
+                "mode": {"enum": ["fast", "slow"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 3},
+                "options": {
+                    "type": "object",
+                    "properties": {"verbose": {"type": "boolean"}},
+                    "required": ["verbose"]
+                }
+            },
+            "required": ["name", "count"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_valid_and_boundary_arguments_match_schema() {
+        let schema = sample_schema();
+        let mut generator = Generator::new(7);
+        for _ in 0..200 {
+            for kind in [CaseKind::Valid, CaseKind::Boundary] {
+                let arguments = generator.arguments(&schema, kind);
+                let violations = schema::validate(&schema, &Value::Object(arguments)).unwrap();
+                assert!(violations.is_empty(), "{kind}: {violations:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_arguments_break_schema() {
+        let schema = sample_schema();
+        let mut generator = Generator::new(7);
+        for _ in 0..200 {
+            let arguments = generator.arguments(&schema, CaseKind::Invalid);
+            let violations = schema::validate(&schema, &Value::Object(arguments)).unwrap();
+            assert!(!violations.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_seed_repeats_run() {
+        let schema = sample_schema();
+        let mut first = Generator::new(42);
+        let mut second = Generator::new(42);
+        for index in 0..30 {
+            let kind = CaseKind::for_iteration(index);
+            assert_eq!(
+                first.arguments(&schema, kind),
+                second.arguments(&schema, kind)
+            );
+        }
+    }
+
+    #[test]
+    fn test_integer_range() {
+        assert_eq!(
+            integer_range(&json!({"exclusiveMinimum": 0, "maximum": 5})),
+            (1, 5)
+        );
+        assert_eq!(integer_range(&json!({})), (i64::MIN, i64::MAX));
+    }
+}
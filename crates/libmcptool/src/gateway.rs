@@ -0,0 +1,926 @@
+//! A gateway that serves several upstream servers as one.
+//!
+//! Clients see a single server whose tools and prompts are the union of the
+//! upstreams', each name prefixed with its upstream's name and `__`, as in
+//! `fs__read_file`, and calls are routed back by that prefix. Resources keep
+//! their URIs, and reads go to the upstream that listed the URI, or failing
+//! that the one with the longest matching template. Each client gets its own
+//! connections to the upstreams, opening more as its requests overlap, and
+//! the upstreams' notifications are passed on to it. A policy can restrict
+//! what clients can use, and rate limits how fast, both matching the
+//! namespaced names they see.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
+
+use serde_json::{Map, Value, json};
+use tmcp::{
+    Arguments, ClientCtx, ClientHandler, ServerAPI,
+    schema::{InitializeResult, ServerNotification},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, split, stdin, stdout},
+    net::TcpListener,
+    sync::mpsc,
+    task::{self, JoinSet},
+    time,
+};
+
+use crate::{
+    Error, Result,
+    client::{self, Connected},
+    ctx::{Ctx, VERSION},
+    jsonrpc::{self, Answer, METHOD_NOT_FOUND, RESOURCE_NOT_FOUND, RpcError, required},
+    mcp::{self, Listing},
+    negotiate,
+    output::Output,
+    policy::{Guard, Policy},
+    proxy::Listen,
+    ratelimit::{Limiter, Limits},
+    target::Target,
+};
+
+/// What separates an upstream's name from the names of its tools and
+/// prompts.
+const SEPARATOR: &str = "__";
+
+/// An upstream server, and the name its tools and prompts are prefixed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    /// The prefix, such as "fs".
+    pub name: String,
+    /// Where the server is.
+    pub target: Target,
+}
+
+/// Splits an `--upstream` value of the form `name=target` into its name and
+/// target. Names are letters, digits and `-`, so they can't be confused
+/// with the separator.
+pub fn parse_upstream(input: &str) -> Result<(String, String)> {
+    let Some((name, target)) = input.split_once('=') else {
+        return Err(Error::Format(format!(
+            "Invalid upstream '{input}'. Use name=target"
+        )));
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(Error::Format(format!(
+            "Invalid upstream name '{name}'. Use letters, digits and '-'"
+        )));
+    }
+    if target.is_empty() {
+        return Err(Error::Format(format!("Upstream '{name}' has no target")));
+    }
+    Ok((name.to_string(), target.to_string()))
+}
+
+/// How the gateway serves its upstreams.
+pub struct GatewayOptions {
+    /// Where clients connect.
+    pub listen: Listen,
+    /// The servers to aggregate.
+    pub upstreams: Vec<Upstream>,
+    /// A policy file restricting the tools, prompts and resources clients
+    /// can use.
+    pub policy: Option<PathBuf>,
+    /// A file of rate limits on requests.
+    pub limits: Option<PathBuf>,
+}
+
+/// The part of a URI template before its first variable, which every URI
+/// it expands to starts with.
+fn template_prefix(template: &str) -> &str {
+    template.split('{').next().unwrap_or_default()
+}
+
+/// Which upstream serves each resource, learned from their listings.
+#[derive(Debug, Clone, Default)]
+struct Routes {
+    /// Upstreams by the URIs of the resources they listed. The first
+    /// upstream to list a URI serves it.
+    resources: HashMap<String, usize>,
+    /// The prefixes of each upstream's resource templates.
+    templates: Vec<(String, usize)>,
+}
+
+impl Routes {
+    /// The upstream that serves a URI: the one that listed it, or the one
+    /// with the longest template prefix it starts with.
+    fn upstream(&self, uri: &str) -> Option<usize> {
+        self.resources.get(uri).copied().or_else(|| {
+            self.templates
+                .iter()
+                .filter(|(prefix, _)| uri.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, index)| *index)
+        })
+    }
+}
+
+/// Locks a mutex, which stays usable even if a holder panicked.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The handler of a connection to an upstream, which passes the upstream's
+/// notifications on to the client.
+#[derive(Clone)]
+struct Forward {
+    /// The upstream's name, given as the logger of log messages that don't
+    /// name one.
+    upstream: String,
+    /// Where notifications for the client go.
+    sender: mpsc::UnboundedSender<Value>,
+    /// The client's progress token for the request the connection is
+    /// making, if it asked for progress. A connection makes one request at
+    /// a time, so any progress it reports belongs to that request.
+    progress: Arc<Mutex<Option<Value>>>,
+}
+
+impl Forward {
+    /// Notes the progress token of the request the connection makes next,
+    /// or that it's done with one.
+    fn track(&self, token: Option<Value>) {
+        *lock(&self.progress) = token;
+    }
+
+    /// An upstream notification as the client should see it. Progress goes
+    /// out under the client's token, and is dropped if the client didn't
+    /// ask for it.
+    fn forwarded(&self, notification: ServerNotification) -> Option<Value> {
+        let (method, params) = match notification {
+            ServerNotification::Progress {
+                progress,
+                total,
+                message,
+                ..
+            } => {
+                let token = lock(&self.progress).clone()?;
+                let mut params = json!({ "progressToken": token, "progress": progress });
+                if let Some(total) = total {
+                    params["total"] = json!(total);
+                }
+                if let Some(message) = message {
+                    params["message"] = json!(message);
+                }
+                ("notifications/progress", params)
+            }
+            ServerNotification::LoggingMessage {
+                level,
+                logger,
+                data,
+            } => (
+                "notifications/message",
+                json!({
+                    "level": level,
+                    "logger": logger.unwrap_or_else(|| self.upstream.clone()),
+                    "data": data,
+                }),
+            ),
+            ServerNotification::ResourceUpdated { uri } => {
+                ("notifications/resources/updated", json!({ "uri": uri }))
+            }
+            ServerNotification::ToolListChanged => ("notifications/tools/list_changed", json!({})),
+            ServerNotification::PromptListChanged => {
+                ("notifications/prompts/list_changed", json!({}))
+            }
+            ServerNotification::ResourceListChanged => {
+                ("notifications/resources/list_changed", json!({}))
+            }
+            _ => return None,
+        };
+        Some(jsonrpc::notification(method, params))
+    }
+}
+
+#[async_trait::async_trait]
+impl ClientHandler for Forward {
+    async fn notification(
+        &self,
+        _context: &ClientCtx,
+        notification: ServerNotification,
+    ) -> tmcp::Result<()> {
+        if let Some(message) = self.forwarded(notification) {
+            // The receiver is gone once the client disconnects
+            _ = self.sender.send(message);
+        }
+        Ok(())
+    }
+}
+
+/// A connection to an upstream, and its handler.
+struct Link {
+    /// The connected client.
+    client: Connected<Forward>,
+    /// The handler the client's notifications go through.
+    forward: Forward,
+}
+
+/// A session's connections to one upstream. Each connection makes one
+/// request at a time, so a request that finds them all busy opens another.
+struct Pool {
+    /// The upstream's name.
+    name: String,
+    /// Where the upstream is.
+    target: Target,
+    /// Where the upstream's notifications for the client go.
+    sender: mpsc::UnboundedSender<Value>,
+    /// The capabilities the upstream declared.
+    capabilities: Value,
+    /// Connections not making a request.
+    idle: Mutex<Vec<Link>>,
+}
+
+impl Pool {
+    /// Connects to an upstream, learning its capabilities.
+    async fn connect(
+        ctx: &Ctx,
+        upstream: &Upstream,
+        sender: mpsc::UnboundedSender<Value>,
+    ) -> Result<Self> {
+        let mut pool = Self {
+            name: upstream.name.clone(),
+            target: upstream.target.clone(),
+            sender,
+            capabilities: Value::Null,
+            idle: Mutex::default(),
+        };
+        let (link, init) = pool.open(ctx).await?;
+        let init = serde_json::to_value(&init)?;
+        pool.capabilities = init.get("capabilities").cloned().unwrap_or_default();
+        pool.put(link);
+        Ok(pool)
+    }
+
+    /// Opens another connection to the upstream.
+    async fn open(&self, ctx: &Ctx) -> Result<(Link, InitializeResult)> {
+        let forward = Forward {
+            upstream: self.name.clone(),
+            sender: self.sender.clone(),
+            progress: Arc::default(),
+        };
+        let (client, init) = client::get_client_with_connection(ctx, &self.target, forward.clone())
+            .await
+            .map_err(|e| {
+                Error::Other(format!("Failed to connect to upstream {}: {e}", self.name))
+            })?;
+        Ok((Link { client, forward }, init))
+    }
+
+    /// An idle connection, or a new one if they're all busy.
+    async fn take(&self, ctx: &Ctx) -> Result<Link> {
+        let idle = lock(&self.idle).pop();
+        match idle {
+            Some(link) => Ok(link),
+            None => Ok(self.open(ctx).await?.0),
+        }
+    }
+
+    /// Returns a connection whose request is done.
+    fn put(&self, link: Link) {
+        lock(&self.idle).push(link);
+    }
+
+    /// What the upstream declared for a capability, if it declared it.
+    fn declared(&self, capability: &str) -> Option<&Value> {
+        self.capabilities
+            .get(capability)
+            .filter(|value| !value.is_null())
+    }
+
+    /// Every item in one of the upstream's listings.
+    async fn list(&self, ctx: &Ctx, listing: Listing) -> Result<Vec<Value>> {
+        let mut link = self.take(ctx).await?;
+        let listed = mcp::list_all(&mut link.client, ctx, listing).await;
+        self.put(link);
+        Ok(listed?.0)
+    }
+}
+
+/// One client's session, with its own connections to every upstream.
+/// Requests are answered concurrently, each on a task of its own.
+struct Session {
+    /// The context requests to upstreams are made through.
+    ctx: Ctx,
+    /// The upstreams, in the order they were given.
+    upstreams: Vec<Pool>,
+    /// Where resource reads go.
+    routes: Mutex<Routes>,
+}
+
+impl Session {
+    /// Connects to every upstream, with their notifications for the client
+    /// going to `sender`.
+    async fn connect(
+        ctx: &Ctx,
+        upstreams: &[Upstream],
+        sender: &mpsc::UnboundedSender<Value>,
+    ) -> Result<Self> {
+        let mut pools = Vec::new();
+        for upstream in upstreams {
+            pools.push(Pool::connect(ctx, upstream, sender.clone()).await?);
+        }
+        Ok(Self {
+            ctx: ctx.clone(),
+            upstreams: pools,
+            routes: Mutex::default(),
+        })
+    }
+
+    /// The upstream a namespaced tool or prompt name belongs to, and the
+    /// name the upstream knows it by.
+    fn route(&self, namespaced: &str) -> Option<(usize, String)> {
+        let (name, rest) = namespaced.split_once(SEPARATOR)?;
+        let index = self.upstreams.iter().position(|u| u.name == name)?;
+        Some((index, rest.to_string()))
+    }
+
+    /// Answers initialize with the capabilities any upstream declares, and
+    /// the protocol version the client asked for if it's one mcptool knows.
+    fn initialize(&self, params: &Value) -> Value {
+        let mut capabilities = Map::new();
+        for capability in ["tools", "prompts", "resources"] {
+            let declared: Vec<&Value> = self
+                .upstreams
+                .iter()
+                .filter_map(|u| u.declared(capability))
+                .collect();
+            if declared.is_empty() {
+                continue;
+            }
+            let mut value = json!({});
+            if declared.iter().any(|d| d["listChanged"] == true) {
+                value["listChanged"] = json!(true);
+            }
+            capabilities.insert(capability.to_string(), value);
+        }
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        json!({
+            "protocolVersion": negotiate::choose(requested),
+            "capabilities": capabilities,
+            "serverInfo": { "name": "mcptool-gateway", "version": VERSION },
+        })
+    }
+
+    /// The union of a listing from every upstream that declares it, with
+    /// tools and prompts namespaced. Resources and templates are noted for
+    /// routing reads.
+    async fn list(&self, listing: Listing) -> Answer<Value> {
+        let mut items = Vec::new();
+        let mut resources = Vec::new();
+        let mut templates = Vec::new();
+        for (index, upstream) in self.upstreams.iter().enumerate() {
+            if upstream.declared(listing.capability()).is_none() {
+                continue;
+            }
+            for mut item in upstream.list(&self.ctx, listing).await? {
+                match listing {
+                    Listing::Tools | Listing::Prompts => {
+                        if let Some(name) = item.get("name").and_then(Value::as_str) {
+                            item["name"] = json!(format!("{}{SEPARATOR}{name}", upstream.name));
+                        }
+                    }
+                    Listing::Resources => {
+                        if let Some(uri) = item.get("uri").and_then(Value::as_str) {
+                            resources.push((uri.to_string(), index));
+                        }
+                    }
+                    Listing::ResourceTemplates => {
+                        if let Some(template) = item.get("uriTemplate").and_then(Value::as_str) {
+                            templates.push((template_prefix(template).to_string(), index));
+                        }
+                    }
+                }
+                items.push(item);
+            }
+        }
+        let mut routes = lock(&self.routes);
+        for (uri, index) in resources {
+            routes.resources.entry(uri).or_insert(index);
+        }
+        if listing == Listing::ResourceTemplates {
+            routes.templates = templates;
+        }
+        let mut result = Map::new();
+        result.insert(listing.field().to_string(), Value::Array(items));
+        Ok(Value::Object(result))
+    }
+
+    /// Routes a tool call to its upstream, passing on any progress the
+    /// upstream reports if the client asked for it.
+    async fn call_tool(&self, params: &Value) -> Answer<Value> {
+        let name = required(params, "name")?;
+        let (index, tool) = self
+            .route(name)
+            .ok_or_else(|| RpcError::invalid_params(format!("Unknown tool '{name}'")))?;
+        let arguments = arguments(params)?;
+        let upstream = &self.upstreams[index];
+        let mut link = upstream.take(&self.ctx).await?;
+        link.forward
+            .track(params.pointer("/_meta/progressToken").cloned());
+        let result = self
+            .ctx
+            .request(link.client.call_tool(&tool, arguments))
+            .await;
+        link.forward.track(None);
+        upstream.put(link);
+        Ok(serde_json::to_value(result?).map_err(Error::from)?)
+    }
+
+    /// Routes a prompt request to its upstream.
+    async fn get_prompt(&self, params: &Value) -> Answer<Value> {
+        let name = required(params, "name")?;
+        let (index, prompt) = self
+            .route(name)
+            .ok_or_else(|| RpcError::invalid_params(format!("Unknown prompt '{name}'")))?;
+        let arguments = arguments(params)?;
+        let upstream = &self.upstreams[index];
+        let mut link = upstream.take(&self.ctx).await?;
+        let result = self
+            .ctx
+            .request(link.client.get_prompt(&prompt, arguments))
+            .await;
+        upstream.put(link);
+        Ok(serde_json::to_value(result?).map_err(Error::from)?)
+    }
+
+    /// Routes a resource read to the upstream serving the URI, listing
+    /// resources and templates first if it isn't known yet.
+    async fn read_resource(&self, params: &Value) -> Answer<Value> {
+        let uri = required(params, "uri")?;
+        let known = lock(&self.routes).upstream(uri);
+        let index = match known {
+            Some(index) => index,
+            None => {
+                self.list(Listing::Resources).await?;
+                self.list(Listing::ResourceTemplates).await?;
+                let found = lock(&self.routes).upstream(uri);
+                found.ok_or_else(|| {
+                    RpcError::new(RESOURCE_NOT_FOUND, format!("Unknown resource '{uri}'"))
+                })?
+            }
+        };
+        let upstream = &self.upstreams[index];
+        let mut link = upstream.take(&self.ctx).await?;
+        let result = self.ctx.request(link.client.resources_read(uri)).await;
+        upstream.put(link);
+        Ok(serde_json::to_value(result?).map_err(Error::from)?)
+    }
+
+    /// Answers a request, or returns None for notifications and anything
+    /// else that isn't a request.
+    async fn handle(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let method = message.get("method").and_then(Value::as_str)?;
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => self.list(Listing::Tools).await,
+            "prompts/list" => self.list(Listing::Prompts).await,
+            "resources/list" => self.list(Listing::Resources).await,
+            "resources/templates/list" => self.list(Listing::ResourceTemplates).await,
+            "tools/call" => self.call_tool(params).await,
+            "prompts/get" => self.get_prompt(params).await,
+            "resources/read" => self.read_resource(params).await,
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("The gateway doesn't serve {method}"),
+            )),
+        };
+        Some(jsonrpc::response(id, result))
+    }
+}
+
+/// The arguments of a tool call or prompt request.
+fn arguments(params: &Value) -> Answer<Option<Arguments>> {
+    match params.get("arguments") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(arguments)) => Ok(Some(Arguments::from(
+            arguments.clone().into_iter().collect::<HashMap<_, _>>(),
+        ))),
+        Some(_) => Err(RpcError::invalid_params("'arguments' must be an object")),
+    }
+}
+
+/// Checks a request against the policy, then the rate limits. Returns the
+/// requests the rate limits now allow, and the error response if this one
+/// was refused.
+fn admit(
+    request: Value,
+    guard: Option<&mut Guard>,
+    limiter: Option<&mut Limiter>,
+    output: &Output,
+) -> Result<(Vec<Value>, Option<Value>)> {
+    if let Some(denial) = guard.and_then(|guard| guard.request(&request)) {
+        output.trace_warn(denial.reason)?;
+        return Ok((Vec::new(), Some(denial.response)));
+    }
+    let Some(limiter) = limiter else {
+        return Ok((vec![request], None));
+    };
+    let now = Instant::now();
+    let refused = match limiter.push(request, now) {
+        Some(denial) => {
+            output.trace_warn(denial.reason)?;
+            Some(denial.response)
+        }
+        None => None,
+    };
+    Ok((limiter.release(now), refused))
+}
+
+/// A request and the response it was answered with, if it needed one.
+type Answered = (Value, Option<Value>);
+
+/// Drops denied items from a response to a listing, and logs it.
+fn respond(
+    output: &Output,
+    guard: Option<&mut Guard>,
+    (request, mut response): Answered,
+) -> Result<Option<Value>> {
+    if let (Some(guard), Some(response)) = (guard, &mut response) {
+        guard.response(response);
+    }
+    if let (Some(method), Some(response)) =
+        (request.get("method").and_then(Value::as_str), &response)
+    {
+        match response["error"].get("message").and_then(Value::as_str) {
+            Some(message) => output.trace_warn(format!("{method}: {message}"))?,
+            None => output.status(format!("{method}: response"))?,
+        }
+    }
+    Ok(response)
+}
+
+/// Answers one client's requests until it disconnects, refusing those the
+/// guard denies, and holding or refusing those over the rate limits.
+/// Requests are answered as they complete, so a slow one doesn't hold up
+/// the rest, and upstream notifications are passed on as they arrive.
+async fn serve<R, W>(
+    session: Arc<Session>,
+    mut notifications: mpsc::UnboundedReceiver<Value>,
+    mut guard: Option<Guard>,
+    mut limiter: Option<Limiter>,
+    reader: R,
+    mut writer: W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let output = session.ctx.output.clone();
+    let mut lines = BufReader::new(reader).lines();
+    let mut pending: JoinSet<Answered> = JoinSet::new();
+    loop {
+        let release = limiter
+            .as_ref()
+            .and_then(|limiter| limiter.next_release(Instant::now()));
+        let release_at = time::Instant::from_std(release.unwrap_or_else(Instant::now));
+        let (requests, refused) = tokio::select! {
+            () = time::sleep_until(release_at), if release.is_some() => {
+                let released = limiter.as_mut().map(|limiter| limiter.release(Instant::now()));
+                (released.unwrap_or_default(), None)
+            }
+            Some(notification) = notifications.recv() => {
+                jsonrpc::write_frame(&mut writer, &notification).await?;
+                continue;
+            }
+            Some(answered) = pending.join_next(), if !pending.is_empty() => {
+                let answered = answered
+                    .map_err(|e| Error::Internal(format!("Request failed: {e}")))?;
+                if let Some(response) = respond(&output, guard.as_mut(), answered)? {
+                    jsonrpc::write_frame(&mut writer, &response).await?;
+                }
+                continue;
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Value>(&line) {
+                    Ok(request) => admit(request, guard.as_mut(), limiter.as_mut(), &output)?,
+                    Err(e) => (Vec::new(), Some(jsonrpc::parse_error(&e))),
+                }
+            }
+        };
+        if let Some(refused) = refused {
+            jsonrpc::write_frame(&mut writer, &refused).await?;
+        }
+        for request in requests {
+            let session = session.clone();
+            pending.spawn(async move {
+                let response = session.handle(&request).await;
+                (request, response)
+            });
+        }
+    }
+    // Requests already under way are still answered
+    while let Some(answered) = pending.join_next().await {
+        let answered = answered.map_err(|e| Error::Internal(format!("Request failed: {e}")))?;
+        if let Some(response) = respond(&output, guard.as_mut(), answered)? {
+            jsonrpc::write_frame(&mut writer, &response).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Connects a client's session to the upstreams and serves it.
+async fn run_session<R, W>(
+    ctx: &Ctx,
+    upstreams: &[Upstream],
+    policy: Option<Arc<Policy>>,
+    limits: Option<Arc<Limits>>,
+    reader: R,
+    writer: W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (sender, notifications) = mpsc::unbounded_channel();
+    let session = Session::connect(ctx, upstreams, &sender).await?;
+    let guard = policy.map(Guard::new);
+    let limiter = limits.map(|limits| Limiter::new(limits, Instant::now()));
+    serve(
+        Arc::new(session),
+        notifications,
+        guard,
+        limiter,
+        reader,
+        writer,
+    )
+    .await
+}
+
+/// Serves the namespaced union of several upstream servers to MCP clients.
+pub async fn gateway_command(ctx: &Ctx, options: GatewayOptions) -> Result<()> {
+    let mut names = Vec::new();
+    for upstream in &options.upstreams {
+        if names.contains(&upstream.name.as_str()) {
+            return Err(Error::Other(format!(
+                "Upstream name '{}' is used more than once",
+                upstream.name
+            )));
+        }
+        names.push(&upstream.name);
+    }
+    let policy = options
+        .policy
+        .as_deref()
+        .map(Policy::load)
+        .transpose()?
+        .map(Arc::new);
+    let limits = options
+        .limits
+        .as_deref()
+        .map(Limits::load)
+        .transpose()?
+        .map(Arc::new);
+    match options.listen {
+        Listen::Stdio => {
+            // Stdout carries the protocol, so nothing else may be written there
+            let output = ctx.output.with_sink(io::sink()).with_quiet(true);
+            let ctx = ctx.clone().with_output(output);
+            run_session(&ctx, &options.upstreams, policy, limits, stdin(), stdout()).await
+        }
+        Listen::Tcp(addr) => {
+            let listener = TcpListener::bind(&addr)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to listen on {addr}: {e}")))?;
+            ctx.output.status(format!(
+                "Serving {} upstreams on tcp://{addr}: {}",
+                names.len(),
+                names.join(", ")
+            ))?;
+            let mut session = 0;
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                session += 1;
+                ctx.output
+                    .status(format!("Session {session}: client connected from {peer}"))?;
+                let ctx = ctx.clone();
+                let upstreams = options.upstreams.clone();
+                let policy = policy.clone();
+                let limits = limits.clone();
+                task::spawn(async move {
+                    let (reader, writer) = split(stream);
+                    let ended =
+                        match run_session(&ctx, &upstreams, policy, limits, reader, writer).await {
+                            Ok(()) => format!("Session {session}: closed"),
+                            Err(e) => format!("Session {session}: {e}"),
+                        };
+                    _ = ctx.output.status(ended);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::Duration};
+
+    use tmcp::{
+        Error as McpError, Server, ServerCtx, ServerHandler,
+        schema::{
+            CallToolResult, ClientCapabilities, Implementation, LATEST_PROTOCOL_VERSION,
+            ProgressToken, ServerCapabilities,
+        },
+    };
+    use tokio::{io::duplex, sync::Notify};
+
+    use super::*;
+    use crate::{jsonrpc::INVALID_PARAMS, output::OutputOptions};
+
+    /// An upstream whose `wait` tool answers once `release` has been
+    /// called, `report` sends progress and a list change before answering,
+    /// and other tools don't exist.
+    #[derive(Clone)]
+    struct TestUpstream {
+        /// Signalled by `release`.
+        released: Arc<Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl ServerHandler for TestUpstream {
+        async fn initialize(
+            &self,
+            _context: &ServerCtx,
+            _protocol_version: String,
+            _capabilities: ClientCapabilities,
+            _client_info: Implementation,
+        ) -> tmcp::Result<InitializeResult> {
+            Ok(InitializeResult::new("upstream").with_version("1.0.0"))
+        }
+
+        async fn call_tool(
+            &self,
+            context: &ServerCtx,
+            name: String,
+            _arguments: Option<Arguments>,
+        ) -> tmcp::Result<CallToolResult> {
+            match name.as_str() {
+                "wait" => self.released.notified().await,
+                "release" => self.released.notify_one(),
+                "report" => {
+                    _ = context.notify(ServerNotification::Progress {
+                        progress_token: ProgressToken::String("upstream".to_string()),
+                        progress: 1.0,
+                        total: Some(2.0),
+                        message: None,
+                    });
+                    _ = context.notify(ServerNotification::ToolListChanged);
+                    // Give the notifications time to arrive before the result
+                    time::sleep(Duration::from_millis(100)).await;
+                }
+                _ => return Err(McpError::InvalidParams(format!("No tool {name}"))),
+            }
+            Ok(CallToolResult::new().with_text_content(name))
+        }
+    }
+
+    /// A tools/call request.
+    fn call(id: u64, name: &str, mut params: Value) -> Value {
+        params["name"] = json!(name);
+        json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": params })
+    }
+
+    #[tokio::test]
+    async fn test_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let released = Arc::new(Notify::new());
+        let server = Server::default()
+            .with_handler(move || TestUpstream {
+                released: released.clone(),
+            })
+            .with_capabilities(ServerCapabilities::default().with_tools(Some(true)));
+        let bind = addr.to_string();
+        task::spawn(async move { server.serve_tcp(&bind).await });
+        time::sleep(Duration::from_millis(100)).await;
+
+        let ctx = Ctx::new(env::temp_dir(), None, OutputOptions::default(), true, false).unwrap();
+        let upstreams = vec![Upstream {
+            name: "up".to_string(),
+            target: Target::parse(&format!("tcp://{addr}")).unwrap(),
+        }];
+        let (client, gateway) = duplex(64 * 1024);
+        let (reader, writer) = split(gateway);
+        let session =
+            task::spawn(
+                async move { run_session(&ctx, &upstreams, None, None, reader, writer).await },
+            );
+
+        let (client_reader, mut client_writer) = split(client);
+        let requests = [
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "clientInfo": { "name": "test", "version": "1.0.0" },
+                },
+            }),
+            call(2, "up__wait", json!({})),
+            call(3, "up__release", json!({})),
+            call(
+                4,
+                "up__report",
+                json!({ "_meta": { "progressToken": "p" } }),
+            ),
+            call(5, "up__missing", json!({})),
+        ];
+        for request in &requests {
+            jsonrpc::write_frame(&mut client_writer, request)
+                .await
+                .unwrap();
+        }
+
+        let mut lines = BufReader::new(client_reader).lines();
+        let mut responses = HashMap::new();
+        let mut order = Vec::new();
+        let mut notifications = Vec::new();
+        while responses.len() < requests.len() {
+            let line = time::timeout(Duration::from_secs(10), lines.next_line())
+                .await
+                .expect("the gateway stopped answering")
+                .unwrap()
+                .unwrap();
+            let message: Value = serde_json::from_str(&line).unwrap();
+            match message["id"].as_u64() {
+                Some(id) => {
+                    order.push(id);
+                    responses.insert(id, message);
+                }
+                None => notifications.push(message),
+            }
+        }
+
+        // An older version the gateway knows is kept, not upgraded
+        assert_ne!(LATEST_PROTOCOL_VERSION, "2024-11-05");
+        assert_eq!(responses[&1]["result"]["protocolVersion"], "2024-11-05");
+        // The waiting call is answered only after the call that released it
+        let position = |id| order.iter().position(|answered| *answered == id);
+        assert!(position(3) < position(2));
+        assert_eq!(responses[&5]["error"]["code"], INVALID_PARAMS);
+        assert!(notifications.contains(&json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": { "progressToken": "p", "progress": 1.0, "total": 2.0 },
+        })));
+        assert!(
+            notifications
+                .iter()
+                .any(|n| n["method"] == "notifications/tools/list_changed")
+        );
+
+        drop((lines, client_writer));
+        session.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_parse_upstream() {
+        assert_eq!(
+            parse_upstream("fs=cmd://./fs-server --root /tmp").unwrap(),
+            (
+                "fs".to_string(),
+                "cmd://./fs-server --root /tmp".to_string()
+            )
+        );
+        assert_eq!(
+            parse_upstream("web-2=https://a.example/?x=1").unwrap().1,
+            "https://a.example/?x=1"
+        );
+        assert!(parse_upstream("fs").is_err());
+        assert!(parse_upstream("=cmd://x").is_err());
+        assert!(parse_upstream("my_fs=cmd://x").is_err());
+        assert!(parse_upstream("fs=").is_err());
+    }
+
+    #[test]
+    fn test_routes() {
+        let mut routes = Routes::default();
+        routes.resources.insert("file:///a".to_string(), 0);
+        for (template, index) in [("file:///{path}", 1), ("file:///docs/{name}", 2)] {
+            routes
+                .templates
+                .push((template_prefix(template).to_string(), index));
+        }
+        assert_eq!(routes.upstream("file:///a"), Some(0));
+        assert_eq!(routes.upstream("file:///b"), Some(1));
+        assert_eq!(routes.upstream("file:///docs/readme"), Some(2));
+        assert_eq!(routes.upstream("https://example.com"), None);
+        assert_eq!(template_prefix("{+uri}"), "");
+    }
+}
@@ -0,0 +1,426 @@
+//! Typed client stubs for a server's tools.
+//!
+//! Each tool's input schema becomes an argument type, and its output schema,
+//! if it has one, a result type. A function per tool sends the arguments
+//! through a small caller interface that the application implements over
+//! its MCP client, and decodes the structured result. Schemas map onto the
+//! language's types as far as they go: objects with properties become
+//! types of their own, string enums become enums, and anything else, such
+//! as `anyOf`, becomes untyped JSON.
+
+pub mod python;
+pub mod rust;
+pub mod typescript;
+
+use std::{collections::HashSet, fs, mem, path::PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::{Error, Result, ctx::Ctx, export::type_name, snapshot::Snapshot, target::Target};
+
+/// Languages stubs can be generated in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lang {
+    /// A Rust module using serde
+    Rust,
+    /// A TypeScript module
+    Typescript,
+    /// A Python module using TypedDict
+    Python,
+}
+
+impl Lang {
+    /// The name of the generated file.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Rust => "tools.rs",
+            Self::Typescript => "tools.ts",
+            Self::Python => "tools.py",
+        }
+    }
+}
+
+/// A type in the generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// A string.
+    String,
+    /// An integer.
+    Integer,
+    /// A floating point number.
+    Number,
+    /// A boolean.
+    Boolean,
+    /// A list of values of one type.
+    Array(Box<Self>),
+    /// A JSON object with no declared properties.
+    Object,
+    /// Any JSON value.
+    Any,
+    /// A struct or enum defined in the generated code.
+    Named(String),
+}
+
+/// A field of a struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The property's name in JSON.
+    pub name: String,
+    /// The field's type.
+    pub ty: Type,
+    /// Whether the property is required.
+    pub required: bool,
+    /// The property's description.
+    pub description: Option<String>,
+}
+
+/// A type defined in the generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Def {
+    /// A struct for an object schema.
+    Struct {
+        /// The struct's name.
+        name: String,
+        /// The schema's description.
+        description: Option<String>,
+        /// The struct's fields, in the order of the schema's properties.
+        fields: Vec<Field>,
+    },
+    /// An enum for a string schema with a fixed set of values.
+    Enum {
+        /// The enum's name.
+        name: String,
+        /// The schema's description.
+        description: Option<String>,
+        /// The allowed values.
+        values: Vec<String>,
+    },
+}
+
+/// A function calling a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stub {
+    /// The tool's name.
+    pub tool: String,
+    /// The tool's description.
+    pub description: Option<String>,
+    /// The name of the argument struct.
+    pub args: String,
+    /// The type of the structured result, if the tool has an output schema.
+    pub result: Option<Type>,
+}
+
+/// Everything generated for a server: the types, in an order where each is
+/// defined before it's used, and a stub per tool.
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    /// The server's name and version, for the file's header.
+    pub server: String,
+    /// The types.
+    pub defs: Vec<Def>,
+    /// The stubs.
+    pub stubs: Vec<Stub>,
+    /// Type names already taken.
+    used: HashSet<String>,
+}
+
+impl Model {
+    /// Builds the model for a server's tools.
+    pub fn new(snapshot: &Snapshot) -> Self {
+        let info = |field: &str| {
+            snapshot
+                .init
+                .pointer(&format!("/serverInfo/{field}"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let mut model = Self {
+            server: format!("{} {}", info("name"), info("version"))
+                .trim()
+                .to_string(),
+            ..Self::default()
+        };
+        for tool in &snapshot.tools {
+            let Some(name) = tool.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let empty = json!({ "type": "object" });
+            let input = tool.get("inputSchema").unwrap_or(&empty);
+            let args = model.add_struct(input, &format!("{name} args"));
+            let result = tool
+                .get("outputSchema")
+                .map(|output| model.add_type(output, &format!("{name} result")));
+            model.stubs.push(Stub {
+                tool: name.to_string(),
+                description: description(tool),
+                args,
+                result,
+            });
+        }
+        model
+    }
+
+    /// The type for a schema, defining a struct or enum named after `name`
+    /// if it needs one.
+    fn add_type(&mut self, schema: &Value, name: &str) -> Type {
+        if let Some(values) = string_enum(schema) {
+            let name = type_name(name, &mut self.used);
+            self.defs.push(Def::Enum {
+                name: name.clone(),
+                description: description(schema),
+                values,
+            });
+            return Type::Named(name);
+        }
+        match schema_type(schema) {
+            Some("string") => Type::String,
+            Some("integer") => Type::Integer,
+            Some("number") => Type::Number,
+            Some("boolean") => Type::Boolean,
+            Some("array") => {
+                let items = schema.get("items").unwrap_or(&Value::Null);
+                Type::Array(Box::new(self.add_type(items, &format!("{name} item"))))
+            }
+            Some("object") if has_properties(schema) => Type::Named(self.add_struct(schema, name)),
+            Some("object") => Type::Object,
+            _ => Type::Any,
+        }
+    }
+
+    /// Defines a struct for an object schema, after any types its fields
+    /// need, and returns its name.
+    fn add_struct(&mut self, schema: &Value, name: &str) -> String {
+        let name = type_name(name, &mut self.used);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let mut fields = Vec::new();
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (property, property_schema) in properties {
+                fields.push(Field {
+                    name: property.clone(),
+                    ty: self.add_type(property_schema, &format!("{name} {property}")),
+                    required: required.contains(&property.as_str()),
+                    description: description(property_schema),
+                });
+            }
+        }
+        self.defs.push(Def::Struct {
+            name: name.clone(),
+            description: description(schema),
+            fields,
+        });
+        name
+    }
+}
+
+/// A schema's description, if it has a non-empty one.
+fn description(schema: &Value) -> Option<String> {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(str::to_string)
+}
+
+/// The type a schema describes, ignoring `null` in a list of types, and
+/// taking an object for a schema with properties but no type.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type") {
+        Some(Value::String(name)) => Some(name.as_str()),
+        Some(Value::Array(names)) => {
+            let types: Vec<&str> = names
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|name| *name != "null")
+                .collect();
+            match types[..] {
+                [name] => Some(name),
+                _ => None,
+            }
+        }
+        _ if has_properties(schema) => Some("object"),
+        _ => None,
+    }
+}
+
+/// Whether a schema declares any properties.
+fn has_properties(schema: &Value) -> bool {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .is_some_and(|properties| !properties.is_empty())
+}
+
+/// The values of a schema that's an enum of strings.
+fn string_enum(schema: &Value) -> Option<Vec<String>> {
+    let values = schema.get("enum")?.as_array()?;
+    if values.is_empty() {
+        return None;
+    }
+    values
+        .iter()
+        .map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
+
+/// The words of a name, lowercased, split at anything that isn't a letter
+/// or digit and where lowercase turns to uppercase.
+pub fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(mem::take(&mut word));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && previous_lower {
+            words.push(mem::take(&mut word));
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// A name made unique among `used` by numbering it.
+pub fn unique(name: String, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    for n in 2.. {
+        if used.insert(candidate.clone()) {
+            break;
+        }
+        candidate = format!("{name}{n}");
+    }
+    candidate
+}
+
+/// Generates stubs for a server's tools and writes them to `directory`.
+pub async fn generate_command(
+    ctx: &Ctx,
+    target: Target,
+    lang: Lang,
+    directory: PathBuf,
+) -> Result<()> {
+    let snapshot = Snapshot::capture(ctx, &target, false).await?;
+    let model = Model::new(&snapshot);
+    let code = match lang {
+        Lang::Rust => rust::render(&model),
+        Lang::Typescript => typescript::render(&model),
+        Lang::Python => python::render(&model),
+    };
+    fs::create_dir_all(&directory)
+        .map_err(|e| Error::Other(format!("Failed to create {}: {e}", directory.display())))?;
+    let path = directory.join(lang.file_name());
+    fs::write(&path, code)
+        .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))?;
+
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_value(&json!({
+            "target": snapshot.target,
+            "path": path,
+            "tools": model.stubs.len(),
+            "types": model.defs.len(),
+        }))?;
+    } else {
+        output.trace_success(format!(
+            "Wrote stubs for {} tools to {}",
+            model.stubs.len(),
+            path.display()
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    /// A snapshot of a server with one tool.
+    pub fn snapshot() -> Snapshot {
+        Snapshot {
+            target: "cmd://server".to_string(),
+            captured_at: Utc::now(),
+            init: json!({ "serverInfo": { "name": "docs", "version": "1.0" } }),
+            tools: vec![json!({
+                "name": "search_docs",
+                "description": "Searches the docs",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "What to find" },
+                        "mode": { "type": "string", "enum": ["fast", "exact"] },
+                        "filter": {
+                            "type": "object",
+                            "properties": { "tags": { "type": "array", "items": { "type": "string" } } },
+                        },
+                        "limit": { "type": ["integer", "null"] },
+                    },
+                    "required": ["query"],
+                },
+                "outputSchema": {
+                    "type": "object",
+                    "properties": { "hits": { "type": "array", "items": {} } },
+                    "required": ["hits"],
+                },
+            })],
+            prompts: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_model() {
+        let model = Model::new(&snapshot());
+        assert_eq!(model.server, "docs 1.0");
+        let names: Vec<&str> = model
+            .defs
+            .iter()
+            .map(|def| match def {
+                Def::Struct { name, .. } | Def::Enum { name, .. } => name.as_str(),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "SearchDocsArgsFilter",
+                "SearchDocsArgsMode",
+                "SearchDocsArgs",
+                "SearchDocsResult"
+            ]
+        );
+        let Def::Struct { fields, .. } = &model.defs[2] else {
+            panic!("not a struct");
+        };
+        assert_eq!(fields[0].name, "filter");
+        assert_eq!(fields[1].ty, Type::Integer);
+        assert!(!fields[1].required);
+        assert_eq!(fields[3].ty, Type::String);
+        assert!(fields[3].required);
+        assert_eq!(
+            model.stubs[0].result,
+            Some(Type::Named("SearchDocsResult".to_string()))
+        );
+
+        assert_eq!(words("searchDocs_v2"), ["search", "docs", "v2"]);
+        assert_eq!(words("HTTPGet"), ["httpget"]);
+    }
+}
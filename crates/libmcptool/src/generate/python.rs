@@ -0,0 +1,225 @@
+//! Python stubs: `TypedDict` classes and `Literal` aliases, a `ToolCaller`
+//! protocol, and an async function per tool.
+
+use std::{collections::HashSet, fmt::Write};
+
+use super::{Def, Field, Model, Stub, Type, unique, words};
+
+/// Words that can't be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// The protocol the stubs call tools through. The MCP SDK's
+/// `ClientSession` matches it.
+const CALLER: &str = "\
+class ToolCaller(Protocol):
+    \"\"\"Sends tool calls to the server, such as the MCP SDK's ClientSession.\"\"\"
+
+    async def call_tool(self, name: str, arguments: dict[str, Any] | None = None) -> Any: ...
+";
+
+/// A string as a Python string literal.
+fn literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// Whether a name can be used as an identifier.
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// A snake case function name for a tool.
+fn function(name: &str) -> String {
+    let mut snake = words(name).join("_");
+    if snake.is_empty() || snake.starts_with(|c: char| c.is_ascii_digit()) {
+        snake = format!("call_{snake}");
+    }
+    if KEYWORDS.contains(&snake.as_str()) {
+        snake.push('_');
+    }
+    snake
+}
+
+/// The Python type for a type.
+fn py_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "str".to_string(),
+        Type::Integer => "int".to_string(),
+        Type::Number => "float".to_string(),
+        Type::Boolean => "bool".to_string(),
+        Type::Array(item) => format!("list[{}]", py_type(item)),
+        Type::Object => "dict[str, Any]".to_string(),
+        Type::Any => "Any".to_string(),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+/// A field's type, marked `NotRequired` if it's optional.
+fn field_type(field: &Field) -> String {
+    let ty = py_type(&field.ty);
+    if field.required {
+        ty
+    } else {
+        format!("NotRequired[{ty}]")
+    }
+}
+
+/// Appends a docstring.
+fn docstring(code: &mut String, indent: &str, text: Option<&str>) {
+    if let Some(text) = text {
+        let text = text.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+        let text = text
+            .lines()
+            .collect::<Vec<_>>()
+            .join(&format!("\n{indent}"));
+        _ = writeln!(code, "{indent}\"\"\"{text}\"\"\"\n");
+    }
+}
+
+/// Appends comments.
+fn comment(code: &mut String, indent: &str, text: Option<&str>) {
+    for line in text.into_iter().flat_map(str::lines) {
+        _ = writeln!(code, "{indent}# {}", line.trim_end());
+    }
+}
+
+/// Appends a `TypedDict` or `Literal` alias. Dicts whose keys aren't all
+/// identifiers use the functional syntax.
+fn def(code: &mut String, def: &Def) {
+    match def {
+        Def::Struct {
+            name,
+            description,
+            fields,
+        } if fields.iter().all(|field| is_identifier(&field.name)) => {
+            _ = writeln!(code, "class {name}(TypedDict):");
+            docstring(code, "    ", description.as_deref());
+            if fields.is_empty() && description.is_none() {
+                code.push_str("    pass\n");
+            }
+            for field in fields {
+                comment(code, "    ", field.description.as_deref());
+                _ = writeln!(code, "    {}: {}", field.name, field_type(field));
+            }
+            code.push_str("\n\n");
+        }
+        Def::Struct {
+            name,
+            description,
+            fields,
+        } => {
+            comment(code, "", description.as_deref());
+            _ = writeln!(code, "{name} = TypedDict(\n    {},\n    {{", literal(name));
+            for field in fields {
+                _ = writeln!(
+                    code,
+                    "        {}: {},",
+                    literal(&field.name),
+                    field_type(field)
+                );
+            }
+            code.push_str("    },\n)\n\n\n");
+        }
+        Def::Enum {
+            name,
+            description,
+            values,
+        } => {
+            comment(code, "", description.as_deref());
+            let values: Vec<String> = values.iter().map(|value| literal(value)).collect();
+            _ = writeln!(code, "{name} = Literal[{}]\n\n", values.join(", "));
+        }
+    }
+}
+
+/// Appends the function calling a tool.
+fn stub(code: &mut String, stub: &Stub, used: &mut HashSet<String>) {
+    let name = unique(function(&stub.tool), used);
+    let result = stub
+        .result
+        .as_ref()
+        .map_or_else(|| "Any".to_string(), py_type);
+    _ = writeln!(
+        code,
+        "async def {name}(caller: ToolCaller, args: {}) -> {result}:",
+        stub.args
+    );
+    docstring(code, "    ", stub.description.as_deref());
+    let call = format!(
+        "await caller.call_tool({}, dict(args))",
+        literal(&stub.tool)
+    );
+    if stub.result.is_some() {
+        _ = writeln!(
+            code,
+            "    result = {call}\n    return result.structuredContent\n\n"
+        );
+    } else {
+        _ = writeln!(code, "    return {call}\n\n");
+    }
+}
+
+/// A Python module for a model.
+pub fn render(model: &Model) -> String {
+    let mut code = format!(
+        "# Generated by mcptool from {}. Do not edit.\n\n\
+         from __future__ import annotations\n\n\
+         from typing import Any, Literal, NotRequired, Protocol, TypedDict\n\n\n",
+        model.server
+    );
+    code.push_str(CALLER);
+    code.push_str("\n\n");
+    for d in &model.defs {
+        def(&mut code, d);
+    }
+    let mut used = HashSet::new();
+    for s in &model.stubs {
+        stub(&mut code, s, &mut used);
+    }
+    code.truncate(code.trim_end().len());
+    code.push('\n');
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::snapshot, *};
+
+    #[test]
+    fn test_render() {
+        let code = render(&Model::new(&snapshot()));
+        assert!(code.contains("SearchDocsArgsMode = Literal[\"fast\", \"exact\"]\n"));
+        assert!(code.contains("class SearchDocsArgs(TypedDict):\n"));
+        assert!(code.contains("    # What to find\n    query: str\n"));
+        assert!(code.contains("    limit: NotRequired[int]\n"));
+        assert!(code.contains(
+            "async def search_docs(caller: ToolCaller, args: SearchDocsArgs) -> SearchDocsResult:\n    \"\"\"Searches the docs\"\"\"\n"
+        ));
+
+        let mut functional = String::new();
+        def(
+            &mut functional,
+            &Def::Struct {
+                name: "Headers".to_string(),
+                description: None,
+                fields: vec![Field {
+                    name: "content-type".to_string(),
+                    ty: Type::String,
+                    required: true,
+                    description: None,
+                }],
+            },
+        );
+        assert_eq!(
+            functional,
+            "Headers = TypedDict(\n    \"Headers\",\n    {\n        \"content-type\": str,\n    },\n)\n\n\n"
+        );
+        assert_eq!(function("import"), "import_");
+    }
+}
@@ -0,0 +1,235 @@
+//! Rust stubs: serde types, a `ToolCaller` trait, and an async function per
+//! tool.
+
+use std::{collections::HashSet, fmt::Write};
+
+use super::{Def, Field, Model, Stub, Type, unique, words};
+
+/// Words that can't be used as identifiers without `r#`.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+/// Keywords that can't be raw identifiers either.
+const RESERVED: &[&str] = &["crate", "self", "super"];
+
+/// The trait the stubs call tools through.
+const CALLER: &str = "\
+/// Sends tool calls to the server. Implement this over your MCP client.
+pub trait ToolCaller {
+    /// The error a call fails with. Arguments and results that don't match
+    /// their types fail as JSON errors.
+    type Error: From<serde_json::Error>;
+
+    /// Calls a tool and returns the `CallToolResult` as JSON.
+    fn call_tool(
+        &mut self,
+        name: &str,
+        arguments: Value,
+    ) -> impl std::future::Future<Output = Result<Value, Self::Error>>;
+}
+";
+
+/// A snake case identifier for a name.
+fn ident(name: &str) -> String {
+    let snake = words(name).join("_");
+    if snake.is_empty() || snake.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{snake}")
+    } else if RESERVED.contains(&snake.as_str()) {
+        format!("{snake}_")
+    } else if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{snake}")
+    } else {
+        snake
+    }
+}
+
+/// An enum variant for a value.
+fn variant(value: &str) -> String {
+    let pascal: String = words(value)
+        .iter()
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect();
+    if pascal.is_empty() || pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("V{pascal}")
+    } else {
+        pascal
+    }
+}
+
+/// The Rust type for a type.
+fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "String".to_string(),
+        Type::Integer => "i64".to_string(),
+        Type::Number => "f64".to_string(),
+        Type::Boolean => "bool".to_string(),
+        Type::Array(item) => format!("Vec<{}>", rust_type(item)),
+        Type::Object => "Map<String, Value>".to_string(),
+        Type::Any => "Value".to_string(),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+/// Whether a type is an untyped object, or contains one.
+fn uses_map(ty: &Type) -> bool {
+    match ty {
+        Type::Object => true,
+        Type::Array(item) => uses_map(item),
+        _ => false,
+    }
+}
+
+/// Appends a doc comment.
+fn doc(code: &mut String, indent: &str, text: Option<&str>) {
+    for line in text.into_iter().flat_map(str::lines) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            _ = writeln!(code, "{indent}///");
+        } else {
+            _ = writeln!(code, "{indent}/// {line}");
+        }
+    }
+}
+
+/// Appends a struct field.
+fn field(code: &mut String, field: &Field) {
+    doc(code, "    ", field.description.as_deref());
+    let ident = ident(&field.name);
+    if ident.trim_start_matches("r#") != field.name {
+        _ = writeln!(code, "    #[serde(rename = {:?})]", field.name);
+    }
+    let ty = rust_type(&field.ty);
+    if field.required {
+        _ = writeln!(code, "    pub {ident}: {ty},");
+    } else {
+        code.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+        _ = writeln!(code, "    pub {ident}: Option<{ty}>,");
+    }
+}
+
+/// Appends a struct or enum.
+fn def(code: &mut String, def: &Def) {
+    match def {
+        Def::Struct {
+            name,
+            description,
+            fields,
+        } => {
+            doc(code, "", description.as_deref());
+            code.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+            _ = writeln!(code, "pub struct {name} {{");
+            for f in fields {
+                field(code, f);
+            }
+            code.push_str("}\n\n");
+        }
+        Def::Enum {
+            name,
+            description,
+            values,
+        } => {
+            doc(code, "", description.as_deref());
+            code.push_str(
+                "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\n",
+            );
+            _ = writeln!(code, "pub enum {name} {{");
+            let mut used = HashSet::new();
+            for value in values {
+                _ = writeln!(code, "    #[serde(rename = {value:?})]");
+                _ = writeln!(code, "    {},", unique(variant(value), &mut used));
+            }
+            code.push_str("}\n\n");
+        }
+    }
+}
+
+/// Appends the function calling a tool.
+fn stub(code: &mut String, stub: &Stub, used: &mut HashSet<String>) {
+    doc(code, "", stub.description.as_deref());
+    let name = unique(ident(&stub.tool), used);
+    let (tool, args) = (&stub.tool, &stub.args);
+    let call = format!("caller.call_tool({tool:?}, serde_json::to_value(args)?).await");
+    match &stub.result {
+        Some(result) => {
+            let result = rust_type(result);
+            _ = writeln!(
+                code,
+                "pub async fn {name}<C: ToolCaller>(\n    caller: &mut C,\n    args: &{args},\n) -> Result<{result}, C::Error> {{\n    \
+                 let result = {call}?;\n    \
+                 let content = result.get(\"structuredContent\").cloned().unwrap_or_default();\n    \
+                 Ok(serde_json::from_value(content)?)\n}}\n"
+            );
+        }
+        None => {
+            _ = writeln!(
+                code,
+                "pub async fn {name}<C: ToolCaller>(\n    caller: &mut C,\n    args: &{args},\n) -> Result<Value, C::Error> {{\n    \
+                 {call}\n}}\n"
+            );
+        }
+    }
+}
+
+/// A Rust module for a model.
+pub fn render(model: &Model) -> String {
+    let mut code = format!(
+        "// Generated by mcptool from {}. Do not edit.\n\n",
+        model.server
+    );
+    let map = model.defs.iter().any(|def| match def {
+        Def::Struct { fields, .. } => fields.iter().any(|f| uses_map(&f.ty)),
+        Def::Enum { .. } => false,
+    }) || model
+        .stubs
+        .iter()
+        .any(|s| s.result.as_ref().is_some_and(uses_map));
+    code.push_str("use serde::{Deserialize, Serialize};\n");
+    code.push_str(if map {
+        "use serde_json::{Map, Value};\n\n"
+    } else {
+        "use serde_json::Value;\n\n"
+    });
+    code.push_str(CALLER);
+    code.push('\n');
+    for d in &model.defs {
+        def(&mut code, d);
+    }
+    let mut used = HashSet::new();
+    for s in &model.stubs {
+        stub(&mut code, s, &mut used);
+    }
+    code.truncate(code.trim_end().len());
+    code.push('\n');
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::snapshot, *};
+
+    #[test]
+    fn test_render() {
+        let code = render(&Model::new(&snapshot()));
+        assert!(code.starts_with("// Generated by mcptool from docs 1.0. Do not edit.\n"));
+        assert!(code.contains("use serde_json::Value;\n"));
+        assert!(code.contains(
+            "    #[serde(rename = \"fast\")]\n    Fast,\n    #[serde(rename = \"exact\")]\n    Exact,\n"
+        ));
+        assert!(code.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub limit: Option<i64>,\n"
+        ));
+        assert!(code.contains("    /// What to find\n    pub query: String,\n"));
+        assert!(code.contains("pub tags: Option<Vec<String>>,"));
+        assert!(code.contains("pub hits: Vec<Value>,"));
+        assert!(code.contains(
+            "pub async fn search_docs<C: ToolCaller>(\n    caller: &mut C,\n    args: &SearchDocsArgs,\n) -> Result<SearchDocsResult, C::Error> {"
+        ));
+        assert_eq!(ident("type"), "r#type");
+        assert_eq!(ident("self"), "self_");
+        assert_eq!(ident("2fa"), "field_2fa");
+    }
+}
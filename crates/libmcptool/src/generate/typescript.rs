@@ -0,0 +1,246 @@
+//! TypeScript stubs: interfaces and string unions, a `ToolCaller`
+//! interface, and an async function per tool.
+
+use std::{collections::HashSet, fmt::Write};
+
+use super::{Def, Model, Stub, Type, unique, words};
+
+/// Words that can't be used as function names.
+const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "let",
+    "static",
+    "implements",
+    "interface",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "await",
+];
+
+/// The interface the stubs call tools through. The MCP SDK's `Client`
+/// matches it.
+const CALLER: &str = "\
+/** Sends tool calls to the server, such as the MCP SDK's `Client`. */
+export interface ToolCaller {
+  callTool(params: {
+    name: string;
+    arguments?: Record<string, unknown>;
+  }): Promise<{ structuredContent?: unknown }>;
+}
+";
+
+/// A string as a TypeScript string literal.
+fn literal(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// A property name, quoted if it isn't a valid identifier.
+fn property(name: &str) -> String {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        name.to_string()
+    } else {
+        literal(name)
+    }
+}
+
+/// A camel case function name for a tool.
+fn function(name: &str) -> String {
+    let mut camel: String = words(name)
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                word.clone()
+            } else {
+                word[..1].to_ascii_uppercase() + &word[1..]
+            }
+        })
+        .collect();
+    if camel.is_empty() || camel.starts_with(|c: char| c.is_ascii_digit()) {
+        camel = format!("call{camel}");
+    }
+    if RESERVED.contains(&camel.as_str()) {
+        camel.push('_');
+    }
+    camel
+}
+
+/// The TypeScript type for a type.
+fn ts_type(ty: &Type) -> String {
+    match ty {
+        Type::String => "string".to_string(),
+        Type::Integer | Type::Number => "number".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Array(item) => format!("Array<{}>", ts_type(item)),
+        Type::Object => "Record<string, unknown>".to_string(),
+        Type::Any => "unknown".to_string(),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+/// Appends a JSDoc comment.
+fn doc(code: &mut String, indent: &str, text: Option<&str>) {
+    let Some(text) = text else {
+        return;
+    };
+    let text = text.replace("*/", "*\\/");
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    if let [line] = lines[..] {
+        _ = writeln!(code, "{indent}/** {line} */");
+        return;
+    }
+    _ = writeln!(code, "{indent}/**");
+    for line in lines {
+        if line.is_empty() {
+            _ = writeln!(code, "{indent} *");
+        } else {
+            _ = writeln!(code, "{indent} * {line}");
+        }
+    }
+    _ = writeln!(code, "{indent} */");
+}
+
+/// Appends an interface or string union.
+fn def(code: &mut String, def: &Def) {
+    match def {
+        Def::Struct {
+            name,
+            description,
+            fields,
+        } => {
+            doc(code, "", description.as_deref());
+            _ = writeln!(code, "export interface {name} {{");
+            for field in fields {
+                doc(code, "  ", field.description.as_deref());
+                let optional = if field.required { "" } else { "?" };
+                _ = writeln!(
+                    code,
+                    "  {}{optional}: {};",
+                    property(&field.name),
+                    ts_type(&field.ty)
+                );
+            }
+            code.push_str("}\n\n");
+        }
+        Def::Enum {
+            name,
+            description,
+            values,
+        } => {
+            doc(code, "", description.as_deref());
+            let values: Vec<String> = values.iter().map(|value| literal(value)).collect();
+            _ = writeln!(code, "export type {name} = {};\n", values.join(" | "));
+        }
+    }
+}
+
+/// Appends the function calling a tool.
+fn stub(code: &mut String, stub: &Stub, used: &mut HashSet<String>) {
+    doc(code, "", stub.description.as_deref());
+    let name = unique(function(&stub.tool), used);
+    let (tool, args) = (literal(&stub.tool), &stub.args);
+    let call = format!("caller.callTool({{ name: {tool}, arguments: {{ ...args }} }})");
+    match &stub.result {
+        Some(result) => {
+            let result = ts_type(result);
+            _ = writeln!(
+                code,
+                "export async function {name}(\n  caller: ToolCaller,\n  args: {args},\n): Promise<{result}> {{\n  \
+                 const result = await {call};\n  \
+                 return result.structuredContent as {result};\n}}\n"
+            );
+        }
+        None => {
+            _ = writeln!(
+                code,
+                "export async function {name}(\n  caller: ToolCaller,\n  args: {args},\n): Promise<{{ structuredContent?: unknown }}> {{\n  \
+                 return {call};\n}}\n"
+            );
+        }
+    }
+}
+
+/// A TypeScript module for a model.
+pub fn render(model: &Model) -> String {
+    let mut code = format!(
+        "// Generated by mcptool from {}. Do not edit.\n\n",
+        model.server
+    );
+    code.push_str(CALLER);
+    code.push('\n');
+    for d in &model.defs {
+        def(&mut code, d);
+    }
+    let mut used = HashSet::new();
+    for s in &model.stubs {
+        stub(&mut code, s, &mut used);
+    }
+    code.truncate(code.trim_end().len());
+    code.push('\n');
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::snapshot, *};
+
+    #[test]
+    fn test_render() {
+        let code = render(&Model::new(&snapshot()));
+        assert!(code.contains("export type SearchDocsArgsMode = \"fast\" | \"exact\";\n"));
+        assert!(code.contains("  /** What to find */\n  query: string;\n"));
+        assert!(code.contains("  limit?: number;\n"));
+        assert!(code.contains("  tags?: Array<string>;\n"));
+        assert!(code.contains("  hits: Array<unknown>;\n"));
+        assert!(code.contains(
+            "export async function searchDocs(\n  caller: ToolCaller,\n  args: SearchDocsArgs,\n): Promise<SearchDocsResult> {"
+        ));
+        assert!(
+            code.contains("caller.callTool({ name: \"search_docs\", arguments: { ...args } })")
+        );
+        assert_eq!(property("content-type"), "\"content-type\"");
+        assert_eq!(function("delete"), "delete_");
+    }
+}
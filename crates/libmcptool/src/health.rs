@@ -0,0 +1,177 @@
+//! Health checks for probes and uptime monitors.
+//!
+//! A health check connects, initializes and pings, then checks any
+//! assertions it was given. It prints one line, or a JSON object, and
+//! reports through its exit code: 0 when the server is healthy, 1 when it
+//! answers but an assertion failed, and 2 when it can't be reached.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    Error, Result, client,
+    ctx::Ctx,
+    mcp::{self, Listing},
+    stats::millis,
+    target::Target,
+    utils::TimedFuture,
+};
+
+/// The timeout for each request when none is given with `--timeout`, so a
+/// hung server fails the check instead of hanging the probe.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Assertions a health check makes beyond the server answering.
+#[derive(Debug, Clone, Default)]
+pub struct HealthOptions {
+    /// Tools the server must list.
+    pub expect_tools: Vec<String>,
+    /// The slowest a ping may be.
+    pub max_latency: Option<Duration>,
+}
+
+/// The outcome of a health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    /// The server answered and every assertion held.
+    Healthy,
+    /// The server answered, but an assertion failed.
+    Unhealthy,
+    /// The server couldn't be connected to, initialized or pinged.
+    Unreachable,
+}
+
+/// What a health check found.
+#[derive(Debug, Clone)]
+struct Probe {
+    /// The outcome.
+    health: Health,
+    /// How long connecting and initializing took, if they succeeded.
+    connect: Option<Duration>,
+    /// How long the ping took, if it succeeded.
+    ping: Option<Duration>,
+    /// Why the server isn't healthy.
+    problems: Vec<String>,
+}
+
+impl Probe {
+    /// A probe that couldn't reach the server.
+    fn unreachable(connect: Option<Duration>, problem: String) -> Self {
+        Self {
+            health: Health::Unreachable,
+            connect,
+            ping: None,
+            problems: vec![problem],
+        }
+    }
+}
+
+/// The expected tools missing from a tool listing.
+fn missing_tools(tools: &[Value], expected: &[String]) -> Vec<String> {
+    expected
+        .iter()
+        .filter(|name| {
+            !tools
+                .iter()
+                .any(|tool| tool.get("name").and_then(Value::as_str) == Some(name.as_str()))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Connects, initializes and pings, then checks the assertions in `options`.
+async fn probe(ctx: &Ctx, target: &Target, options: &HealthOptions) -> Probe {
+    let (connected, connect) = client::get_client(ctx, target).measured().await;
+    let mut client = match connected {
+        Ok((client, _)) => client,
+        Err(e) => return Probe::unreachable(None, e.to_string()),
+    };
+    let (pinged, ping) = ctx.request(client.ping()).measured().await;
+    if let Err(e) = pinged {
+        return Probe::unreachable(Some(connect), format!("Ping failed: {e}"));
+    }
+
+    let mut problems = Vec::new();
+    if let Some(max) = options.max_latency
+        && ping > max
+    {
+        problems.push(format!(
+            "Ping took {:.1}ms, over the maximum of {:.1}ms",
+            millis(ping),
+            millis(max)
+        ));
+    }
+    if !options.expect_tools.is_empty() {
+        match mcp::list_all(&mut client, ctx, Listing::Tools).await {
+            Ok((tools, _)) => {
+                for name in missing_tools(&tools, &options.expect_tools) {
+                    problems.push(format!("Tool '{name}' is not listed"));
+                }
+            }
+            Err(e) => problems.push(format!("tools/list failed: {e}")),
+        }
+    }
+    Probe {
+        health: if problems.is_empty() {
+            Health::Healthy
+        } else {
+            Health::Unhealthy
+        },
+        connect: Some(connect),
+        ping: Some(ping),
+        problems,
+    }
+}
+
+/// Checks a server's health, printing the outcome on one line or as JSON.
+/// Fails with an unhealthy or unreachable error if it isn't healthy.
+pub async fn health_command(ctx: &Ctx, target: Target, options: HealthOptions) -> Result<()> {
+    let ctx = &ctx
+        .clone()
+        .with_timeout(Some(ctx.timeout.unwrap_or(DEFAULT_TIMEOUT)));
+    let probe = probe(ctx, &target, &options).await;
+
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "status": probe.health,
+            "connectMs": probe.connect.map(millis),
+            "pingMs": probe.ping.map(millis),
+            "problems": probe.problems,
+        }))?;
+    } else if let (Health::Healthy, Some(connect), Some(ping)) =
+        (probe.health, probe.connect, probe.ping)
+    {
+        output.text(format!(
+            "healthy: connected in {:.1}ms, ping {:.1}ms",
+            millis(connect),
+            millis(ping)
+        ))?;
+    }
+
+    let problems = probe.problems.join("; ");
+    match probe.health {
+        Health::Healthy => Ok(()),
+        Health::Unhealthy => Err(Error::Unhealthy(problems)),
+        Health::Unreachable => Err(Error::Unreachable(problems)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_tools() {
+        let tools = vec![json!({ "name": "search" }), json!({ "name": "fetch" })];
+        let expected = vec!["fetch".to_string(), "delete".to_string()];
+        assert_eq!(missing_tools(&tools, &expected), ["delete"]);
+        assert!(missing_tools(&tools, &[]).is_empty());
+        assert_eq!(Error::Unhealthy(String::new()).exit_code(), 1);
+        assert_eq!(Error::Unreachable(String::new()).exit_code(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+//! JSON-RPC messages for the servers mcptool runs itself, which speak the
+//! protocol frame by frame rather than through tmcp.
+
+use std::result;
+
+use serde_json::{Value, json};
+use tmcp::Error as McpError;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{Error, Result};
+
+/// The JSON-RPC error code for a method the server doesn't serve.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The JSON-RPC error code for missing or invalid parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// The JSON-RPC error code for a failure inside the server.
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// The JSON-RPC error code for a frame that isn't valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+
+/// The MCP error code for a resource that doesn't exist.
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A JSON-RPC error to answer a request with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    /// The JSON-RPC error code.
+    pub code: i64,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl RpcError {
+    /// An error with a code and message.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// An error for a request with missing or invalid parameters.
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+/// Errors from a server keep the code they were answered with, as far as
+/// tmcp's error tells them apart. Anything else failed inside mcptool.
+impl From<Error> for RpcError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::MpcClient(error) => Self::from(error),
+            error => Self::new(INTERNAL_ERROR, error.to_string()),
+        }
+    }
+}
+
+impl From<McpError> for RpcError {
+    fn from(error: McpError) -> Self {
+        let code = match &error {
+            McpError::MethodNotFound(_) => METHOD_NOT_FOUND,
+            McpError::InvalidParams(_) | McpError::ToolNotFound(_) => INVALID_PARAMS,
+            McpError::ResourceNotFound { .. } => RESOURCE_NOT_FOUND,
+            _ => INTERNAL_ERROR,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+/// The answer to a request: a result, or a JSON-RPC error.
+pub type Answer<T> = result::Result<T, RpcError>;
+
+/// The response to the request with `id`.
+pub fn response(id: Value, answer: Answer<Value>) -> Value {
+    match answer {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    }
+}
+
+/// The response to a frame that couldn't be parsed.
+pub fn parse_error(error: &serde_json::Error) -> Value {
+    response(
+        Value::Null,
+        Err(RpcError::new(PARSE_ERROR, error.to_string())),
+    )
+}
+
+/// A notification.
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// A string parameter a request can't do without.
+pub fn required<'a>(params: &'a Value, name: &str) -> Answer<&'a str> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing '{name}'")))
+}
+
+/// A message as a newline-delimited frame.
+pub fn frame(message: &Value) -> Result<Vec<u8>> {
+    let mut frame = serde_json::to_vec(message)?;
+    frame.push(b'\n');
+    Ok(frame)
+}
+
+/// Writes a message as a newline-delimited frame.
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    writer.write_all(&frame(message)?).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response() {
+        assert_eq!(
+            response(json!(1), Ok(json!({}))),
+            json!({ "jsonrpc": "2.0", "id": 1, "result": {} })
+        );
+        let error = response(json!("a"), Err(RpcError::invalid_params("Missing 'uri'")));
+        assert_eq!(error["error"]["code"], INVALID_PARAMS);
+        assert_eq!(error["id"], "a");
+        assert!(required(&json!({ "uri": 1 }), "uri").is_err());
+        assert_eq!(required(&json!({ "uri": "x" }), "uri").unwrap(), "x");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let code = |error: McpError| RpcError::from(Error::MpcClient(error)).code;
+        assert_eq!(code(McpError::MethodNotFound("x".into())), METHOD_NOT_FOUND);
+        assert_eq!(code(McpError::InvalidParams("x".into())), INVALID_PARAMS);
+        assert_eq!(code(McpError::ToolNotFound("x".into())), INVALID_PARAMS);
+        assert_eq!(
+            code(McpError::ResourceNotFound {
+                uri: "file:///x".into()
+            }),
+            RESOURCE_NOT_FOUND
+        );
+        assert_eq!(code(McpError::InternalError("x".into())), INTERNAL_ERROR);
+        assert_eq!(
+            RpcError::from(Error::Other("x".into())).code,
+            INTERNAL_ERROR
+        );
+    }
+}
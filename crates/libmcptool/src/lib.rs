@@ -1,19 +1,57 @@
 #![allow(missing_docs)]
 pub mod args;
 pub mod auth;
+pub mod batch;
+pub mod bench;
 pub mod calltool;
+pub mod caps;
+pub mod check;
 pub mod client;
 pub mod command;
 pub mod connect;
 pub mod ctx;
+pub mod diff;
+pub mod doc;
 pub mod error;
+pub mod expect;
+pub mod export;
+pub mod filter;
+pub mod frametrace;
+pub mod fuzz;
+pub mod gateway;
+pub mod generate;
+pub mod health;
+pub mod jsonrpc;
+pub mod lint;
+pub mod logs;
 pub mod mcp;
+pub mod mock;
+pub mod negotiate;
 pub mod output;
+pub mod policy;
+pub mod progress;
 pub mod proxy;
+pub mod ratelimit;
+pub mod recording;
+pub mod report;
+pub mod retry;
+pub mod rewrite;
+pub mod schema;
+pub mod secret;
+pub mod serve;
+pub mod snapshot;
+pub mod ssh;
+pub mod stats;
 pub mod storage;
 pub mod target;
+pub mod targets;
+pub mod templates;
 pub mod testserver;
+pub mod tls;
+pub mod trace;
+pub mod tunnel;
 pub mod utils;
+pub mod watch;
 
 // Re-export commonly used error types
 pub use error::{Error, Result};
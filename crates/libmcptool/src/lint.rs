@@ -0,0 +1,411 @@
+//! Linting a server's metadata.
+//!
+//! Where `check` tests that a server follows the protocol, the linter looks
+//! at how usable its tools, prompts and resources are to a model and the
+//! people wiring it up: whether they're described, whether their schemas
+//! say what they accept, and whether tools carry the annotations clients
+//! use to decide what needs confirming.
+
+use std::{collections::HashSet, fmt};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    Error, Result, ctx::Ctx, mcp::Listing, output::Output, snapshot::Snapshot, target::Target,
+};
+
+/// The longest description, in characters, before it's flagged. Long
+/// descriptions take up a model's context on every request.
+const MAX_DESCRIPTION: usize = 1024;
+
+/// Schema keywords that say what a value can be.
+const TYPE_KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "$ref",
+    "anyOf",
+    "oneOf",
+    "allOf",
+    "properties",
+    "items",
+];
+
+/// Schema keywords that constrain a string beyond being free text.
+const STRING_CONSTRAINTS: &[&str] = &["enum", "const", "format", "pattern", "maxLength"];
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth knowing, but often deliberate
+    Info,
+    /// Likely to make the server harder to use
+    Warning,
+    /// Clients can't reliably use what the server offers
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem the linter found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// The rule that found it, such as "missing-description".
+    pub rule: &'static str,
+    /// How serious it is.
+    pub severity: Severity,
+    /// The item it's about, such as "tool search_docs".
+    pub item: String,
+    /// What's wrong.
+    pub message: String,
+}
+
+/// Collects the findings for one item.
+struct Linter<'a> {
+    /// The item being linted.
+    item: String,
+    /// The findings so far.
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Linter<'_> {
+    /// Records a finding about the item.
+    fn flag(&mut self, rule: &'static str, severity: Severity, message: impl Into<String>) {
+        self.findings.push(Finding {
+            rule,
+            severity,
+            item: self.item.clone(),
+            message: message.into(),
+        });
+    }
+
+    /// Flags a missing or overlong description. Tools are chosen by their
+    /// descriptions, so a tool without one is a warning.
+    fn description(&mut self, listing: Listing, item: &Value) {
+        let description = item
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .unwrap_or_default();
+        if description.is_empty() {
+            let severity = if listing == Listing::Tools {
+                Severity::Warning
+            } else {
+                Severity::Info
+            };
+            self.flag("missing-description", severity, "Has no description");
+        } else if description.chars().count() > MAX_DESCRIPTION {
+            self.flag(
+                "long-description",
+                Severity::Warning,
+                format!(
+                    "The description is {} characters, over {MAX_DESCRIPTION}",
+                    description.chars().count()
+                ),
+            );
+        }
+    }
+
+    /// Lints a tool's name, annotations and input schema.
+    fn tool(&mut self, tool: &Value) {
+        let name = tool.get("name").and_then(Value::as_str).unwrap_or_default();
+        let valid_name = !name.is_empty()
+            && name.len() <= 128
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid_name {
+            self.flag(
+                "tool-name",
+                Severity::Warning,
+                "The name isn't 1-128 letters, digits, '_', '-' or '.'",
+            );
+        }
+        let hinted = tool
+            .get("annotations")
+            .and_then(Value::as_object)
+            .is_some_and(|annotations| annotations.keys().any(|key| key.ends_with("Hint")));
+        if !hinted {
+            self.flag(
+                "missing-annotations",
+                Severity::Warning,
+                "Has no annotations saying whether it's read-only, destructive or idempotent",
+            );
+        }
+        match tool.get("inputSchema") {
+            Some(schema) => self.properties(schema, ""),
+            None => self.flag("untyped-schema", Severity::Error, "Has no inputSchema"),
+        }
+    }
+
+    /// Lints the properties of an object schema, and theirs in turn.
+    /// `path` is the object's path from the top of the schema.
+    fn properties(&mut self, schema: &Value, path: &str) {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return;
+        };
+        for (name, property) in properties {
+            let path = if path.is_empty() {
+                name.clone()
+            } else {
+                format!("{path}.{name}")
+            };
+            self.property(property, &path);
+        }
+    }
+
+    /// Lints one property's schema.
+    fn property(&mut self, schema: &Value, path: &str) {
+        let Some(object) = schema.as_object() else {
+            if schema.as_bool() == Some(true) {
+                self.flag(
+                    "untyped-schema",
+                    Severity::Warning,
+                    format!("`{path}` accepts anything"),
+                );
+            }
+            return;
+        };
+        if !TYPE_KEYWORDS.iter().any(|key| object.contains_key(*key)) {
+            self.flag(
+                "untyped-schema",
+                Severity::Warning,
+                format!("`{path}` has no type"),
+            );
+        }
+        let described = object
+            .get("description")
+            .and_then(Value::as_str)
+            .is_some_and(|description| !description.trim().is_empty());
+        if !described && !path.ends_with("[]") {
+            self.flag(
+                "undocumented-argument",
+                Severity::Info,
+                format!("`{path}` has no description"),
+            );
+        }
+        let string = match object.get("type") {
+            Some(Value::String(kind)) => kind == "string",
+            Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "string"),
+            _ => false,
+        };
+        if string
+            && !STRING_CONSTRAINTS
+                .iter()
+                .any(|key| object.contains_key(*key))
+        {
+            self.flag(
+                "free-text",
+                Severity::Info,
+                format!("`{path}` is free text, with no enum, format, pattern or maxLength"),
+            );
+        }
+        if let Some(items) = object.get("items") {
+            self.property(items, &format!("{path}[]"));
+        }
+        self.properties(schema, path);
+    }
+
+    /// Lints a prompt's arguments.
+    fn prompt(&mut self, prompt: &Value) {
+        let arguments = prompt.get("arguments").and_then(Value::as_array);
+        for argument in arguments.into_iter().flatten() {
+            let name = argument
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let described = argument
+                .get("description")
+                .and_then(Value::as_str)
+                .is_some_and(|description| !description.trim().is_empty());
+            if !described {
+                self.flag(
+                    "undocumented-argument",
+                    Severity::Info,
+                    format!("`{name}` has no description"),
+                );
+            }
+        }
+    }
+}
+
+/// Lints everything in a snapshot.
+pub fn lint(snapshot: &Snapshot) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for listing in Listing::ALL {
+        let mut seen = HashSet::new();
+        for item in snapshot.items(listing) {
+            let id = item
+                .get(listing.key())
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let mut linter = Linter {
+                item: format!("{} {id}", listing.noun()),
+                findings: &mut findings,
+            };
+            if !seen.insert(id) {
+                linter.flag(
+                    "duplicate-name",
+                    Severity::Error,
+                    format!("Listed more than once in {}", listing.method()),
+                );
+                continue;
+            }
+            linter.description(listing, item);
+            match listing {
+                Listing::Tools => linter.tool(item),
+                Listing::Prompts => linter.prompt(item),
+                Listing::Resources | Listing::ResourceTemplates => {
+                    if item.get("mimeType").is_none() {
+                        linter.flag("missing-mime-type", Severity::Info, "Has no mimeType");
+                    }
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// Lints a server's metadata and prints what was found. Fails if anything
+/// was found at `fail_on` or above.
+pub async fn lint_command(ctx: &Ctx, target: Target, fail_on: Severity) -> Result<()> {
+    let snapshot = Snapshot::capture(ctx, &target, false).await?;
+    let findings = lint(&snapshot);
+    report(&ctx.output, &target, &findings)?;
+    let failing = findings.iter().filter(|f| f.severity >= fail_on).count();
+    if failing > 0 {
+        return Err(Error::Other(format!(
+            "{failing} finding(s) at {fail_on} or above"
+        )));
+    }
+    Ok(())
+}
+
+/// Prints the findings and a count of each severity.
+fn report(output: &Output, target: &Target, findings: &[Finding]) -> Result<()> {
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    if output.structured() {
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "errors": count(Severity::Error),
+            "warnings": count(Severity::Warning),
+            "info": count(Severity::Info),
+            "findings": findings,
+        }))?;
+        return Ok(());
+    }
+    output.h1(format!("Lint findings for {target}"))?;
+    if findings.is_empty() {
+        output.trace_success("No problems found")?;
+        return Ok(());
+    }
+    let mut item = "";
+    for finding in findings {
+        if finding.item != item {
+            item = &finding.item;
+            output.h2(item)?;
+        }
+        let line = format!(
+            "{}: {} ({})",
+            finding.severity, finding.message, finding.rule
+        );
+        match finding.severity {
+            Severity::Error => output.trace_error(line)?,
+            Severity::Warning => output.trace_warn(line)?,
+            Severity::Info => output.note(line)?,
+        }
+    }
+    output.text(format!(
+        "{} findings: {} errors, {} warnings, {} info",
+        findings.len(),
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info),
+    ))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let documented = json!({
+            "name": "search",
+            "description": "Searches the docs",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "mode": { "type": "string", "enum": ["fast"], "description": "How" },
+                },
+            },
+        });
+        let snapshot = Snapshot {
+            target: "cmd://server".to_string(),
+            captured_at: Utc::now(),
+            init: json!({}),
+            tools: vec![
+                documented.clone(),
+                documented,
+                json!({
+                    "name": "fetch page",
+                    "description": "x".repeat(MAX_DESCRIPTION + 1),
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "url": { "type": "string", "description": "The page" },
+                            "extra": {
+                                "type": "object",
+                                "description": "More",
+                                "properties": { "anything": {} },
+                            },
+                        },
+                    },
+                }),
+            ],
+            prompts: vec![json!({ "name": "greet", "arguments": [{ "name": "who" }] })],
+            resources: vec![json!({ "uri": "file:///a", "mimeType": "text/plain" })],
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        };
+
+        let findings = lint(&snapshot);
+        let rules: Vec<(&str, &str, Severity)> = findings
+            .iter()
+            .map(|f| (f.item.as_str(), f.rule, f.severity))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("tool search", "duplicate-name", Severity::Error),
+                ("tool fetch page", "long-description", Severity::Warning),
+                ("tool fetch page", "tool-name", Severity::Warning),
+                ("tool fetch page", "missing-annotations", Severity::Warning),
+                ("tool fetch page", "untyped-schema", Severity::Warning),
+                ("tool fetch page", "undocumented-argument", Severity::Info),
+                ("tool fetch page", "free-text", Severity::Info),
+                ("prompt greet", "missing-description", Severity::Info),
+                ("prompt greet", "undocumented-argument", Severity::Info),
+                ("resource file:///a", "missing-description", Severity::Info),
+            ]
+        );
+        assert_eq!(findings[4].message, "`extra.anything` has no type");
+        assert!(Severity::Error > Severity::Warning && Severity::Warning > Severity::Info);
+    }
+}
@@ -0,0 +1,199 @@
+//! Tailing the log messages a server sends.
+
+use chrono::{DateTime, Local};
+use serde_json::{Value, json};
+use tmcp::{
+    ClientCtx, ClientHandler,
+    schema::{LoggingLevel, ServerNotification},
+};
+use tokio::{signal, sync::mpsc};
+use tracing::Level;
+
+use crate::{Result, client, ctx::Ctx, mcp, target::Target};
+
+/// The lowest level of log message to ask the server for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ServerLogLevel {
+    /// Everything, down to debugging detail
+    Debug,
+    /// Informational messages
+    Info,
+    /// Normal but significant events
+    Notice,
+    /// Warnings
+    Warning,
+    /// Errors
+    Error,
+    /// Critical conditions
+    Critical,
+    /// Action must be taken immediately
+    Alert,
+    /// The system is unusable
+    Emergency,
+}
+
+impl ServerLogLevel {
+    /// The level's name in the protocol.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Notice => "notice",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Critical => "critical",
+            Self::Alert => "alert",
+            Self::Emergency => "emergency",
+        }
+    }
+}
+
+/// A log message from the server.
+#[derive(Debug, Clone)]
+struct LogMessage {
+    /// When the message arrived.
+    time: DateTime<Local>,
+    /// The message's severity.
+    level: LoggingLevel,
+    /// The logger that sent it, if named.
+    logger: Option<String>,
+    /// The message itself.
+    data: Value,
+}
+
+/// Client connection that forwards log messages to a channel and ignores
+/// other notifications.
+#[derive(Clone)]
+struct LogsConn {
+    /// Sender for log messages.
+    sender: mpsc::UnboundedSender<LogMessage>,
+}
+
+#[async_trait::async_trait]
+impl ClientHandler for LogsConn {
+    async fn notification(
+        &self,
+        _context: &ClientCtx,
+        notification: ServerNotification,
+    ) -> tmcp::Result<()> {
+        if let ServerNotification::LoggingMessage {
+            level,
+            logger,
+            data,
+        } = notification
+        {
+            // The receiver is gone once the command stops
+            _ = self.sender.send(LogMessage {
+                time: Local::now(),
+                level,
+                logger,
+                data,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The level used to color a log message. Syslog's many severities fold
+/// into the few the terminal palette has colors for.
+fn color_level(level: LoggingLevel) -> Level {
+    match level {
+        LoggingLevel::Debug => Level::DEBUG,
+        LoggingLevel::Info | LoggingLevel::Notice => Level::INFO,
+        LoggingLevel::Warning => Level::WARN,
+        LoggingLevel::Error
+        | LoggingLevel::Critical
+        | LoggingLevel::Alert
+        | LoggingLevel::Emergency => Level::ERROR,
+    }
+}
+
+/// A log message as one line of text: the time, level and logger, then the
+/// data, which is shown as is if it's a string and as JSON otherwise.
+fn format_message(message: &LogMessage) -> String {
+    let level = format!("{:?}", message.level).to_uppercase();
+    let logger = message
+        .logger
+        .as_deref()
+        .map(|logger| format!(" [{logger}]"))
+        .unwrap_or_default();
+    let data = match &message.data {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    format!(
+        "{} {level:<9}{logger} {data}",
+        message.time.format("%H:%M:%S%.3f")
+    )
+}
+
+/// Prints a log message, as a colored line or, in JSON mode, a JSON line.
+fn print_message(ctx: &Ctx, message: &LogMessage) -> Result<()> {
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_line(&json!({
+            "time": message.time.to_rfc3339(),
+            "level": message.level,
+            "logger": message.logger,
+            "data": message.data,
+        }))?;
+    } else {
+        output.log(format_message(message), color_level(message.level))?;
+    }
+    Ok(())
+}
+
+/// Sets the server's logging level and prints the log messages it sends
+/// until interrupted.
+pub async fn logs_command(ctx: &Ctx, target: Target, level: ServerLogLevel) -> Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let (mut client, init) =
+        client::get_client_with_connection(ctx, &target, LogsConn { sender }).await?;
+    if init.capabilities.logging.is_none() {
+        ctx.output
+            .trace_warn("The server doesn't declare the logging capability")?;
+    }
+    mcp::set_level(&mut client, ctx, level.as_str()).await?;
+    ctx.output.trace_info(format!(
+        "Following logs from {target}. Press Ctrl-C to stop"
+    ))?;
+    loop {
+        let message = tokio::select! {
+            message = receiver.recv() => message,
+            _ = signal::ctrl_c() => return Ok(()),
+        };
+        match message {
+            Some(message) => print_message(ctx, &message)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        let time = Local::now();
+        let mut message = LogMessage {
+            time,
+            level: LoggingLevel::Warning,
+            logger: Some("db".to_string()),
+            data: json!("slow query"),
+        };
+        let stamp = time.format("%H:%M:%S%.3f");
+        assert_eq!(
+            format_message(&message),
+            format!("{stamp} WARNING   [db] slow query")
+        );
+
+        message.logger = None;
+        message.data = json!({"ms": 1200});
+        assert_eq!(
+            format_message(&message),
+            format!("{stamp} WARNING   {{\"ms\":1200}}")
+        );
+        assert_eq!(color_level(LoggingLevel::Critical), Level::ERROR);
+    }
+}
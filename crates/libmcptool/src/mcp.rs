@@ -1,39 +1,123 @@
 //! MCP client command implementations.
 
+use std::{collections::HashSet, time::Duration};
+
+use serde_json::{Value, json};
 use tmcp::{
-    Client, ClientHandler, ServerAPI,
+    Arguments, Client, ClientHandler, ServerAPI,
     schema::{
-        ArgumentInfo, InitializeResult, LoggingLevel, PromptReference, Reference, ResourceReference,
+        ArgumentInfo, CallToolResult, ClientNotification, CompleteResult, Cursor, GetPromptResult,
+        InitializeResult, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, LoggingLevel, Prompt, PromptReference, ReadResourceResult, Reference,
+        RequestId, ResourceReference, Tool,
     },
 };
+use tokio::time;
 
 use crate::{
-    Error, Result, args::ArgumentParser, calltool, output, output::Output, utils::TimedFuture,
+    Error, Result,
+    args::ArgumentParser,
+    calltool::{self, CallOptions, interactive::Suggestions},
+    ctx::Ctx,
+    output,
+    output::Output,
+    retry::CallRetry,
+    stats::{LatencyStats, millis},
+    utils::TimedFuture,
 };
 
-/// Pings the MCP server.
+/// Pings the MCP server, or with a `count` over one, pings it repeatedly
+/// and prints statistics.
 pub async fn ping<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
+    count: u32,
+    interval: Duration,
 ) -> Result<()> {
-    output.text("Pinging")?;
-    client.ping().timed("   response", output).await?;
+    if count > 1 {
+        return repeat_ping(client, ctx, count, interval).await;
+    }
+    let output = &ctx.output;
+    output.status("Pinging")?;
+    ctx.request(client.ping())
+        .timed("   response", output)
+        .await?;
     output.ping()?;
     Ok(())
 }
 
+/// Pings `count` times, waiting `interval` between pings, printing each
+/// round trip and then min/avg/max/stddev and the share of pings lost, like
+/// ping(8). Fails if no ping was answered.
+async fn repeat_ping<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    count: u32,
+    interval: Duration,
+) -> Result<()> {
+    let output = &ctx.output;
+    let mut latencies = Vec::new();
+    for index in 1..=count {
+        if index > 1 && !interval.is_zero() {
+            time::sleep(interval).await;
+        }
+        let (result, elapsed) = ctx.request(client.ping()).measured().await;
+        match result {
+            Ok(_) => {
+                latencies.push(elapsed);
+                output.text(format!(
+                    "   ping {index}/{count} in {:.2}ms",
+                    millis(elapsed)
+                ))?;
+            }
+            Err(e) => output.text(format!("   ping {index}/{count} failed: {e}"))?,
+        }
+    }
+
+    let lost = count - u32::try_from(latencies.len()).unwrap_or(u32::MAX);
+    let loss = f64::from(lost) / f64::from(count) * 100.0;
+    let stats = LatencyStats::from_samples(&latencies);
+    if output.structured() {
+        output.json_value(&json!({
+            "pings": count,
+            "lost": lost,
+            "lossPercent": loss,
+            "latencyMs": stats.map(|stats| stats.to_json()),
+        }))?;
+    } else {
+        output.h1(format!("Ping statistics over {count} pings"))?;
+        let out = output.indent();
+        out.kv("Lost", format!("{lost} of {count} ({loss:.1}%)"))?;
+        if let Some(stats) = stats {
+            for (key, value) in [
+                ("Min", stats.min),
+                ("Avg", stats.mean),
+                ("Max", stats.max),
+                ("Stddev", stats.stddev),
+            ] {
+                out.kv(key, format!("{:.2}ms", millis(value)))?;
+            }
+        }
+    }
+    if latencies.is_empty() {
+        return Err(Error::Other(format!("No answer to {count} pings")));
+    }
+    Ok(())
+}
+
 /// Lists all available tools from the MCP server.
 pub async fn listtools<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
-) -> Result<()> {
-    output.text("Listing tools")?;
-    let tools_result = client
-        .list_tools(None)
+    ctx: &Ctx,
+) -> Result<ListToolsResult> {
+    let output = &ctx.output;
+    output.status("Listing tools")?;
+    let tools_result = ctx
+        .request(client.list_tools(None))
         .timed("    response", output)
         .await?;
     output::listtools::list_tools_result(output, &tools_result)?;
-    Ok(())
+    Ok(tools_result)
 }
 
 /// Displays the initialization result from the MCP server.
@@ -45,52 +129,195 @@ pub fn init(init_result: &InitializeResult, output: &Output) -> Result<()> {
 /// Lists all available resources from the MCP server.
 pub async fn listresources<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
-) -> Result<()> {
-    output.text("Listing resources")?;
-    let resources_result = client
-        .list_resources(None)
+    ctx: &Ctx,
+) -> Result<ListResourcesResult> {
+    let output = &ctx.output;
+    output.status("Listing resources")?;
+    let resources_result = ctx
+        .request(client.list_resources(None))
         .timed("    response", output)
         .await?;
     output::listresources::list_resources_result(output, &resources_result)?;
-    Ok(())
+    Ok(resources_result)
 }
 
 /// Lists all available prompts from the MCP server.
 pub async fn listprompts<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
-) -> Result<()> {
-    output.text("Listing prompts")?;
-    let prompts_result = client
-        .list_prompts(None)
+    ctx: &Ctx,
+) -> Result<ListPromptsResult> {
+    let output = &ctx.output;
+    output.status("Listing prompts")?;
+    let prompts_result = ctx
+        .request(client.list_prompts(None))
         .timed("    response", output)
         .await?;
     output::listprompts::list_prompts_result(output, &prompts_result)?;
-    Ok(())
+    Ok(prompts_result)
 }
 
 /// Lists all available resource templates from the MCP server.
 pub async fn listresourcetemplates<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
-) -> Result<()> {
-    output.text("Listing resource templates")?;
-    let templates_result = client
-        .list_resource_templates(None)
+    ctx: &Ctx,
+) -> Result<ListResourceTemplatesResult> {
+    let output = &ctx.output;
+    output.status("Listing resource templates")?;
+    let templates_result = ctx
+        .request(client.list_resource_templates(None))
         .timed("    response", output)
         .await?;
     output::listresourcetemplates::list_resource_templates_result(output, &templates_result)?;
-    Ok(())
+    Ok(templates_result)
+}
+
+/// The most pages of a listing walked before it's taken to never end.
+const MAX_PAGES: usize = 1000;
+
+/// A paginated listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listing {
+    /// `tools/list`.
+    Tools,
+    /// `prompts/list`.
+    Prompts,
+    /// `resources/list`.
+    Resources,
+    /// `resources/templates/list`.
+    ResourceTemplates,
+}
+
+impl Listing {
+    /// Every listing.
+    pub const ALL: [Self; 4] = [
+        Self::Tools,
+        Self::Prompts,
+        Self::Resources,
+        Self::ResourceTemplates,
+    ];
+
+    /// The method that fetches the listing.
+    pub fn method(self) -> &'static str {
+        match self {
+            Self::Tools => "tools/list",
+            Self::Prompts => "prompts/list",
+            Self::Resources => "resources/list",
+            Self::ResourceTemplates => "resources/templates/list",
+        }
+    }
+
+    /// The field of a page holding the items.
+    pub fn field(self) -> &'static str {
+        match self {
+            Self::Tools => "tools",
+            Self::Prompts => "prompts",
+            Self::Resources => "resources",
+            Self::ResourceTemplates => "resourceTemplates",
+        }
+    }
+
+    /// The field that identifies an item.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Tools | Self::Prompts => "name",
+            Self::Resources => "uri",
+            Self::ResourceTemplates => "uriTemplate",
+        }
+    }
+
+    /// What one of the listing's items is called, such as "resource
+    /// template".
+    pub fn noun(self) -> &'static str {
+        match self {
+            Self::Tools => "tool",
+            Self::Prompts => "prompt",
+            Self::Resources => "resource",
+            Self::ResourceTemplates => "resource template",
+        }
+    }
+
+    /// The server capability that declares the listing.
+    pub fn capability(self) -> &'static str {
+        match self {
+            Self::Tools => "tools",
+            Self::Prompts => "prompts",
+            Self::Resources | Self::ResourceTemplates => "resources",
+        }
+    }
+}
+
+/// Fetches one page of a listing as JSON.
+pub async fn list_page<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    listing: Listing,
+    cursor: Option<&str>,
+) -> Result<Value> {
+    let cursor: Option<Cursor> = cursor
+        .map(|cursor| serde_json::from_value(json!(cursor)))
+        .transpose()?;
+    let page = match listing {
+        Listing::Tools => serde_json::to_value(ctx.request(client.list_tools(cursor)).await?),
+        Listing::Prompts => serde_json::to_value(ctx.request(client.list_prompts(cursor)).await?),
+        Listing::Resources => {
+            serde_json::to_value(ctx.request(client.list_resources(cursor)).await?)
+        }
+        Listing::ResourceTemplates => {
+            serde_json::to_value(ctx.request(client.list_resource_templates(cursor)).await?)
+        }
+    };
+    Ok(page?)
+}
+
+/// Walks every page of a listing, returning its items and the number of
+/// pages. Fails if a page is malformed or the cursors loop.
+pub async fn list_all<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    listing: Listing,
+) -> Result<(Vec<Value>, usize)> {
+    let mut items = Vec::new();
+    let mut cursors = HashSet::new();
+    let mut cursor: Option<String> = None;
+    for pages in 1..=MAX_PAGES {
+        let mut page = list_page(client, ctx, listing, cursor.as_deref()).await?;
+        match page.get_mut(listing.field()).map(Value::take) {
+            Some(Value::Array(page_items)) => items.extend(page_items),
+            _ => {
+                return Err(Error::Other(format!(
+                    "Page {pages} has no {} array",
+                    listing.field()
+                )));
+            }
+        }
+        match page.get("nextCursor") {
+            None | Some(Value::Null) => return Ok((items, pages)),
+            Some(Value::String(next)) if !cursors.insert(next.clone()) => {
+                return Err(Error::Other(format!(
+                    "Page {pages} repeats the cursor '{next}', so the listing never ends"
+                )));
+            }
+            Some(Value::String(next)) => cursor = Some(next.clone()),
+            Some(other) => {
+                return Err(Error::Other(format!(
+                    "Page {pages} has a nextCursor that isn't a string: {other}"
+                )));
+            }
+        }
+    }
+    Err(Error::Other(format!(
+        "Gave up after {MAX_PAGES} pages without reaching the end"
+    )))
 }
 
 /// Sets the logging level on the MCP server.
 pub async fn set_level<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     level: &str,
 ) -> Result<()> {
-    output.text(format!("Setting logging level to: {level}"))?;
+    let output = &ctx.output;
+    output.status(format!("Setting logging level to: {level}"))?;
 
     // Parse the level string into LoggingLevel enum
     let logging_level = match level.to_lowercase().as_str() {
@@ -111,8 +338,7 @@ pub async fn set_level<C: ClientHandler + 'static>(
     };
 
     // Send the set level request to the server
-    client
-        .set_level(logging_level)
+    ctx.request(client.set_level(logging_level))
         .timed("    response", output)
         .await?;
 
@@ -120,36 +346,25 @@ pub async fn set_level<C: ClientHandler + 'static>(
     Ok(())
 }
 
-/// Calls a tool on the MCP server.
+/// Calls a tool on the MCP server. Arguments come from `--arg` values,
+/// interactive prompts, a JSON or YAML document, or an already parsed JSON
+/// object, and are checked against the tool's input schema unless
+/// validation is turned off. A dry run prints the request instead of sending
+/// it, and returns no result.
 pub async fn calltool<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     tool_name: &str,
-    args: Vec<String>,
-    interactive: bool,
-    json: bool,
-) -> Result<()> {
-    // Validate input modes
-    let mode_count = [!args.is_empty(), interactive, json]
-        .iter()
-        .filter(|&&x| x)
-        .count();
-    if mode_count == 0 {
-        return Err(Error::Other(
-            "Must specify one of: --interactive, --json, or --arg key=value arguments".to_string(),
-        ));
-    }
-    if mode_count > 1 {
-        return Err(Error::Other(
-            "Cannot combine --interactive, --json, and --arg modes".to_string(),
-        ));
-    }
+    mut options: CallOptions,
+) -> Result<Option<CallToolResult>> {
+    let output = &ctx.output;
+    options.check_modes()?;
 
-    output.text(format!("Calling tool: {tool_name}"))?;
+    output.status(format!("Calling tool: {tool_name}"))?;
 
     // First, get tool schema to understand required parameters
-    let tools_result = client
-        .list_tools(None)
+    let tools_result = ctx
+        .request(client.list_tools(None))
         .timed("   fetching tools", output)
         .await?;
 
@@ -159,68 +374,297 @@ pub async fn calltool<C: ClientHandler + 'static>(
         .find(|t| t.name == tool_name)
         .ok_or_else(|| Error::Other(format!("Tool '{tool_name}' not found")))?;
 
-    // Parse arguments based on mode
-    let arguments = if json {
-        calltool::json::parse_json_arguments(output)?
-    } else if interactive {
-        calltool::interactive::parse_interactive_arguments(tool, output)?
-    } else {
-        calltool::cmdline::parse_command_line_arguments(args, output)?
-    };
+    let arguments = options.take_arguments(tool, output)?;
+    let CallOptions {
+        dry_run,
+        strict,
+        save_content,
+        repeat,
+        interval,
+        expect,
+        yes,
+        retry,
+        ..
+    } = options;
+    if dry_run {
+        output.note("Dry run - this request would be sent:")?;
+        output.json_value(&calltool::call_request(tool_name, arguments.as_ref())?)?;
+        return Ok(None);
+    }
+    if !yes
+        && calltool::is_destructive(tool)
+        && atty::is(atty::Stream::Stdin)
+        && !calltool::interactive::confirm_destructive(tool)?
+    {
+        return Err(Error::Other("Tool call cancelled".to_string()));
+    }
+    if repeat > 1 {
+        repeat_call(client, ctx, tool_name, arguments, &retry, repeat, interval).await?;
+        return Ok(None);
+    }
 
     // Call the tool
-    let result = client
-        .call_tool(tool_name, arguments)
+    let result = call_tool_with_retries(client, ctx, tool_name, arguments, &retry)
         .timed("   response", output)
-        .await?;
+        .await;
+    output.end_progress()?;
+    let result = match result {
+        Err(e) if expect.error => {
+            check_expectations(output, expect.check_error(&e))?;
+            return Ok(None);
+        }
+        result => result?,
+    };
+
+    output::calltool::call_tool_result(output, &result)?;
+    if let Some(dir) = save_content {
+        output::calltool::save_content(output, &result, &dir)?;
+    }
 
-    output::calltool::call_tool_result(output, &result)
+    check_result(output, tool, &result, strict)?;
+    if !expect.is_empty() {
+        check_expectations(output, expect.check_result(&result)?)?;
+    }
+    Ok(Some(result))
+}
+
+/// Fails with every unmet expectation listed, or reports that all were met.
+fn check_expectations(output: &Output, failures: Vec<String>) -> Result<()> {
+    if failures.is_empty() {
+        output.trace_success("All expectations met")?;
+        return Ok(());
+    }
+    let details: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("  {failure}"))
+        .collect();
+    Err(Error::Expectation(details.join("\n")))
+}
+
+/// Calls a tool `count` times with the same arguments, waiting `interval`
+/// between calls, then prints latency statistics and the number of failed
+/// calls. A call's latency includes any retries. Fails if any call failed.
+pub async fn repeat_call<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    tool_name: &str,
+    arguments: Option<Arguments>,
+    retry: &CallRetry,
+    count: u32,
+    interval: Duration,
+) -> Result<()> {
+    let output = &ctx.output;
+    let mut latencies = Vec::new();
+    let mut errors = 0;
+    for index in 1..=count {
+        if index > 1 && !interval.is_zero() {
+            time::sleep(interval).await;
+        }
+        let (result, elapsed) =
+            call_tool_with_retries(client, ctx, tool_name, arguments.clone(), retry)
+                .measured()
+                .await;
+        latencies.push(elapsed);
+        let failure = match result {
+            Ok(result) if result.is_error == Some(true) => {
+                Some("tool returned an error result".to_string())
+            }
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        match failure {
+            Some(message) => {
+                errors += 1;
+                output.text(format!(
+                    "   call {index}/{count} failed in {millis:.2}ms: {message}"
+                ))?;
+            }
+            None => output.text(format!("   call {index}/{count} in {millis:.2}ms"))?,
+        }
+    }
+
+    let stats = LatencyStats::from_samples(&latencies);
+    if output.structured() {
+        output.json_value(&json!({
+            "calls": count,
+            "errors": errors,
+            "latencyMs": stats.map(|stats| stats.to_json()),
+        }))?;
+    } else {
+        output.h1(format!("Latency over {count} calls"))?;
+        if let Some(stats) = stats {
+            stats.display(&output.indent())?;
+        }
+        output
+            .indent()
+            .kv("Errors", format!("{errors} of {count}"))?;
+    }
+    if errors > 0 {
+        return Err(Error::Other(format!("{errors} of {count} call(s) failed")));
+    }
+    Ok(())
+}
+
+/// Warns when a result doesn't match the tool's output schema, or fails if
+/// `strict` is set.
+fn check_result(output: &Output, tool: &Tool, result: &CallToolResult, strict: bool) -> Result<()> {
+    let violations = calltool::validate_result(tool, result)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = violations
+        .iter()
+        .map(|violation| format!("  {violation}"))
+        .collect();
+    let message = format!(
+        "Result doesn't match the output schema of '{}':\n{}",
+        tool.name,
+        details.join("\n")
+    );
+    if strict {
+        return Err(Error::Format(message));
+    }
+    output.trace_warn(message)?;
+    Ok(())
 }
 
-/// Reads a resource from the MCP server.
+/// Calls a tool, retrying the failures `retry` covers with exponential
+/// backoff. Each retry is reported through the output.
+pub async fn call_tool_with_retries<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    tool_name: &str,
+    arguments: Option<Arguments>,
+    retry: &CallRetry,
+) -> Result<CallToolResult> {
+    let mut attempt = 0;
+    loop {
+        match ctx
+            .request(client.call_tool(tool_name, arguments.clone()))
+            .await
+        {
+            Err(e) if retry.should_retry(&e, attempt) => {
+                let delay = retry.policy.delay_for(attempt);
+                attempt += 1;
+                ctx.output
+                    .trace_warn(format!("Call to {tool_name} failed: {e}"))?;
+                ctx.output.status(format!(
+                    "Retrying in {:.1}s (attempt {} of {})",
+                    delay.as_secs_f64(),
+                    attempt + 1,
+                    retry.policy.retries + 1
+                ))?;
+                time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Reads a resource from the MCP server. Binary contents are shown as a
+/// hexdump of their first `hexdump_bytes` bytes, unless `raw` is set, in
+/// which case all contents are written to stdout as they are.
 pub async fn read_resource<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     uri: &str,
-) -> Result<()> {
-    output.text(format!("Reading resource: {uri}"))?;
-    let result = client
-        .resources_read(uri)
+    hexdump_bytes: usize,
+    raw: bool,
+) -> Result<ReadResourceResult> {
+    let output = &ctx.output;
+    output.status(format!("Reading resource: {uri}"))?;
+    let result = ctx
+        .request(client.resources_read(uri))
         .timed("    response", output)
         .await?;
-    output::readresource::read_resource_result(output, &result)?;
-    Ok(())
+    if raw {
+        output::readresource::write_raw(output, &result)?;
+    } else {
+        output::readresource::read_resource_result(output, &result, hexdump_bytes)?;
+    }
+    Ok(result)
 }
 
-/// Gets a prompt from the MCP server.
+/// Gets a prompt from the MCP server. In interactive mode the user is asked
+/// for each of the prompt's arguments, with the server's completions as
+/// suggestions.
 pub async fn get_prompt<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     name: &str,
     args: Vec<String>,
-) -> Result<()> {
-    output.text(format!("Getting prompt: {name}"))?;
+    interactive: bool,
+) -> Result<GetPromptResult> {
+    let output = &ctx.output;
+    output.status(format!("Getting prompt: {name}"))?;
 
-    // Parse arguments from key=value format
-    let arguments = ArgumentParser::parse_key_value_args(args)?;
+    let arguments = if interactive {
+        if !args.is_empty() {
+            return Err(Error::Other(
+                "Cannot combine --interactive and --arg".to_string(),
+            ));
+        }
+        let prompts = ctx
+            .request(client.list_prompts(None))
+            .timed("   fetching prompts", output)
+            .await?;
+        let prompt = prompts
+            .prompts
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| Error::Other(format!("Prompt '{name}' not found")))?;
+        let suggestions = prompt_suggestions(client, ctx, prompt).await;
+        calltool::interactive::parse_prompt_arguments(prompt, &suggestions, output)?
+    } else {
+        // Parse arguments from key=value format
+        ArgumentParser::parse_key_value_args(args)?
+    };
 
-    let result = client
-        .get_prompt(name, arguments)
+    let result = ctx
+        .request(client.get_prompt(name, arguments))
         .timed("    response", output)
         .await?;
     output::getprompt::get_prompt_result(output, &result)?;
-    Ok(())
+    Ok(result)
+}
+
+/// Asks the server to complete each of a prompt's arguments from an empty
+/// value, for interactive entry. Servers that don't support completion give
+/// no suggestions.
+async fn prompt_suggestions<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    prompt: &Prompt,
+) -> Suggestions {
+    let mut suggestions = Suggestions::new();
+    for argument in prompt.arguments.iter().flatten() {
+        let reference = Reference::Prompt(PromptReference {
+            name: prompt.name.clone(),
+            title: None,
+        });
+        let argument_info = ArgumentInfo {
+            name: argument.name.clone(),
+            value: String::new(),
+        };
+        if let Ok(result) = ctx.request(client.complete(reference, argument_info)).await
+            && !result.completion.values.is_empty()
+        {
+            suggestions.insert(argument.name.clone(), result.completion.values);
+        }
+    }
+    suggestions
 }
 
 /// Subscribes to resource updates from the MCP server.
 pub async fn subscribe_resource<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     uri: &str,
 ) -> Result<()> {
-    output.text(format!("Subscribing to resource: {uri}"))?;
-    client
-        .resources_subscribe(uri)
+    let output = &ctx.output;
+    output.status(format!("Subscribing to resource: {uri}"))?;
+    ctx.request(client.resources_subscribe(uri))
         .timed("    response", output)
         .await?;
     output.trace_success(format!("Successfully subscribed to resource: {uri}"))?;
@@ -230,26 +674,43 @@ pub async fn subscribe_resource<C: ClientHandler + 'static>(
 /// Unsubscribes from resource updates.
 pub async fn unsubscribe_resource<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     uri: &str,
 ) -> Result<()> {
-    output.text(format!("Unsubscribing from resource: {uri}"))?;
-    client
-        .resources_unsubscribe(uri)
+    let output = &ctx.output;
+    output.status(format!("Unsubscribing from resource: {uri}"))?;
+    ctx.request(client.resources_unsubscribe(uri))
         .timed("    response", output)
         .await?;
     output.trace_success(format!("Successfully unsubscribed from resource: {uri}"))?;
     Ok(())
 }
 
+/// Tells the server the request with `id` is cancelled, giving `reason`.
+pub async fn cancel_request<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    id: &Value,
+    reason: &str,
+) -> Result<()> {
+    let request_id: RequestId = serde_json::from_value(id.clone())?;
+    client
+        .send_notification(ClientNotification::Cancelled {
+            request_id,
+            reason: Some(reason.to_string()),
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Failed to send the cancellation: {e}")))
+}
+
 /// Gets completions for an argument.
 pub async fn complete<C: ClientHandler + 'static>(
     client: &mut Client<C>,
-    output: &Output,
+    ctx: &Ctx,
     reference: &str,
     argument: &str,
-) -> Result<()> {
-    output.text(format!("Getting completions for: {reference}/{argument}"))?;
+) -> Result<CompleteResult> {
+    let output = &ctx.output;
+    output.status(format!("Getting completions for: {reference}/{argument}"))?;
 
     // Parse the reference into Reference
     let completion_ref = if reference.starts_with("resource://") {
@@ -276,10 +737,10 @@ pub async fn complete<C: ClientHandler + 'static>(
         value: "".to_string(),
     };
 
-    let result = client
-        .complete(completion_ref, argument_info)
+    let result = ctx
+        .request(client.complete(completion_ref, argument_info))
         .timed("    response", output)
         .await?;
     output::complete::complete_result(output, &result)?;
-    Ok(())
+    Ok(result)
 }
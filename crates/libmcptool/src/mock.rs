@@ -0,0 +1,299 @@
+//! A mock MCP server that answers requests with the responses from a
+//! recording, so clients can be tested against deterministic behavior.
+
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, split, stdin, stdout},
+    net::TcpListener,
+    task,
+};
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    jsonrpc::{self, METHOD_NOT_FOUND},
+    output::Output,
+    proxy::Listen,
+    recording::{Origin, RecordedFrame},
+};
+
+/// A recorded request and the server's response to it.
+#[derive(Debug, Clone, PartialEq)]
+struct Exchange {
+    /// The request method.
+    method: String,
+    /// The request parameters, if any.
+    params: Option<Value>,
+    /// The response message, without its id.
+    response: Value,
+}
+
+/// The exchanges a mock server answers from, in the order they were
+/// recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    /// Every recorded exchange.
+    exchanges: Vec<Exchange>,
+}
+
+impl Script {
+    /// Loads a recording made with `mcptool proxy --record`, or a fixture
+    /// written by hand in the same format. Only `from` and `message` are
+    /// needed on each line.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        let mut frames = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let frame: RecordedFrame = serde_json::from_str(line)
+                .map_err(|e| Error::Format(format!("{}:{}: {e}", path.display(), index + 1)))?;
+            frames.push(frame);
+        }
+        Ok(Self::from_frames(&frames))
+    }
+
+    /// Pairs client requests with the server responses that share their
+    /// session and id.
+    fn from_frames(frames: &[RecordedFrame]) -> Self {
+        let mut pending = HashMap::new();
+        let mut exchanges = Vec::new();
+        for frame in frames {
+            let message = &frame.message;
+            let Some(id) = message.get("id") else {
+                continue;
+            };
+            let key = (frame.session, id.to_string());
+            match (frame.from, message.get("method").and_then(Value::as_str)) {
+                (Origin::Client, Some(method)) => {
+                    pending.insert(key, (method.to_string(), message.get("params").cloned()));
+                }
+                (Origin::Server, None) => {
+                    if let Some((method, params)) = pending.remove(&key) {
+                        let mut response = message.clone();
+                        if let Some(response) = response.as_object_mut() {
+                            response.remove("id");
+                        }
+                        exchanges.push(Exchange {
+                            method,
+                            params,
+                            response,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self { exchanges }
+    }
+
+    /// The number of exchanges in the script.
+    pub fn len(&self) -> usize {
+        self.exchanges.len()
+    }
+
+    /// Whether the script has no exchanges.
+    pub fn is_empty(&self) -> bool {
+        self.exchanges.is_empty()
+    }
+
+    /// The response to a request, or None for notifications. Recorded
+    /// responses are served in order, preferring ones whose request had
+    /// the same parameters; once all are used, the last is repeated.
+    /// `used` tracks which exchanges a session has served. Fixtures needn't
+    /// record `initialize` or `ping`, which get minimal answers.
+    fn respond(&self, used: &mut [bool], request: &Value) -> Option<Value> {
+        let id = request.get("id")?;
+        let method = request.get("method").and_then(Value::as_str)?;
+        let params = request.get("params");
+        let same_method = |exchange: &Exchange| exchange.method == method;
+        let same_params =
+            |exchange: &Exchange| same_method(exchange) && exchange.params.as_ref() == params;
+
+        let exchanges = &self.exchanges;
+        let first_unused = |matches: &dyn Fn(&Exchange) -> bool| {
+            (0..exchanges.len()).find(|index| !used[*index] && matches(&exchanges[*index]))
+        };
+        let last = |matches: &dyn Fn(&Exchange) -> bool| {
+            (0..exchanges.len())
+                .rev()
+                .find(|index| matches(&exchanges[*index]))
+        };
+        let chosen = first_unused(&same_params)
+            .or_else(|| first_unused(&same_method))
+            .or_else(|| last(&same_params))
+            .or_else(|| last(&same_method));
+
+        let mut response = match chosen {
+            Some(index) => {
+                used[index] = true;
+                self.exchanges[index].response.clone()
+            }
+            None if method == "ping" => json!({ "result": {} }),
+            None if method == "initialize" => json!({
+                "result": {
+                    "protocolVersion": params.and_then(|p| p.get("protocolVersion")),
+                    "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+                    "serverInfo": { "name": "mcptool-mock", "version": env!("CARGO_PKG_VERSION") },
+                }
+            }),
+            None => json!({
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("No recorded response for {method}"),
+                }
+            }),
+        };
+        if let Some(object) = response.as_object_mut() {
+            object.insert("jsonrpc".to_string(), json!("2.0"));
+            object.insert("id".to_string(), id.clone());
+        }
+        Some(response)
+    }
+}
+
+/// Answers one client's requests from the script until it disconnects.
+async fn serve<R, W>(output: &Output, script: &Script, reader: R, mut writer: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut used = vec![false; script.len()];
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                let response = script.respond(&mut used, &request);
+                if let Some(method) = request.get("method").and_then(Value::as_str) {
+                    let answer = match &response {
+                        Some(response) if response.get("error").is_some() => "error",
+                        Some(_) => "response",
+                        None => "notification",
+                    };
+                    output.status(format!("{method}: {answer}"))?;
+                }
+                response
+            }
+            Err(e) => Some(jsonrpc::parse_error(&e)),
+        };
+        if let Some(response) = response {
+            jsonrpc::write_frame(&mut writer, &response).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Serves canned responses from a recording or fixture file to MCP clients.
+pub async fn mock_command(ctx: &Ctx, path: &Path, listen: Listen) -> Result<()> {
+    let script = Arc::new(Script::load(path)?);
+    if script.is_empty() {
+        return Err(Error::Other(format!(
+            "{} has no request/response pairs to serve",
+            path.display()
+        )));
+    }
+    match listen {
+        Listen::Stdio => {
+            // Stdout carries the protocol, so nothing else may be written there
+            let output = ctx.output.with_sink(io::sink()).with_quiet(true);
+            serve(&output, &script, stdin(), stdout()).await
+        }
+        Listen::Tcp(addr) => {
+            let listener = TcpListener::bind(&addr)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to listen on {addr}: {e}")))?;
+            ctx.output.status(format!(
+                "Serving {} recorded responses on tcp://{addr}",
+                script.len()
+            ))?;
+            let mut session = 0;
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                session += 1;
+                ctx.output
+                    .status(format!("Session {session}: client connected from {peer}"))?;
+                let output = ctx.output.clone();
+                let script = Arc::clone(&script);
+                task::spawn(async move {
+                    let (reader, writer) = split(stream);
+                    let ended = match serve(&output, &script, reader, writer).await {
+                        Ok(()) => format!("Session {session}: closed"),
+                        Err(e) => format!("Session {session}: {e}"),
+                    };
+                    _ = output.status(ended);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame with no timing, as in a hand-written fixture.
+    fn frame(session: u64, from: Origin, message: Value) -> RecordedFrame {
+        RecordedFrame {
+            time: Default::default(),
+            elapsed: 0.0,
+            session,
+            from,
+            message,
+        }
+    }
+
+    #[test]
+    fn test_respond() {
+        let script = Script::from_frames(&[
+            frame(
+                1,
+                Origin::Client,
+                json!({"id": 1, "method": "tools/call", "params": {"name": "a"}}),
+            ),
+            frame(
+                1,
+                Origin::Client,
+                json!({"id": 2, "method": "tools/call", "params": {"name": "b"}}),
+            ),
+            frame(1, Origin::Server, json!({"id": 2, "result": "b"})),
+            frame(
+                1,
+                Origin::Server,
+                json!({"method": "notifications/progress"}),
+            ),
+            frame(1, Origin::Server, json!({"id": 1, "result": "a"})),
+            frame(2, Origin::Server, json!({"id": 1, "result": "orphan"})),
+        ]);
+        assert_eq!(script.len(), 2);
+
+        let mut used = vec![false; script.len()];
+        let call = |name: &str, id: u64| json!({"jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {"name": name}});
+        let result = |response: Option<Value>| response.unwrap()["result"].clone();
+        assert_eq!(result(script.respond(&mut used, &call("a", 7))), "a");
+        // No unused response has these params, so the next by method is used
+        assert_eq!(result(script.respond(&mut used, &call("a", 8))), "b");
+        // Everything is used, so the last match repeats
+        assert_eq!(result(script.respond(&mut used, &call("a", 9))), "a");
+
+        let response = script.respond(&mut used, &call("b", 10)).unwrap();
+        assert_eq!(response["id"], 10);
+        assert_eq!(response["jsonrpc"], "2.0");
+
+        let missing = script
+            .respond(&mut used, &json!({"id": 1, "method": "prompts/list"}))
+            .unwrap();
+        assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+        assert!(
+            script
+                .respond(&mut used, &json!({"method": "notifications/initialized"}))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Protocol version negotiation: forcing the version mcptool asks for,
+//! reporting the version the server chose, and choosing one for the clients
+//! of the servers mcptool runs itself.
+//!
+//! tmcp always asks for the latest protocol version, so a forced version is
+//! written into the initialize request on its way to the server. That needs
+//! a stream mcptool can see, so it works for tcp, tls, cmd and ssh targets
+//! but not HTTP. Tool calls get a progress token on the same way out, since
+//! tmcp doesn't send one.
+
+use std::{
+    io, mem,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use serde_json::{Value, json};
+use tmcp::schema::{InitializeResult, LATEST_PROTOCOL_VERSION};
+use tokio::io::AsyncWrite;
+
+use crate::{Result, output::Output, progress};
+
+/// Protocol versions a server may negotiate.
+pub const KNOWN_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// The version a server speaking every known version answers an initialize
+/// request with: the one the client asked for if it's known, otherwise the
+/// latest.
+pub fn choose(requested: Option<&str>) -> &str {
+    requested
+        .filter(|version| KNOWN_VERSIONS.contains(version))
+        .unwrap_or(LATEST_PROTOCOL_VERSION)
+}
+
+/// A frame to the server as it should be sent: an initialize request asks
+/// for `version` if one is given, and a tool call asks for progress. Other
+/// frames are returned unchanged.
+fn rewrite(frame: &[u8], version: Option<&str>) -> Vec<u8> {
+    let Ok(mut message) = serde_json::from_slice::<Value>(frame) else {
+        return frame.to_vec();
+    };
+    let rewritten = match message.get("method").and_then(Value::as_str) {
+        Some("initialize") => version.is_some_and(|version| {
+            message
+                .get_mut("params")
+                .and_then(Value::as_object_mut)
+                .map(|params| params.insert("protocolVersion".to_string(), json!(version)))
+                .is_some()
+        }),
+        Some("tools/call") => progress::request_progress(&mut message),
+        _ => false,
+    };
+    if !rewritten {
+        return frame.to_vec();
+    }
+    let mut rewritten = serde_json::to_vec(&message).unwrap_or_else(|_| frame.to_vec());
+    rewritten.push(b'\n');
+    rewritten
+}
+
+/// A stream frames to the server are written to, which rewrites them on the
+/// way: the first frame, the initialize request, asks for a given protocol
+/// version, and tool calls ask for progress. Frames are newline-delimited,
+/// so each is held until its newline arrives.
+pub struct RequestRewrite<W> {
+    /// The wrapped stream.
+    inner: W,
+    /// The version to ask for, until the first frame has been rewritten.
+    version: Option<String>,
+    /// The frame being written, until it's complete.
+    frame: Vec<u8>,
+    /// Rewritten data not yet written to the stream.
+    pending: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> RequestRewrite<W> {
+    /// Wraps a stream, asking for `version` if one is given.
+    pub fn new(inner: W, version: Option<String>) -> Self {
+        Self {
+            inner,
+            version,
+            frame: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Writes out any rewritten data still pending.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..written);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for RequestRewrite<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        this.frame.extend_from_slice(buf);
+        while let Some(end) = this.frame.iter().position(|b| *b == b'\n') {
+            let rest = this.frame.split_off(end + 1);
+            let frame = mem::replace(&mut this.frame, rest);
+            let rewritten = rewrite(&frame, this.version.take().as_deref());
+            this.pending.extend(rewritten);
+        }
+        // Whatever doesn't go out now is written on the next write or flush
+        if let Poll::Ready(Err(e)) = this.poll_pending(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// Reports the protocol version the server chose, warning if it isn't the
+/// one asked for.
+pub fn report(output: &Output, requested: Option<&str>, init: &InitializeResult) -> Result<()> {
+    let requested = requested.unwrap_or(LATEST_PROTOCOL_VERSION);
+    let negotiated = init.protocol_version.as_str();
+    // Versions are dates, so they compare as strings
+    if negotiated == requested {
+        output.status(format!("Protocol version {negotiated}"))?;
+    } else if negotiated < requested {
+        output.trace_warn(format!(
+            "The server downgraded the protocol version from {requested} to {negotiated}"
+        ))?;
+    } else {
+        output.trace_warn(format!(
+            "Asked for protocol version {requested}, but the server chose {negotiated}"
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_rewrite() {
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": { "protocolVersion": LATEST_PROTOCOL_VERSION, "capabilities": {} },
+        })
+        .to_string();
+        let ping = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n";
+
+        let mut writer = RequestRewrite::new(Vec::new(), Some("2024-11-05".to_string()));
+        let (head, tail) = initialize.split_at(10);
+        writer.write_all(head.as_bytes()).await.unwrap();
+        writer
+            .write_all(format!("{tail}\n{ping}").as_bytes())
+            .await
+            .unwrap();
+        writer.write_all(ping.as_bytes()).await.unwrap();
+        writer.flush().await.unwrap();
+
+        let written = String::from_utf8(writer.inner).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let sent: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(sent["params"]["protocolVersion"], "2024-11-05");
+        assert_eq!(sent["params"]["capabilities"], json!({}));
+        assert_eq!(format!("{}\n{}\n", lines[1], lines[2]), ping.repeat(2));
+
+        let initialize = format!("{initialize}\n");
+        let mut untouched = RequestRewrite::new(Vec::new(), None);
+        untouched.write_all(initialize.as_bytes()).await.unwrap();
+        assert_eq!(untouched.inner, initialize.as_bytes());
+        assert_eq!(
+            rewrite(ping.as_bytes(), Some("2024-11-05")),
+            ping.as_bytes()
+        );
+
+        let call = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": { "name": "echo", "arguments": {} },
+        });
+        let sent: Value =
+            serde_json::from_slice(&rewrite(call.to_string().as_bytes(), None)).unwrap();
+        assert_eq!(sent["params"]["_meta"]["progressToken"], 2);
+        assert_eq!(sent["params"]["name"], "echo");
+    }
+
+    #[test]
+    fn test_choose() {
+        assert_eq!(choose(Some("2024-11-05")), "2024-11-05");
+        assert_eq!(choose(Some("1999-01-01")), LATEST_PROTOCOL_VERSION);
+        assert_eq!(choose(None), LATEST_PROTOCOL_VERSION);
+        assert!(KNOWN_VERSIONS.contains(&LATEST_PROTOCOL_VERSION));
+    }
+}
@@ -1,12 +1,21 @@
 //! Call tool result display formatting.
 
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
 use tmcp::schema::{Annotations, CallToolResult, Content, ResourceContents, Role};
 
-use crate::{Result, output::Output};
+use crate::{
+    Error, Result,
+    output::{Output, highlight, image},
+};
 
 /// Display the result of calling a tool in either JSON or formatted text.
 pub fn call_tool_result(output: &Output, result: &CallToolResult) -> Result<()> {
-    if output.json {
+    if output.structured() {
         output.json_value(result)?;
     } else {
         output.h1("Tool Result")?;
@@ -48,14 +57,81 @@ pub fn call_tool_result(output: &Output, result: &CallToolResult) -> Result<()>
             let out = out.indent();
 
             let structured_str = serde_json::to_string_pretty(structured)?;
-            for line in structured_str.lines() {
-                out.text(line)?;
-            }
+            out.code(&structured_str, Some("json"))?;
         }
     }
     Ok(())
 }
 
+/// Writes each content block of a result to a file in `dir`, named for its
+/// position and MIME type, and prints where it went. Resource links have no
+/// content of their own and are skipped.
+pub fn save_content(output: &Output, result: &CallToolResult, dir: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for (index, content) in result.content.iter().enumerate() {
+        let Some((name, data)) = content_file(index + 1, content)? else {
+            continue;
+        };
+        let path = dir.join(name);
+        fs::write(&path, data)?;
+        output.trace_success(format!("Saved content {} to {}", index + 1, path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// The file name and decoded bytes for a content block.
+fn content_file(number: usize, content: &Content) -> Result<Option<(String, Vec<u8>)>> {
+    let decode = |data: &str| {
+        STANDARD
+            .decode(data)
+            .map_err(|e| Error::Format(format!("Content {number} is not valid base64: {e}")))
+    };
+    let (mime_type, data) = match content {
+        Content::Text(text) => (Some("text/plain"), text.text.clone().into_bytes()),
+        Content::Image(image) => (Some(image.mime_type.as_str()), decode(&image.data)?),
+        Content::Audio(audio) => (Some(audio.mime_type.as_str()), decode(&audio.data)?),
+        Content::Resource(resource) => match &resource.resource {
+            ResourceContents::Text(text) => (
+                Some(text.mime_type.as_deref().unwrap_or("text/plain")),
+                text.text.clone().into_bytes(),
+            ),
+            ResourceContents::Blob(blob) => (blob.mime_type.as_deref(), decode(&blob.blob)?),
+        },
+        Content::ResourceLink(_) => return Ok(None),
+    };
+    Ok(Some((
+        format!("content-{number}.{}", extension(mime_type)),
+        data,
+    )))
+}
+
+/// A file extension for a MIME type, falling back to `bin` for unknown types.
+fn extension(mime_type: Option<&str>) -> &'static str {
+    let essence = mime_type
+        .and_then(|mime_type| mime_type.split(';').next())
+        .map(|mime_type| mime_type.trim().to_ascii_lowercase());
+    match essence.as_deref() {
+        Some("text/plain") => "txt",
+        Some("text/markdown") => "md",
+        Some("text/html") => "html",
+        Some("text/csv") => "csv",
+        Some("application/json") => "json",
+        Some("application/pdf") => "pdf",
+        Some("image/png") => "png",
+        Some("image/jpeg") => "jpg",
+        Some("image/gif") => "gif",
+        Some("image/webp") => "webp",
+        Some("image/svg+xml") => "svg",
+        Some("audio/wav" | "audio/x-wav" | "audio/wave") => "wav",
+        Some("audio/mpeg") => "mp3",
+        Some("audio/ogg") => "ogg",
+        Some("audio/flac") => "flac",
+        _ => "bin",
+    }
+}
+
 /// Displays a single content item.
 fn display_content(output: &Output, content: &Content) -> Result<()> {
     match content {
@@ -63,10 +139,9 @@ fn display_content(output: &Output, content: &Content) -> Result<()> {
             output.kv("Type", "Text")?;
             let out = output.indent();
 
-            // Display text content, handling multi-line text properly
-            for line in text_content.text.lines() {
-                out.text(line)?;
-            }
+            // Display text content, highlighted if it looks like code
+            let text = &text_content.text;
+            out.code(text, highlight::language(None, text))?;
 
             // Show annotations if present
             if let Some(annotations) = &text_content.annotations {
@@ -76,8 +151,9 @@ fn display_content(output: &Output, content: &Content) -> Result<()> {
         Content::Image(image_content) => {
             output.kv("Type", "Image")?;
             let out = output.indent();
-            out.kv("MIME Type", &image_content.mime_type)?;
-            out.kv("Data Length", format!("{} bytes", image_content.data.len()))?;
+            if !image::show(&out, &image_content.mime_type, &image_content.data)? {
+                out.note("Use --save-content DIR to save the image")?;
+            }
 
             // Show annotations if present
             if let Some(annotations) = &image_content.annotations {
@@ -151,19 +227,27 @@ fn display_resource_contents(output: &Output, contents: &ResourceContents) -> Re
             out.text("Content:")?;
             let out = out.indent();
 
-            for line in text_contents.text.lines() {
-                out.text(line)?;
-            }
+            let text = &text_contents.text;
+            out.code(
+                text,
+                highlight::language(text_contents.mime_type.as_deref(), text),
+            )?;
         }
         ResourceContents::Blob(blob_contents) => {
             output.kv("Resource Type", "Blob")?;
             output.kv("URI", &blob_contents.uri)?;
 
-            if let Some(mime_type) = &blob_contents.mime_type {
-                output.kv("MIME Type", mime_type)?;
+            match blob_contents.mime_type.as_deref() {
+                Some(mime_type) if mime_type.starts_with("image/") => {
+                    image::show(output, mime_type, &blob_contents.blob)?;
+                }
+                mime_type => {
+                    if let Some(mime_type) = mime_type {
+                        output.kv("MIME Type", mime_type)?;
+                    }
+                    output.kv("Data Length", format!("{} bytes", blob_contents.blob.len()))?;
+                }
             }
-
-            output.kv("Data Length", format!("{} bytes", blob_contents.blob.len()))?;
         }
     }
     Ok(())
@@ -196,3 +280,26 @@ fn display_annotations(output: &Output, annotations: &Annotations) -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(extension(Some("image/png")), "png");
+        assert_eq!(extension(Some("Audio/WAV")), "wav");
+        assert_eq!(extension(Some("text/plain; charset=utf-8")), "txt");
+        assert_eq!(extension(Some("application/x-unknown")), "bin");
+        assert_eq!(extension(None), "bin");
+    }
+
+    #[test]
+    fn test_save_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = CallToolResult::new().with_text_content("Hello");
+        let paths = save_content(&Output::new(false, 80), &result, dir.path()).unwrap();
+        assert_eq!(paths, vec![dir.path().join("content-1.txt")]);
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "Hello");
+    }
+}
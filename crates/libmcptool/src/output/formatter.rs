@@ -1,6 +1,9 @@
 use std::fmt;
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, highlight},
+};
 
 /// Generic trait for formatting output data in both JSON and text modes
 pub trait OutputFormatter<T> {
@@ -20,7 +23,7 @@ pub trait OutputFormatter<T> {
     where
         T: serde::Serialize,
     {
-        if output.json {
+        if output.structured() {
             self.format_json(output, data)
         } else {
             self.format_text(output, data)
@@ -92,4 +95,14 @@ impl MetadataDisplay {
         output.text(text)?;
         Ok(())
     }
+
+    pub fn display_highlighted_content(
+        output: &Output,
+        text: &str,
+        mime_type: Option<&str>,
+    ) -> Result<()> {
+        output.text("Content:")?;
+        output.code(text, highlight::language(mime_type, text))?;
+        Ok(())
+    }
 }
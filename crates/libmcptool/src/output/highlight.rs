@@ -0,0 +1,85 @@
+//! Detecting the language of embedded text content, so it can be shown with
+//! syntax highlighting.
+
+/// The file extension syntect knows a language by, for text with the given
+/// MIME type. Without a telling MIME type the text itself is examined.
+pub fn language(mime_type: Option<&str>, text: &str) -> Option<&'static str> {
+    let essence = mime_type
+        .and_then(|mime_type| mime_type.split(';').next())
+        .map(|mime_type| mime_type.trim().to_ascii_lowercase());
+    let by_mime = match essence.as_deref() {
+        Some("application/json" | "application/ld+json" | "application/schema+json") => {
+            Some("json")
+        }
+        Some("text/markdown" | "text/x-markdown") => Some("md"),
+        Some("text/html") => Some("html"),
+        Some("application/xml" | "text/xml" | "image/svg+xml") => Some("xml"),
+        Some("application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml") => {
+            Some("yaml")
+        }
+        Some("application/toml" | "text/x-toml") => Some("toml"),
+        Some("text/css") => Some("css"),
+        Some("text/javascript" | "application/javascript") => Some("js"),
+        Some("text/x-python" | "text/x-script.python") => Some("py"),
+        Some("text/x-rust") => Some("rs"),
+        Some("text/x-shellscript" | "application/x-sh") => Some("sh"),
+        Some("application/sql") => Some("sql"),
+        _ => None,
+    };
+    by_mime.or_else(|| guess(text))
+}
+
+/// Guesses the language of text from its content: JSON that parses,
+/// scripts with a shebang, markup, and markdown with headings or fences.
+fn guess(text: &str) -> Option<&'static str> {
+    let trimmed = text.trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        return Some("json");
+    }
+    if let Some(shebang) = trimmed.strip_prefix("#!") {
+        let interpreter = shebang.lines().next().unwrap_or_default();
+        return if interpreter.contains("python") {
+            Some("py")
+        } else if interpreter.contains("node") {
+            Some("js")
+        } else {
+            Some("sh")
+        };
+    }
+    let lower = trimmed.get(..15).unwrap_or(trimmed).to_ascii_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return Some("html");
+    }
+    if lower.starts_with("<?xml") {
+        return Some("xml");
+    }
+    let markdown = text
+        .lines()
+        .any(|line| line.starts_with("```") || line.starts_with("# ") || line.starts_with("## "));
+    markdown.then_some("md")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language(Some("application/json"), "not json"), Some("json"));
+        assert_eq!(
+            language(Some("text/markdown; charset=utf-8"), ""),
+            Some("md")
+        );
+        assert_eq!(language(None, "  {\"a\": [1, 2]}"), Some("json"));
+        assert_eq!(language(None, "{not json"), None);
+        assert_eq!(
+            language(Some("text/plain"), "#!/usr/bin/env python3\n"),
+            Some("py")
+        );
+        assert_eq!(language(None, "<!DOCTYPE html><html></html>"), Some("html"));
+        assert_eq!(language(None, "Intro\n\n## Usage\n"), Some("md"));
+        assert_eq!(language(None, "Echo: hello"), None);
+    }
+}
@@ -0,0 +1,220 @@
+//! Showing image content: inline in terminals with a graphics protocol, and
+//! as a summary of its type, dimensions and size everywhere else.
+
+use std::env;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::{Result, output::Output};
+
+/// Kitty takes image data in chunks of at most this many base64 bytes.
+const KITTY_CHUNK: usize = 4096;
+
+/// A terminal graphics protocol images can be drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageProtocol {
+    /// iTerm2's inline images, also understood by WezTerm.
+    Iterm,
+    /// The kitty graphics protocol, also understood by Ghostty.
+    Kitty,
+}
+
+impl ImageProtocol {
+    /// The protocol the terminal we're running in understands, going by the
+    /// environment it sets, if any.
+    pub fn detect() -> Option<Self> {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        Self::from_env(&var("TERM"), &var("TERM_PROGRAM"), &var("LC_TERMINAL"))
+            .or_else(|| env::var_os("KITTY_WINDOW_ID").map(|_| Self::Kitty))
+    }
+
+    /// The protocol for a terminal identified by `TERM`, `TERM_PROGRAM` and
+    /// `LC_TERMINAL`.
+    fn from_env(term: &str, term_program: &str, lc_terminal: &str) -> Option<Self> {
+        if term == "xterm-kitty" || term == "xterm-ghostty" || term_program == "ghostty" {
+            Some(Self::Kitty)
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || lc_terminal == "iTerm2"
+        {
+            Some(Self::Iterm)
+        } else {
+            None
+        }
+    }
+
+    /// Whether images of this MIME type can be drawn. Kitty only decodes
+    /// PNG itself, iTerm2 handles any common format.
+    fn supports(self, mime_type: &str) -> bool {
+        match self {
+            Self::Iterm => mime_type.starts_with("image/"),
+            Self::Kitty => mime_type == "image/png",
+        }
+    }
+
+    /// The escape sequence that draws an image, given its base64 data.
+    fn escape(self, data: &str, size: usize) -> String {
+        match self {
+            Self::Iterm => {
+                format!("\x1b]1337;File=inline=1;size={size};preserveAspectRatio=1:{data}\x07")
+            }
+            Self::Kitty => {
+                let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+                let mut escape = String::new();
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(index + 1 < chunks.len());
+                    let chunk = String::from_utf8_lossy(chunk);
+                    if index == 0 {
+                        escape.push_str(&format!("\x1b_Gf=100,a=T,q=2,m={more};{chunk}\x1b\\"));
+                    } else {
+                        escape.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+                    }
+                }
+                escape
+            }
+        }
+    }
+}
+
+/// A big-endian u16 at `at`.
+fn be16(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u32::from(u16::from_be_bytes([bytes[0], bytes[1]])))
+}
+
+/// A little-endian u16 at `at`.
+fn le16(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u32::from(u16::from_le_bytes([bytes[0], bytes[1]])))
+}
+
+/// A little-endian 24-bit integer at `at`.
+fn le24(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+/// The width and height of a JPEG, from its first start-of-frame segment.
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    while at + 3 < data.len() {
+        if data[at] != 0xFF {
+            return None;
+        }
+        let marker = data[at + 1];
+        if marker == 0xFF {
+            // Fill byte before a marker
+            at += 1;
+            continue;
+        }
+        let is_frame = (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker);
+        if is_frame {
+            return Some((be16(data, at + 7)?, be16(data, at + 5)?));
+        }
+        at += 2 + usize::try_from(be16(data, at + 2)?).ok()?;
+    }
+    None
+}
+
+/// The width and height of a PNG, GIF, JPEG or WebP image, read from its
+/// header, or None for other formats or malformed data.
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") && data.get(12..16) == Some(&b"IHDR"[..]) {
+        let width = data.get(16..20)?;
+        let height = data.get(20..24)?;
+        Some((
+            u32::from_be_bytes(width.try_into().ok()?),
+            u32::from_be_bytes(height.try_into().ok()?),
+        ))
+    } else if data.starts_with(b"GIF8") {
+        Some((le16(data, 6)?, le16(data, 8)?))
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        jpeg_dimensions(data)
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(&b"WEBP"[..]) {
+        match data.get(12..16)? {
+            b"VP8 " => Some((le16(data, 26)? & 0x3FFF, le16(data, 28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            b"VP8X" => Some((le24(data, 24)? + 1, le24(data, 27)? + 1)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Shows base64 image data: its MIME type, dimensions and size, then the
+/// image itself if the terminal can draw it. Returns whether it was drawn.
+pub fn show(output: &Output, mime_type: &str, data: &str) -> Result<bool> {
+    output.kv("MIME Type", mime_type)?;
+    let Ok(bytes) = STANDARD.decode(data) else {
+        output.kv(
+            "Data Length",
+            format!("{} bytes (invalid base64)", data.len()),
+        )?;
+        return Ok(false);
+    };
+    if let Some((width, height)) = dimensions(&bytes) {
+        output.kv("Dimensions", format!("{width}x{height}"))?;
+    }
+    output.kv("Size", format!("{} bytes", bytes.len()))?;
+
+    match output.options().images {
+        Some(protocol) if protocol.supports(mime_type) => {
+            output.image(&protocol.escape(data, bytes.len()))?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        assert_eq!(dimensions(&png), Some((640, 480)));
+
+        let gif = b"GIF89a\x20\x00\x10\x00";
+        assert_eq!(dimensions(gif), Some((32, 16)));
+
+        // SOI, an APP0 segment, then a baseline frame header
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00,
+            0x64, 0x00, 0xC8,
+        ];
+        assert_eq!(dimensions(&jpeg), Some((200, 100)));
+
+        assert_eq!(dimensions(b"not an image"), None);
+        assert_eq!(dimensions(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn test_protocol() {
+        assert_eq!(
+            ImageProtocol::from_env("xterm-kitty", "", ""),
+            Some(ImageProtocol::Kitty)
+        );
+        assert_eq!(
+            ImageProtocol::from_env("xterm-256color", "iTerm.app", ""),
+            Some(ImageProtocol::Iterm)
+        );
+        assert_eq!(
+            ImageProtocol::from_env("xterm-256color", "Apple_Terminal", ""),
+            None
+        );
+
+        let data = "A".repeat(KITTY_CHUNK + 10);
+        let escape = ImageProtocol::Kitty.escape(&data, 0);
+        assert!(escape.starts_with("\x1b_Gf=100,a=T,q=2,m=1;"));
+        assert!(escape.ends_with(&format!("\x1b_Gm=0;{}\x1b\\", "A".repeat(10))));
+        assert!(!ImageProtocol::Kitty.supports("image/jpeg"));
+        assert!(ImageProtocol::Iterm.supports("image/jpeg"));
+    }
+}
@@ -1,10 +1,16 @@
 use tmcp::schema::InitializeResult;
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, OutputMode, markdown},
+};
 
-/// Display the initialization result in either JSON or formatted text
+/// Display the initialization result as JSON, Markdown or formatted text
 pub fn init_result(output: &Output, init_result: &InitializeResult) -> Result<()> {
-    if output.json {
+    if matches!(output.mode(), OutputMode::Markdown) {
+        return output.markdown(&markdown::init(init_result)?);
+    }
+    if output.structured() {
         // Output as JSON
         output.json_value(init_result)?;
     } else {
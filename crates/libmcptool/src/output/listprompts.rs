@@ -1,10 +1,19 @@
 use tmcp::schema::ListPromptsResult;
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, OutputMode, markdown, table},
+};
 
-/// Display the list of prompts in either JSON or formatted text
+/// Display the list of prompts as JSON, Markdown or formatted text
 pub fn list_prompts_result(output: &Output, prompts_result: &ListPromptsResult) -> Result<()> {
-    if output.json {
+    if matches!(output.mode(), OutputMode::Markdown) {
+        return output.markdown(&markdown::prompts(prompts_result));
+    }
+    if matches!(output.mode(), OutputMode::Table { .. }) {
+        return table::list(output, &prompts_result.prompts, &["name", "description"]);
+    }
+    if output.structured() {
         // Output as JSON
         output.json_value(prompts_result)?;
     } else {
@@ -1,13 +1,23 @@
 use tmcp::schema::ListResourcesResult;
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, OutputMode, table},
+};
 
-/// Display the list of resources in either JSON or formatted text
+/// Display the list of resources as JSON, a table or formatted text
 pub fn list_resources_result(
     output: &Output,
     resources_result: &ListResourcesResult,
 ) -> Result<()> {
-    if output.json {
+    if matches!(output.mode(), OutputMode::Table { .. }) {
+        return table::list(
+            output,
+            &resources_result.resources,
+            &["uri", "name", "mimeType"],
+        );
+    }
+    if output.structured() {
         // Output as JSON
         output.json_value(resources_result)?;
     } else {
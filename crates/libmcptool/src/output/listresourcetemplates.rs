@@ -1,13 +1,23 @@
 use tmcp::schema::ListResourceTemplatesResult;
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, OutputMode, table},
+};
 
-/// Display the list of resource templates in either JSON or formatted text
+/// Display the list of resource templates as JSON, a table or formatted text
 pub fn list_resource_templates_result(
     output: &Output,
     templates_result: &ListResourceTemplatesResult,
 ) -> Result<()> {
-    if output.json {
+    if matches!(output.mode(), OutputMode::Table { .. }) {
+        return table::list(
+            output,
+            &templates_result.resource_templates,
+            &["name", "uriTemplate", "mimeType"],
+        );
+    }
+    if output.structured() {
         // Output as JSON
         output.json_value(templates_result)?;
     } else {
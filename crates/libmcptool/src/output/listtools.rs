@@ -1,6 +1,9 @@
 use tmcp::schema::{ListToolsResult, ToolSchema};
 
-use crate::{Result, output::Output};
+use crate::{
+    Result,
+    output::{Output, OutputMode, markdown, table},
+};
 
 /// Formats a tool schema.
 fn toolschema(output: &Output, schema: &ToolSchema) -> Result<()> {
@@ -70,9 +73,15 @@ fn toolschema(output: &Output, schema: &ToolSchema) -> Result<()> {
     Ok(())
 }
 
-/// Display the list of tools in either JSON or formatted text
+/// Display the list of tools as JSON, Markdown or formatted text
 pub fn list_tools_result(output: &Output, tools_result: &ListToolsResult) -> Result<()> {
-    if output.json {
+    if matches!(output.mode(), OutputMode::Markdown) {
+        return output.markdown(&markdown::tools(tools_result)?);
+    }
+    if matches!(output.mode(), OutputMode::Table { .. }) {
+        return table::list(output, &tools_result.tools, &["name", "description"]);
+    }
+    if output.structured() {
         output.json_value(tools_result)?;
     } else if tools_result.tools.is_empty() {
         output.text("No tools.")?;
@@ -0,0 +1,241 @@
+//! Markdown documents describing a server, for pasting into issues and docs.
+
+use serde_json::Value;
+use tmcp::schema::{InitializeResult, ListPromptsResult, ListToolsResult};
+
+use crate::Result;
+
+/// Escapes text for use in a table cell, where pipes end the cell and
+/// newlines end the row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Appends a table with the given header row.
+pub(crate) fn table(doc: &mut String, header: &[&str], rows: &[Vec<String>]) {
+    doc.push_str(&format!("| {} |\n", header.join(" | ")));
+    doc.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| cell(c)).collect();
+        doc.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    doc.push('\n');
+}
+
+/// Appends a value as a fenced JSON code block.
+pub(crate) fn json_block(doc: &mut String, value: &impl serde::Serialize) -> Result<()> {
+    doc.push_str("```json\n");
+    doc.push_str(&serde_json::to_string_pretty(value)?);
+    doc.push_str("\n```\n\n");
+    Ok(())
+}
+
+/// Appends a paragraph of text, if there is any.
+pub(crate) fn paragraph(doc: &mut String, text: Option<&str>) {
+    if let Some(text) = text.map(str::trim).filter(|text| !text.is_empty()) {
+        doc.push_str(text);
+        doc.push_str("\n\n");
+    }
+}
+
+/// Appends a table of a tool's annotations under `heading`, if it has any.
+pub(crate) fn annotations(doc: &mut String, heading: &str, annotations: &Value) {
+    if let Value::Object(fields) = annotations
+        && !fields.is_empty()
+    {
+        doc.push_str(heading);
+        doc.push_str("\n\n");
+        let rows: Vec<Vec<String>> = fields
+            .iter()
+            .map(|(key, value)| {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                vec![format!("`{key}`"), value]
+            })
+            .collect();
+        table(doc, &["Annotation", "Value"], &rows);
+    }
+}
+
+/// A document with a section for each tool, giving its description,
+/// annotations and schemas.
+pub fn tools(result: &ListToolsResult) -> Result<String> {
+    let mut doc = String::from("# Tools\n\n");
+    if result.tools.is_empty() {
+        doc.push_str("No tools.\n");
+        return Ok(doc);
+    }
+    for tool in &result.tools {
+        doc.push_str(&format!("## {}\n\n", tool.name));
+        paragraph(&mut doc, tool.description.as_deref());
+
+        if let Some(tool_annotations) = &tool.annotations {
+            annotations(
+                &mut doc,
+                "### Annotations",
+                &serde_json::to_value(tool_annotations)?,
+            );
+        }
+
+        doc.push_str("### Input\n\n");
+        json_block(&mut doc, &tool.input_schema)?;
+        if let Some(output_schema) = &tool.output_schema {
+            doc.push_str("### Output\n\n");
+            json_block(&mut doc, output_schema)?;
+        }
+    }
+    Ok(doc)
+}
+
+/// A document with a section for each prompt and a table of its arguments.
+pub fn prompts(result: &ListPromptsResult) -> String {
+    let mut doc = String::from("# Prompts\n\n");
+    if result.prompts.is_empty() {
+        doc.push_str("No prompts.\n");
+        return doc;
+    }
+    for prompt in &result.prompts {
+        doc.push_str(&format!("## {}\n\n", prompt.name));
+        paragraph(&mut doc, prompt.description.as_deref());
+        if let Some(arguments) = &prompt.arguments
+            && !arguments.is_empty()
+        {
+            doc.push_str("### Arguments\n\n");
+            let rows: Vec<Vec<String>> = arguments
+                .iter()
+                .map(|arg| {
+                    vec![
+                        format!("`{}`", arg.name),
+                        if arg.required == Some(true) {
+                            "yes"
+                        } else {
+                            "no"
+                        }
+                        .to_string(),
+                        arg.description.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            table(&mut doc, &["Name", "Required", "Description"], &rows);
+        }
+    }
+    doc
+}
+
+/// A document describing the server, with a table of its capabilities.
+pub fn init(result: &InitializeResult) -> Result<String> {
+    let info = &result.server_info;
+    let mut doc = format!("# {} ({})\n\n", info.name, info.version);
+    let mut rows = vec![vec![
+        "MCP Protocol Version".to_string(),
+        result.protocol_version.clone(),
+    ]];
+    if let Some(title) = &info.title {
+        rows.push(vec!["Title".to_string(), title.clone()]);
+    }
+    table(&mut doc, &["Property", "Value"], &rows);
+
+    doc.push_str("## Capabilities\n\n");
+    let capabilities = &result.capabilities;
+    let list_changed = |on: Option<bool>| {
+        if on.unwrap_or(false) {
+            vec!["list changed"]
+        } else {
+            Vec::new()
+        }
+    };
+    let supported = |name: &str, present: bool, details: Vec<&str>| {
+        vec![
+            name.to_string(),
+            if present { "yes" } else { "no" }.to_string(),
+            details.join(", "),
+        ]
+    };
+    let resource_details = capabilities.resources.as_ref().map_or_else(Vec::new, |r| {
+        let mut details = list_changed(r.list_changed);
+        if r.subscribe.unwrap_or(false) {
+            details.push("subscribe");
+        }
+        details
+    });
+    let rows = vec![
+        supported(
+            "tools",
+            capabilities.tools.is_some(),
+            list_changed(capabilities.tools.as_ref().and_then(|t| t.list_changed)),
+        ),
+        supported(
+            "resources",
+            capabilities.resources.is_some(),
+            resource_details,
+        ),
+        supported(
+            "prompts",
+            capabilities.prompts.is_some(),
+            list_changed(capabilities.prompts.as_ref().and_then(|p| p.list_changed)),
+        ),
+        supported("logging", capabilities.logging.is_some(), Vec::new()),
+        supported(
+            "completions",
+            capabilities.completions.is_some(),
+            Vec::new(),
+        ),
+    ];
+    table(&mut doc, &["Capability", "Supported", "Details"], &rows);
+
+    if let Some(experimental) = &capabilities.experimental
+        && !experimental.is_empty()
+    {
+        doc.push_str("### Experimental Features\n\n");
+        json_block(&mut doc, experimental)?;
+    }
+    if let Some(instructions) = &result.instructions {
+        doc.push_str("## Instructions\n\n");
+        paragraph(&mut doc, Some(instructions));
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_tools() {
+        let result: ListToolsResult = serde_json::from_value(json!({
+            "tools": [{
+                "name": "echo",
+                "description": "Echoes | text",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {"message": {"type": "string"}},
+                    "required": ["message"]
+                },
+                "annotations": {"readOnlyHint": true}
+            }]
+        }))
+        .unwrap();
+        let doc = tools(&result).unwrap();
+        assert!(doc.starts_with("# Tools\n\n## echo\n\nEchoes | text\n\n"));
+        assert!(doc.contains("| `readOnlyHint` | true |"));
+        assert!(doc.contains("### Input\n\n```json\n{"));
+        assert!(!doc.contains("### Output"));
+    }
+
+    #[test]
+    fn test_prompts() {
+        let result: ListPromptsResult = serde_json::from_value(json!({
+            "prompts": [{
+                "name": "review",
+                "arguments": [{"name": "code", "description": "The code\nto review", "required": true}]
+            }]
+        }))
+        .unwrap();
+        let doc = prompts(&result);
+        assert!(doc.contains("| Name | Required | Description |\n| --- | --- | --- |\n"));
+        assert!(doc.contains("| `code` | yes | The code to review |"));
+    }
+}
@@ -8,6 +8,10 @@ pub mod complete;
 pub mod formatter;
 /// Prompt result display.
 pub mod getprompt;
+/// Language detection for highlighting embedded content.
+pub mod highlight;
+/// Image content display.
+pub mod image;
 /// Initialization result display.
 pub mod initresult;
 /// Prompt list display.
@@ -18,24 +22,40 @@ pub mod listresources;
 pub mod listresourcetemplates;
 /// Tool list display.
 pub mod listtools;
+/// Markdown documents for listings and server info.
+pub mod markdown;
 /// Resource read result display.
 pub mod readresource;
+/// Tables of list items.
+pub mod table;
+/// Custom templates for rendering results.
+pub mod template;
+/// Color themes.
+pub mod theme;
 
 use std::{
+    ffi::OsStr,
     fmt,
+    fs::File,
     io::{self, Write},
+    path::Path,
     result,
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
 };
 
+use serde_json::Value;
 use syntect::{
     easy::HighlightLines,
     highlighting::{Style, ThemeSet},
     parsing::SyntaxSet,
     util::{LinesWithEndings, as_24_bit_terminal_escaped},
 };
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use termcolor::{Color, ColorChoice, ColorSpec, NoColor, StandardStream, WriteColor};
+use terminal_size::{Width, terminal_size};
 use textwrap::{Options, wrap};
 use tracing::{Event, Level, Subscriber, field};
 use tracing_subscriber::{
@@ -45,7 +65,15 @@ use tracing_subscriber::{
     util::SubscriberInitExt,
 };
 
-use crate::Result;
+use crate::{
+    Error, Result,
+    filter::Filter,
+    output::{
+        image::ImageProtocol,
+        template::OutputTemplate,
+        theme::{Palette, Theme},
+    },
+};
 
 /// Log level configuration for the application
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -96,7 +124,188 @@ impl FromStr for LogLevel {
     }
 }
 
-/// Solarized Dark color scheme
+/// Where status, progress and trace lines are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatusStream {
+    /// Standard error, keeping standard output for results.
+    #[default]
+    Stderr,
+    /// Standard output, mixed with results.
+    Stdout,
+}
+
+/// How structured results are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataFormat {
+    /// Pretty-printed JSON.
+    #[default]
+    Json,
+    /// YAML, which is easier to skim for deeply nested values.
+    Yaml,
+    /// JSON Lines: one compact JSON value per line, with lists split so that
+    /// each item is its own line.
+    Jsonl,
+}
+
+impl DataFormat {
+    /// The format a file's extension calls for: YAML for `.yaml` and `.yml`,
+    /// JSON Lines for `.jsonl` and `.ndjson`, and JSON otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("jsonl" | "ndjson") => Self::Jsonl,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// How results are shown. The modes exclude each other.
+#[derive(Debug, Clone, Default)]
+pub enum OutputMode {
+    /// Formatted terminal text.
+    #[default]
+    Text,
+    /// Listings and server info as Markdown documents, other results as text.
+    Markdown,
+    /// Lists as tables, one row per item, other results as text.
+    Table {
+        /// The fields shown as columns, or empty for each list's defaults.
+        columns: Vec<String>,
+    },
+    /// Structured results in a data format.
+    Data(DataFormat),
+    /// Structured results rendered with a template.
+    Template(OutputTemplate),
+}
+
+/// How results are written, chosen once from the command line.
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    /// How results are shown.
+    pub mode: OutputMode,
+    /// A filter applied to structured results before they are written.
+    pub filter: Option<Filter>,
+    /// Whether structured results are written with object keys sorted, so
+    /// successive runs can be diffed.
+    pub stable: bool,
+    /// Whether to syntax highlight JSON, Markdown and code embedded in
+    /// results when color is on.
+    pub highlight: bool,
+    /// The graphics protocol images are drawn inline with, or None to only
+    /// describe them.
+    pub images: Option<ImageProtocol>,
+    /// The most bytes of embedded text content shown in text mode, or None
+    /// to show it all.
+    pub max_content_bytes: Option<usize>,
+    /// The color theme.
+    pub theme: Theme,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            mode: OutputMode::Text,
+            filter: None,
+            stable: false,
+            highlight: true,
+            images: None,
+            max_content_bytes: None,
+            theme: Theme::Dark,
+        }
+    }
+}
+
+/// The width text is wrapped to when it isn't going to a terminal.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Fields of a result that hold its list of items, such as the tools in a
+/// tool listing or the content blocks of a call result.
+const ITEM_FIELDS: [&str; 7] = [
+    "tools",
+    "resources",
+    "resourceTemplates",
+    "prompts",
+    "content",
+    "contents",
+    "messages",
+];
+
+/// Splits a value into JSON Lines records. An array gives one record per
+/// element. An object with a list of items gives one record per item,
+/// followed by a record with its remaining fields if there are any. Anything
+/// else is a single record.
+fn records(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Object(mut fields) => {
+            let Some(key) = ITEM_FIELDS
+                .iter()
+                .find(|key| fields.get(**key).is_some_and(Value::is_array))
+            else {
+                return vec![Value::Object(fields)];
+            };
+            let Some(Value::Array(mut items)) = fields.remove(*key) else {
+                return vec![Value::Object(fields)];
+            };
+            if !fields.is_empty() {
+                items.push(Value::Object(fields));
+            }
+            items
+        }
+        other => vec![other],
+    }
+}
+
+/// Sorts the keys of every object in a value, so it is written the same way
+/// whatever order the server sent its fields in.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<(String, Value)> = fields.into_iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// Cuts text to at most `max` bytes, at a character boundary, returning the
+/// text kept and how many bytes were left out.
+fn truncate_bytes(text: &str, max: usize) -> (&str, usize) {
+    if text.len() <= max {
+        return (text, 0);
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], text.len() - end)
+}
+
+/// Highlights text as the language with the given file extension, giving
+/// each line with its terminal escapes and line ending, or None if the
+/// language isn't known.
+fn highlight_lines(text: &str, extension: &str, theme: Theme) -> Option<Vec<String>> {
+    let ps = SyntaxSet::load_defaults_newlines();
+    let ts = ThemeSet::load_defaults();
+    let syntax = ps.find_syntax_by_extension(extension)?;
+    let theme = &ts.themes[theme.syntax_theme()];
+    let mut h = HighlightLines::new(syntax, theme);
+    LinesWithEndings::from(text)
+        .map(|line| {
+            let ranges: Vec<(Style, &str)> = h.highlight_line(line, &ps).ok()?;
+            Some(as_24_bit_terminal_escaped(&ranges[..], false))
+        })
+        .collect()
+}
+
+/// Solarized color scheme
 #[allow(dead_code)]
 struct SolarizedDark;
 
@@ -130,24 +339,41 @@ impl SolarizedDark {
 /// This struct provides a unified interface for outputting text to the console,
 /// with support for both human-readable formatted output and machine-readable JSON output.
 /// It uses the Solarized Dark color scheme for styled terminal output and can switch
-/// between colored text mode and JSON mode based on the `json` flag.
+/// between colored text and structured data based on its [`OutputMode`].
 ///
 /// The struct is `Clone` and thread-safe, allowing it to be shared across different
 /// parts of the application.
 #[derive(Clone)]
 pub struct Output {
-    /// The standard output stream for writing to the terminal.
-    stdout: Arc<Mutex<StandardStream>>,
-    /// Whether to output in JSON format.
-    pub json: bool,
-    /// Whether to suppress non-essential output.
+    /// The stream output is written to: the terminal, or a file sink.
+    stdout: Arc<Mutex<Box<dyn WriteColor + Send>>>,
+    /// The stream status, progress and trace lines are written to: stderr
+    /// unless chosen otherwise, so they don't mix with results.
+    status_stream: Arc<Mutex<Box<dyn WriteColor + Send>>>,
+    /// Which stream `status_stream` is.
+    status_target: StatusStream,
+    /// How results are written.
+    options: OutputOptions,
+    /// Whether to show only results, without status lines, progress,
+    /// warnings or logs.
     pub quiet: bool,
+    /// Where structured results are written instead of the terminal, if
+    /// anywhere.
+    results: Option<Arc<Mutex<Box<dyn WriteColor + Send>>>>,
     /// Whether to use colored output.
     color: bool,
-    /// The terminal width for text wrapping.
-    width: usize,
+    /// The colors of the theme.
+    palette: Palette,
+    /// The width text is wrapped to, shared between copies so a resize is
+    /// seen everywhere.
+    width: Arc<AtomicUsize>,
+    /// Whether the width follows the terminal's, rather than being fixed.
+    follow_terminal: bool,
     /// The current indentation level.
     indent: usize,
+    /// Whether a progress line is drawn and needs clearing before further
+    /// output.
+    progress_shown: Arc<AtomicBool>,
 }
 
 impl Output {
@@ -159,51 +385,266 @@ impl Output {
         };
 
         Self {
-            stdout: Arc::new(Mutex::new(StandardStream::stdout(color_choice))),
-            json: false,
+            stdout: Arc::new(Mutex::new(Box::new(StandardStream::stdout(color_choice)))),
+            status_stream: Arc::new(Mutex::new(Box::new(StandardStream::stderr(color_choice)))),
+            status_target: StatusStream::Stderr,
+            options: OutputOptions::default(),
             quiet: false,
+            results: None,
             color,
-            width,
+            palette: Palette::DARK,
+            width: Arc::new(AtomicUsize::new(width)),
+            follow_terminal: false,
             indent: 0,
+            progress_shown: Arc::new(AtomicBool::new(false)),
         }
     }
 
     /// Output JSON with syntax highlighting if color is enabled
     fn output_json(&self, json_str: &str) -> io::Result<()> {
-        if self.color {
-            // Load syntax highlighting assets
-            let ps = SyntaxSet::load_defaults_newlines();
-            let ts = ThemeSet::load_defaults();
+        self.output_highlighted(json_str, "json")
+    }
 
-            let syntax = ps.find_syntax_by_extension("json").unwrap();
-            let theme = &ts.themes["Solarized (dark)"];
-            let mut h = HighlightLines::new(syntax, theme);
+    /// Output structured text, highlighted as the language with the given
+    /// file extension if color is enabled.
+    fn output_highlighted(&self, text: &str, extension: &str) -> io::Result<()> {
+        if self.results.is_some() {
+            self.write_result(text)
+        } else if self.color
+            && let Some(lines) = highlight_lines(text, extension, self.options.theme)
+        {
+            self.clear_progress()?;
             let mut stdout = self.stdout.lock().unwrap();
-            for line in LinesWithEndings::from(json_str) {
-                let ranges: Vec<(Style, &str)> = h.highlight_line(line, &ps).unwrap();
-                let escaped = as_24_bit_terminal_escaped(&ranges[..], false);
-                write!(stdout, "{escaped}")?;
+            for line in lines {
+                write!(stdout, "{line}")?;
             }
             stdout.reset()?;
             stdout.flush()
         } else {
-            self.raw(json_str)
+            self.raw(text)
         }
     }
 
-    /// Output a JSON value with syntax highlighting if appropriate
-    pub fn json_value<T: serde::Serialize>(&self, value: &T) -> Result<()> {
-        if self.quiet {
+    /// Output text content embedded in a result, such as a tool's text or a
+    /// resource's contents, at the current indent. If the language is known
+    /// and highlighting is on it is highlighted, otherwise it is shown as
+    /// plain text.
+    /// Text longer than the content limit is cut short, with a note saying
+    /// how much was left out.
+    pub fn code(&self, text: &str, language: Option<&str>) -> io::Result<()> {
+        if self.text_hidden() {
+            return Ok(());
+        }
+        let (text, omitted) = match self.options.max_content_bytes {
+            Some(max) => truncate_bytes(text, max),
+            None => (text, 0),
+        };
+        self.write_code(text, language)?;
+        if omitted > 0 {
+            self.note(format!(
+                "… truncated, {omitted} bytes omitted (use --full to show everything)"
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Writes text for `code`, highlighted if possible.
+    fn write_code(&self, text: &str, language: Option<&str>) -> io::Result<()> {
+        let highlighted = language
+            .filter(|_| self.color && self.options.highlight)
+            .and_then(|language| highlight_lines(text, language, self.options.theme));
+        let Some(lines) = highlighted else {
+            for line in text.lines() {
+                self.text(line)?;
+            }
+            return Ok(());
+        };
+        self.clear_progress()?;
+        let mut stdout = self.stdout.lock().unwrap();
+        let indent = " ".repeat(self.indent);
+        for line in lines {
+            write!(stdout, "{indent}{line}")?;
+        }
+        stdout.reset()?;
+        if !text.ends_with('\n') {
+            writeln!(stdout)?;
+        }
+        stdout.flush()
+    }
+
+    /// Output an escape sequence that draws an image, on a line of its own
+    /// at the current indent.
+    pub fn image(&self, escape: &str) -> io::Result<()> {
+        if self.text_hidden() {
             return Ok(());
         }
-        let json_str = serde_json::to_string_pretty(value)?;
-        self.output_json(&json_str)?;
+        self.clear_progress()?;
+        let mut stdout = self.stdout.lock().unwrap();
+        writeln!(stdout, "{}{escape}", " ".repeat(self.indent))?;
+        stdout.flush()
+    }
+
+    /// Output a structured value as JSON, or YAML or JSON Lines if that
+    /// format is chosen, with syntax highlighting if appropriate. If there is
+    /// a filter, each value it produces is written instead.
+    pub fn json_value<T: serde::Serialize>(&self, value: &T) -> Result<()> {
+        if self.options.filter.is_none() && !self.options.stable {
+            return self.write_value(value);
+        }
+        for value in self.prepare(value)? {
+            self.write_value(&value)?;
+        }
+        Ok(())
+    }
+
+    /// The values to write for a result: the values the filter produces from
+    /// it, or the result itself, with keys sorted in stable mode.
+    fn prepare<T: serde::Serialize>(&self, value: &T) -> Result<Vec<Value>> {
+        let value = serde_json::to_value(value)?;
+        let values = match &self.options.filter {
+            Some(filter) => filter.apply(&value)?,
+            None => vec![value],
+        };
+        Ok(if self.options.stable {
+            values.into_iter().map(sort_keys).collect()
+        } else {
+            values
+        })
+    }
+
+    /// Write a structured value in the chosen format, or rendered with the
+    /// template in template mode. Outside the structured modes it's written
+    /// as JSON.
+    fn write_value<T: serde::Serialize>(&self, value: &T) -> Result<()> {
+        let format = match &self.options.mode {
+            OutputMode::Template(template) => {
+                return self.write_unwrapped(&template.render(&serde_json::to_value(value)?)?);
+            }
+            OutputMode::Data(format) => *format,
+            _ => DataFormat::Json,
+        };
+        match format {
+            DataFormat::Json => {
+                let json_str = serde_json::to_string_pretty(value)?;
+                self.output_json(&json_str)?;
+            }
+            DataFormat::Yaml => {
+                let yaml = serde_yaml::to_string(value)
+                    .map_err(|e| Error::Internal(format!("Failed to write YAML: {e}")))?;
+                self.output_highlighted(yaml.trim_end(), "yaml")?;
+            }
+            DataFormat::Jsonl => {
+                for record in records(serde_json::to_value(value)?) {
+                    self.write_line(&record)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Output a JSON value on a single line, as a record in a stream of JSON
+    /// lines. Long lines are not wrapped. If there is a filter, each value it
+    /// produces is written as a line instead.
+    pub fn json_line<T: serde::Serialize>(&self, value: &T) -> Result<()> {
+        if self.options.filter.is_none() && !self.options.stable {
+            return self.write_line(value);
+        }
+        for value in self.prepare(value)? {
+            self.write_line(&value)?;
+        }
+        Ok(())
+    }
+
+    /// Write a JSON value on a single line.
+    fn write_line<T: serde::Serialize>(&self, value: &T) -> Result<()> {
+        self.write_result(&serde_json::to_string(value)?)?;
         Ok(())
     }
 
-    /// Set JSON output mode.
-    pub fn with_json(mut self, json: bool) -> Self {
-        self.json = json;
+    /// Write result text as is, to the results file if there is one and the
+    /// terminal otherwise.
+    fn write_result(&self, text: &str) -> io::Result<()> {
+        if self.results.is_none() {
+            self.clear_progress()?;
+        }
+        let stream = self.results.as_ref().unwrap_or(&self.stdout);
+        let mut stream = stream.lock().unwrap();
+        writeln!(stream, "{}", text.trim_end())?;
+        stream.flush()
+    }
+
+    /// Output bytes exactly as they are, such as a resource's decoded
+    /// contents, to the results file if there is one or stdout.
+    pub fn bytes(&self, data: &[u8]) -> io::Result<()> {
+        if self.results.is_none() {
+            self.clear_progress()?;
+        }
+        let stream = self.results.as_ref().unwrap_or(&self.stdout);
+        let mut stream = stream.lock().unwrap();
+        stream.write_all(data)?;
+        stream.flush()
+    }
+
+    /// Whether status text is hidden: always in quiet mode, and in the
+    /// structured modes unless results go to a file, so it doesn't mix with
+    /// them.
+    fn status_hidden(&self) -> bool {
+        self.quiet || self.text_hidden()
+    }
+
+    /// Whether results shown as text are hidden: in the structured modes,
+    /// where results are written as structured data instead, unless they go
+    /// to a file.
+    fn text_hidden(&self) -> bool {
+        self.structured() && self.results.is_none()
+    }
+
+    /// Whether results are written as structured data, in a data format or
+    /// through a template, rather than shown as text.
+    pub fn structured(&self) -> bool {
+        matches!(
+            self.options.mode,
+            OutputMode::Data(_) | OutputMode::Template(_)
+        )
+    }
+
+    /// How results are shown.
+    pub fn mode(&self) -> &OutputMode {
+        &self.options.mode
+    }
+
+    /// How results are written.
+    pub fn options(&self) -> &OutputOptions {
+        &self.options
+    }
+
+    /// Output a Markdown document as is, without wrapping or indentation.
+    pub fn markdown(&self, document: &str) -> Result<()> {
+        self.write_unwrapped(document)
+    }
+
+    /// Write a document as is, ending with a single newline.
+    fn write_unwrapped(&self, document: &str) -> Result<()> {
+        self.write_result(document)?;
+        Ok(())
+    }
+
+    /// Set how results are written. The `none` theme turns color off.
+    pub fn with_options(mut self, options: OutputOptions) -> Self {
+        let palette = options.theme.palette();
+        self.options = options;
+        match palette {
+            Some(palette) => {
+                self.palette = palette;
+                self
+            }
+            None => self.with_color(false),
+        }
+    }
+
+    /// Switch how results are shown, keeping the other options.
+    pub fn with_mode(mut self, mode: OutputMode) -> Self {
+        self.options.mode = mode;
         self
     }
 
@@ -222,12 +663,103 @@ impl Output {
         Ok(self)
     }
 
-    /// Set quiet output mode.
+    /// Set quiet output mode, which shows only results.
     pub fn with_quiet(mut self, quiet: bool) -> Self {
         self.quiet = quiet;
         self
     }
 
+    /// Return a copy of this Output that writes to the terminal with color
+    /// turned on or off.
+    pub fn with_color(mut self, color: bool) -> Self {
+        let color_choice = if color {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        };
+        self.stdout = Arc::new(Mutex::new(Box::new(StandardStream::stdout(color_choice))));
+        self.color = color;
+        let target = self.status_target;
+        self.with_status_stream(target)
+    }
+
+    /// Set a fixed width text is wrapped to.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Arc::new(AtomicUsize::new(width));
+        self.follow_terminal = false;
+        self
+    }
+
+    /// The color theme.
+    pub fn theme(&self) -> Theme {
+        self.options.theme
+    }
+
+    /// Whether output is colored.
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    /// Wrap text to the terminal's width, keeping the current width when
+    /// output isn't a terminal. `refresh_width` picks up later resizes.
+    pub fn with_terminal_width(mut self) -> Self {
+        self.follow_terminal = true;
+        self.refresh_width();
+        self
+    }
+
+    /// Re-read the terminal's width after a resize, if the width follows it.
+    pub fn refresh_width(&self) {
+        if !self.follow_terminal {
+            return;
+        }
+        if let Some((Width(width), _)) = terminal_size() {
+            self.width.store(usize::from(width), Ordering::Relaxed);
+        }
+    }
+
+    /// The width text is wrapped to.
+    pub fn width(&self) -> usize {
+        self.width.load(Ordering::Relaxed)
+    }
+
+    /// Return a copy of this Output that writes to `sink` instead of the
+    /// terminal, without color.
+    pub fn with_sink(&self, sink: impl Write + Send + 'static) -> Self {
+        let mut output = self.clone();
+        output.stdout = Arc::new(Mutex::new(Box::new(NoColor::new(sink))));
+        output.results = None;
+        output.color = false;
+        output
+    }
+
+    /// Choose where status, progress and trace lines are written.
+    pub fn with_status_stream(mut self, stream: StatusStream) -> Self {
+        self.status_target = stream;
+        self.status_stream = match stream {
+            StatusStream::Stdout => self.stdout.clone(),
+            StatusStream::Stderr => {
+                let color_choice = if self.color {
+                    ColorChoice::Always
+                } else {
+                    ColorChoice::Never
+                };
+                Arc::new(Mutex::new(Box::new(StandardStream::stderr(color_choice))))
+            }
+        };
+        self
+    }
+
+    /// Return a copy of this Output that writes structured results to a file,
+    /// replacing its contents, while status text stays on the terminal.
+    pub fn with_results_file(&self, path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| Error::Other(format!("Failed to create {}: {e}", path.display())))?;
+        let mut output = self.clone();
+        output.results = Some(Arc::new(Mutex::new(Box::new(NoColor::new(file)))));
+        Ok(output)
+    }
+
     /// Return a copy of this Output with indent incremented by 4 spaces.
     pub fn indent(&self) -> Self {
         let mut output = self.clone();
@@ -264,9 +796,25 @@ impl Output {
 
     /// Helper method to write a line with proper indentation, text wrapping, and color.
     fn write_block_with_color(&self, message: &str, color_spec: &ColorSpec) -> io::Result<()> {
-        let mut stdout = self.stdout.lock().unwrap();
+        self.write_block_to(&self.stdout, message, color_spec)
+    }
+
+    /// Writes a block like `write_block_with_color`, to the status stream.
+    fn write_status_block(&self, message: &str, color_spec: &ColorSpec) -> io::Result<()> {
+        self.write_block_to(&self.status_stream, message, color_spec)
+    }
+
+    /// Writes an indented, wrapped and colored block to a stream.
+    fn write_block_to(
+        &self,
+        stream: &Mutex<Box<dyn WriteColor + Send>>,
+        message: &str,
+        color_spec: &ColorSpec,
+    ) -> io::Result<()> {
+        self.clear_progress()?;
+        let mut stdout = stream.lock().unwrap();
         let indent_str = " ".repeat(self.indent);
-        let available_width = self.width.saturating_sub(self.indent);
+        let available_width = self.width().saturating_sub(self.indent);
 
         let wrapped_lines = self.wrap_text(message, available_width, "", "");
         let has_color = color_spec != &ColorSpec::new();
@@ -287,28 +835,24 @@ impl Output {
 
     /// Raw output that is not affected by output settings
     fn raw(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.quiet {
-            return Ok(());
-        }
         let message = message.into();
         self.write_block(&message)
     }
 
     pub fn text(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.text_hidden() {
             return Ok(());
         }
 
         let message = message.into();
 
-        // Use SOLARIZED_BASE0 for regular text
-        let color_spec = ColorSpec::new().set_fg(Some(SolarizedDark::BASE0)).clone();
+        let color_spec = ColorSpec::new().set_fg(Some(self.palette.text)).clone();
 
         self.write_block_with_color(&message, &color_spec)
     }
 
     pub fn h1(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.text_hidden() {
             return Ok(());
         }
 
@@ -319,7 +863,7 @@ impl Output {
         let message_with_spaces = format!(" {message} ");
         let indent_str = " ".repeat(self.indent);
         let total_content_length = self.indent + message_with_spaces.len();
-        let padding = self.width.saturating_sub(total_content_length);
+        let padding = self.width().saturating_sub(total_content_length);
         let header = format!(
             "{}{}{}",
             indent_str,
@@ -329,12 +873,13 @@ impl Output {
 
         // Set lighter content text on dark background for better readability
         let color_spec = ColorSpec::new()
-            .set_fg(Some(SolarizedDark::BASE0))
-            .set_bg(Some(SolarizedDark::BASE03))
+            .set_fg(Some(self.palette.text))
+            .set_bg(Some(self.palette.heading_background))
             .set_bold(true)
             .clone();
 
         // Write directly to stdout with color, bypassing write_block to avoid double indentation
+        self.clear_progress()?;
         let mut stdout = self.stdout.lock().unwrap();
         stdout.set_color(&color_spec)?;
         write!(stdout, "{header}")?;
@@ -344,7 +889,7 @@ impl Output {
     }
 
     pub fn h2(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.text_hidden() {
             return Ok(());
         }
 
@@ -352,7 +897,7 @@ impl Output {
 
         // Use highlighted foreground color without background
         let color_spec = ColorSpec::new()
-            .set_fg(Some(SolarizedDark::BLUE))
+            .set_fg(Some(self.palette.accent))
             .set_bold(true)
             .clone();
 
@@ -360,7 +905,7 @@ impl Output {
     }
 
     pub fn h3(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.text_hidden() {
             return Ok(());
         }
 
@@ -373,60 +918,119 @@ impl Output {
     }
 
     pub fn trace_warn(&self, message: impl Into<String>) -> io::Result<()> {
-        self.status(message, "[WARNING]", SolarizedDark::YELLOW, false)
+        self.trace_status(message, "[WARNING]", self.palette.warning, false)
     }
 
     pub fn trace_error(&self, message: impl Into<String>) -> io::Result<()> {
-        self.status(message, "[ERROR]", SolarizedDark::RED, true)
+        self.trace_status(message, "[ERROR]", self.palette.error, true)
     }
 
     pub fn trace_success(&self, message: impl Into<String>) -> io::Result<()> {
-        self.status(message, "[OK]", SolarizedDark::GREEN, false)
+        self.trace_status(message, "[OK]", self.palette.success, false)
     }
 
     pub fn trace_debug(&self, message: impl Into<String>) -> io::Result<()> {
-        self.status(message, "[DEBUG]", SolarizedDark::MAGENTA, false)
+        self.trace_status(message, "[DEBUG]", self.palette.debug, false)
     }
 
     pub fn trace_info(&self, message: impl Into<String>) -> io::Result<()> {
-        self.status(message, "[INFO]", SolarizedDark::BLUE, false)
+        self.trace_status(message, "[INFO]", self.palette.accent, false)
     }
 
     pub fn success(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.status_hidden() {
             return Ok(());
         }
 
         let message = message.into();
 
-        // Use SOLARIZED_YELLOW for notes
-        let color_spec = ColorSpec::new().set_fg(Some(SolarizedDark::GREEN)).clone();
+        let color_spec = ColorSpec::new().set_fg(Some(self.palette.success)).clone();
 
         self.write_block_with_color(&message, &color_spec)
     }
 
+    /// Show a progress line on the status stream in place of the previous
+    /// one. Color output means a terminal, where the line is redrawn in
+    /// place; otherwise each update is printed on a line of its own.
+    pub fn progress(&self, message: impl Into<String>) -> io::Result<()> {
+        if self.status_hidden() {
+            return Ok(());
+        }
+        let message = message.into();
+        let mut status = self.status_stream.lock().unwrap();
+        if self.color {
+            write!(status, "\r{message}\x1b[K")?;
+            self.progress_shown.store(true, Ordering::Relaxed);
+        } else {
+            writeln!(status, "{message}")?;
+        }
+        status.flush()
+    }
+
+    /// Clear the progress line, if one is shown.
+    pub fn end_progress(&self) -> io::Result<()> {
+        self.clear_progress()
+    }
+
+    /// Erase the progress line, if one is shown, so that other output starts
+    /// on a clean line.
+    fn clear_progress(&self) -> io::Result<()> {
+        if self.progress_shown.swap(false, Ordering::Relaxed) {
+            let mut status = self.status_stream.lock().unwrap();
+            write!(status, "\r\x1b[K")?;
+            status.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Show a status line, such as what a command is doing or how long a
+    /// request took. Status lines go to the status stream, stderr by default,
+    /// so they don't mix with results when output is piped.
+    pub fn status(&self, message: impl Into<String>) -> io::Result<()> {
+        if self.status_hidden() {
+            return Ok(());
+        }
+        let color_spec = ColorSpec::new().set_fg(Some(self.palette.faint)).clone();
+        self.write_status_block(&message.into(), &color_spec)
+    }
+
     pub fn note(&self, message: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.status_hidden() {
             return Ok(());
         }
 
         let message = message.into();
 
-        // Use SOLARIZED_YELLOW for notes
-        let color_spec = ColorSpec::new().set_fg(Some(SolarizedDark::YELLOW)).clone();
+        let color_spec = ColorSpec::new().set_fg(Some(self.palette.warning)).clone();
 
         self.write_block_with_color(&message, &color_spec)
     }
 
+    /// Show a line of a diff, marked `+` for an addition, `-` for a removal
+    /// or `~` for a change, each in its own color.
+    pub fn diff(&self, marker: char, message: impl Into<String>) -> io::Result<()> {
+        if self.text_hidden() {
+            return Ok(());
+        }
+        let color = match marker {
+            '+' => self.palette.success,
+            '-' => self.palette.error,
+            '~' => self.palette.warning,
+            _ => self.palette.text,
+        };
+        let color_spec = ColorSpec::new().set_fg(Some(color)).clone();
+        self.write_block_with_color(&format!("{marker} {}", message.into()), &color_spec)
+    }
+
     /// Helper method to write status messages with consistent formatting.
-    fn status(
+    fn trace_status(
         &self,
         message: impl Into<String>,
         prefix: &str,
         color: Color,
         bold: bool,
     ) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.status_hidden() {
             return Ok(());
         }
 
@@ -439,7 +1043,7 @@ impl Output {
             color_spec.set_bold(true);
         }
 
-        self.write_block_with_color(&formatted_message, &color_spec)
+        self.write_status_block(&formatted_message, &color_spec)
     }
 
     pub fn trace(&self, message: impl Into<String>, level: Level) -> io::Result<()> {
@@ -448,43 +1052,55 @@ impl Output {
         }
         let message = message.into();
         let formatted_message = format!("trace: {message}");
+        self.write_status_block(&formatted_message, &self.level_color(level))
+    }
 
+    /// Writes a log line from a server to stdout, colored by its level.
+    pub fn log(&self, message: impl Into<String>, level: Level) -> io::Result<()> {
+        if self.text_hidden() {
+            return Ok(());
+        }
+        self.write_block_with_color(&message.into(), &self.level_color(level))
+    }
+
+    /// The color for messages at a level.
+    fn level_color(&self, level: Level) -> ColorSpec {
         let mut color_spec = ColorSpec::new();
         match level {
             Level::ERROR => {
-                color_spec.set_fg(Some(SolarizedDark::RED)).set_bold(true);
+                color_spec.set_fg(Some(self.palette.error)).set_bold(true);
             }
             Level::WARN => {
-                color_spec.set_fg(Some(SolarizedDark::YELLOW));
+                color_spec.set_fg(Some(self.palette.warning));
             }
             Level::INFO => {
-                color_spec.set_fg(Some(SolarizedDark::BASE0)); // Light content color
+                color_spec.set_fg(Some(self.palette.text));
             }
             Level::DEBUG => {
-                color_spec.set_fg(Some(SolarizedDark::MAGENTA));
+                color_spec.set_fg(Some(self.palette.debug));
             }
             Level::TRACE => {
-                color_spec.set_fg(Some(SolarizedDark::BASE01)); // Darker content for trace
+                color_spec.set_fg(Some(self.palette.faint));
             }
         };
-
-        self.write_block_with_color(&formatted_message, &color_spec)
+        color_spec
     }
 
     pub fn kv(&self, key: impl Into<String>, value: impl Into<String>) -> io::Result<()> {
-        if self.json || self.quiet {
+        if self.text_hidden() {
             return Ok(());
         }
 
         let key = key.into();
         let value = value.into();
 
+        self.clear_progress()?;
         let mut stdout = self.stdout.lock().unwrap();
         let indent_str = " ".repeat(self.indent);
 
         // Write key with color
         let key_color_spec = ColorSpec::new()
-            .set_fg(Some(SolarizedDark::CYAN))
+            .set_fg(Some(self.palette.key))
             .set_bold(true)
             .clone();
 
@@ -494,9 +1110,9 @@ impl Output {
 
         // Calculate indentation for wrapped value lines
         let key_prefix_len = self.indent + key.len() + 2; // +2 for ": "
-        let available_width = self.width.saturating_sub(key_prefix_len);
+        let available_width = self.width().saturating_sub(key_prefix_len);
         let value_indent = " ".repeat(key_prefix_len);
-        let value_color_spec = ColorSpec::new().set_fg(Some(SolarizedDark::BASE0)).clone();
+        let value_color_spec = ColorSpec::new().set_fg(Some(self.palette.text)).clone();
 
         // Handle simple single-line case
         if value.len() <= available_width && !value.contains('\n') {
@@ -530,7 +1146,11 @@ impl Output {
     }
 
     pub fn ping(&self) -> Result<()> {
-        if self.json {
+        // Quiet pings report only through the exit code
+        if self.quiet {
+            return Ok(());
+        }
+        if self.structured() {
             self.output_json("{}")?;
         } else {
             self.trace_success("Ping successful!")?;
@@ -542,8 +1162,8 @@ impl Output {
 impl Default for Output {
     fn default() -> Self {
         // Default to color detection based on TTY
-        let color = atty::is(atty::Stream::Stdout);
-        Self::new(color, 80)
+        let color = theme::color_from_env(atty::is(atty::Stream::Stdout));
+        Self::new(color, DEFAULT_WIDTH)
     }
 }
 
@@ -603,3 +1223,68 @@ impl field::Visit for MessageVisitor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_sort_keys() {
+        let value = sort_keys(json!({"b": 1, "a": [{"d": 2, "c": 3}]}));
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"a":[{"c":3,"d":2}],"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_truncate_bytes() {
+        assert_eq!(truncate_bytes("hello", 10), ("hello", 0));
+        assert_eq!(truncate_bytes("hello", 3), ("hel", 2));
+        // Never splits a character
+        assert_eq!(truncate_bytes("héllo", 2), ("h", 5));
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            DataFormat::from_path(Path::new("out.yml")),
+            DataFormat::Yaml
+        );
+        assert_eq!(
+            DataFormat::from_path(Path::new("out.ndjson")),
+            DataFormat::Jsonl
+        );
+        assert_eq!(
+            DataFormat::from_path(Path::new("out.json")),
+            DataFormat::Json
+        );
+        assert_eq!(DataFormat::from_path(Path::new("out")), DataFormat::Json);
+    }
+
+    #[test]
+    fn test_records() {
+        assert_eq!(records(json!([1, {"a": 2}])), [json!(1), json!({"a": 2})]);
+        assert_eq!(
+            records(json!({"tools": [{"name": "a"}, {"name": "b"}]})),
+            [json!({"name": "a"}), json!({"name": "b"})]
+        );
+        assert_eq!(
+            records(json!({
+                "content": [{"type": "text", "text": "hi"}],
+                "isError": false
+            })),
+            [
+                json!({"type": "text", "text": "hi"}),
+                json!({"isError": false})
+            ]
+        );
+        assert_eq!(
+            records(json!({"serverInfo": {"name": "s"}})),
+            [json!({"serverInfo": {"name": "s"}})]
+        );
+        assert_eq!(records(json!("done")), [json!("done")]);
+    }
+}
@@ -1,14 +1,22 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
 use tmcp::schema::{self, ReadResourceResult};
 
 use crate::{
-    Result,
+    Error, Result,
     output::{
         Output,
         formatter::{MetadataDisplay, OutputFormatter, format_output},
+        image,
     },
 };
 
-pub struct ReadResourceFormatter;
+/// Bytes shown on each hexdump line.
+const HEXDUMP_WIDTH: usize = 16;
+
+pub struct ReadResourceFormatter {
+    /// How many bytes of binary contents the hexdump shows.
+    pub hexdump_bytes: usize,
+}
 
 impl OutputFormatter<ReadResourceResult> for ReadResourceFormatter {
     fn format_text(&self, output: &Output, result: &ReadResourceResult) -> Result<()> {
@@ -23,16 +31,29 @@ impl OutputFormatter<ReadResourceResult> for ReadResourceFormatter {
                 schema::ResourceContents::Text(text_resource) => {
                     MetadataDisplay::display_uri(output, &text_resource.uri)?;
                     MetadataDisplay::display_mime_type(output, &text_resource.mime_type)?;
-                    MetadataDisplay::display_text_content(output, &text_resource.text)?;
+                    MetadataDisplay::display_highlighted_content(
+                        output,
+                        &text_resource.text,
+                        text_resource.mime_type.as_deref(),
+                    )?;
                 }
                 schema::ResourceContents::Blob(blob_resource) => {
                     MetadataDisplay::display_uri(output, &blob_resource.uri)?;
+                    if let Some(mime_type) = &blob_resource.mime_type
+                        && mime_type.starts_with("image/")
+                    {
+                        image::show(output, mime_type, &blob_resource.blob)?;
+                        continue;
+                    }
                     MetadataDisplay::display_mime_type(output, &blob_resource.mime_type)?;
-                    MetadataDisplay::display_binary_content(
-                        output,
-                        &blob_resource.blob,
-                        blob_resource.blob.len(),
-                    )?;
+                    match STANDARD.decode(&blob_resource.blob) {
+                        Ok(data) => display_hexdump(output, &data, self.hexdump_bytes)?,
+                        Err(_) => MetadataDisplay::display_binary_content(
+                            output,
+                            &blob_resource.blob,
+                            blob_resource.blob.len(),
+                        )?,
+                    }
                 }
             }
         }
@@ -40,6 +61,97 @@ impl OutputFormatter<ReadResourceResult> for ReadResourceFormatter {
     }
 }
 
-pub fn read_resource_result(output: &Output, result: &ReadResourceResult) -> Result<()> {
-    format_output(output, result, &ReadResourceFormatter)
+/// Shows the size of binary data and a hexdump of its first `limit` bytes.
+fn display_hexdump(output: &Output, data: &[u8], limit: usize) -> Result<()> {
+    output.text(format!("Binary content ({} bytes)", data.len()))?;
+    for line in hexdump(&data[..data.len().min(limit)]) {
+        output.text(line)?;
+    }
+    if data.len() > limit {
+        output.note(format!(
+            "{} more bytes not shown; use --hexdump-bytes to show more, or --raw to write \
+             the contents to stdout",
+            data.len() - limit
+        ))?;
+    }
+    Ok(())
+}
+
+/// Formats bytes as hexdump lines: an offset, the bytes in hex, and the
+/// printable ASCII characters among them.
+fn hexdump(data: &[u8]) -> Vec<String> {
+    data.chunks(HEXDUMP_WIDTH)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut hex = String::new();
+            for column in 0..HEXDUMP_WIDTH {
+                if column == HEXDUMP_WIDTH / 2 {
+                    hex.push(' ');
+                }
+                match chunk.get(column) {
+                    Some(byte) => hex.push_str(&format!("{byte:02x} ")),
+                    None => hex.push_str("   "),
+                }
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        char::from(byte)
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {hex} |{ascii}|", index * HEXDUMP_WIDTH)
+        })
+        .collect()
+}
+
+pub fn read_resource_result(
+    output: &Output,
+    result: &ReadResourceResult,
+    hexdump_bytes: usize,
+) -> Result<()> {
+    format_output(output, result, &ReadResourceFormatter { hexdump_bytes })
+}
+
+/// Writes a resource's contents to stdout as they are: text as is and blobs
+/// decoded, one after the other.
+pub fn write_raw(output: &Output, result: &ReadResourceResult) -> Result<()> {
+    for content in &result.contents {
+        match content {
+            schema::ResourceContents::Text(text_resource) => {
+                output.bytes(text_resource.text.as_bytes())?;
+            }
+            schema::ResourceContents::Blob(blob_resource) => {
+                let data = STANDARD.decode(&blob_resource.blob).map_err(|e| {
+                    Error::Format(format!(
+                        "Contents of {} are not valid base64: {e}",
+                        blob_resource.uri
+                    ))
+                })?;
+                output.bytes(&data)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexdump() {
+        let lines = hexdump(b"Hello, world!\n\x00\x01\x02\xff more");
+        assert_eq!(
+            lines,
+            [
+                "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|",
+                "00000010  02 ff 20 6d 6f 72 65                              |.. more|",
+            ]
+        );
+        assert!(hexdump(b"").is_empty());
+    }
 }
@@ -0,0 +1,144 @@
+//! Aligned tables of list items, one row per item.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    Result, expect,
+    output::{Output, OutputMode},
+};
+
+/// Space between columns.
+const GAP: &str = "  ";
+
+/// Columns are never shrunk below this width to fit the terminal.
+const MIN_WIDTH: usize = 5;
+
+/// The text of a column for an item. Columns name a field of the item's
+/// JSON, or a path into it like `annotations.title`. Missing fields are
+/// blank, strings are shown as they are and other values as compact JSON.
+fn value(item: &Value, column: &str) -> String {
+    let text = match expect::lookup(item, column) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Shortens text to `width` characters, marking the cut with an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// Column widths that fit `available` characters, found by repeatedly
+/// narrowing the widest column.
+fn fit(mut widths: Vec<usize>, available: usize) -> Vec<usize> {
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > available {
+        let Some((widest, &width)) = widths.iter().enumerate().max_by_key(|(_, w)| **w) else {
+            break;
+        };
+        if width <= MIN_WIDTH {
+            break;
+        }
+        widths[widest] = width - 1;
+    }
+    widths
+}
+
+/// Renders rows of cells under a header, as lines no wider than `available`
+/// where possible.
+pub fn render(header: &[String], rows: &[Vec<String>], available: usize) -> Vec<String> {
+    let natural = header
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([title.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let widths = fit(natural, available);
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", truncate(cell, width)))
+            .collect();
+        padded.join(GAP).trim_end().to_string()
+    };
+    let mut lines = vec![line(header)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines
+}
+
+/// Shows list items as a table with the columns chosen by `--columns`, or
+/// `defaults` if none were.
+pub fn list<T: Serialize>(output: &Output, items: &[T], defaults: &[&str]) -> Result<()> {
+    let columns: Vec<String> = match output.mode() {
+        OutputMode::Table { columns } if !columns.is_empty() => columns.clone(),
+        _ => defaults.iter().map(|c| c.to_string()).collect(),
+    };
+    let rows = items
+        .iter()
+        .map(|item| {
+            let item = serde_json::to_value(item)?;
+            Ok(columns.iter().map(|column| value(&item, column)).collect())
+        })
+        .collect::<Result<Vec<Vec<String>>>>()?;
+    let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+
+    let mut lines = render(&header, &rows, output.width()).into_iter();
+    if let Some(header) = lines.next() {
+        output.h3(header)?;
+    }
+    for line in lines {
+        output.text(line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let tool = json!({
+            "name": "echo",
+            "description": "Echoes\n  text",
+            "annotations": {"title": "Echo"}
+        });
+        assert_eq!(value(&tool, "description"), "Echoes text");
+        assert_eq!(value(&tool, "annotations.title"), "Echo");
+        assert_eq!(value(&tool, "annotations"), r#"{"title":"Echo"}"#);
+        assert_eq!(value(&tool, "outputSchema"), "");
+    }
+
+    #[test]
+    fn test_render() {
+        let header = vec!["NAME".to_string(), "DESCRIPTION".to_string()];
+        let rows = vec![
+            vec!["echo".to_string(), "Echoes the message back".to_string()],
+            vec!["add".to_string(), String::new()],
+        ];
+        assert_eq!(
+            render(&header, &rows, 80),
+            ["NAME  DESCRIPTION", "echo  Echoes the message back", "add",]
+        );
+        assert_eq!(
+            render(&header, &rows, 20),
+            ["NAME  DESCRIPTION", "echo  Echoes the me…", "add"]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! User-supplied templates for rendering structured results as custom
+//! reports, such as CSV, HTML fragments or chat messages.
+
+use std::{fs, path::Path};
+
+use minijinja::Environment;
+use serde_json::{Map, Value};
+
+use crate::{Error, Result};
+
+/// A minijinja template that results are rendered with instead of being
+/// written as JSON.
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    /// The name used in error messages, usually the file name.
+    name: String,
+    /// The template source.
+    source: String,
+}
+
+impl OutputTemplate {
+    /// A template with the given name and source, checked for syntax errors.
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Result<Self> {
+        let template = Self {
+            name: name.into(),
+            source: source.into(),
+        };
+        template.environment()?;
+        Ok(template)
+    }
+
+    /// Reads a template from a file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        Self::new(path.display().to_string(), source)
+    }
+
+    /// An environment holding just this template.
+    fn environment(&self) -> Result<Environment<'_>> {
+        let mut env = Environment::new();
+        env.add_template(&self.name, &self.source)
+            .map_err(|e| Error::Format(format!("Invalid template {}: {e}", self.name)))?;
+        Ok(env)
+    }
+
+    /// Renders a result. The fields of an object result are variables in the
+    /// template, and the whole result is also available as `result`.
+    pub fn render(&self, value: &Value) -> Result<String> {
+        let mut context = match value {
+            Value::Object(fields) => fields.clone(),
+            _ => Map::new(),
+        };
+        context.insert("result".to_string(), value.clone());
+
+        let env = self.environment()?;
+        env.get_template(&self.name)
+            .and_then(|template| template.render(&context))
+            .map_err(|e| Error::Format(format!("Failed to render template {}: {e}", self.name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = OutputTemplate::new(
+            "tools.csv",
+            "name,readonly\n{% for tool in tools %}{{ tool.name }},{{ tool.annotations.readOnlyHint | default(false) }}\n{% endfor %}",
+        )
+        .unwrap();
+        let result = json!({"tools": [
+            {"name": "echo", "annotations": {"readOnlyHint": true}},
+            {"name": "write", "annotations": {}}
+        ]});
+        assert_eq!(
+            template.render(&result).unwrap(),
+            "name,readonly\necho,true\nwrite,false\n"
+        );
+
+        let template = OutputTemplate::new("count", "{{ result | length }} item(s)").unwrap();
+        assert_eq!(template.render(&json!([1, 2])).unwrap(), "2 item(s)");
+
+        assert!(OutputTemplate::new("broken", "{% for %}").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Color themes, and deciding whether to use color at all.
+
+use std::env;
+
+use serde::{Deserialize, Serialize};
+use termcolor::Color;
+
+use crate::output::SolarizedDark;
+
+/// A color theme for terminal output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Solarized colors for terminals with a dark background.
+    #[default]
+    Dark,
+    /// Solarized colors for terminals with a light background.
+    Light,
+    /// No color at all.
+    None,
+}
+
+impl Theme {
+    /// The colors output is written in, or None if the theme has no color.
+    pub fn palette(self) -> Option<Palette> {
+        match self {
+            Self::Dark => Some(Palette::DARK),
+            Self::Light => Some(Palette {
+                text: SolarizedDark::BASE00,
+                faint: SolarizedDark::BASE1,
+                heading_background: SolarizedDark::BASE2,
+                ..Palette::DARK
+            }),
+            Self::None => None,
+        }
+    }
+
+    /// The syntect theme embedded content is highlighted with.
+    pub fn syntax_theme(self) -> &'static str {
+        match self {
+            Self::Light => "Solarized (light)",
+            Self::Dark | Self::None => "Solarized (dark)",
+        }
+    }
+}
+
+/// The color of each kind of output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Regular text and values.
+    pub text: Color,
+    /// Status lines and low-priority logs.
+    pub faint: Color,
+    /// The background of top-level headings.
+    pub heading_background: Color,
+    /// Second-level headings and informational messages.
+    pub accent: Color,
+    /// Keys of key-value pairs.
+    pub key: Color,
+    /// Warnings and notes.
+    pub warning: Color,
+    /// Errors.
+    pub error: Color,
+    /// Success messages.
+    pub success: Color,
+    /// Debug messages.
+    pub debug: Color,
+}
+
+impl Palette {
+    /// The dark theme's colors. Its accent colors read well on light
+    /// backgrounds too, so the light theme shares them.
+    pub const DARK: Self = Self {
+        text: SolarizedDark::BASE0,
+        faint: SolarizedDark::BASE01,
+        heading_background: SolarizedDark::BASE03,
+        accent: SolarizedDark::BLUE,
+        key: SolarizedDark::CYAN,
+        warning: SolarizedDark::YELLOW,
+        error: SolarizedDark::RED,
+        success: SolarizedDark::GREEN,
+        debug: SolarizedDark::MAGENTA,
+    };
+}
+
+/// Whether to color output that isn't forced on or off with a flag, going by
+/// the `NO_COLOR`, `CLICOLOR_FORCE` and `CLICOLOR` conventions and whether
+/// output goes to a terminal.
+pub fn color_from_env(terminal: bool) -> bool {
+    let var = |name: &str| env::var(name).ok();
+    env_color(
+        var("NO_COLOR").as_deref(),
+        var("CLICOLOR_FORCE").as_deref(),
+        var("CLICOLOR").as_deref(),
+        terminal,
+    )
+}
+
+/// Decides on color from the values of `NO_COLOR`, `CLICOLOR_FORCE` and
+/// `CLICOLOR`. `NO_COLOR` turns color off when set to anything, a
+/// `CLICOLOR_FORCE` other than 0 turns it on even when piped, and a
+/// `CLICOLOR` of 0 turns it off.
+fn env_color(
+    no_color: Option<&str>,
+    clicolor_force: Option<&str>,
+    clicolor: Option<&str>,
+    terminal: bool,
+) -> bool {
+    let set = |value: Option<&str>| value.is_some_and(|value| !value.is_empty());
+    if set(no_color) {
+        false
+    } else if set(clicolor_force) && clicolor_force != Some("0") {
+        true
+    } else if clicolor == Some("0") {
+        false
+    } else {
+        terminal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_color() {
+        assert!(env_color(None, None, None, true));
+        assert!(!env_color(None, None, None, false));
+        assert!(!env_color(Some("1"), Some("1"), None, true));
+        // An empty NO_COLOR doesn't count
+        assert!(env_color(Some(""), None, None, true));
+        assert!(env_color(None, Some("1"), None, false));
+        assert!(!env_color(None, Some("0"), None, false));
+        assert!(!env_color(None, None, Some("0"), true));
+        assert!(env_color(None, None, Some("1"), true));
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(Theme::None.palette(), None);
+        let light = Theme::Light.palette().unwrap();
+        let dark = Theme::Dark.palette().unwrap();
+        assert_ne!(light.text, dark.text);
+        assert_eq!(light.error, dark.error);
+    }
+}
@@ -0,0 +1,453 @@
+//! Policies that restrict what the clients of a proxy or gateway can use.
+//!
+//! A policy is a TOML file of rules, each allowing or denying tools by name
+//! or annotation, prompts by name, or resources by URI. Names and URIs are
+//! patterns where `*` matches any run of characters and `?` any one. Rules
+//! are checked in order and the first that matches decides; anything no
+//! rule matches gets the default. Denied tools, prompts and resources are
+//! dropped from listings, and requests for them are answered with an error
+//! without reaching the server.
+//!
+//! ```toml
+//! default = "allow"
+//!
+//! [[rule]]
+//! action = "deny"
+//! annotation = "destructiveHint"
+//!
+//! [[rule]]
+//! action = "deny"
+//! uri = "file:///home/*/.ssh/*"
+//! ```
+
+use std::{collections::HashMap, fmt, fs, path::Path, sync::Arc};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    Error, Result,
+    jsonrpc::{self, INVALID_PARAMS, RESOURCE_NOT_FOUND, RpcError},
+};
+
+/// What a rule does with the things it matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Let clients see and use them.
+    #[default]
+    Allow,
+    /// Hide them from clients and refuse requests for them.
+    Deny,
+}
+
+/// A rule in a policy. Each matches exactly one kind of thing.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Whether matching things are allowed or denied.
+    pub action: Action,
+    /// A pattern for tool names.
+    pub tool: Option<String>,
+    /// A tool annotation, such as `destructiveHint`, matching tools that
+    /// set it to true, or leave it out where the spec defaults it to true.
+    pub annotation: Option<String>,
+    /// A pattern for prompt names.
+    pub prompt: Option<String>,
+    /// A pattern for resource URIs and URI templates.
+    pub uri: Option<String>,
+}
+
+/// Something a client lists or asks for.
+#[derive(Debug, Clone, Copy)]
+enum Item<'a> {
+    /// A tool, with its annotations if they're known.
+    Tool(&'a str, Option<&'a Value>),
+    /// A prompt.
+    Prompt(&'a str),
+    /// A resource, or a resource template.
+    Resource(&'a str),
+}
+
+impl Item<'_> {
+    /// The name of the tool or prompt, or the resource's URI.
+    fn name(&self) -> &str {
+        match self {
+            Self::Tool(name, _) | Self::Prompt(name) | Self::Resource(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Item<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tool(name, _) => write!(f, "tool '{name}'"),
+            Self::Prompt(name) => write!(f, "prompt '{name}'"),
+            Self::Resource(uri) => write!(f, "resource '{uri}'"),
+        }
+    }
+}
+
+/// Whether a pattern matches a whole string, with `*` matching any run of
+/// characters and `?` any one.
+pub fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // The last star seen, and the text position it's currently matched up to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Whether a tool's annotations set a hint, taking the MCP spec's defaults
+/// for hints that are left out: tools are destructive and open-world, and
+/// neither read-only nor idempotent. Read-only tools are never destructive.
+fn has_hint(annotations: &Value, hint: &str) -> bool {
+    let flag = |name: &str, default: bool| {
+        annotations
+            .get(name)
+            .and_then(Value::as_bool)
+            .unwrap_or(default)
+    };
+    match hint {
+        "destructiveHint" => !flag("readOnlyHint", false) && flag(hint, true),
+        "openWorldHint" => flag(hint, true),
+        _ => flag(hint, false),
+    }
+}
+
+/// An allow/deny policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// What happens to anything no rule matches.
+    #[serde(default)]
+    pub default: Action,
+    /// The rules, in the order they're checked.
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    /// Reads a policy from a TOML file, checking its rules.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        Self::parse(&contents)
+            .map_err(|e| Error::Format(format!("Invalid policy {}: {e}", path.display())))
+    }
+
+    /// Parses a policy, checking that each rule matches one kind of thing.
+    fn parse(contents: &str) -> Result<Self> {
+        let policy: Self = toml::from_str(contents).map_err(|e| Error::Format(e.to_string()))?;
+        for (index, rule) in policy.rules.iter().enumerate() {
+            let subjects = [&rule.tool, &rule.annotation, &rule.prompt, &rule.uri];
+            if subjects.iter().filter(|subject| subject.is_some()).count() != 1 {
+                return Err(Error::Format(format!(
+                    "rule {} needs exactly one of tool, annotation, prompt or uri",
+                    index + 1
+                )));
+            }
+        }
+        Ok(policy)
+    }
+
+    /// What the policy does with an item, and the number of the rule that
+    /// decided, counting from 1, or None for the default.
+    ///
+    /// A tool whose annotations aren't known, because the client called it
+    /// without listing tools, is taken to match every rule that denies by
+    /// annotation, so it can't slip past them.
+    fn decide(&self, item: Item<'_>) -> (Action, Option<usize>) {
+        for (index, rule) in self.rules.iter().enumerate() {
+            let pattern = match item {
+                Item::Tool(..) => &rule.tool,
+                Item::Prompt(_) => &rule.prompt,
+                Item::Resource(_) => &rule.uri,
+            };
+            let matched = match (item, &rule.annotation) {
+                (Item::Tool(_, Some(annotations)), Some(annotation)) => {
+                    has_hint(annotations, annotation)
+                }
+                (Item::Tool(_, None), Some(_)) => rule.action == Action::Deny,
+                _ => pattern
+                    .as_deref()
+                    .is_some_and(|pattern| glob(pattern, item.name())),
+            };
+            if matched {
+                return (rule.action, Some(index + 1));
+            }
+        }
+        (self.default, None)
+    }
+
+    /// Whether an item is allowed.
+    fn allows(&self, item: Item<'_>) -> bool {
+        self.decide(item).0 == Action::Allow
+    }
+}
+
+/// A request refused before it reached the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Denial {
+    /// The error response to send the client in place of the server's.
+    pub response: Value,
+    /// Why the request was refused, for the log.
+    pub reason: String,
+}
+
+/// A policy applied to one client session. It remembers the annotations of
+/// listed tools, and which requests were listings, so their responses can
+/// be filtered.
+#[derive(Debug, Clone)]
+pub struct Guard {
+    /// The policy.
+    policy: Arc<Policy>,
+    /// The annotations of each tool listed so far, by name.
+    annotations: HashMap<String, Value>,
+    /// The methods of listing requests awaiting responses, by request id.
+    pending: HashMap<String, String>,
+}
+
+impl Guard {
+    /// A guard for a new session.
+    pub fn new(policy: Arc<Policy>) -> Self {
+        Self {
+            policy,
+            annotations: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Checks a message from the client, returning the denial if it asks
+    /// for something the policy doesn't allow.
+    pub fn request(&mut self, message: &Value) -> Option<Denial> {
+        let id = message.get("id")?;
+        let method = message.get("method").and_then(Value::as_str)?;
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let param = |name| params.get(name).and_then(Value::as_str).unwrap_or_default();
+        let (item, code) = match method {
+            "tools/list" | "prompts/list" | "resources/list" | "resources/templates/list" => {
+                self.pending.insert(id.to_string(), method.to_string());
+                return None;
+            }
+            "tools/call" => {
+                let name = param("name");
+                (Item::Tool(name, self.annotations.get(name)), INVALID_PARAMS)
+            }
+            "prompts/get" => (Item::Prompt(param("name")), INVALID_PARAMS),
+            "resources/read" | "resources/subscribe" => {
+                (Item::Resource(param("uri")), RESOURCE_NOT_FOUND)
+            }
+            _ => return None,
+        };
+        let (Action::Deny, rule) = self.policy.decide(item) else {
+            return None;
+        };
+        let by = rule.map_or_else(|| "the default".to_string(), |rule| format!("rule {rule}"));
+        Some(Denial {
+            response: jsonrpc::response(
+                id.clone(),
+                Err(RpcError::new(
+                    code,
+                    format!("The {item} is denied by policy"),
+                )),
+            ),
+            reason: format!("{method}: {item} denied by {by}"),
+        })
+    }
+
+    /// Drops denied items from a response to a listing, noting the
+    /// annotations of the tools it lists.
+    pub fn response(&mut self, message: &mut Value) {
+        let Some(method) = message
+            .get("id")
+            .and_then(|id| self.pending.remove(&id.to_string()))
+        else {
+            return;
+        };
+        let (field, key) = match method.as_str() {
+            "tools/list" => ("tools", "name"),
+            "prompts/list" => ("prompts", "name"),
+            "resources/list" => ("resources", "uri"),
+            _ => ("resourceTemplates", "uriTemplate"),
+        };
+        let Some(items) = message
+            .get_mut("result")
+            .and_then(|result| result.get_mut(field))
+            .and_then(Value::as_array_mut)
+        else {
+            return;
+        };
+        items.retain(|item| {
+            let name = item.get(key).and_then(Value::as_str).unwrap_or_default();
+            let item = match field {
+                "tools" => {
+                    let annotations = item.get("annotations").cloned().unwrap_or(Value::Null);
+                    self.annotations.insert(name.to_string(), annotations);
+                    Item::Tool(name, self.annotations.get(name))
+                }
+                "prompts" => Item::Prompt(name),
+                _ => Item::Resource(name),
+            };
+            self.policy.allows(item)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A policy denying destructive tools and secrets, and allowing only
+    /// the `read` tools of a gateway's `fs` upstream.
+    const POLICY: &str = r#"
+        [[rule]]
+        action = "deny"
+        annotation = "destructiveHint"
+
+        [[rule]]
+        action = "deny"
+        uri = "file:///secret/*"
+
+        [[rule]]
+        action = "allow"
+        tool = "fs__read*"
+
+        [[rule]]
+        action = "deny"
+        tool = "fs__*"
+    "#;
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("fs__*", "fs__read_file"));
+        assert!(glob("*", ""));
+        assert!(glob("a?c", "abc"));
+        assert!(glob("*_file", "read_file"));
+        assert!(glob("a*b*c", "aXbYbZc"));
+        assert!(!glob("a*b*c", "aXbYbZ"));
+        assert!(!glob("fs__*", "web__fetch"));
+        assert!(!glob("read", "read_file"));
+    }
+
+    #[test]
+    fn test_has_hint() {
+        let none = Value::Null;
+        assert!(has_hint(&none, "destructiveHint"));
+        assert!(has_hint(&none, "openWorldHint"));
+        assert!(!has_hint(&none, "readOnlyHint"));
+        assert!(!has_hint(&none, "idempotentHint"));
+
+        let read_only = json!({ "readOnlyHint": true });
+        assert!(!has_hint(&read_only, "destructiveHint"));
+        let safe = json!({ "destructiveHint": false, "openWorldHint": false });
+        assert!(!has_hint(&safe, "destructiveHint"));
+        assert!(!has_hint(&safe, "openWorldHint"));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Policy::parse(POLICY).unwrap();
+        assert_eq!(policy.default, Action::Allow);
+        let destructive = json!({ "destructiveHint": true });
+        assert_eq!(
+            policy.decide(Item::Tool("fs__read_file", Some(&destructive))),
+            (Action::Deny, Some(1))
+        );
+        let read_only = json!({ "readOnlyHint": true });
+        assert_eq!(
+            policy.decide(Item::Tool("fs__read_file", Some(&read_only))),
+            (Action::Allow, Some(3))
+        );
+        // Listed tools without annotations are destructive by default
+        assert_eq!(
+            policy.decide(Item::Tool("fs__read_file", Some(&Value::Null))),
+            (Action::Deny, Some(1))
+        );
+        // Unlisted tools can't escape the annotation rule
+        assert_eq!(
+            policy.decide(Item::Tool("web__fetch", None)),
+            (Action::Deny, Some(1))
+        );
+        assert_eq!(
+            policy.decide(Item::Resource("file:///secret/key")),
+            (Action::Deny, Some(2))
+        );
+        assert_eq!(policy.decide(Item::Prompt("fs__x")), (Action::Allow, None));
+
+        assert!(Policy::parse("[[rule]]\naction = \"deny\"").is_err());
+        assert!(Policy::parse("[[rule]]\naction = \"deny\"\ntool = \"a\"\nuri = \"b\"").is_err());
+        assert!(Policy::parse("default = \"maybe\"").is_err());
+    }
+
+    #[test]
+    fn test_guard() {
+        let mut guard = Guard::new(Arc::new(Policy::parse(POLICY).unwrap()));
+        let list = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" });
+        assert_eq!(guard.request(&list), None);
+        let mut response = json!({ "jsonrpc": "2.0", "id": 1, "result": { "tools": [
+            { "name": "fs__read_file", "annotations": { "readOnlyHint": true } },
+            { "name": "fs__write_file", "annotations": { "destructiveHint": false } },
+            { "name": "fs__delete_file" },
+            { "name": "web__delete", "annotations": { "destructiveHint": true } },
+            { "name": "web__fetch", "annotations": { "readOnlyHint": true } },
+        ] } });
+        guard.response(&mut response);
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["fs__read_file", "web__fetch"]);
+
+        let call =
+            |id, name| json!({ "id": id, "method": "tools/call", "params": { "name": name } });
+        assert_eq!(guard.request(&call(2, "web__fetch")), None);
+        let denial = guard.request(&call(3, "fs__write_file")).unwrap();
+        assert_eq!(denial.response["id"], 3);
+        assert_eq!(denial.response["error"]["code"], INVALID_PARAMS);
+        assert_eq!(
+            denial.reason,
+            "tools/call: tool 'fs__write_file' denied by rule 4"
+        );
+        let read = json!({
+            "id": 4,
+            "method": "resources/read",
+            "params": { "uri": "file:///secret/a" },
+        });
+        assert_eq!(
+            guard.request(&read).unwrap().response["error"]["code"],
+            RESOURCE_NOT_FOUND
+        );
+
+        // Responses to requests that weren't listings pass untouched
+        let mut result = json!({ "id": 2, "result": { "tools": [{ "name": "fs__x" }] } });
+        let unchanged = result.clone();
+        guard.response(&mut result);
+        assert_eq!(result, unchanged);
+    }
+}
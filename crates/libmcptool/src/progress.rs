@@ -0,0 +1,132 @@
+//! Live display of the progress notifications a server sends during a call.
+//!
+//! Servers only report progress for requests that carry a progress token.
+//! tmcp doesn't send one, so it is added to each tool call as the request is
+//! written to the server, using the request's id as the token.
+
+use serde_json::{Value, json};
+use tmcp::{ClientCtx, ClientHandler, schema::ServerNotification};
+
+use crate::output::{DataFormat, Output, OutputMode};
+
+/// Width of the progress bar, in characters.
+const BAR_WIDTH: usize = 20;
+
+/// Client connection that draws progress notifications as a progress line,
+/// replacing the previous one. Other notifications are ignored. In JSON Lines
+/// mode every notification is written as a line instead, as it arrives.
+#[derive(Clone)]
+pub struct ProgressConn {
+    /// Where the progress line is drawn.
+    output: Output,
+}
+
+impl ProgressConn {
+    /// A connection handler drawing progress to `output`.
+    pub fn new(output: Output) -> Self {
+        Self { output }
+    }
+}
+
+#[async_trait::async_trait]
+impl ClientHandler for ProgressConn {
+    async fn notification(
+        &self,
+        _context: &ClientCtx,
+        notification: ServerNotification,
+    ) -> tmcp::Result<()> {
+        if matches!(self.output.mode(), OutputMode::Data(DataFormat::Jsonl)) {
+            self.output.json_line(&notification).ok();
+            return Ok(());
+        }
+        if let ServerNotification::Progress {
+            progress,
+            total,
+            message,
+            ..
+        } = notification
+        {
+            // Progress display is best-effort, and must not fail the call
+            self.output
+                .progress(describe(progress, total, message.as_deref()))
+                .ok();
+        }
+        Ok(())
+    }
+}
+
+/// Asks for progress on a request by giving it a progress token, unless it
+/// already has one. Returns whether the request was changed.
+pub fn request_progress(request: &mut Value) -> bool {
+    let Some(id) = request.get("id").cloned() else {
+        return false;
+    };
+    let Some(params) = request.get_mut("params").and_then(Value::as_object_mut) else {
+        return false;
+    };
+    let meta = params.entry("_meta").or_insert_with(|| json!({}));
+    match meta.as_object_mut() {
+        Some(meta) if !meta.contains_key("progressToken") => {
+            meta.insert("progressToken".to_string(), id);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// A progress line: a bar and percentage if the total is known, otherwise
+/// the amount done so far, followed by the server's message.
+pub fn describe(progress: f64, total: Option<f64>, message: Option<&str>) -> String {
+    let mut line = match total.filter(|total| *total > 0.0) {
+        Some(total) => {
+            let fraction = (progress / total).clamp(0.0, 1.0);
+            let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+            format!(
+                "[{}{}] {:>3.0}%",
+                "#".repeat(filled),
+                " ".repeat(BAR_WIDTH - filled),
+                fraction * 100.0
+            )
+        }
+        None => format!("{progress} done"),
+    };
+    if let Some(message) = message.filter(|message| !message.is_empty()) {
+        line.push(' ');
+        line.push_str(message);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(5.0, Some(10.0), Some("halfway")),
+            "[##########          ]  50% halfway"
+        );
+        assert_eq!(
+            describe(0.0, Some(1.0), None),
+            format!("[{}]   0%", " ".repeat(20))
+        );
+        assert_eq!(
+            describe(3.0, Some(2.0), None),
+            format!("[{}] 100%", "#".repeat(20))
+        );
+        assert_eq!(describe(7.0, None, Some("")), "7 done");
+        assert_eq!(describe(7.0, Some(0.0), Some("files")), "7 done files");
+    }
+
+    #[test]
+    fn test_request_progress() {
+        let mut call = json!({"id": "a1", "method": "tools/call", "params": {"name": "x"}});
+        assert!(request_progress(&mut call));
+        assert_eq!(call["params"]["_meta"], json!({"progressToken": "a1"}));
+        assert!(!request_progress(&mut call));
+
+        let mut notification = json!({"method": "tools/call", "params": {}});
+        assert!(!request_progress(&mut notification));
+    }
+}
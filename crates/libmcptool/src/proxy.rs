@@ -1,14 +1,260 @@
-use std::{path::PathBuf, process::Stdio};
+use std::{path::PathBuf, process::Stdio, sync::Arc, time::Instant};
 
 use chrono::Utc;
+use serde_json::Value;
 use tokio::{
     fs::{File, OpenOptions},
     io::{self, AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
+    net::TcpListener,
     process::Command,
+    task, time,
 };
 
-use crate::{Error, Result, target::Target};
+use crate::{
+    Error, Result, client,
+    ctx::Ctx,
+    jsonrpc,
+    output::Output,
+    policy::{Guard, Policy},
+    ratelimit::{Limiter, Limits},
+    recording::{Framer, Origin, Recorder},
+    rewrite::{Rewriter, Rewrites},
+    ssh,
+    target::Target,
+    tls, tunnel,
+};
+
+/// Where the proxy accepts client connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listen {
+    /// A single client on mcptool's own stdin and stdout, for clients that
+    /// launch servers as commands.
+    Stdio,
+    /// Any number of clients connecting to a TCP address.
+    Tcp(String),
+}
+
+impl Listen {
+    /// Parses `stdio` or a `tcp://host:port` address.
+    pub fn parse(input: &str) -> Result<Self> {
+        if input == "stdio" {
+            return Ok(Self::Stdio);
+        }
+        match Target::parse(input) {
+            Ok(Target::Tcp { host, port }) if input.starts_with("tcp://") => {
+                Ok(Self::Tcp(format!("{host}:{port}")))
+            }
+            _ => Err(Error::Format(format!(
+                "Invalid listen address '{input}'. Use stdio or tcp://host:port"
+            ))),
+        }
+    }
+}
+
+/// Where proxied traffic goes besides its destination.
+pub struct ProxyOptions {
+    /// Where clients connect.
+    pub listen: Listen,
+    /// A file all traffic is appended to as it arrives.
+    pub log_file: Option<PathBuf>,
+    /// A file every frame is recorded to, as JSON lines.
+    pub record: Option<PathBuf>,
+    /// A policy file restricting the tools, prompts and resources clients
+    /// can use.
+    pub policy: Option<PathBuf>,
+    /// A file of rules rewriting requests and responses.
+    pub rewrites: Option<PathBuf>,
+    /// A file of rate limits on requests.
+    pub limits: Option<PathBuf>,
+}
+
+/// What becomes of a frame from the client.
+enum Verdict {
+    /// It goes to the server.
+    Forward(Vec<u8>),
+    /// It's answered with this error response instead.
+    Deny(Value),
+}
+
+/// A policy, rewrite rules and rate limits applied to a session's traffic.
+/// Filtered traffic is forwarded frame by frame rather than as it arrives.
+struct Filter {
+    /// The policy, with what it has learned of the session.
+    guard: Option<Guard>,
+    /// The rewrite rules, with what they have learned of the session.
+    rewriter: Option<Rewriter>,
+    /// The rate limits, with the session's buckets and held requests.
+    limiter: Option<Limiter>,
+    /// Where denials and rewrites are logged.
+    output: Output,
+    /// Frames from the client.
+    client_frames: Framer,
+    /// Frames from the server.
+    server_frames: Framer,
+}
+
+impl Filter {
+    /// A filter for a new session, or None if there's nothing to apply.
+    fn new(
+        policy: Option<&Arc<Policy>>,
+        rewrites: Option<&Arc<Rewrites>>,
+        limits: Option<&Arc<Limits>>,
+        output: &Output,
+    ) -> Option<Self> {
+        if policy.is_none() && rewrites.is_none() && limits.is_none() {
+            return None;
+        }
+        Some(Self {
+            guard: policy.cloned().map(Guard::new),
+            rewriter: rewrites.cloned().map(Rewriter::new),
+            limiter: limits
+                .cloned()
+                .map(|limits| Limiter::new(limits, Instant::now())),
+            output: output.clone(),
+            client_frames: Framer::default(),
+            server_frames: Framer::default(),
+        })
+    }
+
+    /// Applies the policy, the rewrites and then the rate limits to a frame
+    /// from the client. A frame held by the rate limits gives no verdict
+    /// until it's released, and may release others.
+    fn client_frame(&mut self, frame: Vec<u8>) -> Result<Vec<Verdict>> {
+        let Ok(mut message) = serde_json::from_slice::<Value>(&frame) else {
+            return Ok(vec![Verdict::Forward(frame)]);
+        };
+        if let Some(denial) = self
+            .guard
+            .as_mut()
+            .and_then(|guard| guard.request(&message))
+        {
+            self.output.trace_warn(denial.reason)?;
+            return Ok(vec![Verdict::Deny(denial.response)]);
+        }
+        let changes = match &mut self.rewriter {
+            Some(rewriter) => rewriter.request(&mut message),
+            None => Vec::new(),
+        };
+        for change in &changes {
+            self.output.status(change)?;
+        }
+        let Some(limiter) = &mut self.limiter else {
+            if changes.is_empty() {
+                return Ok(vec![Verdict::Forward(frame)]);
+            }
+            return Ok(vec![Verdict::Forward(jsonrpc::frame(&message)?)]);
+        };
+        let mut verdicts = Vec::new();
+        if let Some(denial) = limiter.push(message, Instant::now()) {
+            self.output.trace_warn(denial.reason)?;
+            verdicts.push(Verdict::Deny(denial.response));
+        }
+        for message in limiter.release(Instant::now()) {
+            verdicts.push(Verdict::Forward(jsonrpc::frame(&message)?));
+        }
+        Ok(verdicts)
+    }
+
+    /// The held frames the rate limits now allow.
+    fn release(&mut self) -> Result<Vec<Vec<u8>>> {
+        let Some(limiter) = &mut self.limiter else {
+            return Ok(Vec::new());
+        };
+        limiter
+            .release(Instant::now())
+            .iter()
+            .map(jsonrpc::frame)
+            .collect()
+    }
+
+    /// When held frames can next be released, if any are held.
+    fn next_release(&self) -> Option<Instant> {
+        self.limiter.as_ref()?.next_release(Instant::now())
+    }
+
+    /// Applies the rewrites, then the policy, to a frame from the server,
+    /// returning the frame to forward.
+    fn server_frame(&mut self, frame: Vec<u8>) -> Result<Vec<u8>> {
+        let Ok(mut message) = serde_json::from_slice::<Value>(&frame) else {
+            return Ok(frame);
+        };
+        if let Some(rewriter) = &mut self.rewriter {
+            for change in rewriter.response(&mut message) {
+                self.output.status(change)?;
+            }
+        }
+        if let Some(guard) = &mut self.guard {
+            guard.response(&mut message);
+        }
+        jsonrpc::frame(&message)
+    }
+}
+
+/// The logging, recording and filtering of one client session's traffic.
+struct Taps {
+    /// The traffic log, if any.
+    log: Option<File>,
+    /// The frame recorder, if any.
+    recorder: Option<Recorder>,
+    /// The session number frames are recorded under.
+    session: u64,
+    /// Frames from the client.
+    client_frames: Framer,
+    /// Frames from the server.
+    server_frames: Framer,
+    /// The policy filter, if any.
+    filter: Option<Filter>,
+}
+
+impl Taps {
+    /// Taps for a session, opening the traffic log if there is one.
+    async fn open(
+        log_file: Option<&PathBuf>,
+        recorder: Option<Recorder>,
+        session: u64,
+        filter: Option<Filter>,
+    ) -> Result<Self> {
+        let log = match log_file {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            log,
+            recorder,
+            session,
+            client_frames: Framer::default(),
+            server_frames: Framer::default(),
+            filter,
+        })
+    }
+
+    /// Logs and records data sent by one side.
+    async fn tap(&mut self, from: Origin, data: &[u8]) -> Result<()> {
+        if let Some(log) = &mut self.log {
+            let direction = match from {
+                Origin::Client => "CLIENT->SERVER",
+                Origin::Server => "SERVER->CLIENT",
+            };
+            log_traffic(log, direction, data).await?;
+        }
+        if let Some(recorder) = &self.recorder {
+            let framer = match from {
+                Origin::Client => &mut self.client_frames,
+                Origin::Server => &mut self.server_frames,
+            };
+            for frame in framer.push(data) {
+                recorder.record(self.session, from, &frame)?;
+            }
+        }
+        Ok(())
+    }
+}
 
 /// Logs traffic data to the log file with timestamp and direction indicator.
 async fn log_traffic(log_writer: &mut File, direction: &str, data: &[u8]) -> Result<()> {
@@ -22,79 +268,181 @@ async fn log_traffic(log_writer: &mut File, direction: &str, data: &[u8]) -> Res
     Ok(())
 }
 
-/// Forwards data from client to server and logs the traffic.
-async fn handle_client_to_server<W>(
+/// Forwards data from client to server and logs the traffic. Under a
+/// policy, denied requests are answered with an error instead of being
+/// forwarded, and rewrites are applied to the rest.
+async fn handle_client_to_server<W, C>(
     data: &[u8],
     target: &mut W,
-    log_writer: &mut File,
+    client: &mut C,
+    taps: &mut Taps,
 ) -> Result<()>
 where
     W: AsyncWriteExt + Unpin,
+    C: AsyncWriteExt + Unpin,
 {
-    target.write_all(data).await?;
+    let mut denials = Vec::new();
+    match &mut taps.filter {
+        None => target.write_all(data).await?,
+        Some(filter) => {
+            for frame in filter.client_frames.push(data) {
+                for verdict in filter.client_frame(frame)? {
+                    match verdict {
+                        Verdict::Forward(frame) => target.write_all(&frame).await?,
+                        Verdict::Deny(response) => {
+                            let frame = jsonrpc::frame(&response)?;
+                            client.write_all(&frame).await?;
+                            client.flush().await?;
+                            denials.push(frame);
+                        }
+                    }
+                }
+            }
+        }
+    }
     target.flush().await?;
-    log_traffic(log_writer, "CLIENT->SERVER", data).await?;
+    taps.tap(Origin::Client, data).await?;
+    for denial in denials {
+        taps.tap(Origin::Server, &denial).await?;
+    }
     Ok(())
 }
 
-/// Forwards data from server to client and logs the traffic.
-async fn handle_server_to_client<W>(
-    data: &[u8],
-    writer: &mut W,
-    log_writer: &mut File,
-) -> Result<()>
+/// Forwards data from server to client and logs the traffic. Rewrites are
+/// applied, and under a policy, denied items are dropped from listings.
+async fn handle_server_to_client<W>(data: &[u8], writer: &mut W, taps: &mut Taps) -> Result<()>
 where
     W: AsyncWriteExt + Unpin,
 {
-    writer.write_all(data).await?;
+    match &mut taps.filter {
+        None => writer.write_all(data).await?,
+        Some(filter) => {
+            for frame in filter.server_frames.push(data) {
+                writer.write_all(&filter.server_frame(frame)?).await?;
+            }
+        }
+    }
     writer.flush().await?;
-    log_traffic(log_writer, "SERVER->CLIENT", data).await?;
-    Ok(())
+    taps.tap(Origin::Server, data).await
 }
 
-pub async fn proxy_command(target: Target, log_file: PathBuf) -> Result<()> {
-    let mut log_writer = Some(
-        OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file)
-            .await?,
-    );
-
-    let stdin = io::stdin();
-    let stdout = io::stdout();
+/// Proxies MCP clients to a server target, logging and recording the
+/// traffic as asked.
+pub async fn proxy_command(ctx: &Ctx, target: Target, options: ProxyOptions) -> Result<()> {
+    let recorder = options
+        .record
+        .as_deref()
+        .map(Recorder::create)
+        .transpose()?;
+    let policy = options
+        .policy
+        .as_deref()
+        .map(Policy::load)
+        .transpose()?
+        .map(Arc::new);
+    let rewrites = options
+        .rewrites
+        .as_deref()
+        .map(Rewrites::load)
+        .transpose()?
+        .map(Arc::new);
+    let limits = options
+        .limits
+        .as_deref()
+        .map(Limits::load)
+        .transpose()?
+        .map(Arc::new);
+    let filter = || {
+        Filter::new(
+            policy.as_ref(),
+            rewrites.as_ref(),
+            limits.as_ref(),
+            &ctx.output,
+        )
+    };
+    match &options.listen {
+        Listen::Stdio => {
+            let taps = Taps::open(options.log_file.as_ref(), recorder, 1, filter()).await?;
+            proxy_session(ctx, &target, io::stdin(), io::stdout(), taps).await
+        }
+        Listen::Tcp(addr) => {
+            let listener = TcpListener::bind(addr)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to listen on {addr}: {e}")))?;
+            ctx.output
+                .status(format!("Proxying clients on tcp://{addr} to {target}"))?;
+            let mut session = 0;
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                session += 1;
+                ctx.output
+                    .status(format!("Session {session}: client connected from {peer}"))?;
+                let taps = Taps::open(
+                    options.log_file.as_ref(),
+                    recorder.clone(),
+                    session,
+                    filter(),
+                )
+                .await?;
+                let ctx = ctx.clone();
+                let target = target.clone();
+                task::spawn(async move {
+                    let (reader, writer) = io::split(stream);
+                    let ended = match proxy_session(&ctx, &target, reader, writer, taps).await {
+                        Ok(()) => format!("Session {session}: closed"),
+                        Err(e) => format!("Session {session}: {e}"),
+                    };
+                    _ = ctx.output.status(ended);
+                });
+            }
+        }
+    }
+}
 
-    match target {
+/// Proxies one client, connected through `reader` and `writer`, to a new
+/// connection to the target.
+async fn proxy_session<R, W>(
+    ctx: &Ctx,
+    target: &Target,
+    reader: R,
+    writer: W,
+    mut taps: Taps,
+) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    match target.unnamed() {
         Target::Tcp { host, port } => {
-            let addr = format!("{host}:{port}");
-            let target_stream = TcpStream::connect(&addr).await?;
-            proxy_streams(stdin, stdout, target_stream, log_writer.as_mut().unwrap()).await?;
+            let proxy = ctx.connect.tcp_proxy(host);
+            let target_stream = tunnel::connect(host, *port, proxy.as_deref()).await?;
+            proxy_streams(reader, writer, target_stream, &mut taps).await?;
+        }
+        Target::Tls { host, port } => {
+            let proxy = ctx.connect.tcp_proxy(host);
+            let stream = tunnel::connect(host, *port, proxy.as_deref()).await?;
+            let target_stream = tls::connect(stream, host, &ctx.connect.tls).await?;
+            proxy_streams(reader, writer, target_stream, &mut taps).await?;
         }
         Target::Stdio { command, args } => {
-            let mut cmd = Command::new(command);
-            cmd.args(args);
-            cmd.stdin(Stdio::piped());
-            cmd.stdout(Stdio::piped());
-
-            let mut child = cmd.spawn()?;
-            let child_stdin = child.stdin.take().unwrap();
-            let child_stdout = child.stdout.take().unwrap();
-
-            proxy_process_streams(
-                stdin,
-                stdout,
-                child_stdin,
-                child_stdout,
-                log_writer.as_mut().unwrap(),
-            )
-            .await?;
+            let cmd = client::resolve_options(ctx, target)?.server_command(command, args);
+            proxy_process(cmd, reader, writer, &mut taps).await?;
+        }
+        Target::Ssh {
+            user,
+            host,
+            port,
+            command,
+        } => {
+            let cmd = ssh::command(user.as_deref(), host, *port, command);
+            proxy_process(cmd, reader, writer, &mut taps).await?;
         }
         Target::Http { .. } | Target::Https { .. } => {
             return Err(Error::Other(
                 "HTTP/HTTPS connections are not yet supported for proxy".to_string(),
             ));
         }
-        Target::Auth { .. } => {
+        Target::Auth { .. } | Target::Named { .. } => {
             return Err(Error::Other(
                 "Auth targets should be resolved to actual targets before calling proxy_command"
                     .to_string(),
@@ -105,56 +453,45 @@ pub async fn proxy_command(target: Target, log_file: PathBuf) -> Result<()> {
     Ok(())
 }
 
-/// Proxies bidirectional traffic between reader/writer and a target stream.
-async fn proxy_streams<R, W, T>(
-    mut reader: R,
-    mut writer: W,
-    mut target: T,
-    log_writer: &mut File,
-) -> Result<()>
+/// Spawns a server process and proxies traffic to its stdio.
+async fn proxy_process<R, W>(mut cmd: Command, reader: R, writer: W, taps: &mut Taps) -> Result<()>
 where
     R: AsyncReadExt + Unpin,
     W: AsyncWriteExt + Unpin,
-    T: AsyncReadExt + AsyncWriteExt + Unpin,
 {
-    let mut buf1 = [0u8; 8192];
-    let mut buf2 = [0u8; 8192];
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::piped());
+    cmd.kill_on_drop(true);
 
-    loop {
-        tokio::select! {
-            result = reader.read(&mut buf1) => {
-                match result {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        let data = &buf1[..n];
-                        handle_client_to_server(data, &mut target, log_writer).await?;
-                    }
-                    Err(e) => return Err(Error::Io(e)),
-                }
-            }
-            result = target.read(&mut buf2) => {
-                match result {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        let data = &buf2[..n];
-                        handle_server_to_client(data, &mut writer, log_writer).await?;
-                    }
-                    Err(e) => return Err(Error::Io(e)),
-                }
-            }
-        }
-    }
+    let mut child = cmd.spawn()?;
+    let (Some(child_stdin), Some(child_stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err(Error::Internal(
+            "Server process has no stdio pipes".to_string(),
+        ));
+    };
 
-    Ok(())
+    proxy_process_streams(reader, writer, child_stdin, child_stdout, taps).await
 }
 
-/// Proxies bidirectional traffic between reader/writer and a child process.
+/// Proxies bidirectional traffic between reader/writer and a target stream.
+async fn proxy_streams<R, W, T>(reader: R, writer: W, target: T, taps: &mut Taps) -> Result<()>
+where
+    R: AsyncReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+    T: AsyncReadExt + AsyncWriteExt + Unpin,
+{
+    let (target_reader, target_writer) = io::split(target);
+    proxy_process_streams(reader, writer, target_writer, target_reader, taps).await
+}
+
+/// Proxies bidirectional traffic between reader/writer and a server's input
+/// and output.
 async fn proxy_process_streams<R, W, S, T>(
     mut reader: R,
     mut writer: W,
     mut target_stdin: S,
     mut target_stdout: T,
-    log_writer: &mut File,
+    taps: &mut Taps,
 ) -> Result<()>
 where
     R: AsyncReadExt + Unpin,
@@ -166,13 +503,24 @@ where
     let mut buf2 = [0u8; 8192];
 
     loop {
+        let release = taps.filter.as_ref().and_then(Filter::next_release);
+        let release_at = time::Instant::from_std(release.unwrap_or_else(Instant::now));
         tokio::select! {
+            () = time::sleep_until(release_at), if release.is_some() => {
+                let Some(filter) = &mut taps.filter else {
+                    continue;
+                };
+                for frame in filter.release()? {
+                    target_stdin.write_all(&frame).await?;
+                }
+                target_stdin.flush().await?;
+            }
             result = reader.read(&mut buf1) => {
                 match result {
                     Ok(0) => break,
                     Ok(n) => {
                         let data = &buf1[..n];
-                        handle_client_to_server(data, &mut target_stdin, log_writer).await?;
+                        handle_client_to_server(data, &mut target_stdin, &mut writer, taps).await?;
                     }
                     Err(e) => return Err(Error::Io(e)),
                 }
@@ -182,7 +530,7 @@ where
                     Ok(0) => break,
                     Ok(n) => {
                         let data = &buf2[..n];
-                        handle_server_to_client(data, &mut writer, log_writer).await?;
+                        handle_server_to_client(data, &mut writer, taps).await?;
                     }
                     Err(e) => return Err(Error::Io(e)),
                 }
@@ -192,3 +540,19 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen() {
+        assert_eq!(Listen::parse("stdio").unwrap(), Listen::Stdio);
+        assert_eq!(
+            Listen::parse("tcp://0.0.0.0:9000").unwrap(),
+            Listen::Tcp("0.0.0.0:9000".to_string())
+        );
+        assert!(Listen::parse("http://localhost:9000").is_err());
+        assert!(Listen::parse("localhost:9000").is_err());
+    }
+}
@@ -0,0 +1,465 @@
+//! Rate limits on the requests clients send through a proxy or gateway, so
+//! a runaway client can't hammer an expensive upstream tool.
+//!
+//! Limits are token buckets, read from a TOML file. The global limit covers
+//! every request but `initialize`, and tool limits cover calls to the tools
+//! they match, with each tool counted on its own. A bucket holds `burst`
+//! requests, `requests` by default, and refills at `requests` per `per`.
+//! What happens to a request over its limit is set by `excess`: `reject`
+//! answers it with an error, `delay` holds it until the buckets refill, and
+//! `queue` holds it the same way unless `queue` requests are already
+//! waiting, when it's rejected. Each client session has its own buckets.
+//!
+//! ```toml
+//! excess = "queue"
+//! queue = 10
+//!
+//! [global]
+//! requests = 20
+//!
+//! [[tool]]
+//! name = "search*"
+//! requests = 10
+//! per = "1m"
+//! burst = 2
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, mem,
+    path::Path,
+    result,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Deserializer, de};
+use serde_json::Value;
+
+use crate::{
+    Error, Result,
+    jsonrpc::{self, RpcError},
+    policy::{Denial, glob},
+};
+
+/// The JSON-RPC error code for a request refused by a rate limit, from the
+/// range reserved for server errors.
+pub const RATE_LIMITED: i64 = -32000;
+
+/// What happens to a request over its limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Excess {
+    /// Answer it with an error.
+    #[default]
+    Reject,
+    /// Hold it until the buckets refill, however long that takes.
+    Delay,
+    /// Hold it until the buckets refill, unless the queue is full.
+    Queue,
+}
+
+/// Reads a duration such as "1s" or "5m".
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> result::Result<Duration, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    humantime::parse_duration(&text).map_err(de::Error::custom)
+}
+
+/// The default period limits are counted over.
+const fn second() -> Duration {
+    Duration::from_secs(1)
+}
+
+/// The default number of requests a queue holds.
+const fn queue() -> usize {
+    10
+}
+
+/// A limit on all requests.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rate {
+    /// How many requests are allowed in each period.
+    pub requests: u32,
+    /// The period, one second by default.
+    #[serde(default = "second", deserialize_with = "duration")]
+    pub per: Duration,
+    /// How many requests can be made at once after a quiet spell.
+    pub burst: Option<u32>,
+}
+
+/// A limit on calls to each tool it matches.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolLimit {
+    /// A pattern for tool names, as in policies.
+    pub name: String,
+    /// How many calls are allowed in each period.
+    pub requests: u32,
+    /// The period, one second by default.
+    #[serde(default = "second", deserialize_with = "duration")]
+    pub per: Duration,
+    /// How many calls can be made at once after a quiet spell.
+    pub burst: Option<u32>,
+}
+
+/// A set of rate limits.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    /// What happens to requests over their limit.
+    #[serde(default)]
+    pub excess: Excess,
+    /// How many requests can wait at once when `excess` is `queue`.
+    #[serde(default = "queue")]
+    pub queue: usize,
+    /// The limit on all requests.
+    pub global: Option<Rate>,
+    /// Limits on tools, the first matching a tool applying to it.
+    #[serde(default, rename = "tool")]
+    pub tools: Vec<ToolLimit>,
+}
+
+impl Limits {
+    /// Reads limits from a TOML file, checking them.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        Self::parse(&contents)
+            .map_err(|e| Error::Format(format!("Invalid limits {}: {e}", path.display())))
+    }
+
+    /// Parses limits, checking that every bucket can fill.
+    fn parse(contents: &str) -> Result<Self> {
+        let limits: Self = toml::from_str(contents).map_err(|e| Error::Format(e.to_string()))?;
+        let rates = limits
+            .global
+            .iter()
+            .map(|rate| {
+                (
+                    "the global limit".to_string(),
+                    rate.requests,
+                    rate.per,
+                    rate.burst,
+                )
+            })
+            .chain(limits.tools.iter().map(|tool| {
+                let what = format!("the limit on {}", tool.name);
+                (what, tool.requests, tool.per, tool.burst)
+            }));
+        for (what, requests, per, burst) in rates {
+            if requests == 0 || per.is_zero() || burst == Some(0) {
+                return Err(Error::Format(format!(
+                    "{what} needs requests, per and burst above zero"
+                )));
+            }
+        }
+        Ok(limits)
+    }
+}
+
+/// A token bucket.
+#[derive(Debug, Clone)]
+struct Bucket {
+    /// The most tokens it holds.
+    capacity: f64,
+    /// The tokens added each second.
+    rate: f64,
+    /// The tokens it held when last updated.
+    tokens: f64,
+    /// When it was last updated.
+    updated: Instant,
+}
+
+impl Bucket {
+    /// A full bucket.
+    fn new(requests: u32, per: Duration, burst: Option<u32>, now: Instant) -> Self {
+        let capacity = f64::from(burst.unwrap_or(requests));
+        Self {
+            capacity,
+            rate: f64::from(requests) / per.as_secs_f64(),
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    /// The tokens it holds at a time.
+    fn tokens(&self, now: Instant) -> f64 {
+        let refilled = now.saturating_duration_since(self.updated).as_secs_f64() * self.rate;
+        (self.tokens + refilled).min(self.capacity)
+    }
+
+    /// How long from a time until it holds a token.
+    fn wait(&self, now: Instant) -> Duration {
+        let missing = 1.0 - self.tokens(now);
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.rate)
+        }
+    }
+
+    /// Takes a token.
+    fn take(&mut self, now: Instant) {
+        self.tokens = self.tokens(now) - 1.0;
+        self.updated = now;
+    }
+}
+
+/// Whether a message counts against the limits, and the tool it calls if
+/// it's a tool call.
+fn limited(message: &Value) -> Option<Option<&str>> {
+    message.get("id")?;
+    let method = message.get("method").and_then(Value::as_str)?;
+    if method == "initialize" {
+        return None;
+    }
+    let tool = (method == "tools/call")
+        .then(|| message.get("params")?.get("name")?.as_str())
+        .flatten();
+    Some(tool)
+}
+
+/// Rate limits applied to one client session. Messages from the client are
+/// pushed in, and released once the buckets allow, in the order they came
+/// except where one waits on a bucket another doesn't.
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    /// The limits.
+    limits: Arc<Limits>,
+    /// The bucket for all requests, if they're limited.
+    global: Option<Bucket>,
+    /// The buckets for tools with limits, by tool name.
+    tools: HashMap<String, Bucket>,
+    /// Messages waiting for their buckets.
+    held: VecDeque<Value>,
+    /// Messages their buckets have allowed, waiting to be released.
+    ready: Vec<Value>,
+}
+
+impl Limiter {
+    /// A limiter for a new session, with full buckets.
+    pub fn new(limits: Arc<Limits>, now: Instant) -> Self {
+        let global = limits
+            .global
+            .as_ref()
+            .map(|rate| Bucket::new(rate.requests, rate.per, rate.burst, now));
+        Self {
+            limits,
+            global,
+            tools: HashMap::new(),
+            held: VecDeque::new(),
+            ready: Vec::new(),
+        }
+    }
+
+    /// How long from a time until a message's buckets allow it.
+    fn wait(&self, message: &Value, now: Instant) -> Duration {
+        let Some(tool) = limited(message) else {
+            return Duration::ZERO;
+        };
+        let global = self.global.as_ref().map(|bucket| bucket.wait(now));
+        let tool = tool
+            .and_then(|tool| self.tools.get(tool))
+            .map(|bucket| bucket.wait(now));
+        global.into_iter().chain(tool).max().unwrap_or_default()
+    }
+
+    /// Takes a token for a message from each of its buckets.
+    fn take(&mut self, message: &Value, now: Instant) {
+        let Some(tool) = limited(message) else {
+            return;
+        };
+        if let Some(bucket) = &mut self.global {
+            bucket.take(now);
+        }
+        if let Some(bucket) = tool.and_then(|tool| self.tools.get_mut(tool)) {
+            bucket.take(now);
+        }
+    }
+
+    /// Moves the held messages whose buckets allow them to the ready list,
+    /// in order, taking their tokens.
+    fn settle(&mut self, now: Instant) {
+        let mut index = 0;
+        while index < self.held.len() {
+            if self.wait(&self.held[index], now).is_zero() {
+                let message = self.held.remove(index).expect("index is in bounds");
+                self.take(&message, now);
+                self.ready.push(message);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// Takes a message from the client, to be released when its buckets
+    /// allow. Returns the denial if it's rejected instead.
+    pub fn push(&mut self, message: Value, now: Instant) -> Option<Denial> {
+        if let Some(Some(tool)) = limited(&message)
+            && !self.tools.contains_key(tool)
+            && let Some(limit) = self.limits.tools.iter().find(|t| glob(&t.name, tool))
+        {
+            let bucket = Bucket::new(limit.requests, limit.per, limit.burst, now);
+            self.tools.insert(tool.to_string(), bucket);
+        }
+        self.held.push_back(message);
+        self.settle(now);
+        // A message that had to wait is the last one held
+        let full = match self.limits.excess {
+            Excess::Reject => !self.held.is_empty(),
+            Excess::Delay => false,
+            Excess::Queue => self.held.len() > self.limits.queue,
+        };
+        if !full {
+            return None;
+        }
+        let message = self.held.pop_back()?;
+        let wait = self.wait(&message, now);
+        let method = message["method"].as_str().unwrap_or_default();
+        let what = match limited(&message) {
+            Some(Some(tool)) => format!("tool '{tool}'"),
+            _ => method.to_string(),
+        };
+        Some(Denial {
+            response: jsonrpc::response(
+                message["id"].clone(),
+                Err(RpcError::new(
+                    RATE_LIMITED,
+                    format!(
+                        "Rate limit exceeded for {what}; try again in {:.1}s",
+                        wait.as_secs_f64()
+                    ),
+                )),
+            ),
+            reason: format!("{method}: {what} rate limited"),
+        })
+    }
+
+    /// The messages their buckets now allow, in order.
+    pub fn release(&mut self, now: Instant) -> Vec<Value> {
+        self.settle(now);
+        mem::take(&mut self.ready)
+    }
+
+    /// When the next held message can be released, if any are held.
+    pub fn next_release(&self, now: Instant) -> Option<Instant> {
+        self.held
+            .iter()
+            .map(|message| now + self.wait(message, now))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A tool call.
+    fn call(id: u64, tool: &str) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": { "name": tool } })
+    }
+
+    /// The ids of released messages.
+    fn ids(messages: &[Value]) -> Vec<u64> {
+        messages.iter().filter_map(|m| m["id"].as_u64()).collect()
+    }
+
+    #[test]
+    fn test_bucket() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2, Duration::from_secs(1), Some(1), start);
+        assert_eq!(bucket.wait(start), Duration::ZERO);
+        bucket.take(start);
+        assert_eq!(bucket.wait(start), Duration::from_millis(500));
+        let later = start + Duration::from_secs(10);
+        // Refills stop at the burst
+        assert_eq!(bucket.tokens(later), 1.0);
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits::parse(
+            r#"
+            excess = "queue"
+
+            [global]
+            requests = 5
+
+            [[tool]]
+            name = "a"
+            requests = 1
+            per = "1m"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(limits.excess, Excess::Queue);
+        assert_eq!(limits.queue, 10);
+        assert_eq!(limits.global.unwrap().per, Duration::from_secs(1));
+        assert_eq!(limits.tools[0].per, Duration::from_secs(60));
+        assert!(Limits::parse("[global]\nrequests = 0").is_err());
+        assert!(Limits::parse("[global]\nrequests = 1\nper = \"soon\"").is_err());
+        assert!(Limits::parse("excess = \"drop\"").is_err());
+    }
+
+    #[test]
+    fn test_limiter() {
+        let start = Instant::now();
+        let limits = |excess| {
+            let mut limits = Limits::parse(
+                r#"
+                [global]
+                requests = 10
+
+                [[tool]]
+                name = "slow*"
+                requests = 1
+                per = "2s"
+                "#,
+            )
+            .unwrap();
+            limits.excess = excess;
+            limits.queue = 1;
+            Arc::new(limits)
+        };
+
+        let mut limiter = Limiter::new(limits(Excess::Reject), start);
+        assert!(limiter.push(call(1, "slow_a"), start).is_none());
+        let denial = limiter.push(call(2, "slow_a"), start).unwrap();
+        assert_eq!(denial.response["error"]["code"], RATE_LIMITED);
+        assert_eq!(denial.reason, "tools/call: tool 'slow_a' rate limited");
+        // Each tool has its own bucket, and other tools only the global one
+        assert!(limiter.push(call(3, "slow_b"), start).is_none());
+        assert!(limiter.push(call(4, "fast"), start).is_none());
+        assert_eq!(ids(&limiter.release(start)), [1, 3, 4]);
+
+        let mut limiter = Limiter::new(limits(Excess::Queue), start);
+        for id in 1..=2 {
+            assert!(limiter.push(call(id, "slow"), start).is_none());
+        }
+        assert!(limiter.push(call(3, "slow"), start).is_some());
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/cancelled" });
+        assert!(limiter.push(notification, start).is_none());
+        assert_eq!(ids(&limiter.release(start)), [1]);
+        assert_eq!(limiter.held.len(), 1);
+        assert_eq!(
+            limiter.next_release(start),
+            Some(start + Duration::from_secs(2))
+        );
+        let later = start + Duration::from_secs(2);
+        assert_eq!(ids(&limiter.release(later)), [2]);
+        assert_eq!(limiter.next_release(later), None);
+
+        let mut limiter = Limiter::new(limits(Excess::Delay), start);
+        for id in 1..=3 {
+            assert!(limiter.push(call(id, "slow"), start).is_none());
+        }
+        assert_eq!(ids(&limiter.release(start)), [1]);
+        assert_eq!(
+            limiter.next_release(start),
+            Some(start + Duration::from_secs(2))
+        );
+    }
+}
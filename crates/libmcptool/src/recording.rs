@@ -0,0 +1,160 @@
+//! Recordings of MCP sessions: every frame exchanged between a client and a
+//! server, one JSON record per line.
+
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::Instant,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Which side of a session sent a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    /// The MCP client.
+    Client,
+    /// The MCP server.
+    Server,
+}
+
+/// A frame in a recording. Hand-written fixtures can leave out the timing and
+/// session fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// When the frame was seen.
+    #[serde(default)]
+    pub time: DateTime<Utc>,
+    /// Seconds since the recording started.
+    #[serde(default)]
+    pub elapsed: f64,
+    /// The client connection the frame belongs to, counting from 1.
+    #[serde(default)]
+    pub session: u64,
+    /// Who sent the frame.
+    pub from: Origin,
+    /// The frame's JSON-RPC message, or the frame's text if it isn't JSON.
+    pub message: Value,
+}
+
+/// State shared by every copy of a recorder.
+struct RecorderState {
+    /// The recording file.
+    file: File,
+    /// When recording started.
+    start: Instant,
+}
+
+/// Writes frames to a recording file. Clones share the same file, so
+/// concurrent sessions can be recorded together.
+#[derive(Clone)]
+pub struct Recorder {
+    /// The shared recording state.
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl Recorder {
+    /// Starts a recording in a file, replacing its contents.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| Error::Other(format!("Failed to create {}: {e}", path.display())))?;
+        Ok(Self {
+            state: Arc::new(Mutex::new(RecorderState {
+                file,
+                start: Instant::now(),
+            })),
+        })
+    }
+
+    /// Records a frame. Blank frames are skipped.
+    pub fn record(&self, session: u64, from: Origin, frame: &[u8]) -> Result<()> {
+        let text = String::from_utf8_lossy(frame);
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let record = RecordedFrame {
+            time: Utc::now(),
+            elapsed: state.start.elapsed().as_secs_f64(),
+            session,
+            from,
+            message: serde_json::from_str(text).unwrap_or_else(|_| Value::from(text)),
+        };
+        let line = serde_json::to_string(&record)?;
+        writeln!(state.file, "{line}")?;
+        state.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Splits a byte stream into newline-delimited frames.
+#[derive(Debug, Default)]
+pub struct Framer {
+    /// Data that hasn't yet made a complete frame.
+    buffer: Vec<u8>,
+}
+
+impl Framer {
+    /// Adds data from the stream, returning the frames it completes.
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            frames.push(self.buffer.drain(..=end).collect());
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn test_framer() {
+        let mut framer = Framer::default();
+        assert!(framer.push(b"{\"id\":").is_empty());
+        assert_eq!(
+            framer.push(b"1}\n{}\n{"),
+            [b"{\"id\":1}\n".to_vec(), b"{}\n".to_vec()]
+        );
+        assert_eq!(framer.push(b"}\n"), [b"{}\n".to_vec()]);
+    }
+
+    #[test]
+    fn test_record() {
+        let path = env::temp_dir().join(format!("mcptool_recording_{}.jsonl", process::id()));
+        let recorder = Recorder::create(&path).unwrap();
+        recorder
+            .record(
+                1,
+                Origin::Client,
+                b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n",
+            )
+            .unwrap();
+        recorder.record(1, Origin::Server, b"\n").unwrap();
+        recorder.record(2, Origin::Server, b"not json\n").unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let frames: Vec<RecordedFrame> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].from, Origin::Client);
+        assert_eq!(frames[0].message["method"], "ping");
+        assert_eq!(frames[1].session, 2);
+        assert_eq!(frames[1].message, "not json");
+    }
+}
@@ -0,0 +1,240 @@
+//! Test reports for CI systems: JUnit XML, which most of them show as test
+//! cases, and a JSON summary.
+
+use std::{fs, io, path::Path, result, time::Duration};
+
+use quick_xml::{
+    Writer,
+    events::{BytesDecl, BytesText, Event},
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{Error, Result};
+
+/// The outcome of a test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseStatus {
+    /// The case passed.
+    Passed,
+    /// The case failed.
+    Failed,
+    /// The case wasn't run.
+    Skipped,
+}
+
+/// One test case in a report.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCase {
+    /// The group the case belongs to, such as a check category or a script.
+    pub classname: String,
+    /// The name of the case.
+    pub name: String,
+    /// The outcome.
+    pub status: CaseStatus,
+    /// Why the case failed or was skipped.
+    pub message: Option<String>,
+    /// Further lines of detail.
+    pub output: Vec<String>,
+    /// How long the case took.
+    #[serde(serialize_with = "serialize_seconds")]
+    pub time: Duration,
+}
+
+/// Serializes a duration as fractional seconds.
+fn serialize_seconds<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// The results of a run, written out for CI.
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// The name of the suite, such as the command and target.
+    pub suite: String,
+    /// The cases, in the order they ran.
+    pub cases: Vec<TestCase>,
+}
+
+impl Report {
+    /// The number of cases with a status.
+    fn count(&self, status: CaseStatus) -> usize {
+        self.cases.iter().filter(|c| c.status == status).count()
+    }
+
+    /// The total time of all the cases.
+    fn time(&self) -> Duration {
+        self.cases.iter().map(|c| c.time).sum()
+    }
+
+    /// The report as a JUnit XML document.
+    pub fn to_junit(&self) -> Result<String> {
+        let suite = printable(&self.suite);
+        let tests = self.cases.len().to_string();
+        let failures = self.count(CaseStatus::Failed).to_string();
+        let skipped = self.count(CaseStatus::Skipped).to_string();
+        let time = seconds(self.time());
+        let counts = [
+            ("name", suite.as_str()),
+            ("tests", tests.as_str()),
+            ("failures", failures.as_str()),
+            ("errors", "0"),
+            ("skipped", skipped.as_str()),
+            ("time", time.as_str()),
+        ];
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer
+            .create_element("testsuites")
+            .with_attributes(counts)
+            .write_inner_content(|writer| {
+                writer
+                    .create_element("testsuite")
+                    .with_attributes(counts)
+                    .write_inner_content(|writer| {
+                        for case in &self.cases {
+                            write_case(writer, case)?;
+                        }
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+        let mut xml = String::from_utf8(writer.into_inner())
+            .map_err(|e| Error::Internal(format!("JUnit XML isn't UTF-8: {e}")))?;
+        xml.push('\n');
+        Ok(xml)
+    }
+
+    /// The report as a JSON summary.
+    pub fn to_summary(&self) -> Value {
+        json!({
+            "suite": self.suite,
+            "tests": self.cases.len(),
+            "passed": self.count(CaseStatus::Passed),
+            "failed": self.count(CaseStatus::Failed),
+            "skipped": self.count(CaseStatus::Skipped),
+            "time": self.time().as_secs_f64(),
+            "cases": self.cases,
+        })
+    }
+
+    /// Writes the report to a file: JUnit XML if the name ends in `.xml`,
+    /// and a JSON summary otherwise.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let is_xml = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"));
+        let contents = if is_xml {
+            self.to_junit()?
+        } else {
+            serde_json::to_string_pretty(&self.to_summary())? + "\n"
+        };
+        fs::write(path, contents)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))
+    }
+}
+
+/// Writes a test case's element.
+fn write_case<W: io::Write>(writer: &mut Writer<W>, case: &TestCase) -> io::Result<()> {
+    let classname = printable(&case.classname);
+    let name = printable(&case.name);
+    let time = seconds(case.time);
+    let message = printable(case.message.as_deref().unwrap_or_default());
+    let output = printable(&case.output.join("\n"));
+    let element = writer.create_element("testcase").with_attributes([
+        ("classname", classname.as_str()),
+        ("name", name.as_str()),
+        ("time", time.as_str()),
+    ]);
+    match case.status {
+        CaseStatus::Passed if output.is_empty() => {
+            element.write_empty()?;
+        }
+        CaseStatus::Passed => {
+            element.write_inner_content(|writer| {
+                writer
+                    .create_element("system-out")
+                    .write_text_content(BytesText::new(&output))?;
+                Ok(())
+            })?;
+        }
+        CaseStatus::Failed => {
+            element.write_inner_content(|writer| {
+                writer
+                    .create_element("failure")
+                    .with_attribute(("message", message.as_str()))
+                    .write_text_content(BytesText::new(&output))?;
+                Ok(())
+            })?;
+        }
+        CaseStatus::Skipped => {
+            element.write_inner_content(|writer| {
+                writer
+                    .create_element("skipped")
+                    .with_attribute(("message", message.as_str()))
+                    .write_empty()?;
+                Ok(())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// A duration as seconds, to the millisecond.
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+/// Text without the control characters XML can't hold.
+fn printable(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\n' | '\t' | '\r') || !c.is_control())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A case with nothing but a name and status.
+    fn case(name: &str, status: CaseStatus) -> TestCase {
+        TestCase {
+            classname: "errors".to_string(),
+            name: name.to_string(),
+            status,
+            message: None,
+            output: Vec::new(),
+            time: Duration::from_millis(250),
+        }
+    }
+
+    #[test]
+    fn test_junit() {
+        let mut failed = case("tools/call <unknown>", CaseStatus::Failed);
+        failed.message = Some("Succeeded".to_string());
+        failed.output = vec!["a \"quoted\" & \u{1} line".to_string()];
+        let report = Report {
+            suite: "check cmd://server".to_string(),
+            cases: vec![case("ping", CaseStatus::Passed), failed],
+        };
+        let xml = report.to_junit().unwrap();
+        assert!(
+            xml.contains("tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\"0.500\"")
+        );
+        assert!(xml.contains("<testcase classname=\"errors\" name=\"ping\" time=\"0.250\"/>"));
+        assert!(xml.contains("name=\"tools/call &lt;unknown&gt;\""));
+        assert!(
+            xml.contains(
+                "<failure message=\"Succeeded\">a &quot;quoted&quot; &amp;  line</failure>"
+            )
+        );
+
+        let summary = report.to_summary();
+        assert_eq!(summary["failed"], 1);
+        assert_eq!(summary["cases"][0]["status"], "passed");
+        assert_eq!(summary["cases"][0]["time"], 0.25);
+    }
+}
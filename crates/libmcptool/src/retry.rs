@@ -0,0 +1,129 @@
+//! Retry policies with exponential backoff and jitter.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::Error;
+
+/// Upper bound on the delay between two attempts.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How many times to retry a failed operation, and how long to wait between attempts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt.
+    pub retries: u32,
+    /// Delay before the first retry. Each further retry doubles it.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The backoff before retry number `retry` (starting at 0), without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(MAX_DELAY)
+    }
+
+    /// The delay before retry number `retry`, with random jitter of up to half
+    /// the backoff so that many clients don't retry in lockstep.
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        self.backoff(retry)
+            .mul_f64(rand::rng().random_range(0.5..=1.0))
+    }
+}
+
+/// Failures a tool call can be retried on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RetryOn {
+    /// The call took longer than the request timeout.
+    Timeout,
+    /// The server answered the call with an error.
+    ServerError,
+}
+
+impl RetryOn {
+    /// Whether a failed call's error is of this kind.
+    pub fn matches(self, error: &Error) -> bool {
+        match self {
+            Self::Timeout => matches!(error, Error::Timeout(_)),
+            Self::ServerError => matches!(error, Error::MpcClient(_)),
+        }
+    }
+}
+
+/// How to retry failed tool calls: how often, and on which failures.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CallRetry {
+    /// The number of retries and the backoff between them.
+    pub policy: RetryPolicy,
+    /// The failures worth retrying.
+    pub on: Vec<RetryOn>,
+}
+
+impl CallRetry {
+    /// Whether a call that failed with `error` should be retried, given that
+    /// it has been retried `retry` times already.
+    pub fn should_retry(&self, error: &Error, retry: u32) -> bool {
+        retry < self.policy.retries && self.on.iter().any(|on| on.matches(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            retries: 10,
+            delay: Duration::from_millis(250),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(10), MAX_DELAY);
+        assert_eq!(policy.backoff(u32::MAX), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_call_retry() {
+        let retry = CallRetry {
+            policy: RetryPolicy {
+                retries: 2,
+                delay: Duration::from_millis(100),
+            },
+            on: vec![RetryOn::Timeout],
+        };
+        let timeout = Error::Timeout(Duration::from_secs(1));
+        assert!(retry.should_retry(&timeout, 0));
+        assert!(retry.should_retry(&timeout, 1));
+        assert!(!retry.should_retry(&timeout, 2));
+        assert!(!retry.should_retry(&Error::Other("refused".to_string()), 0));
+        assert!(!CallRetry::default().should_retry(&timeout, 0));
+    }
+
+    #[test]
+    fn test_delay_for_stays_within_jitter_bounds() {
+        let policy = RetryPolicy {
+            retries: 5,
+            delay: Duration::from_secs(1),
+        };
+        for retry in 0..5 {
+            let backoff = policy.backoff(retry);
+            let delay = policy.delay_for(retry);
+            assert!(delay <= backoff);
+            assert!(delay >= backoff / 2);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Rewrite rules that change traffic passing through the proxy, to see how
+//! clients cope with a server that behaves differently, without touching
+//! the server.
+//!
+//! Rules are read from a TOML file. Each kind of rule is a table array:
+//! `inject` adds arguments to tool calls, `redact` replaces fields in
+//! responses, and `describe` overrides tool descriptions in listings. Tool
+//! names are patterns where `*` matches any run of characters and `?` any
+//! one, as in policies.
+//!
+//! ```toml
+//! [[inject]]
+//! tool = "search*"
+//! arguments = { limit = 5 }
+//!
+//! [[redact]]
+//! tool = "get_user"
+//! path = "structuredContent.email"
+//!
+//! [[describe]]
+//! tool = "delete_file"
+//! description = "Deletes a file. Never call this."
+//! ```
+
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::{Error, Result, policy::glob};
+
+/// Arguments added to calls of matching tools.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Inject {
+    /// A pattern for tool names.
+    pub tool: String,
+    /// The arguments, replacing any the client sent with the same names.
+    pub arguments: Map<String, Value>,
+}
+
+/// The value redacted fields are replaced with by default.
+fn redacted() -> Value {
+    json!("[REDACTED]")
+}
+
+/// A field replaced in the results of matching requests.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Redact {
+    /// The method whose results are redacted. Any method if unset.
+    pub method: Option<String>,
+    /// A pattern for the tools whose call results are redacted. Any request
+    /// if unset.
+    pub tool: Option<String>,
+    /// The dotted path of the field in the result, where `*` stands for
+    /// every element of an array or value of an object, as in
+    /// `content.*.text`.
+    pub path: String,
+    /// What the field is replaced with.
+    #[serde(default = "redacted")]
+    pub value: Value,
+}
+
+/// A tool description overridden in listings.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Describe {
+    /// A pattern for tool names.
+    pub tool: String,
+    /// The description clients see.
+    pub description: String,
+}
+
+/// A set of rewrite rules.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rewrites {
+    /// Arguments to add to tool calls.
+    #[serde(default)]
+    pub inject: Vec<Inject>,
+    /// Fields to replace in results.
+    #[serde(default)]
+    pub redact: Vec<Redact>,
+    /// Tool descriptions to override.
+    #[serde(default)]
+    pub describe: Vec<Describe>,
+}
+
+impl Rewrites {
+    /// Reads rules from a TOML file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::Format(format!("Invalid rewrites {}: {e}", path.display())))
+    }
+}
+
+/// Replaces the fields at a path with a value, returning how many were
+/// replaced. Fields that don't exist are left alone.
+fn replace(value: &mut Value, path: &[&str], replacement: &Value) -> usize {
+    let Some((key, rest)) = path.split_first() else {
+        *value = replacement.clone();
+        return 1;
+    };
+    match value {
+        Value::Array(items) if *key == "*" => items
+            .iter_mut()
+            .map(|item| replace(item, rest, replacement))
+            .sum(),
+        Value::Object(fields) if *key == "*" => fields
+            .values_mut()
+            .map(|field| replace(field, rest, replacement))
+            .sum(),
+        Value::Array(items) => key
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| items.get_mut(index))
+            .map_or(0, |item| replace(item, rest, replacement)),
+        Value::Object(fields) => fields
+            .get_mut(*key)
+            .map_or(0, |field| replace(field, rest, replacement)),
+        _ => 0,
+    }
+}
+
+/// Rewrite rules applied to one client session. It remembers what each
+/// request was, so the rules for its response can be found.
+#[derive(Debug, Clone)]
+pub struct Rewriter {
+    /// The rules.
+    rules: Arc<Rewrites>,
+    /// The method of each request awaiting a response, and the tool for
+    /// tool calls, by request id.
+    pending: HashMap<String, (String, Option<String>)>,
+}
+
+impl Rewriter {
+    /// A rewriter for a new session.
+    pub fn new(rules: Arc<Rewrites>) -> Self {
+        Self {
+            rules,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Rewrites a message from the client, returning what was changed.
+    pub fn request(&mut self, message: &mut Value) -> Vec<String> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        let method = method.to_string();
+        let tool = (method == "tools/call")
+            .then(|| message["params"].get("name").and_then(Value::as_str))
+            .flatten()
+            .map(str::to_string);
+        if let Some(id) = message.get("id") {
+            self.pending
+                .insert(id.to_string(), (method.clone(), tool.clone()));
+        }
+        let mut changes = Vec::new();
+        let Some(tool) = tool else {
+            return changes;
+        };
+        for inject in &self.rules.inject {
+            if !glob(&inject.tool, &tool) {
+                continue;
+            }
+            let params = &mut message["params"];
+            if !params.get("arguments").is_some_and(Value::is_object) {
+                params["arguments"] = json!({});
+            }
+            for (name, value) in &inject.arguments {
+                params["arguments"][name] = value.clone();
+                changes.push(format!("{method}: injected '{name}' into {tool}"));
+            }
+        }
+        changes
+    }
+
+    /// Rewrites a response from the server, returning what was changed.
+    pub fn response(&mut self, message: &mut Value) -> Vec<String> {
+        let Some((method, tool)) = message
+            .get("id")
+            .and_then(|id| self.pending.remove(&id.to_string()))
+        else {
+            return Vec::new();
+        };
+        let Some(result) = message.get_mut("result") else {
+            return Vec::new();
+        };
+        let mut changes = Vec::new();
+        for redact in &self.rules.redact {
+            let method_matches = redact.method.as_ref().is_none_or(|m| *m == method);
+            let tool_matches = redact
+                .tool
+                .as_ref()
+                .is_none_or(|pattern| tool.as_ref().is_some_and(|tool| glob(pattern, tool)));
+            if !(method_matches && tool_matches) {
+                continue;
+            }
+            let path: Vec<&str> = redact.path.split('.').collect();
+            if replace(result, &path, &redact.value) > 0 {
+                changes.push(format!("{method}: redacted {}", redact.path));
+            }
+        }
+        if method == "tools/list"
+            && let Some(tools) = result.get_mut("tools").and_then(Value::as_array_mut)
+        {
+            for item in tools {
+                let name = item["name"].as_str().unwrap_or_default().to_string();
+                if let Some(describe) = self.rules.describe.iter().find(|d| glob(&d.tool, &name)) {
+                    item["description"] = json!(describe.description);
+                    changes.push(format!("{method}: described {name}"));
+                }
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rules exercising each kind of rewrite.
+    const REWRITES: &str = r#"
+        [[inject]]
+        tool = "search*"
+        arguments = { limit = 5 }
+
+        [[redact]]
+        tool = "get_user"
+        path = "structuredContent.email"
+
+        [[redact]]
+        method = "resources/read"
+        path = "contents.*.text"
+        value = ""
+
+        [[describe]]
+        tool = "delete_*"
+        description = "Never call this."
+    "#;
+
+    #[test]
+    fn test_replace() {
+        let mut value = json!({ "a": [{ "b": 1 }, { "b": 2 }, { "c": 3 }], "d": { "e": 4 } });
+        assert_eq!(replace(&mut value, &["a", "*", "b"], &json!(0)), 2);
+        assert_eq!(replace(&mut value, &["a", "2", "c"], &json!(0)), 1);
+        assert_eq!(replace(&mut value, &["d", "missing"], &json!(0)), 0);
+        assert_eq!(replace(&mut value, &["d", "*"], &json!(0)), 1);
+        assert_eq!(
+            value,
+            json!({ "a": [{ "b": 0 }, { "b": 0 }, { "c": 0 }], "d": { "e": 0 } })
+        );
+    }
+
+    #[test]
+    fn test_rewriter() {
+        let rules: Rewrites = toml::from_str(REWRITES).unwrap();
+        let mut rewriter = Rewriter::new(Arc::new(rules));
+
+        let mut call = json!({ "id": 1, "method": "tools/call", "params": {
+            "name": "search_docs",
+            "arguments": { "query": "x", "limit": 100 },
+        } });
+        assert_eq!(rewriter.request(&mut call).len(), 1);
+        assert_eq!(
+            call["params"]["arguments"],
+            json!({ "query": "x", "limit": 5 })
+        );
+        let mut call = json!({ "id": 2, "method": "tools/call", "params": { "name": "get_user" } });
+        assert!(rewriter.request(&mut call).is_empty());
+        assert!(call["params"].get("arguments").is_none());
+
+        let mut user = json!({ "id": 2, "result": {
+            "structuredContent": { "name": "a", "email": "a@example.com" },
+        } });
+        assert_eq!(rewriter.response(&mut user).len(), 1);
+        assert_eq!(user["result"]["structuredContent"]["email"], "[REDACTED]");
+        // The search call's result has no field to redact
+        let mut search = json!({ "id": 1, "result": { "content": [] } });
+        assert!(rewriter.response(&mut search).is_empty());
+
+        let mut list = json!({ "id": 3, "method": "tools/list" });
+        rewriter.request(&mut list);
+        let mut tools = json!({ "id": 3, "result": { "tools": [
+            { "name": "delete_file", "description": "Deletes a file" },
+            { "name": "read_file", "description": "Reads a file" },
+        ] } });
+        assert_eq!(rewriter.response(&mut tools).len(), 1);
+        assert_eq!(
+            tools["result"]["tools"][0]["description"],
+            "Never call this."
+        );
+        assert_eq!(tools["result"]["tools"][1]["description"], "Reads a file");
+
+        let mut read = json!({ "id": 4, "method": "resources/read" });
+        rewriter.request(&mut read);
+        let mut contents = json!({ "id": 4, "result": { "contents": [{ "text": "secret" }] } });
+        rewriter.response(&mut contents);
+        assert_eq!(contents["result"]["contents"][0]["text"], "");
+
+        assert!(toml::from_str::<Rewrites>("[[inject]]\ntool = \"a\"").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Validation of values against the JSON Schemas that tools declare.
+//!
+//! Validation is done by the `jsonschema` crate, and each error it reports is
+//! turned into a [`Violation`] whose path reads like the value it points at,
+//! such as `user.tags[1]`. Schemas are never fetched from the network, so a
+//! remote `$ref` makes the schema invalid.
+
+use std::fmt;
+
+use jsonschema::paths::{Location, LocationSegment};
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// A place where a value doesn't match its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Where the value is, such as `user.tags[1]`. Empty for the value itself.
+    pub path: String,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Checks a value against a schema, returning every violation found. Fails
+/// if the schema itself isn't valid.
+pub fn validate(schema: &Value, value: &Value) -> Result<Vec<Violation>> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| Error::Format(format!("Invalid schema: {e}")))?;
+    Ok(validator
+        .iter_errors(value)
+        .map(|error| Violation {
+            path: display_path(error.instance_path()),
+            message: error.to_string(),
+        })
+        .collect())
+}
+
+/// Renders a location in a value as a path like `user.tags[1]`.
+fn display_path(location: &Location) -> String {
+    let mut path = String::new();
+    for segment in location {
+        match segment {
+            LocationSegment::Index(index) => path.push_str(&format!("[{index}]")),
+            LocationSegment::Property(name) if path.is_empty() => path.push_str(&name),
+            LocationSegment::Property(name) => path.push_str(&format!(".{name}")),
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn messages(schema: &Value, value: &Value) -> Vec<String> {
+        let mut messages: Vec<String> = validate(schema, value)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        messages.sort();
+        messages
+    }
+
+    fn tool_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {"type": "string", "minLength": 1},
+                "count": {"type": "integer", "minimum": 1, "maximum": 10},
+                "mode": {"enum": ["fast", "slow"]},
+                "user": {
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}},
+                    "required": ["name"],
+                    "additionalProperties": false
+                },
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2}
+            },
+            "required": ["text"]
+        })
+    }
+
+    #[test]
+    fn test_valid() {
+        let value = json!({
+            "text": "hi",
+            "count": 3,
+            "mode": "fast",
+            "user": {"name": "alice"},
+            "tags": ["a", "b"],
+            "extra": true
+        });
+        assert!(validate(&tool_schema(), &value).unwrap().is_empty());
+        assert!(validate(&json!({}), &value).unwrap().is_empty());
+        assert!(validate(&json!(true), &value).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_violations() {
+        let value = json!({
+            "count": 2.5,
+            "mode": "medium",
+            "user": {"nick": "al"},
+            "tags": ["a", 1, "c"]
+        });
+        assert_eq!(
+            messages(&tool_schema(), &value),
+            [
+                "\"text\" is a required property",
+                "count: 2.5 is not of type \"integer\"",
+                "mode: \"medium\" is not one of \"fast\" or \"slow\"",
+                "tags: [\"a\",1,\"c\"] has more than 2 items",
+                "tags[1]: 1 is not of type \"string\"",
+                "user: \"name\" is a required property",
+                "user: Additional properties are not allowed ('nick' was unexpected)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(
+            messages(&tool_schema(), &json!({"text": "", "count": 11})),
+            [
+                "count: 11 is greater than the maximum of 10",
+                "text: \"\" is shorter than 1 character",
+            ]
+        );
+        assert_eq!(
+            messages(&json!({"pattern": "^a"}), &json!("b")),
+            ["\"b\" does not match \"^a\""]
+        );
+        assert_eq!(
+            messages(
+                &json!({"oneOf": [{"type": "number"}, {"type": "integer"}]}),
+                &json!(1)
+            ),
+            ["1 is valid under more than one of the schemas listed in the 'oneOf' keyword"]
+        );
+        assert!(
+            validate(&json!({"type": ["string", "null"]}), &json!(null))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(validate(&json!({"type": 3}), &json!(1)).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Commands for managing named secrets.
+
+use std::io::{self, BufRead};
+
+use crate::{Error, Result, ctx::Ctx};
+
+/// Validates that a secret name contains only letters, numbers, `_`, `-` and `.`
+pub fn validate_secret_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::Format("Secret name cannot be empty".to_string()));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(Error::Format(format!(
+            "Secret name '{name}' is invalid. Names can only contain letters, numbers, '_', '-' and '.'"
+        )));
+    }
+    Ok(())
+}
+
+/// Stores a secret, prompting for the value if it is not given.
+pub async fn set_command(ctx: &Ctx, name: String, value: Option<String>) -> Result<()> {
+    validate_secret_name(&name)?;
+    let value = match value {
+        Some(value) => value,
+        None => read_secret_value()?,
+    };
+    if value.is_empty() {
+        return Err(Error::Other("Secret value cannot be empty".to_string()));
+    }
+
+    ctx.storage()?.secrets().set(&name, &value)?;
+    ctx.output.trace_success(format!("Secret '{name}' saved"))?;
+    ctx.output.text(format!(
+        "Reference it in header values as {{secret:{name}}}"
+    ))?;
+    Ok(())
+}
+
+/// Lists the names of all stored secrets.
+pub async fn list_command(ctx: &Ctx) -> Result<()> {
+    let names = ctx.storage()?.secrets().list()?;
+    if names.is_empty() {
+        ctx.output.text("No secrets found.")?;
+        return Ok(());
+    }
+    ctx.output.h1(format!("Secrets ({}):", names.len()))?;
+    for name in names {
+        ctx.output.text(format!("  {name}"))?;
+    }
+    Ok(())
+}
+
+/// Removes a stored secret.
+pub async fn remove_command(ctx: &Ctx, name: String) -> Result<()> {
+    ctx.storage()?.secrets().remove(&name)?;
+    ctx.output
+        .trace_success(format!("Secret '{name}' removed"))?;
+    Ok(())
+}
+
+/// Reads a secret value without echo from a terminal, or as a line from piped stdin.
+fn read_secret_value() -> Result<String> {
+    if atty::is(atty::Stream::Stdin) {
+        return Ok(rpassword::prompt_password("Secret value: ")?);
+    }
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_secret_name() {
+        assert!(validate_secret_name("github_token").is_ok());
+        assert!(validate_secret_name("prod-api.key").is_ok());
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("has space").is_err());
+        assert!(validate_secret_name("brace}").is_err());
+    }
+}
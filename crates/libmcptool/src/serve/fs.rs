@@ -0,0 +1,610 @@
+//! A filesystem server: a directory served as MCP resources.
+//!
+//! Every file under the directory is a resource with a `file://` URI, listed
+//! in pages, and a template covers files added later. The directory is
+//! watched, so clients are told when subscribed files change and when files
+//! come and go. A client that offers roots only sees the files inside them.
+//! Symlinks aren't followed, so nothing outside the directory is served.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tmcp::{
+    ClientAPI, Error as McpError, Result as McpResult, Server, ServerCtx, ServerHandler,
+    schema::{
+        ClientCapabilities, ClientNotification, Cursor, Implementation, InitializeResult,
+        ListResourceTemplatesResult, ListResourcesResult, ReadResourceResult, ServerCapabilities,
+        ServerNotification,
+    },
+};
+use tokio::{sync::mpsc, task};
+use url::Url;
+
+use crate::{
+    Error, Result,
+    ctx::{Ctx, VERSION},
+    output::Output,
+    proxy::Listen,
+};
+
+/// The most resources in a page of the listing.
+const PAGE_SIZE: usize = 100;
+
+/// The MIME type of a file, from its extension.
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" | "mjs" => "text/javascript",
+        "rs" => "text/x-rust",
+        "py" => "text/x-python",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    })
+}
+
+/// The `file://` URI of a path.
+fn file_uri(path: &Path) -> String {
+    Url::from_file_path(path).map_or_else(|()| format!("file://{}", path.display()), String::from)
+}
+
+/// The cursor for the page starting at an offset, encoded so clients treat
+/// it as opaque.
+fn encode_cursor(offset: usize) -> String {
+    URL_SAFE_NO_PAD.encode(format!("offset:{offset}"))
+}
+
+/// The offset a cursor points at.
+fn decode_cursor(cursor: &str) -> McpResult<usize> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|text| text.strip_prefix("offset:")?.parse().ok())
+        .ok_or_else(|| McpError::InvalidParams(format!("Invalid cursor: {cursor}")))
+}
+
+/// Turns a result built as JSON into its protocol type.
+fn convert<T: DeserializeOwned>(value: Value) -> McpResult<T> {
+    serde_json::from_value(value).map_err(|e| McpError::InternalError(e.to_string()))
+}
+
+/// A directory served as resources.
+#[derive(Debug, Clone)]
+pub struct FsServer {
+    /// The directory, canonicalized.
+    root: PathBuf,
+}
+
+impl FsServer {
+    /// A server for a directory.
+    pub fn new(dir: &Path) -> Result<Self> {
+        let root = fs::canonicalize(dir)
+            .map_err(|e| Error::Other(format!("Can't serve {}: {e}", dir.display())))?;
+        if !root.is_dir() {
+            return Err(Error::Other(format!(
+                "{} is not a directory",
+                dir.display()
+            )));
+        }
+        Ok(Self { root })
+    }
+
+    /// The file a URI names, if it's inside the directory.
+    fn path(&self, uri: &str) -> Option<PathBuf> {
+        let path = Url::parse(uri).ok()?.to_file_path().ok()?;
+        let path = fs::canonicalize(path).ok()?;
+        path.starts_with(&self.root).then_some(path)
+    }
+
+    /// Every file under the directory, sorted.
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                match entry.file_type() {
+                    Ok(kind) if kind.is_dir() => dirs.push(entry.path()),
+                    Ok(kind) if kind.is_file() => files.push(entry.path()),
+                    _ => {}
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    /// A file as a resource, named by its path in the directory.
+    fn resource(&self, path: &Path) -> Value {
+        let name = path.strip_prefix(&self.root).unwrap_or(path);
+        let mut resource = json!({
+            "uri": file_uri(path),
+            "name": name.to_string_lossy(),
+        });
+        if let Some(mime_type) = mime_type(path) {
+            resource["mimeType"] = json!(mime_type);
+        }
+        if let Ok(metadata) = fs::metadata(path) {
+            resource["size"] = json!(metadata.len());
+        }
+        resource
+    }
+
+    /// The template covering every file under the directory.
+    fn template(&self) -> Value {
+        let root = file_uri(&self.root);
+        json!({
+            "uriTemplate": format!("{}/{{+path}}", root.trim_end_matches('/')),
+            "name": "file",
+            "title": "Files",
+            "description": format!("A file under {}, by its relative path", self.root.display()),
+        })
+    }
+
+    /// The page of the files a client can see starting at `offset`.
+    fn list(&self, view: &ClientView, offset: usize) -> Value {
+        let files = view.files(self);
+        let resources: Vec<Value> = files
+            .iter()
+            .skip(offset)
+            .take(PAGE_SIZE)
+            .map(|path| self.resource(path))
+            .collect();
+        let mut page = json!({ "resources": resources });
+        if offset + PAGE_SIZE < files.len() {
+            page["nextCursor"] = json!(encode_cursor(offset + PAGE_SIZE));
+        }
+        page
+    }
+}
+
+/// A file's contents: text if it's UTF-8, and base64 otherwise.
+fn read(path: &Path, uri: &str) -> McpResult<Value> {
+    let data = fs::read(path).map_err(|_| McpError::ResourceNotFound {
+        uri: uri.to_string(),
+    })?;
+    let mime_type = mime_type(path);
+    let contents = match String::from_utf8(data) {
+        Ok(text) => json!({
+            "uri": uri,
+            "mimeType": mime_type.unwrap_or("text/plain"),
+            "text": text,
+        }),
+        Err(e) => json!({
+            "uri": uri,
+            "mimeType": mime_type.unwrap_or("application/octet-stream"),
+            "blob": STANDARD.encode(e.as_bytes()),
+        }),
+    };
+    Ok(json!({ "contents": [contents] }))
+}
+
+/// The local directories among the roots a client listed. Roots that aren't
+/// local directories can't contain any files, so they're left out.
+fn root_paths(roots: &Value) -> Vec<PathBuf> {
+    roots
+        .get("roots")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|root| {
+            let path = Url::parse(root.get("uri")?.as_str()?)
+                .ok()?
+                .to_file_path()
+                .ok()?;
+            Some(fs::canonicalize(&path).unwrap_or(path))
+        })
+        .collect()
+}
+
+/// What one client can see and has asked to hear about. Shared between the
+/// session's handler and the task watching the directory for it.
+#[derive(Debug, Default)]
+struct ClientView {
+    /// The directories the client offered as roots, once it has answered.
+    roots: Option<Vec<PathBuf>>,
+    /// Subscribed files, with the URIs they were subscribed by.
+    subscriptions: HashMap<PathBuf, String>,
+}
+
+impl ClientView {
+    /// Whether a file is inside the client's roots, if it offered any.
+    fn visible(&self, path: &Path) -> bool {
+        self.roots
+            .as_ref()
+            .is_none_or(|roots| roots.iter().any(|root| path.starts_with(root)))
+    }
+
+    /// The files the client can see.
+    fn files(&self, server: &FsServer) -> Vec<PathBuf> {
+        let mut files = server.files();
+        files.retain(|path| self.visible(path));
+        files
+    }
+
+    /// The notifications a batch of changes to the directory calls for: an
+    /// update for each subscribed file touched, and one listing change if
+    /// files the client can see came or went.
+    fn changes(&self, events: &[Event]) -> Vec<ServerNotification> {
+        let updated: BTreeSet<&String> = events
+            .iter()
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| &event.paths)
+            .filter_map(|path| self.subscriptions.get(path))
+            .collect();
+        let mut notifications: Vec<ServerNotification> = updated
+            .into_iter()
+            .map(|uri| ServerNotification::ResourceUpdated { uri: uri.clone() })
+            .collect();
+        let listing_changed = events.iter().any(|event| {
+            matches!(
+                event.kind,
+                EventKind::Create(_)
+                    | EventKind::Remove(_)
+                    | EventKind::Modify(ModifyKind::Name(_))
+            ) && event.paths.iter().any(|path| self.visible(path))
+        });
+        if listing_changed {
+            notifications.push(ServerNotification::ResourceListChanged);
+        }
+        notifications
+    }
+}
+
+/// Locks a client's view, which stays usable even if a holder panicked.
+fn lock(view: &Mutex<ClientView>) -> MutexGuard<'_, ClientView> {
+    view.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// One client's session.
+#[derive(Clone)]
+struct FsConn {
+    /// The directory served.
+    server: FsServer,
+    /// Where requests are logged.
+    output: Output,
+    /// What the client can see and is subscribed to.
+    view: Arc<Mutex<ClientView>>,
+    /// Whether the client said it can list roots.
+    client_roots: Arc<AtomicBool>,
+    /// Watches the directory for as long as the session lasts.
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+}
+
+impl FsConn {
+    /// A session that hasn't been initialized.
+    fn new(server: FsServer, output: Output) -> Self {
+        Self {
+            server,
+            output,
+            view: Arc::default(),
+            client_roots: Arc::default(),
+            watcher: Arc::default(),
+        }
+    }
+
+    /// Logs the outcome of a request.
+    fn log<T>(&self, method: &str, result: McpResult<T>) -> McpResult<T> {
+        _ = match &result {
+            Ok(_) => self.output.status(format!("{method}: response")),
+            Err(e) => self.output.trace_warn(format!("{method}: {e}")),
+        };
+        result
+    }
+
+    /// The file a URI names, if the client can see it.
+    fn resolve(&self, uri: &str) -> McpResult<PathBuf> {
+        self.server
+            .path(uri)
+            .filter(|path| lock(&self.view).visible(path))
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: uri.to_string(),
+            })
+    }
+
+    /// Starts watching the directory, sending the client the notifications
+    /// its changes call for until the session ends and the watcher with it.
+    fn watch(&self, context: &ServerCtx) -> McpResult<()> {
+        let (sender, mut events) = mpsc::unbounded_channel();
+        let watching = |e: notify::Error| {
+            McpError::InternalError(format!("Can't watch {}: {e}", self.server.root.display()))
+        };
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // Once the session has ended nobody is listening
+            _ = sender.send(event);
+        })
+        .map_err(watching)?;
+        watcher
+            .watch(&self.server.root, RecursiveMode::Recursive)
+            .map_err(watching)?;
+        *self.watcher.lock().unwrap_or_else(PoisonError::into_inner) = Some(watcher);
+
+        let (view, output, context) = (self.view.clone(), self.output.clone(), context.clone());
+        task::spawn(async move {
+            while let Some(event) = events.recv().await {
+                // A single change to a file is often reported as several events
+                let mut batch = vec![event];
+                while let Ok(event) = events.try_recv() {
+                    batch.push(event);
+                }
+                let mut changes = Vec::new();
+                for event in batch {
+                    match event {
+                        Ok(event) => changes.push(event),
+                        Err(e) => _ = output.trace_warn(format!("Watching failed: {e}")),
+                    }
+                }
+                for notification in lock(&view).changes(&changes) {
+                    if context.notify(notification).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Asks the client for its roots, and serves only the files inside them
+    /// from then on. The answer comes back on the connection the request
+    /// that prompted this arrived on, so it is awaited in a task of its own.
+    fn ask_roots(&self, context: &ServerCtx) {
+        let (view, output, mut context) = (self.view.clone(), self.output.clone(), context.clone());
+        task::spawn(async move {
+            let roots = match context.list_roots().await.map(|roots| json!(roots)) {
+                Ok(roots) => root_paths(&roots),
+                Err(e) => {
+                    _ = output.trace_warn(format!(
+                        "The client didn't list its roots, serving every file: {e}"
+                    ));
+                    return;
+                }
+            };
+            let shown: Vec<String> = roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect();
+            _ = output.status(format!("Client roots: {}", shown.join(", ")));
+            lock(&view).roots = Some(roots);
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl ServerHandler for FsConn {
+    async fn on_connect(&self, _context: &ServerCtx, remote_addr: &str) -> McpResult<()> {
+        _ = self
+            .output
+            .status(format!("Client connected from {remote_addr}"));
+        Ok(())
+    }
+
+    async fn on_shutdown(&self) -> McpResult<()> {
+        // Dropping the watcher ends the task sending its notifications
+        self.watcher
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        _ = self.output.status("Session closed");
+        Ok(())
+    }
+
+    async fn initialize(
+        &self,
+        context: &ServerCtx,
+        protocol_version: String,
+        capabilities: ClientCapabilities,
+        _client_info: Implementation,
+    ) -> McpResult<InitializeResult> {
+        let roots = json!(capabilities)
+            .get("roots")
+            .is_some_and(|roots| !roots.is_null());
+        self.client_roots.store(roots, Ordering::Relaxed);
+        self.watch(context)?;
+        let result = convert(json!({
+            "protocolVersion": protocol_version,
+            "capabilities": { "resources": { "subscribe": true, "listChanged": true } },
+            "serverInfo": { "name": "mcptool-fs", "version": VERSION },
+            "instructions": format!("Files under {}", self.server.root.display()),
+        }));
+        self.log("initialize", result)
+    }
+
+    async fn notification(
+        &self,
+        context: &ServerCtx,
+        notification: ClientNotification,
+    ) -> McpResult<()> {
+        if matches!(
+            notification,
+            ClientNotification::Initialized | ClientNotification::RootsListChanged
+        ) && self.client_roots.load(Ordering::Relaxed)
+        {
+            self.ask_roots(context);
+        }
+        Ok(())
+    }
+
+    async fn list_resources(
+        &self,
+        _context: &ServerCtx,
+        cursor: Option<Cursor>,
+    ) -> McpResult<ListResourcesResult> {
+        let result = match json!(cursor).as_str() {
+            Some(cursor) => decode_cursor(cursor),
+            None => Ok(0),
+        }
+        .and_then(|offset| convert(self.server.list(&lock(&self.view), offset)));
+        self.log("resources/list", result)
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _context: &ServerCtx,
+        _cursor: Option<Cursor>,
+    ) -> McpResult<ListResourceTemplatesResult> {
+        let result = convert(json!({ "resourceTemplates": [self.server.template()] }));
+        self.log("resources/templates/list", result)
+    }
+
+    async fn read_resource(
+        &self,
+        _context: &ServerCtx,
+        uri: String,
+    ) -> McpResult<ReadResourceResult> {
+        let result = self
+            .resolve(&uri)
+            .and_then(|path| read(&path, &uri))
+            .and_then(convert);
+        self.log("resources/read", result)
+    }
+
+    async fn resources_subscribe(&self, _context: &ServerCtx, uri: String) -> McpResult<()> {
+        let result = self.resolve(&uri).map(|path| {
+            lock(&self.view).subscriptions.insert(path, uri);
+        });
+        self.log("resources/subscribe", result)
+    }
+
+    async fn resources_unsubscribe(&self, _context: &ServerCtx, uri: String) -> McpResult<()> {
+        lock(&self.view)
+            .subscriptions
+            .retain(|_, subscribed| *subscribed != uri);
+        self.log("resources/unsubscribe", Ok(()))
+    }
+}
+
+/// Serves a directory as MCP resources.
+pub async fn fs_command(ctx: &Ctx, dir: &Path, listen: Listen) -> Result<()> {
+    let server = FsServer::new(dir)?;
+    let output = match listen {
+        // Stdout carries the protocol, so nothing else may be written there
+        Listen::Stdio => ctx.output.with_sink(io::sink()).with_quiet(true),
+        Listen::Tcp(_) => ctx.output.clone(),
+    };
+    let root = server.root.clone();
+    let mcp = Server::default()
+        .with_handler(move || FsConn::new(server.clone(), output.clone()))
+        .with_capabilities(ServerCapabilities::default().with_resources(Some(true), Some(true)));
+    match listen {
+        Listen::Stdio => mcp.serve_stdio().await?,
+        Listen::Tcp(addr) => {
+            ctx.output
+                .status(format!("Serving {} on tcp://{addr}", root.display()))?;
+            mcp.serve_tcp(&addr).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    use super::*;
+
+    #[test]
+    fn test_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/readme.md"), "# Hello").unwrap();
+        fs::write(dir.path().join("image.bin"), [0xff, 0xfe]).unwrap();
+        let server = FsServer::new(dir.path()).unwrap();
+        let mut view = ClientView::default();
+
+        let listed = server.list(&view, 0);
+        let names: Vec<&str> = listed["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resource| resource["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["docs/readme.md", "image.bin"]);
+        assert!(listed.get("nextCursor").is_none());
+
+        let readme = server.root.join("docs/readme.md");
+        let contents = read(&readme, &file_uri(&readme)).unwrap();
+        assert_eq!(contents["contents"][0]["text"], "# Hello");
+        assert_eq!(contents["contents"][0]["mimeType"], "text/markdown");
+        let image = server.root.join("image.bin");
+        let contents = read(&image, &file_uri(&image)).unwrap();
+        assert_eq!(contents["contents"][0]["blob"], "//4=");
+        assert!(
+            server
+                .path(&file_uri(&server.root.join("../elsewhere")))
+                .is_none()
+        );
+
+        // Roots narrow what the client sees
+        let roots = json!({ "roots": [{ "uri": file_uri(&server.root.join("docs")) }] });
+        view.roots = Some(root_paths(&roots));
+        assert_eq!(view.files(&server), [readme]);
+        assert!(!view.visible(&image));
+    }
+
+    #[test]
+    fn test_changes() {
+        let root = PathBuf::from("/served");
+        let readme = root.join("readme.md");
+        let mut view = ClientView::default();
+        view.subscriptions.insert(readme.clone(), file_uri(&readme));
+
+        let written = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Any)))
+            .add_path(readme.clone());
+        let notifications = view.changes(&[written.clone(), written]);
+        assert_eq!(notifications.len(), 1);
+        assert!(matches!(
+            &notifications[0],
+            ServerNotification::ResourceUpdated { uri } if *uri == file_uri(&readme)
+        ));
+
+        let created =
+            Event::new(EventKind::Create(CreateKind::File)).add_path(root.join("new.txt"));
+        let notifications = view.changes(&[created.clone()]);
+        assert!(matches!(
+            notifications[..],
+            [ServerNotification::ResourceListChanged]
+        ));
+
+        // Files outside the client's roots come and go unseen
+        view.roots = Some(vec![root.join("docs")]);
+        assert!(view.changes(&[created]).is_empty());
+        let removed = Event::new(EventKind::Remove(RemoveKind::File)).add_path(readme);
+        assert_eq!(view.changes(&[removed]).len(), 1);
+    }
+
+    #[test]
+    fn test_cursor() {
+        assert_eq!(decode_cursor(&encode_cursor(200)).unwrap(), 200);
+        assert!(decode_cursor("nonsense").is_err());
+    }
+}
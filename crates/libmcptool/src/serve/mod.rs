@@ -0,0 +1,4 @@
+//! Ready-made MCP servers, for giving clients something real to talk to and
+//! for testing mcptool itself.
+
+pub mod fs;
@@ -0,0 +1,230 @@
+//! Snapshots of what a server offers: its initialize result and full tool,
+//! prompt, resource and resource template listings, and optionally the
+//! contents of its resources, captured from a live server or loaded from a
+//! file.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tmcp::{Client, ClientHandler, schema::InitializeResult};
+
+use crate::{
+    Error, Result, client,
+    ctx::Ctx,
+    mcp::{self, Listing},
+    target::Target,
+};
+
+/// What a server offered at one point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    /// The target the snapshot was taken from.
+    pub target: String,
+    /// When the snapshot was taken.
+    pub captured_at: DateTime<Utc>,
+    /// The server's initialize result.
+    pub init: Value,
+    /// Every tool, across all pages.
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    /// Every prompt, across all pages.
+    #[serde(default)]
+    pub prompts: Vec<Value>,
+    /// Every resource, across all pages.
+    #[serde(default)]
+    pub resources: Vec<Value>,
+    /// Every resource template, across all pages.
+    #[serde(default)]
+    pub resource_templates: Vec<Value>,
+    /// The result of reading each resource, by URI, if contents were
+    /// captured.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contents: BTreeMap<String, Value>,
+}
+
+impl Snapshot {
+    /// Connects to a target and captures its initialize result and every
+    /// listing its capabilities declare, and with `contents`, the contents
+    /// of every resource.
+    pub async fn capture(ctx: &Ctx, target: &Target, contents: bool) -> Result<Self> {
+        let (mut client, init) = client::get_client(ctx, target).await?;
+        Self::fetch(&mut client, ctx, target, &init, contents).await
+    }
+
+    /// Captures a snapshot over an open connection, given the result it was
+    /// initialized with.
+    pub async fn fetch<C: ClientHandler + 'static>(
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        target: &Target,
+        init: &InitializeResult,
+        contents: bool,
+    ) -> Result<Self> {
+        let mut snapshot = Self {
+            target: target.to_string(),
+            captured_at: Utc::now(),
+            init: serde_json::to_value(init)?,
+            tools: Vec::new(),
+            prompts: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            contents: BTreeMap::new(),
+        };
+        for listing in Listing::ALL {
+            if snapshot.declares(listing.capability()) {
+                snapshot.refresh(client, ctx, listing).await?;
+            }
+        }
+        if contents {
+            for uri in snapshot.resource_uris() {
+                if let Err(e) = snapshot.read(client, ctx, &uri).await {
+                    ctx.output
+                        .trace_warn(format!("Failed to read {uri}: {e}"))?;
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Fetches a listing again, across all pages. Refreshing resources drops
+    /// the contents of any that are no longer listed.
+    pub async fn refresh<C: ClientHandler + 'static>(
+        &mut self,
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        listing: Listing,
+    ) -> Result<()> {
+        ctx.output
+            .status(format!("Fetching {}", listing.method()))?;
+        let (items, _) = mcp::list_all(client, ctx, listing).await?;
+        *self.items_mut(listing) = items;
+        if listing == Listing::Resources {
+            let uris = self.resource_uris();
+            self.contents.retain(|uri, _| uris.contains(uri));
+        }
+        self.captured_at = Utc::now();
+        Ok(())
+    }
+
+    /// Reads a resource into `contents`.
+    pub async fn read<C: ClientHandler + 'static>(
+        &mut self,
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        uri: &str,
+    ) -> Result<()> {
+        ctx.output.status(format!("Reading {uri}"))?;
+        let result = ctx.request(client.resources_read(uri)).await?;
+        self.contents
+            .insert(uri.to_string(), serde_json::to_value(&result)?);
+        self.captured_at = Utc::now();
+        Ok(())
+    }
+
+    /// The URIs of the listed resources.
+    pub fn resource_uris(&self) -> Vec<String> {
+        self.resources
+            .iter()
+            .filter_map(|resource| resource.get("uri")?.as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Writes the snapshot to a file as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)? + "\n";
+        fs::write(path, contents)
+            .map_err(|e| Error::Other(format!("Failed to write {}: {e}", path.display())))
+    }
+
+    /// Loads a snapshot from a file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| Error::Format(format!("{} is not a snapshot: {e}", path.display())))
+    }
+
+    /// Loads a snapshot if `source` names a file, and otherwise captures one
+    /// from the target it names.
+    pub async fn from_source(ctx: &Ctx, source: &str) -> Result<Self> {
+        let path = Path::new(source);
+        if path.is_file() {
+            return Self::load(path);
+        }
+        let target = Target::resolve(source, &ctx.storage()?)?;
+        Self::capture(ctx, &target, false).await
+    }
+
+    /// Whether the server declared a capability.
+    pub fn declares(&self, capability: &str) -> bool {
+        self.init
+            .pointer(&format!("/capabilities/{capability}"))
+            .is_some_and(|value| !value.is_null())
+    }
+
+    /// The items of a listing.
+    pub fn items(&self, listing: Listing) -> &[Value] {
+        match listing {
+            Listing::Tools => &self.tools,
+            Listing::Prompts => &self.prompts,
+            Listing::Resources => &self.resources,
+            Listing::ResourceTemplates => &self.resource_templates,
+        }
+    }
+
+    /// The items of a listing, for filling in.
+    fn items_mut(&mut self, listing: Listing) -> &mut Vec<Value> {
+        match listing {
+            Listing::Tools => &mut self.tools,
+            Listing::Prompts => &mut self.prompts,
+            Listing::Resources => &mut self.resources,
+            Listing::ResourceTemplates => &mut self.resource_templates,
+        }
+    }
+}
+
+/// Captures a snapshot of a target and writes it to `path`, for `diff` and
+/// other offline uses.
+pub async fn snapshot_command(
+    ctx: &Ctx,
+    target: Target,
+    path: PathBuf,
+    contents: bool,
+) -> Result<()> {
+    let snapshot = Snapshot::capture(ctx, &target, contents).await?;
+    snapshot.save(&path)?;
+
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_value(&json!({
+            "target": snapshot.target,
+            "path": path,
+            "tools": snapshot.tools.len(),
+            "prompts": snapshot.prompts.len(),
+            "resources": snapshot.resources.len(),
+            "resourceTemplates": snapshot.resource_templates.len(),
+            "contents": snapshot.contents.len(),
+        }))?;
+    } else {
+        output.trace_success(format!("Wrote snapshot of {target} to {}", path.display()))?;
+        let out = output.indent();
+        out.kv("tools", snapshot.tools.len().to_string())?;
+        out.kv("prompts", snapshot.prompts.len().to_string())?;
+        out.kv("resources", snapshot.resources.len().to_string())?;
+        out.kv(
+            "resource templates",
+            snapshot.resource_templates.len().to_string(),
+        )?;
+        if contents {
+            out.kv("resource contents", snapshot.contents.len().to_string())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Running MCP servers on remote hosts over SSH.
+
+use tokio::process::Command;
+
+/// Builds an `ssh` invocation that runs `remote` on `host`, with the server's
+/// stdio carried over the SSH channel.
+///
+/// The system `ssh` client is used, so keys, agents and `~/.ssh/config` host
+/// aliases all work as they do on the command line.
+pub fn command(user: Option<&str>, host: &str, port: Option<u16>, remote: &str) -> Command {
+    let mut cmd = Command::new("ssh");
+    // No pseudo-terminal: the channel carries raw JSON-RPC
+    cmd.arg("-T");
+    if let Some(port) = port {
+        cmd.arg("-p").arg(port.to_string());
+    }
+    if let Some(user) = user {
+        cmd.arg("-l").arg(user);
+    }
+    cmd.arg("--").arg(host).arg(remote);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_args() {
+        let cmd = command(Some("alice"), "box", Some(2222), "bin/server --verbose");
+        let args: Vec<_> = cmd
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-T",
+                "-p",
+                "2222",
+                "-l",
+                "alice",
+                "--",
+                "box",
+                "bin/server --verbose"
+            ]
+        );
+
+        let cmd = command(None, "box", None, "server");
+        assert_eq!(cmd.as_std().get_args().count(), 4);
+    }
+}
@@ -0,0 +1,114 @@
+//! Summary statistics for request latencies.
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::{Result, output::Output};
+
+/// The spread of a set of latencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// The fastest sample.
+    pub min: Duration,
+    /// The median.
+    pub p50: Duration,
+    /// The 95th percentile.
+    pub p95: Duration,
+    /// The slowest sample.
+    pub max: Duration,
+    /// The arithmetic mean.
+    pub mean: Duration,
+    /// The population standard deviation.
+    pub stddev: Duration,
+}
+
+impl LatencyStats {
+    /// Summarizes a set of samples, or returns `None` if there are none.
+    /// Percentiles use the nearest-rank method, so they are always one of the
+    /// samples.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let (&min, &max) = (sorted.first()?, sorted.last()?);
+        let total: Duration = sorted.iter().sum();
+        let count = u32::try_from(sorted.len()).unwrap_or(u32::MAX);
+        let mean = total / count;
+        let variance = sorted
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / f64::from(count);
+        Some(Self {
+            min,
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            max,
+            mean,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+
+    /// The statistics in milliseconds, as a JSON object.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "min": millis(self.min),
+            "p50": millis(self.p50),
+            "p95": millis(self.p95),
+            "max": millis(self.max),
+            "mean": millis(self.mean),
+            "stddev": millis(self.stddev),
+        })
+    }
+
+    /// Prints the statistics as key/value pairs in milliseconds.
+    pub fn display(&self, output: &Output) -> Result<()> {
+        for (key, value) in [
+            ("Min", self.min),
+            ("p50", self.p50),
+            ("p95", self.p95),
+            ("Max", self.max),
+            ("Mean", self.mean),
+            ("Stddev", self.stddev),
+        ] {
+            output.kv(key, format!("{:.2}ms", millis(value)))?;
+        }
+        Ok(())
+    }
+}
+
+/// The nearest-rank percentile of sorted, non-empty samples.
+pub fn percentile(sorted: &[Duration], percent: f64) -> Duration {
+    // The epsilon keeps rounding error from pushing an exact rank up by one
+    let rank = ((sorted.len() as f64 * percent / 100.0 - 1e-9).ceil() as usize).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// A duration in fractional milliseconds.
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_samples() {
+        assert_eq!(LatencyStats::from_samples(&[]), None);
+
+        let samples: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(&samples).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.p50, Duration::from_millis(10));
+        assert_eq!(stats.p95, Duration::from_millis(19));
+        assert_eq!(stats.max, Duration::from_millis(20));
+        assert_eq!(stats.mean, Duration::from_micros(10_500));
+        // The population standard deviation of 1..=20 is sqrt(399 / 12)
+        assert_eq!(stats.stddev.as_micros(), 5766);
+
+        let single = LatencyStats::from_samples(&[Duration::from_millis(7)]).unwrap();
+        assert_eq!(single.p95, Duration::from_millis(7));
+        assert_eq!(single.stddev, Duration::ZERO);
+    }
+}
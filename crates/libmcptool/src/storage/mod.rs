@@ -0,0 +1,758 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{client::ConnectOptions, output::theme::Theme};
+
+/// Named secrets backed by the OS keyring or an encrypted file.
+mod secrets;
+
+pub use secrets::SecretStore;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Entry not found: {0}")]
+    NotFound(String),
+    #[error("Keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
+    #[error("Secret error: {0}")]
+    Secret(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAuth {
+    pub name: String,
+    pub server_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<SystemTime>,
+    pub auth_url: String,
+    pub token_url: String,
+    pub redirect_url: Option<String>,
+    pub scopes: Vec<String>,
+    /// The resource (audience) tokens are requested for, per RFC 8707.
+    #[serde(default)]
+    pub resource: Option<String>,
+}
+
+/// A target saved under a short name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredTarget {
+    pub name: String,
+    /// The target specification, in its canonical form.
+    pub target: String,
+    /// Connection settings used whenever the target is connected to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<ConnectOptions>,
+}
+
+/// A tool call saved under a short name, to be run against any target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredTemplate {
+    pub name: String,
+    /// The tool to call.
+    pub tool: String,
+    /// Arguments in the `key=value` form taken by `calltool --arg`.
+    pub args: Vec<String>,
+}
+
+/// User preferences, read from `config.json` in the configuration directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// The color theme, unless one is chosen on the command line.
+    #[serde(default)]
+    pub theme: Option<Theme>,
+}
+
+/// Storage for authentication tokens and credentials.
+///
+/// Client secrets and tokens of authentication entries are kept in the secret
+/// store rather than in `auth.json`.
+pub struct TokenStorage {
+    /// Directory where configuration files are stored.
+    config_dir: PathBuf,
+    /// Store for credentials that should not be written in plain text.
+    secrets: SecretStore,
+}
+
+impl TokenStorage {
+    pub fn new(config_dir: PathBuf) -> Result<Self, StorageError> {
+        fs::create_dir_all(&config_dir)?;
+        let secrets = SecretStore::new(config_dir.clone());
+        Ok(Self {
+            config_dir,
+            secrets,
+        })
+    }
+
+    /// Replaces the secret store, e.g. to avoid the OS keyring.
+    pub fn with_secrets(mut self, secrets: SecretStore) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// The store holding named secrets.
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+
+    pub fn store_auth(&self, auth: &StoredAuth) -> Result<(), StorageError> {
+        let auth_path = self.config_dir.join("auth.json");
+        let mut all_auths = self.load_all_auth_data()?;
+
+        let mut stored = auth.clone();
+        for (field, value) in [
+            ("client_secret", stored.client_secret.take()),
+            ("access_token", stored.access_token.take()),
+            ("refresh_token", stored.refresh_token.take()),
+        ] {
+            let secret_name = auth_secret_name(&auth.name, field);
+            match value {
+                Some(value) => self.secrets.set(&secret_name, &value)?,
+                None => self.remove_secret_if_present(&secret_name)?,
+            }
+        }
+        all_auths.insert(auth.name.clone(), stored);
+
+        let json = serde_json::to_string_pretty(&all_auths)?;
+        fs::write(auth_path, json)?;
+
+        Ok(())
+    }
+
+    pub fn get_auth(&self, name: &str) -> Result<StoredAuth, StorageError> {
+        let all_auths = self.load_all_auth_data()?;
+        let auth = all_auths
+            .get(name)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(name.to_string()))?;
+        self.with_auth_secrets(auth)
+    }
+
+    pub fn remove_auth(&self, name: &str) -> Result<(), StorageError> {
+        let auth_path = self.config_dir.join("auth.json");
+        let mut all_auths = self.load_all_auth_data()?;
+
+        if !all_auths.contains_key(name) {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+
+        all_auths.remove(name);
+        for field in ["client_secret", "access_token", "refresh_token"] {
+            self.remove_secret_if_present(&auth_secret_name(name, field))?;
+        }
+        let json = serde_json::to_string_pretty(&all_auths)?;
+        fs::write(auth_path, json)?;
+
+        Ok(())
+    }
+
+    pub fn list_auth(&self) -> Result<Vec<String>, StorageError> {
+        let all_auths = self.load_all_auth_data()?;
+        let mut names: Vec<String> = all_auths.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn get_all_auth(&self) -> Result<Vec<StoredAuth>, StorageError> {
+        let all_auths = self.load_all_auth_data()?;
+        let mut auths = all_auths
+            .into_values()
+            .map(|auth| self.with_auth_secrets(auth))
+            .collect::<Result<Vec<_>, _>>()?;
+        auths.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(auths)
+    }
+
+    /// Fills in credentials held in the secret store. Entries written before
+    /// secrets were split out keep their inline values.
+    fn with_auth_secrets(&self, mut auth: StoredAuth) -> Result<StoredAuth, StorageError> {
+        let lookup = |field| {
+            self.secrets
+                .get_optional(&auth_secret_name(&auth.name, field))
+        };
+        let client_secret = lookup("client_secret")?;
+        let access_token = lookup("access_token")?;
+        let refresh_token = lookup("refresh_token")?;
+        auth.client_secret = client_secret.or(auth.client_secret);
+        auth.access_token = access_token.or(auth.access_token);
+        auth.refresh_token = refresh_token.or(auth.refresh_token);
+        Ok(auth)
+    }
+
+    /// Removes a secret, ignoring secrets that were never stored.
+    fn remove_secret_if_present(&self, name: &str) -> Result<(), StorageError> {
+        match self.secrets.remove(name) {
+            Ok(()) | Err(StorageError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stores connection settings to be reused for a target in `targets.json`.
+    ///
+    /// Header values, which often carry credentials, are kept in the secret
+    /// store, and only `{secret:NAME}` references to them are written out.
+    /// Values that already use references are written as they are.
+    pub fn store_target_settings(
+        &self,
+        target: &str,
+        settings: &ConnectOptions,
+    ) -> Result<(), StorageError> {
+        let settings = self.with_header_secrets(target, settings.clone())?;
+        let mut all_settings = self.load_all_target_settings()?;
+        all_settings.insert(target.to_string(), settings);
+
+        let json = serde_json::to_string_pretty(&all_settings)?;
+        fs::write(self.config_dir.join("targets.json"), json)?;
+
+        Ok(())
+    }
+
+    /// Returns the connection settings stored for a target in `targets.json`,
+    /// if any.
+    pub fn get_target_settings(
+        &self,
+        target: &str,
+    ) -> Result<Option<ConnectOptions>, StorageError> {
+        Ok(self.load_all_target_settings()?.remove(target))
+    }
+
+    /// Stores connection settings with a named target, replacing any it had.
+    /// Header values are moved to the secret store, as for other targets.
+    pub fn store_named_target_settings(
+        &self,
+        name: &str,
+        settings: &ConnectOptions,
+    ) -> Result<(), StorageError> {
+        let mut all_targets = self.load_all_saved_targets()?;
+        let saved = all_targets
+            .get_mut(name)
+            .ok_or_else(|| StorageError::NotFound(name.to_string()))?;
+        saved.settings = Some(self.with_header_secrets(name, settings.clone())?);
+        self.write_saved_targets(&all_targets)
+    }
+
+    /// Returns the connection settings of a named target. Targets saved before
+    /// settings were kept with them fall back to `targets.json`.
+    pub fn get_named_target_settings(
+        &self,
+        saved: &StoredTarget,
+    ) -> Result<Option<ConnectOptions>, StorageError> {
+        match &saved.settings {
+            Some(settings) => Ok(Some(settings.clone())),
+            None => Ok(self.load_all_target_settings()?.remove(&saved.target)),
+        }
+    }
+
+    /// Saves a named target, replacing any target with the same name. Header
+    /// values in its settings are moved to the secret store.
+    pub fn store_target(&self, target: &StoredTarget) -> Result<(), StorageError> {
+        let mut target = target.clone();
+        if let Some(settings) = target.settings.take() {
+            target.settings = Some(self.with_header_secrets(&target.name, settings)?);
+        }
+        let mut all_targets = self.load_all_saved_targets()?;
+        all_targets.insert(target.name.clone(), target);
+        self.write_saved_targets(&all_targets)
+    }
+
+    /// Moves literal header values into the secret store, leaving
+    /// `{secret:NAME}` references in their place. `owner` is the target or
+    /// target name the secrets are named after.
+    fn with_header_secrets(
+        &self,
+        owner: &str,
+        mut settings: ConnectOptions,
+    ) -> Result<ConnectOptions, StorageError> {
+        for (header, value) in &mut settings.headers {
+            if value.contains("{secret:") {
+                continue;
+            }
+            let secret_name = header_secret_name(owner, header);
+            self.secrets.set(&secret_name, value)?;
+            *value = format!("{{secret:{secret_name}}}");
+        }
+        Ok(settings)
+    }
+
+    pub fn get_target(&self, name: &str) -> Result<StoredTarget, StorageError> {
+        self.load_all_saved_targets()?
+            .remove(name)
+            .ok_or_else(|| StorageError::NotFound(name.to_string()))
+    }
+
+    pub fn remove_target(&self, name: &str) -> Result<(), StorageError> {
+        let mut all_targets = self.load_all_saved_targets()?;
+        if all_targets.remove(name).is_none() {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+        self.write_saved_targets(&all_targets)
+    }
+
+    /// Returns all named targets, sorted by name.
+    pub fn get_all_targets(&self) -> Result<Vec<StoredTarget>, StorageError> {
+        let mut targets: Vec<StoredTarget> = self.load_all_saved_targets()?.into_values().collect();
+        targets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(targets)
+    }
+
+    /// Saves a call template, replacing any template with the same name.
+    pub fn store_template(&self, template: &StoredTemplate) -> Result<(), StorageError> {
+        let mut all_templates = self.load_all_templates()?;
+        all_templates.insert(template.name.clone(), template.clone());
+        self.write_templates(&all_templates)
+    }
+
+    pub fn get_template(&self, name: &str) -> Result<StoredTemplate, StorageError> {
+        self.load_all_templates()?
+            .remove(name)
+            .ok_or_else(|| StorageError::NotFound(name.to_string()))
+    }
+
+    pub fn remove_template(&self, name: &str) -> Result<(), StorageError> {
+        let mut all_templates = self.load_all_templates()?;
+        if all_templates.remove(name).is_none() {
+            return Err(StorageError::NotFound(name.to_string()));
+        }
+        self.write_templates(&all_templates)
+    }
+
+    /// Returns all call templates, sorted by name.
+    pub fn get_all_templates(&self) -> Result<Vec<StoredTemplate>, StorageError> {
+        Ok(self.load_all_templates()?.into_values().collect())
+    }
+
+    /// Returns the user's preferences, or the defaults if there is no
+    /// config file.
+    pub fn get_config(&self) -> Result<Config, StorageError> {
+        let path = self.config_dir.join("config.json");
+
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Returns the REPL command aliases, by name.
+    pub fn get_aliases(&self) -> Result<BTreeMap<String, String>, StorageError> {
+        let path = self.config_dir.join("aliases.json");
+
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Replaces the REPL command aliases.
+    pub fn store_aliases(&self, aliases: &BTreeMap<String, String>) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(aliases)?;
+        fs::write(self.config_dir.join("aliases.json"), json)?;
+        Ok(())
+    }
+
+    /// Loads all named targets.
+    fn load_all_saved_targets(&self) -> Result<HashMap<String, StoredTarget>, StorageError> {
+        let path = self.config_dir.join("saved_targets.json");
+
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Writes all named targets to the storage file.
+    fn write_saved_targets(
+        &self,
+        targets: &HashMap<String, StoredTarget>,
+    ) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(targets)?;
+        fs::write(self.config_dir.join("saved_targets.json"), json)?;
+        Ok(())
+    }
+
+    /// Loads all call templates.
+    fn load_all_templates(&self) -> Result<BTreeMap<String, StoredTemplate>, StorageError> {
+        let path = self.config_dir.join("templates.json");
+
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Writes all call templates to the storage file.
+    fn write_templates(
+        &self,
+        templates: &BTreeMap<String, StoredTemplate>,
+    ) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(templates)?;
+        fs::write(self.config_dir.join("templates.json"), json)?;
+        Ok(())
+    }
+
+    /// Loads all stored per-target connection settings.
+    fn load_all_target_settings(&self) -> Result<HashMap<String, ConnectOptions>, StorageError> {
+        let path = self.config_dir.join("targets.json");
+
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Loads all authentication data from the storage file.
+    fn load_all_auth_data(&self) -> Result<HashMap<String, StoredAuth>, StorageError> {
+        let auth_path = self.config_dir.join("auth.json");
+
+        if !auth_path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read_to_string(auth_path)?;
+        let auths: HashMap<String, StoredAuth> = serde_json::from_str(&contents)?;
+        Ok(auths)
+    }
+}
+
+/// Name of the secret holding a credential of an authentication entry.
+fn auth_secret_name(auth_name: &str, field: &str) -> String {
+    format!("auth.{auth_name}.{field}")
+}
+
+/// Name of the secret holding the value of a header stored for a target or
+/// named target.
+fn header_secret_name(owner: &str, header: &str) -> String {
+    format!("target.{owner}.header.{}", header.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, time::UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn test_storage_lifecycle() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "test_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        let storage = TokenStorage::new(test_dir.clone())
+            .expect("Failed to create storage")
+            .with_secrets(SecretStore::encrypted_file(test_dir.clone()));
+
+        // Create test auth
+        let auth = StoredAuth {
+            name: "test_auth".to_string(),
+            server_url: "https://example.com".to_string(),
+            client_id: "test_client".to_string(),
+            client_secret: Some("test_secret".to_string()),
+            access_token: Some("test_token".to_string()),
+            refresh_token: Some("test_refresh".to_string()),
+            expires_at: Some(SystemTime::now()),
+            auth_url: "https://example.com/auth".to_string(),
+            token_url: "https://example.com/token".to_string(),
+            redirect_url: Some("http://localhost:8080".to_string()),
+            scopes: vec!["read".to_string(), "write".to_string()],
+            resource: Some("https://example.com".to_string()),
+        };
+
+        // Store
+        storage.store_auth(&auth).expect("Failed to store auth");
+
+        // List
+        let names = storage.list_auth().expect("Failed to list auth");
+        assert!(names.contains(&"test_auth".to_string()));
+
+        // Get
+        let retrieved = storage.get_auth("test_auth").expect("Failed to get auth");
+        assert_eq!(retrieved.name, auth.name);
+        assert_eq!(retrieved.server_url, auth.server_url);
+        assert_eq!(retrieved.access_token, auth.access_token);
+        assert_eq!(retrieved.refresh_token, auth.refresh_token);
+        assert_eq!(retrieved.resource, auth.resource);
+
+        // Credentials are not written to the auth file in plain text
+        let auth_file = fs::read_to_string(test_dir.join("auth.json")).unwrap();
+        assert!(!auth_file.contains("test_token"));
+        assert!(!auth_file.contains("test_secret"));
+
+        // Remove
+        storage
+            .remove_auth("test_auth")
+            .expect("Failed to remove auth");
+
+        // Verify removed
+        let names = storage.list_auth().expect("Failed to list auth");
+        assert!(!names.contains(&"test_auth".to_string()));
+    }
+
+    #[test]
+    fn test_target_settings_roundtrip() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "settings_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir.clone())
+            .expect("Failed to create storage")
+            .with_secrets(SecretStore::encrypted_file(test_dir.clone()));
+
+        assert_eq!(storage.get_target_settings("tls://prod:443").unwrap(), None);
+
+        let mut settings = ConnectOptions::default();
+        settings.tls.client_cert = Some(PathBuf::from("/certs/client.pem"));
+        settings.tls.client_key = Some(PathBuf::from("/certs/client.key"));
+        storage
+            .store_target_settings("tls://prod:443", &settings)
+            .expect("Failed to store settings");
+
+        let retrieved = storage
+            .get_target_settings("tls://prod:443")
+            .expect("Failed to get settings");
+        assert_eq!(retrieved, Some(settings));
+
+        // Header values are moved to the secret store
+        let mut settings = ConnectOptions::default();
+        settings
+            .headers
+            .insert("Authorization".to_string(), "Bearer abc123".to_string());
+        settings
+            .headers
+            .insert("X-Key".to_string(), "{secret:api-key}".to_string());
+        storage
+            .store_target_settings("https://api:443", &settings)
+            .expect("Failed to store settings");
+        let file = fs::read_to_string(test_dir.join("targets.json")).unwrap();
+        assert!(!file.contains("abc123"));
+        let headers = storage
+            .get_target_settings("https://api:443")
+            .unwrap()
+            .unwrap()
+            .headers;
+        assert_eq!(
+            headers["Authorization"],
+            "{secret:target.https://api:443.header.authorization}"
+        );
+        assert_eq!(headers["X-Key"], "{secret:api-key}");
+        assert_eq!(
+            storage.secrets().expand(&headers["Authorization"]).unwrap(),
+            "Bearer abc123"
+        );
+    }
+
+    #[test]
+    fn test_named_target_settings() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "named_settings_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir.clone())
+            .expect("Failed to create storage")
+            .with_secrets(SecretStore::encrypted_file(test_dir.clone()));
+        for name in ["staging", "production"] {
+            storage
+                .store_target(&StoredTarget {
+                    name: name.to_string(),
+                    target: "cmd://./server".to_string(),
+                    settings: None,
+                })
+                .expect("Failed to store");
+        }
+
+        // Settings are kept with the named target they were saved for, even
+        // when another name has the same target
+        let mut settings = ConnectOptions::default();
+        settings.cwd = Some(PathBuf::from("/srv/mcp"));
+        storage
+            .store_named_target_settings("staging", &settings)
+            .expect("Failed to store settings");
+        assert!(!test_dir.join("targets.json").exists());
+        assert_eq!(
+            storage.get_target("staging").unwrap().settings,
+            Some(settings.clone())
+        );
+        assert_eq!(storage.get_target("production").unwrap().settings, None);
+        assert_eq!(storage.get_target_settings("cmd://./server").unwrap(), None);
+
+        // Settings for the bare target don't touch the named ones
+        let mut bare = ConnectOptions::default();
+        bare.cwd = Some(PathBuf::from("/tmp"));
+        storage
+            .store_target_settings("cmd://./server", &bare)
+            .expect("Failed to store settings");
+        assert_eq!(
+            storage.get_target_settings("cmd://./server").unwrap(),
+            Some(bare)
+        );
+        assert_eq!(
+            storage.get_target("staging").unwrap().settings,
+            Some(settings)
+        );
+        assert!(
+            storage
+                .store_named_target_settings("missing", &ConnectOptions::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_saved_targets_lifecycle() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "saved_targets_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir).expect("Failed to create storage");
+
+        assert!(matches!(
+            storage.get_target("staging"),
+            Err(StorageError::NotFound(_))
+        ));
+
+        let staging = StoredTarget {
+            name: "staging".to_string(),
+            target: "tcp://10.0.0.5:9000".to_string(),
+            settings: None,
+        };
+        let local = StoredTarget {
+            name: "local".to_string(),
+            target: "cmd://./server".to_string(),
+            settings: None,
+        };
+        storage.store_target(&staging).expect("Failed to store");
+        storage.store_target(&local).expect("Failed to store");
+
+        assert_eq!(storage.get_target("staging").unwrap(), staging);
+        let names: Vec<String> = storage
+            .get_all_targets()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["local", "staging"]);
+
+        storage.remove_target("staging").expect("Failed to remove");
+        assert!(storage.get_target("staging").is_err());
+        assert!(storage.remove_target("staging").is_err());
+    }
+
+    #[test]
+    fn test_templates_lifecycle() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "templates_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir).expect("Failed to create storage");
+        assert!(storage.get_all_templates().unwrap().is_empty());
+
+        let report = StoredTemplate {
+            name: "nightly-report".to_string(),
+            tool: "generate_report".to_string(),
+            args: vec!["period=daily".to_string(), "limit:=10".to_string()],
+        };
+        let cleanup = StoredTemplate {
+            name: "cleanup".to_string(),
+            tool: "prune".to_string(),
+            args: Vec::new(),
+        };
+        storage.store_template(&report).expect("Failed to store");
+        storage.store_template(&cleanup).expect("Failed to store");
+
+        assert_eq!(storage.get_template("nightly-report").unwrap(), report);
+        let names: Vec<String> = storage
+            .get_all_templates()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["cleanup", "nightly-report"]);
+
+        storage
+            .remove_template("cleanup")
+            .expect("Failed to remove");
+        assert!(matches!(
+            storage.get_template("cleanup"),
+            Err(StorageError::NotFound(_))
+        ));
+        assert!(storage.remove_template("cleanup").is_err());
+    }
+
+    #[test]
+    fn test_aliases_roundtrip() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "aliases_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir).expect("Failed to create storage");
+        assert!(storage.get_aliases().unwrap().is_empty());
+
+        let aliases = BTreeMap::from([
+            ("ls".to_string(), "listtools".to_string()),
+            ("rt".to_string(), "readresource".to_string()),
+        ]);
+        storage.store_aliases(&aliases).expect("Failed to store");
+        assert_eq!(storage.get_aliases().unwrap(), aliases);
+    }
+
+    #[test]
+    fn test_config() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "config_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir.clone()).expect("Failed to create storage");
+        assert_eq!(storage.get_config().unwrap(), Config::default());
+
+        fs::write(test_dir.join("config.json"), r#"{"theme": "light"}"#).unwrap();
+        assert_eq!(storage.get_config().unwrap().theme, Some(Theme::Light));
+    }
+}
@@ -0,0 +1,311 @@
+//! Named secrets kept in the OS keyring, with an encrypted file fallback for
+//! systems where no keyring is available.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    AeadCore, ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, OsRng},
+};
+use keyring::{
+    Entry,
+    Error::{NoEntry, NoStorageAccess, PlatformFailure},
+};
+use serde::{Deserialize, Serialize};
+
+use super::StorageError;
+
+/// Keyring service name under which all secrets are stored.
+const SERVICE: &str = "mcptool";
+
+/// Where a secret's value lives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+enum SecretRecord {
+    /// The value is held by the OS keyring.
+    Keyring,
+    /// The value is encrypted in the secrets file.
+    File {
+        /// Base64-encoded nonce.
+        nonce: String,
+        /// Base64-encoded ciphertext.
+        ciphertext: String,
+    },
+}
+
+/// Store for named secrets such as bearer tokens and OAuth refresh tokens.
+///
+/// An index of secret names is kept in `secrets.json` so secrets can be
+/// listed. Values go to the OS keyring when one is available; otherwise they
+/// are encrypted into the index using a key kept in `secrets.key`, readable
+/// only by the current user.
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    /// Directory holding the secrets index and fallback key.
+    dir: PathBuf,
+    /// Whether to try the OS keyring before falling back to the file.
+    use_keyring: bool,
+}
+
+impl SecretStore {
+    /// Creates a store that prefers the OS keyring.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            use_keyring: true,
+        }
+    }
+
+    /// Creates a store that only uses the encrypted file.
+    pub fn encrypted_file(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            use_keyring: false,
+        }
+    }
+
+    /// Stores a secret, replacing any existing value.
+    pub fn set(&self, name: &str, value: &str) -> Result<(), StorageError> {
+        let mut index = self.load_index()?;
+        let record = if self.set_keyring(name, value)? {
+            SecretRecord::Keyring
+        } else {
+            self.encrypt(value)?
+        };
+        index.insert(name.to_string(), record);
+        self.save_index(&index)
+    }
+
+    /// Retrieves a secret's value.
+    pub fn get(&self, name: &str) -> Result<String, StorageError> {
+        self.get_optional(name)?
+            .ok_or_else(|| StorageError::NotFound(name.to_string()))
+    }
+
+    /// Retrieves a secret's value, returning `None` if it does not exist.
+    pub fn get_optional(&self, name: &str) -> Result<Option<String>, StorageError> {
+        let index = self.load_index()?;
+        match index.get(name) {
+            None => Ok(None),
+            Some(SecretRecord::Keyring) => match Entry::new(SERVICE, name)?.get_password() {
+                Ok(value) => Ok(Some(value)),
+                Err(NoEntry) => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Some(SecretRecord::File { nonce, ciphertext }) => {
+                self.decrypt(nonce, ciphertext).map(Some)
+            }
+        }
+    }
+
+    /// Removes a secret. Removing a secret that does not exist is an error.
+    pub fn remove(&self, name: &str) -> Result<(), StorageError> {
+        let mut index = self.load_index()?;
+        match index.remove(name) {
+            None => return Err(StorageError::NotFound(name.to_string())),
+            Some(SecretRecord::Keyring) => match Entry::new(SERVICE, name)?.delete_credential() {
+                Ok(()) | Err(NoEntry) => {}
+                Err(e) => return Err(e.into()),
+            },
+            Some(SecretRecord::File { .. }) => {}
+        }
+        self.save_index(&index)
+    }
+
+    /// Returns the names of all stored secrets, sorted.
+    pub fn list(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.load_index()?.into_keys().collect())
+    }
+
+    /// Replaces `{secret:NAME}` references in the input with secret values.
+    pub fn expand(&self, input: &str) -> Result<String, StorageError> {
+        expand_references(input, |name| self.get(name))
+    }
+
+    /// Tries to store a secret in the keyring, returning false if no keyring is
+    /// available.
+    fn set_keyring(&self, name: &str, value: &str) -> Result<bool, StorageError> {
+        if !self.use_keyring {
+            return Ok(false);
+        }
+        match Entry::new(SERVICE, name).and_then(|entry| entry.set_password(value)) {
+            Ok(()) => Ok(true),
+            Err(PlatformFailure(_) | NoStorageAccess(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Encrypts a value for the secrets file.
+    fn encrypt(&self, value: &str) -> Result<SecretRecord, StorageError> {
+        let cipher = self.cipher()?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|e| StorageError::Secret(format!("Encryption failed: {e}")))?;
+        Ok(SecretRecord::File {
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    /// Decrypts a value from the secrets file.
+    fn decrypt(&self, nonce: &str, ciphertext: &str) -> Result<String, StorageError> {
+        let decode = |data: &str| {
+            STANDARD
+                .decode(data)
+                .map_err(|e| StorageError::Secret(e.to_string()))
+        };
+        let nonce = decode(nonce)?;
+        if nonce.len() != 12 {
+            return Err(StorageError::Secret("Invalid nonce length".to_string()));
+        }
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(&nonce), decode(ciphertext)?.as_slice())
+            .map_err(|_| StorageError::Secret("Failed to decrypt secret".to_string()))?;
+        String::from_utf8(plaintext).map_err(|e| StorageError::Secret(e.to_string()))
+    }
+
+    /// Loads the fallback encryption key, generating it on first use.
+    fn cipher(&self) -> Result<ChaCha20Poly1305, StorageError> {
+        let key_path = self.dir.join("secrets.key");
+        let key = if key_path.exists() {
+            let bytes = fs::read(&key_path)?;
+            if bytes.len() != 32 {
+                return Err(StorageError::Secret(format!(
+                    "Invalid key file: {}",
+                    key_path.display()
+                )));
+            }
+            *Key::from_slice(&bytes)
+        } else {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            write_private(&key_path, &key)?;
+            key
+        };
+        Ok(ChaCha20Poly1305::new(&key))
+    }
+
+    /// Loads the secrets index.
+    fn load_index(&self) -> Result<BTreeMap<String, SecretRecord>, StorageError> {
+        let path = self.dir.join("secrets.json");
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Writes the secrets index.
+    fn save_index(&self, index: &BTreeMap<String, SecretRecord>) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(index)?;
+        write_private(&self.dir.join("secrets.json"), json.as_bytes())
+    }
+}
+
+/// Writes a file that only the current user can read.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), StorageError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+/// Replaces `{secret:NAME}` references using the given lookup.
+fn expand_references<F>(input: &str, mut lookup: F) -> Result<String, StorageError>
+where
+    F: FnMut(&str) -> Result<String, StorageError>,
+{
+    const PREFIX: &str = "{secret:";
+    let mut output = String::new();
+    let mut rest = input;
+    while let Some(start) = rest.find(PREFIX) {
+        let after = &rest[start + PREFIX.len()..];
+        let end = after.find('}').ok_or_else(|| {
+            StorageError::Secret(format!("Unterminated secret reference in '{input}'"))
+        })?;
+        output.push_str(&rest[..start]);
+        output.push_str(&lookup(&after[..end])?);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, time::SystemTime};
+
+    use super::*;
+
+    fn lookup(name: &str) -> Result<String, StorageError> {
+        match name {
+            "token" => Ok("abc123".to_string()),
+            _ => Err(StorageError::NotFound(name.to_string())),
+        }
+    }
+
+    #[test]
+    fn test_expand_references() {
+        assert_eq!(
+            expand_references("Bearer {secret:token}", lookup).unwrap(),
+            "Bearer abc123"
+        );
+        assert_eq!(
+            expand_references("{secret:token}:{secret:token}", lookup).unwrap(),
+            "abc123:abc123"
+        );
+        assert_eq!(
+            expand_references("no references", lookup).unwrap(),
+            "no references"
+        );
+        assert!(matches!(
+            expand_references("{secret:missing}", lookup),
+            Err(StorageError::NotFound(_))
+        ));
+        assert!(matches!(
+            expand_references("{secret:token", lookup),
+            Err(StorageError::Secret(_))
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_file_roundtrip() {
+        let dir = env::temp_dir().join("mcptool_test").join(format!(
+            "secrets_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let store = SecretStore::encrypted_file(dir.clone());
+
+        store.set("github", "ghp_secret").unwrap();
+        assert_eq!(store.get("github").unwrap(), "ghp_secret");
+        assert_eq!(store.list().unwrap(), vec!["github".to_string()]);
+
+        // The value must not be stored in plain text
+        let index = fs::read_to_string(dir.join("secrets.json")).unwrap();
+        assert!(!index.contains("ghp_secret"));
+
+        store.remove("github").unwrap();
+        assert_eq!(store.get_optional("github").unwrap(), None);
+        assert!(matches!(
+            store.remove("github"),
+            Err(StorageError::NotFound(_))
+        ));
+    }
+}
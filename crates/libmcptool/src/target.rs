@@ -1,22 +1,60 @@
 use std::fmt;
 
-use crate::{Error, Result, auth::validate_auth_name};
+use crate::{
+    Error, Result,
+    auth::validate_auth_name,
+    storage::{StorageError, TokenStorage},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Target {
-    Tcp { host: String, port: u16 },
-    Stdio { command: String, args: Vec<String> },
-    Http { host: String, port: u16 },
-    Https { host: String, port: u16 },
-    Auth { name: String },
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    Tls {
+        host: String,
+        port: u16,
+    },
+    Stdio {
+        command: String,
+        args: Vec<String>,
+    },
+    Ssh {
+        user: Option<String>,
+        host: String,
+        port: Option<u16>,
+        command: String,
+    },
+    Http {
+        host: String,
+        port: u16,
+    },
+    Https {
+        host: String,
+        port: u16,
+    },
+    Auth {
+        name: String,
+    },
+    /// A target saved under a name, connected to with the settings saved
+    /// with it.
+    Named {
+        name: String,
+        target: Box<Target>,
+    },
 }
 
 impl Target {
     pub fn parse(input: &str) -> Result<Self> {
         if let Some(remainder) = input.strip_prefix("tcp://") {
             Self::parse_tcp(remainder)
+        } else if let Some(remainder) = input.strip_prefix("tls://") {
+            Self::parse_host_port(remainder, "TLS").map(|(host, port)| Self::Tls { host, port })
         } else if let Some(remainder) = input.strip_prefix("cmd://") {
             Self::parse_stdio(remainder)
+        } else if let Some(remainder) = input.strip_prefix("ssh://") {
+            Self::parse_ssh(remainder)
         } else if let Some(remainder) = input.strip_prefix("https://") {
             Self::parse_https(remainder)
         } else if let Some(remainder) = input.strip_prefix("http://") {
@@ -29,8 +67,47 @@ impl Target {
         }
     }
 
+    /// Parses a target, first checking whether the input names a saved target.
+    pub fn resolve(input: &str, storage: &TokenStorage) -> Result<Self> {
+        match storage.get_target(input) {
+            Ok(saved) => Ok(Self::Named {
+                target: Box::new(Self::parse(&saved.target)?),
+                name: saved.name,
+            }),
+            Err(StorageError::NotFound(_)) => Self::parse(input),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The target itself, without the name it was saved under.
+    pub fn unnamed(&self) -> &Self {
+        match self {
+            Self::Named { target, .. } => target,
+            target => target,
+        }
+    }
+
+    /// Builds a stdio target from an already-split command line, such as the
+    /// trailing arguments after `--` on the command line.
+    pub fn from_command(mut command: Vec<String>) -> Result<Self> {
+        if command.is_empty() {
+            return Err(Error::Format("Empty command specification".to_string()));
+        }
+        let args = command.split_off(1);
+        Ok(Self::Stdio {
+            command: command.remove(0),
+            args,
+        })
+    }
+
     /// Parses a TCP target specification from the given input string.
     fn parse_tcp(input: &str) -> Result<Self> {
+        Self::parse_host_port(input, "TCP").map(|(host, port)| Self::Tcp { host, port })
+    }
+
+    /// Parses a `host:port` pair where the port is mandatory. `kind` names the
+    /// target type in errors.
+    fn parse_host_port(input: &str, kind: &str) -> Result<(String, u16)> {
         if input.is_empty() {
             return Err(Error::Format("Empty host specification".to_string()));
         }
@@ -45,10 +122,7 @@ impl Target {
             let port = port_str
                 .parse::<u16>()
                 .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-            return Ok(Self::Tcp {
-                host: "0.0.0.0".to_string(),
-                port,
-            });
+            return Ok(("0.0.0.0".to_string(), port));
         }
 
         // Handle IPv6 addresses in brackets
@@ -58,14 +132,14 @@ impl Target {
                 let remainder = &input[end + 1..];
 
                 if remainder.is_empty() {
-                    return Err(Error::Format(
-                        "Port is required for TCP targets".to_string(),
-                    ));
+                    return Err(Error::Format(format!(
+                        "Port is required for {kind} targets"
+                    )));
                 } else if let Some(port_str) = remainder.strip_prefix(':') {
                     let port = port_str
                         .parse::<u16>()
                         .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-                    return Ok(Self::Tcp { host, port });
+                    return Ok((host, port));
                 } else {
                     return Err(Error::Format(
                         "Invalid character after IPv6 address".to_string(),
@@ -84,21 +158,21 @@ impl Target {
             // Check if this might be part of an IPv6 address without brackets
             if host.contains(':') {
                 // This is likely an IPv6 address without brackets and no port
-                Err(Error::Format(
-                    "Port is required for TCP targets".to_string(),
-                ))
+                Err(Error::Format(format!(
+                    "Port is required for {kind} targets"
+                )))
             } else if port_str.is_empty() {
                 Err(Error::Format("Empty port specification".to_string()))
             } else {
                 let port = port_str
                     .parse::<u16>()
                     .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-                Ok(Self::Tcp { host, port })
+                Ok((host, port))
             }
         } else {
-            Err(Error::Format(
-                "Port is required for TCP targets".to_string(),
-            ))
+            Err(Error::Format(format!(
+                "Port is required for {kind} targets"
+            )))
         }
     }
 
@@ -122,6 +196,48 @@ impl Target {
         Ok(Self::Stdio { command, args })
     }
 
+    /// Parses an SSH target of the form `[user@]host[:port]/command`. The
+    /// command line after the first `/` is run by the remote shell, so
+    /// `host//opt/bin/server` names an absolute path.
+    fn parse_ssh(input: &str) -> Result<Self> {
+        let (authority, command) = input.split_once('/').unwrap_or((input, ""));
+        if command.trim().is_empty() {
+            return Err(Error::Format(
+                "SSH targets need a command, e.g. ssh://user@host/path/to/server".to_string(),
+            ));
+        }
+
+        let (user, host_port) = match authority.rsplit_once('@') {
+            Some((user, _)) if user.is_empty() => {
+                return Err(Error::Format("Empty user specification".to_string()));
+            }
+            Some((user, host_port)) => (Some(user.to_string()), host_port),
+            None => (None, authority),
+        };
+        if host_port.is_empty() {
+            return Err(Error::Format("Empty host specification".to_string()));
+        }
+
+        let (host, port) = if let Some(host) = host_port
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            (host.to_string(), None)
+        } else if host_port.starts_with('[') || host_port.contains(':') {
+            let (host, port) = Self::parse_host_port(host_port, "SSH")?;
+            (host, Some(port))
+        } else {
+            (host_port.to_string(), None)
+        };
+
+        Ok(Self::Ssh {
+            user,
+            host,
+            port,
+            command: command.to_string(),
+        })
+    }
+
     /// Parses an HTTP target specification from the given input string.
     fn parse_http(input: &str) -> Result<Self> {
         Self::parse_http_common(input, 80, |host, port| Self::Http { host, port })
@@ -132,11 +248,22 @@ impl Target {
         Self::parse_http_common(input, 443, |host, port| Self::Https { host, port })
     }
 
-    /// Common parsing logic for HTTP and HTTPS targets.
+    /// Common parsing logic for HTTP and HTTPS targets. A trailing `/` is
+    /// allowed, but other paths can't be given.
     fn parse_http_common<F>(input: &str, default_port: u16, constructor: F) -> Result<Self>
     where
         F: Fn(String, u16) -> Self,
     {
+        let input = match input.find('/') {
+            Some(slash) if &input[slash..] == "/" => &input[..slash],
+            Some(slash) => {
+                return Err(Error::Format(format!(
+                    "HTTP targets can't have a path: {}",
+                    &input[slash..]
+                )));
+            }
+            None => input,
+        };
         if input.is_empty() {
             return Err(Error::Format("Empty host specification".to_string()));
         }
@@ -213,6 +340,13 @@ impl fmt::Display for Target {
                     write!(f, "tcp://{host}:{port}")
                 }
             }
+            Self::Tls { host, port } => {
+                if host.contains(':') && !host.starts_with('[') {
+                    write!(f, "tls://[{host}]:{port}")
+                } else {
+                    write!(f, "tls://{host}:{port}")
+                }
+            }
             Self::Stdio { command, args } => {
                 if args.is_empty() {
                     write!(f, "cmd://{command}")
@@ -220,6 +354,26 @@ impl fmt::Display for Target {
                     write!(f, "cmd://{} {}", command, shell_words::join(args))
                 }
             }
+            Self::Ssh {
+                user,
+                host,
+                port,
+                command,
+            } => {
+                write!(f, "ssh://")?;
+                if let Some(user) = user {
+                    write!(f, "{user}@")?;
+                }
+                if host.contains(':') && !host.starts_with('[') {
+                    write!(f, "[{host}]")?;
+                } else {
+                    write!(f, "{host}")?;
+                }
+                if let Some(port) = port {
+                    write!(f, ":{port}")?;
+                }
+                write!(f, "/{command}")
+            }
             Self::Http { host, port } => {
                 // Check if host is an IPv6 address
                 if host.contains(':') && !host.starts_with('[') {
@@ -251,13 +405,20 @@ impl fmt::Display for Target {
             Self::Auth { name } => {
                 write!(f, "auth://{name}")
             }
+            Self::Named { target, .. } => target.fmt(f),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        env, process,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
     use super::*;
+    use crate::storage::StoredTarget;
 
     fn format_err(msg: &str) -> Error {
         Error::Format(msg.to_string())
@@ -325,6 +486,28 @@ mod tests {
                 }),
                 description: "explicit TCP with port",
             },
+            // TLS
+            TestCase {
+                input: "tls://example.com:8443",
+                expected: Ok(Target::Tls {
+                    host: "example.com".to_string(),
+                    port: 8443,
+                }),
+                description: "TLS with port",
+            },
+            TestCase {
+                input: "tls://[::1]:8443",
+                expected: Ok(Target::Tls {
+                    host: "::1".to_string(),
+                    port: 8443,
+                }),
+                description: "TLS with IPv6 and port",
+            },
+            TestCase {
+                input: "tls://example.com",
+                expected: Err(format_err("Port is required for TLS targets")),
+                description: "TLS without port",
+            },
             // IPv6
             TestCase {
                 input: "[::1]",
@@ -407,6 +590,69 @@ mod tests {
                 expected: Err(format_err("Empty command specification")),
                 description: "stdio scheme without command",
             },
+            // SSH
+            TestCase {
+                input: "ssh://alice@box.example.com/bin/mcp-server --verbose",
+                expected: Ok(Target::Ssh {
+                    user: Some("alice".to_string()),
+                    host: "box.example.com".to_string(),
+                    port: None,
+                    command: "bin/mcp-server --verbose".to_string(),
+                }),
+                description: "SSH with user and relative command",
+            },
+            TestCase {
+                input: "ssh://box:2222//opt/mcp/server",
+                expected: Ok(Target::Ssh {
+                    user: None,
+                    host: "box".to_string(),
+                    port: Some(2222),
+                    command: "/opt/mcp/server".to_string(),
+                }),
+                description: "SSH with port and absolute command",
+            },
+            TestCase {
+                input: "ssh://bob@[::1]:22/server",
+                expected: Ok(Target::Ssh {
+                    user: Some("bob".to_string()),
+                    host: "::1".to_string(),
+                    port: Some(22),
+                    command: "server".to_string(),
+                }),
+                description: "SSH with IPv6 host and port",
+            },
+            TestCase {
+                input: "ssh://[::1]/server",
+                expected: Ok(Target::Ssh {
+                    user: None,
+                    host: "::1".to_string(),
+                    port: None,
+                    command: "server".to_string(),
+                }),
+                description: "SSH with IPv6 host without port",
+            },
+            TestCase {
+                input: "ssh://alice@box",
+                expected: Err(format_err(
+                    "SSH targets need a command, e.g. ssh://user@host/path/to/server",
+                )),
+                description: "SSH without command",
+            },
+            TestCase {
+                input: "ssh://alice@/server",
+                expected: Err(format_err("Empty host specification")),
+                description: "SSH without host",
+            },
+            TestCase {
+                input: "ssh://@box/server",
+                expected: Err(format_err("Empty user specification")),
+                description: "SSH with empty user",
+            },
+            TestCase {
+                input: "ssh://box:abc/server",
+                expected: Err(format_err("Invalid port: abc")),
+                description: "SSH invalid port",
+            },
             TestCase {
                 input: "example.com:",
                 expected: Err(format_err("Empty port specification")),
@@ -580,6 +826,24 @@ mod tests {
                 expected: Err(format_err("Unclosed IPv6 address bracket")),
                 description: "HTTP unclosed IPv6 bracket",
             },
+            TestCase {
+                input: "http://localhost:3000/mcp",
+                expected: Err(format_err("HTTP targets can't have a path: /mcp")),
+                description: "HTTP with a path",
+            },
+            TestCase {
+                input: "https://example.com/mcp",
+                expected: Err(format_err("HTTP targets can't have a path: /mcp")),
+                description: "HTTPS with a path and no port",
+            },
+            TestCase {
+                input: "https://example.com:8443/",
+                expected: Ok(Target::Https {
+                    host: "example.com".to_string(),
+                    port: 8443,
+                }),
+                description: "HTTPS with a trailing slash",
+            },
         ];
 
         for test_case in test_cases {
@@ -616,6 +880,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_target_resolve_prefers_saved_names() {
+        let test_dir = env::temp_dir().join("mcptool_test").join(format!(
+            "resolve_{}_{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let storage = TokenStorage::new(test_dir).unwrap();
+        storage
+            .store_target(&StoredTarget {
+                name: "staging".to_string(),
+                target: "tcp://10.0.0.5:9000".to_string(),
+                settings: None,
+            })
+            .unwrap();
+
+        let staging = Target::resolve("staging", &storage).unwrap();
+        assert_eq!(
+            staging,
+            Target::Named {
+                name: "staging".to_string(),
+                target: Box::new(Target::Tcp {
+                    host: "10.0.0.5".to_string(),
+                    port: 9000,
+                }),
+            }
+        );
+        assert_eq!(staging.to_string(), "tcp://10.0.0.5:9000");
+        assert_eq!(staging.unnamed(), &Target::parse("10.0.0.5:9000").unwrap());
+        assert_eq!(
+            Target::resolve("localhost:3000", &storage).unwrap(),
+            Target::parse("localhost:3000").unwrap()
+        );
+        assert!(Target::resolve("production", &storage).is_err());
+    }
+
+    #[test]
+    fn test_target_from_command() {
+        assert_eq!(
+            Target::from_command(vec![
+                "npx".to_string(),
+                "some-mcp-server".to_string(),
+                "--flag".to_string(),
+            ])
+            .unwrap(),
+            Target::Stdio {
+                command: "npx".to_string(),
+                args: vec!["some-mcp-server".to_string(), "--flag".to_string()],
+            }
+        );
+        assert_eq!(
+            Target::from_command(vec!["./server".to_string()]).unwrap(),
+            Target::Stdio {
+                command: "./server".to_string(),
+                args: vec![],
+            }
+        );
+        assert_eq!(
+            Target::from_command(vec![]).unwrap_err().to_string(),
+            format_err("Empty command specification").to_string()
+        );
+    }
+
     #[test]
     fn test_target_display() {
         struct TestCase {
@@ -641,6 +971,22 @@ mod tests {
                 expected: "tcp://[::1]:3000",
                 description: "IPv6 with port",
             },
+            TestCase {
+                target: Target::Tls {
+                    host: "example.com".to_string(),
+                    port: 8443,
+                },
+                expected: "tls://example.com:8443",
+                description: "TLS with port",
+            },
+            TestCase {
+                target: Target::Tls {
+                    host: "::1".to_string(),
+                    port: 8443,
+                },
+                expected: "tls://[::1]:8443",
+                description: "TLS IPv6 with port",
+            },
             TestCase {
                 target: Target::Stdio {
                     command: "./server".to_string(),
@@ -665,6 +1011,26 @@ mod tests {
                 expected: r#"cmd://server --path '/some path/'"#,
                 description: "stdio with quoted args",
             },
+            TestCase {
+                target: Target::Ssh {
+                    user: Some("alice".to_string()),
+                    host: "box".to_string(),
+                    port: None,
+                    command: "bin/server --verbose".to_string(),
+                },
+                expected: "ssh://alice@box/bin/server --verbose",
+                description: "SSH with user",
+            },
+            TestCase {
+                target: Target::Ssh {
+                    user: None,
+                    host: "::1".to_string(),
+                    port: Some(2222),
+                    command: "/opt/server".to_string(),
+                },
+                expected: "ssh://[::1]:2222//opt/server",
+                description: "SSH IPv6 with port and absolute command",
+            },
             // HTTP display tests
             TestCase {
                 target: Target::Http {
@@ -0,0 +1,147 @@
+//! Exporting saved targets as configuration for other applications.
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    Error, Result, client::ConnectOptions, ctx::Ctx, storage::StoredTarget, target::Target,
+};
+
+/// Configuration formats saved targets can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// The `mcpServers` section of Claude Desktop's `claude_desktop_config.json`
+    ClaudeDesktop,
+}
+
+/// Builds a server entry for a target, or explains why the target can't be
+/// expressed in the host application's configuration.
+fn server_entry(target: &Target, settings: &ConnectOptions) -> Result<Value> {
+    let mut entry = match target {
+        Target::Stdio { command, args } => json!({ "command": command, "args": args }),
+        Target::Http { .. } | Target::Https { .. } => {
+            let mut entry = json!({ "url": target.to_string() });
+            if !settings.headers.is_empty() {
+                entry["headers"] = json!(settings.headers);
+            }
+            entry
+        }
+        other => {
+            return Err(Error::Other(format!(
+                "{other} can't be used from Claude Desktop, which only runs stdio and HTTP servers"
+            )));
+        }
+    };
+    if !settings.env.is_empty() {
+        entry["env"] = json!(settings.env);
+    }
+    Ok(entry)
+}
+
+/// Builds a Claude Desktop configuration fragment holding the given targets
+/// and their settings. Targets that can't be expressed are returned with the
+/// reason.
+pub fn export_config(
+    targets: &[(StoredTarget, Option<ConnectOptions>)],
+) -> Result<(Value, Vec<(String, Error)>)> {
+    let mut servers = Map::new();
+    let mut skipped = Vec::new();
+    for (saved, settings) in targets {
+        let target = Target::parse(&saved.target)?;
+        match server_entry(&target, &settings.clone().unwrap_or_default()) {
+            Ok(entry) => {
+                servers.insert(saved.name.clone(), entry);
+            }
+            Err(e) => skipped.push((saved.name.clone(), e)),
+        }
+    }
+    Ok((json!({ "mcpServers": servers }), skipped))
+}
+
+/// Prints a configuration fragment for saved targets, or only for the named
+/// ones if any are given.
+pub async fn export_command(ctx: &Ctx, format: ExportFormat, names: Vec<String>) -> Result<()> {
+    let storage = ctx.storage()?;
+    let saved = if names.is_empty() {
+        storage.get_all_targets()?
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                storage
+                    .get_target(name)
+                    .map_err(|_| Error::Other(format!("Target '{name}' not found")))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    let targets = saved
+        .into_iter()
+        .map(|saved| {
+            let settings = storage.get_named_target_settings(&saved)?;
+            Ok((saved, settings))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (config, skipped) = match format {
+        ExportFormat::ClaudeDesktop => export_config(&targets)?,
+    };
+    for (name, reason) in skipped {
+        ctx.output
+            .trace_warn(format!("Skipping '{name}': {reason}"))?;
+    }
+    // The fragment is the command's output, so it is printed as JSON regardless of --json
+    ctx.output.json_value(&config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::targets::import::parse_config;
+
+    fn saved(name: &str, target: &str) -> StoredTarget {
+        StoredTarget {
+            name: name.to_string(),
+            target: target.to_string(),
+            settings: None,
+        }
+    }
+
+    #[test]
+    fn test_export_claude_desktop() {
+        let mut env_settings = ConnectOptions::default();
+        env_settings
+            .env
+            .insert("DEBUG".to_string(), "1".to_string());
+        let targets = vec![
+            (
+                saved("local", "cmd://./server --verbose"),
+                Some(env_settings),
+            ),
+            (saved("remote", "https://mcp.example.com"), None),
+            (saved("raw", "tcp://10.0.0.5:9000"), None),
+        ];
+
+        let (config, skipped) = export_config(&targets).unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "mcpServers": {
+                    "local": {
+                        "command": "./server",
+                        "args": ["--verbose"],
+                        "env": {"DEBUG": "1"}
+                    },
+                    "remote": {"url": "https://mcp.example.com"}
+                }
+            })
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, "raw");
+
+        // An exported config imports back to the same targets
+        let (imported, _) = parse_config(&config.to_string()).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].target.to_string(), "cmd://./server --verbose");
+        assert_eq!(imported[0].settings.env["DEBUG"], "1");
+    }
+}
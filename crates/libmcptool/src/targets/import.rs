@@ -0,0 +1,332 @@
+//! Importing servers from the MCP configuration of other applications.
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    result,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::validate_target_name;
+use crate::{
+    Error, Result, client::ConnectOptions, ctx::Ctx, storage::StoredTarget, target::Target,
+};
+
+/// Applications whose MCP server configuration can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportSource {
+    /// Claude Desktop's `claude_desktop_config.json`
+    ClaudeDesktop,
+    /// VS Code's `mcp.json`, from the current workspace or the user profile
+    Vscode,
+    /// Cursor's global `~/.cursor/mcp.json`
+    Cursor,
+}
+
+impl ImportSource {
+    /// The configuration files the source is read from by default, in order of
+    /// preference.
+    fn default_paths(self) -> Vec<PathBuf> {
+        let config = dirs::config_dir();
+        let home = dirs::home_dir();
+        match self {
+            Self::ClaudeDesktop => config
+                .map(|dir| dir.join("Claude").join("claude_desktop_config.json"))
+                .into_iter()
+                .collect(),
+            Self::Vscode => {
+                let workspace = env::current_dir()
+                    .ok()
+                    .map(|dir| dir.join(".vscode").join("mcp.json"));
+                let user = config.map(|dir| dir.join("Code").join("User").join("mcp.json"));
+                workspace.into_iter().chain(user).collect()
+            }
+            Self::Cursor => home
+                .map(|dir| dir.join(".cursor").join("mcp.json"))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// The first default configuration file that exists.
+    fn find_config(self) -> Result<PathBuf> {
+        let candidates = self.default_paths();
+        candidates
+            .iter()
+            .find(|path| path.exists())
+            .cloned()
+            .ok_or_else(|| {
+                let tried: Vec<String> = candidates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                Error::Other(format!(
+                    "No configuration file found (looked for {}). Use --path to point at one",
+                    tried.join(", ")
+                ))
+            })
+    }
+}
+
+/// A server entry as written in an application's configuration.
+#[derive(Debug, Deserialize)]
+struct ServerEntry {
+    /// Program to spawn for stdio servers.
+    command: Option<String>,
+    /// Arguments for the program.
+    #[serde(default)]
+    args: Vec<String>,
+    /// Environment variables for the program.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Endpoint for HTTP servers.
+    url: Option<String>,
+    /// Headers sent to HTTP servers.
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+/// A server read from a configuration file, ready to be saved.
+#[derive(Debug, PartialEq)]
+pub struct ImportedServer {
+    /// Name to save the target as.
+    pub name: String,
+    /// The server's target.
+    pub target: Target,
+    /// Environment and headers to store as the target's settings.
+    pub settings: ConnectOptions,
+}
+
+/// A server in a configuration file that can't be imported.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SkippedServer {
+    /// The server's name in the configuration.
+    pub name: String,
+    /// Why it can't be imported.
+    pub reason: String,
+}
+
+/// Reads the servers defined in an application's configuration file. Servers
+/// that can't be imported, such as HTTP servers with a path in their URL, are
+/// returned separately so the rest can still be imported.
+///
+/// Claude Desktop and Cursor keep servers under `mcpServers`, VS Code under
+/// `servers` (or `mcp.servers` in `settings.json`).
+pub fn parse_config(contents: &str) -> Result<(Vec<ImportedServer>, Vec<SkippedServer>)> {
+    let config: Value = serde_json::from_str(contents)?;
+    let servers = config
+        .get("mcpServers")
+        .or_else(|| config.get("servers"))
+        .or_else(|| config.pointer("/mcp/servers"))
+        .and_then(Value::as_object)
+        .ok_or_else(|| Error::Format("No MCP servers found in configuration".to_string()))?;
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, entry) in servers {
+        let server = serde_json::from_value(entry.clone())
+            .map_err(|e| format!("invalid entry: {e}"))
+            .and_then(|entry| imported_server(name, entry));
+        match server {
+            Ok(server) => imported.push(server),
+            Err(reason) => skipped.push(SkippedServer {
+                name: name.clone(),
+                reason,
+            }),
+        }
+    }
+    Ok((imported, skipped))
+}
+
+/// Converts a configuration entry into a target and its settings, or says
+/// why it can't be.
+fn imported_server(name: &str, entry: ServerEntry) -> result::Result<ImportedServer, String> {
+    let target = match (entry.command, entry.url) {
+        (Some(command), _) => Target::Stdio {
+            command,
+            args: entry.args,
+        },
+        (None, Some(url)) => Target::parse(&url).map_err(|e| match e {
+            Error::Format(reason) => format!("unsupported url {url}: {reason}"),
+            e => e.to_string(),
+        })?,
+        (None, None) => return Err("it has neither a command nor a url".to_string()),
+    };
+    let settings = ConnectOptions {
+        env: entry.env,
+        headers: entry.headers,
+        ..ConnectOptions::default()
+    };
+    Ok(ImportedServer {
+        name: target_name(name),
+        target,
+        settings,
+    })
+}
+
+/// Turns a server name into a valid target name by replacing unsupported
+/// characters with `-`.
+fn target_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Imports the servers configured in another application as saved targets.
+/// Existing targets with the same name are skipped unless `force` is set, as
+/// are servers that can't be imported. Each target keeps its settings in
+/// `saved_targets.json`, with header values in the secret store.
+pub async fn import_command(
+    ctx: &Ctx,
+    source: ImportSource,
+    path: Option<PathBuf>,
+    force: bool,
+) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => source.find_config()?,
+    };
+    let (servers, skipped) = read_config(&path)?;
+    let storage = ctx.storage()?;
+
+    ctx.output
+        .h1(format!("Importing from {}", path.display()))?;
+    for server in &skipped {
+        ctx.output
+            .trace_warn(format!("Skipping '{}': {}", server.name, server.reason))?;
+    }
+    let mut imported = 0;
+    for server in servers {
+        validate_target_name(&server.name)?;
+        if !force && storage.get_target(&server.name).is_ok() {
+            ctx.output.trace_warn(format!(
+                "Skipping '{}': a target with this name already exists (use --force to replace it)",
+                server.name
+            ))?;
+            continue;
+        }
+
+        let target = server.target.to_string();
+        storage.store_target(&StoredTarget {
+            name: server.name.clone(),
+            target: target.clone(),
+            settings: (server.settings != ConnectOptions::default()).then_some(server.settings),
+        })?;
+        ctx.output.kv(&server.name, &target)?;
+        imported += 1;
+    }
+    ctx.output
+        .trace_success(format!("Imported {imported} target(s)"))?;
+    Ok(())
+}
+
+/// Reads and parses a configuration file.
+fn read_config(path: &Path) -> Result<(Vec<ImportedServer>, Vec<SkippedServer>)> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+    parse_config(&contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_claude_desktop_config() {
+        let config = r#"{
+            "mcpServers": {
+                "filesystem": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"],
+                    "env": {"DEBUG": "1"}
+                },
+                "remote.api": {
+                    "url": "https://mcp.example.com",
+                    "headers": {"Authorization": "Bearer token"}
+                }
+            }
+        }"#;
+        let (servers, skipped) = parse_config(config).unwrap();
+        assert_eq!(servers.len(), 2);
+        assert!(skipped.is_empty());
+
+        assert_eq!(servers[0].name, "filesystem");
+        assert_eq!(
+            servers[0].target,
+            Target::Stdio {
+                command: "npx".to_string(),
+                args: vec![
+                    "-y".to_string(),
+                    "@modelcontextprotocol/server-filesystem".to_string(),
+                    "/tmp".to_string(),
+                ],
+            }
+        );
+        assert_eq!(servers[0].settings.env["DEBUG"], "1");
+
+        assert_eq!(servers[1].name, "remote-api");
+        assert_eq!(
+            servers[1].target,
+            Target::Https {
+                host: "mcp.example.com".to_string(),
+                port: 443,
+            }
+        );
+        assert_eq!(servers[1].settings.headers["Authorization"], "Bearer token");
+    }
+
+    #[test]
+    fn test_parse_vscode_config() {
+        let mcp_json = r#"{"servers": {"local": {"type": "stdio", "command": "./server"}}}"#;
+        let settings_json =
+            r#"{"editor.tabSize": 4, "mcp": {"servers": {"local": {"command": "./server"}}}}"#;
+        for config in [mcp_json, settings_json] {
+            let (servers, _) = parse_config(config).unwrap();
+            assert_eq!(servers.len(), 1);
+            assert_eq!(servers[0].name, "local");
+            assert_eq!(servers[0].settings, ConnectOptions::default());
+        }
+    }
+
+    #[test]
+    fn test_parse_config_skips_servers() {
+        let config = r#"{
+            "mcpServers": {
+                "local": {"url": "http://localhost:3000"},
+                "pathed": {"url": "http://localhost:3000/mcp"},
+                "empty": {}
+            }
+        }"#;
+        let (servers, skipped) = parse_config(config).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].name, "local");
+        assert_eq!(
+            skipped,
+            [
+                SkippedServer {
+                    name: "empty".to_string(),
+                    reason: "it has neither a command nor a url".to_string(),
+                },
+                SkippedServer {
+                    name: "pathed".to_string(),
+                    reason: "unsupported url http://localhost:3000/mcp: HTTP targets can't have a path: /mcp".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        assert!(parse_config("{}").is_err());
+        assert!(parse_config("not json").is_err());
+    }
+}
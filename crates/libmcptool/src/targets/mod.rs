@@ -0,0 +1,153 @@
+//! Commands for managing named targets.
+
+/// Exporting saved targets as other applications' configuration.
+mod export;
+/// Importing servers from other applications' configuration.
+mod import;
+
+pub use export::{ExportFormat, export_command};
+pub use import::{ImportSource, import_command};
+
+use crate::{Error, Result, ctx::Ctx, output::Output, storage::StoredTarget, target::Target};
+
+/// Validates that a target name contains only letters, numbers, `_` and `-`.
+pub fn validate_target_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::Format("Target name cannot be empty".to_string()));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        return Err(Error::Format(format!(
+            "Target name '{name}' is invalid. Names can only contain letters, numbers, '_' and '-'"
+        )));
+    }
+    Ok(())
+}
+
+/// Saves a target under a name, along with any connection options given on the
+/// command line. An existing target with the same name is only replaced if
+/// `force` is set.
+pub async fn add_command(ctx: &Ctx, name: String, target: Target, force: bool) -> Result<()> {
+    validate_target_name(&name)?;
+    let storage = ctx.storage()?;
+    if !force && storage.get_target(&name).is_ok() {
+        return Err(Error::Other(format!(
+            "Target '{name}' already exists. Use --force to replace it"
+        )));
+    }
+
+    let key = target.to_string();
+    let settings = if ctx.connect.has_stored_settings() {
+        Some(match storage.get_target_settings(&key)? {
+            Some(stored) => ctx.connect.clone().merged_with(&stored),
+            None => ctx.connect.clone(),
+        })
+    } else {
+        None
+    };
+    storage.store_target(&StoredTarget {
+        name: name.clone(),
+        target: key,
+        settings,
+    })?;
+    ctx.output
+        .trace_success(format!("Target '{name}' saved as {target}"))?;
+    ctx.output.text(format!(
+        "Use it in place of a target, e.g. mcptool connect {name}"
+    ))?;
+    Ok(())
+}
+
+/// Lists all named targets.
+pub async fn list_command(ctx: &Ctx) -> Result<()> {
+    let targets = ctx.storage()?.get_all_targets()?;
+    if targets.is_empty() {
+        ctx.output.text("No saved targets found.")?;
+        ctx.output.text("")?;
+        ctx.output
+            .text("Use 'mcptool target add <name> <target>' to save a target.")?;
+        return Ok(());
+    }
+
+    ctx.output
+        .h1(format!("Saved targets ({}):", targets.len()))?;
+    let width = targets
+        .iter()
+        .map(|t| t.name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    for target in targets {
+        ctx.output
+            .text(format!("  {:<width$}  {}", target.name, target.target))?;
+    }
+    Ok(())
+}
+
+/// Removes a named target.
+pub async fn remove_command(ctx: &Ctx, name: String) -> Result<()> {
+    ctx.storage()?
+        .remove_target(&name)
+        .map_err(|_| Error::Other(format!("Target '{name}' not found")))?;
+    ctx.output
+        .trace_success(format!("Target '{name}' removed"))?;
+    Ok(())
+}
+
+/// Shows a named target and any connection settings saved for it.
+pub async fn show_command(ctx: &Ctx, name: String) -> Result<()> {
+    let storage = ctx.storage()?;
+    let saved = storage
+        .get_target(&name)
+        .map_err(|_| Error::Other(format!("Target '{name}' not found")))?;
+    let settings = storage.get_named_target_settings(&saved)?;
+
+    if ctx.output.structured() {
+        ctx.output.json_value(&serde_json::json!({
+            "name": saved.name,
+            "target": saved.target,
+            "settings": settings,
+        }))?;
+    } else {
+        ctx.output.h1(&saved.name)?;
+        ctx.output.kv("Target", &saved.target)?;
+        match settings {
+            Some(settings) => show_settings(&ctx.output, &serde_json::to_value(settings)?)?,
+            None => ctx.output.kv("Settings", "none")?,
+        }
+    }
+    Ok(())
+}
+
+/// Shows saved connection settings as key/value pairs.
+fn show_settings(output: &Output, settings: &serde_json::Value) -> Result<()> {
+    output.h2("Settings")?;
+    let Some(fields) = settings.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in fields {
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        output.kv(key, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_target_name() {
+        assert!(validate_target_name("staging").is_ok());
+        assert!(validate_target_name("prod-eu_1").is_ok());
+        assert!(validate_target_name("").is_err());
+        assert!(validate_target_name("host:80").is_err());
+        assert!(validate_target_name("a/b").is_err());
+        assert!(validate_target_name("has space").is_err());
+    }
+}
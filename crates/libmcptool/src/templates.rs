@@ -0,0 +1,201 @@
+//! Commands for managing saved call templates.
+
+use clap::Parser;
+use serde_json::Map;
+
+use crate::{
+    Error, Result,
+    command::{McpCommand, ReplCommandWrapper, execute_mcp_command},
+    ctx::Ctx,
+    storage::StoredTemplate,
+};
+
+/// Validates that a template name contains only letters, numbers, `_` and `-`.
+pub fn validate_template_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::Format("Template name cannot be empty".to_string()));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        return Err(Error::Format(format!(
+            "Template name '{name}' is invalid. Names can only contain letters, numbers, '_' and '-'"
+        )));
+    }
+    Ok(())
+}
+
+/// The parameter an argument in `key=value` or `key:=json` form sets.
+fn argument_key(arg: &str) -> Result<&str> {
+    arg.split_once('=')
+        .map(|(key, _)| key.strip_suffix(':').unwrap_or(key))
+        .ok_or_else(|| {
+            Error::Format(format!(
+                "Argument '{arg}' must be in key=value or key:=json form"
+            ))
+        })
+}
+
+/// Combines saved arguments with overrides. An override replaces every saved
+/// argument for the same parameter, and the rest are kept in order.
+pub fn merge_arguments(saved: &[String], overrides: &[String]) -> Result<Vec<String>> {
+    let overridden = overrides
+        .iter()
+        .map(|arg| argument_key(arg))
+        .collect::<Result<Vec<_>>>()?;
+    let mut merged = Vec::new();
+    for arg in saved {
+        if !overridden.contains(&argument_key(arg)?) {
+            merged.push(arg.clone());
+        }
+    }
+    merged.extend(overrides.iter().cloned());
+    Ok(merged)
+}
+
+/// Saves a tool call under a name. An existing template with the same name is
+/// only replaced if `force` is set.
+pub async fn save_command(
+    ctx: &Ctx,
+    name: String,
+    tool: String,
+    args: Vec<String>,
+    force: bool,
+) -> Result<()> {
+    validate_template_name(&name)?;
+    for arg in &args {
+        argument_key(arg)?;
+    }
+    let storage = ctx.storage()?;
+    if !force && storage.get_template(&name).is_ok() {
+        return Err(Error::Other(format!(
+            "Template '{name}' already exists. Use --force to replace it"
+        )));
+    }
+
+    storage.store_template(&StoredTemplate {
+        name: name.clone(),
+        tool: tool.clone(),
+        args,
+    })?;
+    ctx.output
+        .trace_success(format!("Template '{name}' saved, calling {tool}"))?;
+    ctx.output.text(format!(
+        "Run it with mcptool template run {name} <target> [key=value ...]"
+    ))?;
+    Ok(())
+}
+
+/// Calls the tool of a saved template on a target, with `overrides` replacing
+/// saved arguments for the same parameters. `yes` skips the confirmation for
+/// destructive tools.
+pub async fn run_command(
+    ctx: &Ctx,
+    name: String,
+    target: &str,
+    overrides: Vec<String>,
+    yes: bool,
+) -> Result<()> {
+    let template = ctx
+        .storage()?
+        .get_template(&name)
+        .map_err(|_| Error::Other(format!("Template '{name}' not found")))?;
+    let args = merge_arguments(&template.args, &overrides)?;
+
+    let mut words = vec!["calltool".to_string(), template.tool];
+    for arg in args {
+        words.push("--arg".to_string());
+        words.push(arg);
+    }
+    if yes {
+        words.push("--yes".to_string());
+    }
+    let mut command = ReplCommandWrapper::try_parse_from(words)
+        .map_err(|e| Error::Other(format!("Template '{name}' is invalid: {e}")))?
+        .command;
+    // A template for a tool without parameters calls it with no arguments
+    if let McpCommand::Calltool {
+        args, arguments, ..
+    } = &mut command
+        && args.is_empty()
+    {
+        *arguments = Some(Map::new());
+    }
+    execute_mcp_command(command, target, ctx).await
+}
+
+/// Lists all call templates.
+pub async fn list_command(ctx: &Ctx) -> Result<()> {
+    let templates = ctx.storage()?.get_all_templates()?;
+    if ctx.output.structured() {
+        ctx.output.json_value(&templates)?;
+        return Ok(());
+    }
+    if templates.is_empty() {
+        ctx.output.text("No saved templates found.")?;
+        ctx.output.text("")?;
+        ctx.output.text(
+            "Use 'mcptool template save <name> --tool <tool> --arg key=value' to save one.",
+        )?;
+        return Ok(());
+    }
+
+    ctx.output
+        .h1(format!("Saved templates ({}):", templates.len()))?;
+    let width = templates
+        .iter()
+        .map(|t| t.name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    for template in templates {
+        ctx.output.text(format!(
+            "  {:<width$}  {} {}",
+            template.name,
+            template.tool,
+            shell_words::join(&template.args)
+        ))?;
+    }
+    Ok(())
+}
+
+/// Removes a call template.
+pub async fn remove_command(ctx: &Ctx, name: String) -> Result<()> {
+    ctx.storage()?
+        .remove_template(&name)
+        .map_err(|_| Error::Other(format!("Template '{name}' not found")))?;
+    ctx.output
+        .trace_success(format!("Template '{name}' removed"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_template_name() {
+        assert!(validate_template_name("nightly-report").is_ok());
+        assert!(validate_template_name("").is_err());
+        assert!(validate_template_name("a b").is_err());
+    }
+
+    #[test]
+    fn test_merge_arguments() {
+        let saved = vec![
+            "period=daily".to_string(),
+            "limit:=10".to_string(),
+            "tags=a".to_string(),
+        ];
+        let merged = merge_arguments(
+            &saved,
+            &["limit=5".to_string(), "period:=\"weekly\"".to_string()],
+        )
+        .unwrap();
+        assert_eq!(merged, ["tags=a", "limit=5", "period:=\"weekly\""]);
+
+        assert_eq!(merge_arguments(&saved, &[]).unwrap(), saved);
+        assert!(merge_arguments(&saved, &["limit".to_string()]).is_err());
+    }
+}
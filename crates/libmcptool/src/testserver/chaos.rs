@@ -0,0 +1,238 @@
+//! Chaos mode: a shim in front of the TCP server that misbehaves at the
+//! transport level, for hardening clients against broken servers. It drops
+//! connections, stalls the handshake, sends malformed frames and answers
+//! requests out of order.
+
+use std::{io, sync::Arc, time::Duration};
+
+use clap::ValueEnum;
+use rand::Rng;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, copy},
+    net::{TcpListener, TcpStream},
+    task,
+    time::{sleep, timeout},
+};
+
+use crate::{output::Output, recording::Framer};
+
+/// How long a held-back frame waits for a later one to overtake it before
+/// it's sent anyway, so a client waiting on it isn't stalled forever.
+const REORDER_WAIT: Duration = Duration::from_millis(500);
+
+/// Frames that aren't valid JSON-RPC messages.
+const MALFORMED: [&[u8]; 4] = [
+    b"{\"jsonrpc\":\"2.0\",\"id\":\n",
+    b"this is not json\n",
+    b"{\"jsonrpc\":\"1.0\",\"result\":{}}\n",
+    b"[1, 2, 3]\n",
+];
+
+/// A kind of misbehaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChaosKind {
+    /// Close the connection mid-session.
+    Drop,
+    /// Stall before answering `initialize`.
+    SlowInit,
+    /// Send a frame that isn't valid JSON-RPC.
+    Malformed,
+    /// Hold a response back until the one after it has been sent.
+    Reorder,
+}
+
+/// The misbehaviour of the chaos shim.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chaos {
+    /// The kinds of misbehaviour. Empty means all of them.
+    pub kinds: Vec<ChaosKind>,
+    /// The chance, from 0 to 1, of each kind striking at each frame the
+    /// server sends. A slow initialize is decided once per connection.
+    pub rate: f64,
+    /// How long a slow initialize stalls for.
+    pub init_delay: Duration,
+}
+
+impl Chaos {
+    /// The kinds of misbehaviour in play.
+    fn kinds(&self) -> &[ChaosKind] {
+        if self.kinds.is_empty() {
+            &[
+                ChaosKind::Drop,
+                ChaosKind::SlowInit,
+                ChaosKind::Malformed,
+                ChaosKind::Reorder,
+            ]
+        } else {
+            &self.kinds
+        }
+    }
+
+    /// Whether a kind of misbehaviour strikes now.
+    fn strikes(&self, kind: ChaosKind) -> bool {
+        self.kinds().contains(&kind) && self.rate > 0.0 && rand::rng().random_bool(self.rate)
+    }
+
+    /// A one-line summary, for the server banner.
+    pub fn describe(&self) -> String {
+        let kinds: Vec<String> = self
+            .kinds()
+            .iter()
+            .filter_map(|kind| kind.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        format!(
+            "{} at {:.0}% per frame, initialize delayed by {}ms",
+            kinds.join(", "),
+            self.rate * 100.0,
+            self.init_delay.as_millis()
+        )
+    }
+
+    /// Accepts connections on `addr`, relaying each to the real server at
+    /// `upstream` with chaos applied to the server's side.
+    pub async fn serve(
+        self: Arc<Self>,
+        addr: &str,
+        upstream: &str,
+        output: Output,
+    ) -> io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        loop {
+            let (client, peer) = listener.accept().await?;
+            let chaos = self.clone();
+            let upstream = upstream.to_string();
+            let output = output.clone();
+            task::spawn(async move {
+                if let Err(e) = chaos.relay(client, &upstream, &output).await {
+                    _ = output.trace_warn(format!("Chaos: connection from {peer} failed: {e}"));
+                }
+            });
+        }
+    }
+
+    /// Relays one client connection. Returning drops both connections.
+    async fn relay(&self, client: TcpStream, upstream: &str, output: &Output) -> io::Result<()> {
+        let server = TcpStream::connect(upstream).await?;
+        let (mut client_read, mut client_write) = client.into_split();
+        let (mut server_read, mut server_write) = server.into_split();
+        tokio::select! {
+            result = copy(&mut client_read, &mut server_write) => result.map(|_| ()),
+            result = self.forward(&mut server_read, &mut client_write, output) => result,
+        }
+    }
+
+    /// Forwards frames from the server to the client, misbehaving along the
+    /// way.
+    async fn forward<R, W>(&self, reader: &mut R, writer: &mut W, output: &Output) -> io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut framer = Framer::default();
+        let mut buffer = vec![0; 8192];
+        let mut held: Option<Vec<u8>> = None;
+        // The first frame the server sends is the initialize response
+        let mut slow_init = self.strikes(ChaosKind::SlowInit);
+        loop {
+            let read = if held.is_some() {
+                timeout(REORDER_WAIT, reader.read(&mut buffer)).await.ok()
+            } else {
+                Some(reader.read(&mut buffer).await)
+            };
+            let Some(read) = read else {
+                // Nothing came along to overtake the held frame, so let it go
+                if let Some(frame) = held.take() {
+                    writer.write_all(&frame).await?;
+                }
+                continue;
+            };
+            let read = read?;
+            if read == 0 {
+                if let Some(frame) = held.take() {
+                    writer.write_all(&frame).await?;
+                }
+                return Ok(());
+            }
+            for frame in framer.push(&buffer[..read]) {
+                if slow_init {
+                    slow_init = false;
+                    _ = output.trace_warn(format!(
+                        "Chaos: delaying initialize by {}ms",
+                        self.init_delay.as_millis()
+                    ));
+                    sleep(self.init_delay).await;
+                }
+                if self.strikes(ChaosKind::Drop) {
+                    _ = output.trace_warn("Chaos: dropping connection");
+                    return Ok(());
+                }
+                if self.strikes(ChaosKind::Malformed) {
+                    _ = output.trace_warn("Chaos: sending a malformed frame");
+                    let malformed = MALFORMED[rand::rng().random_range(0..MALFORMED.len())];
+                    writer.write_all(malformed).await?;
+                }
+                if held.is_none() && self.strikes(ChaosKind::Reorder) {
+                    _ = output.trace_warn("Chaos: holding a frame back");
+                    held = Some(frame);
+                    continue;
+                }
+                writer.write_all(&frame).await?;
+                if let Some(frame) = held.take() {
+                    writer.write_all(&frame).await?;
+                }
+            }
+            writer.flush().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs frames through a shim, returning what the client receives.
+    async fn forward(chaos: &Chaos, input: &[u8]) -> Vec<u8> {
+        let output = Output::new(false, 80).with_sink(io::sink());
+        let mut reader = input;
+        let mut writer = Vec::new();
+        chaos
+            .forward(&mut reader, &mut writer, &output)
+            .await
+            .unwrap();
+        writer
+    }
+
+    #[tokio::test]
+    async fn test_forward() {
+        let reorder = Chaos {
+            kinds: vec![ChaosKind::Reorder],
+            rate: 1.0,
+            init_delay: Duration::ZERO,
+        };
+        assert_eq!(forward(&reorder, b"a\nb\nc\n").await, b"b\na\nc\n");
+
+        let malformed = Chaos {
+            kinds: vec![ChaosKind::Malformed],
+            ..reorder.clone()
+        };
+        let received = forward(&malformed, b"{}\n{}\n").await;
+        assert_eq!(received.iter().filter(|b| **b == b'\n').count(), 4);
+
+        let dropping = Chaos {
+            kinds: vec![ChaosKind::Drop],
+            ..reorder.clone()
+        };
+        assert!(forward(&dropping, b"{}\n").await.is_empty());
+
+        let calm = Chaos {
+            rate: 0.0,
+            ..reorder
+        };
+        assert_eq!(forward(&calm, b"a\nb\n").await, b"a\nb\n");
+        assert_eq!(
+            calm.describe(),
+            "reorder at 0% per frame, initialize delayed by 0ms"
+        );
+    }
+}
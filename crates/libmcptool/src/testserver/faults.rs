@@ -0,0 +1,114 @@
+//! Faults injected into test server responses: added latency and failed
+//! requests, for seeing how clients cope with slow or flaky servers.
+
+use std::time::Duration;
+
+use rand::Rng;
+use tmcp::{Error, Result};
+use tokio::time::sleep;
+
+/// The faults to inject into every request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Faults {
+    /// A delay added before every response.
+    pub latency: Duration,
+    /// The most extra delay added at random on top of the latency.
+    pub jitter: Duration,
+    /// The fraction of requests, from 0 to 1, that fail at random.
+    /// `initialize` is spared, so clients can still connect.
+    pub error_rate: f64,
+    /// Methods that always fail, such as `tools/call`.
+    pub fail_methods: Vec<String>,
+}
+
+impl Faults {
+    /// Whether any fault is configured.
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// The delay before the next response.
+    fn delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.latency;
+        }
+        self.latency + self.jitter.mul_f64(rand::rng().random::<f64>())
+    }
+
+    /// Whether a request for a method should fail.
+    fn fails(&self, method: &str) -> bool {
+        self.fail_methods.iter().any(|failing| failing == method)
+            || (method != "initialize"
+                && self.error_rate > 0.0
+                && rand::rng().random_bool(self.error_rate))
+    }
+
+    /// Waits out the latency for a request, then fails it if it should fail.
+    pub async fn apply(&self, method: &str) -> Result<()> {
+        let delay = self.delay();
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+        if self.fails(method) {
+            return Err(Error::InternalError(format!(
+                "Injected failure for {method}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// A one-line summary of the faults, for the server banner.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.latency.is_zero() || !self.jitter.is_zero() {
+            parts.push(format!(
+                "latency {}ms + up to {}ms jitter",
+                self.latency.as_millis(),
+                self.jitter.as_millis()
+            ));
+        }
+        if self.error_rate > 0.0 {
+            parts.push(format!("{:.0}% errors", self.error_rate * 100.0));
+        }
+        if !self.fail_methods.is_empty() {
+            parts.push(format!("failing {}", self.fail_methods.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fails() {
+        let faults = Faults {
+            fail_methods: vec!["tools/call".to_string()],
+            ..Faults::default()
+        };
+        assert!(faults.fails("tools/call"));
+        assert!(!faults.fails("tools/list"));
+
+        let always = Faults {
+            error_rate: 1.0,
+            ..Faults::default()
+        };
+        assert!(always.fails("tools/list"));
+        assert!(!always.fails("initialize"));
+        assert!(!Faults::default().is_active());
+    }
+
+    #[test]
+    fn test_delay() {
+        let faults = Faults {
+            latency: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            ..Faults::default()
+        };
+        for _ in 0..10 {
+            let delay = faults.delay();
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(150));
+        }
+    }
+}
@@ -1,6 +1,15 @@
+pub mod chaos;
+pub mod faults;
+pub mod paging;
+pub mod payload;
+pub mod spec;
+pub mod storm;
+
 use std::{
     collections::HashMap,
     future::Future,
+    io,
+    path::PathBuf,
     sync::{
         Arc, Mutex,
         atomic::{AtomicU64, Ordering},
@@ -9,7 +18,8 @@ use std::{
 };
 
 use rustyline::{DefaultEditor, error::ReadlineError};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
 use tmcp::{
     Error, Result, Server, ServerCtx, ServerHandler,
     schema::{
@@ -20,10 +30,50 @@ use tmcp::{
         Resource, ResourceTemplate, Role, ServerCapabilities, ServerNotification, Tool, ToolSchema,
     },
 };
-use tokio::{runtime::Handle, task};
-
+use tokio::{net::TcpListener, runtime::Handle, task, time};
+
+use self::{
+    chaos::{self, Chaos},
+    faults::Faults,
+    paging::Paging,
+    payload::{PAYLOAD_TOOL, PAYLOAD_URI, Payload},
+    spec::ServerSpec,
+    storm::Storm,
+};
 use crate::{ctx::Ctx, output::Output};
 
+/// How the test server is run.
+#[derive(Debug, Clone)]
+pub struct TestServerOptions {
+    /// Serve over stdio instead of HTTP.
+    pub stdio: bool,
+    /// Serve over TCP instead of HTTP.
+    pub tcp: bool,
+    /// The port to listen on for TCP and HTTP.
+    pub port: u16,
+    /// Run a REPL for managing the server alongside it.
+    pub interactive: bool,
+    /// A file defining the tools, prompts and resources to serve instead of
+    /// the built-in ones.
+    pub spec: Option<PathBuf>,
+    /// Latency and failures to inject into responses.
+    pub faults: Faults,
+    /// A large generated payload to serve as a resource and a tool result.
+    pub payload: Option<Payload>,
+    /// A stream of notifications to send to every connected client.
+    pub storm: Option<Storm>,
+    /// Generated items and page sizes for listings.
+    pub paging: Paging,
+    /// Transport-level misbehaviour, applied by a shim in front of the TCP
+    /// server.
+    pub chaos: Option<Chaos>,
+}
+
+/// Converts JSON to a protocol type.
+fn convert<T: DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|e| Error::InternalError(e.to_string()))
+}
+
 /// Sample user data structure for demonstrating JSON resource serving
 #[derive(Serialize, Deserialize)]
 #[allow(clippy::missing_docs_in_private_items)]
@@ -63,17 +113,32 @@ struct TestServerState {
     log_level: Arc<Mutex<LoggingLevel>>,
     connected_clients: Arc<Mutex<HashMap<String, ClientInfo>>>,
     active_contexts: Arc<Mutex<HashMap<String, ServerCtx>>>,
+    spec: Option<Arc<ServerSpec>>,
+    faults: Arc<Faults>,
+    payload: Option<Arc<Payload>>,
+    paging: Arc<Paging>,
 }
 
 #[allow(clippy::missing_docs_in_private_items)]
 impl TestServerState {
-    fn new(output: Output, request_counter: Arc<AtomicU64>) -> Self {
+    fn new(
+        output: Output,
+        request_counter: Arc<AtomicU64>,
+        spec: Option<Arc<ServerSpec>>,
+        faults: Faults,
+        payload: Option<Payload>,
+        paging: Paging,
+    ) -> Self {
         Self {
             request_counter,
             output,
             log_level: Arc::new(Mutex::new(LoggingLevel::Error)),
             connected_clients: Arc::new(Mutex::new(HashMap::new())),
             active_contexts: Arc::new(Mutex::new(HashMap::new())),
+            spec,
+            faults: Arc::new(faults),
+            payload: payload.map(Arc::new),
+            paging: Arc::new(paging),
         }
     }
 
@@ -180,6 +245,62 @@ impl TestServerConn {
         }
     }
 
+    /// Applies the configured faults to a request, noting injected failures.
+    async fn inject(&self, method: &str) -> Result<()> {
+        let result = self.state.faults.apply(method).await;
+        if let Err(e) = &result {
+            _ = self.state.output.trace_warn(e.to_string());
+        }
+        result
+    }
+
+    /// Adds the payload tool to a tool listing, if there's a payload.
+    fn with_payload_tool(&self, result: ListToolsResult) -> Result<ListToolsResult> {
+        match &self.state.payload {
+            Some(payload) => Ok(result.with_tool(convert(payload.tool())?)),
+            None => Ok(result),
+        }
+    }
+
+    /// Adds the payload resource to a resource listing, if there's a payload.
+    fn with_payload_resource(&self, result: ListResourcesResult) -> Result<ListResourcesResult> {
+        match &self.state.payload {
+            Some(payload) => Ok(result.with_resource(convert(payload.resource())?)),
+            None => Ok(result),
+        }
+    }
+
+    /// Pads a listing with generated items and cuts it down to the page
+    /// the request's cursor asks for, if paging is configured.
+    fn page<T: Serialize + DeserializeOwned>(
+        &self,
+        result: T,
+        field: &str,
+        params: &Value,
+    ) -> Result<T> {
+        if !self.state.paging.is_active() {
+            return Ok(result);
+        }
+        let listing =
+            serde_json::to_value(result).map_err(|e| Error::InternalError(e.to_string()))?;
+        convert(
+            self.state
+                .paging
+                .apply(listing, field, params["cursor"].as_str())?,
+        )
+    }
+
+    /// Logs the result of a request answered from the server spec.
+    fn spec_result<T: Serialize>(&self, result: Result<T>) -> Result<T> {
+        if let Ok(result) = &result {
+            _ = self.state.output.text(format!(
+                "result: {}",
+                serde_json::to_string_pretty(result).unwrap()
+            ));
+        }
+        result
+    }
+
     fn should_log_message(
         &self,
         message_level: &LoggingLevel,
@@ -213,6 +334,7 @@ impl ServerHandler for TestServerConn {
     ) -> Result<InitializeResult> {
         self.state.request_counter.fetch_add(1, Ordering::Relaxed);
         _ = self.state.output.h1("initialize");
+        self.inject("initialize").await?;
         let params = serde_json::json!({
             "protocol_version": protocol_version,
             "capabilities": capabilities,
@@ -228,12 +350,15 @@ impl ServerHandler for TestServerConn {
         self.state
             .add_client(context, "client_connection", client_info);
 
-        let result = InitializeResult::new("mcptool-testserver")
-            .with_version(env!("CARGO_PKG_VERSION"))
-            .with_tools(true)
-            .with_prompts(true)
-            .with_resources(true, true)
-            .with_instructions("mcptool test server");
+        let result = match &self.state.spec {
+            Some(spec) => spec.initialize(),
+            None => InitializeResult::new("mcptool-testserver")
+                .with_version(env!("CARGO_PKG_VERSION"))
+                .with_tools(true)
+                .with_prompts(true)
+                .with_resources(true, true)
+                .with_instructions("mcptool test server"),
+        };
 
         _ = self.state.output.text(format!(
             "result: {}",
@@ -245,6 +370,7 @@ impl ServerHandler for TestServerConn {
 
     async fn pong(&self, _context: &ServerCtx) -> Result<()> {
         _ = self.state.output.h1("pong");
+        self.inject("ping").await?;
         _ = self.state.output.text("parameters: {}");
         _ = self.state.output.text("result: pong");
         Ok(())
@@ -256,6 +382,7 @@ impl ServerHandler for TestServerConn {
         cursor: Option<Cursor>,
     ) -> Result<ListToolsResult> {
         _ = self.state.output.h1("list_tools");
+        self.inject("tools/list").await?;
         let params = serde_json::json!({
             "cursor": cursor,
         });
@@ -264,6 +391,14 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(
+                spec.list_tools()
+                    .and_then(|r| self.with_payload_tool(r))
+                    .and_then(|r| self.page(r, "tools", &params)),
+            );
+        }
+
         let echo_tool = Tool::new(
             "echo",
             ToolSchema::default()
@@ -278,7 +413,8 @@ impl ServerHandler for TestServerConn {
         )
         .with_description("Echoes back the provided message");
 
-        let result = ListToolsResult::default().with_tool(echo_tool);
+        let result = self.with_payload_tool(ListToolsResult::default().with_tool(echo_tool))?;
+        let result = self.page(result, "tools", &params)?;
 
         _ = self.state.output.text(format!(
             "result: {}",
@@ -295,6 +431,7 @@ impl ServerHandler for TestServerConn {
         arguments: Option<tmcp::Arguments>,
     ) -> Result<CallToolResult> {
         _ = self.state.output.h1("call_tool");
+        self.inject("tools/call").await?;
         let params = serde_json::json!({
             "name": name,
             "arguments": arguments,
@@ -304,6 +441,17 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(payload) = self.state.payload.as_ref().filter(|_| name == PAYLOAD_TOOL) {
+            _ = self.state.output.text(payload.describe());
+            return convert(payload.call_result());
+        }
+        if let Some(result) = self.state.paging.call_tool(&name) {
+            return self.spec_result(convert(result));
+        }
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(spec.call_tool(&name, &params["arguments"]));
+        }
+
         // Send notification about tool call
         self.send_log_message(
             context,
@@ -369,6 +517,7 @@ impl ServerHandler for TestServerConn {
 
     async fn set_level(&self, context: &ServerCtx, level: LoggingLevel) -> Result<()> {
         _ = self.state.output.h1("set_level");
+        self.inject("logging/setLevel").await?;
         _ = self.state.output.text(format!(
             "level: {}",
             serde_json::to_string_pretty(&level).unwrap()
@@ -423,6 +572,7 @@ impl ServerHandler for TestServerConn {
         cursor: Option<Cursor>,
     ) -> Result<ListPromptsResult> {
         _ = self.state.output.h1("list_prompts");
+        self.inject("prompts/list").await?;
         let params = serde_json::json!({
             "cursor": cursor,
         });
@@ -431,6 +581,13 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(
+                spec.list_prompts()
+                    .and_then(|r| self.page(r, "prompts", &params)),
+            );
+        }
+
         let greeting_prompt = Prompt {
             name: "greeting".to_string(),
             title: None,
@@ -476,6 +633,7 @@ impl ServerHandler for TestServerConn {
         let result = ListPromptsResult::default()
             .with_prompt(greeting_prompt)
             .with_prompt(code_review_prompt);
+        let result = self.page(result, "prompts", &params)?;
 
         _ = self.state.output.text(format!(
             "result: {}",
@@ -492,6 +650,7 @@ impl ServerHandler for TestServerConn {
         arguments: Option<tmcp::Arguments>,
     ) -> Result<GetPromptResult> {
         _ = self.state.output.h1("get_prompt");
+        self.inject("prompts/get").await?;
         let params = serde_json::json!({
             "name": name,
             "arguments": arguments,
@@ -501,6 +660,10 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(spec.get_prompt(&name, &params["arguments"]));
+        }
+
         let result = match name.as_str() {
             "greeting" => {
                 let name = arguments
@@ -556,6 +719,7 @@ impl ServerHandler for TestServerConn {
         cursor: Option<Cursor>,
     ) -> Result<ListResourcesResult> {
         _ = self.state.output.h1("list_resources");
+        self.inject("resources/list").await?;
         let params = serde_json::json!({
             "cursor": cursor,
         });
@@ -564,6 +728,14 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(
+                spec.list_resources()
+                    .and_then(|r| self.with_payload_resource(r))
+                    .and_then(|r| self.page(r, "resources", &params)),
+            );
+        }
+
         let log_resource = Resource::new("server-log", "log://testserver/current")
             .with_description("Current test server log")
             .with_mime_type("text/plain");
@@ -577,10 +749,13 @@ impl ServerHandler for TestServerConn {
             .with_description("Server performance metrics")
             .with_mime_type("text/plain");
 
-        let result = ListResourcesResult::default()
-            .with_resource(log_resource)
-            .with_resource(sample_data_resource)
-            .with_resource(metrics_resource);
+        let result = self.with_payload_resource(
+            ListResourcesResult::default()
+                .with_resource(log_resource)
+                .with_resource(sample_data_resource)
+                .with_resource(metrics_resource),
+        )?;
+        let result = self.page(result, "resources", &params)?;
 
         _ = self.state.output.text(format!(
             "result: {}",
@@ -592,6 +767,7 @@ impl ServerHandler for TestServerConn {
 
     async fn read_resource(&self, context: &ServerCtx, uri: String) -> Result<ReadResourceResult> {
         _ = self.state.output.h1("read_resource");
+        self.inject("resources/read").await?;
         let params = serde_json::json!({
             "uri": uri,
         });
@@ -600,6 +776,17 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(payload) = self.state.payload.as_ref().filter(|_| uri == PAYLOAD_URI) {
+            _ = self.state.output.text(payload.describe());
+            return convert(payload.contents());
+        }
+        if let Some(result) = self.state.paging.read_resource(&uri) {
+            return self.spec_result(convert(result));
+        }
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(spec.read_resource(&uri));
+        }
+
         // Send notification about resource access
         self.send_log_message(
             context,
@@ -676,6 +863,7 @@ impl ServerHandler for TestServerConn {
         cursor: Option<Cursor>,
     ) -> Result<ListResourceTemplatesResult> {
         _ = self.state.output.h1("list_resource_templates");
+        self.inject("resources/templates/list").await?;
         let params = serde_json::json!({
             "cursor": cursor,
         });
@@ -684,6 +872,13 @@ impl ServerHandler for TestServerConn {
             serde_json::to_string_pretty(&params).unwrap()
         ));
 
+        if let Some(spec) = &self.state.spec {
+            return self.spec_result(
+                spec.list_resource_templates()
+                    .and_then(|r| self.page(r, "resourceTemplates", &params)),
+            );
+        }
+
         // Create sample resource templates
         let user_template =
             ResourceTemplate::new("user-profile", "user://testserver/{user_id}/profile")
@@ -725,6 +920,7 @@ impl ServerHandler for TestServerConn {
             .with_resource_template(log_template)
             .with_resource_template(config_template)
             .with_resource_template(metrics_template);
+        let result = self.page(result, "resourceTemplates", &params)?;
 
         _ = self.state.output.text(format!(
             "result: {}",
@@ -1011,15 +1207,37 @@ fn run_interactive_repl_blocking(
     Ok(())
 }
 
+/// Sends a storm of notifications to every connected client, for as long as
+/// the server runs.
+async fn run_storm(state: TestServerState, storm: Storm) {
+    let mut interval = time::interval(storm.interval());
+    let mut count = 0;
+    loop {
+        interval.tick().await;
+        if state.get_client_count() == 0 {
+            continue;
+        }
+        // A client that has gone away shouldn't stop the storm for the others
+        _ = state
+            .broadcast_notification(storm.notification(count))
+            .await;
+        count += 1;
+    }
+}
+
 /// Create a configured server instance with test connection handler
 fn create_test_server(
     output: Output,
     request_counter: Arc<AtomicU64>,
+    spec: Option<Arc<ServerSpec>>,
+    faults: Faults,
+    payload: Option<Payload>,
+    paging: Paging,
 ) -> (
     Server<impl Fn() -> Box<dyn ServerHandler> + Clone + Send + Sync + 'static>,
     TestServerState,
 ) {
-    let state = TestServerState::new(output, request_counter);
+    let state = TestServerState::new(output, request_counter, spec, faults, payload, paging);
     let state_for_conn = state.clone();
 
     let server = Server::default()
@@ -1074,21 +1292,57 @@ where
 async fn handle_tcp_non_interactive(
     server: Server<impl Fn() -> Box<dyn ServerHandler> + Clone + Send + Sync + 'static>,
     addr: &str,
+    chaos: Option<Arc<Chaos>>,
     output: &Output,
 ) -> Result<()> {
     _ = output.text("Transport: TCP");
     _ = output.trace_success(format!("Listening on: tcp://{}", addr));
     _ = output.text("Press Ctrl+C to stop the server");
-    server.serve_tcp(addr).await
+    serve_tcp(server, addr, chaos, output).await
 }
 
-pub async fn run_test_server(
-    ctx: &Ctx,
-    stdio: bool,
-    tcp: bool,
-    port: u16,
-    interactive: bool,
+/// Serves over TCP, behind a chaos shim if chaos is configured. The real
+/// server then listens on a local port picked by the OS, and the shim on
+/// `addr`. The port is bound once and handed to the server, so nothing else
+/// can take it in between.
+async fn serve_tcp(
+    server: Server<impl Fn() -> Box<dyn ServerHandler> + Clone + Send + Sync + 'static>,
+    addr: &str,
+    chaos: Option<Arc<Chaos>>,
+    output: &Output,
 ) -> Result<()> {
+    let Some(chaos) = chaos else {
+        return server.serve_tcp(addr).await;
+    };
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| Error::InternalError(e.to_string()))?;
+    let upstream = listener
+        .local_addr()
+        .map_err(|e| Error::InternalError(e.to_string()))?
+        .to_string();
+    tokio::select! {
+        result = server.serve_tcp_listener(listener) => result,
+        result = chaos.serve(addr, &upstream, output.clone()) => {
+            result.map_err(|e| Error::InternalError(e.to_string()))
+        }
+    }
+}
+
+pub async fn run_test_server(ctx: &Ctx, options: TestServerOptions) -> Result<()> {
+    let TestServerOptions {
+        stdio,
+        tcp,
+        port,
+        interactive,
+        spec,
+        faults,
+        payload,
+        storm,
+        paging,
+        chaos,
+    } = options;
+
     // Validate that only one transport is specified
     let transport_count = [stdio, tcp].iter().filter(|&&x| x).count();
     if transport_count > 1 {
@@ -1097,6 +1351,13 @@ pub async fn run_test_server(
         ));
     }
 
+    // Chaos works on the TCP byte stream, which stdio doesn't give us access to
+    if chaos.is_some() && stdio {
+        return Err(Error::InvalidConfiguration(
+            "Chaos mode is not compatible with stdio transport".to_string(),
+        ));
+    }
+
     // Interactive mode is incompatible with stdio transport
     if interactive && stdio {
         return Err(Error::InvalidConfiguration(
@@ -1105,8 +1366,8 @@ pub async fn run_test_server(
     }
 
     let output = if stdio {
-        // In stdio mode, silence all output
-        ctx.output.clone().with_quiet(true)
+        // In stdio mode stdout carries the protocol, so silence all output
+        ctx.output.with_sink(io::sink()).with_quiet(true)
     } else {
         ctx.output.clone()
     };
@@ -1115,9 +1376,44 @@ pub async fn run_test_server(
     _ = output.text(format!("Version: {}", env!("CARGO_PKG_VERSION")));
     _ = output.text(format!("Protocol: {}", LATEST_PROTOCOL_VERSION));
 
+    let spec = match spec {
+        Some(path) => {
+            let spec = ServerSpec::load(&path)?;
+            _ = output.text(format!("Spec: {}", path.display()));
+            Some(Arc::new(spec))
+        }
+        None => None,
+    };
+    if faults.is_active() {
+        _ = output.text(format!("Faults: {}", faults.describe()));
+    }
+    if let Some(payload) = &payload {
+        _ = output.text(format!("Payload: {}", payload.describe()));
+    }
+    if let Some(storm) = &storm {
+        _ = output.text(format!("Storm: {}", storm.describe()));
+    }
+    if paging.is_active() {
+        _ = output.text(format!("Paging: {}", paging.describe()));
+    }
+    if let Some(chaos) = &chaos {
+        _ = output.text(format!("Chaos: {}", chaos.describe()));
+    }
+    let chaos = chaos.map(Arc::new);
+
     // Create shared request counter for interactive mode
     let request_counter = Arc::new(AtomicU64::new(0));
-    let (server, server_state) = create_test_server(output.clone(), request_counter.clone());
+    let (server, server_state) = create_test_server(
+        output.clone(),
+        request_counter.clone(),
+        spec,
+        faults,
+        payload,
+        paging,
+    );
+    if let Some(storm) = storm {
+        task::spawn(run_storm(server_state.clone(), storm));
+    }
 
     if stdio {
         server.serve_stdio().await?;
@@ -1129,11 +1425,11 @@ pub async fn run_test_server(
                 format!("tcp://{addr}"),
                 server_state,
                 &output.clone(),
-                || async move { server.serve_tcp(&addr).await },
+                || async move { serve_tcp(server, &addr, chaos, &output).await },
             )
             .await?;
         } else {
-            handle_tcp_non_interactive(server, &addr, &output).await?;
+            handle_tcp_non_interactive(server, &addr, chaos, &output).await?;
         }
     }
 
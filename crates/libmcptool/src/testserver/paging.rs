@@ -0,0 +1,165 @@
+//! Paginated listings: splitting tool, prompt and resource lists into small
+//! pages with cursors, padded out with generated items so there are many
+//! pages to walk.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::{Value, json};
+use tmcp::{Error, Result};
+
+/// The prefix of generated tool names.
+const BULK_TOOL: &str = "bulk_tool_";
+
+/// The prefix of generated resource URIs.
+const BULK_RESOURCE: &str = "bulk://testserver/";
+
+/// How listings are padded and paginated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Paging {
+    /// The most items in a page, or None to return whole listings.
+    pub page_size: Option<usize>,
+    /// The number of generated tools, and of generated resources, added to
+    /// the listings.
+    pub bulk: usize,
+}
+
+/// The cursor for the page starting at an offset. Cursors are opaque to
+/// clients, so they're encoded to discourage depending on their contents.
+fn encode_cursor(offset: usize) -> String {
+    URL_SAFE_NO_PAD.encode(format!("offset:{offset}"))
+}
+
+/// The offset a cursor points at.
+fn decode_cursor(cursor: &str) -> Result<usize> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|text| text.strip_prefix("offset:")?.parse().ok())
+        .ok_or_else(|| Error::InvalidParams(format!("Invalid cursor: {cursor}")))
+}
+
+impl Paging {
+    /// Whether listings are changed at all.
+    pub fn is_active(&self) -> bool {
+        self.page_size.is_some() || self.bulk > 0
+    }
+
+    /// A one-line summary, for the server banner.
+    pub fn describe(&self) -> String {
+        let pages = match self.page_size {
+            Some(size) => format!("pages of {size}"),
+            None => "no pages".to_string(),
+        };
+        format!("{} generated tools and resources, {pages}", self.bulk)
+    }
+
+    /// The generated items for a listing field.
+    fn generated(&self, field: &str) -> Vec<Value> {
+        (1..=self.bulk)
+            .filter_map(|index| match field {
+                "tools" => Some(json!({
+                    "name": format!("{BULK_TOOL}{index:04}"),
+                    "description": format!("Generated tool {index}"),
+                    "inputSchema": { "type": "object" },
+                })),
+                "resources" => Some(json!({
+                    "uri": format!("{BULK_RESOURCE}{index:04}"),
+                    "name": format!("bulk-{index:04}"),
+                    "description": format!("Generated resource {index}"),
+                    "mimeType": "text/plain",
+                })),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Pads a listing's `field` array with generated items and cuts it down
+    /// to the page starting at the cursor, setting `nextCursor` if more
+    /// pages follow.
+    pub fn apply(&self, mut listing: Value, field: &str, cursor: Option<&str>) -> Result<Value> {
+        let mut items = match listing.get_mut(field).map(Value::take) {
+            Some(Value::Array(items)) => items,
+            _ => Vec::new(),
+        };
+        items.extend(self.generated(field));
+        let total = items.len();
+        let start = cursor.map(decode_cursor).transpose()?.unwrap_or(0);
+        if start > total {
+            return Err(Error::InvalidParams(format!(
+                "Cursor is past the end of the {field} listing"
+            )));
+        }
+        let end = self
+            .page_size
+            .map_or(total, |size| (start + size).min(total));
+        listing[field] = Value::Array(items.drain(start..end).collect());
+        if end < total {
+            listing["nextCursor"] = json!(encode_cursor(end));
+        } else if let Some(listing) = listing.as_object_mut() {
+            listing.remove("nextCursor");
+        }
+        Ok(listing)
+    }
+
+    /// The result of calling a generated tool, or None if `name` isn't one.
+    pub fn call_tool(&self, name: &str) -> Option<Value> {
+        let index: usize = name.strip_prefix(BULK_TOOL)?.parse().ok()?;
+        (1..=self.bulk).contains(&index).then(|| {
+            json!({ "content": [{ "type": "text", "text": format!("Called generated tool {index}") }] })
+        })
+    }
+
+    /// The contents of a generated resource, or None if `uri` isn't one.
+    pub fn read_resource(&self, uri: &str) -> Option<Value> {
+        let index: usize = uri.strip_prefix(BULK_RESOURCE)?.parse().ok()?;
+        (1..=self.bulk).contains(&index).then(|| {
+            json!({
+                "contents": [{
+                    "uri": uri,
+                    "mimeType": "text/plain",
+                    "text": format!("Generated resource {index}"),
+                }],
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let paging = Paging {
+            page_size: Some(10),
+            bulk: 25,
+        };
+        let listing = json!({ "tools": [{ "name": "echo" }] });
+
+        let mut names = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0;
+        loop {
+            let page = paging
+                .apply(listing.clone(), "tools", cursor.as_deref())
+                .unwrap();
+            pages += 1;
+            for tool in page["tools"].as_array().unwrap() {
+                names.push(tool["name"].as_str().unwrap().to_string());
+            }
+            match page.get("nextCursor") {
+                Some(next) => cursor = Some(next.as_str().unwrap().to_string()),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(names.len(), 26);
+        assert_eq!(names[0], "echo");
+        assert_eq!(names[25], "bulk_tool_0025");
+
+        assert!(paging.apply(listing, "tools", Some("nonsense")).is_err());
+        assert!(paging.call_tool("bulk_tool_0025").is_some());
+        assert!(paging.call_tool("bulk_tool_0026").is_none());
+        assert!(paging.read_resource("bulk://testserver/0001").is_some());
+    }
+}
@@ -0,0 +1,175 @@
+//! Large generated payloads, served as a resource and a tool result, for
+//! exercising how clients handle big responses.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+
+use crate::{Error, Result};
+
+/// The URI the payload is served at.
+pub const PAYLOAD_URI: &str = "payload://testserver/blob";
+
+/// The name of the tool that returns the payload.
+pub const PAYLOAD_TOOL: &str = "payload";
+
+/// Parses a size such as `512`, `64KB`, `50MB` or `1GiB`. KB, MB and GB are
+/// powers of 1000, and KiB, MiB and GiB powers of 1024.
+pub fn parse_size(input: &str) -> Result<usize> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(Error::Format(format!("Unknown size unit in '{input}'"))),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| Error::Format(format!("Invalid size '{input}'")))?;
+    Ok((number * multiplier) as usize)
+}
+
+/// The bytes files of a type start with, so sniffers recognize the payload.
+fn magic(content_type: &str) -> &'static [u8] {
+    match content_type {
+        "image/png" => b"\x89PNG\r\n\x1a\n",
+        "image/gif" => b"GIF89a",
+        "image/jpeg" => &[0xFF, 0xD8, 0xFF],
+        "application/pdf" => b"%PDF-1.7\n",
+        _ => b"",
+    }
+}
+
+/// A generated payload of a given size and content type.
+#[derive(Debug, Clone)]
+pub struct Payload {
+    /// The payload's MIME type.
+    content_type: String,
+    /// The payload as text, for text types.
+    text: Option<String>,
+    /// The payload, base64 encoded, for binary types.
+    blob: Option<String>,
+    /// The payload size in bytes, before any encoding.
+    size: usize,
+}
+
+impl Payload {
+    /// Generates a payload. Text and JSON types get readable filler text;
+    /// other types get the format's magic bytes followed by filler bytes,
+    /// which exercise size handling rather than decoding.
+    pub fn new(size: usize, content_type: &str) -> Self {
+        let is_text = content_type.starts_with("text/") || content_type.ends_with("json");
+        let line = "The quick brown fox jumps over the lazy dog. ";
+        let filler: String = line.chars().cycle().take(size).collect();
+        let (text, blob) = if content_type.ends_with("json") {
+            // A JSON string of exactly `size` bytes, including its quotes
+            let text = format!("\"{}\"", &filler[..size.saturating_sub(2)]);
+            (Some(text), None)
+        } else if is_text {
+            (Some(filler), None)
+        } else {
+            let mut bytes = magic(content_type).to_vec();
+            bytes.extend((0..size.saturating_sub(bytes.len())).map(|i| (i % 251) as u8));
+            bytes.truncate(size);
+            (None, Some(STANDARD.encode(bytes)))
+        };
+        Self {
+            content_type: content_type.to_string(),
+            text,
+            blob,
+            size,
+        }
+    }
+
+    /// A one-line summary of the payload, for the server banner.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} bytes of {} at {PAYLOAD_URI} and from the {PAYLOAD_TOOL} tool",
+            self.size, self.content_type
+        )
+    }
+
+    /// The payload's listing as a resource.
+    pub fn resource(&self) -> Value {
+        json!({
+            "uri": PAYLOAD_URI,
+            "name": "payload",
+            "description": "A generated payload for testing large responses",
+            "mimeType": self.content_type,
+            "size": self.size,
+        })
+    }
+
+    /// The payload's listing as a tool.
+    pub fn tool(&self) -> Value {
+        json!({
+            "name": PAYLOAD_TOOL,
+            "description": format!("Returns {} bytes of {}", self.size, self.content_type),
+            "inputSchema": { "type": "object" },
+        })
+    }
+
+    /// The result of reading the payload resource.
+    pub fn contents(&self) -> Value {
+        let mut contents = json!({ "uri": PAYLOAD_URI, "mimeType": self.content_type });
+        match (&self.text, &self.blob) {
+            (Some(text), _) => contents["text"] = json!(text),
+            (None, blob) => contents["blob"] = json!(blob),
+        }
+        json!({ "contents": [contents] })
+    }
+
+    /// The result of calling the payload tool: text, an image, or an
+    /// embedded resource, depending on the content type.
+    pub fn call_result(&self) -> Value {
+        let content = match (&self.text, &self.blob) {
+            (Some(text), _) => json!({ "type": "text", "text": text }),
+            (None, Some(blob)) if self.content_type.starts_with("image/") => {
+                json!({ "type": "image", "data": blob, "mimeType": self.content_type })
+            }
+            (None, blob) => json!({
+                "type": "resource",
+                "resource": { "uri": PAYLOAD_URI, "mimeType": self.content_type, "blob": blob },
+            }),
+        };
+        json!({ "content": [content] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("64KB").unwrap(), 64_000);
+        assert_eq!(parse_size("50MB").unwrap(), 50_000_000);
+        assert_eq!(parse_size("1.5 MiB").unwrap(), 1_572_864);
+        assert!(parse_size("10 parsecs").is_err());
+        assert!(parse_size("MB").is_err());
+    }
+
+    #[test]
+    fn test_payload() {
+        let text = Payload::new(100, "text/plain");
+        assert_eq!(text.text.as_ref().unwrap().len(), 100);
+
+        let json = Payload::new(100, "application/json");
+        let parsed: Value = serde_json::from_str(json.text.as_ref().unwrap()).unwrap();
+        assert_eq!(parsed.as_str().unwrap().len(), 98);
+
+        let png = Payload::new(1000, "image/png");
+        let bytes = STANDARD.decode(png.blob.as_ref().unwrap()).unwrap();
+        assert_eq!(bytes.len(), 1000);
+        assert!(bytes.starts_with(b"\x89PNG"));
+        assert_eq!(png.call_result()["content"][0]["type"], "image");
+    }
+}
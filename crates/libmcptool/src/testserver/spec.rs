@@ -0,0 +1,487 @@
+//! Declarative test server definitions: the tools, prompts and resources a
+//! test server offers, and what it answers with, read from a TOML file.
+
+use std::{fs, path::Path};
+
+use minijinja::Environment;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tmcp::{
+    Error, Result,
+    schema::{
+        CallToolResult, GetPromptResult, InitializeResult, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, ReadResourceResult,
+    },
+};
+
+use super::convert;
+
+/// Details the server reports when a client initializes.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerInfo {
+    /// The server name.
+    pub name: Option<String>,
+    /// The server version.
+    pub version: Option<String>,
+    /// Instructions for clients.
+    pub instructions: Option<String>,
+}
+
+/// A tool, and what calling it returns.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolSpec {
+    /// The tool name.
+    pub name: String,
+    /// What the tool does.
+    pub description: Option<String>,
+    /// The JSON schema of the tool's arguments. Defaults to any object.
+    pub input_schema: Option<Value>,
+    /// A template rendered with the call's arguments, returned as text.
+    pub text: Option<String>,
+    /// A static result, returned as is.
+    pub result: Option<Value>,
+    /// A template rendered with the call's arguments, returned as an error
+    /// result.
+    pub error: Option<String>,
+}
+
+/// An argument to a prompt.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptArgumentSpec {
+    /// The argument name.
+    pub name: String,
+    /// What the argument is for.
+    pub description: Option<String>,
+    /// Whether the argument must be given.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A message a prompt produces.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptMessageSpec {
+    /// Who the message is from, "user" or "assistant".
+    #[serde(default = "PromptMessageSpec::default_role")]
+    pub role: String,
+    /// A template rendered with the prompt's arguments.
+    pub text: String,
+}
+
+impl PromptMessageSpec {
+    /// Messages are from the user unless said otherwise.
+    fn default_role() -> String {
+        "user".to_string()
+    }
+}
+
+/// A prompt, and the messages it produces.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptSpec {
+    /// The prompt name.
+    pub name: String,
+    /// What the prompt is for.
+    pub description: Option<String>,
+    /// The prompt's arguments.
+    #[serde(default)]
+    pub arguments: Vec<PromptArgumentSpec>,
+    /// The messages the prompt produces.
+    #[serde(default)]
+    pub messages: Vec<PromptMessageSpec>,
+}
+
+/// A resource with fixed contents.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceSpec {
+    /// The resource URI.
+    pub uri: String,
+    /// The resource name.
+    pub name: String,
+    /// What the resource holds.
+    pub description: Option<String>,
+    /// The MIME type of the contents.
+    pub mime_type: Option<String>,
+    /// Text contents.
+    pub text: Option<String>,
+    /// Binary contents, base64 encoded.
+    pub blob: Option<String>,
+}
+
+/// A family of resources whose URIs match a template.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceTemplateSpec {
+    /// The URI template, with `{name}` placeholders.
+    pub uri_template: String,
+    /// The template name.
+    pub name: String,
+    /// What the resources hold.
+    pub description: Option<String>,
+    /// The MIME type of the contents.
+    pub mime_type: Option<String>,
+    /// A template rendered with the URI's placeholder values.
+    pub text: String,
+}
+
+/// A test server definition.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSpec {
+    /// Details reported on initialization.
+    #[serde(default)]
+    pub server: ServerInfo,
+    /// The tools offered.
+    #[serde(default)]
+    pub tools: Vec<ToolSpec>,
+    /// The prompts offered.
+    #[serde(default)]
+    pub prompts: Vec<PromptSpec>,
+    /// The fixed resources offered.
+    #[serde(default)]
+    pub resources: Vec<ResourceSpec>,
+    /// The templated resources offered.
+    #[serde(default)]
+    pub resource_templates: Vec<ResourceTemplateSpec>,
+}
+
+/// Checks a template for syntax errors.
+fn check_template(what: &str, source: &str) -> Result<()> {
+    Environment::new()
+        .template_from_str(source)
+        .map(|_| ())
+        .map_err(|e| Error::InvalidConfiguration(format!("Invalid template in {what}: {e}")))
+}
+
+/// Renders a template. The fields of `values` are variables, and `values`
+/// is also available as a whole as `arguments`.
+fn render(source: &str, values: &Value) -> Result<String> {
+    let mut context = values.as_object().cloned().unwrap_or_default();
+    context.insert("arguments".to_string(), values.clone());
+    Environment::new()
+        .render_str(source, context)
+        .map_err(|e| Error::InternalError(format!("Failed to render template: {e}")))
+}
+
+/// The values of a URI template's `{name}` placeholders in a URI, or None if
+/// the URI doesn't match the template.
+fn match_uri(template: &str, uri: &str) -> Option<Map<String, Value>> {
+    let mut values = Map::new();
+    let mut template = template;
+    let mut uri = uri;
+    while !template.is_empty() {
+        match template.find('{') {
+            Some(0) => {
+                let end = template.find('}')?;
+                let name = &template[1..end];
+                template = &template[end + 1..];
+                // A placeholder runs up to the next literal text, or the end
+                let literal = &template[..template.find('{').unwrap_or(template.len())];
+                let len = if literal.is_empty() {
+                    uri.len()
+                } else {
+                    uri.find(literal)?
+                };
+                if len == 0 {
+                    return None;
+                }
+                values.insert(name.to_string(), Value::from(&uri[..len]));
+                uri = &uri[len..];
+            }
+            next => {
+                let literal = &template[..next.unwrap_or(template.len())];
+                uri = uri.strip_prefix(literal)?;
+                template = &template[literal.len()..];
+            }
+        }
+    }
+    uri.is_empty().then_some(values)
+}
+
+impl ServerSpec {
+    /// Reads a definition from a TOML file, checking its templates.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            Error::InvalidConfiguration(format!("Failed to read {}: {e}", path.display()))
+        })?;
+        let spec: Self = toml::from_str(&contents).map_err(|e| {
+            Error::InvalidConfiguration(format!("Invalid server spec {}: {e}", path.display()))
+        })?;
+        spec.check()?;
+        Ok(spec)
+    }
+
+    /// Checks that each tool has one kind of response and that every
+    /// template parses.
+    fn check(&self) -> Result<()> {
+        for tool in &self.tools {
+            let what = format!("tool {}", tool.name);
+            let responses = [
+                tool.text.is_some(),
+                tool.result.is_some(),
+                tool.error.is_some(),
+            ];
+            if responses.iter().filter(|set| **set).count() != 1 {
+                return Err(Error::InvalidConfiguration(format!(
+                    "{what} needs exactly one of text, result or error"
+                )));
+            }
+            for source in [&tool.text, &tool.error].into_iter().flatten() {
+                check_template(&what, source)?;
+            }
+        }
+        for prompt in &self.prompts {
+            for message in &prompt.messages {
+                check_template(&format!("prompt {}", prompt.name), &message.text)?;
+            }
+        }
+        for resource in &self.resources {
+            if resource.text.is_some() == resource.blob.is_some() {
+                return Err(Error::InvalidConfiguration(format!(
+                    "resource {} needs exactly one of text or blob",
+                    resource.uri
+                )));
+            }
+        }
+        for template in &self.resource_templates {
+            check_template(
+                &format!("resource template {}", template.uri_template),
+                &template.text,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The result of initialization, advertising what the spec offers.
+    pub fn initialize(&self) -> InitializeResult {
+        let info = &self.server;
+        let result = InitializeResult::new(info.name.as_deref().unwrap_or("mcptool-testserver"))
+            .with_version(info.version.as_deref().unwrap_or(env!("CARGO_PKG_VERSION")))
+            .with_tools(!self.tools.is_empty())
+            .with_prompts(!self.prompts.is_empty())
+            .with_resources(
+                !self.resources.is_empty() || !self.resource_templates.is_empty(),
+                false,
+            );
+        match &info.instructions {
+            Some(instructions) => result.with_instructions(instructions),
+            None => result,
+        }
+    }
+
+    /// The tools the spec offers.
+    pub fn list_tools(&self) -> Result<ListToolsResult> {
+        let tools: Vec<Value> = self
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema.clone().unwrap_or_else(|| json!({"type": "object"})),
+                })
+            })
+            .collect();
+        convert(json!({ "tools": tools }))
+    }
+
+    /// Calls a tool, rendering its response with the arguments.
+    pub fn call_tool(&self, name: &str, arguments: &Value) -> Result<CallToolResult> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .ok_or_else(|| Error::ToolNotFound(format!("Unknown tool: {name}")))?;
+        if let Some(result) = &tool.result {
+            return convert(result.clone());
+        }
+        let (source, is_error) = match (&tool.text, &tool.error) {
+            (Some(text), _) => (text, false),
+            (None, Some(error)) => (error, true),
+            (None, None) => return convert(json!({ "content": [] })),
+        };
+        let text = render(source, arguments)?;
+        convert(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        }))
+    }
+
+    /// The prompts the spec offers.
+    pub fn list_prompts(&self) -> Result<ListPromptsResult> {
+        let prompts: Vec<Value> = self
+            .prompts
+            .iter()
+            .map(|prompt| {
+                let arguments: Vec<Value> = prompt
+                    .arguments
+                    .iter()
+                    .map(|argument| {
+                        json!({
+                            "name": argument.name,
+                            "description": argument.description,
+                            "required": argument.required,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": prompt.name,
+                    "description": prompt.description,
+                    "arguments": arguments,
+                })
+            })
+            .collect();
+        convert(json!({ "prompts": prompts }))
+    }
+
+    /// Gets a prompt, rendering its messages with the arguments.
+    pub fn get_prompt(&self, name: &str, arguments: &Value) -> Result<GetPromptResult> {
+        let prompt = self
+            .prompts
+            .iter()
+            .find(|prompt| prompt.name == name)
+            .ok_or_else(|| Error::MethodNotFound(format!("Unknown prompt: {name}")))?;
+        let mut messages = Vec::new();
+        for message in &prompt.messages {
+            messages.push(json!({
+                "role": message.role,
+                "content": { "type": "text", "text": render(&message.text, arguments)? },
+            }));
+        }
+        convert(json!({
+            "description": prompt.description,
+            "messages": messages,
+        }))
+    }
+
+    /// The fixed resources the spec offers.
+    pub fn list_resources(&self) -> Result<ListResourcesResult> {
+        let resources: Vec<Value> = self
+            .resources
+            .iter()
+            .map(|resource| {
+                json!({
+                    "uri": resource.uri,
+                    "name": resource.name,
+                    "description": resource.description,
+                    "mimeType": resource.mime_type,
+                })
+            })
+            .collect();
+        convert(json!({ "resources": resources }))
+    }
+
+    /// The resource templates the spec offers.
+    pub fn list_resource_templates(&self) -> Result<ListResourceTemplatesResult> {
+        let templates: Vec<Value> = self
+            .resource_templates
+            .iter()
+            .map(|template| {
+                json!({
+                    "uriTemplate": template.uri_template,
+                    "name": template.name,
+                    "description": template.description,
+                    "mimeType": template.mime_type,
+                })
+            })
+            .collect();
+        convert(json!({ "resourceTemplates": templates }))
+    }
+
+    /// Reads a resource: a fixed one with this URI, or else the first
+    /// template the URI matches, rendered with the URI's values.
+    pub fn read_resource(&self, uri: &str) -> Result<ReadResourceResult> {
+        if let Some(resource) = self.resources.iter().find(|resource| resource.uri == uri) {
+            let mut contents = json!({ "uri": uri, "mimeType": resource.mime_type });
+            // Contents are told apart by which of these fields they have
+            match (&resource.text, &resource.blob) {
+                (Some(text), _) => contents["text"] = json!(text),
+                (None, blob) => contents["blob"] = json!(blob),
+            }
+            return convert(json!({ "contents": [contents] }));
+        }
+        for template in &self.resource_templates {
+            if let Some(values) = match_uri(&template.uri_template, uri) {
+                let text = render(&template.text, &Value::Object(values))?;
+                return convert(json!({
+                    "contents": [{ "uri": uri, "mimeType": template.mime_type, "text": text }],
+                }));
+            }
+        }
+        Err(Error::ResourceNotFound {
+            uri: uri.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A spec using each kind of definition.
+    const SPEC: &str = r#"
+        [server]
+        name = "acme"
+
+        [[tools]]
+        name = "greet"
+        input_schema = { type = "object", properties = { name = { type = "string" } } }
+        text = "Hello, {{ name }}!"
+
+        [[tools]]
+        name = "broken"
+        error = "Nothing works"
+
+        [[prompts]]
+        name = "review"
+        arguments = [{ name = "code", required = true }]
+        messages = [{ text = "Review {{ code }}" }]
+
+        [[resource_templates]]
+        uri_template = "user://{id}/profile"
+        name = "profile"
+        text = "Profile of {{ id }}"
+    "#;
+
+    #[test]
+    fn test_spec() {
+        let spec: ServerSpec = toml::from_str(SPEC).unwrap();
+        spec.check().unwrap();
+        assert_eq!(spec.tools.len(), 2);
+
+        let result = spec.call_tool("greet", &json!({"name": "Ada"})).unwrap();
+        let result = serde_json::to_value(result).unwrap();
+        assert_eq!(result["content"][0]["text"], "Hello, Ada!");
+        let result = serde_json::to_value(spec.call_tool("broken", &json!({})).unwrap()).unwrap();
+        assert_eq!(result["isError"], true);
+        assert!(spec.call_tool("missing", &json!({})).is_err());
+
+        let prompt = spec
+            .get_prompt("review", &json!({"code": "x = 1"}))
+            .unwrap();
+        let prompt = serde_json::to_value(prompt).unwrap();
+        assert_eq!(prompt["messages"][0]["content"]["text"], "Review x = 1");
+
+        let resource =
+            serde_json::to_value(spec.read_resource("user://42/profile").unwrap()).unwrap();
+        assert_eq!(resource["contents"][0]["text"], "Profile of 42");
+        assert!(spec.read_resource("user://42").is_err());
+
+        let invalid: ServerSpec = toml::from_str("[[tools]]\nname = \"none\"").unwrap();
+        assert!(invalid.check().is_err());
+    }
+
+    #[test]
+    fn test_match_uri() {
+        let values = match_uri("config://{section}/{key}", "config://db/host").unwrap();
+        assert_eq!(values["section"], "db");
+        assert_eq!(values["key"], "host");
+        assert!(match_uri("config://{section}/{key}", "config://db").is_none());
+        assert!(match_uri("log://{date}/entries", "log:///entries").is_none());
+        assert!(match_uri("a://fixed", "a://fixed").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Notification storms: a steady stream of server notifications sent to
+//! every connected client, for load-testing notification handling.
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+use tmcp::schema::{LoggingLevel, ProgressToken, ServerNotification};
+
+/// A kind of notification a storm sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StormKind {
+    /// Log messages, cycling through the levels.
+    Log,
+    /// Progress on a never-ending operation.
+    Progress,
+    /// Updates to a resource.
+    ResourceUpdated,
+    /// Changes to the tool, prompt and resource lists, in turn.
+    ListChanged,
+}
+
+/// A storm of notifications.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Storm {
+    /// Notifications sent per second.
+    pub rate: f64,
+    /// The kinds of notification sent, in turn. Empty means all of them.
+    pub kinds: Vec<StormKind>,
+}
+
+impl Storm {
+    /// The kinds of notification sent, in the order they're cycled through.
+    fn kinds(&self) -> &[StormKind] {
+        if self.kinds.is_empty() {
+            &[
+                StormKind::Log,
+                StormKind::Progress,
+                StormKind::ResourceUpdated,
+                StormKind::ListChanged,
+            ]
+        } else {
+            &self.kinds
+        }
+    }
+
+    /// The time between notifications.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.rate)
+    }
+
+    /// A one-line summary of the storm, for the server banner.
+    pub fn describe(&self) -> String {
+        let kinds: Vec<String> = self
+            .kinds()
+            .iter()
+            .filter_map(|kind| kind.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        format!("{}/s of {}", self.rate, kinds.join(", "))
+    }
+
+    /// The `count`th notification of the storm.
+    pub fn notification(&self, count: u64) -> ServerNotification {
+        let kinds = self.kinds();
+        let round = count / kinds.len() as u64;
+        match kinds[(count % kinds.len() as u64) as usize] {
+            StormKind::Log => {
+                let levels = [
+                    LoggingLevel::Debug,
+                    LoggingLevel::Info,
+                    LoggingLevel::Warning,
+                    LoggingLevel::Error,
+                ];
+                ServerNotification::LoggingMessage {
+                    level: levels[(round % levels.len() as u64) as usize],
+                    logger: Some("testserver-storm".to_string()),
+                    data: serde_json::json!({ "message": format!("Storm notification {count}") }),
+                }
+            }
+            StormKind::Progress => ServerNotification::Progress {
+                progress_token: ProgressToken::String("storm".to_string()),
+                progress: (round % 100) as f64,
+                total: Some(100.0),
+                message: Some(format!("Storm notification {count}")),
+            },
+            StormKind::ResourceUpdated => ServerNotification::ResourceUpdated {
+                uri: "metrics://testserver/stats".to_string(),
+            },
+            StormKind::ListChanged => match round % 3 {
+                0 => ServerNotification::ToolListChanged,
+                1 => ServerNotification::PromptListChanged,
+                _ => ServerNotification::ResourceListChanged,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification() {
+        let storm = Storm {
+            rate: 100.0,
+            kinds: vec![StormKind::Progress, StormKind::ListChanged],
+        };
+        assert_eq!(storm.interval(), Duration::from_millis(10));
+        assert!(matches!(
+            storm.notification(0),
+            ServerNotification::Progress { .. }
+        ));
+        assert!(matches!(
+            storm.notification(1),
+            ServerNotification::ToolListChanged
+        ));
+        assert!(matches!(
+            storm.notification(3),
+            ServerNotification::PromptListChanged
+        ));
+        assert_eq!(storm.describe(), "100/s of progress, list-changed");
+    }
+}
@@ -0,0 +1,238 @@
+//! TLS stream setup for `tls://` targets.
+
+use std::{
+    path::{Path, PathBuf},
+    result,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio_rustls::{
+    TlsConnector,
+    client::TlsStream,
+    rustls::{
+        self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+    },
+};
+
+use crate::{Error, Result, tunnel};
+
+/// Options controlling how TLS connections are established and verified.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsOptions {
+    /// PEM bundle of CA certificates to trust instead of the built-in roots.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    /// Skip server certificate verification entirely. Unset means verify.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+    /// Server name to send via SNI and verify against, instead of the target host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    /// PEM certificate chain presented to servers that require client authentication.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key matching the client certificate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    /// Fills unset options from `stored`, keeping values that were given explicitly.
+    pub fn merged_with(self, stored: &Self) -> Self {
+        Self {
+            ca_bundle: self.ca_bundle.or_else(|| stored.ca_bundle.clone()),
+            insecure: self.insecure.or(stored.insecure),
+            server_name: self.server_name.or_else(|| stored.server_name.clone()),
+            client_cert: self.client_cert.or_else(|| stored.client_cert.clone()),
+            client_key: self.client_key.or_else(|| stored.client_key.clone()),
+        }
+    }
+
+    /// Returns true if a client certificate has been configured.
+    pub fn has_client_auth(&self) -> bool {
+        self.client_cert.is_some() || self.client_key.is_some()
+    }
+}
+
+/// Performs a TLS handshake with `host` over an established TCP connection.
+pub async fn connect(
+    stream: tunnel::Stream,
+    host: &str,
+    options: &TlsOptions,
+) -> Result<TlsStream<tunnel::Stream>> {
+    let connector = TlsConnector::from(Arc::new(client_config(options)?));
+    let name = options.server_name.as_deref().unwrap_or(host);
+    let server_name = ServerName::try_from(name.to_string())
+        .map_err(|e| Error::Format(format!("Invalid TLS server name '{name}': {e}")))?;
+
+    connector
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| Error::Other(format!("TLS handshake with {host} failed: {e}")))
+}
+
+/// Builds the rustls client configuration for the given options.
+fn client_config(options: &TlsOptions) -> Result<ClientConfig> {
+    let builder = if options.insecure == Some(true) {
+        ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification))
+    } else {
+        ClientConfig::builder().with_root_certificates(root_store(options)?)
+    };
+
+    match (&options.client_cert, &options.client_key) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(|e| Error::Config(format!("Invalid client certificate: {e}"))),
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err(Error::Format(
+            "--client-cert and --client-key must be given together".to_string(),
+        )),
+    }
+}
+
+/// Builds the set of trusted roots, either from a CA bundle or the built-in roots.
+fn root_store(options: &TlsOptions) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    match &options.ca_bundle {
+        Some(path) => {
+            for cert in load_certs(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| Error::Config(format!("Invalid CA certificate: {e}")))?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    Ok(roots)
+}
+
+/// Loads all certificates from a PEM file.
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let read_err = |e| {
+        Error::Config(format!(
+            "Failed to read certificates from {}: {e}",
+            path.display()
+        ))
+    };
+    let certs = CertificateDer::pem_file_iter(path)
+        .map_err(read_err)?
+        .collect::<result::Result<Vec<_>, _>>()
+        .map_err(read_err)?;
+    if certs.is_empty() {
+        return Err(Error::Config(format!(
+            "No certificates found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+/// Loads the first private key from a PEM file.
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path).map_err(|e| {
+        Error::Config(format!(
+            "Failed to read private key from {}: {e}",
+            path.display()
+        ))
+    })
+}
+
+/// Certificate verifier that accepts any server certificate, used for `--insecure`.
+#[derive(Debug)]
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> result::Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> result::Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_with_prefers_explicit_values() {
+        let stored = TlsOptions {
+            ca_bundle: Some(PathBuf::from("stored-ca.pem")),
+            insecure: Some(true),
+            server_name: Some("stored.example.com".to_string()),
+            client_cert: Some(PathBuf::from("stored-cert.pem")),
+            client_key: Some(PathBuf::from("stored-key.pem")),
+        };
+        let explicit = TlsOptions {
+            client_cert: Some(PathBuf::from("cert.pem")),
+            client_key: Some(PathBuf::from("key.pem")),
+            ..Default::default()
+        };
+
+        let merged = explicit.merged_with(&stored);
+        assert_eq!(merged.ca_bundle, Some(PathBuf::from("stored-ca.pem")));
+        assert_eq!(merged.server_name.as_deref(), Some("stored.example.com"));
+        assert_eq!(merged.client_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(merged.client_key, Some(PathBuf::from("key.pem")));
+        assert_eq!(merged.insecure, Some(true));
+
+        // An explicit setting overrides the stored one, either way
+        let verify = TlsOptions {
+            insecure: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(verify.merged_with(&stored).insecure, Some(false));
+    }
+
+    #[test]
+    fn test_client_cert_requires_key() {
+        let options = TlsOptions {
+            client_cert: Some(PathBuf::from("cert.pem")),
+            ..Default::default()
+        };
+        assert!(matches!(client_config(&options), Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,237 @@
+//! A timed breakdown of connecting to a server, for diagnosing slow startup.
+//!
+//! The handshake is split into phases: establishing the transport, the
+//! initialize round trip, parsing the capabilities the server declared, and
+//! the first list call. The frames exchanged are kept with the times they
+//! passed through, which splits transport from initialize. HTTP targets
+//! aren't carried on a stream mcptool can see, so for them the two are
+//! timed together.
+
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use crate::{
+    Result, client,
+    ctx::Ctx,
+    frametrace::{Direction, Frame, ProtocolTrace},
+    mcp::{self, Listing},
+    output::{Output, table},
+    stats::millis,
+    target::Target,
+    utils::TimedFuture,
+};
+
+/// A timed phase of the handshake.
+#[derive(Debug, Clone)]
+struct Phase {
+    /// What the phase is, such as "initialize".
+    name: &'static str,
+    /// How long it took, or `None` if it couldn't be timed.
+    duration: Option<Duration>,
+    /// What happened in it.
+    detail: String,
+}
+
+/// When the last initialize request was sent and its response arrived,
+/// among the frames kept while connecting. Retried connections send more
+/// than one, and only the last succeeded.
+fn initialize_times(frames: &[Frame]) -> Option<(Instant, Instant)> {
+    let request = frames.iter().rev().find(|frame| {
+        frame.direction == Direction::Sent
+            && frame.message.get("method").and_then(Value::as_str) == Some("initialize")
+    })?;
+    let id = request.message.get("id")?;
+    let response = frames.iter().find(|frame| {
+        frame.direction == Direction::Received
+            && frame.at >= request.at
+            && frame.message.get("method").is_none()
+            && frame.message.get("id") == Some(id)
+    })?;
+    Some((request.at, response.at))
+}
+
+/// Whether a target's frames pass through a stream mcptool can trace.
+pub(crate) fn traceable(target: &Target) -> bool {
+    matches!(
+        target.unnamed(),
+        Target::Tcp { .. } | Target::Tls { .. } | Target::Stdio { .. } | Target::Ssh { .. }
+    )
+}
+
+/// The capabilities declared in an initialize result, by name.
+fn declared_capabilities(init: &Value) -> Vec<String> {
+    init.get("capabilities")
+        .and_then(Value::as_object)
+        .map(|capabilities| {
+            capabilities
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Connects to a target, timing each phase of the handshake, and prints the
+/// breakdown.
+pub async fn trace_command(ctx: &Ctx, target: Target) -> Result<()> {
+    let trace = traceable(&target).then(|| ProtocolTrace::keeping(ctx.trace_protocol.clone()));
+    let ctx = &ctx.clone().with_trace_protocol(trace.clone());
+
+    let mut phases = Vec::new();
+    let start = Instant::now();
+    let (mut client, init) = client::get_client(ctx, &target).await?;
+    let connected = Instant::now();
+    let server = format!(
+        "{} {}, protocol version {}",
+        init.server_info.name, init.server_info.version, init.protocol_version
+    );
+    match trace
+        .as_ref()
+        .and_then(|trace| initialize_times(&trace.frames()))
+    {
+        Some((sent, received)) => {
+            phases.push(Phase {
+                name: "transport connect",
+                duration: Some(sent.duration_since(start)),
+                detail: "Until the initialize request was sent".to_string(),
+            });
+            phases.push(Phase {
+                name: "initialize",
+                duration: Some(received.duration_since(sent)),
+                detail: server,
+            });
+            phases.push(Phase {
+                name: "initialized",
+                duration: Some(connected.duration_since(received)),
+                detail: "Until the client was ready".to_string(),
+            });
+        }
+        None => phases.push(Phase {
+            name: "connect and initialize",
+            duration: Some(connected.duration_since(start)),
+            detail: format!("{server}; the transport can't be timed on its own"),
+        }),
+    }
+
+    let parsing = Instant::now();
+    let capabilities = declared_capabilities(&serde_json::to_value(&init)?);
+    let parse = parsing.elapsed();
+    phases.push(Phase {
+        name: "capability parsing",
+        duration: Some(parse),
+        detail: if capabilities.is_empty() {
+            "No capabilities declared".to_string()
+        } else {
+            format!("Declared {}", capabilities.join(", "))
+        },
+    });
+
+    // List whatever the server declares, preferring tools
+    let listing = Listing::ALL
+        .into_iter()
+        .find(|listing| capabilities.iter().any(|c| c == listing.capability()))
+        .unwrap_or(Listing::Tools);
+    let (page, list) = mcp::list_page(&mut client, ctx, listing, None)
+        .measured()
+        .await;
+    phases.push(Phase {
+        name: "first list call",
+        duration: page.is_ok().then_some(list),
+        detail: match &page {
+            Ok(page) => format!(
+                "{}: {} item(s) on the first page",
+                listing.method(),
+                page.get(listing.field())
+                    .and_then(Value::as_array)
+                    .map_or(0, Vec::len)
+            ),
+            Err(e) => format!(
+                "{} failed after {:.1}ms: {e}",
+                listing.method(),
+                millis(list)
+            ),
+        },
+    });
+
+    report(&ctx.output, &target, &phases, start.elapsed())
+}
+
+/// Prints the phases as a table, with the total.
+fn report(output: &Output, target: &Target, phases: &[Phase], total: Duration) -> Result<()> {
+    if output.structured() {
+        let phases: Vec<Value> = phases
+            .iter()
+            .map(|phase| {
+                json!({
+                    "phase": phase.name,
+                    "ms": phase.duration.map(millis),
+                    "detail": phase.detail,
+                })
+            })
+            .collect();
+        output.json_value(&json!({
+            "target": target.to_string(),
+            "phases": phases,
+            "totalMs": millis(total),
+        }))?;
+        return Ok(());
+    }
+    output.h1(format!("Handshake with {target}"))?;
+    let header: Vec<String> = ["Phase", "Time", "Detail"].map(str::to_string).to_vec();
+    let rows: Vec<Vec<String>> = phases
+        .iter()
+        .map(|phase| {
+            vec![
+                phase.name.to_string(),
+                phase
+                    .duration
+                    .map_or_else(|| "-".to_string(), |d| format!("{:.1}ms", millis(d))),
+                phase.detail.clone(),
+            ]
+        })
+        .collect();
+    for line in table::render(&header, &rows, output.width()) {
+        output.text(line)?;
+    }
+    output.text(format!("Total: {:.1}ms", millis(total)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialize_times() {
+        let start = Instant::now();
+        let frame = |direction, offset, message| Frame {
+            direction,
+            at: start + Duration::from_millis(offset),
+            message,
+        };
+        let initialize = |id| json!({ "jsonrpc": "2.0", "id": id, "method": "initialize" });
+        let response = |id| json!({ "jsonrpc": "2.0", "id": id, "result": {} });
+        let frames = vec![
+            // A failed attempt, then a retry that succeeded
+            frame(Direction::Sent, 1, initialize(0)),
+            frame(Direction::Sent, 10, initialize(0)),
+            frame(
+                Direction::Received,
+                12,
+                json!({ "method": "notifications/message" }),
+            ),
+            frame(Direction::Received, 15, response(0)),
+        ];
+        let (sent, received) = initialize_times(&frames).unwrap();
+        assert_eq!(sent.duration_since(start), Duration::from_millis(10));
+        assert_eq!(received.duration_since(sent), Duration::from_millis(5));
+
+        assert!(initialize_times(&frames[..3]).is_none());
+        assert_eq!(
+            declared_capabilities(&json!({ "capabilities": { "tools": {}, "logging": {} } })),
+            ["logging", "tools"]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Outbound TCP connections through SOCKS5 and HTTP CONNECT proxies.
+
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpStream, lookup_host},
+};
+use tokio_socks::{IntoTargetAddr, tcp::Socks5Stream};
+use url::Url;
+
+use crate::{Error, Result};
+
+/// Environment variables consulted for a proxy, in order of preference.
+const PROXY_VARS: [&str; 4] = ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+/// Environment variables listing hosts that are reached without a proxy.
+const NO_PROXY_VARS: [&str; 2] = ["NO_PROXY", "no_proxy"];
+
+/// A connection opened by [`connect`]. Anything a proxy sent after its
+/// CONNECT response is held in the buffer and read before the rest of the
+/// stream.
+pub type Stream = BufReader<TcpStream>;
+
+/// Returns the proxy configured in the environment for connections to
+/// `host`, if any. Loopback hosts and hosts matched by `NO_PROXY` are
+/// connected to directly.
+pub fn proxy_from_env(host: &str) -> Option<String> {
+    let no_proxy = NO_PROXY_VARS
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    if is_loopback(host) || bypasses_proxy(&no_proxy, host) {
+        return None;
+    }
+    PROXY_VARS
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Whether a host is `localhost` or a loopback address.
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|addr| addr.is_loopback())
+}
+
+/// Whether a `NO_PROXY` list matches a host. Entries are separated by commas
+/// and match the host itself or any of its subdomains, with a leading `.` or
+/// `*.` ignored. A lone `*` matches every host.
+fn bypasses_proxy(no_proxy: &str, host: &str) -> bool {
+    let host = host.trim_matches(['[', ']']).to_ascii_lowercase();
+    no_proxy
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            let entry = entry
+                .trim_start_matches("*.")
+                .trim_start_matches('.')
+                .to_ascii_lowercase();
+            host == entry
+                || host
+                    .strip_suffix(&entry)
+                    .is_some_and(|rest| rest.ends_with('.'))
+        })
+}
+
+/// Opens a TCP connection to `host:port`, tunnelling through `proxy` if given.
+///
+/// Supported proxy URLs are `socks5://`, `socks5h://` and `http://`, with
+/// optional `user:password@` credentials. As with curl, `socks5://` resolves
+/// the host locally and gives the proxy its address, while `socks5h://`
+/// leaves resolving it to the proxy.
+pub async fn connect(host: &str, port: u16, proxy: Option<&str>) -> Result<Stream> {
+    let Some(proxy) = proxy else {
+        return Ok(BufReader::new(TcpStream::connect((host, port)).await?));
+    };
+
+    let url = Url::parse(proxy)
+        .map_err(|e| Error::Format(format!("Invalid proxy URL '{proxy}': {e}")))?;
+    let proxy_host = url
+        .host_str()
+        .ok_or_else(|| Error::Format(format!("Proxy URL has no host: {proxy}")))?;
+    let proxy_port = url.port_or_known_default().unwrap_or(1080);
+    let credentials =
+        (!url.username().is_empty()).then(|| (url.username(), url.password().unwrap_or_default()));
+
+    match url.scheme() {
+        "socks5" | "socks5h" => {
+            let target = if url.scheme() == "socks5" {
+                resolve(host, port).await?.into_target_addr()
+            } else {
+                (host, port).into_target_addr()
+            }
+            .map_err(|e| Error::Format(format!("Invalid address {host}:{port}: {e}")))?;
+            let proxy_addr = (proxy_host, proxy_port);
+            let stream = match credentials {
+                Some((user, password)) => {
+                    Socks5Stream::connect_with_password(proxy_addr, target, user, password).await
+                }
+                None => Socks5Stream::connect(proxy_addr, target).await,
+            }
+            .map_err(|e| Error::Other(format!("SOCKS5 proxy {proxy_host} failed: {e}")))?;
+            Ok(BufReader::new(stream.into_inner()))
+        }
+        "http" => {
+            let stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+            http_connect(stream, host, port, credentials).await
+        }
+        scheme => Err(Error::Format(format!(
+            "Unsupported proxy scheme '{scheme}'. Use socks5://, socks5h:// or http://"
+        ))),
+    }
+}
+
+/// Resolves a host to the first of its addresses.
+async fn resolve(host: &str, port: u16) -> Result<SocketAddr> {
+    lookup_host((host, port))
+        .await
+        .map_err(|e| Error::Other(format!("Failed to resolve {host}: {e}")))?
+        .next()
+        .ok_or_else(|| Error::Other(format!("No addresses found for {host}")))
+}
+
+/// Asks an HTTP proxy to open a tunnel to `host:port` with a CONNECT request.
+/// The reader used for the response is returned, so bytes read past its end
+/// aren't lost.
+async fn http_connect(
+    mut stream: TcpStream,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<Stream> {
+    let authority = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some((user, password)) = credentials {
+        let token = STANDARD.encode(format!("{user}:{password}"));
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).await?;
+    let status = parse_status(&status_line)?;
+    if status != 200 {
+        return Err(Error::Other(format!(
+            "Proxy refused CONNECT to {authority}: {}",
+            status_line.trim()
+        )));
+    }
+
+    // Skip the remaining response headers
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+    }
+    Ok(reader)
+}
+
+/// Extracts the status code from an HTTP status line.
+fn parse_status(line: &str) -> Result<u16> {
+    line.split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| Error::Other(format!("Invalid proxy response: {}", line.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::AsyncReadExt, net::TcpListener};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(
+            parse_status("HTTP/1.1 200 Connection established\r\n").unwrap(),
+            200
+        );
+        assert_eq!(
+            parse_status("HTTP/1.0 407 Proxy Authentication Required").unwrap(),
+            407
+        );
+        assert!(parse_status("garbage").is_err());
+        assert!(parse_status("").is_err());
+    }
+
+    #[test]
+    fn test_is_loopback() {
+        assert!(is_loopback("localhost"));
+        assert!(is_loopback("127.0.0.1"));
+        assert!(is_loopback("::1"));
+        assert!(is_loopback("[::1]"));
+        assert!(!is_loopback("10.0.0.1"));
+        assert!(!is_loopback("example.com"));
+    }
+
+    #[test]
+    fn test_bypasses_proxy() {
+        let no_proxy = "internal.acme.ai, .corp.example,*.lan,10.0.0.5";
+        assert!(bypasses_proxy(no_proxy, "internal.acme.ai"));
+        assert!(bypasses_proxy(no_proxy, "mcp.internal.acme.ai"));
+        assert!(bypasses_proxy(no_proxy, "corp.example"));
+        assert!(bypasses_proxy(no_proxy, "a.corp.example"));
+        assert!(bypasses_proxy(no_proxy, "printer.LAN"));
+        assert!(bypasses_proxy(no_proxy, "10.0.0.5"));
+        assert!(!bypasses_proxy(no_proxy, "acme.ai"));
+        assert!(!bypasses_proxy(no_proxy, "notinternal.acme.ai"));
+        assert!(bypasses_proxy("*", "anything.example"));
+        assert!(!bypasses_proxy("", "anything.example"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_keeps_early_bytes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let proxy = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            _ = socket.read(&mut request).await.unwrap();
+            // The server's first bytes arrive in the same packet as the response
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nhello")
+                .await
+                .unwrap();
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = http_connect(stream, "example.com", 80, None).await.unwrap();
+        let mut greeting = String::new();
+        stream.read_to_string(&mut greeting).await.unwrap();
+        assert_eq!(greeting, "hello");
+        proxy.await.unwrap();
+    }
+
+    /// Runs a SOCKS5 proxy that refuses the one CONNECT request it takes,
+    /// and returns the type of address the request named: 1 or 4 for an IP
+    /// address, 3 for a domain name.
+    async fn socks_address_type(scheme: &str) -> u8 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("{scheme}://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut greeting = [0; 2];
+            socket.read_exact(&mut greeting).await.unwrap();
+            let mut methods = vec![0; usize::from(greeting[1])];
+            socket.read_exact(&mut methods).await.unwrap();
+            socket.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0; 4];
+            socket.read_exact(&mut request).await.unwrap();
+            socket
+                .write_all(&[5, 1, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            request[3]
+        });
+        assert!(connect("localhost", 9, Some(&proxy)).await.is_err());
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_socks5_resolution() {
+        assert!([1, 4].contains(&socks_address_type("socks5").await));
+        assert_eq!(socks_address_type("socks5h").await, 3);
+    }
+}
@@ -1,4 +1,8 @@
-use std::{fmt::Display, future::Future, time::Instant};
+use std::{
+    fmt::Display,
+    future::Future,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 
@@ -11,17 +15,23 @@ pub trait TimedFuture: Future + Sized {
         title: S,
         output: &Output,
     ) -> Self::Output {
-        let start = Instant::now();
-        let result = self.await;
+        let (result, elapsed) = self.measured().await;
 
-        drop(output.text(format!(
+        drop(output.status(format!(
             "{} in {:.2}ms",
             title,
-            start.elapsed().as_secs_f64() * 1000.0,
+            elapsed.as_secs_f64() * 1000.0,
         )));
 
         result
     }
+
+    /// Awaits the future, returning its output along with how long it took.
+    async fn measured(self) -> (Self::Output, Duration) {
+        let start = Instant::now();
+        let result = self.await;
+        (result, start.elapsed())
+    }
 }
 
 impl<F: Future> TimedFuture for F {}
@@ -0,0 +1,310 @@
+//! Watching a server for changes to what it offers.
+//!
+//! The watcher keeps one connection open and re-fetches a listing when the
+//! server says it changed, and a resource's contents when the server says
+//! it was updated. Servers that don't send these notifications are caught
+//! by polling every listing on an interval.
+
+use std::{collections::BTreeSet, process::Stdio, time::Duration};
+
+use chrono::Local;
+use serde_json::json;
+use tmcp::{
+    Client, ClientCtx, ClientHandler,
+    schema::{InitializeResult, ServerNotification},
+};
+use tokio::{
+    process::Command,
+    signal,
+    sync::mpsc,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    Error, Result, client,
+    ctx::Ctx,
+    diff::{self, Change},
+    mcp::Listing,
+    snapshot::Snapshot,
+    target::Target,
+};
+
+/// How long to wait for further notifications before fetching, so that a
+/// burst of them is shown as one change.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// Options for watching a server.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// How often every listing is fetched, whether or not the server said
+    /// it changed.
+    pub interval: Duration,
+    /// A shell command run after each change.
+    pub exec: Option<String>,
+}
+
+/// Client connection that forwards server notifications to a channel.
+#[derive(Clone)]
+struct WatchConn {
+    /// Sender for server notifications.
+    sender: mpsc::UnboundedSender<ServerNotification>,
+}
+
+#[async_trait::async_trait]
+impl ClientHandler for WatchConn {
+    async fn notification(
+        &self,
+        _context: &ClientCtx,
+        notification: ServerNotification,
+    ) -> tmcp::Result<()> {
+        // The receiver is gone once the watcher stops
+        _ = self.sender.send(notification);
+        Ok(())
+    }
+}
+
+/// What needs fetching again.
+#[derive(Debug, Default)]
+struct Stale {
+    /// Listings to fetch.
+    listings: Vec<Listing>,
+    /// Resources to read.
+    uris: BTreeSet<String>,
+}
+
+impl Stale {
+    /// Marks what a notification says has changed.
+    fn note(&mut self, notification: &ServerNotification) {
+        match notification {
+            ServerNotification::ToolListChanged => self.listing(Listing::Tools),
+            ServerNotification::PromptListChanged => self.listing(Listing::Prompts),
+            ServerNotification::ResourceListChanged => {
+                self.listing(Listing::Resources);
+                self.listing(Listing::ResourceTemplates);
+            }
+            ServerNotification::ResourceUpdated { uri } => {
+                self.uris.insert(uri.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Marks a listing.
+    fn listing(&mut self, listing: Listing) {
+        if !self.listings.contains(&listing) {
+            self.listings.push(listing);
+        }
+    }
+
+    /// Whether nothing needs fetching.
+    fn is_empty(&self) -> bool {
+        self.listings.is_empty() && self.uris.is_empty()
+    }
+}
+
+/// The state of a watched server, and the resources it sends updates for.
+struct Watched {
+    /// What the server offered when last fetched.
+    state: Snapshot,
+    /// Whether the server supports resource subscriptions.
+    subscribe: bool,
+    /// The resources subscribed to.
+    subscribed: BTreeSet<String>,
+}
+
+/// Subscribes to every listed resource not yet in `subscribed`, reading its
+/// contents so later updates can be compared. Resources no longer listed are
+/// forgotten.
+async fn subscribe<C: ClientHandler + 'static>(
+    client: &mut Client<C>,
+    ctx: &Ctx,
+    subscribed: &mut BTreeSet<String>,
+    state: &mut Snapshot,
+) -> Result<()> {
+    let uris = state.resource_uris();
+    subscribed.retain(|uri| uris.contains(uri));
+    for uri in uris {
+        if subscribed.contains(&uri) {
+            continue;
+        }
+        let read = match ctx.request(client.resources_subscribe(&uri)).await {
+            Ok(_) => state.read(client, ctx, &uri).await,
+            Err(e) => Err(e),
+        };
+        match read {
+            Ok(()) => {
+                subscribed.insert(uri);
+            }
+            Err(e) => ctx
+                .output
+                .trace_warn(format!("Failed to subscribe to {uri}: {e}"))?,
+        }
+    }
+    Ok(())
+}
+
+impl Watched {
+    /// Fetches what a newly connected server offers, subscribing to its
+    /// resources if it supports that.
+    async fn start<C: ClientHandler + 'static>(
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        target: &Target,
+        init: &InitializeResult,
+    ) -> Result<Self> {
+        let mut watched = Self {
+            state: Snapshot::fetch(client, ctx, target, init, false).await?,
+            subscribe: init
+                .capabilities
+                .resources
+                .as_ref()
+                .and_then(|resources| resources.subscribe)
+                .unwrap_or(false),
+            subscribed: BTreeSet::new(),
+        };
+        if watched.subscribe {
+            subscribe(client, ctx, &mut watched.subscribed, &mut watched.state).await?;
+        }
+        Ok(watched)
+    }
+
+    /// Fetches what's stale and returns how the server changed.
+    async fn update<C: ClientHandler + 'static>(
+        &mut self,
+        client: &mut Client<C>,
+        ctx: &Ctx,
+        stale: &Stale,
+    ) -> Result<Vec<Change>> {
+        let mut next = self.state.clone();
+        for &listing in &stale.listings {
+            if next.declares(listing.capability()) {
+                next.refresh(client, ctx, listing).await?;
+            }
+        }
+        if self.subscribe && stale.listings.contains(&Listing::Resources) {
+            subscribe(client, ctx, &mut self.subscribed, &mut next).await?;
+        }
+        for uri in &stale.uris {
+            if let Err(e) = next.read(client, ctx, uri).await {
+                ctx.output
+                    .trace_warn(format!("Failed to read {uri}: {e}"))?;
+            }
+        }
+        let changes = diff::compare(&self.state, &next);
+        self.state = next;
+        Ok(changes)
+    }
+}
+
+/// Prints a change to the server, stamped with the time it was seen.
+fn report(ctx: &Ctx, changes: &[Change]) -> Result<()> {
+    let now = Local::now();
+    let output = &ctx.output;
+    if output.structured() {
+        output.json_line(&json!({
+            "time": now.to_rfc3339(),
+            "changes": changes,
+        }))?;
+    } else {
+        output.h2(now.format("%H:%M:%S").to_string())?;
+        diff::print_changes(output, changes)?;
+    }
+    Ok(())
+}
+
+/// Runs the `--exec` hook through the shell, with the target and the
+/// changes as JSON in its environment. A failing hook is warned about, and
+/// watching carries on.
+async fn run_hook(ctx: &Ctx, command: &str, target: &str, changes: &[Change]) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("MCPTOOL_TARGET", target)
+        .env("MCPTOOL_CHANGES", serde_json::to_string(changes)?)
+        .stdin(Stdio::null())
+        .status()
+        .await;
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => ctx
+            .output
+            .trace_warn(format!("Hook '{command}' failed: {status}"))?,
+        Err(e) => ctx
+            .output
+            .trace_warn(format!("Failed to run hook '{command}': {e}"))?,
+    }
+    Ok(())
+}
+
+/// Watches a server until interrupted, printing a timestamped diff each
+/// time what it offers changes.
+pub async fn watch_command(ctx: &Ctx, target: Target, options: WatchOptions) -> Result<()> {
+    if options.interval.is_zero() {
+        return Err(Error::Other(
+            "The polling interval must be more than zero".to_string(),
+        ));
+    }
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let (mut client, init) =
+        client::get_client_with_connection(ctx, &target, WatchConn { sender }).await?;
+    let mut watched = Watched::start(&mut client, ctx, &target, &init).await?;
+
+    ctx.output.trace_info(format!(
+        "Watching {target}, polling every {}s. Press Ctrl-C to stop",
+        options.interval.as_secs_f64()
+    ))?;
+    let mut poll = time::interval(options.interval);
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick is immediate, and the state was just fetched
+    poll.tick().await;
+    loop {
+        let mut stale = Stale::default();
+        tokio::select! {
+            notification = receiver.recv() => {
+                let Some(notification) = notification else {
+                    return Err(Error::Other("The connection closed".to_string()));
+                };
+                stale.note(&notification);
+                while let Ok(Some(notification)) = time::timeout(SETTLE, receiver.recv()).await {
+                    stale.note(&notification);
+                }
+            }
+            _ = poll.tick() => {
+                stale.listings = Listing::ALL.to_vec();
+            }
+            _ = signal::ctrl_c() => return Ok(()),
+        }
+        if stale.is_empty() {
+            continue;
+        }
+        let changes = watched.update(&mut client, ctx, &stale).await?;
+        if changes.is_empty() {
+            continue;
+        }
+        report(ctx, &changes)?;
+        if let Some(command) = &options.exec {
+            run_hook(ctx, command, &watched.state.target, &changes).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale() {
+        let mut stale = Stale::default();
+        assert!(stale.is_empty());
+        stale.note(&ServerNotification::ResourceListChanged);
+        stale.note(&ServerNotification::ResourceListChanged);
+        stale.note(&ServerNotification::ResourceUpdated {
+            uri: "file:///a".to_string(),
+        });
+        assert_eq!(
+            stale.listings,
+            [Listing::Resources, Listing::ResourceTemplates]
+        );
+        assert_eq!(stale.uris.len(), 1);
+    }
+}
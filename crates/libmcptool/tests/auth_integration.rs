@@ -6,7 +6,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use libmcptool::storage::{StorageError, StoredAuth, TokenStorage};
+use libmcptool::storage::{SecretStore, StorageError, StoredAuth, TokenStorage};
 
 fn create_test_storage() -> TokenStorage {
     let test_dir = env::temp_dir().join("mcptool_test").join(format!(
@@ -18,7 +18,9 @@ fn create_test_storage() -> TokenStorage {
             .as_nanos()
     ));
 
-    TokenStorage::new(test_dir).expect("Failed to create test storage")
+    TokenStorage::new(test_dir.clone())
+        .expect("Failed to create test storage")
+        .with_secrets(SecretStore::encrypted_file(test_dir))
 }
 
 #[test]
@@ -44,6 +46,7 @@ fn test_auth_storage_lifecycle() {
         token_url: "https://test.example.com/token".to_string(),
         redirect_url: Some("http://localhost:8080".to_string()),
         scopes: vec!["read".to_string(), "write".to_string()],
+        resource: None,
     };
 
     // Test store
@@ -3,7 +3,7 @@
 
 use std::collections::HashMap;
 
-use libmcptool::output::{Output, listtools};
+use libmcptool::output::{DataFormat, Output, OutputMode, listtools};
 use serde_json::json;
 use tmcp::schema::{ListToolsResult, Tool, ToolSchema};
 
@@ -33,12 +33,12 @@ fn test_list_tools_result_json_output() {
     };
 
     // Test JSON output
-    let json_output = Output::new(false, 80).with_json(true);
+    let json_output = Output::new(false, 80).with_mode(OutputMode::Data(DataFormat::Json));
     let result = listtools::list_tools_result(&json_output, &tools_result);
     assert!(result.is_ok());
 
     // Test text output
-    let text_output = Output::new(true, 80).with_json(false);
+    let text_output = Output::new(true, 80).with_mode(OutputMode::Text);
     let result = listtools::list_tools_result(&text_output, &tools_result);
     assert!(result.is_ok());
 }
@@ -52,12 +52,12 @@ fn test_list_tools_result_empty_tools() {
     };
 
     // Test JSON output with empty tools
-    let json_output = Output::new(false, 80).with_json(true);
+    let json_output = Output::new(false, 80).with_mode(OutputMode::Data(DataFormat::Json));
     let result = listtools::list_tools_result(&json_output, &tools_result);
     assert!(result.is_ok());
 
     // Test text output with empty tools
-    let text_output = Output::new(true, 80).with_json(false);
+    let text_output = Output::new(true, 80).with_mode(OutputMode::Text);
     let result = listtools::list_tools_result(&text_output, &tools_result);
     assert!(result.is_ok());
 }
@@ -65,12 +65,12 @@ fn test_list_tools_result_empty_tools() {
 #[test]
 fn test_ping_output() {
     // Test JSON output
-    let json_output = Output::new(false, 80).with_json(true);
+    let json_output = Output::new(false, 80).with_mode(OutputMode::Data(DataFormat::Json));
     let result = json_output.ping();
     assert!(result.is_ok());
 
     // Test text output
-    let text_output = Output::new(true, 80).with_json(false);
+    let text_output = Output::new(true, 80).with_mode(OutputMode::Text);
     let result = text_output.ping();
     assert!(result.is_ok());
 }
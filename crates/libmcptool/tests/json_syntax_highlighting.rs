@@ -1,7 +1,7 @@
 //! Integration tests for JSON syntax highlighting functionality.
 #![allow(clippy::tests_outside_test_module)]
 
-use libmcptool::output::Output;
+use libmcptool::output::{DataFormat, Output, OutputMode};
 use serde_json::json;
 
 #[test]
@@ -21,7 +21,7 @@ fn test_json_syntax_highlighting() {
     assert!(output.json_value(&test_data).is_ok());
 
     // Test with JSON mode (should always output plain JSON)
-    let json_output = Output::new(true, 80).with_json(true);
+    let json_output = Output::new(true, 80).with_mode(OutputMode::Data(DataFormat::Json));
     assert!(json_output.json_value(&test_data).is_ok());
 }
 
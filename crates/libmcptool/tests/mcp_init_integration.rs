@@ -3,7 +3,13 @@
 
 use std::{collections::HashMap, time::Duration};
 
-use libmcptool::{client, ctx::Ctx, mcp, output::Output, target::Target};
+use libmcptool::{
+    client,
+    ctx::Ctx,
+    mcp,
+    output::{DataFormat, Output, OutputMode, OutputOptions},
+    target::Target,
+};
 use tempfile::TempDir;
 use tmcp::{
     Result as McpResult, Server, ServerCtx, ServerHandler,
@@ -18,8 +24,8 @@ use tokio::{net::TcpListener, time::sleep};
 fn create_test_ctx() -> (Ctx, TempDir) {
     let temp_dir = TempDir::new().expect("Failed to create temp dir");
     let config_path = temp_dir.path().to_path_buf();
-    let ctx =
-        Ctx::new(config_path, None, false, false, false, 80).expect("Failed to create context");
+    let ctx = Ctx::new(config_path, None, OutputOptions::default(), false, false)
+        .expect("Failed to create context");
     (ctx, temp_dir)
 }
 
@@ -57,7 +63,7 @@ async fn test_mcp_init_with_test_server() {
         .port();
     drop(listener); // Release the port so test server can bind to it
 
-    let (_ctx, _temp_dir) = create_test_ctx();
+    let (ctx, _temp_dir) = create_test_ctx();
 
     // Create and start the server directly
     let server = Server::default()
@@ -77,11 +83,11 @@ async fn test_mcp_init_with_test_server() {
 
     // Test with JSON output
     {
-        let output = Output::new(false, 80).with_json(true);
+        let output = Output::new(false, 80).with_mode(OutputMode::Data(DataFormat::Json));
         let target =
             Target::parse(&format!("tcp://127.0.0.1:{port}")).expect("Failed to parse target");
 
-        let (_client, init_result) = client::connect_to_server(&target, ())
+        let (_client, init_result) = client::connect_to_server(&ctx, &target, ())
             .await
             .expect("Failed to connect to server");
 
@@ -96,11 +102,11 @@ async fn test_mcp_init_with_test_server() {
 
     // Test with text output
     {
-        let output = Output::new(false, 80).with_json(false);
+        let output = Output::new(false, 80).with_mode(OutputMode::Text);
         let target =
             Target::parse(&format!("tcp://127.0.0.1:{port}")).expect("Failed to parse target");
 
-        let (_client, init_result) = client::connect_to_server(&target, ())
+        let (_client, init_result) = client::connect_to_server(&ctx, &target, ())
             .await
             .expect("Failed to connect to server");
 
@@ -155,14 +161,14 @@ async fn test_mcp_init_output_format() {
 
     // Test JSON output
     {
-        let output = Output::new(false, 80).with_json(true);
+        let output = Output::new(false, 80).with_mode(OutputMode::Data(DataFormat::Json));
         let result = mcp::init(&init_result, &output);
         assert!(result.is_ok(), "init should succeed with JSON output");
     }
 
     // Test text output
     {
-        let output = Output::new(false, 80).with_json(false);
+        let output = Output::new(false, 80).with_mode(OutputMode::Text);
         let result = mcp::init(&init_result, &output);
         assert!(result.is_ok(), "init should succeed with text output");
     }
@@ -181,7 +187,7 @@ async fn test_mcp_init_output_format() {
     };
 
     {
-        let output = Output::new(false, 80).with_json(false);
+        let output = Output::new(false, 80).with_mode(OutputMode::Text);
         let result = mcp::init(&minimal_init_result, &output);
         assert!(result.is_ok(), "init should succeed with minimal server");
     }
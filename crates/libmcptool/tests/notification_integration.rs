@@ -3,7 +3,7 @@
 
 use std::{error::Error, time::Duration};
 
-use libmcptool::{client, ctx::Ctx, target::Target};
+use libmcptool::{client, ctx::Ctx, output::OutputOptions, target::Target};
 use tmcp::{
     ClientCtx, ClientHandler, Result as McpResult, Server, ServerAPI, ServerCtx, ServerHandler,
     schema::{
@@ -80,7 +80,13 @@ async fn test_set_level_command_notifications_via_tcp() -> Result<(), Box<dyn Er
     let config_path = temp_dir.path().to_path_buf();
 
     // Create context
-    let ctx = Ctx::new(config_path.clone(), None, false, true, false, 80)?;
+    let ctx = Ctx::new(
+        config_path.clone(),
+        None,
+        OutputOptions::default(),
+        true,
+        false,
+    )?;
 
     // Create channels for capturing notifications
     let (client_notification_sender, mut client_notification_receiver) = mpsc::unbounded_channel();
@@ -1,32 +1,271 @@
 //! mcptool CLI application for interacting with MCP servers.
 #![allow(clippy::missing_docs_in_private_items)]
-use std::{error::Error, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{self, Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
 use clap::{Args, Parser, Subcommand};
 use libmcptool::{
-    LogLevel, auth,
-    command::{CliMcpCommand, execute_mcp_command},
-    connect, ctx, proxy,
+    LogLevel,
+    args::ArgumentParser,
+    auth, batch,
+    bench::{self, BenchMethod, BenchOptions, BenchRequest},
+    caps, check,
+    client::{self, ConnectOptions},
+    command::{CliMcpCommand, RetryArgs, execute_mcp_command},
+    connect, ctx, diff, doc,
+    export::{self, SchemaFormat},
+    filter::Filter,
+    frametrace::ProtocolTrace,
+    fuzz,
+    gateway::{self, GatewayOptions, Upstream},
+    generate::{self, Lang},
+    health::{self, HealthOptions},
+    lint::{self, Severity},
+    logs::{self, ServerLogLevel},
+    mock,
+    output::{
+        DataFormat, OutputMode, OutputOptions, StatusStream,
+        image::ImageProtocol,
+        template::OutputTemplate,
+        theme::{self, Theme},
+    },
+    proxy,
+    retry::RetryPolicy,
+    secret, serve, snapshot,
+    storage::TokenStorage,
     target::Target,
-    testserver,
+    targets::{self, ExportFormat, ImportSource},
+    templates,
+    testserver::{
+        self,
+        chaos::{Chaos, ChaosKind},
+        faults::Faults,
+        paging::Paging,
+        payload::{self, Payload},
+        storm::{Storm, StormKind},
+    },
+    tls::TlsOptions,
+    trace,
+    watch::{self, WatchOptions},
 };
-use terminal_size::{Width, terminal_size};
 use tmcp::schema::LATEST_PROTOCOL_VERSION;
 
 #[derive(Args)]
 struct TargetArgs {
-    /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "https://host:port", "cmd://./server")
-    target: String,
+    /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "tls://host:port", "http://host:port", "https://host:port", "cmd://./server", "ssh://user@host/server", "auth://name") or a saved target name
+    #[arg(required_unless_present = "command", conflicts_with = "command")]
+    target: Option<String>,
+
+    /// A local server command to spawn and speak MCP to over stdio, given after `--`
+    #[arg(last = true)]
+    command: Vec<String>,
+}
+
+impl TargetArgs {
+    /// Resolves either the target specification or the trailing command into a target.
+    fn into_target(self, ctx: &ctx::Ctx) -> libmcptool::Result<Target> {
+        match self.target {
+            Some(target) => Target::resolve(&target, &ctx.storage()?),
+            None => Target::from_command(self.command),
+        }
+    }
+}
+
+#[derive(Args)]
+struct ConnectArgs {
+    /// PEM bundle of CA certificates to trust for tls:// targets
+    #[arg(long, global = true)]
+    tls_ca: Option<PathBuf>,
+
+    /// Skip certificate verification for tls:// targets. `--insecure=false` turns
+    /// verification back on for a target saved as insecure
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        require_equals = true,
+        value_name = "BOOL"
+    )]
+    insecure: Option<bool>,
+
+    /// Server name to use for SNI and verification instead of the target host
+    #[arg(long, global = true)]
+    tls_server_name: Option<String>,
+
+    /// PEM client certificate for servers that require mutual TLS
+    #[arg(long, global = true, requires = "client_key")]
+    client_cert: Option<PathBuf>,
+
+    /// PEM private key for the client certificate
+    #[arg(long, global = true, requires = "client_cert")]
+    client_key: Option<PathBuf>,
+
+    /// Proxy for outgoing connections (socks5://, socks5h:// or http://); defaults to HTTPS_PROXY
+    #[arg(long, global = true)]
+    proxy: Option<String>,
+
+    /// Extra header to send to HTTP targets, as KEY=VALUE; values may reference {secret:NAME} (can be specified multiple times)
+    #[arg(long = "header", short = 'H', global = true, value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Environment variable for spawned stdio servers, as KEY=VALUE (can be specified multiple times)
+    #[arg(long = "env", global = true, value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
+
+    /// File of KEY=VALUE lines to add to the environment of spawned stdio servers (can be specified multiple times)
+    #[arg(long, global = true)]
+    env_file: Vec<PathBuf>,
+
+    /// Pass mcptool's own environment on to spawned stdio servers (the default)
+    #[arg(long, global = true, overrides_with = "no_inherit_env")]
+    inherit_env: bool,
+
+    /// Start spawned stdio servers with only the variables from --env and --env-file
+    #[arg(long, global = true, overrides_with = "inherit_env")]
+    no_inherit_env: bool,
+
+    /// Working directory for spawned stdio servers
+    #[arg(long, global = true)]
+    cwd: Option<PathBuf>,
+
+    /// Protocol version to ask for instead of the latest (e.g. "2024-11-05"), to test downgrade negotiation
+    #[arg(long, global = true, value_name = "VERSION")]
+    protocol_version: Option<String>,
+
+    /// Retry failed connection attempts up to N times, with exponential backoff
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retry: u32,
+
+    /// Delay before the first retry of a connection or tool call (e.g. "500ms", "2s"); doubles on each retry
+    #[arg(long, global = true, value_parser = humantime::parse_duration, default_value = "500ms")]
+    retry_delay: Duration,
+
+    /// Remember these connection settings for the target after connecting
+    #[arg(long, global = true)]
+    save_settings: bool,
+}
+
+fn parse_header(input: &str) -> Result<(String, String), String> {
+    client::parse_header(input).map_err(|e| e.to_string())
+}
+
+fn parse_env_var(input: &str) -> Result<(String, String), String> {
+    client::parse_env_var(input).map_err(|e| e.to_string())
+}
+
+fn parse_upstream(input: &str) -> Result<(String, String), String> {
+    gateway::parse_upstream(input).map_err(|e| e.to_string())
+}
+
+fn parse_size(input: &str) -> Result<usize, String> {
+    payload::parse_size(input).map_err(|e| e.to_string())
+}
+
+fn parse_storm_rate(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("'{input}' is not a positive number")),
+    }
+}
+
+fn parse_page_size(input: &str) -> Result<usize, String> {
+    match input.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("'{input}' is not a positive whole number")),
+    }
+}
+
+fn parse_error_rate(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("'{input}' is not a number between 0 and 1")),
+    }
+}
+
+impl ConnectArgs {
+    fn into_options(self) -> libmcptool::Result<ConnectOptions> {
+        // Files are applied in order, and --env values take precedence over all of them
+        let mut env = BTreeMap::new();
+        for path in &self.env_file {
+            let contents = fs::read_to_string(path).map_err(|e| {
+                libmcptool::Error::Other(format!("Failed to read {}: {e}", path.display()))
+            })?;
+            env.extend(client::parse_env_file(&contents)?);
+        }
+        env.extend(self.env);
+
+        let inherit_env = if self.no_inherit_env {
+            Some(false)
+        } else if self.inherit_env {
+            Some(true)
+        } else {
+            None
+        };
+
+        Ok(ConnectOptions {
+            tls: TlsOptions {
+                ca_bundle: self.tls_ca,
+                insecure: self.insecure,
+                server_name: self.tls_server_name,
+                client_cert: self.client_cert,
+                client_key: self.client_key,
+            },
+            proxy: self.proxy,
+            headers: self.headers.into_iter().collect(),
+            env,
+            inherit_env,
+            // Stored settings outlive the current directory, so keep the path absolute
+            cwd: self.cwd.map(path::absolute).transpose()?,
+            protocol_version: self.protocol_version,
+            retry: RetryPolicy {
+                retries: self.retry,
+                delay: self.retry_delay,
+            },
+            save: self.save_settings,
+        })
+    }
 }
 
 #[derive(Args)]
 struct ProxyArgs {
-    /// The MCP server target to proxy to (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "https://host:port", "cmd://./server")
-    target: String,
+    /// The MCP server target to proxy to (e.g., "localhost:3000", "tcp://host:port", "tls://host:port", "http://host:port", "https://host:port", "cmd://./server", "ssh://user@host/server") or a saved target name
+    #[arg(required_unless_present = "upstream", conflicts_with = "upstream")]
+    target: Option<String>,
+
+    /// The MCP server target to proxy to, as an alternative to the positional target
+    #[arg(long, value_name = "TARGET")]
+    upstream: Option<String>,
+
+    /// Where clients connect: "stdio", or "tcp://host:port" to accept any number of clients
+    #[arg(long, value_name = "ADDR", default_value = "stdio")]
+    listen: String,
 
     /// File path to log all proxy traffic
     #[arg(long)]
-    log_file: PathBuf,
+    log_file: Option<PathBuf>,
+
+    /// Record every frame, with timestamps, to FILE as JSON lines
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// A TOML policy file allowing or denying tools, prompts and resources
+    #[arg(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
+
+    /// A TOML file of rules that inject arguments, redact response fields and override tool descriptions
+    #[arg(long, value_name = "FILE")]
+    rewrites: Option<PathBuf>,
+
+    /// A TOML file of global and per-tool rate limits on requests
+    #[arg(long, value_name = "FILE")]
+    limits: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -91,6 +330,166 @@ enum AuthCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum TargetCommands {
+    /// Save a target under a short name
+    Add {
+        /// Name to save the target as
+        name: String,
+
+        #[command(flatten)]
+        target_args: TargetArgs,
+
+        /// Replace an existing target with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List saved targets
+    #[command(alias = "ls")]
+    List,
+
+    /// Remove a saved target
+    #[command(alias = "rm")]
+    Remove {
+        /// Name of the target to remove
+        name: String,
+    },
+
+    /// Show a saved target and its stored connection settings
+    Show {
+        /// Name of the target to show
+        name: String,
+    },
+
+    /// Import the MCP servers configured in another application as saved targets
+    Import {
+        /// Application to import from
+        #[arg(long, value_enum)]
+        from: ImportSource,
+
+        /// Configuration file to read instead of the application's default location
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Replace existing targets with the same names
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print a configuration fragment for saved targets, to paste into another application
+    Export {
+        /// Configuration format to produce
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Targets to export (defaults to all saved targets)
+        names: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// Save a tool call under a short name
+    Save {
+        /// Name to save the template as
+        name: String,
+
+        /// Name of the tool to call
+        #[arg(long)]
+        tool: String,
+
+        /// Arguments in key=value format, as taken by calltool (can be specified multiple times)
+        #[arg(long = "arg", short = 'a')]
+        args: Vec<String>,
+
+        /// Replace an existing template with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Call the tool of a saved template on a target
+    Run {
+        /// Name of the template to run
+        name: String,
+
+        /// Target to connect to
+        target: String,
+
+        /// Arguments in key=value format that replace the saved values for the same keys
+        overrides: Vec<String>,
+
+        /// Call tools marked destructive without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// List saved templates
+    #[command(alias = "ls")]
+    List,
+
+    /// Remove a saved template
+    #[command(alias = "rm")]
+    Remove {
+        /// Name of the template to remove
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretCommands {
+    /// Store a secret in the OS keyring (or encrypted file if no keyring is available)
+    Set {
+        /// Name of the secret
+        name: String,
+
+        /// Secret value (read from the terminal or stdin if omitted)
+        #[arg(long)]
+        value: Option<String>,
+    },
+
+    /// List stored secret names
+    #[command(alias = "ls")]
+    List,
+
+    /// Remove a stored secret
+    #[command(alias = "rm")]
+    Remove {
+        /// Name of the secret to remove
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Export a server's tool schemas as an OpenAPI document or a JSON Schema bundle
+    Schema {
+        /// Target to connect to
+        target: String,
+
+        /// Format of the exported document
+        #[arg(long, value_enum, default_value = "openapi")]
+        format: SchemaFormat,
+
+        /// Write the document to FILE instead of printing it
+        #[arg(short = 'o', long, value_name = "FILE")]
+        out_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ServeCommands {
+    /// Serve a directory as resources, with templates, subscriptions and roots
+    Fs {
+        /// The directory to serve
+        dir: PathBuf,
+
+        /// Where clients connect: "stdio", or "tcp://host:port" to accept any number of clients
+        #[arg(long, value_name = "ADDR", default_value = "stdio")]
+        listen: String,
+    },
+}
+
 #[derive(Parser)]
 #[command(
     name = "mcptool",
@@ -102,6 +501,55 @@ struct Cli {
     #[arg(long, global = true)]
     json: bool,
 
+    /// Output results in YAML format
+    #[arg(long, global = true, conflicts_with = "json")]
+    yaml: bool,
+
+    /// Output results as JSON Lines, one item per line
+    #[arg(long, global = true, conflicts_with_all = ["json", "yaml"])]
+    jsonl: bool,
+
+    /// Output tool and prompt listings and server info as Markdown documents
+    #[arg(long, global = true, conflicts_with_all = ["json", "yaml", "jsonl"])]
+    markdown: bool,
+
+    /// Apply a jq-style filter to JSON results before printing, e.g. '.tools[].name'
+    #[arg(long, global = true, value_name = "EXPR", conflicts_with_all = ["markdown", "table"])]
+    filter: Option<String>,
+
+    /// Render results with a minijinja template file instead of printing JSON
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        conflicts_with_all = ["yaml", "jsonl", "markdown", "table"]
+    )]
+    format_template: Option<PathBuf>,
+
+    /// Write results to FILE as JSON, or YAML or JSON Lines by extension, keeping status text on the terminal
+    #[arg(long, global = true, value_name = "FILE", conflicts_with_all = ["markdown", "table"])]
+    output: Option<PathBuf>,
+
+    /// Output JSON with object keys sorted and consistent formatting, so successive runs can be diffed
+    #[arg(long, global = true, conflicts_with_all = ["markdown", "table"])]
+    stable_json: bool,
+
+    /// Where status, progress and trace lines go, keeping stdout for results by default
+    #[arg(long, global = true, value_enum, default_value_t = StatusStream::Stderr)]
+    status_stream: StatusStream,
+
+    /// Print every JSON-RPC frame sent and received to stderr, or to FILE
+    #[arg(long, global = true, num_args = 0..=1, value_name = "FILE")]
+    trace_protocol: Option<Option<PathBuf>>,
+
+    /// Show tool, resource and prompt lists as aligned tables
+    #[arg(long, global = true, conflicts_with_all = ["json", "yaml", "jsonl", "markdown"])]
+    table: bool,
+
+    /// Table columns, as comma-separated field names or paths like annotations.title
+    #[arg(long, global = true, value_delimiter = ',', requires = "table")]
+    columns: Vec<String>,
+
     /// Enable logging with specified level
     #[arg(long, global = true, value_enum)]
     logs: Option<LogLevel>,
@@ -114,10 +562,37 @@ struct Cli {
     #[arg(long, global = true, conflicts_with = "color")]
     no_color: bool,
 
-    /// Suppress all output including JSON output
+    /// Show JSON, Markdown and code in tool results and resources without syntax highlighting
+    #[arg(long, global = true)]
+    no_highlight: bool,
+
+    /// Describe image content instead of drawing it inline in iTerm2, WezTerm, kitty or Ghostty
+    #[arg(long, global = true)]
+    no_images: bool,
+
+    /// Truncate text content in tool results and resources to N bytes in text mode
+    #[arg(long, global = true, value_name = "N", default_value_t = 65536)]
+    max_content_bytes: usize,
+
+    /// Show tool results and resources in full, however large
+    #[arg(long, global = true, conflicts_with = "max_content_bytes")]
+    full: bool,
+
+    /// Color theme, overriding the one in config.json
+    #[arg(long, global = true, value_enum)]
+    theme: Option<Theme>,
+
+    /// Print only results, without status lines, progress or timings; ping and subscribe print nothing
     #[arg(long, global = true)]
     quiet: bool,
 
+    /// Fail any request that takes longer than this (e.g. "500ms", "30s"); exits with code 124
+    #[arg(long, global = true, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    #[command(flatten)]
+    connect: ConnectArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -135,16 +610,258 @@ enum Commands {
 
     /// Connect to an MCP server and start an interactive REPL
     Connect {
-        /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "auth://name")
+        #[command(flatten)]
+        target_args: TargetArgs,
+
+        /// Record every command, result and notification to a JSONL file
+        #[arg(long, value_name = "FILE")]
+        transcript: Option<PathBuf>,
+
+        /// Don't reload tool, prompt and resource lists when the server says they changed
+        #[arg(long)]
+        no_refresh: bool,
+    },
+
+    /// Connect to an MCP server and run the REPL commands in a file
+    Script {
+        /// Target to connect to
+        target: String,
+
+        /// File of REPL commands, one per line
+        file: PathBuf,
+
+        /// Run the remaining commands after one fails
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Write each command's outcome to FILE, as JUnit XML if it ends in .xml or a JSON summary otherwise; can be repeated
+        #[arg(long, value_name = "FILE")]
+        report: Vec<PathBuf>,
+    },
+
+    /// Call the tools listed in a JSONL file, printing one result per line
+    Callbatch {
+        /// Target to connect to
+        target: String,
+
+        /// File of calls, one {"tool": ..., "arguments": ...} object per line
+        file: PathBuf,
+
+        /// Run up to N calls at once, each over its own connection; results keep the input order
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        concurrency: usize,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+    },
+
+    /// Call a tool with arguments generated from its input schema, and report failures
+    Fuzz {
+        /// Target to connect to
+        target: String,
+
+        /// Name of the tool to fuzz
+        tool: String,
+
+        /// Number of calls to make, cycling through valid, boundary and invalid arguments
+        #[arg(long, value_name = "N", default_value_t = 100)]
+        iterations: usize,
+
+        /// Seed for argument generation, to repeat an earlier run
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Send one request over and over from several connections, and report throughput and latency
+    Bench {
+        /// Target to connect to
+        target: String,
+
+        /// The request to send
+        #[arg(long, value_enum, default_value = "ping")]
+        method: BenchMethod,
+
+        /// Number of connections sending requests at once
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        concurrency: usize,
+
+        /// How long to send requests for (e.g. "30s")
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "10s")]
+        duration: Duration,
+
+        /// The tool to call, for --method tools/call
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Tool arguments in key=value format (can be specified multiple times), for --method tools/call
+        #[arg(long = "arg", short = 'a', requires = "tool")]
+        args: Vec<String>,
+
+        /// The resource to read, for --method resources/read
+        #[arg(long)]
+        uri: Option<String>,
+
+        /// Write an HDR-style latency histogram to FILE as JSON
+        #[arg(long, value_name = "FILE")]
+        histogram: Option<PathBuf>,
+    },
+
+    /// Save everything a server offers to a file, for diff and offline use
+    Snapshot {
+        /// Target to connect to
+        target: String,
+
+        /// The file to write the snapshot to
+        #[arg(short = 'o', long, value_name = "FILE")]
+        out_file: PathBuf,
+
+        /// Also read every resource and save its contents
+        #[arg(long)]
+        contents: bool,
+    },
+
+    /// Compare the tools, prompts, resources and capabilities of two servers or snapshots
+    Diff {
+        /// The first server: a target, or a snapshot file
+        first: String,
+
+        /// The second server: a target, or a snapshot file
+        second: String,
+
+        /// Exit with an error if the servers differ
+        #[arg(long)]
+        exit_code: bool,
+    },
+
+    /// Write Markdown documentation for a server's tools, prompts, resources and templates
+    Doc {
+        /// Target to connect to
+        target: String,
+
+        /// The directory to write the pages to
+        #[arg(short = 'o', long, value_name = "DIR", default_value = "docs")]
+        out_dir: PathBuf,
+    },
+
+    /// Generate typed client stubs for a server's tools
+    Generate {
+        /// Target to connect to
+        target: String,
+
+        /// Language to generate
+        #[arg(long, value_enum)]
+        lang: Lang,
+
+        /// The directory to write the stubs to
+        #[arg(short = 'o', long, value_name = "DIR", default_value = ".")]
+        out_dir: PathBuf,
+    },
+
+    /// Stay connected to a server and print what changes in its tools, prompts and resources
+    Watch {
+        /// Target to connect to
+        target: String,
+
+        /// How often to fetch every listing, for servers that don't announce changes
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+        interval: Duration,
+
+        /// Shell command to run after each change, with the changes as JSON in $MCPTOOL_CHANGES
+        #[arg(long, value_name = "CMD")]
+        exec: Option<String>,
+    },
+
+    /// Set a server's logging level and print the log messages it sends
+    Logs {
+        /// Target to connect to
+        target: String,
+
+        /// The lowest level to receive
+        #[arg(long, value_enum, default_value = "debug")]
+        level: ServerLogLevel,
+    },
+
+    /// Run protocol conformance checks against a server and report what passed and failed
+    Check {
+        /// Target to connect to
+        target: String,
+
+        /// Also write the results to FILE, as JUnit XML if it ends in .xml or a JSON summary otherwise; can be repeated
+        #[arg(long, value_name = "FILE")]
+        report: Vec<PathBuf>,
+    },
+
+    /// Probe each capability a server could have and show what's declared against what works
+    Caps {
+        /// Target to connect to
+        target: String,
+    },
+
+    /// Check that a server is up, with minimal output and an exit code for probes
+    Health {
+        /// Target to connect to
+        target: String,
+
+        /// Fail unless the server lists this tool; can be repeated
+        #[arg(long = "expect-tool", value_name = "NAME")]
+        expect_tools: Vec<String>,
+
+        /// Fail if a ping takes longer than this
+        #[arg(long, value_parser = humantime::parse_duration)]
+        max_latency: Option<Duration>,
+    },
+
+    /// Time each phase of connecting to a server, to diagnose slow startup
+    Trace {
+        /// Target to connect to
         target: String,
     },
 
+    /// Flag quality issues in a server's tools, prompts and resources
+    Lint {
+        /// Target to connect to
+        target: String,
+
+        /// Exit nonzero if anything is found at this severity or above
+        #[arg(long, value_enum, value_name = "SEVERITY", default_value = "error")]
+        fail_on: Severity,
+    },
+
     /// Transparently proxy and print traffic forwarded to the target
     Proxy {
         #[command(flatten)]
         proxy_args: ProxyArgs,
     },
 
+    /// Serve several servers as one, with their tools and prompts prefixed by upstream name
+    Gateway {
+        /// An upstream server as NAME=TARGET; can be repeated. Its tools and prompts are served as NAME__tool
+        #[arg(long = "upstream", value_name = "NAME=TARGET", value_parser = parse_upstream, required = true)]
+        upstreams: Vec<(String, String)>,
+
+        /// Where clients connect: "stdio", or "tcp://host:port" to accept any number of clients
+        #[arg(long, value_name = "ADDR", default_value = "stdio")]
+        listen: String,
+
+        /// A TOML policy file allowing or denying tools, prompts and resources, by their namespaced names
+        #[arg(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
+
+        /// A TOML file of global and per-tool rate limits on requests, by namespaced tool names
+        #[arg(long, value_name = "FILE")]
+        limits: Option<PathBuf>,
+    },
+
+    /// Serve canned responses from a recorded session or fixture file
+    Mock {
+        /// A recording made with "proxy --record", or a fixture in the same format
+        file: PathBuf,
+
+        /// Where clients connect: "stdio", or "tcp://host:port" to accept any number of clients
+        #[arg(long, value_name = "ADDR", default_value = "stdio")]
+        listen: String,
+    },
+
     /// Run a test MCP server with verbose logging
     Testserver {
         /// Use stdio transport instead of HTTP
@@ -162,6 +879,74 @@ enum Commands {
         /// Run in interactive mode with REPL for server management
         #[arg(long)]
         interactive: bool,
+
+        /// Serve the tools, prompts and resources defined in a TOML file instead of the built-in ones
+        #[arg(long, value_name = "FILE")]
+        spec: Option<PathBuf>,
+
+        /// Delay every response by this long (e.g. "500ms")
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+        latency: Duration,
+
+        /// Add up to this much random delay on top of --latency
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+        jitter: Duration,
+
+        /// Fraction of requests, from 0 to 1, that fail at random (initialize is spared)
+        #[arg(long, value_name = "RATE", value_parser = parse_error_rate, default_value_t = 0.0)]
+        error_rate: f64,
+
+        /// Always fail requests for this method (e.g. "tools/call"); can be repeated
+        #[arg(long, value_name = "METHOD")]
+        fail_method: Vec<String>,
+
+        /// Serve a generated payload of this size (e.g. "50MB") as a resource and a tool result
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        payload_size: Option<usize>,
+
+        /// The content type of the generated payload
+        #[arg(
+            long,
+            value_name = "MIME",
+            default_value = "text/plain",
+            requires = "payload_size"
+        )]
+        content_type: String,
+
+        /// Send this many notifications per second to every connected client
+        #[arg(long, value_name = "RATE", value_parser = parse_storm_rate)]
+        storm: Option<f64>,
+
+        /// The kinds of notification the storm sends, in turn; defaults to all
+        #[arg(long, value_enum, value_delimiter = ',', requires = "storm")]
+        storm_kinds: Vec<StormKind>,
+
+        /// Split tool, prompt, resource and template listings into pages of at most N items
+        #[arg(long, value_name = "N", value_parser = parse_page_size)]
+        page_size: Option<usize>,
+
+        /// Add N generated tools and N generated resources to the listings
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        bulk: usize,
+
+        /// Misbehave at the transport level: drop connections, delay initialize, send malformed
+        /// frames or reorder responses; defaults to all (TCP only)
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            num_args = 0..,
+            conflicts_with = "stdio"
+        )]
+        chaos: Option<Vec<ChaosKind>>,
+
+        /// Chance, from 0 to 1, of each kind of chaos striking at each frame the server sends
+        #[arg(long, value_name = "RATE", value_parser = parse_error_rate, default_value_t = 0.1, requires = "chaos")]
+        chaos_rate: f64,
+
+        /// How long a delayed initialize stalls for
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "5s", requires = "chaos")]
+        init_delay: Duration,
     },
 
     /// Manage OAuth authentication entries
@@ -169,12 +954,112 @@ enum Commands {
         #[command(subcommand)]
         command: AuthCommands,
     },
+
+    /// Manage saved targets
+    Target {
+        #[command(subcommand)]
+        command: TargetCommands,
+    },
+
+    /// Manage named secrets referenced from connection settings
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommands,
+    },
+
+    /// Save tool calls and run them later against any target
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+
+    /// Export what a server offers for use with other tools
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+
+    /// Run a ready-made MCP server
+    Serve {
+        #[command(subcommand)]
+        command: ServeCommands,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            let code = e
+                .downcast_ref::<libmcptool::Error>()
+                .map_or(1, libmcptool::Error::exit_code);
+            ExitCode::from(code)
+        }
+    }
+}
 
+/// How results are written, from the output flags. The flags that choose a
+/// mode are kept apart by clap.
+fn output_options(
+    cli: &Cli,
+    config_path: &Path,
+    color: bool,
+) -> Result<OutputOptions, Box<dyn Error>> {
+    let format = if cli.yaml {
+        DataFormat::Yaml
+    } else if cli.jsonl {
+        DataFormat::Jsonl
+    } else {
+        cli.output
+            .as_deref()
+            .map_or(DataFormat::Json, DataFormat::from_path)
+    };
+    // Filters, output files and stable JSON work on the structured form of
+    // results
+    let mode = if let Some(path) = &cli.format_template {
+        OutputMode::Template(OutputTemplate::load(path)?)
+    } else if cli.markdown {
+        OutputMode::Markdown
+    } else if cli.table {
+        OutputMode::Table {
+            columns: cli.columns.clone(),
+        }
+    } else if cli.json
+        || cli.yaml
+        || cli.jsonl
+        || cli.filter.is_some()
+        || cli.output.is_some()
+        || cli.stable_json
+    {
+        OutputMode::Data(format)
+    } else {
+        OutputMode::Text
+    };
+    let theme = match cli.theme {
+        Some(theme) => theme,
+        None => TokenStorage::new(config_path.to_path_buf())?
+            .get_config()?
+            .theme
+            .unwrap_or_default(),
+    };
+    Ok(OutputOptions {
+        mode,
+        filter: cli.filter.as_deref().map(Filter::parse).transpose()?,
+        stable: cli.stable_json,
+        highlight: !cli.no_highlight,
+        images: if color && !cli.no_images {
+            ImageProtocol::detect()
+        } else {
+            None
+        },
+        max_content_bytes: (!cli.full).then_some(cli.max_content_bytes),
+        theme,
+    })
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
     // Calculate the configuration directory
     let config_path = dirs::config_dir()
         .ok_or("Failed to get config directory")?
@@ -186,19 +1071,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
     } else if cli.color {
         true
     } else {
-        // Auto-detect based on TTY
-        atty::is(atty::Stream::Stdout)
-    };
-
-    // Detect terminal width, default to 80
-    let width = if let Some((Width(w), _)) = terminal_size() {
-        w as usize
-    } else {
-        80
+        // Auto-detect based on TTY, respecting NO_COLOR and CLICOLOR
+        theme::color_from_env(atty::is(atty::Stream::Stdout))
     };
 
     // Create the MCPTool instance
-    let ctx = ctx::Ctx::new(config_path, cli.logs, cli.json, cli.quiet, color, width)?;
+    let options = output_options(&cli, &config_path, color)?;
+    let mut ctx = ctx::Ctx::new(config_path, cli.logs, options, cli.quiet, color)?
+        .with_status_stream(cli.status_stream)
+        .with_connect_options(cli.connect.into_options()?)
+        .with_timeout(cli.timeout)
+        .with_trace_protocol(match cli.trace_protocol {
+            None => None,
+            Some(None) => Some(ProtocolTrace::stderr()),
+            Some(Some(path)) => Some(ProtocolTrace::file(&path)?),
+        });
+    if let Some(path) = &cli.output {
+        let output = ctx.output.with_results_file(path)?;
+        ctx = ctx.with_output(output);
+    }
 
     match cli.command {
         Commands::Version => {
@@ -212,13 +1103,189 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .map_err(|e| Box::new(e) as Box<dyn Error>)?;
         }
 
-        Commands::Connect { target } => {
-            connect::connect_command(&ctx, target).await?;
+        Commands::Connect {
+            target_args,
+            transcript,
+            no_refresh,
+        } => {
+            let target = target_args.into_target(&ctx)?;
+            connect::connect_command(&ctx, target, transcript, !no_refresh).await?;
+        }
+
+        Commands::Script {
+            target,
+            file,
+            keep_going,
+            report,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            connect::script_command(&ctx, target, &file, keep_going, &report).await?;
+        }
+
+        Commands::Callbatch {
+            target,
+            file,
+            concurrency,
+            retry,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            let retry = retry.into_call_retry(&ctx);
+            batch::callbatch_command(&ctx, target, &file, concurrency, retry).await?;
+        }
+
+        Commands::Fuzz {
+            target,
+            tool,
+            iterations,
+            seed,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            fuzz::fuzz_command(&ctx, target, &tool, iterations, seed).await?;
+        }
+
+        Commands::Bench {
+            target,
+            method,
+            concurrency,
+            duration,
+            tool,
+            args,
+            uri,
+            histogram,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            let arguments = ArgumentParser::parse_key_value_args(args)?;
+            let options = BenchOptions {
+                request: BenchRequest::new(method, tool, arguments, uri)?,
+                concurrency,
+                duration,
+                histogram,
+            };
+            bench::bench_command(&ctx, target, options).await?;
+        }
+
+        Commands::Snapshot {
+            target,
+            out_file,
+            contents,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            snapshot::snapshot_command(&ctx, target, out_file, contents).await?;
+        }
+
+        Commands::Diff {
+            first,
+            second,
+            exit_code,
+        } => {
+            diff::diff_command(&ctx, &first, &second, exit_code).await?;
+        }
+
+        Commands::Doc { target, out_dir } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            doc::doc_command(&ctx, target, out_dir).await?;
+        }
+
+        Commands::Generate {
+            target,
+            lang,
+            out_dir,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            generate::generate_command(&ctx, target, lang, out_dir).await?;
+        }
+
+        Commands::Watch {
+            target,
+            interval,
+            exec,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            watch::watch_command(&ctx, target, WatchOptions { interval, exec }).await?;
+        }
+
+        Commands::Logs { target, level } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            logs::logs_command(&ctx, target, level).await?;
+        }
+
+        Commands::Check { target, report } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            check::check_command(&ctx, target, &report).await?;
+        }
+
+        Commands::Caps { target } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            caps::caps_command(&ctx, target).await?;
+        }
+
+        Commands::Health {
+            target,
+            expect_tools,
+            max_latency,
+        } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            let options = HealthOptions {
+                expect_tools,
+                max_latency,
+            };
+            health::health_command(&ctx, target, options).await?;
+        }
+
+        Commands::Trace { target } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            trace::trace_command(&ctx, target).await?;
+        }
+
+        Commands::Lint { target, fail_on } => {
+            let target = Target::resolve(&target, &ctx.storage()?)?;
+            lint::lint_command(&ctx, target, fail_on).await?;
         }
 
         Commands::Proxy { proxy_args } => {
-            let target = Target::parse(&proxy_args.target)?;
-            proxy::proxy_command(target, proxy_args.log_file).await?;
+            let spec = proxy_args
+                .target
+                .or(proxy_args.upstream)
+                .expect("clap requires a target or --upstream");
+            let target = Target::resolve(&spec, &ctx.storage()?)?;
+            let options = proxy::ProxyOptions {
+                listen: proxy::Listen::parse(&proxy_args.listen)?,
+                log_file: proxy_args.log_file,
+                record: proxy_args.record,
+                policy: proxy_args.policy,
+                rewrites: proxy_args.rewrites,
+                limits: proxy_args.limits,
+            };
+            proxy::proxy_command(&ctx, target, options).await?;
+        }
+
+        Commands::Gateway {
+            upstreams,
+            listen,
+            policy,
+            limits,
+        } => {
+            let storage = ctx.storage()?;
+            let upstreams = upstreams
+                .into_iter()
+                .map(|(name, spec)| {
+                    Ok(Upstream {
+                        name,
+                        target: Target::resolve(&spec, &storage)?,
+                    })
+                })
+                .collect::<libmcptool::Result<Vec<_>>>()?;
+            let options = GatewayOptions {
+                listen: proxy::Listen::parse(&listen)?,
+                upstreams,
+                policy,
+                limits,
+            };
+            gateway::gateway_command(&ctx, options).await?;
+        }
+
+        Commands::Mock { file, listen } => {
+            mock::mock_command(&ctx, &file, proxy::Listen::parse(&listen)?).await?;
         }
 
         Commands::Testserver {
@@ -226,8 +1293,46 @@ async fn main() -> Result<(), Box<dyn Error>> {
             tcp,
             port,
             interactive,
+            spec,
+            latency,
+            jitter,
+            error_rate,
+            fail_method,
+            payload_size,
+            content_type,
+            storm,
+            storm_kinds,
+            page_size,
+            bulk,
+            chaos,
+            chaos_rate,
+            init_delay,
         } => {
-            testserver::run_test_server(&ctx, stdio, tcp, port, interactive).await?;
+            let options = testserver::TestServerOptions {
+                stdio,
+                tcp,
+                port,
+                interactive,
+                spec,
+                faults: Faults {
+                    latency,
+                    jitter,
+                    error_rate,
+                    fail_methods: fail_method,
+                },
+                payload: payload_size.map(|size| Payload::new(size, &content_type)),
+                storm: storm.map(|rate| Storm {
+                    rate,
+                    kinds: storm_kinds,
+                }),
+                paging: Paging { page_size, bulk },
+                chaos: chaos.map(|kinds| Chaos {
+                    kinds,
+                    rate: chaos_rate,
+                    init_delay,
+                }),
+            };
+            testserver::run_test_server(&ctx, options).await?;
         }
 
         Commands::Auth { command } => match command {
@@ -254,6 +1359,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     resource,
                     scopes,
                     show_redirect_url,
+                    resource_metadata: None,
                 };
                 auth::add_command(&ctx, args).await?;
             }
@@ -261,7 +1367,222 @@ async fn main() -> Result<(), Box<dyn Error>> {
             AuthCommands::Remove { name } => auth::remove_command(&ctx, name).await?,
             AuthCommands::Renew { name } => auth::renew_command(&ctx, name).await?,
         },
+
+        Commands::Target { command } => match command {
+            TargetCommands::Add {
+                name,
+                target_args,
+                force,
+            } => {
+                let target = target_args.into_target(&ctx)?;
+                targets::add_command(&ctx, name, target, force).await?;
+            }
+            TargetCommands::List => targets::list_command(&ctx).await?,
+            TargetCommands::Remove { name } => targets::remove_command(&ctx, name).await?,
+            TargetCommands::Show { name } => targets::show_command(&ctx, name).await?,
+            TargetCommands::Import { from, path, force } => {
+                targets::import_command(&ctx, from, path, force).await?;
+            }
+            TargetCommands::Export { format, names } => {
+                targets::export_command(&ctx, format, names).await?;
+            }
+        },
+
+        Commands::Secret { command } => match command {
+            SecretCommands::Set { name, value } => secret::set_command(&ctx, name, value).await?,
+            SecretCommands::List => secret::list_command(&ctx).await?,
+            SecretCommands::Remove { name } => secret::remove_command(&ctx, name).await?,
+        },
+
+        Commands::Template { command } => match command {
+            TemplateCommands::Save {
+                name,
+                tool,
+                args,
+                force,
+            } => templates::save_command(&ctx, name, tool, args, force).await?,
+            TemplateCommands::Run {
+                name,
+                target,
+                overrides,
+                yes,
+            } => templates::run_command(&ctx, name, &target, overrides, yes).await?,
+            TemplateCommands::List => templates::list_command(&ctx).await?,
+            TemplateCommands::Remove { name } => templates::remove_command(&ctx, name).await?,
+        },
+
+        Commands::Export { command } => match command {
+            ExportCommands::Schema {
+                target,
+                format,
+                out_file,
+            } => {
+                let target = Target::resolve(&target, &ctx.storage()?)?;
+                export::export_schema_command(&ctx, target, format, out_file).await?;
+            }
+        },
+
+        Commands::Serve { command } => match command {
+            ServeCommands::Fs { dir, listen } => {
+                serve::fs::fs_command(&ctx, &dir, proxy::Listen::parse(&listen)?).await?;
+            }
+        },
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use libmcptool::command::McpCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_doc_out_dir() {
+        let cli = Cli::parse_from(["mcptool", "doc", "cmd://server"]);
+        assert!(cli.output.is_none());
+        assert!(matches!(
+            cli.command,
+            Commands::Doc { out_dir, .. } if out_dir == PathBuf::from("docs")
+        ));
+
+        let cli = Cli::parse_from(["mcptool", "doc", "cmd://server", "-o", "site"]);
+        assert!(cli.output.is_none());
+        assert!(matches!(
+            cli.command,
+            Commands::Doc { out_dir, .. } if out_dir == PathBuf::from("site")
+        ));
+    }
+
+    /// The `--json-args` and `--yaml-args` of a parsed calltool command line.
+    fn calltool_args(cli: &Cli) -> (Option<Option<PathBuf>>, Option<Option<PathBuf>>) {
+        match &cli.command {
+            Commands::Mcp { mcp_command } => match &mcp_command.command {
+                McpCommand::Calltool {
+                    json_args,
+                    yaml_args,
+                    ..
+                } => (json_args.clone(), yaml_args.clone()),
+                _ => panic!("not calltool"),
+            },
+            _ => panic!("not mcp"),
+        }
+    }
+
+    #[test]
+    fn test_calltool_args() {
+        let cli = Cli::parse_from([
+            "mcptool",
+            "mcp",
+            "cmd://server",
+            "calltool",
+            "echo",
+            "--json-args",
+        ]);
+        assert!(!cli.json);
+        assert_eq!(calltool_args(&cli), (Some(None), None));
+
+        let cli = Cli::parse_from([
+            "mcptool",
+            "mcp",
+            "cmd://server",
+            "calltool",
+            "echo",
+            "--yaml-args",
+        ]);
+        assert!(!cli.yaml);
+        assert_eq!(calltool_args(&cli), (None, Some(None)));
+
+        let cli = Cli::parse_from([
+            "mcptool",
+            "mcp",
+            "cmd://server",
+            "calltool",
+            "echo",
+            "--json-args",
+            "args.json",
+        ]);
+        assert_eq!(
+            calltool_args(&cli),
+            (Some(Some(PathBuf::from("args.json"))), None)
+        );
+
+        // The output format flags stay output format flags, wherever they're given
+        for args in [
+            [
+                "mcptool",
+                "--yaml",
+                "mcp",
+                "cmd://server",
+                "calltool",
+                "echo",
+            ],
+            [
+                "mcptool",
+                "mcp",
+                "cmd://server",
+                "calltool",
+                "echo",
+                "--yaml",
+            ],
+        ] {
+            let cli = Cli::parse_from(args);
+            assert!(cli.yaml);
+            assert_eq!(calltool_args(&cli), (None, None));
+        }
+        for args in [
+            [
+                "mcptool",
+                "--json",
+                "mcp",
+                "cmd://server",
+                "calltool",
+                "echo",
+            ],
+            [
+                "mcptool",
+                "mcp",
+                "cmd://server",
+                "calltool",
+                "echo",
+                "--json",
+            ],
+        ] {
+            let cli = Cli::parse_from(args);
+            assert!(cli.json);
+            assert_eq!(calltool_args(&cli), (None, None));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_out_file() {
+        let cli = Cli::parse_from(["mcptool", "snapshot", "cmd://server", "-o", "snap.json"]);
+        assert!(cli.output.is_none());
+        assert!(matches!(
+            cli.command,
+            Commands::Snapshot { out_file, .. } if out_file == PathBuf::from("snap.json")
+        ));
+    }
+
+    #[test]
+    fn test_logs() {
+        let cli = Cli::parse_from(["mcptool", "logs", "cmd://server", "--level", "warning"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Logs {
+                level: ServerLogLevel::Warning,
+                ..
+            }
+        ));
+        assert!(
+            Cli::try_parse_from(["mcptool", "logs", "cmd://server", "--level", "loud"]).is_err()
+        );
+    }
+}